@@ -0,0 +1,90 @@
+//! CRDT 变更系统热路径的基准测试
+//!
+//! 运行：`cargo bench --bench change`，可追加过滤条件，如 `cargo bench --bench change -- merge`。
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+use zhiyun_backend::common::change::MergeEngine;
+use zhiyun_backend::common::change::sparse::{SparseCheckout, SparseConfig};
+use zhiyun_backend::common::change::synthetic::{GraphSpec, SyntheticGraph};
+
+/// (宽度, 深度) 组合：从少量长分支到大量短分支
+const SHAPES: [(usize, usize); 3] = [(2, 64), (8, 16), (32, 4)];
+
+/// 冲突率
+const CONFLICT_RATES: [f64; 3] = [0.0, 0.1, 0.5];
+
+fn graph(width: usize, depth: usize, conflict_rate: f64) -> SyntheticGraph {
+    SyntheticGraph::generate(&GraphSpec {
+        width,
+        depth,
+        conflict_rate,
+        ..Default::default()
+    })
+}
+
+fn bench_sort_changes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merge_engine/sort_changes");
+    let engine = MergeEngine::new();
+    for (width, depth) in SHAPES {
+        let changes = graph(width, depth, 0.1).shuffled_changes(1);
+        group.throughput(Throughput::Elements(changes.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{}x{}", width, depth)),
+            &changes,
+            |b, changes| b.iter(|| engine.sort_changes(black_box(changes.clone()))),
+        );
+    }
+    group.finish();
+}
+
+fn bench_merge(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merge_engine/merge");
+    let engine = MergeEngine::new();
+    for (width, depth) in SHAPES {
+        for rate in CONFLICT_RATES {
+            let graph = graph(width, depth, rate);
+            let changes = graph.shuffled_changes(1);
+            group.throughput(Throughput::Elements(changes.len() as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format!("{}x{}", width, depth), rate),
+                &changes,
+                |b, changes| {
+                    b.iter(|| {
+                        engine
+                            .merge(black_box(graph.root.clone()), black_box(changes))
+                            .unwrap()
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_materialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot/materialize");
+    let full = SparseCheckout::default();
+    let sparse = SparseCheckout::new(SparseConfig {
+        enabled: true,
+        include_prefixes: vec!["shared".to_string()],
+        max_expanded: None,
+    });
+    for (width, depth) in SHAPES {
+        let changes = graph(width, depth, 0.1).changes();
+        group.throughput(Throughput::Elements(changes.len() as u64));
+        let shape = format!("{}x{}", width, depth);
+        group.bench_with_input(BenchmarkId::new("full", &shape), &changes, |b, changes| {
+            b.iter(|| full.materialize(black_box(changes)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("sparse", &shape),
+            &changes,
+            |b, changes| b.iter(|| sparse.materialize(black_box(changes))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_sort_changes, bench_merge, bench_materialize);
+criterion_main!(benches);
@@ -0,0 +1,354 @@
+use crate::agent::RoutineId;
+use crate::common::intent::{IntentHandler, SystemIntent};
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// Agent 提议的、可能需要人工批准的操作
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProposedOperation {
+    DeleteFile {
+        path: String,
+    },
+    Command {
+        command: String,
+    },
+    /// 修改文件，`lines` 为改动的行数
+    Edit {
+        path: String,
+        lines: usize,
+    },
+}
+
+impl fmt::Display for ProposedOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProposedOperation::DeleteFile { path } => write!(f, "delete {}", path),
+            ProposedOperation::Command { command } => write!(f, "run `{}`", command),
+            ProposedOperation::Edit { path, lines } => {
+                write!(f, "edit {} lines in {}", lines, path)
+            }
+        }
+    }
+}
+
+/// 哪些操作需要批准，以及等待批准的时长
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalPolicy {
+    /// 改动超过该行数的编辑需要批准
+    pub max_edit_lines: usize,
+    /// 超时未处理的请求按拒绝处理
+    pub timeout: Duration,
+}
+
+impl Default for ApprovalPolicy {
+    fn default() -> Self {
+        Self {
+            max_edit_lines: 200,
+            timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+impl ApprovalPolicy {
+    pub fn with_max_edit_lines(mut self, max_edit_lines: usize) -> Self {
+        self.max_edit_lines = max_edit_lines;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 删除文件与执行命令总是需要批准，编辑超过行数上限时需要批准
+    pub fn requires_approval(&self, operation: &ProposedOperation) -> bool {
+        match operation {
+            ProposedOperation::DeleteFile { .. } | ProposedOperation::Command { .. } => true,
+            ProposedOperation::Edit { lines, .. } => *lines > self.max_edit_lines,
+        }
+    }
+}
+
+/// 批准请求的状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected {
+        reason: Option<String>,
+    },
+    /// 超时未处理，按拒绝处理
+    Expired,
+}
+
+impl ApprovalStatus {
+    pub fn is_approved(&self) -> bool {
+        matches!(self, ApprovalStatus::Approved)
+    }
+}
+
+/// 一次批准请求
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub id: Uuid,
+    pub routine_id: RoutineId,
+    pub operation: ProposedOperation,
+    pub status: ApprovalStatus,
+    pub requested_at: DateTime<Utc>,
+}
+
+/// 用户对批准请求的处理意图
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalIntent {
+    Approve {
+        request_id: Uuid,
+    },
+    Reject {
+        request_id: Uuid,
+        reason: Option<String>,
+    },
+}
+
+/// 记录批准请求并接收 `ApprovalIntent` 的处理器
+///
+/// 由 `RoutineExecutor::request_approval` 发起请求并等待；注册到
+/// `IntentCategory::Approval` 后，用户经 `IntentDispatcher` 批准或拒绝。
+pub struct ApprovalGate {
+    policy: ApprovalPolicy,
+    requests: Mutex<BTreeMap<Uuid, ApprovalRequest>>,
+    /// 任一请求被处理时通知等待者
+    resolved: Notify,
+}
+
+impl Default for ApprovalGate {
+    fn default() -> Self {
+        Self::new(ApprovalPolicy::default())
+    }
+}
+
+impl ApprovalGate {
+    pub fn new(policy: ApprovalPolicy) -> Self {
+        Self {
+            policy,
+            requests: Mutex::new(BTreeMap::new()),
+            resolved: Notify::new(),
+        }
+    }
+
+    pub fn policy(&self) -> &ApprovalPolicy {
+        &self.policy
+    }
+
+    /// 创建待处理的请求
+    pub fn open(&self, routine_id: RoutineId, operation: ProposedOperation) -> ApprovalRequest {
+        let request = ApprovalRequest {
+            id: Uuid::new_v4(),
+            routine_id,
+            operation,
+            status: ApprovalStatus::Pending,
+            requested_at: Utc::now(),
+        };
+        self.requests
+            .lock()
+            .unwrap()
+            .insert(request.id, request.clone());
+        request
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<ApprovalRequest> {
+        self.requests.lock().unwrap().get(&id).cloned()
+    }
+
+    /// 待处理的请求（按 ID 排序）
+    pub fn pending(&self) -> Vec<ApprovalRequest> {
+        self.requests
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|r| r.status == ApprovalStatus::Pending)
+            .cloned()
+            .collect()
+    }
+
+    pub fn approve(&self, id: Uuid) -> Result<ApprovalRequest> {
+        self.resolve(id, ApprovalStatus::Approved)
+    }
+
+    pub fn reject(&self, id: Uuid, reason: Option<String>) -> Result<ApprovalRequest> {
+        self.resolve(id, ApprovalStatus::Rejected { reason })
+    }
+
+    /// 等待请求被处理；超时仍未处理时将其置为 `Expired`
+    pub async fn wait(&self, id: Uuid, timeout: Duration) -> Result<ApprovalStatus> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let notified = self.resolved.notified();
+            let status = self
+                .get(id)
+                .ok_or_else(|| anyhow!("Unknown approval request: {}", id))?
+                .status;
+            if status != ApprovalStatus::Pending {
+                return Ok(status);
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                // 超时与处理同时发生时以先写入的结果为准
+                return match self.resolve(id, ApprovalStatus::Expired) {
+                    Ok(request) => Ok(request.status),
+                    Err(_) => Ok(self.get(id).map(|r| r.status).unwrap_or(status)),
+                };
+            }
+        }
+    }
+
+    fn resolve(&self, id: Uuid, status: ApprovalStatus) -> Result<ApprovalRequest> {
+        let request = {
+            let mut requests = self.requests.lock().unwrap();
+            let request = requests
+                .get_mut(&id)
+                .ok_or_else(|| anyhow!("Unknown approval request: {}", id))?;
+            if request.status != ApprovalStatus::Pending {
+                bail!("Approval request {} was already resolved", id);
+            }
+            request.status = status;
+            request.clone()
+        };
+        self.resolved.notify_waiters();
+        Ok(request)
+    }
+}
+
+#[async_trait]
+impl IntentHandler for ApprovalGate {
+    async fn handle(&self, intent: SystemIntent) -> Result<()> {
+        match intent {
+            SystemIntent::Approval(ApprovalIntent::Approve { request_id }) => {
+                self.approve(request_id).map(|_| ())
+            }
+            SystemIntent::Approval(ApprovalIntent::Reject { request_id, reason }) => {
+                self.reject(request_id, reason).map(|_| ())
+            }
+            other => Err(anyhow!("Unsupported intent: {}", other.summary())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::executor::RoutineExecutor;
+    use crate::agent::intent::AgentIntent;
+    use crate::agent::manager::RoutineManager;
+    use crate::agent::{Routine, RoutineStatus};
+    use crate::common::change::thread::ThreadManager;
+    use crate::common::intent::{IntentCategory, IntentDispatcher};
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    /// 把收到的批准请求转交给测试
+    struct Surface(mpsc::UnboundedSender<ApprovalRequest>);
+
+    #[async_trait]
+    impl IntentHandler for Surface {
+        async fn handle(&self, intent: SystemIntent) -> Result<()> {
+            if let SystemIntent::Agent(AgentIntent::RequestApproval { request }) = intent {
+                let _ = self.0.send(request);
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_high_impact_operations_wait_for_approval() {
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let gate = Arc::new(ApprovalGate::new(
+            ApprovalPolicy::default()
+                .with_max_edit_lines(50)
+                .with_timeout(Duration::from_millis(100)),
+        ));
+        let executor = RoutineExecutor::new(threads).with_approvals(gate.clone());
+        let manager = RoutineManager::new();
+        let routine = Routine::new(main);
+        let id = routine.id;
+        manager.register(routine);
+        let (tx, mut requests) = mpsc::unbounded_channel();
+        let dispatcher = Arc::new(IntentDispatcher::new());
+        dispatcher
+            .register(IntentCategory::Agent, Arc::new(Surface(tx)))
+            .await;
+        dispatcher
+            .register(IntentCategory::Approval, gate.clone())
+            .await;
+
+        // 小范围编辑不需要批准
+        let small = ProposedOperation::Edit {
+            path: "src/lib.rs".to_string(),
+            lines: 10,
+        };
+        executor
+            .request_approval(&manager, id, small, &dispatcher)
+            .await
+            .unwrap();
+        assert!(requests.try_recv().is_err());
+
+        // 等待期间 Routine 暂停，批准后恢复运行
+        let delete = ProposedOperation::DeleteFile {
+            path: "src/old.rs".to_string(),
+        };
+        let user = async {
+            let request = requests.recv().await.unwrap();
+            assert_eq!(manager.get(&id).unwrap().status, RoutineStatus::Paused);
+            dispatcher
+                .dispatch(SystemIntent::Approval(ApprovalIntent::Approve {
+                    request_id: request.id,
+                }))
+                .await
+                .unwrap();
+        };
+        let (approved, _) = tokio::join!(
+            executor.request_approval(&manager, id, delete, &dispatcher),
+            user
+        );
+        approved.unwrap();
+        assert_eq!(manager.get(&id).unwrap().status, RoutineStatus::Running);
+
+        let command = ProposedOperation::Command {
+            command: "git push --force".to_string(),
+        };
+        let user = async {
+            let request = requests.recv().await.unwrap();
+            gate.reject(request.id, Some("not now".to_string()))
+                .unwrap();
+        };
+        let (rejected, _) = tokio::join!(
+            executor.request_approval(&manager, id, command.clone(), &dispatcher),
+            user
+        );
+        assert!(rejected.unwrap_err().to_string().contains("not now"));
+
+        // 无人处理时超时按拒绝处理
+        let err = executor
+            .request_approval(&manager, id, command, &dispatcher)
+            .await
+            .unwrap_err();
+        let expired = requests.recv().await.unwrap();
+        assert_eq!(
+            gate.get(expired.id).unwrap().status,
+            ApprovalStatus::Expired
+        );
+        assert!(err.to_string().contains("expired"));
+        assert!(gate.approve(expired.id).is_err());
+        assert!(gate.pending().is_empty());
+        assert_eq!(manager.get(&id).unwrap().status, RoutineStatus::Running);
+    }
+}
@@ -1,6 +1,20 @@
 use crate::common::change::Change;
 use crate::common::change::thread::ThreadId;
-use anyhow::Result;
+use crate::common::endpoint::stream::SseParser;
+use crate::common::endpoint::{ChatOptions, FunctionDefinition, ToolDefinition, ToolExecutor};
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{Mutex, mpsc, watch};
+use tokio::task::JoinHandle;
 
 /// 协调 Routine 产生的变更合并到对应的 Thread
 pub struct MergerBridge;
@@ -28,9 +42,508 @@ impl MergerBridge {
     }
 }
 
+/// MCP 协议版本
+pub const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// 桥接工具名中服务器名与工具名的分隔符
+const NAME_SEPARATOR: &str = "__";
+
+/// 与 MCP 服务器交换 JSON-RPC 消息的传输层
+#[async_trait]
+pub trait McpTransport: Send + Sync {
+    async fn send(&self, message: Value) -> Result<()>;
+
+    /// 下一条来自服务器的消息；连接关闭时返回 `None`
+    async fn receive(&self) -> Result<Option<Value>>;
+}
+
+/// stdio 传输：启动服务器进程，经标准输入输出逐行交换消息
+pub struct StdioTransport {
+    stdin: Mutex<ChildStdin>,
+    stdout: Mutex<Lines<BufReader<ChildStdout>>>,
+    _child: Child,
+}
+
+impl StdioTransport {
+    /// 启动 `program`，进程随传输一同结束
+    pub fn spawn(program: &str, args: &[String], env: &BTreeMap<String, String>) -> Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .envs(env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("MCP server stdin unavailable"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("MCP server stdout unavailable"))?;
+        Ok(Self {
+            stdin: Mutex::new(stdin),
+            stdout: Mutex::new(BufReader::new(stdout).lines()),
+            _child: child,
+        })
+    }
+}
+
+#[async_trait]
+impl McpTransport for StdioTransport {
+    async fn send(&self, message: Value) -> Result<()> {
+        let mut line = serde_json::to_vec(&message)?;
+        line.push(b'\n');
+        let mut stdin = self.stdin.lock().await;
+        stdin.write_all(&line).await?;
+        stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn receive(&self) -> Result<Option<Value>> {
+        let mut stdout = self.stdout.lock().await;
+        while let Some(line) = stdout.next_line().await? {
+            if !line.trim().is_empty() {
+                return Ok(Some(serde_json::from_str(&line)?));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// SSE 传输：以 GET 订阅服务器的事件流，服务器经 `endpoint` 事件告知 POST 消息的地址，
+/// 之后的响应以 `message` 事件返回
+pub struct SseTransport {
+    http: reqwest::Client,
+    endpoint: watch::Receiver<Option<Url>>,
+    incoming: Mutex<mpsc::UnboundedReceiver<Result<Value>>>,
+    reader: JoinHandle<()>,
+}
+
+impl SseTransport {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let url = Url::parse(url)?;
+        let http = reqwest::Client::new();
+        let mut response = http
+            .get(url.clone())
+            .header("accept", "text/event-stream")
+            .send()
+            .await?
+            .error_for_status()?;
+        let (endpoint_tx, endpoint) = watch::channel(None);
+        let (tx, incoming) = mpsc::unbounded_channel();
+        let reader = tokio::spawn(async move {
+            let mut parser = SseParser::new();
+            loop {
+                let chunk = match response.chunk().await {
+                    Ok(Some(chunk)) => chunk,
+                    Ok(None) => break,
+                    Err(e) => {
+                        let _ = tx.send(Err(e.into()));
+                        break;
+                    }
+                };
+                for event in parser.feed(&chunk) {
+                    match event.event.as_deref() {
+                        Some("endpoint") => match url.join(event.data.trim()) {
+                            Ok(endpoint) => {
+                                let _ = endpoint_tx.send(Some(endpoint));
+                            }
+                            Err(e) => {
+                                let _ = tx.send(Err(e.into()));
+                            }
+                        },
+                        None | Some("message") => {
+                            let message = serde_json::from_str(&event.data).map_err(Into::into);
+                            if tx.send(message).is_err() {
+                                return;
+                            }
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            http,
+            endpoint,
+            incoming: Mutex::new(incoming),
+            reader,
+        })
+    }
+}
+
+impl Drop for SseTransport {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+#[async_trait]
+impl McpTransport for SseTransport {
+    async fn send(&self, message: Value) -> Result<()> {
+        let mut endpoint = self.endpoint.clone();
+        let url = endpoint
+            .wait_for(Option::is_some)
+            .await
+            .map_err(|_| anyhow!("MCP server closed the event stream before sending its endpoint"))?
+            .clone()
+            .ok_or_else(|| anyhow!("MCP endpoint unavailable"))?;
+        self.http
+            .post(url)
+            .json(&message)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn receive(&self) -> Result<Option<Value>> {
+        self.incoming.lock().await.recv().await.transpose()
+    }
+}
+
+/// MCP 服务器提供的工具
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "empty_schema")]
+    pub input_schema: Value,
+}
+
+fn empty_schema() -> Value {
+    json!({ "type": "object" })
+}
+
+/// MCP 服务器提供的资源
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+}
+
+/// MCP 客户端：完成初始化握手后按 JSON-RPC 发起请求
+///
+/// 请求串行发送，等待响应期间收到的通知被忽略，服务器发来的 `ping` 会被应答。
+pub struct McpClient {
+    transport: Box<dyn McpTransport>,
+    next_id: AtomicU64,
+    exchange: Mutex<()>,
+    server_info: Value,
+}
+
+impl McpClient {
+    pub async fn connect(transport: impl McpTransport + 'static) -> Result<Self> {
+        let mut client = Self {
+            transport: Box::new(transport),
+            next_id: AtomicU64::new(1),
+            exchange: Mutex::new(()),
+            server_info: Value::Null,
+        };
+        let result = client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "zhiyun", "version": env!("CARGO_PKG_VERSION") },
+                }),
+            )
+            .await?;
+        client.server_info = result["serverInfo"].clone();
+        client
+            .transport
+            .send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await?;
+        Ok(client)
+    }
+
+    /// 服务器在握手时报告的名称与版本
+    pub fn server_info(&self) -> &Value {
+        &self.server_info
+    }
+
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let _exchange = self.exchange.lock().await;
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.transport
+            .send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+            .await?;
+        loop {
+            let message = self
+                .transport
+                .receive()
+                .await?
+                .ok_or_else(|| anyhow!("MCP server closed the connection during {}", method))?;
+            if let Some(request) = message["method"].as_str() {
+                if !message["id"].is_null() {
+                    self.answer(&message["id"], request).await?;
+                }
+                continue;
+            }
+            if message["id"].as_u64() != Some(id) {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                bail!(
+                    "MCP {} failed ({}): {}",
+                    method,
+                    error["code"],
+                    error["message"].as_str().unwrap_or_default()
+                );
+            }
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    async fn answer(&self, id: &Value, method: &str) -> Result<()> {
+        let reply = if method == "ping" {
+            json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+        } else {
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": format!("Method not found: {}", method) },
+            })
+        };
+        self.transport.send(reply).await
+    }
+
+    /// 按游标分页取得完整列表
+    async fn list<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        key: &str,
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let mut result = self.request(method, params).await?;
+            items.extend(serde_json::from_value::<Vec<T>>(result[key].take())?);
+            match result["nextCursor"].as_str() {
+                Some(next) => cursor = Some(next.to_string()),
+                None => return Ok(items),
+            }
+        }
+    }
+
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>> {
+        self.list("tools/list", "tools").await
+    }
+
+    pub async fn list_resources(&self) -> Result<Vec<McpResource>> {
+        self.list("resources/list", "resources").await
+    }
+
+    /// 调用工具；服务器以 `isError` 报告的失败作为错误返回
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<String> {
+        let result = self
+            .request(
+                "tools/call",
+                json!({ "name": name, "arguments": arguments }),
+            )
+            .await?;
+        let text = content_text(&result["content"]);
+        if result["isError"].as_bool().unwrap_or(false) {
+            bail!("{}", text);
+        }
+        Ok(text)
+    }
+
+    pub async fn read_resource(&self, uri: &str) -> Result<String> {
+        let result = self
+            .request("resources/read", json!({ "uri": uri }))
+            .await?;
+        Ok(content_text(&result["contents"]))
+    }
+}
+
+/// 将 MCP 内容块拼接为文本；非文本内容以占位说明代替
+fn content_text(content: &Value) -> String {
+    content
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|block| {
+            if let Some(text) = block["text"].as_str() {
+                return text.to_string();
+            }
+            if let Some(text) = block["resource"]["text"].as_str() {
+                return text.to_string();
+            }
+            let kind = block["mimeType"]
+                .as_str()
+                .or(block["type"].as_str())
+                .unwrap_or("binary");
+            format!("[{} content omitted]", kind)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 已连接的 MCP 服务器及其发现的工具与资源
+struct McpServer {
+    client: McpClient,
+    tools: Vec<McpTool>,
+    resources: Vec<McpResource>,
+}
+
+/// 把外部 MCP 服务器的工具提供给 Agent 的 `ToolExecutor`
+///
+/// 工具以 `<服务器>__<工具>` 命名暴露给模型，提供资源的服务器额外暴露
+/// `<服务器>__read_resource`；模型的工具调用按名称路由回对应服务器。
+#[derive(Default)]
+pub struct McpBridge {
+    servers: RwLock<BTreeMap<String, Arc<McpServer>>>,
+}
+
+impl McpBridge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 连接服务器并发现其工具与资源，返回发现的工具数
+    pub async fn connect(
+        &self,
+        name: &str,
+        transport: impl McpTransport + 'static,
+    ) -> Result<usize> {
+        if name.is_empty() || name.contains(NAME_SEPARATOR) {
+            bail!("Invalid MCP server name: {}", name);
+        }
+        let client = McpClient::connect(transport).await?;
+        let tools = client.list_tools().await?;
+        // 未声明资源能力的服务器可能不支持 resources/list
+        let resources = client.list_resources().await.unwrap_or_default();
+        let count = tools.len();
+        self.servers.write().unwrap().insert(
+            name.to_string(),
+            Arc::new(McpServer {
+                client,
+                tools,
+                resources,
+            }),
+        );
+        Ok(count)
+    }
+
+    pub fn disconnect(&self, name: &str) -> bool {
+        self.servers.write().unwrap().remove(name).is_some()
+    }
+
+    pub fn servers(&self) -> Vec<String> {
+        self.servers.read().unwrap().keys().cloned().collect()
+    }
+
+    /// 各服务器提供的资源
+    pub fn resources(&self) -> Vec<(String, McpResource)> {
+        self.servers
+            .read()
+            .unwrap()
+            .iter()
+            .flat_map(|(name, server)| server.resources.iter().map(|r| (name.clone(), r.clone())))
+            .collect()
+    }
+
+    pub async fn read_resource(&self, server: &str, uri: &str) -> Result<String> {
+        self.server(server)?.client.read_resource(uri).await
+    }
+
+    /// 在选项中追加桥接的工具定义
+    pub fn chat_options(&self, options: ChatOptions) -> ChatOptions {
+        let mut tools = options.tools.clone().unwrap_or_default();
+        tools.extend(self.definitions());
+        ChatOptions {
+            tools: (!tools.is_empty()).then_some(tools),
+            ..options
+        }
+    }
+
+    fn server(&self, name: &str) -> Result<Arc<McpServer>> {
+        self.servers
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown MCP server: {}", name))
+    }
+}
+
+#[async_trait(?Send)]
+impl ToolExecutor for McpBridge {
+    fn definitions(&self) -> Vec<ToolDefinition> {
+        let definition =
+            |name: String, description: Option<String>, parameters: Value| ToolDefinition {
+                r#type: "function".to_string(),
+                function: FunctionDefinition {
+                    name,
+                    description,
+                    parameters,
+                },
+            };
+        let mut definitions = Vec::new();
+        for (server_name, server) in self.servers.read().unwrap().iter() {
+            for tool in &server.tools {
+                definitions.push(definition(
+                    format!("{}{}{}", server_name, NAME_SEPARATOR, tool.name),
+                    tool.description.clone(),
+                    tool.input_schema.clone(),
+                ));
+            }
+            if !server.resources.is_empty() {
+                let uris: Vec<&str> = server.resources.iter().map(|r| r.uri.as_str()).collect();
+                definitions.push(definition(
+                    format!("{}{}read_resource", server_name, NAME_SEPARATOR),
+                    Some(format!("Read a resource provided by {}", server_name)),
+                    json!({
+                        "type": "object",
+                        "properties": { "uri": { "type": "string", "enum": uris } },
+                        "required": ["uri"],
+                    }),
+                ));
+            }
+        }
+        definitions
+    }
+
+    async fn call(&self, name: &str, arguments: Value) -> Result<String> {
+        let (server_name, tool) = name
+            .split_once(NAME_SEPARATOR)
+            .ok_or_else(|| anyhow!("Unknown tool: {}", name))?;
+        let server = self.server(server_name)?;
+        if server.tools.iter().any(|t| t.name == tool) {
+            return server.client.call_tool(tool, arguments).await;
+        }
+        if tool == "read_resource" && !server.resources.is_empty() {
+            let uri = arguments["uri"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Missing resource uri"))?;
+            return server.client.read_resource(uri).await;
+        }
+        bail!("Unknown tool: {}", name)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::AtomicBool;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::{TcpListener, TcpStream};
     use uuid::Uuid;
 
     #[tokio::test]
@@ -40,4 +553,151 @@ mod tests {
         let t2 = Uuid::new_v4();
         assert!(bridge.propose_merge(t1, t2, vec![]).await.is_ok());
     }
+
+    /// 读取一个 HTTP 请求，返回请求体
+    async fn read_body(socket: &mut TcpStream) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                let head = String::from_utf8_lossy(&data[..end]).to_lowercase();
+                let length = head
+                    .lines()
+                    .find_map(|l| l.strip_prefix("content-length:"))
+                    .map_or(0, |v| v.trim().parse().unwrap());
+                if data.len() >= end + 4 + length {
+                    return data[end + 4..end + 4 + length].to_vec();
+                }
+            }
+            let n = socket.read(&mut buf).await.unwrap();
+            assert!(n > 0);
+            data.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    async fn send(events: &mut TcpStream, message: Value) {
+        let event = format!("event: message\ndata: {}\n\n", message);
+        events.write_all(event.as_bytes()).await.unwrap();
+    }
+
+    /// 最小的 MCP SSE 服务器：工具分两页返回，调用工具前先 ping 客户端
+    async fn serve(listener: TcpListener, pong: Arc<AtomicBool>) {
+        let (mut events, _) = listener.accept().await.unwrap();
+        read_body(&mut events).await;
+        events
+            .write_all(
+                b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\r\n\
+                  event: endpoint\ndata: /messages?session=1\n\n",
+            )
+            .await
+            .unwrap();
+        loop {
+            let Ok((mut post, _)) = listener.accept().await else {
+                return;
+            };
+            let request: Value = serde_json::from_slice(&read_body(&mut post).await).unwrap();
+            post.write_all(
+                b"HTTP/1.1 202 Accepted\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            )
+            .await
+            .unwrap();
+            let (Some(method), Some(id)) = (request["method"].as_str(), request.get("id")) else {
+                if request["id"] == "srv-1" {
+                    pong.store(true, Ordering::SeqCst);
+                }
+                continue;
+            };
+            let params = &request["params"];
+            let result = match method {
+                "initialize" => json!({ "serverInfo": { "name": "docs", "version": "1" } }),
+                "tools/list" if params["cursor"].is_null() => json!({
+                    "tools": [{ "name": "search", "description": "Search docs",
+                                "inputSchema": { "type": "object", "properties": { "q": { "type": "string" } } } }],
+                    "nextCursor": "2",
+                }),
+                "tools/list" => json!({ "tools": [{ "name": "fail" }] }),
+                "resources/list" => json!({
+                    "resources": [{ "uri": "docs://readme", "name": "README", "mimeType": "text/markdown" }],
+                }),
+                "resources/read" => json!({
+                    "contents": [{ "uri": params["uri"], "text": "# Docs" }],
+                }),
+                "tools/call" => {
+                    send(
+                        &mut events,
+                        json!({ "jsonrpc": "2.0", "method": "notifications/progress" }),
+                    )
+                    .await;
+                    send(
+                        &mut events,
+                        json!({ "jsonrpc": "2.0", "id": "srv-1", "method": "ping" }),
+                    )
+                    .await;
+                    match params["name"].as_str() {
+                        Some("search") => json!({
+                            "content": [{ "type": "text", "text": format!("found {}", params["arguments"]["q"]) },
+                                        { "type": "image", "data": "", "mimeType": "image/png" }],
+                        }),
+                        _ => {
+                            json!({ "content": [{ "type": "text", "text": "boom" }], "isError": true })
+                        }
+                    }
+                }
+                _ => {
+                    let error = json!({ "jsonrpc": "2.0", "id": id,
+                                        "error": { "code": -32601, "message": "unknown" } });
+                    send(&mut events, error).await;
+                    continue;
+                }
+            };
+            send(
+                &mut events,
+                json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            )
+            .await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mcp_bridge_over_sse() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let pong = Arc::new(AtomicBool::new(false));
+        tokio::spawn(serve(listener, pong.clone()));
+
+        let bridge = McpBridge::new();
+        let transport = SseTransport::connect(&format!("http://{}/sse", addr))
+            .await
+            .unwrap();
+        assert_eq!(bridge.connect("docs", transport).await.unwrap(), 2);
+        assert_eq!(bridge.resources()[0].1.uri, "docs://readme");
+
+        // 工具与资源以带服务器前缀的名称出现在聊天选项中
+        let options = bridge.chat_options(ChatOptions::default());
+        let names: Vec<String> = options
+            .tools
+            .unwrap()
+            .into_iter()
+            .map(|t| t.function.name)
+            .collect();
+        assert_eq!(names, ["docs__search", "docs__fail", "docs__read_resource"]);
+
+        let output = bridge
+            .call("docs__search", json!({ "q": "mcp" }))
+            .await
+            .unwrap();
+        assert_eq!(output, "found \"mcp\"\n[image/png content omitted]");
+        assert!(pong.load(Ordering::SeqCst));
+        let err = bridge.call("docs__fail", json!({})).await.unwrap_err();
+        assert_eq!(err.to_string(), "boom");
+        assert_eq!(
+            bridge
+                .call("docs__read_resource", json!({ "uri": "docs://readme" }))
+                .await
+                .unwrap(),
+            "# Docs"
+        );
+        assert!(bridge.call("other__search", json!({})).await.is_err());
+        assert!(bridge.call("docs__missing", json!({})).await.is_err());
+    }
 }
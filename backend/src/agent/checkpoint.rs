@@ -0,0 +1,201 @@
+use crate::agent::context::{ContextManager, RunningSummary};
+use crate::agent::{Routine, RoutineId, RoutineStatus};
+use crate::common::endpoint::ChatMessage;
+use crate::common::endpoint::traits::ToolCall;
+use crate::common::provider::traits::StorageProvider;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Routine 检查点的默认目录（相对项目根目录）
+pub const CHECKPOINT_DIR: &str = ".zhiyun/routines";
+
+/// Routine 的完整运行状态，足以在进程重启后从中断处继续
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutineCheckpoint {
+    pub routine: Routine,
+    /// 对话上下文
+    pub messages: Vec<ChatMessage>,
+    /// 固定消息的下标
+    #[serde(default)]
+    pub pinned: Vec<usize>,
+    /// 早期对话的滚动摘要
+    #[serde(default)]
+    pub summary: Option<RunningSummary>,
+    /// 模型已发出但尚未返回结果的工具调用
+    #[serde(default)]
+    pub pending_tool_calls: Vec<ToolCall>,
+    /// 已完成的步骤数
+    pub step: usize,
+    pub saved_at: DateTime<Utc>,
+}
+
+impl RoutineCheckpoint {
+    pub fn new(routine: &Routine, context: &ContextManager, step: usize) -> Self {
+        Self {
+            routine: routine.clone(),
+            messages: context.messages().to_vec(),
+            pinned: context.pinned(),
+            summary: context.summary().cloned(),
+            pending_tool_calls: Vec::new(),
+            step,
+            saved_at: Utc::now(),
+        }
+    }
+
+    pub fn with_pending_tool_calls(mut self, calls: Vec<ToolCall>) -> Self {
+        self.pending_tool_calls = calls;
+        self
+    }
+
+    /// 由保存的对话重建上下文
+    pub fn context(&self) -> ContextManager {
+        ContextManager::from_messages(self.messages.clone())
+            .with_memory(&self.pinned, self.summary.clone())
+    }
+
+    /// 暂停或运行中被中断的 Routine 可以恢复
+    pub fn is_resumable(&self) -> bool {
+        matches!(
+            self.routine.status,
+            RoutineStatus::Paused | RoutineStatus::Running
+        )
+    }
+}
+
+/// 基于存储提供者的 Routine 检查点存储，每个 Routine 一个 JSON 文件
+pub struct CheckpointStore {
+    storage: Arc<dyn StorageProvider>,
+    dir: String,
+}
+
+impl CheckpointStore {
+    pub fn new(storage: Arc<dyn StorageProvider>) -> Self {
+        Self {
+            storage,
+            dir: CHECKPOINT_DIR.to_string(),
+        }
+    }
+
+    pub fn with_dir(mut self, dir: &str) -> Self {
+        self.dir = dir.trim_end_matches('/').to_string();
+        self
+    }
+
+    fn path(&self, id: &RoutineId) -> String {
+        format!("{}/{}.json", self.dir, id)
+    }
+
+    /// 保存检查点，覆盖同一 Routine 之前的检查点
+    pub async fn save(&self, checkpoint: &RoutineCheckpoint) -> Result<()> {
+        self.storage
+            .write_file(
+                &self.path(&checkpoint.routine.id),
+                &serde_json::to_vec_pretty(checkpoint)?,
+            )
+            .await
+    }
+
+    pub async fn load(&self, id: &RoutineId) -> Result<Option<RoutineCheckpoint>> {
+        let path = self.path(id);
+        if !self.storage.exists(&path).await? {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(
+            &self.storage.read_file(&path).await?,
+        )?))
+    }
+
+    pub async fn remove(&self, id: &RoutineId) -> Result<()> {
+        let path = self.path(id);
+        if self.storage.exists(&path).await? {
+            self.storage.delete(&path, false).await?;
+        }
+        Ok(())
+    }
+
+    /// 全部检查点，按保存时间排序（无法解析的文件被跳过）
+    pub async fn list(&self) -> Result<Vec<RoutineCheckpoint>> {
+        if !self.storage.exists(&self.dir).await? {
+            return Ok(Vec::new());
+        }
+        let mut checkpoints = Vec::new();
+        for entry in self.storage.list_dir(&self.dir).await? {
+            if entry.is_dir || !entry.path.ends_with(".json") {
+                continue;
+            }
+            let name = entry.path.rsplit('/').next().unwrap_or(&entry.path);
+            let path = format!("{}/{}", self.dir, name);
+            if let Ok(checkpoint) = serde_json::from_slice(&self.storage.read_file(&path).await?) {
+                checkpoints.push(checkpoint);
+            }
+        }
+        checkpoints.sort_by_key(|c: &RoutineCheckpoint| c.saved_at);
+        Ok(checkpoints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::executor::RoutineExecutor;
+    use crate::common::change::thread::ThreadManager;
+    use crate::common::endpoint::traits::{FunctionCall, MessageRole};
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+
+    #[tokio::test]
+    async fn test_interrupted_routine_resumes_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let open = || {
+            let storage = Arc::new(LocalFileSystem::new(dir.path()));
+            RoutineExecutor::new(threads.clone())
+                .with_checkpoints(Arc::new(CheckpointStore::new(storage)))
+        };
+
+        let executor = open();
+        let routine = executor.fork(&Routine::new(main), "agent/fix").unwrap();
+        let call = ToolCall {
+            id: "call_1".to_string(),
+            r#type: "function".to_string(),
+            function: FunctionCall {
+                name: "read_file".to_string(),
+                arguments: r#"{"path":"src/lib.rs"}"#.to_string(),
+            },
+        };
+        let mut context = ContextManager::new();
+        context.add_message(ChatMessage::text(MessageRole::User, "fix the build"));
+        let mut reply = ChatMessage::text(MessageRole::Assistant, "");
+        reply.tool_calls = Some(vec![call.clone()]);
+        context.add_message(reply);
+        executor
+            .checkpoint(
+                &RoutineCheckpoint::new(&routine, &context, 2).with_pending_tool_calls(vec![call]),
+            )
+            .await
+            .unwrap();
+
+        let mut done = Routine::new(main);
+        done.status = RoutineStatus::Completed;
+        let done_checkpoint = RoutineCheckpoint::new(&done, &ContextManager::new(), 5);
+        executor.checkpoint(&done_checkpoint).await.unwrap();
+        drop(executor);
+
+        // 进程重启：新的执行器从同一存储恢复，运行中被打断的 Routine 可继续
+        let executor = open();
+        let resumable = executor.resumable().await.unwrap();
+        assert_eq!(resumable.len(), 1);
+        let resumed = executor.resume(routine.id).await.unwrap();
+        assert_eq!(resumed.routine, routine);
+        assert_eq!(resumed.step, 2);
+        assert_eq!(resumed.context().messages(), context.messages());
+        assert_eq!(resumed.pending_tool_calls[0].function.name, "read_file");
+        assert!(executor.resume(done.id).await.is_err());
+
+        executor.finish(routine.id).await.unwrap();
+        assert!(executor.resume(routine.id).await.is_err());
+        assert!(executor.resumable().await.unwrap().is_empty());
+    }
+}
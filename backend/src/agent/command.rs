@@ -0,0 +1,227 @@
+use crate::agent::explain::{ExplainService, LineRange};
+use crate::agent::intent::AgentIntent;
+use crate::common::intent::{IntentHandler, SystemIntent};
+use crate::common::provider::traits::{ExecuteOptions, ExecutionProvider};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// 非 `verbose` 时返回的测试输出最大字符数
+const MAX_OUTPUT_CHARS: usize = 4000;
+
+/// `/explain` 沿调用关系展开的层数
+const EXPLAIN_DEPTH: usize = 1;
+
+/// 处理内置斜杠命令产生的 Agent 意图：`RunTests` 运行测试命令，`Explain` 解释选区
+///
+/// 未配置对应能力或收到其他 Agent 意图时返回错误。
+#[derive(Default)]
+pub struct CommandHandler {
+    tests: Option<(Arc<dyn ExecutionProvider>, String)>,
+    explain: Option<Arc<ExplainService>>,
+}
+
+impl CommandHandler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 经 `runner` 运行 `command` 执行测试，过滤文本作为单个参数追加在命令末尾
+    pub fn with_tests(
+        mut self,
+        runner: Arc<dyn ExecutionProvider>,
+        command: impl Into<String>,
+    ) -> Self {
+        self.tests = Some((runner, command.into()));
+        self
+    }
+
+    pub fn with_explain(mut self, explain: Arc<ExplainService>) -> Self {
+        self.explain = Some(explain);
+        self
+    }
+
+    /// 运行测试，返回退出码、是否通过与输出（非 `verbose` 时只保留末尾部分）
+    async fn run_tests(&self, filter: Option<&str>, verbose: bool) -> Result<Value> {
+        let (runner, command) = self
+            .tests
+            .as_ref()
+            .ok_or_else(|| anyhow!("No test command configured"))?;
+        let command = match filter {
+            Some(filter) => format!("{} '{}'", command, filter.replace('\'', r"'\''")),
+            None => command.clone(),
+        };
+        let run = runner.execute(&command, ExecuteOptions::default()).await?;
+        let output = if verbose {
+            run.output_tail(usize::MAX)
+        } else {
+            run.output_tail(MAX_OUTPUT_CHARS)
+        };
+        Ok(json!({
+            "exit_code": run.exit_code,
+            "passed": run.exit_code == 0,
+            "output": output,
+        }))
+    }
+}
+
+#[async_trait]
+impl IntentHandler for CommandHandler {
+    async fn handle(&self, intent: SystemIntent) -> Result<()> {
+        self.respond(intent).await.map(|_| ())
+    }
+
+    /// 运行测试返回 `{exit_code, passed, output}`，解释选区返回 `Explanation`
+    async fn respond(&self, intent: SystemIntent) -> Result<Value> {
+        match intent {
+            SystemIntent::Agent(AgentIntent::RunTests { filter, verbose }) => {
+                self.run_tests(filter.as_deref(), verbose).await
+            }
+            SystemIntent::Agent(AgentIntent::Explain {
+                selection,
+                question,
+            }) => {
+                let explain = self
+                    .explain
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("No explain service configured"))?;
+                let range = LineRange {
+                    start: selection.start_line,
+                    end: selection.end_line,
+                };
+                let explanation = explain
+                    .explain_focused(&selection.path, range, EXPLAIN_DEPTH, question.as_deref())
+                    .await?;
+                Ok(serde_json::to_value(explanation)?)
+            }
+            other => Err(anyhow!("CommandHandler cannot handle {}", other.summary())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::thread::ThreadManager;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::common::intent::command::{CommandContext, CommandRegistry, Selection};
+    use crate::common::intent::{EditorIntent, IntentCategory, IntentDispatcher};
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::common::provider::traits::{ExecuteResult, StorageProvider};
+    use crate::editor::session::EditorSession;
+    use crate::semantic::graph::GraphBuilder;
+    use std::sync::{Mutex, RwLock};
+
+    struct Runner {
+        commands: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ExecutionProvider for Runner {
+        async fn execute(&self, command: &str, _options: ExecuteOptions) -> Result<ExecuteResult> {
+            self.commands.lock().unwrap().push(command.to_string());
+            Ok(ExecuteResult {
+                exit_code: 1,
+                stdout: "test smoke ... FAILED".to_string(),
+                stderr: String::new(),
+            })
+        }
+
+        async fn kill(&self, _task_id: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_commands_run_end_to_end() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "fn main() {}\n").unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+
+        let threads = Arc::new(ThreadManager::new());
+        let main_id = threads.get_thread_id_by_name("main").unwrap();
+        let session = Arc::new(EditorSession::new(
+            "/".to_string(),
+            main_id,
+            storage.clone(),
+            threads,
+        ));
+        let runner = Arc::new(Runner {
+            commands: Mutex::new(Vec::new()),
+        });
+        let client = Arc::new(
+            ScriptedClient::new("explainer").with_responder(|_, messages| {
+                let prompt = messages.last().unwrap().content.as_text();
+                assert!(prompt.contains("Focus on this question: why main?"));
+                r#"{"summary": "entry point", "steps": [], "pitfalls": []}"#.to_string()
+            }),
+        );
+        let explain = ExplainService::new(
+            storage.clone(),
+            Arc::new(RwLock::new(GraphBuilder::new())),
+            client,
+            "gpt-4o",
+        );
+        let dispatcher = IntentDispatcher::new();
+        dispatcher
+            .register(IntentCategory::Editor, session.clone())
+            .await;
+        dispatcher
+            .register(
+                IntentCategory::Agent,
+                Arc::new(
+                    CommandHandler::new()
+                        .with_tests(runner.clone(), "cargo test")
+                        .with_explain(Arc::new(explain)),
+                ),
+            )
+            .await;
+
+        let registry = CommandRegistry::with_defaults();
+        let context = CommandContext {
+            active_file: Some("src/lib.rs".to_string()),
+            selection: Some(Selection {
+                path: "src/lib.rs".to_string(),
+                text: "fn main() {}".to_string(),
+                start_line: 1,
+                end_line: 1,
+            }),
+        };
+        let run = |input: &str| registry.resolve(input, &context).unwrap().unwrap();
+
+        let report: Value = dispatcher.request(run("/test smoke")).await.unwrap();
+        assert_eq!(*runner.commands.lock().unwrap(), vec!["cargo test 'smoke'"]);
+        assert_eq!(report["passed"], false);
+        assert_eq!(report["output"], "test smoke ... FAILED");
+
+        session
+            .handle(SystemIntent::Editor(EditorIntent::WriteFile {
+                path: "src/lib.rs".to_string(),
+                content: b"fn main() { run(); }\n".to_vec(),
+            }))
+            .await
+            .unwrap();
+        let diff: Value = dispatcher.request(run("/diff")).await.unwrap();
+        assert_eq!(diff["files"][0]["path"], "src/lib.rs");
+        assert!(
+            diff["diff"]
+                .as_str()
+                .unwrap()
+                .contains("-fn main() {}\n+fn main() { run(); }\n")
+        );
+        // 查看差异不会写入存储
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("src/lib.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+
+        let explanation: Value = dispatcher
+            .request(run("/explain 'why main?'"))
+            .await
+            .unwrap();
+        assert_eq!(explanation["summary"], "entry point");
+        assert_eq!(explanation["citations"][0]["kind"], "selection");
+    }
+}
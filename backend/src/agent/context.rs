@@ -1,8 +1,30 @@
-use crate::common::endpoint::ChatMessage;
+use crate::common::endpoint::context::{PromptBudget, SUMMARY_MAX_TOKENS, SUMMARY_PREFIX};
+use crate::common::endpoint::{ChatMessage, ChatOptions, LLMClient, MessageRole, ModelLimit};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// 提示词达到预算的该比例时开始压缩
+const COMPRESS_AT: f32 = 0.8;
+/// 压缩后提示词不超过预算的该比例
+const COMPRESS_TO: f32 = 0.5;
+/// 对早期对话的滚动摘要，覆盖 `through` 之前所有未固定的消息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunningSummary {
+    /// 历史中该下标之前的未固定消息都已并入摘要
+    pub through: usize,
+    pub text: String,
+}
 
 /// 负责对话上下文的智能压缩与窗口管理
+///
+/// 完整历史始终保留（检查点保存的也是完整历史）；接近模型上下文上限时，
+/// `prepare` 将最早的轮次并入滚动摘要，发送给模型的窗口由固定消息、摘要与
+/// 尚未摘要的近期消息组成。系统消息与经 `pin` 固定的消息（如计划）永不被摘要替代。
 pub struct ContextManager {
     messages: Vec<ChatMessage>,
+    pinned: BTreeSet<usize>,
+    summary: Option<RunningSummary>,
 }
 
 impl Default for ContextManager {
@@ -15,31 +37,185 @@ impl ContextManager {
     pub fn new() -> Self {
         Self {
             messages: Vec::new(),
+            pinned: BTreeSet::new(),
+            summary: None,
+        }
+    }
+
+    /// 由已有对话（例如检查点中保存的对话）构建上下文
+    pub fn from_messages(messages: Vec<ChatMessage>) -> Self {
+        let mut context = Self::new();
+        for message in messages {
+            context.add_message(message);
         }
+        context
+    }
+
+    /// 恢复固定消息与滚动摘要（例如从检查点）
+    pub fn with_memory(mut self, pinned: &[usize], summary: Option<RunningSummary>) -> Self {
+        self.pinned
+            .extend(pinned.iter().filter(|i| **i < self.messages.len()));
+        self.summary = summary.filter(|s| s.through <= self.messages.len());
+        self
+    }
+
+    /// 完整的对话历史
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// 固定消息在历史中的下标
+    pub fn pinned(&self) -> Vec<usize> {
+        self.pinned.iter().copied().collect()
     }
 
-    /// 添加消息到上下文
+    pub fn summary(&self) -> Option<&RunningSummary> {
+        self.summary.as_ref()
+    }
+
+    /// 添加消息到上下文；系统消息自动固定
     pub fn add_message(&mut self, message: ChatMessage) {
+        if message.role == MessageRole::System {
+            self.pinned.insert(self.messages.len());
+        }
+        self.messages.push(message);
+    }
+
+    /// 添加一条永不被摘要替代的消息
+    pub fn add_pinned(&mut self, message: ChatMessage) {
+        self.pinned.insert(self.messages.len());
         self.messages.push(message);
     }
 
+    /// 固定已有的消息
+    pub fn pin(&mut self, index: usize) {
+        if index < self.messages.len() {
+            self.pinned.insert(index);
+        }
+    }
+
     /// 压缩上下文（Mock 逻辑：保留最后 N 条）
     pub fn compress(&mut self, limit: usize) {
         if self.messages.len() > limit {
             let start = self.messages.len() - limit;
             self.messages = self.messages.drain(start..).collect();
+            self.pinned = self
+                .pinned
+                .iter()
+                .filter_map(|i| i.checked_sub(start))
+                .collect();
+            self.summary = None;
         }
     }
 
     pub fn message_count(&self) -> usize {
         self.messages.len()
     }
+
+    /// 发送给模型的消息：摘要之前的固定消息、摘要、其后的全部消息
+    pub fn window(&self) -> Vec<ChatMessage> {
+        let through = self.summary.as_ref().map_or(0, |s| s.through);
+        let mut window: Vec<ChatMessage> = self
+            .pinned
+            .iter()
+            .take_while(|i| **i < through)
+            .map(|i| self.messages[*i].clone())
+            .collect();
+        if let Some(summary) = &self.summary {
+            window.push(ChatMessage::text(
+                MessageRole::System,
+                &format!("{}{}", SUMMARY_PREFIX, summary.text),
+            ));
+        }
+        window.extend(self.messages[through..].iter().cloned());
+        window
+    }
+
+    /// 准备发送给模型的窗口；接近上下文上限时先把最早的轮次并入滚动摘要
+    ///
+    /// 最后一轮（从最后一条用户消息开始）始终原样保留。压缩后仍超出预算时返回错误。
+    pub async fn prepare(
+        &mut self,
+        client: &dyn LLMClient,
+        model: &str,
+        limit: ModelLimit,
+        options: &ChatOptions,
+    ) -> Result<Vec<ChatMessage>> {
+        let prompt = PromptBudget::new(model, limit);
+        let budget = prompt.budget(options);
+        let counter = prompt.counter();
+        let window = self.window();
+        let tokens = counter.count_messages(&window);
+        if (tokens as f32) < budget as f32 * COMPRESS_AT {
+            return Ok(window);
+        }
+
+        // 从已摘要的位置起，按轮次累计要并入摘要的消息，直到剩余部分降到目标以下
+        let through = self.summary.as_ref().map_or(0, |s| s.through);
+        let last_turn = self
+            .messages
+            .iter()
+            .rposition(|m| m.role == MessageRole::User)
+            .unwrap_or(self.messages.len());
+        let target = (budget as f32 * COMPRESS_TO) as u32;
+        let mut remaining = tokens;
+        let mut end = through;
+        for (i, message) in self
+            .messages
+            .iter()
+            .enumerate()
+            .take(last_turn)
+            .skip(through)
+        {
+            if remaining <= target && message.role == MessageRole::User && i > through {
+                break;
+            }
+            if !self.pinned.contains(&i) {
+                remaining = remaining.saturating_sub(counter.count_message(message));
+            }
+            end = i + 1;
+        }
+        // 只在轮次边界截断，避免留下孤立的工具结果
+        while end < last_turn && self.messages[end].role != MessageRole::User {
+            end += 1;
+        }
+        let evicted: Vec<ChatMessage> = (through..end)
+            .filter(|i| !self.pinned.contains(i))
+            .map(|i| self.messages[i].clone())
+            .collect();
+        if !evicted.is_empty() {
+            let previous = self.summary.as_ref().map(|s| s.text.as_str());
+            let text = prompt
+                .summarize(
+                    client,
+                    model,
+                    previous,
+                    &evicted,
+                    budget,
+                    SUMMARY_MAX_TOKENS,
+                )
+                .await?;
+            self.summary = Some(RunningSummary { through: end, text });
+        }
+
+        let window = self.window();
+        let tokens = counter.count_messages(&window);
+        if tokens > budget {
+            return Err(anyhow!(
+                "Context still exceeds the budget after summarization: {} > {} tokens",
+                tokens,
+                budget
+            ));
+        }
+        Ok(window)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::common::endpoint::{MessageContent, MessageRole};
+    use crate::common::endpoint::MessageContent;
+    use crate::common::endpoint::scripted::ScriptedClient;
 
     #[test]
     fn test_context_manager() {
@@ -48,6 +224,7 @@ mod tests {
             role: MessageRole::User,
             content: MessageContent::Text("hello".to_string()),
             tool_calls: None,
+            tool_call_id: None,
         };
 
         manager.add_message(msg.clone());
@@ -57,4 +234,97 @@ mod tests {
         manager.compress(1);
         assert_eq!(manager.message_count(), 1);
     }
+
+    #[tokio::test]
+    async fn test_old_turns_become_running_summaries() {
+        let client = ScriptedClient::new("summarizer")
+            .with_responder(|call, _| format!("summary #{}", call + 1));
+        let limit = ModelLimit {
+            context: 600,
+            output: Some(100),
+        };
+        let options = ChatOptions::default();
+        let mut context = ContextManager::new();
+        context.add_message(ChatMessage::text(
+            MessageRole::System,
+            "You are a coding agent.",
+        ));
+        context.add_pinned(ChatMessage::text(
+            MessageRole::User,
+            "Plan: 1. reproduce 2. fix 3. test",
+        ));
+        let filler = "the build fails in module parser because of a lifetime issue ".repeat(3);
+        let mut turn = 0;
+        let mut add_turn = |context: &mut ContextManager| {
+            turn += 1;
+            context.add_message(ChatMessage::text(
+                MessageRole::User,
+                &format!("step {}: {}", turn, filler),
+            ));
+            context.add_message(ChatMessage::text(
+                MessageRole::Assistant,
+                &format!("done {}: {}", turn, filler),
+            ));
+        };
+
+        // 未接近上限时原样发送
+        add_turn(&mut context);
+        let window = context
+            .prepare(&client, "m", limit, &options)
+            .await
+            .unwrap();
+        assert_eq!(window.len(), 4);
+        assert_eq!(client.calls(), 0);
+
+        for _ in 0..5 {
+            add_turn(&mut context);
+        }
+        let window = context
+            .prepare(&client, "m", limit, &options)
+            .await
+            .unwrap();
+        let first = context.summary().unwrap().clone();
+        assert_eq!(window[0].content.as_text(), "You are a coding agent.");
+        assert!(window[1].content.as_text().starts_with("Plan:"));
+        assert_eq!(
+            window[2].content.as_text(),
+            format!("{}summary #1", SUMMARY_PREFIX)
+        );
+        assert!(
+            window
+                .last()
+                .unwrap()
+                .content
+                .as_text()
+                .starts_with("done 6")
+        );
+        assert!(!client.prompts()[0].contains("Plan:"));
+        // 完整历史仍然保留
+        assert_eq!(context.message_count(), 14);
+
+        // 再次接近上限时，新的摘要合并上一份摘要
+        for _ in 0..5 {
+            add_turn(&mut context);
+        }
+        let window = context
+            .prepare(&client, "m", limit, &options)
+            .await
+            .unwrap();
+        let second = context.summary().unwrap();
+        assert!(second.through > first.through);
+        assert!(client.prompts()[1].contains("Previous summary:\nsummary #1"));
+        assert!(window[1].content.as_text().starts_with("Plan:"));
+        let budget = PromptBudget::new("m", limit).budget(&options);
+        assert!(
+            PromptBudget::new("m", limit)
+                .counter()
+                .count_messages(&window)
+                <= budget
+        );
+
+        // 固定消息与摘要可随检查点恢复
+        let restored = ContextManager::from_messages(context.messages().to_vec())
+            .with_memory(&context.pinned(), context.summary().cloned());
+        assert_eq!(restored.window(), window);
+    }
 }
@@ -0,0 +1,261 @@
+use crate::agent::explain::read_lines;
+use crate::common::provider::traits::StorageProvider;
+use crate::compiler::stacktrace::{NavigationTarget, ResolvedFrame, StackTrace};
+use crate::project::index::WorkspaceIndex;
+use crate::semantic::graph::{GraphBuilder, SymbolLocation};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+
+/// 崩溃上下文中的一段源码
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashSnippet {
+    /// 对应的栈帧位置
+    pub target: NavigationTarget,
+    /// 包含该行的函数；语义图谱中找不到时为 `None`，片段为该行前后的窗口
+    pub symbol: Option<String>,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub code: String,
+}
+
+/// 交给 Agent 调试崩溃的上下文
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashContext {
+    pub trace: StackTrace,
+    /// 全部栈帧，工作区内的帧带有跳转位置
+    pub frames: Vec<ResolvedFrame>,
+    pub snippets: Vec<CrashSnippet>,
+}
+
+impl CrashContext {
+    /// 可跳转的栈帧位置（从崩溃点向外）
+    pub fn targets(&self) -> impl Iterator<Item = &NavigationTarget> {
+        self.frames.iter().filter_map(|f| f.target.as_ref())
+    }
+
+    /// 渲染为“调试此崩溃”的提示词
+    pub fn prompt(&self) -> String {
+        let mut prompt = format!(
+            "The program crashed ({:?}) with:\n{}\n\nStack (innermost first):\n",
+            self.trace.language, self.trace.message
+        );
+        for resolved in &self.frames {
+            let frame = &resolved.frame;
+            let location = match &resolved.target {
+                Some(target) => format!("{}:{}", target.path, target.line),
+                None => format!("{}:{} (external)", frame.file, frame.line),
+            };
+            prompt.push_str(&format!(
+                "- {} at {}\n",
+                frame.function.as_deref().unwrap_or("<unknown>"),
+                location
+            ));
+        }
+        for snippet in &self.snippets {
+            prompt.push_str(&format!(
+                "\n{}:{}-{}{}\n```\n{}\n```\n",
+                snippet.target.path,
+                snippet.start_line,
+                snippet.end_line,
+                snippet
+                    .symbol
+                    .as_ref()
+                    .map(|s| format!(" ({})", s))
+                    .unwrap_or_default(),
+                snippet.code
+            ));
+        }
+        prompt.push_str(
+            "\nExplain the root cause of this crash and propose a fix to the workspace code.",
+        );
+        prompt
+    }
+}
+
+/// 从栈回溯组装调试上下文：将帧映射到工作区文件，并附上崩溃路径上的函数源码
+pub struct CrashContextBuilder {
+    storage: Arc<dyn StorageProvider>,
+    graph: Arc<RwLock<GraphBuilder>>,
+    /// 最多附带源码的工作区帧数
+    max_frames: usize,
+    /// 找不到所在函数时，出错行前后各取的行数
+    window: u32,
+    /// 单个片段的最大行数
+    max_snippet_lines: usize,
+}
+
+impl CrashContextBuilder {
+    pub fn new(storage: Arc<dyn StorageProvider>, graph: Arc<RwLock<GraphBuilder>>) -> Self {
+        Self {
+            storage,
+            graph,
+            max_frames: 5,
+            window: 5,
+            max_snippet_lines: 80,
+        }
+    }
+
+    pub fn with_max_frames(mut self, frames: usize) -> Self {
+        self.max_frames = frames;
+        self
+    }
+
+    pub fn with_window(mut self, lines: u32) -> Self {
+        self.window = lines;
+        self
+    }
+
+    pub fn with_max_snippet_lines(mut self, lines: usize) -> Self {
+        self.max_snippet_lines = lines.max(1);
+        self
+    }
+
+    /// 解析 `text` 中的栈回溯，并按 `root` 下的工作区文件组装上下文
+    pub async fn build(&self, text: &str, root: &str) -> Result<CrashContext> {
+        let trace = StackTrace::parse(text).ok_or_else(|| anyhow!("No stack trace found"))?;
+        let index = WorkspaceIndex::scan(self.storage.as_ref(), root).await?;
+        self.build_from(trace, &index).await
+    }
+
+    pub async fn build_from(
+        &self,
+        trace: StackTrace,
+        index: &WorkspaceIndex,
+    ) -> Result<CrashContext> {
+        let frames = trace.resolve(index);
+        let mut snippets = Vec::new();
+        // 同一函数（或同一窗口）在递归等情况下只附带一次
+        let mut seen = BTreeSet::new();
+        for target in frames
+            .iter()
+            .filter_map(|f| f.target.as_ref())
+            .take(self.max_frames)
+        {
+            let enclosing = self.enclosing_function(target);
+            let (symbol, start, end) = match enclosing {
+                Some((name, location)) => (Some(name), location.start_line, location.end_line),
+                None => (
+                    None,
+                    target.line.saturating_sub(self.window).max(1),
+                    target.line + self.window,
+                ),
+            };
+            if !seen.insert((target.path.clone(), start, end)) {
+                continue;
+            }
+            let Ok((code, end)) = read_lines(
+                self.storage.as_ref(),
+                &target.path,
+                start,
+                end,
+                self.max_snippet_lines,
+            )
+            .await
+            else {
+                continue;
+            };
+            snippets.push(CrashSnippet {
+                target: target.clone(),
+                symbol,
+                start_line: start,
+                end_line: end,
+                code,
+            });
+        }
+        Ok(CrashContext {
+            trace,
+            frames,
+            snippets,
+        })
+    }
+
+    /// 包含该行的最内层定义
+    fn enclosing_function(&self, target: &NavigationTarget) -> Option<(String, SymbolLocation)> {
+        let graph = self.graph.read().unwrap();
+        graph
+            .definitions_in(&target.path, target.line, target.line)
+            .into_iter()
+            .filter_map(|d| Some((d.name.clone(), d.location.clone()?)))
+            .min_by_key(|(_, l)| l.end_line - l.start_line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::meta::MetaNode;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_crash_context_includes_functions_on_the_stack() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/config.rs"),
+            "use std::env;\n\npub fn load() -> u16 {\n    let port = env::var(\"PORT\").ok();\n    port.unwrap().parse().unwrap()\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("src/main.rs"),
+            "mod config;\n\nfn main() {\n    let port = config::load();\n    println!(\"{}\", port);\n}\n",
+        )
+        .unwrap();
+
+        let module: MetaNode = serde_json::from_value(json!({
+            "type": "module", "name": "config", "path": "src/config.rs",
+            "children": [
+                {"type": "function", "name": "load", "params": [], "start_line": 3, "end_line": 6,
+                 "body": null}
+            ]
+        }))
+        .unwrap();
+        let mut graph = GraphBuilder::new();
+        graph.build(module);
+
+        let builder = CrashContextBuilder::new(
+            Arc::new(LocalFileSystem::new(dir.path())),
+            Arc::new(RwLock::new(graph)),
+        )
+        .with_window(1);
+        let text = "\
+thread 'main' panicked at src/config.rs:5:10:
+called `Option::unwrap()` on a `None` value
+stack backtrace:
+   0: core::option::unwrap_failed
+             at /rustc/90b35a623/library/core/src/option.rs:2015:5
+   1: demo::config::load
+             at ./src/config.rs:5:10
+   2: demo::main
+             at /home/dev/demo/src/main.rs:4:16
+";
+        let context = builder.build(text, "").await.unwrap();
+        let targets: Vec<(&str, u32)> = context
+            .targets()
+            .map(|t| (t.path.as_str(), t.line))
+            .collect();
+        assert_eq!(targets, vec![("src/config.rs", 5), ("src/main.rs", 4)]);
+
+        // 图谱中有定义的帧附带整个函数，其余帧附带出错行附近的窗口
+        assert_eq!(context.snippets.len(), 2);
+        assert_eq!(context.snippets[0].symbol.as_deref(), Some("load"));
+        assert_eq!(
+            (context.snippets[0].start_line, context.snippets[0].end_line),
+            (3, 6)
+        );
+        assert_eq!(context.snippets[1].symbol, None);
+        assert_eq!(
+            context.snippets[1].code,
+            "fn main() {\n    let port = config::load();\n    println!(\"{}\", port);"
+        );
+
+        let prompt = context.prompt();
+        assert!(prompt.contains("- demo::config::load at src/config.rs:5\n"));
+        assert!(prompt.contains("option.rs:2015 (external)"));
+        assert!(prompt.contains("src/config.rs:3-6 (load)\n```\npub fn load() -> u16 {"));
+
+        assert!(builder.build("no crash here", "").await.is_err());
+    }
+}
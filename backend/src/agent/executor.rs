@@ -1,15 +1,353 @@
-use crate::agent::Routine;
-use crate::common::change::thread::ThreadManager;
+use crate::agent::approval::{ApprovalGate, ApprovalPolicy, ApprovalStatus, ProposedOperation};
+use crate::agent::checkpoint::{CheckpointStore, RoutineCheckpoint};
+use crate::agent::guardrail::{Guardrails, RoutineBudget};
+use crate::agent::intent::AgentIntent;
+use crate::agent::manager::RoutineManager;
+use crate::agent::planner::{PlanRun, PlanStep, StepOutcome, StepResult};
+use crate::agent::{Routine, RoutineId, RoutineStatus};
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::endpoint::cassette::{Cassette, CassetteClient};
+use crate::common::endpoint::traits::{ChatOptions, LLMClient};
+use crate::common::intent::journal::IntentJournal;
+use crate::common::intent::{IntentDispatcher, IntentOrigin, SystemIntent};
 use anyhow::Result;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::collections::BTreeSet;
+use std::future::Future;
 use std::sync::Arc;
 
+/// 录制或回放中的运行
+struct Recording {
+    client: Arc<CassetteClient>,
+    journal: Arc<IntentJournal>,
+    seed: Option<u64>,
+}
+
 pub struct RoutineExecutor {
     thread_manager: Arc<ThreadManager>,
+    recording: Option<Recording>,
+    checkpoints: Option<Arc<CheckpointStore>>,
+    guardrails: Guardrails,
+    approvals: Option<Arc<ApprovalGate>>,
 }
 
 impl RoutineExecutor {
     pub fn new(thread_manager: Arc<ThreadManager>) -> Self {
-        Self { thread_manager }
+        Self {
+            thread_manager,
+            recording: None,
+            checkpoints: None,
+            guardrails: Guardrails::default(),
+            approvals: None,
+        }
+    }
+
+    /// 录制模式：模型调用经 `inner` 执行并录制，采样使用固定种子
+    pub fn record(mut self, inner: Arc<dyn LLMClient>, seed: u64) -> Self {
+        self.recording = Some(Recording {
+            client: Arc::new(CassetteClient::record(inner, Some(seed))),
+            journal: Arc::new(IntentJournal::new()),
+            seed: Some(seed),
+        });
+        self
+    }
+
+    /// 回放模式：模型调用返回录制的响应，意图日志以录制结果为预期
+    pub fn replay(mut self, cassette: Cassette) -> Self {
+        let seed = cassette.seed;
+        let journal = Arc::new(IntentJournal::expecting(cassette.intents.clone()));
+        self.recording = Some(Recording {
+            client: Arc::new(CassetteClient::replay(cassette)),
+            journal,
+            seed,
+        });
+        self
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.recording
+            .as_ref()
+            .is_some_and(|r| r.client.is_replaying())
+    }
+
+    /// 录制/回放时 Routine 应使用的模型客户端
+    pub fn client(&self) -> Option<Arc<dyn LLMClient>> {
+        self.recording
+            .as_ref()
+            .map(|r| r.client.clone() as Arc<dyn LLMClient>)
+    }
+
+    /// 录制/回放时应交给 `IntentDispatcher::with_journal` 的意图日志
+    pub fn journal(&self) -> Option<Arc<IntentJournal>> {
+        self.recording.as_ref().map(|r| r.journal.clone())
+    }
+
+    /// 补齐采样选项：录制/回放时固定种子并使用零温度
+    pub fn options(&self, base: ChatOptions) -> ChatOptions {
+        match &self.recording {
+            Some(recording) => ChatOptions {
+                seed: recording.seed,
+                temperature: Some(0.0),
+                ..base
+            },
+            None => base,
+        }
+    }
+
+    /// 当前运行的录制结果（含意图日志），可保存后用于回放
+    pub fn cassette(&self) -> Option<Cassette> {
+        self.recording.as_ref().map(|r| Cassette {
+            intents: r.journal.entries(),
+            ..r.client.cassette()
+        })
+    }
+
+    /// 回放时意图日志第一次偏离录制的位置
+    pub fn divergence(&self) -> Option<usize> {
+        self.recording.as_ref()?.journal.divergence()
+    }
+
+    /// 持久化 Routine 检查点，进程重启后可经 `resume` 继续
+    pub fn with_checkpoints(mut self, store: Arc<CheckpointStore>) -> Self {
+        self.checkpoints = Some(store);
+        self
+    }
+
+    fn checkpoint_store(&self) -> Result<&CheckpointStore> {
+        self.checkpoints
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Checkpoint store not configured"))
+    }
+
+    /// 保存 Routine 的当前状态（通常在每个步骤之后或暂停时调用）
+    pub async fn checkpoint(&self, checkpoint: &RoutineCheckpoint) -> Result<()> {
+        self.checkpoint_store()?.save(checkpoint).await
+    }
+
+    /// 可恢复的检查点：已暂停或运行中被进程退出打断的 Routine
+    pub async fn resumable(&self) -> Result<Vec<RoutineCheckpoint>> {
+        Ok(self
+            .checkpoint_store()?
+            .list()
+            .await?
+            .into_iter()
+            .filter(RoutineCheckpoint::is_resumable)
+            .collect())
+    }
+
+    /// 从检查点恢复 Routine：对话、待执行的工具调用与步骤计数保持不变，状态重新置为运行中
+    pub async fn resume(&self, id: RoutineId) -> Result<RoutineCheckpoint> {
+        let mut checkpoint = self
+            .checkpoint_store()?
+            .load(&id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No checkpoint for routine {}", id))?;
+        if !checkpoint.is_resumable() {
+            return Err(anyhow::anyhow!(
+                "Routine {} is not resumable: {:?}",
+                id,
+                checkpoint.routine.status
+            ));
+        }
+        if self
+            .thread_manager
+            .get_thread(checkpoint.routine.active_thread)
+            .is_none()
+        {
+            return Err(anyhow::anyhow!(
+                "Thread {} of routine {} no longer exists",
+                checkpoint.routine.active_thread,
+                id
+            ));
+        }
+        checkpoint.routine.status = RoutineStatus::Running;
+        Ok(checkpoint)
+    }
+
+    /// Routine 结束后删除其检查点
+    pub async fn finish(&self, id: RoutineId) -> Result<()> {
+        self.checkpoint_store()?.remove(&id).await
+    }
+
+    /// 每个 Routine 的资源上限（步骤、token、费用、时长）
+    pub fn with_guardrails(mut self, guardrails: Guardrails) -> Self {
+        self.guardrails = guardrails;
+        self
+    }
+
+    /// 开始为一个 Routine 计量，计时从此刻开始
+    pub fn budget(&self) -> RoutineBudget {
+        RoutineBudget::new(self.guardrails.clone())
+    }
+
+    /// 检查 Routine 的消耗；超出上限时将其置为失败、派发 `GuardrailExceeded` 意图并返回错误
+    ///
+    /// 应在每个步骤与每次模型调用之后调用。没有注册 Agent 意图处理器时仍会使 Routine 失败。
+    pub async fn enforce(
+        &self,
+        manager: &RoutineManager,
+        routine_id: RoutineId,
+        budget: &RoutineBudget,
+        dispatcher: &Arc<IntentDispatcher>,
+    ) -> Result<()> {
+        let Err(violation) = budget.check() else {
+            return Ok(());
+        };
+        manager.set_status(&routine_id, RoutineStatus::Failed(violation.to_string()));
+        let _ = dispatcher
+            .enqueue(
+                routine_id,
+                IntentOrigin::agent(routine_id),
+                vec![SystemIntent::Agent(AgentIntent::GuardrailExceeded {
+                    routine_id,
+                    violation: violation.clone(),
+                })],
+            )
+            .await;
+        Err(violation.into())
+    }
+
+    /// 高影响操作执行前经该处理器请求人工批准
+    pub fn with_approvals(mut self, gate: Arc<ApprovalGate>) -> Self {
+        self.approvals = Some(gate);
+        self
+    }
+
+    /// 执行操作前按批准策略请求批准，未获批准时返回错误
+    ///
+    /// 需要批准时派发 `AgentIntent::RequestApproval` 并将 Routine 置为 `Paused` 直到请求被处理；
+    /// 超时或被拒绝都不执行操作。等待期间 Routine 被取消时同样返回错误。
+    /// 未配置批准处理器时任何需要批准的操作都被拒绝。
+    pub async fn request_approval(
+        &self,
+        manager: &RoutineManager,
+        routine_id: RoutineId,
+        operation: ProposedOperation,
+        dispatcher: &Arc<IntentDispatcher>,
+    ) -> Result<()> {
+        let Some(gate) = &self.approvals else {
+            if ApprovalPolicy::default().requires_approval(&operation) {
+                return Err(anyhow::anyhow!(
+                    "No approval handler configured to {}",
+                    operation
+                ));
+            }
+            return Ok(());
+        };
+        if !gate.policy().requires_approval(&operation) {
+            return Ok(());
+        }
+        let request = gate.open(routine_id, operation);
+        manager.set_status(&routine_id, RoutineStatus::Paused);
+        let _ = dispatcher
+            .enqueue(
+                routine_id,
+                IntentOrigin::agent(routine_id),
+                vec![SystemIntent::Agent(AgentIntent::RequestApproval {
+                    request: request.clone(),
+                })],
+            )
+            .await;
+        let status = gate.wait(request.id, gate.policy().timeout).await?;
+
+        if manager.get(&routine_id).map(|r| r.status) != Some(RoutineStatus::Paused) {
+            return Err(anyhow::anyhow!(
+                "Routine {} stopped while waiting for approval",
+                routine_id
+            ));
+        }
+        manager.set_status(&routine_id, RoutineStatus::Running);
+        match status {
+            ApprovalStatus::Approved => Ok(()),
+            ApprovalStatus::Rejected { reason } => Err(anyhow::anyhow!(
+                "Approval to {} was rejected{}",
+                request.operation,
+                reason.map(|r| format!(": {}", r)).unwrap_or_default()
+            )),
+            _ => Err(anyhow::anyhow!("Approval to {} expired", request.operation)),
+        }
+    }
+
+    /// 按 Routine 上的计划执行步骤，依赖已满足的步骤并发运行
+    ///
+    /// 每个步骤在分叉出的 `plan/<routine>/<step>` 线程上运行：只依赖一个步骤时从该步骤的线程分叉，
+    /// 否则从 Routine 的活动线程分叉。依赖失败或被跳过的步骤不再运行。
+    pub async fn run_plan<F, Fut>(&self, routine: &Routine, run: F) -> Result<PlanRun>
+    where
+        F: Fn(PlanStep, ThreadId) -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let plan = routine
+            .plan
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Routine {} has no plan", routine.id))?;
+        plan.layers()?;
+
+        let mut result = PlanRun::default();
+        let mut started = BTreeSet::new();
+        let mut running = FuturesUnordered::new();
+        loop {
+            // 跳过的步骤可能让更多步骤变为可处理，反复扫描直到没有新的跳过
+            let mut skipped = true;
+            while skipped {
+                skipped = false;
+                for step in &plan.steps {
+                    if started.contains(&step.id) {
+                        continue;
+                    }
+                    let deps: Option<Vec<&StepResult>> = step
+                        .depends_on
+                        .iter()
+                        .map(|d| result.steps.get(d))
+                        .collect();
+                    let Some(deps) = deps else {
+                        continue;
+                    };
+                    started.insert(step.id.clone());
+                    if deps
+                        .iter()
+                        .any(|d| !matches!(d.outcome, StepOutcome::Completed { .. }))
+                    {
+                        result.steps.insert(
+                            step.id.clone(),
+                            StepResult {
+                                thread: None,
+                                outcome: StepOutcome::Skipped,
+                            },
+                        );
+                        skipped = true;
+                        continue;
+                    }
+                    let base = match deps.as_slice() {
+                        [dep] => dep.thread.unwrap_or(routine.active_thread),
+                        _ => routine.active_thread,
+                    };
+                    let thread = self
+                        .thread_manager
+                        .create_branch(base, &format!("plan/{}/{}", routine.id, step.id))?;
+                    let id = step.id.clone();
+                    let future = run(step.clone(), thread);
+                    running.push(async move { (id, thread, future.await) });
+                }
+            }
+            let Some((id, thread, output)) = running.next().await else {
+                break;
+            };
+            let outcome = match output {
+                Ok(output) => StepOutcome::Completed { output },
+                Err(e) => StepOutcome::Failed {
+                    reason: e.to_string(),
+                },
+            };
+            result.steps.insert(
+                id,
+                StepResult {
+                    thread: Some(thread),
+                    outcome,
+                },
+            );
+        }
+        Ok(result)
     }
 
     pub fn fork(&self, parent: &Routine, name: &str) -> Result<Routine> {
@@ -17,9 +355,120 @@ impl RoutineExecutor {
             .thread_manager
             .create_branch(parent.active_thread, name)?;
 
-        let mut child = Routine::new(child_thread);
+        let mut child = Routine::new(child_thread).with_tool_policy(parent.tool_policy.clone());
         child.parent = Some(parent.id);
 
         Ok(child)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::common::endpoint::traits::{ChatMessage, MessageRole};
+    use crate::common::intent::traits::SystemIntent;
+    use crate::common::intent::{EditorIntent, IntentCategory, IntentDispatcher, IntentHandler};
+    use async_trait::async_trait;
+
+    struct Accept;
+
+    #[async_trait]
+    impl IntentHandler for Accept {
+        async fn handle(&self, _intent: SystemIntent) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    async fn run(executor: &RoutineExecutor, prompt: &str) -> Result<String> {
+        let dispatcher = IntentDispatcher::new().with_journal(executor.journal().unwrap());
+        dispatcher
+            .register(IntentCategory::Editor, Arc::new(Accept))
+            .await;
+        let client = executor.client().unwrap();
+        let options = executor.options(ChatOptions::default());
+        let messages = vec![ChatMessage::text(MessageRole::User, prompt)];
+        let response = client.chat("m", &messages, &options).await?;
+        let answer = response.choices[0].message.content.as_text();
+        dispatcher
+            .dispatch(SystemIntent::Editor(EditorIntent::OpenFile {
+                path: answer.clone(),
+            }))
+            .await?;
+        Ok(answer)
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay_is_deterministic() {
+        let threads = Arc::new(ThreadManager::new());
+        // 每次返回不同内容，模拟非确定性的采样
+        let model = Arc::new(
+            ScriptedClient::new("drifting").with_responder(|call, _| format!("answer {}", call)),
+        );
+        let recorder = RoutineExecutor::new(threads.clone()).record(model.clone(), 7);
+        let first = run(&recorder, "fix the bug").await.unwrap();
+        assert_eq!(model.requests()[0].options.seed, Some(7));
+        let cassette = recorder.cassette().unwrap();
+        assert_eq!(cassette.interactions.len(), 1);
+        assert_eq!(cassette.intents.len(), 1);
+
+        let saved: Cassette =
+            serde_json::from_slice(&serde_json::to_vec(&cassette).unwrap()).unwrap();
+        let replayer = RoutineExecutor::new(threads.clone()).replay(saved.clone());
+        assert!(replayer.is_replaying());
+        assert_eq!(run(&replayer, "fix the bug").await.unwrap(), first);
+        assert_eq!(replayer.divergence(), None);
+
+        // 请求偏离录制时立即失败
+        let diverged = RoutineExecutor::new(threads).replay(saved);
+        assert!(run(&diverged, "something else").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_forked_routine_tools_and_processes_follow_the_tool_policy() {
+        use crate::common::provider::local::process::LocalProcess;
+        use crate::common::provider::traits::ExecuteOptions;
+        use crate::skill::sandbox::ToolPolicy;
+        use crate::skill::traits::SkillError;
+
+        let threads = Arc::new(ThreadManager::new());
+        let executor = RoutineExecutor::new(threads.clone());
+        let root = threads.get_thread_id_by_name("main").unwrap();
+        let parent = Routine::new(root).with_tool_policy(
+            ToolPolicy::default()
+                .deny("register_skill")
+                .allow_command("echo"),
+        );
+        let child = executor.fork(&parent, "child").unwrap();
+        assert_eq!(child.tool_policy, parent.tool_policy);
+
+        let tools = child.tools();
+        let skill = serde_json::json!({ "name": "x", "description": "", "content": "" });
+        assert!(matches!(
+            tools.execute("register_skill", skill).await,
+            Err(SkillError::Forbidden(_))
+        ));
+
+        let process = child.process(Arc::new(LocalProcess));
+        let echoed = process
+            .execute("echo hi", ExecuteOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(echoed.stdout.trim(), "hi");
+        assert!(
+            process
+                .execute("rm -rf target", ExecuteOptions::default())
+                .await
+                .is_err()
+        );
+
+        let review = Routine::new(root).with_tool_policy(ToolPolicy::review());
+        assert!(
+            review
+                .process(Arc::new(LocalProcess))
+                .execute("echo hi", ExecuteOptions::default())
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,453 @@
+use crate::common::endpoint::{ChatMessage, ChatOptions, LLMClient, MessageRole};
+use crate::common::provider::traits::StorageProvider;
+use crate::semantic::graph::{Definition, GraphBuilder, SymbolLocation};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::{Arc, RwLock};
+
+/// 选区行范围（行号从 1 开始，闭区间）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// 上下文片段与选区的关系
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CitationKind {
+    Selection,
+    Caller,
+    Callee,
+    Doc,
+}
+
+/// 解释中可引用的代码库位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Citation {
+    /// 提示词中的编号，如 `C1`
+    pub id: String,
+    pub kind: CitationKind,
+    pub path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub symbol: Option<String>,
+}
+
+/// 解释中的一条要点及其引用
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExplanationPoint {
+    pub text: String,
+    #[serde(default)]
+    pub citations: Vec<String>,
+}
+
+/// 结构化的代码解释
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Explanation {
+    pub summary: String,
+    pub steps: Vec<ExplanationPoint>,
+    pub pitfalls: Vec<ExplanationPoint>,
+    /// 解释中实际引用到的位置
+    pub citations: Vec<Citation>,
+}
+
+/// 发送给模型的一段上下文
+struct Snippet {
+    citation: Citation,
+    code: String,
+}
+
+const EXPLAIN_PROMPT: &str = "You explain code to a developer. Use only the numbered context \
+blocks below; cite them by id (e.g. \"C2\") when a statement relies on them. Reply with JSON only: \
+{\"summary\": \"...\", \"steps\": [{\"text\": \"...\", \"citations\": [\"C1\"]}], \
+\"pitfalls\": [{\"text\": \"...\", \"citations\": []}]}";
+
+/// 代码解释服务：收集选区、语义图谱中的调用者/被调用者与相关文档，
+/// 请模型给出带引用的结构化解释
+pub struct ExplainService {
+    storage: Arc<dyn StorageProvider>,
+    graph: Arc<RwLock<GraphBuilder>>,
+    client: Arc<dyn LLMClient>,
+    model: String,
+    /// 单个上下文片段的最大行数
+    max_snippet_lines: usize,
+}
+
+impl ExplainService {
+    pub fn new(
+        storage: Arc<dyn StorageProvider>,
+        graph: Arc<RwLock<GraphBuilder>>,
+        client: Arc<dyn LLMClient>,
+        model: &str,
+    ) -> Self {
+        Self {
+            storage,
+            graph,
+            client,
+            model: model.to_string(),
+            max_snippet_lines: 80,
+        }
+    }
+
+    pub fn with_max_snippet_lines(mut self, lines: usize) -> Self {
+        self.max_snippet_lines = lines.max(1);
+        self
+    }
+
+    /// 解释 `path` 中 `range` 范围的代码；`depth` 为沿调用关系展开的层数（0 表示只看选区）
+    pub async fn explain(&self, path: &str, range: LineRange, depth: usize) -> Result<Explanation> {
+        self.explain_focused(path, range, depth, None).await
+    }
+
+    /// 同 `explain`，`question` 为用户希望解释侧重的问题
+    pub async fn explain_focused(
+        &self,
+        path: &str,
+        range: LineRange,
+        depth: usize,
+        question: Option<&str>,
+    ) -> Result<Explanation> {
+        let snippets = self.gather(path, range, depth).await?;
+        let mut prompt = String::new();
+        for snippet in &snippets {
+            let c = &snippet.citation;
+            prompt.push_str(&format!(
+                "[{}] {:?} {}:{}-{}{}\n```\n{}\n```\n\n",
+                c.id,
+                c.kind,
+                c.path,
+                c.start_line,
+                c.end_line,
+                c.symbol
+                    .as_ref()
+                    .map(|s| format!(" ({})", s))
+                    .unwrap_or_default(),
+                snippet.code
+            ));
+        }
+        prompt.push_str("Explain the Selection block.");
+        if let Some(question) = question {
+            prompt.push_str(&format!(" Focus on this question: {}", question));
+        }
+
+        let messages = [
+            ChatMessage::text(MessageRole::System, EXPLAIN_PROMPT),
+            ChatMessage::text(MessageRole::User, &prompt),
+        ];
+        let options = ChatOptions {
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+        let response = self.client.chat(&self.model, &messages, &options).await?;
+        let text = response
+            .choices
+            .first()
+            .map(|c| c.message.content.as_text())
+            .unwrap_or_default();
+        let json = match (text.find('{'), text.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &text[start..=end],
+            _ => return Err(anyhow!("Unparseable explanation: {}", text)),
+        };
+
+        #[derive(Deserialize)]
+        struct Reply {
+            summary: String,
+            #[serde(default)]
+            steps: Vec<ExplanationPoint>,
+            #[serde(default)]
+            pitfalls: Vec<ExplanationPoint>,
+        }
+        let reply: Reply = serde_json::from_str(json)?;
+        let known: BTreeSet<&str> = snippets.iter().map(|s| s.citation.id.as_str()).collect();
+        let clean = |points: Vec<ExplanationPoint>| -> Vec<ExplanationPoint> {
+            points
+                .into_iter()
+                .map(|mut p| {
+                    p.citations.retain(|c| known.contains(c.as_str()));
+                    p
+                })
+                .collect()
+        };
+        let steps = clean(reply.steps);
+        let pitfalls = clean(reply.pitfalls);
+        let cited: BTreeSet<&str> = steps
+            .iter()
+            .chain(&pitfalls)
+            .flat_map(|p| p.citations.iter().map(String::as_str))
+            .collect();
+        let citations = snippets
+            .iter()
+            .filter(|s| {
+                s.citation.kind == CitationKind::Selection || cited.contains(s.citation.id.as_str())
+            })
+            .map(|s| s.citation.clone())
+            .collect();
+        Ok(Explanation {
+            summary: reply.summary,
+            steps,
+            pitfalls,
+            citations,
+        })
+    }
+
+    /// 收集选区、调用关系与文档片段，依次编号为 `C1`、`C2`……
+    async fn gather(&self, path: &str, range: LineRange, depth: usize) -> Result<Vec<Snippet>> {
+        if range.start == 0 || range.end < range.start {
+            return Err(anyhow!("Invalid line range {}-{}", range.start, range.end));
+        }
+        let mut snippets = Vec::new();
+        let selection = read_lines(
+            self.storage.as_ref(),
+            path,
+            range.start,
+            range.end,
+            self.max_snippet_lines,
+        )
+        .await?
+        .0;
+        self.push(
+            &mut snippets,
+            CitationKind::Selection,
+            path,
+            range,
+            None,
+            selection,
+        );
+
+        // 按层展开调用关系；图谱锁不跨越 await
+        let related: Vec<(CitationKind, Definition)> = {
+            let graph = self.graph.read().unwrap();
+            let selected: Vec<&Definition> = graph.definitions_in(path, range.start, range.end);
+            let mut seen: BTreeSet<String> = selected.iter().map(|d| d.name.clone()).collect();
+            let mut queue: VecDeque<(String, usize)> =
+                selected.iter().map(|d| (d.name.clone(), 0)).collect();
+            let mut related: Vec<(CitationKind, Definition)> = selected
+                .iter()
+                .filter(|d| d.doc.is_some())
+                .map(|d| (CitationKind::Doc, (*d).clone()))
+                .collect();
+            while let Some((name, level)) = queue.pop_front() {
+                if level >= depth {
+                    continue;
+                }
+                let neighbours = graph
+                    .callers(&name)
+                    .into_iter()
+                    .map(|n| (CitationKind::Caller, n))
+                    .chain(
+                        graph
+                            .callees(&name)
+                            .into_iter()
+                            .map(|n| (CitationKind::Callee, n)),
+                    );
+                for (kind, neighbour) in neighbours {
+                    if !seen.insert(neighbour.to_string()) {
+                        continue;
+                    }
+                    queue.push_back((neighbour.to_string(), level + 1));
+                    related.extend(
+                        graph
+                            .definitions(neighbour)
+                            .iter()
+                            .map(|d| (kind, d.clone())),
+                    );
+                }
+            }
+            related
+        };
+
+        for (kind, definition) in related {
+            let Some(SymbolLocation {
+                path,
+                start_line,
+                end_line,
+            }) = definition.location
+            else {
+                continue;
+            };
+            let code = match kind {
+                CitationKind::Doc => definition.doc.unwrap_or_default(),
+                _ => match read_lines(
+                    self.storage.as_ref(),
+                    &path,
+                    start_line,
+                    end_line,
+                    self.max_snippet_lines,
+                )
+                .await
+                {
+                    Ok((code, _)) => code,
+                    Err(_) => continue,
+                },
+            };
+            self.push(
+                &mut snippets,
+                kind,
+                &path,
+                LineRange {
+                    start: start_line,
+                    end: end_line,
+                },
+                Some(definition.name),
+                code,
+            );
+        }
+        Ok(snippets)
+    }
+
+    fn push(
+        &self,
+        snippets: &mut Vec<Snippet>,
+        kind: CitationKind,
+        path: &str,
+        range: LineRange,
+        symbol: Option<String>,
+        code: String,
+    ) {
+        snippets.push(Snippet {
+            citation: Citation {
+                id: format!("C{}", snippets.len() + 1),
+                kind,
+                path: path.to_string(),
+                start_line: range.start,
+                end_line: range.end,
+                symbol,
+            },
+            code,
+        });
+    }
+}
+
+/// 读取 `path` 的第 `start`..=`end` 行（从 1 开始），超过 `max_lines` 行时截断并以 `...` 结尾
+///
+/// 返回片段与实际读到的最后一行；范围完全越界时返回错误。
+pub(crate) async fn read_lines(
+    storage: &dyn StorageProvider,
+    path: &str,
+    start: u32,
+    end: u32,
+    max_lines: usize,
+) -> Result<(String, u32)> {
+    let content = String::from_utf8(storage.read_file(path).await?)?;
+    let lines: Vec<&str> = content
+        .lines()
+        .skip(start.saturating_sub(1) as usize)
+        .take((end - start + 1) as usize)
+        .collect();
+    if lines.is_empty() {
+        return Err(anyhow!("{}:{}-{} is out of range", path, start, end));
+    }
+    let end = start + lines.len() as u32 - 1;
+    let mut snippet = lines[..lines.len().min(max_lines)].join("\n");
+    if lines.len() > max_lines {
+        snippet.push_str("\n...");
+    }
+    Ok((snippet, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::common::meta::MetaNode;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_read_lines_clamps_and_truncates() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "1\n2\n3\n4\n").unwrap();
+        let storage = LocalFileSystem::new(dir.path());
+
+        assert_eq!(
+            read_lines(&storage, "a.rs", 2, 9, 10).await.unwrap(),
+            ("2\n3\n4".to_string(), 4)
+        );
+        assert_eq!(
+            read_lines(&storage, "a.rs", 1, 4, 2).await.unwrap(),
+            ("1\n2\n...".to_string(), 4)
+        );
+        assert!(read_lines(&storage, "a.rs", 5, 6, 10).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_explain_gathers_call_graph_context() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "fn main() {\n    run();\n}\n\nfn run() {\n    parse();\n}\n\nfn parse() {}\n",
+        )
+        .unwrap();
+
+        let module: MetaNode = serde_json::from_value(json!({
+            "type": "module", "name": "lib", "path": "src/lib.rs",
+            "children": [
+                {"type": "function", "name": "main", "params": [], "start_line": 1, "end_line": 3,
+                 "body": {"type": "call", "callee": {"type": "identifier", "name": "run"}, "args": []}},
+                {"type": "function", "name": "run", "params": [], "start_line": 5, "end_line": 7,
+                 "doc": "Runs the pipeline.",
+                 "body": {"type": "call", "callee": {"type": "identifier", "name": "parse"}, "args": []}},
+                {"type": "function", "name": "parse", "params": [], "start_line": 9, "end_line": 9,
+                 "body": null}
+            ]
+        }))
+        .unwrap();
+        let mut graph = GraphBuilder::new();
+        graph.build(module);
+
+        let reply = r#"{"summary": "run drives parsing",
+                "steps": [{"text": "called from main", "citations": ["C3", "C9"]}],
+                "pitfalls": [{"text": "no error handling"}]}"#;
+        let client =
+            Arc::new(ScriptedClient::new("explainer").with_responder(|_, _| reply.to_string()));
+        let service = ExplainService::new(
+            Arc::new(LocalFileSystem::new(dir.path())),
+            Arc::new(RwLock::new(graph)),
+            client.clone(),
+            "gpt-4o",
+        );
+
+        let explanation = service
+            .explain("src/lib.rs", LineRange { start: 5, end: 7 }, 1)
+            .await
+            .unwrap();
+        assert_eq!(explanation.summary, "run drives parsing");
+        // 未知的引用被丢弃
+        assert_eq!(explanation.steps[0].citations, vec!["C3"]);
+        assert!(explanation.pitfalls[0].citations.is_empty());
+        let cited: Vec<(&str, CitationKind)> = explanation
+            .citations
+            .iter()
+            .map(|c| (c.id.as_str(), c.kind))
+            .collect();
+        assert_eq!(
+            cited,
+            vec![
+                ("C1", CitationKind::Selection),
+                ("C3", CitationKind::Caller)
+            ]
+        );
+        assert_eq!(explanation.citations[1].symbol.as_deref(), Some("main"));
+
+        let prompt = client.prompts()[0].clone();
+        assert!(prompt.contains("[C1] Selection src/lib.rs:5-7\n```\nfn run() {"));
+        assert!(prompt.contains("[C2] Doc src/lib.rs:5-7 (run)\n```\nRuns the pipeline."));
+        assert!(prompt.contains("[C4] Callee src/lib.rs:9-9 (parse)"));
+
+        // depth 为 0 时只包含选区与文档
+        service
+            .explain("src/lib.rs", LineRange { start: 5, end: 7 }, 0)
+            .await
+            .unwrap();
+        assert!(!client.prompts()[1].contains("[C3]"));
+        assert!(
+            service
+                .explain("src/lib.rs", LineRange { start: 50, end: 60 }, 1)
+                .await
+                .is_err()
+        );
+    }
+}
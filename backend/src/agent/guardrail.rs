@@ -0,0 +1,244 @@
+use crate::common::endpoint::traits::{CostBreakdown, ModelCost, Usage};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 单个 Routine 的资源上限，未设置的项不限制
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Guardrails {
+    pub max_steps: Option<usize>,
+    /// 提示与补全 token 之和
+    pub max_tokens: Option<u64>,
+    /// 费用上限（与 `CostBreakdown` 同单位）
+    pub max_cost: Option<ModelCost>,
+    pub timeout: Option<Duration>,
+}
+
+impl Guardrails {
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u64) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn with_max_cost(mut self, max_cost: ModelCost) -> Self {
+        self.max_cost = Some(max_cost);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// 触发的资源上限
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GuardrailViolation {
+    Steps { limit: usize, used: usize },
+    Tokens { limit: u64, used: u64 },
+    Cost { limit: ModelCost, spent: ModelCost },
+    Timeout { limit: Duration, elapsed: Duration },
+}
+
+impl std::fmt::Display for GuardrailViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GuardrailViolation::Steps { limit, used } => {
+                write!(f, "Step limit exceeded: {} of {} steps", used, limit)
+            }
+            GuardrailViolation::Tokens { limit, used } => {
+                write!(f, "Token limit exceeded: {} of {} tokens", used, limit)
+            }
+            GuardrailViolation::Cost { limit, spent } => {
+                write!(f, "Cost limit exceeded: {:.4} of {:.4}", spent, limit)
+            }
+            GuardrailViolation::Timeout { limit, elapsed } => {
+                write!(f, "Timed out after {:?} (limit {:?})", elapsed, limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GuardrailViolation {}
+
+/// Routine 已消耗的资源
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetUsage {
+    pub steps: usize,
+    pub tokens: u64,
+    pub cost: ModelCost,
+}
+
+/// 按 `Guardrails` 累计一个 Routine 的消耗，计时从创建时开始
+pub struct RoutineBudget {
+    guardrails: Guardrails,
+    started: Instant,
+    usage: Mutex<BudgetUsage>,
+}
+
+impl RoutineBudget {
+    pub fn new(guardrails: Guardrails) -> Self {
+        Self {
+            guardrails,
+            started: Instant::now(),
+            usage: Mutex::new(BudgetUsage::default()),
+        }
+    }
+
+    pub fn guardrails(&self) -> &Guardrails {
+        &self.guardrails
+    }
+
+    pub fn usage(&self) -> BudgetUsage {
+        self.usage.lock().unwrap().clone()
+    }
+
+    pub fn record_step(&self) {
+        self.usage.lock().unwrap().steps += 1;
+    }
+
+    /// 记录一次模型调用的用量与费用
+    pub fn record_usage(&self, usage: &Usage, cost: &CostBreakdown) {
+        let mut total = self.usage.lock().unwrap();
+        total.tokens += u64::from(usage.prompt_tokens) + u64::from(usage.completion_tokens);
+        total.cost += cost.values().sum::<ModelCost>();
+    }
+
+    /// 检查是否超出任一上限（依次检查步骤、token、费用与时长）
+    pub fn check(&self) -> Result<(), GuardrailViolation> {
+        let usage = self.usage();
+        let limits = &self.guardrails;
+        if let Some(limit) = limits.max_steps
+            && usage.steps > limit
+        {
+            return Err(GuardrailViolation::Steps {
+                limit,
+                used: usage.steps,
+            });
+        }
+        if let Some(limit) = limits.max_tokens
+            && usage.tokens > limit
+        {
+            return Err(GuardrailViolation::Tokens {
+                limit,
+                used: usage.tokens,
+            });
+        }
+        if let Some(limit) = limits.max_cost
+            && usage.cost > limit
+        {
+            return Err(GuardrailViolation::Cost {
+                limit,
+                spent: usage.cost,
+            });
+        }
+        let elapsed = self.started.elapsed();
+        if let Some(limit) = limits.timeout
+            && elapsed > limit
+        {
+            return Err(GuardrailViolation::Timeout { limit, elapsed });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::executor::RoutineExecutor;
+    use crate::agent::intent::AgentIntent;
+    use crate::agent::manager::RoutineManager;
+    use crate::agent::{Routine, RoutineStatus};
+    use crate::common::change::thread::ThreadManager;
+    use crate::common::intent::{IntentCategory, IntentDispatcher, IntentHandler, SystemIntent};
+    use async_trait::async_trait;
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct Surface(Mutex<Vec<GuardrailViolation>>);
+
+    #[async_trait]
+    impl IntentHandler for Surface {
+        async fn handle(&self, intent: SystemIntent) -> anyhow::Result<()> {
+            if let SystemIntent::Agent(AgentIntent::GuardrailExceeded { violation, .. }) = intent {
+                self.0.lock().unwrap().push(violation);
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exceeding_a_guardrail_fails_the_routine() {
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let executor = RoutineExecutor::new(threads).with_guardrails(
+            Guardrails::default()
+                .with_max_steps(3)
+                .with_max_tokens(1_000)
+                .with_max_cost(0.05),
+        );
+        let manager = RoutineManager::new();
+        let routine = Routine::new(main);
+        let id = routine.id;
+        manager.register(routine);
+        let surface = Arc::new(Surface::default());
+        let dispatcher = Arc::new(IntentDispatcher::new());
+        dispatcher
+            .register(IntentCategory::Agent, surface.clone())
+            .await;
+
+        let budget = executor.budget();
+        let usage = Usage {
+            prompt_tokens: 300,
+            completion_tokens: 100,
+            total_tokens: 400,
+        };
+        let cost = CostBreakdown::from([("input".to_string(), 0.02), ("output".to_string(), 0.01)]);
+        budget.record_step();
+        budget.record_usage(&usage, &cost);
+        executor
+            .enforce(&manager, id, &budget, &dispatcher)
+            .await
+            .unwrap();
+        assert_eq!(manager.get(&id).unwrap().status, RoutineStatus::Running);
+
+        // 第二次调用后费用超出上限（token 仍在上限内）
+        budget.record_step();
+        budget.record_usage(&usage, &cost);
+        let err = executor
+            .enforce(&manager, id, &budget, &dispatcher)
+            .await
+            .unwrap_err();
+        let violation = err.downcast_ref::<GuardrailViolation>().unwrap();
+        assert!(matches!(violation, GuardrailViolation::Cost { limit, .. } if *limit == 0.05));
+        assert!(matches!(
+            manager.get(&id).unwrap().status,
+            RoutineStatus::Failed(reason) if reason.starts_with("Cost limit exceeded")
+        ));
+        assert_eq!(
+            surface.0.lock().unwrap().as_slice(),
+            std::slice::from_ref(violation)
+        );
+
+        let slow = RoutineBudget::new(Guardrails::default().with_timeout(Duration::ZERO));
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(matches!(
+            slow.check(),
+            Err(GuardrailViolation::Timeout { .. })
+        ));
+        let busy = RoutineBudget::new(Guardrails::default().with_max_steps(1));
+        busy.record_step();
+        assert!(busy.check().is_ok());
+        busy.record_step();
+        assert_eq!(
+            busy.check(),
+            Err(GuardrailViolation::Steps { limit: 1, used: 2 })
+        );
+    }
+}
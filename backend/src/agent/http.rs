@@ -0,0 +1,355 @@
+use crate::skill::tool::{Tool, ToolOutput};
+use crate::skill::traits::SkillError;
+use async_trait::async_trait;
+use reqwest::{Client, Method, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// 出站 HTTP 访问策略
+///
+/// 默认只允许访问本机（开发服务器），其余主机需显式放行。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkPolicy {
+    /// 允许的主机，支持 `*.example.com` 形式的子域通配，`*` 表示任意主机
+    pub allowed_hosts: Vec<String>,
+    /// 允许的方法（大写）；为空时不限制
+    #[serde(default)]
+    pub allowed_methods: Vec<String>,
+}
+
+impl Default for NetworkPolicy {
+    fn default() -> Self {
+        Self::local_only()
+    }
+}
+
+impl NetworkPolicy {
+    pub fn local_only() -> Self {
+        Self {
+            allowed_hosts: vec![
+                "localhost".to_string(),
+                "127.0.0.1".to_string(),
+                "::1".to_string(),
+            ],
+            allowed_methods: Vec::new(),
+        }
+    }
+
+    pub fn with_host(mut self, pattern: &str) -> Self {
+        self.allowed_hosts.push(pattern.to_ascii_lowercase());
+        self
+    }
+
+    /// 只允许不修改服务端状态的方法
+    pub fn read_only(mut self) -> Self {
+        self.allowed_methods = ["GET", "HEAD", "OPTIONS"].map(String::from).to_vec();
+        self
+    }
+
+    /// 检查请求是否被允许，拒绝时返回原因
+    pub fn check(&self, method: &Method, url: &Url) -> Result<(), String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Scheme '{}' is not allowed", url.scheme()));
+        }
+        if !self.allowed_methods.is_empty()
+            && !self.allowed_methods.iter().any(|m| m == method.as_str())
+        {
+            return Err(format!("Method {} is not allowed", method));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| "URL has no host".to_string())?
+            .trim_matches(['[', ']'])
+            .to_ascii_lowercase();
+        let allowed = self.allowed_hosts.iter().any(|pattern| {
+            pattern == "*"
+                || match pattern.strip_prefix("*.") {
+                    Some(domain) => host == domain || host.ends_with(&format!(".{}", domain)),
+                    None => host == *pattern,
+                }
+        });
+        if !allowed {
+            return Err(format!(
+                "Host '{}' is not allowed by the network policy",
+                host
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 捕获的 HTTP 响应
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpExchange {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    /// 响应体（非 UTF-8 字节按有损方式转换）
+    pub body: String,
+    /// 响应体超过上限被截断
+    pub truncated: bool,
+    pub elapsed_ms: u64,
+}
+
+/// `http_request` 工具：按网络策略发送 HTTP 请求并捕获响应，供 Agent 验证接口
+///
+/// 不自动跟随重定向（重定向目标同样需要经过策略检查），响应体超过上限时截断。
+pub struct HttpRequestTool {
+    client: Client,
+    policy: NetworkPolicy,
+    max_body_bytes: usize,
+    default_timeout: Duration,
+    max_timeout: Duration,
+}
+
+impl HttpRequestTool {
+    pub fn new(policy: NetworkPolicy) -> Self {
+        Self {
+            client: Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .expect("HTTP client"),
+            policy,
+            max_body_bytes: 64 * 1024,
+            default_timeout: Duration::from_secs(30),
+            max_timeout: Duration::from_secs(120),
+        }
+    }
+
+    pub fn with_max_body_bytes(mut self, bytes: usize) -> Self {
+        self.max_body_bytes = bytes.max(1);
+        self
+    }
+
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = timeout;
+        self
+    }
+
+    /// 单次请求允许的最长超时，超过时按此值截断
+    pub fn with_max_timeout(mut self, timeout: Duration) -> Self {
+        self.max_timeout = timeout;
+        self
+    }
+
+    pub fn policy(&self) -> &NetworkPolicy {
+        &self.policy
+    }
+
+    /// 发送请求；`body` 为字符串时原样发送，其余 JSON 值序列化后以 `application/json` 发送
+    pub async fn send(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &BTreeMap<String, String>,
+        body: Option<&Value>,
+        timeout: Option<Duration>,
+    ) -> anyhow::Result<HttpExchange> {
+        let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())?;
+        let url = Url::parse(url)?;
+        self.policy
+            .check(&method, &url)
+            .map_err(|reason| anyhow::anyhow!(reason))?;
+
+        let timeout = timeout
+            .unwrap_or(self.default_timeout)
+            .min(self.max_timeout);
+        let mut request = self
+            .client
+            .request(method.clone(), url.clone())
+            .timeout(timeout);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        request = match body {
+            None | Some(Value::Null) => request,
+            Some(Value::String(text)) => request.body(text.clone()),
+            Some(value) => request.json(value),
+        };
+
+        let started = Instant::now();
+        let mut response = request.send().await?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        let mut bytes = Vec::new();
+        let mut truncated = false;
+        while let Some(chunk) = response.chunk().await? {
+            let room = self.max_body_bytes - bytes.len();
+            if chunk.len() > room {
+                bytes.extend_from_slice(&chunk[..room]);
+                truncated = true;
+                break;
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(HttpExchange {
+            method: method.to_string(),
+            url: url.to_string(),
+            status,
+            headers,
+            body: String::from_utf8_lossy(&bytes).into_owned(),
+            truncated,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Tool for HttpRequestTool {
+    fn name(&self) -> &'static str {
+        "http_request"
+    }
+
+    fn description(&self) -> &'static str {
+        "发送 HTTP 请求并返回状态码、响应头与（截断后的）响应体，用于验证正在开发的接口；仅能访问网络策略允许的主机。"
+    }
+
+    fn parameter_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "method": {
+                    "type": "string",
+                    "description": "HTTP 方法，默认 GET"
+                },
+                "url": { "type": "string" },
+                "headers": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" }
+                },
+                "body": {
+                    "description": "请求体；字符串原样发送，对象或数组以 JSON 发送"
+                },
+                "timeout_ms": { "type": "integer" }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
+        let url = args["url"]
+            .as_str()
+            .ok_or_else(|| SkillError::InvalidSkill("Missing 'url' parameter".into()))?;
+        let method = args["method"].as_str().unwrap_or("GET");
+        let headers: BTreeMap<String, String> = match &args["headers"] {
+            Value::Null => BTreeMap::new(),
+            value => serde_json::from_value(value.clone())
+                .map_err(|e| SkillError::InvalidSkill(format!("Invalid headers: {}", e)))?,
+        };
+        let timeout = args["timeout_ms"].as_u64().map(Duration::from_millis);
+
+        let exchange = self
+            .send(method, url, &headers, args.get("body"), timeout)
+            .await
+            .map_err(|e| SkillError::IoError(std::io::Error::other(e.to_string())))?;
+        let mut content = format!(
+            "{} {} -> {} ({} ms)\n",
+            exchange.method, exchange.url, exchange.status, exchange.elapsed_ms
+        );
+        for (name, value) in &exchange.headers {
+            content.push_str(&format!("{}: {}\n", name, value));
+        }
+        content.push('\n');
+        content.push_str(&exchange.body);
+        if exchange.truncated {
+            content.push_str("\n[body truncated]");
+        }
+        Ok(ToolOutput {
+            content,
+            data: Some(json!(exchange)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_network_policy() {
+        let policy = NetworkPolicy::default().with_host("*.example.com");
+        let check = |method: Method, url: &str| policy.check(&method, &Url::parse(url).unwrap());
+        assert!(check(Method::POST, "http://localhost:3000/api").is_ok());
+        assert!(check(Method::GET, "http://[::1]:8080/").is_ok());
+        assert!(check(Method::GET, "https://api.example.com/v1").is_ok());
+        assert!(check(Method::GET, "https://example.com.evil.io/").is_err());
+        assert!(check(Method::GET, "file:///etc/passwd").is_err());
+
+        let read_only = NetworkPolicy::local_only().read_only();
+        assert!(
+            read_only
+                .check(&Method::DELETE, &Url::parse("http://localhost/").unwrap())
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_request_tool_captures_and_truncates_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !String::from_utf8_lossy(&request).contains("{\"name\":\"zhiyun\"}") {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let body = "x".repeat(100);
+            socket
+                .write_all(
+                    format!(
+                        "HTTP/1.1 201 Created\r\ncontent-length: {}\r\nx-request-id: 7\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let tool = HttpRequestTool::new(NetworkPolicy::local_only()).with_max_body_bytes(10);
+        let output = tool
+            .execute(json!({
+                "method": "post",
+                "url": format!("http://{}/users", addr),
+                "headers": {"authorization": "Bearer t"},
+                "body": {"name": "zhiyun"}
+            }))
+            .await
+            .unwrap();
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /users"));
+        assert!(request.contains("authorization: Bearer t"));
+        assert!(request.contains("content-type: application/json"));
+
+        let exchange: HttpExchange = serde_json::from_value(output.data.unwrap()).unwrap();
+        assert_eq!(exchange.status, 201);
+        assert_eq!(exchange.headers["x-request-id"], "7");
+        assert_eq!(exchange.body, "x".repeat(10));
+        assert!(exchange.truncated);
+        assert!(output.content.ends_with("xxxxxxxxxx\n[body truncated]"));
+
+        let denied = tool
+            .execute(json!({"url": "https://example.com/"}))
+            .await
+            .unwrap_err();
+        assert!(denied.to_string().contains("not allowed"));
+    }
+}
@@ -1,3 +1,8 @@
+use crate::agent::RoutineId;
+use crate::agent::approval::ApprovalRequest;
+use crate::agent::guardrail::GuardrailViolation;
+use crate::common::intent::command::Selection;
+
 /// 智能体特定的意图。
 #[derive(Debug, Clone)]
 pub enum AgentIntent {
@@ -5,4 +10,21 @@ pub enum AgentIntent {
     CallTool { name: String, args: String },
     /// 终止当前任务
     Abort,
+    /// Routine 超出资源上限而失败（供界面提示）
+    GuardrailExceeded {
+        routine_id: RoutineId,
+        violation: GuardrailViolation,
+    },
+    /// Routine 提议了高影响操作，暂停等待用户经 `ApprovalIntent` 批准或拒绝
+    RequestApproval { request: ApprovalRequest },
+    /// 运行项目测试，`filter` 只运行名称包含该文本的测试
+    RunTests {
+        filter: Option<String>,
+        verbose: bool,
+    },
+    /// 解释编辑器选区中的代码，`question` 为关注点
+    Explain {
+        selection: Selection,
+        question: Option<String>,
+    },
 }
@@ -0,0 +1,388 @@
+use crate::common::provider::traits::{ExecuteOptions, ExecutionProvider, StorageProvider};
+use crate::skill::sandbox::ToolEffects;
+use crate::skill::tool::{Tool, ToolOutput};
+use crate::skill::traits::SkillError;
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::StreamExt;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// 日志来源
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "source", rename_all = "snake_case")]
+pub enum LogSource {
+    /// 跟踪文件新增的内容
+    File(String),
+    /// 运行命令并读取其 stdout/stderr
+    Command(String),
+}
+
+/// 一次日志跟踪的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TailReport {
+    pub source: LogSource,
+    /// 通过过滤的行
+    pub lines: Vec<String>,
+    /// 过滤前读到的行数
+    pub scanned: usize,
+    /// 达到行数或字节上限后提前停止
+    pub truncated: bool,
+    /// 文件被截断或轮转（从头重新读取）的次数
+    pub rotations: usize,
+}
+
+/// 日志跟踪：在限定时间内观察文件或命令的实时输出，
+/// 在服务端按正则过滤并限制返回的行数与字节数
+pub struct LogTailer {
+    storage: Arc<dyn StorageProvider>,
+    runner: Arc<dyn ExecutionProvider>,
+    /// 文件轮询间隔
+    poll_interval: Duration,
+    /// 开始跟踪前附带的文件末尾行数
+    backlog: usize,
+    max_lines: usize,
+    max_bytes: usize,
+    max_duration: Duration,
+}
+
+impl LogTailer {
+    pub fn new(storage: Arc<dyn StorageProvider>, runner: Arc<dyn ExecutionProvider>) -> Self {
+        Self {
+            storage,
+            runner,
+            poll_interval: Duration::from_millis(250),
+            backlog: 20,
+            max_lines: 200,
+            max_bytes: 16 * 1024,
+            max_duration: Duration::from_secs(60),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    pub fn with_backlog(mut self, lines: usize) -> Self {
+        self.backlog = lines;
+        self
+    }
+
+    pub fn with_max_lines(mut self, lines: usize) -> Self {
+        self.max_lines = lines.max(1);
+        self
+    }
+
+    pub fn with_max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = bytes.max(1);
+        self
+    }
+
+    /// 单次跟踪的最长时间，超过时按此值截断
+    pub fn with_max_duration(mut self, duration: Duration) -> Self {
+        self.max_duration = duration;
+        self
+    }
+
+    /// 跟踪 `source` 至多 `duration`，只保留匹配 `filter` 的行
+    pub async fn tail(
+        &self,
+        source: LogSource,
+        filter: Option<&Regex>,
+        duration: Duration,
+    ) -> Result<TailReport> {
+        let deadline = Instant::now() + duration.min(self.max_duration);
+        let mut report = TailReport {
+            source: source.clone(),
+            lines: Vec::new(),
+            scanned: 0,
+            truncated: false,
+            rotations: 0,
+        };
+        let mut collector = Collector {
+            filter,
+            max_lines: self.max_lines,
+            max_bytes: self.max_bytes,
+            bytes: 0,
+            report: &mut report,
+        };
+        match &source {
+            LogSource::File(path) => self.tail_file(path, &mut collector, deadline).await?,
+            LogSource::Command(command) => {
+                self.tail_command(command, &mut collector, deadline).await?
+            }
+        }
+        Ok(report)
+    }
+
+    async fn tail_file(
+        &self,
+        path: &str,
+        collector: &mut Collector<'_>,
+        deadline: Instant,
+    ) -> Result<()> {
+        let content = self.storage.read_file(path).await?;
+        let mut offset = content.len();
+        // 最后一个换行之后的内容可能是尚未写完的行，留到下一次读取
+        let complete = content
+            .iter()
+            .rposition(|b| *b == b'\n')
+            .map_or(0, |i| i + 1);
+        let backlog: Vec<&str> = std::str::from_utf8(&content[..complete])
+            .unwrap_or_default()
+            .lines()
+            .collect();
+        for line in &backlog[backlog.len().saturating_sub(self.backlog)..] {
+            if !collector.push(line) {
+                return Ok(());
+            }
+        }
+        let mut partial = content[complete..].to_vec();
+
+        while Instant::now() < deadline {
+            tokio::time::sleep_until((Instant::now() + self.poll_interval).min(deadline)).await;
+            let size = self.storage.get_metadata(path).await?.size as usize;
+            if size < offset {
+                // 文件被截断或轮转
+                collector.report.rotations += 1;
+                offset = 0;
+                partial.clear();
+            }
+            if size == offset {
+                continue;
+            }
+            let content = self.storage.read_file(path).await?;
+            partial.extend_from_slice(&content[offset.min(content.len())..]);
+            offset = content.len();
+            while let Some(end) = partial.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = partial.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                if !collector.push(line.trim_end_matches(['\r', '\n'])) {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn tail_command(
+        &self,
+        command: &str,
+        collector: &mut Collector<'_>,
+        deadline: Instant,
+    ) -> Result<()> {
+        let mut stream = self
+            .runner
+            .execute_stream(command, ExecuteOptions::default())
+            .await?;
+        // 到时或提前结束时丢弃输出流，由提供者终止进程
+        while let Ok(Some(line)) = tokio::time::timeout_at(deadline, stream.next()).await {
+            if !collector.push(line?.text()) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 过滤并累计输出，达到上限时返回 `false`
+struct Collector<'a> {
+    filter: Option<&'a Regex>,
+    max_lines: usize,
+    max_bytes: usize,
+    bytes: usize,
+    report: &'a mut TailReport,
+}
+
+impl Collector<'_> {
+    fn push(&mut self, line: &str) -> bool {
+        self.report.scanned += 1;
+        if self.filter.is_some_and(|f| !f.is_match(line)) {
+            return true;
+        }
+        if self.report.lines.len() >= self.max_lines || self.bytes + line.len() > self.max_bytes {
+            self.report.truncated = true;
+            return false;
+        }
+        self.bytes += line.len() + 1;
+        self.report.lines.push(line.to_string());
+        true
+    }
+}
+
+/// `tail_logs` 工具：观察日志文件或命令的实时输出
+pub struct TailLogsTool {
+    tailer: Arc<LogTailer>,
+}
+
+impl TailLogsTool {
+    pub fn new(tailer: Arc<LogTailer>) -> Self {
+        Self { tailer }
+    }
+}
+
+#[async_trait(?Send)]
+impl Tool for TailLogsTool {
+    fn name(&self) -> &'static str {
+        "tail_logs"
+    }
+
+    fn description(&self) -> &'static str {
+        "在限定时间内观察日志文件的新增内容或命令的实时输出，可按正则过滤，返回的行数与字节数有上限。"
+    }
+
+    fn parameter_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "source": {
+                    "type": "string",
+                    "description": "日志文件路径；文件不存在时作为命令执行"
+                },
+                "filter": {
+                    "type": "string",
+                    "description": "只返回匹配该正则的行"
+                },
+                "duration_ms": {
+                    "type": "integer",
+                    "description": "观察时长（毫秒），默认 5000"
+                }
+            },
+            "required": ["source"]
+        })
+    }
+
+    async fn effects(&self, args: &Value) -> ToolEffects {
+        let Some(source) = args["source"].as_str() else {
+            return ToolEffects::default();
+        };
+        // 与 `execute` 相同：文件不存在时作为命令执行
+        match self.tailer.storage.exists(source).await {
+            Ok(true) => ToolEffects::default(),
+            _ => ToolEffects::runs(vec![source.to_string()]),
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
+        let source = args["source"]
+            .as_str()
+            .ok_or_else(|| SkillError::InvalidSkill("Missing 'source' parameter".into()))?;
+        let filter = args["filter"]
+            .as_str()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| SkillError::InvalidSkill(format!("Invalid filter: {}", e)))?;
+        let duration = Duration::from_millis(args["duration_ms"].as_u64().unwrap_or(5_000));
+        let io_error = |e: anyhow::Error| SkillError::IoError(std::io::Error::other(e.to_string()));
+
+        let source = if self.tailer.storage.exists(source).await.map_err(io_error)? {
+            LogSource::File(source.to_string())
+        } else {
+            LogSource::Command(source.to_string())
+        };
+        let report = self
+            .tailer
+            .tail(source, filter.as_ref(), duration)
+            .await
+            .map_err(io_error)?;
+        let mut content = report.lines.join("\n");
+        if report.truncated {
+            content.push_str("\n[output truncated]");
+        }
+        Ok(ToolOutput {
+            content,
+            data: Some(json!(report)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::common::provider::traits::ExecuteResult;
+
+    /// 一次性返回固定输出的执行器（使用默认的 `execute_stream`）
+    struct Canned;
+
+    #[async_trait]
+    impl ExecutionProvider for Canned {
+        async fn execute(&self, command: &str, _options: ExecuteOptions) -> Result<ExecuteResult> {
+            assert_eq!(command, "docker logs api");
+            Ok(ExecuteResult {
+                exit_code: 0,
+                stdout: "INFO boot\nERROR db down\nERROR retry failed\n".to_string(),
+                stderr: "ERROR panic\n".to_string(),
+            })
+        }
+
+        async fn kill(&self, _task_id: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tail_file_follows_appended_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("app.log");
+        std::fs::write(&log, "INFO old\nERROR old failure\nINFO partial").unwrap();
+        let tailer = LogTailer::new(Arc::new(LocalFileSystem::new(dir.path())), Arc::new(Canned))
+            .with_poll_interval(Duration::from_millis(10));
+
+        let writer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let mut content = std::fs::read_to_string(&log).unwrap();
+            content.push_str(" line\nERROR new failure\nINFO ok\n");
+            std::fs::write(&log, content).unwrap();
+        });
+        let filter = Regex::new("ERROR").unwrap();
+        let report = tailer
+            .tail(
+                LogSource::File("app.log".to_string()),
+                Some(&filter),
+                Duration::from_millis(300),
+            )
+            .await
+            .unwrap();
+        writer.await.unwrap();
+
+        assert_eq!(report.lines, vec!["ERROR old failure", "ERROR new failure"]);
+        // 未写完的行在补全后才被读取
+        assert_eq!(report.scanned, 5);
+        assert!(!report.truncated);
+    }
+
+    #[tokio::test]
+    async fn test_tail_logs_tool_runs_commands_with_caps() {
+        let dir = tempfile::tempdir().unwrap();
+        let tailer = LogTailer::new(Arc::new(LocalFileSystem::new(dir.path())), Arc::new(Canned))
+            .with_max_lines(2);
+        let tool = TailLogsTool::new(Arc::new(tailer));
+
+        let output = tool
+            .execute(json!({"source": "docker logs api", "filter": "^ERROR", "duration_ms": 100}))
+            .await
+            .unwrap();
+        assert_eq!(
+            output.content,
+            "ERROR db down\nERROR retry failed\n[output truncated]"
+        );
+        let report: TailReport = serde_json::from_value(output.data.unwrap()).unwrap();
+        assert_eq!(
+            report.source,
+            LogSource::Command("docker logs api".to_string())
+        );
+        assert!(report.truncated);
+
+        assert!(
+            tool.execute(json!({"source": "docker logs api", "filter": "("}))
+                .await
+                .is_err()
+        );
+    }
+}
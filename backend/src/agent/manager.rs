@@ -1,10 +1,24 @@
-use crate::agent::{Routine, RoutineId};
+use crate::agent::spawn::ChildReport;
+use crate::agent::{Routine, RoutineId, RoutineStatus};
+use crate::common::change::thread::ThreadManager;
+use crate::common::event::{BackendEvent, EventBus};
+use crate::common::lifecycle::shutdown::{ShutdownHook, ShutdownPhase};
+use async_trait::async_trait;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
+use tokio::sync::watch;
 
 /// 跟踪所有活跃的 Routine 及其层级关系
 pub struct RoutineManager {
-    routines: Arc<RwLock<HashMap<RoutineId, Routine>>>,
+    pub(super) routines: Arc<RwLock<HashMap<RoutineId, Routine>>>,
+    /// Routine 注册与状态变化时向其发布 Agent 事件
+    pub(super) events: Option<Arc<EventBus>>,
+    /// 派生子 Routine 时用于分叉线程
+    pub(super) threads: Option<Arc<ThreadManager>>,
+    /// 运行中子 Routine 的取消信号
+    pub(super) cancels: Arc<RwLock<HashMap<RoutineId, watch::Sender<bool>>>>,
+    /// 父 Routine 尚未取走的子 Routine 结果
+    pub(super) reports: Arc<RwLock<HashMap<RoutineId, Vec<ChildReport>>>>,
 }
 
 impl Default for RoutineManager {
@@ -17,11 +31,38 @@ impl RoutineManager {
     pub fn new() -> Self {
         Self {
             routines: Arc::new(RwLock::new(HashMap::new())),
+            events: None,
+            threads: None,
+            cancels: Arc::new(RwLock::new(HashMap::new())),
+            reports: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// 将 Routine 生命周期事件发布到事件总线
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 派生子 Routine 时分叉的线程管理器
+    pub fn with_threads(mut self, threads: Arc<ThreadManager>) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    fn emit(&self, id: RoutineId, event: &str, status: &RoutineStatus) {
+        if let Some(events) = &self.events {
+            events.emit(BackendEvent::Agent {
+                routine_id: id,
+                event: event.to_string(),
+                detail: serde_json::json!({ "status": status }),
+            });
         }
     }
 
     /// 注册新的 Routine
     pub fn register(&self, routine: Routine) {
+        self.emit(routine.id, "registered", &routine.status);
         let mut routines = self.routines.write().unwrap();
         routines.insert(routine.id, routine);
     }
@@ -37,12 +78,56 @@ impl RoutineManager {
         let routines = self.routines.read().unwrap();
         routines.len()
     }
+
+    /// 更新 Routine 状态，返回是否存在该 Routine
+    pub fn set_status(&self, id: &RoutineId, status: RoutineStatus) -> bool {
+        let mut routines = self.routines.write().unwrap();
+        match routines.get_mut(id) {
+            Some(routine) => {
+                self.emit(*id, "status_changed", &status);
+                routine.status = status;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 将所有运行中的 Routine 暂停（检查点），返回受影响的 ID
+    pub fn checkpoint_running(&self) -> Vec<RoutineId> {
+        let mut routines = self.routines.write().unwrap();
+        routines
+            .values_mut()
+            .filter(|r| r.status == RoutineStatus::Running)
+            .map(|r| {
+                r.status = RoutineStatus::Paused;
+                self.emit(r.id, "status_changed", &r.status);
+                r.id
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ShutdownHook for RoutineManager {
+    fn name(&self) -> &str {
+        "routine-manager"
+    }
+
+    fn phase(&self) -> ShutdownPhase {
+        ShutdownPhase::Routines
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        self.checkpoint_running();
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::agent::Routine;
+    use crate::common::event::{EventFilter, EventKind, FileSink};
     use uuid::Uuid;
 
     #[test]
@@ -54,5 +139,34 @@ mod tests {
         manager.register(routine);
         assert_eq!(manager.count(), 1);
         assert!(manager.get(&id).is_some());
+
+        assert_eq!(manager.checkpoint_running(), vec![id]);
+        assert_eq!(manager.get(&id).unwrap().status, RoutineStatus::Paused);
+    }
+
+    #[tokio::test]
+    async fn test_routine_manager_publishes_lifecycle_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let bus = Arc::new(EventBus::new());
+        bus.subscribe(
+            Arc::new(FileSink::new(&path)),
+            EventFilter::only([EventKind::Agent]),
+        );
+        let manager = RoutineManager::new().with_events(bus.clone());
+        let routine = Routine::new(Uuid::new_v4());
+        let id = routine.id;
+        manager.register(routine);
+        manager.set_status(&id, RoutineStatus::Failed("boom".to_string()));
+        assert_eq!(bus.flush().await, 2);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let events: Vec<serde_json::Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(events[0]["event"], "registered");
+        assert_eq!(events[1]["routine_id"], id.to_string());
+        assert_eq!(events[1]["detail"]["status"]["Failed"], "boom");
     }
 }
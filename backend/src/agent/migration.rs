@@ -0,0 +1,596 @@
+use crate::agent::executor::RoutineExecutor;
+use crate::agent::webhook::RoutineTemplate;
+use crate::agent::{Routine, RoutineStatus};
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::change::{Change, Operation, VectorClock};
+use crate::common::provider::traits::{ExecuteOptions, ExecutionProvider, StorageProvider};
+use crate::project::index::WorkspaceIndex;
+use crate::syntax::executor::ParserExecutor;
+use crate::syntax::query::SyntaxQuery;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 一条弃用 API 规则
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeprecationRule {
+    pub id: String,
+    pub query: SyntaxQuery,
+    pub message: String,
+    /// 建议的替代写法
+    #[serde(default)]
+    pub replacement: Option<String>,
+}
+
+/// 框架或语言版本升级指南
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationGuide {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    /// 解析源文件使用的语言（对应 `ParserExecutor` 中注册的解析器）
+    pub language: String,
+    /// 需要扫描的文件扩展名
+    pub extensions: Vec<String>,
+    pub rules: Vec<DeprecationRule>,
+    /// 验证阶段依次执行的命令
+    pub verify: Vec<String>,
+}
+
+impl MigrationGuide {
+    /// 内置指南：axum 0.6 -> 0.7
+    pub fn axum_0_7() -> Self {
+        let rule =
+            |id: &str, query: SyntaxQuery, message: &str, replacement: &str| DeprecationRule {
+                id: id.to_string(),
+                query,
+                message: message.to_string(),
+                replacement: Some(replacement.to_string()),
+            };
+        Self {
+            name: "axum-0.7".to_string(),
+            from: "axum 0.6".to_string(),
+            to: "axum 0.7".to_string(),
+            language: "rust".to_string(),
+            extensions: vec!["rs".to_string()],
+            rules: vec![
+                rule(
+                    "server-removed",
+                    SyntaxQuery::Call("*Server::bind".to_string()),
+                    "`axum::Server` was removed",
+                    "let listener = tokio::net::TcpListener::bind(addr).await?; axum::serve(listener, app).await?",
+                ),
+                rule(
+                    "box-body-removed",
+                    SyntaxQuery::Identifier("*body::BoxBody".to_string()),
+                    "`axum::body::BoxBody` was removed",
+                    "axum::body::Body",
+                ),
+                rule(
+                    "boxed-removed",
+                    SyntaxQuery::Call("*body::boxed".to_string()),
+                    "`axum::body::boxed` was removed",
+                    "axum::body::Body::new",
+                ),
+                rule(
+                    "hyper-body",
+                    SyntaxQuery::Identifier("hyper::Body".to_string()),
+                    "axum 0.7 uses its own body type instead of `hyper::Body`",
+                    "axum::body::Body",
+                ),
+            ],
+            verify: vec!["cargo build".to_string(), "cargo test".to_string()],
+        }
+    }
+}
+
+/// 文件中命中的一处弃用用法
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationFinding {
+    pub rule_id: String,
+    pub message: String,
+    pub replacement: Option<String>,
+    /// 命中的名称文本
+    pub matched: String,
+    pub symbol: Option<String>,
+    pub start_line: Option<u32>,
+    pub end_line: Option<u32>,
+}
+
+/// 单个文件的迁移计划
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileMigration {
+    pub path: String,
+    pub findings: Vec<MigrationFinding>,
+}
+
+/// 按文件组织的迁移计划
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationPlan {
+    pub guide: MigrationGuide,
+    /// 扫描的目录，验证命令在此执行
+    pub root: String,
+    pub files: Vec<FileMigration>,
+    /// 无法解析而跳过的文件及原因
+    pub skipped: BTreeMap<String, String>,
+}
+
+impl MigrationPlan {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// 渲染为 Markdown 清单
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "Migration {} -> {} ({} file(s))\n",
+            self.guide.from,
+            self.guide.to,
+            self.files.len()
+        );
+        for file in &self.files {
+            out.push_str(&format!("\n## {}\n", file.path));
+            for finding in &file.findings {
+                let location = match (finding.start_line, finding.end_line) {
+                    (Some(start), Some(end)) => format!(" (lines {}-{})", start, end),
+                    _ => String::new(),
+                };
+                let symbol = finding
+                    .symbol
+                    .as_ref()
+                    .map(|s| format!(" in `{}`", s))
+                    .unwrap_or_default();
+                out.push_str(&format!(
+                    "- [{}] `{}`{}{}: {}",
+                    finding.rule_id, finding.matched, symbol, location, finding.message
+                ));
+                if let Some(replacement) = &finding.replacement {
+                    out.push_str(&format!(" -> `{}`", replacement));
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// 执行该计划的 Routine 模板（可注册到 `WebhookTrigger`，`thread` 参数为目标 Thread）
+    pub fn routine_template(&self) -> RoutineTemplate {
+        RoutineTemplate {
+            name: format!("migrate-{}", self.guide.name),
+            prompt: format!(
+                "Upgrade the project on {{{{thread}}}} from {} to {}. Work through the plan file by file: \
+                 rewrite every listed usage, stage each migrated file, then run the verification \
+                 commands ({}) and fix failures until they pass.\n\n{}",
+                self.guide.from,
+                self.guide.to,
+                self.guide.verify.join(", "),
+                self.render()
+            ),
+            params: BTreeMap::from([("thread".to_string(), "/thread".to_string())]),
+            thread_param: Some("thread".to_string()),
+        }
+    }
+}
+
+/// 一次迁移：分叉出的 Routine 及其已暂存的文件
+#[derive(Debug, Clone)]
+pub struct MigrationSession {
+    pub routine: Routine,
+    pub plan: MigrationPlan,
+    /// 路径 -> 迁移后的内容（验证通过前只保存在会话中）
+    pub staged: BTreeMap<String, String>,
+}
+
+impl MigrationSession {
+    pub fn thread_id(&self) -> ThreadId {
+        self.routine.active_thread
+    }
+}
+
+/// 验证阶段执行的一条命令
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationStep {
+    pub command: String,
+    pub exit_code: i32,
+    pub output: String,
+}
+
+/// 验证结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub steps: Vec<VerificationStep>,
+    /// 暂存内容中仍然存在的弃用用法
+    pub remaining: Vec<FileMigration>,
+    pub passed: bool,
+    /// 通过时提交到分叉 Thread 的 Change
+    pub change_id: Option<Uuid>,
+    /// 未通过的原因
+    pub failure: Option<String>,
+}
+
+/// 迁移助手：用语法查询扫描弃用 API 生成逐文件计划，在分叉 Thread 上暂存修改，
+/// 并以构建/测试命令验证
+pub struct MigrationAssistant {
+    storage: Arc<dyn StorageProvider>,
+    parsers: Arc<ParserExecutor>,
+    threads: Arc<ThreadManager>,
+    runner: Arc<dyn ExecutionProvider>,
+}
+
+impl MigrationAssistant {
+    pub fn new(
+        storage: Arc<dyn StorageProvider>,
+        parsers: Arc<ParserExecutor>,
+        threads: Arc<ThreadManager>,
+        runner: Arc<dyn ExecutionProvider>,
+    ) -> Self {
+        Self {
+            storage,
+            parsers,
+            threads,
+            runner,
+        }
+    }
+
+    /// 扫描 `root` 下符合指南扩展名的文件
+    pub async fn scan(&self, guide: &MigrationGuide, root: &str) -> Result<MigrationPlan> {
+        let index = WorkspaceIndex::scan(self.storage.as_ref(), root).await?;
+        let mut files = Vec::new();
+        let mut skipped = BTreeMap::new();
+        for path in index.files().filter(|p| has_extension(guide, p)) {
+            let source = match self.storage.read_file(path).await {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Err(e) => {
+                    skipped.insert(path.to_string(), e.to_string());
+                    continue;
+                }
+            };
+            match self.findings(guide, &source).await {
+                Ok(findings) if findings.is_empty() => {}
+                Ok(findings) => files.push(FileMigration {
+                    path: path.to_string(),
+                    findings,
+                }),
+                Err(e) => {
+                    skipped.insert(path.to_string(), e.to_string());
+                }
+            }
+        }
+        Ok(MigrationPlan {
+            guide: guide.clone(),
+            root: root.to_string(),
+            files,
+            skipped,
+        })
+    }
+
+    /// 为计划分叉出工作 Thread
+    pub fn begin(&self, parent: &Routine, plan: MigrationPlan) -> Result<MigrationSession> {
+        let routine = RoutineExecutor::new(self.threads.clone())
+            .fork(parent, &format!("migrate/{}", plan.guide.name))?;
+        Ok(MigrationSession {
+            routine,
+            plan,
+            staged: BTreeMap::new(),
+        })
+    }
+
+    /// 暂存迁移后的文件内容，验证通过后才提交到会话的 Thread
+    pub fn stage(&self, session: &mut MigrationSession, path: &str, content: &str) {
+        session.staged.insert(path.to_string(), content.to_string());
+    }
+
+    /// 重新扫描暂存内容，并把暂存文件临时写入工作区执行验证命令（遇到失败即停止）
+    ///
+    /// 命令执行后恢复工作区原内容。全部通过且没有残留的弃用用法时，暂存内容作为一个
+    /// Change 提交到分叉 Thread，Routine 标记为完成；否则不提交，报告失败原因，
+    /// Routine 标记为失败，重新暂存后可再次验证。
+    pub async fn verify(&self, session: &mut MigrationSession) -> Result<VerificationReport> {
+        let guide = &session.plan.guide;
+        let mut remaining = Vec::new();
+        for (path, content) in &session.staged {
+            let findings = self.findings(guide, content).await?;
+            if !findings.is_empty() {
+                remaining.push(FileMigration {
+                    path: path.clone(),
+                    findings,
+                });
+            }
+        }
+
+        let mut originals = HashMap::new();
+        for path in session.staged.keys() {
+            originals.insert(path.clone(), self.storage.read_file(path).await.ok());
+        }
+        let mut steps = Vec::new();
+        let mut result = Ok(());
+        for (path, content) in &session.staged {
+            if let Err(e) = self.storage.write_file(path, content.as_bytes()).await {
+                result = Err(e);
+                break;
+            }
+        }
+        if result.is_ok() {
+            for command in &guide.verify {
+                let run = self
+                    .runner
+                    .execute(
+                        command,
+                        ExecuteOptions {
+                            cwd: Some(session.plan.root.clone()),
+                            ..Default::default()
+                        },
+                    )
+                    .await;
+                match run {
+                    Ok(run) => {
+                        let exit_code = run.exit_code;
+                        steps.push(VerificationStep {
+                            command: command.clone(),
+                            exit_code,
+                            output: format!("{}{}", run.stdout, run.stderr),
+                        });
+                        if exit_code != 0 {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        result = Err(e);
+                        break;
+                    }
+                }
+            }
+        }
+        for (path, original) in originals {
+            match original {
+                Some(bytes) => self.storage.write_file(&path, &bytes).await?,
+                None => self.storage.delete(&path, false).await?,
+            }
+        }
+        result?;
+
+        let failure = if let Some(step) = steps.iter().find(|s| s.exit_code != 0) {
+            Some(format!(
+                "`{}` failed with exit code {}",
+                step.command, step.exit_code
+            ))
+        } else if !remaining.is_empty() {
+            let count: usize = remaining.iter().map(|f| f.findings.len()).sum();
+            Some(format!("{} deprecated usage(s) remain", count))
+        } else {
+            None
+        };
+        let change_id = match &failure {
+            Some(reason) => {
+                session.routine.status = RoutineStatus::Failed(reason.clone());
+                None
+            }
+            None => {
+                let id = self.commit(session)?;
+                session.routine.status = RoutineStatus::Completed;
+                Some(id)
+            }
+        };
+        Ok(VerificationReport {
+            steps,
+            remaining,
+            passed: failure.is_none(),
+            change_id,
+            failure,
+        })
+    }
+
+    /// 把暂存内容作为一个 Change 提交到会话的 Thread
+    fn commit(&self, session: &MigrationSession) -> Result<Uuid> {
+        let head = self
+            .threads
+            .get_thread(session.thread_id())
+            .and_then(|t| t.head_change_id);
+        let change = Change::new(
+            session.routine.id,
+            session
+                .staged
+                .iter()
+                .map(|(path, content)| {
+                    Operation::file_write(path.clone(), content.as_bytes().to_vec())
+                })
+                .collect(),
+            VectorClock::new(),
+            head.into_iter().collect(),
+        );
+        let id = change.id;
+        self.threads.commit_change(session.thread_id(), change)?;
+        Ok(id)
+    }
+
+    async fn findings(
+        &self,
+        guide: &MigrationGuide,
+        source: &str,
+    ) -> Result<Vec<MigrationFinding>> {
+        let tree = self.parsers.parse(&guide.language, source).await?;
+        Ok(guide
+            .rules
+            .iter()
+            .flat_map(|rule| {
+                rule.query
+                    .find(&tree)
+                    .into_iter()
+                    .map(|m| MigrationFinding {
+                        rule_id: rule.id.clone(),
+                        message: rule.message.clone(),
+                        replacement: rule.replacement.clone(),
+                        matched: m.name,
+                        symbol: m.symbol,
+                        start_line: m.start_line,
+                        end_line: m.end_line,
+                    })
+            })
+            .collect())
+    }
+}
+
+fn has_extension(guide: &MigrationGuide, path: &str) -> bool {
+    path.rsplit_once('.')
+        .is_some_and(|(_, ext)| guide.extensions.iter().any(|e| e == ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::meta::MetaNode;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::common::provider::traits::ExecuteResult;
+    use crate::syntax::engine::interface::Parser;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// 每行 `call <name>` 解析为一次调用，`use <name>` 解析为一个标识符
+    struct LineParser;
+
+    #[async_trait]
+    impl Parser for LineParser {
+        fn language(&self) -> &str {
+            "rust"
+        }
+
+        async fn parse(&self, source: &str) -> Result<MetaNode> {
+            let mut children = Vec::new();
+            for (i, line) in source.lines().enumerate() {
+                let node = match line.split_once(' ') {
+                    Some(("call", name)) => serde_json::json!({"type": "call", "args": [],
+                        "callee": {"type": "identifier", "name": name}}),
+                    Some(("use", name)) => serde_json::json!({"type": "identifier", "name": name}),
+                    _ => continue,
+                };
+                children.push(
+                    serde_json::json!({"type": "function", "name": format!("line{}", i + 1),
+                    "params": [], "start_line": i + 1, "end_line": i + 1, "body": node}),
+                );
+            }
+            Ok(serde_json::from_value(serde_json::json!({
+                "type": "module", "name": "file", "children": children
+            }))?)
+        }
+
+        async fn load_scm(&self, _name: &str, _content: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// 记录执行命令时工作区中 main.rs 的内容
+    struct Runner {
+        root: std::path::PathBuf,
+        seen: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl ExecutionProvider for Runner {
+        async fn execute(&self, cmd: &str, _opts: ExecuteOptions) -> Result<ExecuteResult> {
+            let content = std::fs::read_to_string(self.root.join("src/main.rs")).unwrap();
+            self.seen.lock().unwrap().push((cmd.to_string(), content));
+            Ok(ExecuteResult {
+                exit_code: 0,
+                stdout: "ok".to_string(),
+                stderr: String::new(),
+            })
+        }
+
+        async fn kill(&self, _id: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migration_scan_stage_and_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = "call axum::Server::bind\nuse axum::body::BoxBody\ncall serve\n";
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), original).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "call serve\n").unwrap();
+        std::fs::write(dir.path().join("README.md"), "call axum::Server::bind\n").unwrap();
+
+        let mut parsers = ParserExecutor::new();
+        parsers.register_parser("rust", Arc::new(LineParser));
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let runner = Arc::new(Runner {
+            root: dir.path().to_path_buf(),
+            seen: Mutex::new(Vec::new()),
+        });
+        let assistant = MigrationAssistant::new(
+            Arc::new(LocalFileSystem::new(dir.path())),
+            Arc::new(parsers),
+            threads.clone(),
+            runner.clone(),
+        );
+
+        let plan = assistant
+            .scan(&MigrationGuide::axum_0_7(), "")
+            .await
+            .unwrap();
+        assert_eq!(plan.files.len(), 1);
+        let file = &plan.files[0];
+        assert_eq!(file.path, "src/main.rs");
+        let rules: Vec<&str> = file.findings.iter().map(|f| f.rule_id.as_str()).collect();
+        assert_eq!(rules, vec!["server-removed", "box-body-removed"]);
+        assert_eq!(file.findings[1].start_line, Some(2));
+        assert!(
+            plan.render()
+                .contains("- [server-removed] `axum::Server::bind` in `line1` (lines 1-1)")
+        );
+        let template = plan.routine_template();
+        assert_eq!(template.name, "migrate-axum-0.7");
+        assert!(template.prompt.contains("## src/main.rs"));
+
+        let mut session = assistant.begin(&Routine::new(main), plan).unwrap();
+        // 仍残留弃用用法时验证不通过，不提交到分叉 Thread
+        assistant.stage(
+            &mut session,
+            "src/main.rs",
+            "call axum::serve\nuse axum::body::BoxBody\n",
+        );
+        let report = assistant.verify(&mut session).await.unwrap();
+        assert!(!report.passed);
+        assert_eq!(report.remaining[0].findings[0].rule_id, "box-body-removed");
+        assert_eq!(report.change_id, None);
+        assert_eq!(
+            report.failure.as_deref(),
+            Some("1 deprecated usage(s) remain")
+        );
+        assert!(matches!(session.routine.status, RoutineStatus::Failed(_)));
+        let fork = session.thread_id();
+        assert_eq!(threads.get_thread(fork).unwrap().head_change_id, None);
+
+        let migrated = "call axum::serve\nuse axum::body::Body\ncall serve\n";
+        assistant.stage(&mut session, "src/main.rs", migrated);
+        let report = assistant.verify(&mut session).await.unwrap();
+        assert!(report.passed);
+        assert_eq!(report.failure, None);
+        assert_eq!(session.routine.status, RoutineStatus::Completed);
+        let seen = runner.seen.lock().unwrap();
+        assert_eq!(seen.len(), 4);
+        assert_eq!(seen[3], ("cargo test".to_string(), migrated.to_string()));
+
+        // 工作区恢复原状，通过验证的修改以一个 Change 留在分叉 Thread 上
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("src/main.rs")).unwrap(),
+            original
+        );
+        let thread = threads.get_thread(session.thread_id()).unwrap();
+        assert_eq!(thread.name, "migrate/axum-0.7");
+        assert_eq!(thread.head_change_id, report.change_id);
+        let head = threads.get_change(thread.head_change_id.unwrap()).unwrap();
+        assert!(head.parents.is_empty());
+        assert_eq!(
+            head.operations,
+            vec![Operation::file_write(
+                "src/main.rs".to_string(),
+                migrated.as_bytes().to_vec()
+            )]
+        );
+        assert_eq!(threads.get_thread(main).unwrap().head_change_id, None);
+    }
+}
@@ -1,11 +1,62 @@
+pub mod approval;
 pub mod bridge;
+pub mod checkpoint;
+pub mod command;
 pub mod context;
+pub mod debug;
 pub mod executor;
+pub mod explain;
+pub mod guardrail;
+pub mod http;
 pub mod intent;
+pub mod logs;
 pub mod manager;
+pub mod migration;
 pub mod planner;
+pub mod review;
 pub mod routine;
+pub mod spawn;
+pub mod team;
+pub mod testing;
+pub mod trace;
+pub mod webhook;
 
 pub use intent::AgentIntent;
 
+pub use approval::{
+    ApprovalGate, ApprovalIntent, ApprovalPolicy, ApprovalRequest, ApprovalStatus,
+    ProposedOperation,
+};
+
+pub use bridge::{
+    McpBridge, McpClient, McpResource, McpTool, McpTransport, MergerBridge, SseTransport,
+    StdioTransport,
+};
+pub use checkpoint::{CheckpointStore, RoutineCheckpoint};
+pub use command::CommandHandler;
+pub use debug::{CrashContext, CrashContextBuilder, CrashSnippet};
+pub use explain::{
+    Citation, CitationKind, ExplainService, Explanation, ExplanationPoint, LineRange,
+};
+pub use guardrail::{BudgetUsage, GuardrailViolation, Guardrails, RoutineBudget};
+pub use http::{HttpExchange, HttpRequestTool, NetworkPolicy};
+pub use logs::{LogSource, LogTailer, TailLogsTool, TailReport};
+pub use migration::{
+    DeprecationRule, FileMigration, MigrationAssistant, MigrationFinding, MigrationGuide,
+    MigrationPlan, MigrationSession, VerificationReport, VerificationStep,
+};
+pub use planner::{Plan, PlanRun, PlanStep, Planner, StepOutcome, StepResult};
+pub use review::{
+    ReviewComment, ReviewIntent, ReviewItem, ReviewQueue, ReviewStatus, ReviewSubmission,
+    TestStatus,
+};
 pub use routine::{Routine, RoutineId, RoutineStatus};
+pub use spawn::{ChildContext, ChildHandle, ChildOutcome, ChildReport, ChildTask, ThreadFork};
+pub use team::{AgentTeam, TeamIntent, TeamMember, TeamMessage, WorkStatus};
+pub use testing::{GenerateTestsTool, TestAttempt, TestGenerationReport, TestGenerator};
+pub use trace::{
+    RoutineTrace, TraceEvent, TraceEventKind, TraceFilter, TraceKind, TracedClient, TracedTools,
+};
+pub use webhook::{
+    RoutineLauncher, RoutineTemplate, TriggeredRoutine, WebhookError, WebhookTrigger,
+};
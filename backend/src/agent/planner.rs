@@ -1,7 +1,126 @@
-use anyhow::Result;
+use crate::common::change::thread::ThreadId;
+use crate::common::endpoint::{ChatMessage, ChatOptions, LLMClient, MessageRole};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+const PLAN_PROMPT: &str = "You break a software engineering request into steps. Steps that do not \
+depend on each other will run concurrently on separate branches. Reply with JSON only: \
+{\"steps\": [{\"id\": \"short-id\", \"description\": \"...\", \"depends_on\": [\"other-id\"], \
+\"target_files\": [\"src/...\"], \"tools\": [\"read_file\"], \"acceptance\": [\"...\"]}]}";
+
+/// 计划中的一个步骤
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub id: String,
+    pub description: String,
+    /// 必须先完成的步骤
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// 预计修改的文件
+    #[serde(default)]
+    pub target_files: Vec<String>,
+    /// 需要的工具
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// 验收标准
+    #[serde(default)]
+    pub acceptance: Vec<String>,
+}
+
+impl PlanStep {
+    pub fn new(id: &str, description: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            description: description.to_string(),
+            depends_on: Vec::new(),
+            target_files: Vec::new(),
+            tools: Vec::new(),
+            acceptance: Vec::new(),
+        }
+    }
+
+    pub fn after(mut self, step: &str) -> Self {
+        self.depends_on.push(step.to_string());
+        self
+    }
+}
+
+/// 由步骤及其依赖构成的有向无环图
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    pub request: String,
+    pub steps: Vec<PlanStep>,
+}
+
+impl Plan {
+    /// 构建并校验计划
+    pub fn new(request: &str, steps: Vec<PlanStep>) -> Result<Self> {
+        let plan = Self {
+            request: request.to_string(),
+            steps,
+        };
+        plan.layers()?;
+        Ok(plan)
+    }
+
+    pub fn step(&self, id: &str) -> Option<&PlanStep> {
+        self.steps.iter().find(|s| s.id == id)
+    }
+
+    /// 按依赖分层：同一层的步骤互不依赖，可以并发执行
+    ///
+    /// 步骤 ID 重复、依赖未知步骤或存在环时返回错误。
+    pub fn layers(&self) -> Result<Vec<Vec<&PlanStep>>> {
+        let mut ids = BTreeSet::new();
+        for step in &self.steps {
+            if !ids.insert(step.id.as_str()) {
+                return Err(anyhow!("Duplicate plan step: {}", step.id));
+            }
+        }
+        for step in &self.steps {
+            if let Some(missing) = step.depends_on.iter().find(|d| !ids.contains(d.as_str())) {
+                return Err(anyhow!(
+                    "Plan step {} depends on unknown step {}",
+                    step.id,
+                    missing
+                ));
+            }
+        }
+
+        let mut done: BTreeSet<&str> = BTreeSet::new();
+        let mut layers = Vec::new();
+        while done.len() < self.steps.len() {
+            let layer: Vec<&PlanStep> = self
+                .steps
+                .iter()
+                .filter(|s| !done.contains(s.id.as_str()))
+                .filter(|s| s.depends_on.iter().all(|d| done.contains(d.as_str())))
+                .collect();
+            if layer.is_empty() {
+                let cycle: Vec<&str> = self
+                    .steps
+                    .iter()
+                    .map(|s| s.id.as_str())
+                    .filter(|id| !done.contains(id))
+                    .collect();
+                return Err(anyhow!(
+                    "Plan has a dependency cycle among: {}",
+                    cycle.join(", ")
+                ));
+            }
+            done.extend(layer.iter().map(|s| s.id.as_str()));
+            layers.push(layer);
+        }
+        Ok(layers)
+    }
+}
 
 /// 使用 LLM 将用户意图分解为一系列 Skill 调用
-pub struct Planner;
+pub struct Planner {
+    model: Option<(Arc<dyn LLMClient>, String)>,
+}
 
 impl Default for Planner {
     fn default() -> Self {
@@ -11,19 +130,125 @@ impl Default for Planner {
 
 impl Planner {
     pub fn new() -> Self {
-        Self
+        Self { model: None }
+    }
+
+    /// 使用模型分解请求；未配置时退化为固定的“分析 -> 执行”两步计划
+    pub fn with_model(mut self, client: Arc<dyn LLMClient>, model: &str) -> Self {
+        self.model = Some((client, model.to_string()));
+        self
     }
 
     /// 生成计划
-    pub async fn plan(&self, _intent: &str) -> Result<Vec<String>> {
-        // Mock 逻辑：返回固定步骤
-        Ok(vec!["Analyze".to_string(), "Execute".to_string()])
+    pub async fn plan(&self, intent: &str) -> Result<Vec<String>> {
+        Ok(self
+            .decompose(intent)
+            .await?
+            .steps
+            .into_iter()
+            .map(|s| s.description)
+            .collect())
+    }
+
+    /// 将自然语言请求分解为步骤 DAG
+    pub async fn decompose(&self, request: &str) -> Result<Plan> {
+        let Some((client, model)) = &self.model else {
+            return Plan::new(
+                request,
+                vec![
+                    PlanStep::new("analyze", "Analyze"),
+                    PlanStep::new("execute", "Execute").after("analyze"),
+                ],
+            );
+        };
+        let messages = [
+            ChatMessage::text(MessageRole::System, PLAN_PROMPT),
+            ChatMessage::text(MessageRole::User, request),
+        ];
+        let options = ChatOptions {
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+        let response = client.chat(model, &messages, &options).await?;
+        let text = response
+            .choices
+            .first()
+            .map(|c| c.message.content.as_text())
+            .unwrap_or_default();
+        let json = match (text.find('{'), text.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &text[start..=end],
+            _ => return Err(anyhow!("Unparseable plan: {}", text)),
+        };
+
+        #[derive(Deserialize)]
+        struct Reply {
+            steps: Vec<PlanStep>,
+        }
+        let reply: Reply = serde_json::from_str(json)?;
+        if reply.steps.is_empty() {
+            return Err(anyhow!("Plan has no steps"));
+        }
+        Plan::new(request, reply.steps)
+    }
+}
+
+/// 步骤的执行结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StepOutcome {
+    Completed {
+        output: String,
+    },
+    Failed {
+        reason: String,
+    },
+    /// 依赖的步骤失败，未执行
+    Skipped,
+}
+
+/// 单个步骤的执行记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepResult {
+    /// 步骤运行所在的分叉线程（跳过的步骤没有）
+    pub thread: Option<ThreadId>,
+    pub outcome: StepOutcome,
+}
+
+/// 一次计划执行的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanRun {
+    pub steps: BTreeMap<String, StepResult>,
+}
+
+impl PlanRun {
+    pub fn is_success(&self) -> bool {
+        self.steps
+            .values()
+            .all(|s| matches!(s.outcome, StepOutcome::Completed { .. }))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::Routine;
+    use crate::agent::executor::RoutineExecutor;
+    use crate::common::change::thread::ThreadManager;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// 回复固定计划的模型
+    fn planner_model() -> Arc<ScriptedClient> {
+        let plan = r#"Here is the plan:
+{"steps": [
+  {"id": "api", "description": "Add endpoint", "target_files": ["src/api.rs"], "tools": ["apply_patch"]},
+  {"id": "docs", "description": "Document endpoint"},
+  {"id": "tests", "description": "Test endpoint", "depends_on": ["api"], "acceptance": ["cargo test passes"]},
+  {"id": "release", "description": "Changelog", "depends_on": ["tests", "docs"]}
+]}"#;
+        Arc::new(ScriptedClient::new("scripted").with_replies([plan]))
+    }
 
     #[tokio::test]
     async fn test_planner() {
@@ -31,4 +256,79 @@ mod tests {
         let steps = planner.plan("fix bug").await.unwrap();
         assert_eq!(steps.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_plan_dag_runs_independent_steps_concurrently() {
+        let plan = Planner::new()
+            .with_model(planner_model(), "m")
+            .decompose("add an endpoint")
+            .await
+            .unwrap();
+        let layers: Vec<Vec<&str>> = plan
+            .layers()
+            .unwrap()
+            .iter()
+            .map(|l| l.iter().map(|s| s.id.as_str()).collect())
+            .collect();
+        assert_eq!(
+            layers,
+            vec![vec!["api", "docs"], vec!["tests"], vec!["release"]]
+        );
+        assert_eq!(
+            plan.step("tests").unwrap().acceptance,
+            ["cargo test passes"]
+        );
+        let cyclic = Plan::new(
+            "loop",
+            vec![
+                PlanStep::new("a", "A").after("b"),
+                PlanStep::new("b", "B").after("a"),
+            ],
+        );
+        assert!(cyclic.unwrap_err().to_string().contains("cycle"));
+
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let executor = RoutineExecutor::new(threads.clone());
+        let routine = Routine::new(main).with_plan(plan);
+        let restored: Routine =
+            serde_json::from_str(&serde_json::to_string(&routine).unwrap()).unwrap();
+        assert_eq!(restored.plan, routine.plan);
+
+        // api 与 docs 同时运行：api 等待 docs 开始后才结束
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let run = executor
+            .run_plan(&routine, |step, thread| {
+                let started = started.clone();
+                async move {
+                    started.lock().unwrap().push(step.id.clone());
+                    if step.id == "api" {
+                        for _ in 0..100 {
+                            if started.lock().unwrap().contains(&"docs".to_string()) {
+                                break;
+                            }
+                            tokio::time::sleep(Duration::from_millis(5)).await;
+                        }
+                        assert!(started.lock().unwrap().contains(&"docs".to_string()));
+                    }
+                    if step.id == "release" {
+                        return Err(anyhow!("changelog conflict"));
+                    }
+                    Ok(format!("{} on {}", step.id, thread))
+                }
+            })
+            .await
+            .unwrap();
+        assert!(!run.is_success());
+        assert!(matches!(
+            run.steps["release"].outcome,
+            StepOutcome::Failed { .. }
+        ));
+
+        // 单一依赖的步骤从依赖的线程分叉，其余从 Routine 的线程分叉
+        let thread = |id: &str| threads.get_thread(run.steps[id].thread.unwrap()).unwrap();
+        assert_eq!(thread("api").parent_id, Some(main));
+        assert_eq!(thread("tests").parent_id, run.steps["api"].thread);
+        assert_eq!(thread("release").parent_id, Some(main));
+    }
 }
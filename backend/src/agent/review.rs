@@ -0,0 +1,400 @@
+use crate::agent::RoutineId;
+use crate::common::change::MergeEngine;
+use crate::common::change::thread::{MergeOutcome, ThreadId, ThreadManager};
+use crate::common::change::threeway::FileMerge;
+use crate::common::endpoint::{UsageLedger, UsageSummary};
+use crate::common::event::{BackendEvent, EventBus};
+use crate::common::intent::handler::IntentHandler;
+use crate::common::intent::traits::SystemIntent;
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// 用户对审阅队列的操作意图
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewIntent {
+    /// 批准并合并到目标线程
+    Approve { thread_id: ThreadId },
+    /// 拒绝，线程保留但不再出现在待审列表中
+    Reject {
+        thread_id: ThreadId,
+        reason: Option<String>,
+    },
+    /// 添加评论（不改变状态，Agent 可据此继续修改）
+    Comment { thread_id: ThreadId, text: String },
+}
+
+/// 测试运行状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum TestStatus {
+    NotRun,
+    Running,
+    Passed,
+    Failed { summary: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ReviewStatus {
+    Pending,
+    Approved { outcome: MergeOutcome },
+    Rejected { reason: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewComment {
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Agent 提交审阅时提供的信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewSubmission {
+    /// 待合并的 Agent 线程
+    pub thread_id: ThreadId,
+    /// 批准后合并进的线程
+    pub target: ThreadId,
+    /// 产生该线程的 Routine，用于汇总费用
+    pub routine_id: Option<RoutineId>,
+    pub title: String,
+    pub summary: String,
+}
+
+/// 审阅队列中的一项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewItem {
+    pub thread_id: ThreadId,
+    pub target: ThreadId,
+    pub routine_id: Option<RoutineId>,
+    pub title: String,
+    pub summary: String,
+    /// 相对目标线程的逐文件差异（提交或刷新时计算）
+    pub files: Vec<FileMerge>,
+    pub tests: TestStatus,
+    /// 产生该线程的 Routine 的模型用量与费用
+    pub cost: UsageSummary,
+    pub comments: Vec<ReviewComment>,
+    pub status: ReviewStatus,
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// 待用户批准的 Agent 变更队列
+///
+/// Agent 完成任务后将其线程提交到队列，用户在方便时批量查看摘要、差异、测试状态与费用，
+/// 再通过意图批准（合并）、拒绝或评论，而不是在每次编辑时被打断。
+pub struct ReviewQueue {
+    threads: Arc<ThreadManager>,
+    items: RwLock<BTreeMap<ThreadId, ReviewItem>>,
+    /// 批准时生成的合并 Change 的作者
+    reviewer: Uuid,
+    usage: Option<Arc<UsageLedger>>,
+    events: Option<Arc<EventBus>>,
+}
+
+impl ReviewQueue {
+    pub fn new(threads: Arc<ThreadManager>) -> Self {
+        Self {
+            threads,
+            items: RwLock::new(BTreeMap::new()),
+            reviewer: Uuid::new_v4(),
+            usage: None,
+            events: None,
+        }
+    }
+
+    /// 合并 Change 的作者 ID
+    pub fn with_reviewer(mut self, reviewer: Uuid) -> Self {
+        self.reviewer = reviewer;
+        self
+    }
+
+    /// 从用量账本汇总每项对应 Routine 的费用
+    pub fn with_usage(mut self, usage: Arc<UsageLedger>) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// 将队列变化作为 Agent 事件发布
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn emit(&self, item: &ReviewItem, event: &str) {
+        if let Some(events) = &self.events {
+            events.emit(BackendEvent::Agent {
+                routine_id: item.routine_id.unwrap_or(item.thread_id),
+                event: event.to_string(),
+                detail: serde_json::json!({
+                    "thread_id": item.thread_id,
+                    "title": item.title,
+                    "status": item.status,
+                }),
+            });
+        }
+    }
+
+    /// 提交线程等待审阅；重复提交会刷新差异并重新置为待审
+    pub fn submit(&self, submission: ReviewSubmission) -> Result<ReviewItem> {
+        let files = self.diff(submission.target, submission.thread_id)?;
+        let mut items = self.items.write().unwrap();
+        let previous = items.remove(&submission.thread_id);
+        let item = ReviewItem {
+            thread_id: submission.thread_id,
+            target: submission.target,
+            routine_id: submission.routine_id,
+            title: submission.title,
+            summary: submission.summary,
+            files,
+            tests: TestStatus::NotRun,
+            cost: UsageSummary::default(),
+            comments: previous.map(|p| p.comments).unwrap_or_default(),
+            status: ReviewStatus::Pending,
+            submitted_at: Utc::now(),
+        };
+        items.insert(item.thread_id, item.clone());
+        drop(items);
+        self.emit(&item, "review_submitted");
+        Ok(self.with_cost(item))
+    }
+
+    /// 更新测试状态
+    pub fn set_tests(&self, thread_id: ThreadId, tests: TestStatus) -> Result<()> {
+        let mut items = self.items.write().unwrap();
+        let item = items
+            .get_mut(&thread_id)
+            .ok_or_else(|| anyhow!("No review item for thread {}", thread_id))?;
+        item.tests = tests;
+        Ok(())
+    }
+
+    /// 重新计算相对目标线程的差异（线程有新提交后调用）
+    pub fn refresh(&self, thread_id: ThreadId) -> Result<ReviewItem> {
+        let target = self.get(thread_id)?.target;
+        let files = self.diff(target, thread_id)?;
+        let mut items = self.items.write().unwrap();
+        let item = items
+            .get_mut(&thread_id)
+            .ok_or_else(|| anyhow!("No review item for thread {}", thread_id))?;
+        item.files = files;
+        Ok(self.with_cost(item.clone()))
+    }
+
+    pub fn get(&self, thread_id: ThreadId) -> Result<ReviewItem> {
+        self.items
+            .read()
+            .unwrap()
+            .get(&thread_id)
+            .cloned()
+            .map(|item| self.with_cost(item))
+            .ok_or_else(|| anyhow!("No review item for thread {}", thread_id))
+    }
+
+    /// 待审阅的项，按提交时间排序
+    pub fn pending(&self) -> Vec<ReviewItem> {
+        let mut pending: Vec<ReviewItem> = self
+            .items
+            .read()
+            .unwrap()
+            .values()
+            .filter(|item| item.status == ReviewStatus::Pending)
+            .cloned()
+            .map(|item| self.with_cost(item))
+            .collect();
+        pending.sort_by_key(|item| item.submitted_at);
+        pending
+    }
+
+    /// 批准：将线程合并到目标线程
+    pub fn approve(&self, thread_id: ThreadId) -> Result<MergeOutcome> {
+        let item = self.pending_item(thread_id)?;
+        let outcome = self
+            .threads
+            .merge_thread(thread_id, item.target, self.reviewer)?;
+        self.finish(
+            thread_id,
+            ReviewStatus::Approved {
+                outcome: outcome.clone(),
+            },
+            "review_approved",
+        );
+        Ok(outcome)
+    }
+
+    pub fn reject(&self, thread_id: ThreadId, reason: Option<String>) -> Result<()> {
+        self.pending_item(thread_id)?;
+        self.finish(
+            thread_id,
+            ReviewStatus::Rejected { reason },
+            "review_rejected",
+        );
+        Ok(())
+    }
+
+    pub fn comment(&self, thread_id: ThreadId, text: &str) -> Result<()> {
+        let mut items = self.items.write().unwrap();
+        let item = items
+            .get_mut(&thread_id)
+            .ok_or_else(|| anyhow!("No review item for thread {}", thread_id))?;
+        item.comments.push(ReviewComment {
+            text: text.to_string(),
+            created_at: Utc::now(),
+        });
+        let item = item.clone();
+        drop(items);
+        self.emit(&item, "review_commented");
+        Ok(())
+    }
+
+    fn pending_item(&self, thread_id: ThreadId) -> Result<ReviewItem> {
+        let item = self.get(thread_id)?;
+        if item.status != ReviewStatus::Pending {
+            bail!("Review for thread {} is already closed", thread_id);
+        }
+        Ok(item)
+    }
+
+    fn finish(&self, thread_id: ThreadId, status: ReviewStatus, event: &str) {
+        let mut items = self.items.write().unwrap();
+        if let Some(item) = items.get_mut(&thread_id) {
+            item.status = status;
+            let item = item.clone();
+            drop(items);
+            self.emit(&item, event);
+        }
+    }
+
+    fn diff(&self, target: ThreadId, thread_id: ThreadId) -> Result<Vec<FileMerge>> {
+        Ok(MergeEngine::new()
+            .merge_threads(&self.threads, target, thread_id)?
+            .report
+            .files)
+    }
+
+    fn with_cost(&self, mut item: ReviewItem) -> ReviewItem {
+        if let (Some(usage), Some(routine)) = (&self.usage, item.routine_id) {
+            item.cost = usage.by_routine().remove(&routine).unwrap_or_default();
+        }
+        item
+    }
+}
+
+#[async_trait]
+impl IntentHandler for ReviewQueue {
+    async fn handle(&self, intent: SystemIntent) -> Result<()> {
+        match intent {
+            SystemIntent::Review(ReviewIntent::Approve { thread_id }) => {
+                self.approve(thread_id).map(|_| ())
+            }
+            SystemIntent::Review(ReviewIntent::Reject { thread_id, reason }) => {
+                self.reject(thread_id, reason)
+            }
+            SystemIntent::Review(ReviewIntent::Comment { thread_id, text }) => {
+                self.comment(thread_id, &text)
+            }
+            other => Err(anyhow!("Unsupported intent: {}", other.summary())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::Operation;
+    use crate::common::change::threeway::FileMergeStatus;
+    use crate::common::endpoint::Usage;
+    use crate::common::intent::dispatcher::IntentDispatcher;
+    use crate::common::intent::traits::IntentCategory;
+
+    #[tokio::test]
+    async fn test_review_queue_through_intents() {
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        threads.commit_test(
+            main,
+            vec![Operation::file_write("a.txt".into(), "a\n".into())],
+        );
+        let first = threads.create_branch(main, "agent/1").unwrap();
+        threads.commit_test(
+            first,
+            vec![Operation::file_write("a.txt".into(), "b\n".into())],
+        );
+        let second = threads.create_branch(main, "agent/2").unwrap();
+        threads.commit_test(
+            second,
+            vec![Operation::file_write("c.txt".into(), "c\n".into())],
+        );
+
+        let usage = Arc::new(UsageLedger::new());
+        let routine = Uuid::new_v4();
+        usage.record("m", "p", Some(routine), &Usage::default());
+        let queue = Arc::new(ReviewQueue::new(threads.clone()).with_usage(usage));
+        let dispatcher = IntentDispatcher::new();
+        dispatcher
+            .register(IntentCategory::Review, queue.clone())
+            .await;
+
+        let item = queue
+            .submit(ReviewSubmission {
+                thread_id: first,
+                target: main,
+                routine_id: Some(routine),
+                title: "Edit a".into(),
+                summary: "Replace a with b".into(),
+            })
+            .unwrap();
+        assert_eq!(item.files[0].status, FileMergeStatus::Right);
+        assert_eq!(item.cost.requests, 1);
+        queue
+            .submit(ReviewSubmission {
+                thread_id: second,
+                target: main,
+                routine_id: None,
+                title: "Add c".into(),
+                summary: String::new(),
+            })
+            .unwrap();
+        queue.set_tests(first, TestStatus::Passed).unwrap();
+        assert_eq!(queue.pending().len(), 2);
+
+        dispatcher
+            .dispatch(SystemIntent::Review(ReviewIntent::Comment {
+                thread_id: second,
+                text: "Please add a header".into(),
+            }))
+            .await
+            .unwrap();
+        dispatcher
+            .dispatch(SystemIntent::Review(ReviewIntent::Approve {
+                thread_id: first,
+            }))
+            .await
+            .unwrap();
+        dispatcher
+            .dispatch(SystemIntent::Review(ReviewIntent::Reject {
+                thread_id: second,
+                reason: None,
+            }))
+            .await
+            .unwrap();
+
+        assert!(queue.pending().is_empty());
+        assert!(matches!(
+            queue.get(first).unwrap().status,
+            ReviewStatus::Approved {
+                outcome: MergeOutcome::FastForward { .. }
+            }
+        ));
+        assert_eq!(queue.get(second).unwrap().comments.len(), 1);
+        assert!(queue.approve(second).is_err());
+        assert_eq!(
+            threads.get_thread(main).unwrap().head_change_id,
+            threads.get_thread(first).unwrap().head_change_id
+        );
+    }
+}
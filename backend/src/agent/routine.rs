@@ -1,15 +1,27 @@
+use crate::agent::planner::Plan;
 use crate::common::change::thread::ThreadId;
+use crate::common::i18n::{Locale, Localize, translate};
+use crate::common::provider::traits::ExecutionProvider;
+use crate::skill::sandbox::ToolPolicy;
+use crate::skill::tool::SkillToolRegistry;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use uuid::Uuid;
 
 pub type RoutineId = Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Routine {
     pub id: RoutineId,
     pub parent: Option<RoutineId>,
     pub active_thread: ThreadId,
     pub status: RoutineStatus,
+    /// Routine 执行的计划（见 `RoutineExecutor::run_plan`）
+    #[serde(default)]
+    pub plan: Option<Plan>,
+    /// 工具沙箱策略，交给该 Routine 使用的 `SkillToolRegistry`
+    #[serde(default)]
+    pub tool_policy: ToolPolicy,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -18,6 +30,22 @@ pub enum RoutineStatus {
     Paused,
     Completed,
     Failed(String),
+    /// 被取消（父 Routine 取消时级联）
+    Cancelled,
+}
+
+impl Localize for RoutineStatus {
+    fn localize(&self, locale: Locale) -> String {
+        match self {
+            RoutineStatus::Running => translate(locale, "agent.status.running", &[]),
+            RoutineStatus::Paused => translate(locale, "agent.status.paused", &[]),
+            RoutineStatus::Completed => translate(locale, "agent.status.completed", &[]),
+            RoutineStatus::Failed(reason) => {
+                translate(locale, "agent.status.failed", &[("reason", reason)])
+            }
+            RoutineStatus::Cancelled => translate(locale, "agent.status.cancelled", &[]),
+        }
+    }
 }
 
 impl Routine {
@@ -27,6 +55,28 @@ impl Routine {
             parent: None,
             active_thread,
             status: RoutineStatus::Running,
+            plan: None,
+            tool_policy: ToolPolicy::default(),
         }
     }
+
+    pub fn with_tool_policy(mut self, policy: ToolPolicy) -> Self {
+        self.tool_policy = policy;
+        self
+    }
+
+    /// 该 Routine 的工具注册表，每次工具调用前按 `tool_policy` 检查
+    pub fn tools(&self) -> SkillToolRegistry {
+        SkillToolRegistry::new().with_policy(self.tool_policy.clone())
+    }
+
+    /// 该 Routine 运行命令所用的执行提供者，在进程层同样套用 `tool_policy`
+    pub fn process(&self, inner: Arc<dyn ExecutionProvider>) -> Arc<dyn ExecutionProvider> {
+        self.tool_policy.process(inner)
+    }
+
+    pub fn with_plan(mut self, plan: Plan) -> Self {
+        self.plan = Some(plan);
+        self
+    }
 }
@@ -0,0 +1,373 @@
+use crate::agent::manager::RoutineManager;
+use crate::agent::{Routine, RoutineId, RoutineStatus};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// 子 Routine 使用的线程
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThreadFork {
+    /// 从父 Routine 的活动线程分叉出指定名称的新线程
+    Fork { name: String },
+    /// 与父 Routine 共用活动线程
+    Shared,
+}
+
+/// 交给子 Routine 的任务及其独立预算
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChildTask {
+    pub description: String,
+    /// 最多执行的步骤数（见 `ChildContext::step`）
+    #[serde(default)]
+    pub max_steps: Option<usize>,
+    /// 运行时长上限
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+}
+
+impl ChildTask {
+    pub fn new(description: &str) -> Self {
+        Self {
+            description: description.to_string(),
+            max_steps: None,
+            timeout: None,
+        }
+    }
+
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// 子 Routine 运行时可见的上下文
+pub struct ChildContext {
+    pub routine: Routine,
+    pub task: ChildTask,
+    steps: AtomicUsize,
+    cancel: watch::Receiver<bool>,
+}
+
+impl ChildContext {
+    /// 记录一个步骤，超出预算或已被取消时返回错误
+    pub fn step(&self) -> Result<usize> {
+        if self.is_cancelled() {
+            return Err(anyhow::anyhow!("Routine {} was cancelled", self.routine.id));
+        }
+        let step = self.steps.fetch_add(1, Ordering::SeqCst) + 1;
+        match self.task.max_steps {
+            Some(max) if step > max => Err(anyhow::anyhow!(
+                "Step budget exhausted: {} steps allowed",
+                max
+            )),
+            _ => Ok(step),
+        }
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps.load(Ordering::SeqCst)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancel.borrow()
+    }
+}
+
+/// 子 Routine 的结束方式
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChildOutcome {
+    Completed { output: String },
+    Failed { reason: String },
+    Cancelled,
+}
+
+impl ChildOutcome {
+    fn status(&self) -> RoutineStatus {
+        match self {
+            ChildOutcome::Completed { .. } => RoutineStatus::Completed,
+            ChildOutcome::Failed { reason } => RoutineStatus::Failed(reason.clone()),
+            ChildOutcome::Cancelled => RoutineStatus::Cancelled,
+        }
+    }
+}
+
+/// 回传给父 Routine 的子 Routine 结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChildReport {
+    pub child_id: RoutineId,
+    pub task: ChildTask,
+    pub steps: usize,
+    pub outcome: ChildOutcome,
+}
+
+/// 运行中的子 Routine
+pub struct ChildHandle {
+    pub id: RoutineId,
+    task: JoinHandle<ChildReport>,
+}
+
+impl ChildHandle {
+    /// 等待子 Routine 结束（结果同时进入父 Routine 的收件箱）
+    pub async fn join(self) -> Result<ChildReport> {
+        Ok(self.task.await?)
+    }
+}
+
+impl RoutineManager {
+    /// 派生子 Routine：按 `fork` 准备线程，以独立预算运行 `run`，结果回传给父 Routine
+    ///
+    /// 子 Routine 失败不会改变父 Routine 的状态，父 Routine 经 `take_child_reports`
+    /// 取得结果后自行决定；取消父 Routine 时级联取消其全部后代（见 `cancel`）。
+    pub fn spawn_child<F, Fut>(
+        self: &Arc<Self>,
+        parent_id: RoutineId,
+        task: ChildTask,
+        fork: ThreadFork,
+        run: F,
+    ) -> Result<ChildHandle>
+    where
+        F: FnOnce(Arc<ChildContext>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        let parent = self
+            .get(&parent_id)
+            .ok_or_else(|| anyhow::anyhow!("Routine not found: {}", parent_id))?;
+        if parent.status != RoutineStatus::Running {
+            return Err(anyhow::anyhow!(
+                "Routine {} is not running: {:?}",
+                parent_id,
+                parent.status
+            ));
+        }
+        let thread = match fork {
+            ThreadFork::Fork { name } => self
+                .threads
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Thread manager not configured"))?
+                .create_branch(parent.active_thread, &name)?,
+            ThreadFork::Shared => parent.active_thread,
+        };
+        let mut child = Routine::new(thread);
+        child.parent = Some(parent_id);
+        let id = child.id;
+
+        let (cancel, cancelled) = watch::channel(false);
+        self.cancels.write().unwrap().insert(id, cancel);
+        self.register(child.clone());
+
+        let context = Arc::new(ChildContext {
+            routine: child,
+            task: task.clone(),
+            steps: AtomicUsize::new(0),
+            cancel: cancelled.clone(),
+        });
+        let manager = self.clone();
+        let handle = tokio::spawn(async move {
+            let outcome = supervise(run(context.clone()), task.timeout, cancelled).await;
+            manager.cancels.write().unwrap().remove(&id);
+            // 已被级联取消的子 Routine 保持取消状态
+            let outcome = match manager.get(&id).map(|r| r.status) {
+                Some(RoutineStatus::Cancelled) => ChildOutcome::Cancelled,
+                _ => outcome,
+            };
+            manager.set_status(&id, outcome.status());
+            let report = ChildReport {
+                child_id: id,
+                task,
+                steps: context.steps(),
+                outcome,
+            };
+            manager
+                .reports
+                .write()
+                .unwrap()
+                .entry(parent_id)
+                .or_default()
+                .push(report.clone());
+            report
+        });
+        Ok(ChildHandle { id, task: handle })
+    }
+
+    /// 直接子 Routine
+    pub fn children(&self, parent_id: &RoutineId) -> Vec<RoutineId> {
+        self.routines
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| r.parent == Some(*parent_id))
+            .map(|r| r.id)
+            .collect()
+    }
+
+    /// 取走父 Routine 收到的子 Routine 结果（按结束顺序）
+    pub fn take_child_reports(&self, parent_id: &RoutineId) -> Vec<ChildReport> {
+        self.reports
+            .write()
+            .unwrap()
+            .remove(parent_id)
+            .unwrap_or_default()
+    }
+
+    /// 取消 Routine 及其全部未结束的后代，返回被取消的 ID（父在前）
+    pub fn cancel(&self, id: &RoutineId) -> Vec<RoutineId> {
+        let mut cancelled = Vec::new();
+        let mut queue = vec![*id];
+        while let Some(id) = queue.pop() {
+            let active = self.get(&id).is_some_and(|r| {
+                matches!(r.status, RoutineStatus::Running | RoutineStatus::Paused)
+            });
+            if active {
+                self.set_status(&id, RoutineStatus::Cancelled);
+                if let Some(cancel) = self.cancels.read().unwrap().get(&id) {
+                    let _ = cancel.send(true);
+                }
+                cancelled.push(id);
+            }
+            queue.extend(self.children(&id));
+        }
+        cancelled
+    }
+}
+
+/// 运行子 Routine，直到完成、超时或被取消
+async fn supervise<Fut>(
+    run: Fut,
+    timeout: Option<Duration>,
+    mut cancelled: watch::Receiver<bool>,
+) -> ChildOutcome
+where
+    Fut: Future<Output = Result<String>>,
+{
+    let deadline = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        result = run => match result {
+            Ok(output) => ChildOutcome::Completed { output },
+            Err(e) => ChildOutcome::Failed { reason: e.to_string() },
+        },
+        _ = cancelled.wait_for(|c| *c) => ChildOutcome::Cancelled,
+        _ = deadline => ChildOutcome::Failed {
+            reason: format!("Timed out after {:?}", timeout.unwrap_or_default()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::thread::ThreadManager;
+
+    #[tokio::test]
+    async fn test_children_report_back_and_cancel_cascades() {
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let manager = Arc::new(RoutineManager::new().with_threads(threads.clone()));
+        let root = Routine::new(main);
+        let root_id = root.id;
+        manager.register(root);
+
+        // 子 Routine 在分叉线程上运行，结果与失败都回传给父 Routine
+        let ok = manager
+            .spawn_child(
+                root_id,
+                ChildTask::new("summarize"),
+                ThreadFork::Fork {
+                    name: "agent/summarize".to_string(),
+                },
+                |ctx| async move {
+                    ctx.step()?;
+                    Ok(format!("done on {}", ctx.routine.active_thread))
+                },
+            )
+            .unwrap();
+        let child_thread = manager.get(&ok.id).unwrap().active_thread;
+        assert_ne!(child_thread, main);
+        assert_eq!(
+            threads.get_thread(child_thread).unwrap().parent_id,
+            Some(main)
+        );
+        let report = ok.join().await.unwrap();
+        assert_eq!(report.steps, 1);
+        assert!(matches!(report.outcome, ChildOutcome::Completed { .. }));
+
+        let over_budget = manager
+            .spawn_child(
+                root_id,
+                ChildTask::new("loop").with_max_steps(2),
+                ThreadFork::Shared,
+                |ctx| async move {
+                    loop {
+                        ctx.step()?;
+                    }
+                },
+            )
+            .unwrap();
+        let failed = over_budget.id;
+        over_budget.join().await.unwrap();
+        assert!(matches!(
+            manager.get(&failed).unwrap().status,
+            RoutineStatus::Failed(reason) if reason.contains("budget")
+        ));
+        let reports = manager.take_child_reports(&root_id);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[1].child_id, failed);
+        assert_eq!(
+            manager.get(&root_id).unwrap().status,
+            RoutineStatus::Running
+        );
+
+        // 孙 Routine 阻塞运行，取消根 Routine 时级联取消
+        let (started_tx, started) = tokio::sync::oneshot::channel::<RoutineId>();
+        let nested = manager.clone();
+        let child = manager
+            .spawn_child(
+                root_id,
+                ChildTask::new("delegate"),
+                ThreadFork::Shared,
+                move |ctx| async move {
+                    let grandchild = nested.spawn_child(
+                        ctx.routine.id,
+                        ChildTask::new("wait"),
+                        ThreadFork::Shared,
+                        |_| std::future::pending(),
+                    )?;
+                    let _ = started_tx.send(grandchild.id);
+                    let report = grandchild.join().await?;
+                    Ok(format!("{:?}", report.outcome))
+                },
+            )
+            .unwrap();
+        let grandchild = started.await.unwrap();
+        let cancelled = manager.cancel(&root_id);
+        assert_eq!(cancelled.len(), 3);
+        assert_eq!(cancelled[0], root_id);
+        let child_id = child.id;
+        assert_eq!(child.join().await.unwrap().outcome, ChildOutcome::Cancelled);
+        for id in [root_id, child_id, grandchild] {
+            assert_eq!(manager.get(&id).unwrap().status, RoutineStatus::Cancelled);
+        }
+        assert!(
+            manager
+                .spawn_child(root_id, ChildTask::new("late"), ThreadFork::Shared, |_| {
+                    async { Ok(String::new()) }
+                })
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,559 @@
+use crate::agent::manager::RoutineManager;
+use crate::agent::spawn::{ChildContext, ChildHandle, ChildTask, ThreadFork};
+use crate::agent::{RoutineId, RoutineStatus};
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::change::{Change, MergeEngine, VectorClock};
+use crate::common::intent::{IntentHandler, SystemIntent};
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// 团队成员之间的一条消息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamMessage {
+    pub id: Uuid,
+    pub from: String,
+    /// 接收角色，`None` 表示发给除发送者外的全部角色
+    pub to: Option<String>,
+    pub body: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+impl TeamMessage {
+    pub fn new(from: &str, to: Option<&str>, body: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            from: from.to_string(),
+            to: to.map(str::to_string),
+            body: body.to_string(),
+            sent_at: Utc::now(),
+        }
+    }
+}
+
+/// 团队成员经 `IntentDispatcher` 发出的协作意图
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TeamIntent {
+    Message(TeamMessage),
+    /// 角色完成工作，其线程等待验收
+    Submit {
+        role: String,
+        summary: String,
+    },
+    /// 验收通过，协调者合并时并入主线程
+    Accept {
+        role: String,
+    },
+    Reject {
+        role: String,
+        reason: Option<String>,
+    },
+}
+
+/// 角色工作的验收与合并状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum WorkStatus {
+    InProgress,
+    Submitted {
+        summary: String,
+    },
+    Accepted,
+    Rejected {
+        reason: Option<String>,
+    },
+    /// 已并入主线程；没有新改动时 `change_id` 为 `None`
+    Merged {
+        change_id: Option<Uuid>,
+    },
+    /// 与主线程冲突，需要角色在其线程上解决后重新提交
+    Conflicted {
+        paths: Vec<String>,
+    },
+}
+
+/// 团队中的一个角色
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamMember {
+    pub role: String,
+    pub routine_id: RoutineId,
+    /// 从协调者的活动线程分叉出的工作线程
+    pub thread: ThreadId,
+    pub work: WorkStatus,
+}
+
+/// 多个具名角色协作完成任务的 Agent 团队
+///
+/// 每个角色（如 planner、coder、reviewer、tester）作为协调者 Routine 的子 Routine，
+/// 在分叉出的 `team/<协调者>/<角色>` 线程上运行；角色之间经 `TeamIntent` 互发消息、
+/// 提交与验收工作。协调者调用 `merge_accepted` 以 `MergeEngine` 三方合并，
+/// 把验收通过的线程并入自己的活动线程，冲突的线程保持不动并标记为 `Conflicted`。
+pub struct AgentTeam {
+    manager: Arc<RoutineManager>,
+    threads: Arc<ThreadManager>,
+    coordinator: RoutineId,
+    members: Arc<RwLock<BTreeMap<String, TeamMember>>>,
+    inboxes: RwLock<BTreeMap<String, VecDeque<TeamMessage>>>,
+    delivered: Notify,
+}
+
+impl AgentTeam {
+    /// `manager` 需配置线程管理器（见 `RoutineManager::with_threads`）
+    pub fn new(
+        manager: Arc<RoutineManager>,
+        threads: Arc<ThreadManager>,
+        coordinator: RoutineId,
+    ) -> Self {
+        Self {
+            manager,
+            threads,
+            coordinator,
+            members: Arc::new(RwLock::new(BTreeMap::new())),
+            inboxes: RwLock::new(BTreeMap::new()),
+            delivered: Notify::new(),
+        }
+    }
+
+    pub fn coordinator(&self) -> RoutineId {
+        self.coordinator
+    }
+
+    /// 在分叉线程上启动角色；角色名在团队内唯一
+    pub fn spawn<F, Fut>(&self, role: &str, task: ChildTask, run: F) -> Result<ChildHandle>
+    where
+        F: FnOnce(Arc<ChildContext>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        if self.members.read().unwrap().contains_key(role)
+            || self.inboxes.read().unwrap().contains_key(role)
+        {
+            bail!("Role already exists in team: {}", role);
+        }
+        self.inboxes
+            .write()
+            .unwrap()
+            .insert(role.to_string(), VecDeque::new());
+        let members = self.members.clone();
+        let name = role.to_string();
+        let fork = ThreadFork::Fork {
+            name: format!("team/{}/{}", self.coordinator, role),
+        };
+        let handle = self
+            .manager
+            .spawn_child(self.coordinator, task, fork, move |ctx| {
+                // 角色开始运行前登记，保证其提交的意图能找到对应线程
+                members.write().unwrap().insert(
+                    name.clone(),
+                    TeamMember {
+                        role: name,
+                        routine_id: ctx.routine.id,
+                        thread: ctx.routine.active_thread,
+                        work: WorkStatus::InProgress,
+                    },
+                );
+                run(ctx)
+            });
+        match handle {
+            Ok(handle) => {
+                let routine = self
+                    .manager
+                    .get(&handle.id)
+                    .ok_or_else(|| anyhow!("Routine not found: {}", handle.id))?;
+                self.members
+                    .write()
+                    .unwrap()
+                    .entry(role.to_string())
+                    .or_insert_with(|| TeamMember {
+                        role: role.to_string(),
+                        routine_id: routine.id,
+                        thread: routine.active_thread,
+                        work: WorkStatus::InProgress,
+                    });
+                Ok(handle)
+            }
+            Err(e) => {
+                self.inboxes.write().unwrap().remove(role);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn member(&self, role: &str) -> Option<TeamMember> {
+        self.members.read().unwrap().get(role).cloned()
+    }
+
+    /// 全部角色（按角色名排序）
+    pub fn members(&self) -> Vec<TeamMember> {
+        self.members.read().unwrap().values().cloned().collect()
+    }
+
+    /// 投递消息；广播发给除发送者外的全部角色
+    pub fn deliver(&self, message: TeamMessage) -> Result<()> {
+        let mut inboxes = self.inboxes.write().unwrap();
+        if !inboxes.contains_key(&message.from) {
+            bail!("Unknown team role: {}", message.from);
+        }
+        match &message.to {
+            Some(to) => inboxes
+                .get_mut(to)
+                .ok_or_else(|| anyhow!("Unknown team role: {}", to))?
+                .push_back(message.clone()),
+            None => {
+                for (role, inbox) in inboxes.iter_mut() {
+                    if *role != message.from {
+                        inbox.push_back(message.clone());
+                    }
+                }
+            }
+        }
+        drop(inboxes);
+        self.delivered.notify_waiters();
+        Ok(())
+    }
+
+    /// 取走角色收件箱中的全部消息
+    pub fn take_messages(&self, role: &str) -> Vec<TeamMessage> {
+        self.inboxes
+            .write()
+            .unwrap()
+            .get_mut(role)
+            .map(|inbox| inbox.drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    /// 等待角色的下一条消息
+    pub async fn recv(&self, role: &str) -> Result<TeamMessage> {
+        loop {
+            let delivered = self.delivered.notified();
+            if let Some(message) = self
+                .inboxes
+                .write()
+                .unwrap()
+                .get_mut(role)
+                .ok_or_else(|| anyhow!("Unknown team role: {}", role))?
+                .pop_front()
+            {
+                return Ok(message);
+            }
+            delivered.await;
+        }
+    }
+
+    pub fn submit(&self, role: &str, summary: &str) -> Result<()> {
+        self.set_work(role, |work| match work {
+            WorkStatus::Merged { .. } | WorkStatus::Accepted => {
+                Err(anyhow!("Work of {} was already accepted", role))
+            }
+            _ => Ok(WorkStatus::Submitted {
+                summary: summary.to_string(),
+            }),
+        })
+    }
+
+    pub fn accept(&self, role: &str) -> Result<()> {
+        self.set_work(role, |work| match work {
+            WorkStatus::Submitted { .. } => Ok(WorkStatus::Accepted),
+            other => Err(anyhow!("Work of {} is not submitted: {:?}", role, other)),
+        })
+    }
+
+    pub fn reject(&self, role: &str, reason: Option<String>) -> Result<()> {
+        self.set_work(role, |work| match work {
+            WorkStatus::Submitted { .. } => Ok(WorkStatus::Rejected { reason }),
+            other => Err(anyhow!("Work of {} is not submitted: {:?}", role, other)),
+        })
+    }
+
+    fn set_work(
+        &self,
+        role: &str,
+        next: impl FnOnce(&WorkStatus) -> Result<WorkStatus>,
+    ) -> Result<()> {
+        let mut members = self.members.write().unwrap();
+        let member = members
+            .get_mut(role)
+            .ok_or_else(|| anyhow!("Unknown team role: {}", role))?;
+        member.work = next(&member.work)?;
+        Ok(())
+    }
+
+    /// 将验收通过的角色线程依次（按角色名）合并进协调者的活动线程
+    ///
+    /// 有冲突的线程不会改动目标线程；合并生成的 Change 以协调者 Routine ID 为作者。
+    pub fn merge_accepted(&self) -> Result<Vec<TeamMember>> {
+        let coordinator = self
+            .manager
+            .get(&self.coordinator)
+            .ok_or_else(|| anyhow!("Routine not found: {}", self.coordinator))?;
+        if matches!(
+            coordinator.status,
+            RoutineStatus::Cancelled | RoutineStatus::Failed(_)
+        ) {
+            bail!("Coordinator {} has stopped", self.coordinator);
+        }
+        let target = coordinator.active_thread;
+        let accepted: Vec<TeamMember> = self
+            .members()
+            .into_iter()
+            .filter(|m| m.work == WorkStatus::Accepted)
+            .collect();
+
+        let mut merged = Vec::new();
+        for member in accepted {
+            let work = self.merge_member(target, &member)?;
+            self.set_work(&member.role, |_| Ok(work))?;
+            merged.extend(self.member(&member.role));
+        }
+        Ok(merged)
+    }
+
+    fn merge_member(&self, target: ThreadId, member: &TeamMember) -> Result<WorkStatus> {
+        let merge = MergeEngine::new().merge_threads(&self.threads, target, member.thread)?;
+        if merge.report.has_conflicts() {
+            return Ok(WorkStatus::Conflicted {
+                paths: merge.report.conflicts().map(|f| f.path.clone()).collect(),
+            });
+        }
+        if merge.operations.is_empty() {
+            return Ok(WorkStatus::Merged { change_id: None });
+        }
+
+        let mut version = VectorClock::new();
+        for head in [merge.report.left, merge.report.right]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(change) = self.threads.get_change(head) {
+                version.merge(&change.version);
+            }
+        }
+        version.increment(self.coordinator);
+        let parents = [merge.report.left, merge.report.right]
+            .into_iter()
+            .flatten()
+            .collect();
+        let change = Change::new(self.coordinator, merge.operations, version, parents);
+        let change_id = change.id;
+        self.threads.commit_change(target, change)?;
+        self.threads.record_merge(member.thread, target)?;
+        Ok(WorkStatus::Merged {
+            change_id: Some(change_id),
+        })
+    }
+}
+
+#[async_trait]
+impl IntentHandler for AgentTeam {
+    async fn handle(&self, intent: SystemIntent) -> Result<()> {
+        match intent {
+            SystemIntent::Team(TeamIntent::Message(message)) => self.deliver(message),
+            SystemIntent::Team(TeamIntent::Submit { role, summary }) => {
+                self.submit(&role, &summary)
+            }
+            SystemIntent::Team(TeamIntent::Accept { role }) => self.accept(&role),
+            SystemIntent::Team(TeamIntent::Reject { role, reason }) => self.reject(&role, reason),
+            other => Err(anyhow!("Unsupported intent: {}", other.summary())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Routine;
+    use crate::common::change::Operation;
+    use crate::common::change::snapshot::materialize_files;
+    use crate::common::intent::{IntentCategory, IntentDispatcher};
+
+    fn file(threads: &ThreadManager, thread: ThreadId, path: &str) -> Option<String> {
+        let head = threads.get_thread(thread).unwrap().head_change_id?;
+        let history: Vec<Change> = threads
+            .ancestors(head)
+            .into_iter()
+            .filter_map(|id| threads.get_change(id))
+            .collect();
+        materialize_files(&history)
+            .unwrap()
+            .remove(path)
+            .map(|bytes| String::from_utf8(bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_team_roles_collaborate_and_merge_accepted_work() {
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        threads.commit_test(
+            main,
+            vec![Operation::file_write(
+                "src/lib.rs".into(),
+                "fn parse() {}\n".into(),
+            )],
+        );
+        let manager = Arc::new(RoutineManager::new().with_threads(threads.clone()));
+        let coordinator = Routine::new(main);
+        let coordinator_id = coordinator.id;
+        manager.register(coordinator);
+        let team = Arc::new(AgentTeam::new(
+            manager.clone(),
+            threads.clone(),
+            coordinator_id,
+        ));
+        let dispatcher = Arc::new(IntentDispatcher::new());
+        dispatcher
+            .register(IntentCategory::Team, team.clone())
+            .await;
+
+        // coder 按 planner 的消息修改代码并提交验收
+        let (coder_team, coder_dispatcher, coder_threads) =
+            (team.clone(), dispatcher.clone(), threads.clone());
+        let coder = team
+            .spawn(
+                "coder",
+                ChildTask::new("implement"),
+                move |ctx| async move {
+                    let plan = coder_team.recv("coder").await?;
+                    let thread = ctx.routine.active_thread;
+                    coder_threads.commit_test(
+                        thread,
+                        vec![Operation::file_write(
+                            "src/lib.rs".into(),
+                            plan.body.as_bytes().to_vec(),
+                        )],
+                    );
+                    coder_dispatcher
+                        .dispatch(SystemIntent::Team(TeamIntent::Submit {
+                            role: "coder".to_string(),
+                            summary: "implement parse".to_string(),
+                        }))
+                        .await?;
+                    Ok("implemented".to_string())
+                },
+            )
+            .unwrap();
+        let (tester_threads, tester_dispatcher) = (threads.clone(), dispatcher.clone());
+        let tester = team
+            .spawn("tester", ChildTask::new("test"), move |ctx| async move {
+                let thread = ctx.routine.active_thread;
+                tester_threads.commit_test(
+                    thread,
+                    vec![Operation::file_write(
+                        "tests/parse.rs".into(),
+                        "#[test]\n".into(),
+                    )],
+                );
+                tester_dispatcher
+                    .dispatch(SystemIntent::Team(TeamIntent::Submit {
+                        role: "tester".to_string(),
+                        summary: "add tests".to_string(),
+                    }))
+                    .await?;
+                Ok("tested".to_string())
+            })
+            .unwrap();
+        let planner = team
+            .spawn("planner", ChildTask::new("plan"), |_| async {
+                Ok(String::new())
+            })
+            .unwrap();
+        assert!(
+            team.spawn("coder", ChildTask::new("again"), |_| async {
+                Ok(String::new())
+            })
+            .is_err()
+        );
+        planner.join().await.unwrap();
+        dispatcher
+            .dispatch(SystemIntent::Team(TeamIntent::Message(TeamMessage::new(
+                "planner",
+                Some("coder"),
+                "fn parse() -> Ast { todo!() }\n",
+            ))))
+            .await
+            .unwrap();
+        coder.join().await.unwrap();
+        tester.join().await.unwrap();
+
+        let members = team.members();
+        assert_eq!(members.len(), 3);
+        let coder = team.member("coder").unwrap();
+        assert_ne!(coder.thread, main);
+        assert_eq!(
+            manager.get(&coder.routine_id).unwrap().parent,
+            Some(coordinator_id)
+        );
+
+        // reviewer 只接受 coder 的工作；未验收的工作不会合并
+        dispatcher
+            .dispatch(SystemIntent::Team(TeamIntent::Accept {
+                role: "coder".to_string(),
+            }))
+            .await
+            .unwrap();
+        let merged = team.merge_accepted().unwrap();
+        assert_eq!(merged.len(), 1);
+        assert!(matches!(
+            merged[0].work,
+            WorkStatus::Merged { change_id: Some(_) }
+        ));
+        assert_eq!(
+            file(&threads, main, "src/lib.rs").unwrap(),
+            "fn parse() -> Ast { todo!() }\n"
+        );
+        assert!(file(&threads, main, "tests/parse.rs").is_none());
+        assert!(matches!(
+            team.member("tester").unwrap().work,
+            WorkStatus::Submitted { .. }
+        ));
+
+        team.accept("tester").unwrap();
+        team.merge_accepted().unwrap();
+        assert!(file(&threads, main, "tests/parse.rs").is_some());
+        assert_eq!(
+            file(&threads, main, "src/lib.rs").unwrap(),
+            "fn parse() -> Ast { todo!() }\n"
+        );
+        assert!(team.merge_accepted().unwrap().is_empty());
+
+        // 与主线程冲突的工作不会并入
+        let fixer = team
+            .spawn("fixer", ChildTask::new("fix"), |_| async {
+                Ok(String::new())
+            })
+            .unwrap();
+        fixer.join().await.unwrap();
+        let fixer = team.member("fixer").unwrap();
+        threads.commit_test(
+            fixer.thread,
+            vec![Operation::file_write(
+                "src/lib.rs".into(),
+                "fn parse() -> Tree {}\n".into(),
+            )],
+        );
+        threads.commit_test(
+            main,
+            vec![Operation::file_write(
+                "src/lib.rs".into(),
+                "fn parse() -> Node {}\n".into(),
+            )],
+        );
+        team.submit("fixer", "rename").unwrap();
+        team.accept("fixer").unwrap();
+        let conflicted = team.merge_accepted().unwrap();
+        assert_eq!(
+            conflicted[0].work,
+            WorkStatus::Conflicted {
+                paths: vec!["src/lib.rs".to_string()]
+            }
+        );
+        assert_eq!(
+            file(&threads, main, "src/lib.rs").unwrap(),
+            "fn parse() -> Node {}\n"
+        );
+    }
+}
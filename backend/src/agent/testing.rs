@@ -0,0 +1,553 @@
+use crate::agent::executor::RoutineExecutor;
+use crate::agent::{Routine, RoutineStatus};
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::change::{Change, Operation, VectorClock};
+use crate::common::endpoint::{ChatMessage, ChatOptions, LLMClient, MessageRole};
+use crate::common::provider::traits::{ExecuteOptions, ExecutionProvider, StorageProvider};
+use crate::project::conventions::TestConventions;
+use crate::skill::sandbox::ToolEffects;
+use crate::skill::tool::{Tool, ToolOutput};
+use crate::skill::traits::SkillError;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// 一次生成并运行测试的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestAttempt {
+    pub exit_code: i32,
+    /// 测试命令输出（截取末尾部分）
+    pub output: String,
+}
+
+/// 测试生成的最终报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestGenerationReport {
+    /// 承载生成测试的子 Routine
+    pub routine: Routine,
+    /// 子 Routine 的分叉 Thread，生成的测试以 Change 形式提交在其上
+    pub thread_id: ThreadId,
+    pub conventions: TestConventions,
+    pub command: String,
+    /// 最后一次生成的测试文件完整内容
+    pub content: String,
+    pub passed: bool,
+    pub attempts: Vec<TestAttempt>,
+}
+
+const GENERATE_PROMPT: &str = "You write unit tests. Follow the framework and the style of the \
+existing tests exactly. Reply with a single fenced code block containing only the test code.";
+
+/// 反馈给模型的测试输出最大字符数
+const MAX_OUTPUT_CHARS: usize = 4000;
+
+/// 测试生成器：识别项目的测试约定，在分叉 Thread 上生成测试并运行，失败时携带输出重试，
+/// 直到测试通过或尝试次数用尽
+///
+/// 运行测试需要把文件写入工作区，每次运行结束后都会恢复原内容；
+/// 生成结果只以 Change 的形式留在分叉 Thread 上，由合并流程决定是否采纳。
+pub struct TestGenerator {
+    storage: Arc<dyn StorageProvider>,
+    threads: Arc<ThreadManager>,
+    runner: Arc<dyn ExecutionProvider>,
+    client: Arc<dyn LLMClient>,
+    model: String,
+    max_attempts: usize,
+    timeout_ms: u64,
+}
+
+impl TestGenerator {
+    pub fn new(
+        storage: Arc<dyn StorageProvider>,
+        threads: Arc<ThreadManager>,
+        runner: Arc<dyn ExecutionProvider>,
+        client: Arc<dyn LLMClient>,
+        model: &str,
+    ) -> Self {
+        Self {
+            storage,
+            threads,
+            runner,
+            client,
+            model: model.to_string(),
+            max_attempts: 3,
+            timeout_ms: 300_000,
+        }
+    }
+
+    /// 最多生成并运行的次数
+    pub fn with_max_attempts(mut self, attempts: usize) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// 单次测试运行的超时时间
+    pub fn with_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// 为 `path` 中的 `symbol` 生成测试
+    pub async fn generate(
+        &self,
+        parent: &Routine,
+        path: &str,
+        symbol: &str,
+    ) -> Result<TestGenerationReport> {
+        let conventions = TestConventions::detect(self.storage.as_ref(), path).await?;
+        let source = read_text(self.storage.as_ref(), path).await?;
+        let original = if conventions.inline {
+            Some(source.clone())
+        } else {
+            read_text(self.storage.as_ref(), &conventions.test_path)
+                .await
+                .ok()
+        };
+        let mut examples = String::new();
+        for example in &conventions.examples {
+            if let Ok(text) = read_text(self.storage.as_ref(), example).await {
+                examples.push_str(&format!("### {}\n```\n{}\n```\n\n", example, text));
+            }
+        }
+
+        let mut routine = RoutineExecutor::new(self.threads.clone())
+            .fork(parent, &format!("tests/{}", symbol))?;
+        let command = conventions.command();
+        let mut messages = vec![
+            ChatMessage::text(MessageRole::System, GENERATE_PROMPT),
+            ChatMessage::text(
+                MessageRole::User,
+                &self.task_prompt(&conventions, path, symbol, &source, &examples),
+            ),
+        ];
+        let options = ChatOptions {
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+
+        let mut attempts = Vec::new();
+        let mut content = String::new();
+        let mut passed = false;
+        while attempts.len() < self.max_attempts {
+            let response = self.client.chat(&self.model, &messages, &options).await?;
+            let reply = response
+                .choices
+                .first()
+                .map(|c| c.message.content.as_text())
+                .unwrap_or_default();
+            let code = code_block(&reply);
+            content = match &original {
+                Some(base) if conventions.inline => format!("{}\n{}\n", base.trim_end(), code),
+                _ => format!("{}\n", code.trim_end()),
+            };
+
+            let guard = TestFileGuard::new(
+                self.storage.clone(),
+                &conventions.test_path,
+                original.clone(),
+            );
+            self.storage
+                .write_file(&conventions.test_path, content.as_bytes())
+                .await?;
+            let run = self
+                .runner
+                .execute(
+                    &command,
+                    ExecuteOptions {
+                        cwd: Some(conventions.root.clone()),
+                        timeout_ms: Some(self.timeout_ms),
+                        ..Default::default()
+                    },
+                )
+                .await;
+            guard.restore().await?;
+            let run = run?;
+
+            let output = run.output_tail(MAX_OUTPUT_CHARS);
+            attempts.push(TestAttempt {
+                exit_code: run.exit_code,
+                output: output.clone(),
+            });
+            if run.exit_code == 0 {
+                passed = true;
+                break;
+            }
+            messages.push(ChatMessage::text(MessageRole::Assistant, &reply));
+            messages.push(ChatMessage::text(
+                MessageRole::User,
+                &format!(
+                    "`{}` failed with exit code {}:\n```\n{}\n```\nFix the tests (not the code under test) and reply with the full test code again.",
+                    command, run.exit_code, output
+                ),
+            ));
+        }
+
+        let head = self
+            .threads
+            .get_thread(routine.active_thread)
+            .and_then(|t| t.head_change_id);
+        let change = Change::new(
+            routine.id,
+            vec![Operation::file_write(
+                conventions.test_path.clone(),
+                content.clone().into_bytes(),
+            )],
+            VectorClock::new(),
+            head.into_iter().collect(),
+        );
+        self.threads.commit_change(routine.active_thread, change)?;
+        routine.status = if passed {
+            RoutineStatus::Completed
+        } else {
+            RoutineStatus::Failed(format!(
+                "Tests still failing after {} attempt(s)",
+                attempts.len()
+            ))
+        };
+
+        Ok(TestGenerationReport {
+            thread_id: routine.active_thread,
+            routine,
+            conventions,
+            command,
+            content,
+            passed,
+            attempts,
+        })
+    }
+
+    fn task_prompt(
+        &self,
+        conventions: &TestConventions,
+        path: &str,
+        symbol: &str,
+        source: &str,
+        examples: &str,
+    ) -> String {
+        let placement = if conventions.inline {
+            format!(
+                "Write a `#[cfg(test)] mod generated_tests` module that will be appended to the end of {}; \
+                 use `super::*` to reach the code under test.",
+                path
+            )
+        } else {
+            format!("Write the complete test file {}.", conventions.test_path)
+        };
+        let examples = if examples.is_empty() {
+            "(none)\n".to_string()
+        } else {
+            examples.to_string()
+        };
+        format!(
+            "Framework: {}\nTest command: {}\n{}\n\nGenerate tests for `{}` in {}:\n```\n{}\n```\n\nExisting tests in this project:\n{}",
+            conventions.framework.name(),
+            conventions.command(),
+            placement,
+            symbol,
+            path,
+            source,
+            examples
+        )
+    }
+}
+
+/// 运行期间替换工作区中测试文件的守卫
+///
+/// 正常结束时经 `restore` 恢复原内容；写入失败或生成过程被取消（Future 被丢弃）时，
+/// 在 `Drop` 中于后台恢复，工作区不会留下生成的测试。
+struct TestFileGuard {
+    storage: Arc<dyn StorageProvider>,
+    path: String,
+    original: Option<String>,
+    restored: bool,
+}
+
+impl TestFileGuard {
+    fn new(storage: Arc<dyn StorageProvider>, path: &str, original: Option<String>) -> Self {
+        Self {
+            storage,
+            path: path.to_string(),
+            original,
+            restored: false,
+        }
+    }
+
+    async fn restore(mut self) -> Result<()> {
+        self.restored = true;
+        restore_file(self.storage.as_ref(), &self.path, self.original.as_deref()).await
+    }
+}
+
+impl Drop for TestFileGuard {
+    fn drop(&mut self) {
+        if self.restored {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let storage = self.storage.clone();
+        let path = std::mem::take(&mut self.path);
+        let original = self.original.take();
+        runtime.spawn(async move {
+            // 已无调用者可以接收错误
+            let _ = restore_file(storage.as_ref(), &path, original.as_deref()).await;
+        });
+    }
+}
+
+/// 恢复工作区中的测试文件：写回原内容，原本不存在时删除
+async fn restore_file(
+    storage: &dyn StorageProvider,
+    path: &str,
+    original: Option<&str>,
+) -> Result<()> {
+    match original {
+        Some(text) => storage.write_file(path, text.as_bytes()).await,
+        None if storage.exists(path).await? => storage.delete(path, false).await,
+        None => Ok(()),
+    }
+}
+
+async fn read_text(storage: &dyn StorageProvider, path: &str) -> Result<String> {
+    String::from_utf8(storage.read_file(path).await?)
+        .map_err(|_| anyhow!("{} is not valid UTF-8", path))
+}
+
+/// 取回复中第一个围栏代码块的内容；没有代码块时使用整段回复
+fn code_block(reply: &str) -> String {
+    let Some(start) = reply.find("```") else {
+        return reply.trim().to_string();
+    };
+    let body = &reply[start + 3..];
+    let body = body.split_once('\n').map_or("", |(_, rest)| rest);
+    body.find("```")
+        .map_or(body, |end| &body[..end])
+        .trim_end()
+        .to_string()
+}
+
+/// `generate_tests` 工具：在调用者 Routine 的分叉 Thread 上为指定符号生成测试
+pub struct GenerateTestsTool {
+    generator: Arc<TestGenerator>,
+    parent: Routine,
+}
+
+impl GenerateTestsTool {
+    pub fn new(generator: Arc<TestGenerator>, parent: Routine) -> Self {
+        Self { generator, parent }
+    }
+}
+
+#[async_trait(?Send)]
+impl Tool for GenerateTestsTool {
+    fn name(&self) -> &'static str {
+        "generate_tests"
+    }
+
+    fn description(&self) -> &'static str {
+        "为指定文件中的符号生成测试：自动识别项目的测试框架与现有测试风格，在分叉 Thread 上运行并迭代直到通过。"
+    }
+
+    fn parameter_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "被测符号所在的文件"
+                },
+                "symbol": {
+                    "type": "string",
+                    "description": "要测试的函数或类型名称"
+                }
+            },
+            "required": ["path", "symbol"]
+        })
+    }
+
+    /// 写入的测试文件与运行的测试命令取决于项目约定，事先未知
+    async fn effects(&self, _args: &Value) -> ToolEffects {
+        ToolEffects::writes(Vec::new()).and_runs(Vec::new())
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
+        let path = args["path"]
+            .as_str()
+            .ok_or_else(|| SkillError::InvalidSkill("Missing 'path' parameter".into()))?;
+        let symbol = args["symbol"]
+            .as_str()
+            .ok_or_else(|| SkillError::InvalidSkill("Missing 'symbol' parameter".into()))?;
+        let report = self
+            .generator
+            .generate(&self.parent, path, symbol)
+            .await
+            .map_err(|e| SkillError::IoError(std::io::Error::other(e.to_string())))?;
+        Ok(ToolOutput {
+            content: format!(
+                "{} for '{}' in {} after {} attempt(s)",
+                if report.passed {
+                    "Generated passing tests"
+                } else {
+                    "Generated tests are still failing"
+                },
+                symbol,
+                report.conventions.test_path,
+                report.attempts.len()
+            ),
+            data: Some(json!(report)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::common::provider::traits::ExecuteResult;
+    use std::sync::Mutex;
+
+    /// 测试文件包含 `assert_eq!(add(1, 2), 3)` 时才通过，并记录运行时的文件内容
+    struct Runner {
+        root: std::path::PathBuf,
+        seen: Mutex<Vec<(String, Option<String>)>>,
+    }
+
+    #[async_trait]
+    impl ExecutionProvider for Runner {
+        async fn execute(&self, cmd: &str, opts: ExecuteOptions) -> Result<ExecuteResult> {
+            let content = std::fs::read_to_string(self.root.join("src/lib.rs")).unwrap();
+            self.seen.lock().unwrap().push((cmd.to_string(), opts.cwd));
+            let ok = content.contains("assert_eq!(add(1, 2), 3)");
+            Ok(ExecuteResult {
+                exit_code: if ok { 0 } else { 101 },
+                stdout: String::new(),
+                stderr: if ok {
+                    String::new()
+                } else {
+                    "assertion failed: left: 3, right: 4".to_string()
+                },
+            })
+        }
+
+        async fn kill(&self, _id: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_tests_iterates_until_green() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = "pub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n";
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), source).unwrap();
+
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let client = Arc::new(ScriptedClient::new("scripted").with_replies([
+                "```rust\n#[cfg(test)]\nmod generated_tests {\n    use super::*;\n    #[test]\n    fn adds() { assert_eq!(add(1, 2), 4); }\n}\n```",
+                "Fixed:\n```rust\n#[cfg(test)]\nmod generated_tests {\n    use super::*;\n    #[test]\n    fn adds() { assert_eq!(add(1, 2), 3); }\n}\n```",
+        ]));
+        let runner = Arc::new(Runner {
+            root: dir.path().to_path_buf(),
+            seen: Mutex::new(Vec::new()),
+        });
+        let generator = Arc::new(TestGenerator::new(
+            Arc::new(LocalFileSystem::new(dir.path())),
+            threads.clone(),
+            runner.clone(),
+            client.clone(),
+            "model",
+        ));
+        let tool = GenerateTestsTool::new(generator, Routine::new(main));
+
+        let output = tool
+            .execute(json!({"path": "src/lib.rs", "symbol": "add"}))
+            .await
+            .unwrap();
+        let report: TestGenerationReport = serde_json::from_value(output.data.unwrap()).unwrap();
+        assert!(report.passed);
+        assert_eq!(report.attempts.len(), 2);
+        assert_eq!(report.routine.status, RoutineStatus::Completed);
+        assert_eq!(report.command, "cargo test");
+        assert!(report.content.starts_with(source.trim_end()));
+
+        let prompts = client.prompts();
+        assert!(prompts[0].contains("Generate tests for `add`"));
+        assert!(prompts[1].contains("right: 4"));
+        assert_eq!(runner.seen.lock().unwrap()[0].1.as_deref(), Some(""));
+
+        // 工作区恢复原状，生成结果提交在分叉 Thread 上
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("src/lib.rs")).unwrap(),
+            source
+        );
+        let thread = threads.get_thread(report.thread_id).unwrap();
+        assert_eq!(thread.name, "tests/add");
+        assert_eq!(thread.parent_id, Some(main));
+        let change = threads.get_change(thread.head_change_id.unwrap()).unwrap();
+        assert_eq!(
+            change.operations,
+            vec![Operation::file_write(
+                "src/lib.rs".to_string(),
+                report.content.into_bytes()
+            )]
+        );
+        assert_eq!(threads.get_thread(main).unwrap().head_change_id, None);
+    }
+
+    struct Hang;
+
+    #[async_trait]
+    impl ExecutionProvider for Hang {
+        async fn execute(&self, _cmd: &str, _opts: ExecuteOptions) -> Result<ExecuteResult> {
+            std::future::pending().await
+        }
+
+        async fn kill(&self, _id: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_generation_restores_the_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = "pub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n";
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), source).unwrap();
+
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let client = Arc::new(
+            ScriptedClient::new("scripted")
+                .with_replies(["```rust\n#[cfg(test)]\nmod generated_tests {}\n```"]),
+        );
+        let generator = TestGenerator::new(
+            Arc::new(LocalFileSystem::new(dir.path())),
+            threads,
+            Arc::new(Hang),
+            client,
+            "model",
+        );
+
+        let parent = Routine::new(main);
+        let run = generator.generate(&parent, "src/lib.rs", "add");
+        let cancelled = tokio::time::timeout(std::time::Duration::from_millis(50), run).await;
+        assert!(cancelled.is_err());
+
+        // 守卫在后台恢复原内容
+        let path = dir.path().join("src/lib.rs");
+        for _ in 0..100 {
+            if std::fs::read_to_string(&path).unwrap() == source {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), source);
+    }
+}
@@ -0,0 +1,652 @@
+use crate::agent::{Routine, RoutineId};
+use crate::common::change::thread::ThreadId;
+use crate::common::endpoint::error::EndpointResult;
+use crate::common::endpoint::traits::EmbeddingResponse;
+use crate::common::endpoint::{
+    ChatMessage, ChatOptions, ChatResponse, LLMClient, ToolDefinition, ToolExecutor,
+};
+use crate::common::event::sink::EventSink;
+use crate::common::event::types::{BackendEvent, EventEnvelope};
+use crate::common::provider::traits::StorageProvider;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Routine 追踪记录的默认目录（相对项目根目录）
+pub const TRACE_DIR: &str = ".zhiyun/traces";
+
+/// 追踪事件的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceKind {
+    LlmCall,
+    ToolCall,
+    Intent,
+    ChangeCommitted,
+    Error,
+}
+
+/// 追踪事件的内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TraceEventKind {
+    /// 一次模型调用及其请求的工具
+    LlmCall {
+        model: String,
+        messages: usize,
+        prompt_tokens: Option<u32>,
+        completion_tokens: Option<u32>,
+        reply: String,
+        tool_calls: Vec<String>,
+    },
+    ToolCall {
+        name: String,
+        arguments: Value,
+        output: String,
+        is_error: bool,
+    },
+    Intent {
+        category: String,
+        intent: String,
+        error: Option<String>,
+    },
+    ChangeCommitted {
+        thread_id: ThreadId,
+        change_id: uuid::Uuid,
+        operations: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl TraceEventKind {
+    pub fn kind(&self) -> TraceKind {
+        match self {
+            TraceEventKind::LlmCall { .. } => TraceKind::LlmCall,
+            TraceEventKind::ToolCall { .. } => TraceKind::ToolCall,
+            TraceEventKind::Intent { .. } => TraceKind::Intent,
+            TraceEventKind::ChangeCommitted { .. } => TraceKind::ChangeCommitted,
+            TraceEventKind::Error { .. } => TraceKind::Error,
+        }
+    }
+}
+
+/// Routine 运行中的一个事件
+///
+/// `parent_id` 指向直接原因（工具调用的父事件是请求它的模型调用，工具执行期间
+/// 派发的意图与提交的变更的父事件是该工具调用），`correlation_id` 为因果链的根。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceEvent {
+    pub id: uuid::Uuid,
+    pub routine_id: RoutineId,
+    pub correlation_id: uuid::Uuid,
+    #[serde(default)]
+    pub parent_id: Option<uuid::Uuid>,
+    pub timestamp: DateTime<Utc>,
+    /// 有持续时间的事件（模型调用、工具调用）的耗时
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    #[serde(flatten)]
+    pub event: TraceEventKind,
+}
+
+impl TraceEvent {
+    fn contains(&self, at: DateTime<Utc>) -> bool {
+        let end =
+            self.timestamp + chrono::Duration::milliseconds(self.duration_ms.unwrap_or(0) as i64);
+        self.timestamp <= at && at <= end
+    }
+}
+
+/// `RoutineTrace::events` 的查询条件，未设置的条件不限制
+#[derive(Debug, Clone, Default)]
+pub struct TraceFilter {
+    pub kinds: HashSet<TraceKind>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub correlation_id: Option<uuid::Uuid>,
+}
+
+impl TraceFilter {
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    pub fn only(kinds: impl IntoIterator<Item = TraceKind>) -> Self {
+        Self {
+            kinds: kinds.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// 只保留同一因果链上的事件
+    pub fn correlated(mut self, correlation_id: uuid::Uuid) -> Self {
+        self.correlation_id = Some(correlation_id);
+        self
+    }
+
+    pub fn matches(&self, event: &TraceEvent) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&event.event.kind()))
+            && self.since.is_none_or(|t| event.timestamp >= t)
+            && self.until.is_none_or(|t| event.timestamp <= t)
+            && self
+                .correlation_id
+                .is_none_or(|id| event.correlation_id == id)
+    }
+}
+
+/// 正在执行的工具调用
+struct OpenSpan {
+    id: uuid::Uuid,
+    started: DateTime<Utc>,
+}
+
+/// 单个 Routine 的结构化追踪记录
+///
+/// 模型调用与工具调用经 `TracedClient`、`TracedTools` 自动记录；订阅到 `EventBus` 后，
+/// 派发的意图与提交到该 Routine 线程上的变更也会被记录，并按时间归因到当时正在执行的工具调用。
+/// 配置存储后每个 Routine 的记录保存为一个 JSON Lines 文件。
+pub struct RoutineTrace {
+    routine_id: RoutineId,
+    threads: RwLock<HashSet<ThreadId>>,
+    events: RwLock<Vec<TraceEvent>>,
+    open: Mutex<Vec<OpenSpan>>,
+    storage: Option<(Arc<dyn StorageProvider>, String)>,
+    name: String,
+}
+
+impl RoutineTrace {
+    pub fn new(routine: &Routine) -> Self {
+        Self {
+            routine_id: routine.id,
+            threads: RwLock::new(HashSet::from([routine.active_thread])),
+            events: RwLock::new(Vec::new()),
+            open: Mutex::new(Vec::new()),
+            storage: None,
+            name: format!("trace:{}", routine.id),
+        }
+    }
+
+    /// 持久化到 `TRACE_DIR/<routine>.jsonl`
+    pub fn with_storage(self, storage: Arc<dyn StorageProvider>) -> Self {
+        self.with_storage_dir(storage, TRACE_DIR)
+    }
+
+    pub fn with_storage_dir(mut self, storage: Arc<dyn StorageProvider>, dir: &str) -> Self {
+        let path = format!("{}/{}.jsonl", dir.trim_end_matches('/'), self.routine_id);
+        self.storage = Some((storage, path));
+        self
+    }
+
+    /// 同时记录提交到该线程上的变更（例如 Routine 分叉出的线程）
+    pub fn with_thread(self, thread: ThreadId) -> Self {
+        self.threads.write().unwrap().insert(thread);
+        self
+    }
+
+    pub fn routine_id(&self) -> RoutineId {
+        self.routine_id
+    }
+
+    /// 载入已保存的记录（进程重启后继续追加）
+    pub async fn load(&self) -> Result<usize> {
+        let Some((storage, path)) = &self.storage else {
+            return Ok(0);
+        };
+        if !storage.exists(path).await? {
+            return Ok(0);
+        }
+        let content = String::from_utf8(storage.read_file(path).await?)?;
+        let loaded = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<serde_json::Result<Vec<TraceEvent>>>()?;
+        let count = loaded.len();
+        let mut events = self.events.write().unwrap();
+        let mut merged = loaded;
+        merged.append(&mut events);
+        *events = merged;
+        Ok(count)
+    }
+
+    /// 将全部记录写入存储
+    pub async fn flush(&self) -> Result<()> {
+        let Some((storage, path)) = &self.storage else {
+            return Ok(());
+        };
+        let mut buf = Vec::new();
+        for event in self.events.read().unwrap().iter() {
+            serde_json::to_writer(&mut buf, event)?;
+            buf.push(b'\n');
+        }
+        storage.write_file(path, &buf).await
+    }
+
+    /// 按条件查询事件（按时间顺序）
+    pub fn events(&self, filter: &TraceFilter) -> Vec<TraceEvent> {
+        let mut events: Vec<TraceEvent> = self
+            .events
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|e| filter.matches(e))
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| e.timestamp);
+        events
+    }
+
+    pub fn get(&self, id: uuid::Uuid) -> Option<TraceEvent> {
+        self.events
+            .read()
+            .unwrap()
+            .iter()
+            .find(|e| e.id == id)
+            .cloned()
+    }
+
+    /// 事件的因果链，从根（通常是一次模型调用）到事件本身
+    pub fn causes(&self, id: uuid::Uuid) -> Vec<TraceEvent> {
+        let mut chain = VecDeque::new();
+        let mut next = Some(id);
+        while let Some(id) = next {
+            let Some(event) = self.get(id) else {
+                break;
+            };
+            next = event.parent_id;
+            chain.push_front(event);
+        }
+        chain.into()
+    }
+
+    /// 记录一个即时事件，原因归于当时正在执行的工具调用或最近的模型调用
+    pub fn record(&self, event: TraceEventKind) -> uuid::Uuid {
+        self.record_at(Utc::now(), event)
+    }
+
+    fn record_at(&self, at: DateTime<Utc>, event: TraceEventKind) -> uuid::Uuid {
+        let parent = self.cause_at(at);
+        self.push(uuid::Uuid::new_v4(), parent, at, None, event)
+    }
+
+    fn push(
+        &self,
+        id: uuid::Uuid,
+        parent_id: Option<uuid::Uuid>,
+        timestamp: DateTime<Utc>,
+        duration_ms: Option<u64>,
+        event: TraceEventKind,
+    ) -> uuid::Uuid {
+        let correlation_id = parent_id
+            .and_then(|p| self.get(p))
+            .map_or(id, |p| p.correlation_id);
+        self.events.write().unwrap().push(TraceEvent {
+            id,
+            routine_id: self.routine_id,
+            correlation_id,
+            parent_id,
+            timestamp,
+            duration_ms,
+            event,
+        });
+        id
+    }
+
+    /// `at` 时刻的原因：正在执行或时间上包含 `at` 的工具调用，否则为此前最近的模型调用
+    fn cause_at(&self, at: DateTime<Utc>) -> Option<uuid::Uuid> {
+        if let Some(span) = self
+            .open
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.started <= at)
+            .max_by_key(|s| s.started)
+        {
+            return Some(span.id);
+        }
+        let events = self.events.read().unwrap();
+        events
+            .iter()
+            .filter(|e| e.event.kind() == TraceKind::ToolCall && e.contains(at))
+            .max_by_key(|e| e.timestamp)
+            .or_else(|| {
+                events
+                    .iter()
+                    .filter(|e| e.event.kind() == TraceKind::LlmCall && e.timestamp <= at)
+                    .max_by_key(|e| e.timestamp)
+            })
+            .map(|e| e.id)
+    }
+
+    fn last_llm_call(&self) -> Option<uuid::Uuid> {
+        self.events
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|e| e.event.kind() == TraceKind::LlmCall)
+            .max_by_key(|e| e.timestamp)
+            .map(|e| e.id)
+    }
+}
+
+/// 向上取整到毫秒，保证调用期间发生的事件落在 `[timestamp, timestamp + duration]` 内
+fn elapsed_ms(started: DateTime<Utc>) -> Option<u64> {
+    let micros = (Utc::now() - started)
+        .num_microseconds()
+        .unwrap_or(i64::MAX);
+    Some((micros.max(0) as u64).div_ceil(1000))
+}
+
+/// 把总线上与该 Routine 相关的意图与变更写入追踪记录
+#[async_trait]
+impl EventSink for RoutineTrace {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn publish(&self, events: &[EventEnvelope]) -> Result<()> {
+        let mut recorded = false;
+        for envelope in events {
+            let event = match &envelope.event {
+                BackendEvent::IntentDispatched {
+                    category,
+                    intent,
+                    error,
+                } => TraceEventKind::Intent {
+                    category: category.clone(),
+                    intent: intent.clone(),
+                    error: error.clone(),
+                },
+                BackendEvent::ChangeCommitted {
+                    thread_id,
+                    change_id,
+                    operations,
+                    ..
+                } if self.threads.read().unwrap().contains(thread_id) => {
+                    TraceEventKind::ChangeCommitted {
+                        thread_id: *thread_id,
+                        change_id: *change_id,
+                        operations: *operations,
+                    }
+                }
+                _ => continue,
+            };
+            self.record_at(envelope.timestamp, event);
+            recorded = true;
+        }
+        if recorded {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+/// 记录每次模型调用的客户端包装
+pub struct TracedClient {
+    inner: Arc<dyn LLMClient>,
+    trace: Arc<RoutineTrace>,
+}
+
+impl TracedClient {
+    pub fn new(inner: Arc<dyn LLMClient>, trace: Arc<RoutineTrace>) -> Self {
+        Self { inner, trace }
+    }
+}
+
+#[async_trait]
+impl LLMClient for TracedClient {
+    fn provider_id(&self) -> &str {
+        self.inner.provider_id()
+    }
+
+    async fn chat(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> EndpointResult<ChatResponse> {
+        let started = Utc::now();
+        let result = self.inner.chat(model, messages, options).await;
+        let id = uuid::Uuid::new_v4();
+        let event = match &result {
+            Ok(response) => {
+                let message = response.choices.first().map(|c| &c.message);
+                TraceEventKind::LlmCall {
+                    model: model.to_string(),
+                    messages: messages.len(),
+                    prompt_tokens: response.usage.as_ref().map(|u| u.prompt_tokens),
+                    completion_tokens: response.usage.as_ref().map(|u| u.completion_tokens),
+                    reply: message.map(|m| m.content.as_text()).unwrap_or_default(),
+                    tool_calls: message
+                        .and_then(|m| m.tool_calls.as_ref())
+                        .into_iter()
+                        .flatten()
+                        .map(|c| c.function.name.clone())
+                        .collect(),
+                }
+            }
+            Err(e) => TraceEventKind::Error {
+                message: format!("{} call failed: {}", model, e),
+            },
+        };
+        self.trace
+            .push(id, None, started, elapsed_ms(started), event);
+        // 追踪写入失败不影响模型调用
+        let _ = self.trace.flush().await;
+        result
+    }
+
+    async fn embed(&self, model: &str, input: &[String]) -> EndpointResult<EmbeddingResponse> {
+        self.inner.embed(model, input).await
+    }
+
+    async fn health_check(&self) -> EndpointResult<()> {
+        self.inner.health_check().await
+    }
+}
+
+/// 记录每次工具调用的工具集包装
+pub struct TracedTools<T> {
+    inner: T,
+    trace: Arc<RoutineTrace>,
+}
+
+impl<T: ToolExecutor> TracedTools<T> {
+    pub fn new(inner: T, trace: Arc<RoutineTrace>) -> Self {
+        Self { inner, trace }
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: ToolExecutor> ToolExecutor for TracedTools<T> {
+    fn definitions(&self) -> Vec<ToolDefinition> {
+        self.inner.definitions()
+    }
+
+    async fn call(&self, name: &str, arguments: Value) -> Result<String> {
+        let id = uuid::Uuid::new_v4();
+        let started = Utc::now();
+        self.trace
+            .open
+            .lock()
+            .unwrap()
+            .push(OpenSpan { id, started });
+        let result = self.inner.call(name, arguments.clone()).await;
+        self.trace.open.lock().unwrap().retain(|s| s.id != id);
+
+        let (output, is_error) = match &result {
+            Ok(output) => (output.clone(), false),
+            Err(e) => (e.to_string(), true),
+        };
+        let parent = self.trace.last_llm_call();
+        self.trace.push(
+            id,
+            parent,
+            started,
+            elapsed_ms(started),
+            TraceEventKind::ToolCall {
+                name: name.to_string(),
+                arguments,
+                output,
+                is_error,
+            },
+        );
+        let _ = self.trace.flush().await;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::common::endpoint::traits::{FunctionCall, FunctionDefinition, ToolCall};
+    use crate::common::endpoint::{AgentLoop, MessageRole};
+    use crate::common::event::bus::EventBus;
+    use crate::common::event::types::EventFilter;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use serde_json::json;
+
+    /// 执行时在总线上报告意图与变更的编辑工具
+    struct Editor {
+        bus: Arc<EventBus>,
+        thread: ThreadId,
+    }
+
+    #[async_trait(?Send)]
+    impl ToolExecutor for Editor {
+        fn definitions(&self) -> Vec<ToolDefinition> {
+            vec![ToolDefinition {
+                r#type: "function".to_string(),
+                function: FunctionDefinition {
+                    name: "edit".to_string(),
+                    description: None,
+                    parameters: json!({"type": "object"}),
+                },
+            }]
+        }
+
+        async fn call(&self, _name: &str, _arguments: Value) -> Result<String> {
+            self.bus.emit(BackendEvent::IntentDispatched {
+                category: "Editor".to_string(),
+                intent: "WriteFile src/lib.rs".to_string(),
+                error: None,
+            });
+            self.bus.emit(BackendEvent::ChangeCommitted {
+                thread_id: self.thread,
+                change_id: uuid::Uuid::new_v4(),
+                author_id: uuid::Uuid::new_v4(),
+                hash: "h".to_string(),
+                operations: 1,
+            });
+            // 其他线程上的变更不属于该 Routine
+            self.bus.emit(BackendEvent::ChangeCommitted {
+                thread_id: uuid::Uuid::new_v4(),
+                change_id: uuid::Uuid::new_v4(),
+                author_id: uuid::Uuid::new_v4(),
+                hash: "h".to_string(),
+                operations: 1,
+            });
+            Ok("edited".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trace_explains_why_an_edit_was_made() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let routine = Routine::new(uuid::Uuid::new_v4());
+        let trace = Arc::new(RoutineTrace::new(&routine).with_storage(storage.clone()));
+        let bus = Arc::new(EventBus::new());
+        bus.subscribe(trace.clone(), EventFilter::all());
+
+        let request = ChatMessage {
+            tool_calls: Some(vec![ToolCall {
+                id: "c1".to_string(),
+                r#type: "function".to_string(),
+                function: FunctionCall {
+                    name: "edit".to_string(),
+                    arguments: r#"{"path": "src/lib.rs"}"#.to_string(),
+                },
+            }]),
+            ..ChatMessage::text(MessageRole::Assistant, "The parser needs a fix")
+        };
+        let client = Arc::new(TracedClient::new(
+            Arc::new(
+                ScriptedClient::new("scripted")
+                    .with_usage(20, 5)
+                    .with_messages([request, ChatMessage::text(MessageRole::Assistant, "Fixed")]),
+            ),
+            trace.clone(),
+        ));
+        let tools = TracedTools::new(
+            Editor {
+                bus: bus.clone(),
+                thread: routine.active_thread,
+            },
+            trace.clone(),
+        );
+        let mut messages = vec![ChatMessage::text(MessageRole::User, "fix the parser")];
+        AgentLoop::new(client, "m")
+            .run(&mut messages, &tools)
+            .await
+            .unwrap();
+        bus.flush().await;
+
+        let all = trace.events(&TraceFilter::all());
+        let kinds: Vec<TraceKind> = all.iter().map(|e| e.event.kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                TraceKind::LlmCall,
+                TraceKind::ToolCall,
+                TraceKind::Intent,
+                TraceKind::ChangeCommitted,
+                TraceKind::LlmCall
+            ]
+        );
+
+        // 变更的因果链：模型决定 -> 工具调用 -> 变更
+        let change = &trace.events(&TraceFilter::only([TraceKind::ChangeCommitted]))[0];
+        let chain = trace.causes(change.id);
+        assert_eq!(chain.len(), 3);
+        assert!(matches!(
+            &chain[0].event,
+            TraceEventKind::LlmCall { reply, tool_calls, .. }
+                if reply == "The parser needs a fix" && tool_calls == &["edit"]
+        ));
+        assert!(matches!(
+            &chain[1].event,
+            TraceEventKind::ToolCall { arguments, .. } if arguments["path"] == "src/lib.rs"
+        ));
+        assert_eq!(
+            trace
+                .events(&TraceFilter::all().correlated(chain[0].id))
+                .len(),
+            4
+        );
+        let later = trace.events(&TraceFilter::all().since(all[4].timestamp));
+        assert_eq!(later.last().unwrap().id, all[4].id);
+
+        // 记录随 Routine 持久化，重启后可继续查询
+        let reopened = RoutineTrace::new(&routine).with_storage(storage);
+        assert_eq!(reopened.load().await.unwrap(), 5);
+        assert_eq!(reopened.causes(change.id), chain);
+    }
+}
@@ -0,0 +1,406 @@
+use crate::agent::manager::RoutineManager;
+use crate::agent::{Routine, RoutineId, RoutineStatus};
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use async_trait::async_trait;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// 携带请求体 HMAC-SHA256 签名的请求头，格式为 `sha256=<hex>`
+pub const SIGNATURE_HEADER: &str = "x-zhiyun-signature";
+
+/// 未指定目标 Thread 时使用的主线名称
+const DEFAULT_THREAD: &str = "main";
+
+/// 可由 Webhook 启动的 Routine 模板
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoutineTemplate {
+    pub name: String,
+    /// 任务提示词，`{{param}}` 占位符替换为参数值
+    pub prompt: String,
+    /// 参数名 -> 负载中的 JSON Pointer（如 `/build/branch`）
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+    /// 其值为目标 Thread 名称的参数；为空时在主线上启动
+    #[serde(default)]
+    pub thread_param: Option<String>,
+}
+
+impl RoutineTemplate {
+    /// 从负载中提取全部参数，缺少任一参数时报错
+    pub fn extract(&self, payload: &Value) -> Result<BTreeMap<String, String>, WebhookError> {
+        self.params
+            .iter()
+            .map(|(name, pointer)| {
+                let value =
+                    payload
+                        .pointer(pointer)
+                        .ok_or_else(|| WebhookError::MissingParameter {
+                            template: self.name.clone(),
+                            param: name.clone(),
+                        })?;
+                let value = match value {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                Ok((name.clone(), value))
+            })
+            .collect()
+    }
+
+    /// 用参数替换提示词中的占位符
+    pub fn render(&self, params: &BTreeMap<String, String>) -> String {
+        params
+            .iter()
+            .fold(self.prompt.clone(), |prompt, (name, value)| {
+                prompt.replace(&format!("{{{{{}}}}}", name), value)
+            })
+    }
+}
+
+/// 一次 Webhook 触发的结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TriggeredRoutine {
+    pub routine_id: RoutineId,
+    pub template: String,
+    /// 为 Routine 从目标 Thread 分叉出的工作 Thread
+    pub thread_id: ThreadId,
+    pub params: BTreeMap<String, String>,
+    pub prompt: String,
+}
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("Missing or invalid webhook signature")]
+    InvalidSignature,
+
+    #[error("Unknown routine template: {0}")]
+    UnknownTemplate(String),
+
+    #[error("Template '{template}' requires parameter '{param}'")]
+    MissingParameter { template: String, param: String },
+
+    #[error("Unknown thread: {0}")]
+    UnknownThread(String),
+
+    #[error("Invalid payload: {0}")]
+    InvalidPayload(String),
+
+    #[error("Failed to launch routine: {0}")]
+    Launch(String),
+}
+
+impl WebhookError {
+    /// 对应的 HTTP 状态码
+    pub fn status(&self) -> StatusCode {
+        match self {
+            WebhookError::InvalidSignature => StatusCode::UNAUTHORIZED,
+            WebhookError::UnknownTemplate(_) | WebhookError::UnknownThread(_) => {
+                StatusCode::NOT_FOUND
+            }
+            WebhookError::MissingParameter { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            WebhookError::InvalidPayload(_) => StatusCode::BAD_REQUEST,
+            WebhookError::Launch(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// 负责实际运行被触发的 Routine（如交给 Agent 执行器）
+#[async_trait]
+pub trait RoutineLauncher: Send + Sync {
+    async fn launch(&self, triggered: &TriggeredRoutine) -> anyhow::Result<()>;
+}
+
+/// 入站 Webhook：校验签名后按模板启动 Routine
+///
+/// 每次触发都会从目标 Thread 分叉出独立的工作 Thread，Routine 的修改不会直接落在目标分支上。
+pub struct WebhookTrigger {
+    secret: Vec<u8>,
+    templates: RwLock<HashMap<String, RoutineTemplate>>,
+    threads: Arc<ThreadManager>,
+    routines: Arc<RoutineManager>,
+    launcher: Option<Arc<dyn RoutineLauncher>>,
+}
+
+impl WebhookTrigger {
+    pub fn new(
+        secret: impl Into<Vec<u8>>,
+        threads: Arc<ThreadManager>,
+        routines: Arc<RoutineManager>,
+    ) -> Self {
+        Self {
+            secret: secret.into(),
+            templates: RwLock::new(HashMap::new()),
+            threads,
+            routines,
+            launcher: None,
+        }
+    }
+
+    pub fn with_launcher(mut self, launcher: Arc<dyn RoutineLauncher>) -> Self {
+        self.launcher = Some(launcher);
+        self
+    }
+
+    /// 注册（或替换）模板
+    pub fn register_template(&self, template: RoutineTemplate) {
+        self.templates
+            .write()
+            .unwrap()
+            .insert(template.name.clone(), template);
+    }
+
+    pub fn template(&self, name: &str) -> Option<RoutineTemplate> {
+        self.templates.read().unwrap().get(name).cloned()
+    }
+
+    /// 计算请求体签名（供调用方配置 CI 时使用）
+    pub fn sign(secret: &[u8], body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key length");
+        mac.update(body);
+        let digest: String = mac
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        format!("sha256={}", digest)
+    }
+
+    /// 以常量时间校验签名
+    pub fn verify(&self, signature: Option<&str>, body: &[u8]) -> Result<(), WebhookError> {
+        let expected = signature
+            .and_then(|s| s.strip_prefix("sha256="))
+            .and_then(decode_hex)
+            .ok_or(WebhookError::InvalidSignature)?;
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(body);
+        mac.verify_slice(&expected)
+            .map_err(|_| WebhookError::InvalidSignature)
+    }
+
+    /// 校验并解析请求体，然后按模板启动 Routine
+    pub async fn handle(
+        &self,
+        template: &str,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<TriggeredRoutine, WebhookError> {
+        self.verify(signature, body)?;
+        let payload: Value = serde_json::from_slice(body)
+            .map_err(|e| WebhookError::InvalidPayload(e.to_string()))?;
+        self.trigger(template, &payload).await
+    }
+
+    /// 按模板启动 Routine（不校验签名）
+    pub async fn trigger(
+        &self,
+        template: &str,
+        payload: &Value,
+    ) -> Result<TriggeredRoutine, WebhookError> {
+        let template = self
+            .template(template)
+            .ok_or_else(|| WebhookError::UnknownTemplate(template.to_string()))?;
+        let params = template.extract(payload)?;
+
+        let base_name = template
+            .thread_param
+            .as_ref()
+            .and_then(|p| params.get(p))
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_THREAD);
+        let base = self
+            .threads
+            .get_thread_id_by_name(base_name)
+            .ok_or_else(|| WebhookError::UnknownThread(base_name.to_string()))?;
+
+        let routine_id = Uuid::new_v4();
+        let branch = format!("webhook/{}/{}", template.name, routine_id.simple());
+        let thread_id = self
+            .threads
+            .create_branch(base, &branch)
+            .map_err(|e| WebhookError::Launch(e.to_string()))?;
+        let mut routine = Routine::new(thread_id);
+        routine.id = routine_id;
+        self.routines.register(routine);
+
+        let triggered = TriggeredRoutine {
+            routine_id,
+            template: template.name.clone(),
+            thread_id,
+            prompt: template.render(&params),
+            params,
+        };
+        if let Some(launcher) = &self.launcher
+            && let Err(e) = launcher.launch(&triggered).await
+        {
+            self.routines
+                .set_status(&routine_id, RoutineStatus::Failed(e.to_string()));
+            return Err(WebhookError::Launch(e.to_string()));
+        }
+        Ok(triggered)
+    }
+
+    /// `POST /hooks/{template}` 路由
+    pub fn router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route("/hooks/:template", post(receive))
+            .with_state(self)
+    }
+}
+
+async fn receive(
+    State(trigger): State<Arc<WebhookTrigger>>,
+    Path(template): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let signature = headers.get(SIGNATURE_HEADER).and_then(|v| v.to_str().ok());
+    match trigger.handle(&template, signature, &body).await {
+        Ok(triggered) => (StatusCode::ACCEPTED, Json(triggered)).into_response(),
+        Err(e) => (
+            e.status(),
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingLauncher {
+        launched: Mutex<Vec<TriggeredRoutine>>,
+    }
+
+    #[async_trait]
+    impl RoutineLauncher for RecordingLauncher {
+        async fn launch(&self, triggered: &TriggeredRoutine) -> anyhow::Result<()> {
+            self.launched.lock().unwrap().push(triggered.clone());
+            Ok(())
+        }
+    }
+
+    fn diagnose_template() -> RoutineTemplate {
+        RoutineTemplate {
+            name: "diagnose-build".to_string(),
+            prompt: "Diagnose the failing build {{build}} on {{branch}}".to_string(),
+            params: BTreeMap::from([
+                ("branch".to_string(), "/ref".to_string()),
+                ("build".to_string(), "/build/id".to_string()),
+            ]),
+            thread_param: Some("branch".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhook_starts_routine_on_forked_branch() {
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        threads.create_branch(main, "feature/login").unwrap();
+        let routines = Arc::new(RoutineManager::new());
+        let launcher = Arc::new(RecordingLauncher::default());
+        let trigger = Arc::new(
+            WebhookTrigger::new("s3cret", threads.clone(), routines.clone())
+                .with_launcher(launcher.clone()),
+        );
+        trigger.register_template(diagnose_template());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, trigger.router()).await.unwrap();
+        });
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/hooks/diagnose-build", addr);
+        let body = br#"{"ref":"feature/login","build":{"id":42}}"#.to_vec();
+
+        let unsigned = client.post(&url).body(body.clone()).send().await.unwrap();
+        assert_eq!(unsigned.status(), StatusCode::UNAUTHORIZED);
+
+        let response = client
+            .post(&url)
+            .header(SIGNATURE_HEADER, WebhookTrigger::sign(b"s3cret", &body))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let triggered: TriggeredRoutine = response.json().await.unwrap();
+        assert_eq!(
+            triggered.prompt,
+            "Diagnose the failing build 42 on feature/login"
+        );
+
+        let thread = threads.get_thread(triggered.thread_id).unwrap();
+        assert_eq!(
+            thread.parent_id,
+            threads.get_thread_id_by_name("feature/login")
+        );
+        let routine = routines.get(&triggered.routine_id).unwrap();
+        assert_eq!(routine.active_thread, triggered.thread_id);
+        assert_eq!(launcher.launched.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_rejects_bad_requests() {
+        let threads = Arc::new(ThreadManager::new());
+        let trigger = WebhookTrigger::new("key", threads, Arc::new(RoutineManager::new()));
+        trigger.register_template(diagnose_template());
+
+        let sign = |body: &[u8]| WebhookTrigger::sign(b"key", body);
+        let body = br#"{"ref":"main"}"#;
+        let error = trigger
+            .handle("diagnose-build", Some(&sign(body)), body)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error, WebhookError::MissingParameter { ref param, .. } if param == "build")
+        );
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = br#"{"ref":"nope","build":{"id":1}}"#;
+        assert!(matches!(
+            trigger
+                .handle("diagnose-build", Some(&sign(body)), body)
+                .await,
+            Err(WebhookError::UnknownThread(_))
+        ));
+        assert!(matches!(
+            trigger.handle("missing", Some(&sign(body)), body).await,
+            Err(WebhookError::UnknownTemplate(_))
+        ));
+        assert!(matches!(
+            trigger
+                .handle("diagnose-build", Some("sha256=00"), body)
+                .await,
+            Err(WebhookError::InvalidSignature)
+        ));
+    }
+}
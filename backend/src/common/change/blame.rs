@@ -0,0 +1,303 @@
+use crate::common::change::Change;
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::notebook::apply_cell_operation;
+use crate::common::change::operation::Operation;
+use crate::common::change::snapshot::edit_error;
+use crate::common::change::sparse::normalize;
+use crate::common::change::text::apply_text_operation;
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::change::threeway::line_edits;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 连续且来自同一 Change 的行：`[start_line, end_line)`（从 0 开始）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlameHunk {
+    pub start_line: usize,
+    pub end_line: usize,
+    /// 引入这些行的 Change
+    pub change_id: Uuid,
+    /// Change 的作者（用户或 Agent）
+    pub author_id: Uuid,
+    pub author_name: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 文件的逐行追溯结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileBlame {
+    pub path: String,
+    /// 追溯所基于的 Change
+    pub head: Uuid,
+    pub lines: Vec<String>,
+    pub hunks: Vec<BlameHunk>,
+}
+
+impl FileBlame {
+    /// 第 `line` 行（从 0 开始）所在的块
+    pub fn hunk_at(&self, line: usize) -> Option<&BlameHunk> {
+        self.hunks
+            .iter()
+            .find(|h| h.start_line <= line && line < h.end_line)
+    }
+}
+
+/// 某个 Change 应用后的文件状态
+struct Tracked {
+    /// 最后一次修改该文件的 Change 在因果顺序中的位置
+    writer: usize,
+    file: Option<TrackedFile>,
+}
+
+struct TrackedFile {
+    content: Vec<u8>,
+    lines: Vec<String>,
+    /// 每一行的来源 Change
+    origins: Vec<Uuid>,
+}
+
+/// 基于变更图的逐行追溯
+///
+/// 按因果顺序重放 Head 可达的 Change，对每个修改目标文件的 Change 与其父状态做行级差异：
+/// 保留的行沿用原来源，新增或改写的行归属该 Change。合并 Change 依次对照每个父状态，
+/// 因此从任一分支带入的行都保留其原始来源。不依赖外部 Git。
+pub struct Blame {
+    threads: Arc<ThreadManager>,
+}
+
+impl Blame {
+    pub fn new(threads: Arc<ThreadManager>) -> Self {
+        Self { threads }
+    }
+
+    /// 追溯线程 Head 处的文件
+    pub fn blame_thread(&self, thread_id: ThreadId, path: &str) -> anyhow::Result<FileBlame> {
+        let head = self
+            .threads
+            .get_thread(thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?
+            .head_change_id
+            .ok_or_else(|| anyhow::anyhow!("Thread has no changes"))?;
+        self.blame(head, path)
+    }
+
+    /// 追溯 `head` 应用后的文件
+    pub fn blame(&self, head: Uuid, path: &str) -> anyhow::Result<FileBlame> {
+        let target = normalize(path);
+        let history: Vec<Change> = self
+            .threads
+            .ancestors(head)
+            .into_iter()
+            .filter_map(|id| self.threads.get_change(id))
+            .collect();
+        if history.is_empty() {
+            return Err(anyhow::anyhow!("Change not found: {}", head));
+        }
+        let sorted = MergeEngine::new().sort_changes(history);
+
+        let mut states: HashMap<Uuid, Arc<Tracked>> = HashMap::new();
+        let mut changes: HashMap<Uuid, Change> = HashMap::new();
+        for (position, change) in sorted.into_iter().enumerate() {
+            // 按因果顺序物化时，文件内容取决于最后一个修改它的 Change
+            let mut parents: Vec<&Arc<Tracked>> = change
+                .parents
+                .iter()
+                .filter_map(|p| states.get(p))
+                .collect();
+            parents.sort_by_key(|s| std::cmp::Reverse(s.writer));
+            let inherited = parents.first().map(|s| (*s).clone());
+
+            let touches = change
+                .operations
+                .iter()
+                .any(|op| op.path().is_some_and(|p| normalize(p) == target));
+            let state = if touches {
+                let base = inherited
+                    .as_ref()
+                    .and_then(|s| s.file.as_ref())
+                    .map(|f| f.content.clone());
+                let content = apply(base, &change, target)?;
+                let candidates: Vec<&TrackedFile> =
+                    parents.iter().filter_map(|s| s.file.as_ref()).collect();
+                Arc::new(Tracked {
+                    writer: position,
+                    file: content.map(|content| attribute(content, &candidates, change.id)),
+                })
+            } else if let Some(inherited) = inherited {
+                inherited
+            } else {
+                continue;
+            };
+            states.insert(change.id, state);
+            changes.insert(change.id, change);
+        }
+
+        let file = states
+            .get(&head)
+            .and_then(|s| s.file.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("File not found at {}: {}", head, target))?;
+        let mut hunks: Vec<BlameHunk> = Vec::new();
+        for (line, origin) in file.origins.iter().enumerate() {
+            match hunks.last_mut() {
+                Some(hunk) if hunk.change_id == *origin => hunk.end_line = line + 1,
+                _ => {
+                    let change = &changes[origin];
+                    hunks.push(BlameHunk {
+                        start_line: line,
+                        end_line: line + 1,
+                        change_id: change.id,
+                        author_id: change.author_id,
+                        author_name: self.threads.author_name(change.author_id),
+                        timestamp: change.timestamp,
+                    });
+                }
+            }
+        }
+        Ok(FileBlame {
+            path: target.to_string(),
+            head,
+            lines: file.lines.clone(),
+            hunks,
+        })
+    }
+}
+
+/// 依次应用 Change 中作用于目标文件的操作
+fn apply(
+    mut content: Option<Vec<u8>>,
+    change: &Change,
+    target: &str,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    for op in &change.operations {
+        if op.path().is_none_or(|p| normalize(p) != target) {
+            continue;
+        }
+        match op {
+            Operation::FileWrite { content: c, .. } => content = Some(c.clone()),
+            Operation::FileDelete { .. } => content = None,
+            Operation::NotebookCell { path, op } => {
+                let current = content.as_deref().ok_or_else(|| {
+                    edit_error(path, change, anyhow::anyhow!("file does not exist"))
+                })?;
+                content = Some(
+                    apply_cell_operation(current, op).map_err(|e| edit_error(path, change, e))?,
+                );
+            }
+            Operation::TextEdit { path, op } => {
+                let current = content.as_deref().ok_or_else(|| {
+                    edit_error(path, change, anyhow::anyhow!("file does not exist"))
+                })?;
+                content = Some(
+                    apply_text_operation(current, op).map_err(|e| edit_error(path, change, e))?,
+                );
+            }
+            Operation::FileWriteRef { blob, .. } => {
+                return Err(anyhow::anyhow!(
+                    "Change {} references blob {}; hydrate it before blaming",
+                    change.id,
+                    blob
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(content)
+}
+
+/// 按候选父状态的优先级为每一行确定来源，未能匹配的行归属 `change_id`
+fn attribute(content: Vec<u8>, candidates: &[&TrackedFile], change_id: Uuid) -> TrackedFile {
+    let lines: Vec<String> = String::from_utf8_lossy(&content)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let new: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let mut origins: Vec<Option<Uuid>> = vec![None; lines.len()];
+    for candidate in candidates {
+        let old: Vec<&str> = candidate.lines.iter().map(String::as_str).collect();
+        let (mut from, mut to) = (0, 0);
+        let mut keep = |from: usize, until: usize, to: usize| {
+            for (offset, line) in (from..until).enumerate() {
+                origins[to + offset].get_or_insert(candidate.origins[line]);
+            }
+        };
+        for edit in line_edits(&old, &new) {
+            keep(from, edit.start, to);
+            to += edit.start - from + edit.lines.len();
+            from = edit.end;
+        }
+        keep(from, old.len(), to);
+    }
+    TrackedFile {
+        content,
+        lines,
+        origins: origins
+            .into_iter()
+            .map(|o| o.unwrap_or(change_id))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(text: &str) -> Vec<Operation> {
+        vec![Operation::file_write("src/lib.rs".to_string(), text.into())]
+    }
+
+    #[test]
+    fn test_blame_tracks_lines_across_edits_and_merges() {
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let (user, agent) = (Uuid::new_v4(), Uuid::new_v4());
+        threads.set_author_name(agent, "refactor-agent");
+
+        let base = threads
+            .commit_operations(main, user, write("fn a() {}\nfn b() {}\n"))
+            .unwrap();
+        let branch = threads.create_branch(main, "agent").unwrap();
+        let agent_edit = threads
+            .commit_operations(branch, agent, write("fn a() {}\nfn b() {}\nfn c() {}\n"))
+            .unwrap();
+        let user_edit = threads
+            .commit_operations(main, user, write("// doc\nfn a() {}\nfn b() {}\n"))
+            .unwrap();
+
+        // 合并 Change 写入双方的结果，行来源应保留各自的原始 Change
+        let mut clock = threads.get_change(user_edit).unwrap().version;
+        clock.merge(&threads.get_change(agent_edit).unwrap().version);
+        clock.increment(user);
+        let merge = Change::new(
+            user,
+            vec![Operation::file_write(
+                "src/lib.rs".to_string(),
+                b"// doc\nfn a() {}\nfn b() {}\nfn c() {}\n".to_vec(),
+            )],
+            clock,
+            vec![user_edit, agent_edit],
+        );
+        threads.commit_change(main, merge).unwrap();
+
+        let blame = Blame::new(threads.clone())
+            .blame_thread(main, "./src/lib.rs")
+            .unwrap();
+        assert_eq!(blame.lines.len(), 4);
+        let origins: Vec<Uuid> = (0..4)
+            .map(|l| blame.hunk_at(l).unwrap().change_id)
+            .collect();
+        assert_eq!(origins, vec![user_edit, base, base, agent_edit]);
+        assert_eq!(blame.hunks.len(), 3);
+        assert_eq!(
+            blame.hunk_at(3).unwrap().author_name.as_deref(),
+            Some("refactor-agent")
+        );
+
+        // 基于较早的 Change 追溯
+        let earlier = Blame::new(threads).blame(agent_edit, "src/lib.rs").unwrap();
+        assert_eq!(earlier.hunks[0].change_id, base);
+        assert_eq!(earlier.hunks[0].end_line, 2);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::common::change::Change;
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::notebook::apply_cell_operation;
+use crate::common::change::operation::Operation;
+use crate::common::change::snapshot::edit_error;
+use crate::common::change::sparse::normalize;
+use crate::common::change::text::apply_text_operation;
+use crate::common::provider::traits::StorageProvider;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+/// 内容哈希（SHA-256 十六进制）
+pub type BlobHash = String;
+
+/// Blob 存储统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlobStats {
+    /// 唯一 Blob 数量
+    pub blobs: usize,
+    /// 实际存储的字节数
+    pub stored_bytes: u64,
+    /// 因去重而未重复写入的字节数
+    pub deduplicated_bytes: u64,
+}
+
+/// 物化到目录的统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MaterializeStats {
+    pub files: usize,
+    /// 从 Blob 直接复制的字节数（未经进程内存）
+    pub copied_bytes: u64,
+    /// 由内联内容写入的字节数
+    pub written_bytes: u64,
+}
+
+/// 物化过程中文件的当前内容来源
+enum FileSource {
+    Inline(Vec<u8>),
+    Blob(BlobHash),
+}
+
+/// 内容寻址的 Blob 存储
+///
+/// 文件内容按哈希存放一次，`Operation::FileWriteRef` 仅引用哈希，
+/// 使相同内容在不同线程、不同变动之间共享，显著缩小持久化的变更日志。
+pub struct BlobStore {
+    storage: Arc<dyn StorageProvider>,
+    root: String,
+    /// 已知 Blob 的哈希 -> 大小
+    index: RwLock<HashMap<BlobHash, u64>>,
+    deduplicated_bytes: RwLock<u64>,
+}
+
+impl BlobStore {
+    pub fn new(storage: Arc<dyn StorageProvider>, root: &str) -> Self {
+        Self {
+            storage,
+            root: root.trim_end_matches('/').to_string(),
+            index: RwLock::new(HashMap::new()),
+            deduplicated_bytes: RwLock::new(0),
+        }
+    }
+
+    /// 计算内容哈希
+    pub fn hash(content: &[u8]) -> BlobHash {
+        format!("{:x}", Sha256::digest(content))
+    }
+
+    /// 写入内容并返回其哈希；内容已存在时不会重复写入
+    pub async fn put(&self, content: &[u8]) -> anyhow::Result<BlobHash> {
+        let hash = Self::hash(content);
+        if self.contains(&hash).await? {
+            *self.deduplicated_bytes.write().unwrap() += content.len() as u64;
+            return Ok(hash);
+        }
+        // 预写日志与变更图记录引用 Blob，Blob 必须与引用它的记录同样持久
+        self.storage
+            .write_file_durable(&self.blob_path(&hash), content)
+            .await?;
+        self.index
+            .write()
+            .unwrap()
+            .insert(hash.clone(), content.len() as u64);
+        Ok(hash)
+    }
+
+    /// 将已有文件存入 Blob 存储而不读入内存（哈希在阻塞线程池中分块流式计算）
+    pub async fn put_file(&self, path: &str) -> anyhow::Result<BlobHash> {
+        let hash = self.storage.hash_file(path).await?;
+        let size = self.storage.get_metadata(path).await?.size;
+        if self.contains(&hash).await? {
+            *self.deduplicated_bytes.write().unwrap() += size;
+            return Ok(hash);
+        }
+        self.storage.copy_file(path, &self.blob_path(&hash)).await?;
+        self.index.write().unwrap().insert(hash.clone(), size);
+        Ok(hash)
+    }
+
+    /// 校验已存储 Blob 的完整性而不读入内容
+    pub async fn verify(&self, hash: &str) -> anyhow::Result<bool> {
+        Ok(self.storage.hash_file(&self.blob_path(hash)).await? == hash)
+    }
+
+    /// 读取指定哈希的内容，并校验完整性
+    pub async fn get(&self, hash: &str) -> anyhow::Result<Vec<u8>> {
+        let content = self.storage.read_file(&self.blob_path(hash)).await?;
+        if Self::hash(&content) != hash {
+            return Err(anyhow::anyhow!("Blob integrity check failed: {}", hash));
+        }
+        Ok(content)
+    }
+
+    /// 是否已存储指定哈希
+    pub async fn contains(&self, hash: &str) -> anyhow::Result<bool> {
+        if self.index.read().unwrap().contains_key(hash) {
+            return Ok(true);
+        }
+        let path = self.blob_path(hash);
+        if self.storage.exists(&path).await? {
+            let size = self.storage.get_metadata(&path).await?.size;
+            self.index.write().unwrap().insert(hash.to_string(), size);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// 获取统计信息
+    pub fn stats(&self) -> BlobStats {
+        let index = self.index.read().unwrap();
+        BlobStats {
+            blobs: index.len(),
+            stored_bytes: index.values().sum(),
+            deduplicated_bytes: *self.deduplicated_bytes.read().unwrap(),
+        }
+    }
+
+    /// 将变动中的内联文件内容写入 Blob 存储，替换为哈希引用（用于持久化）
+    ///
+    /// 返回的变动保留原始 `hash`，需经 [`BlobStore::hydrate`] 还原后再校验。
+    pub async fn externalize(&self, change: &Change) -> anyhow::Result<Change> {
+        let mut externalized = change.clone();
+        for op in &mut externalized.operations {
+            if let Operation::FileWrite { path, content } = op {
+                let blob = self.put(content).await?;
+                *op = Operation::FileWriteRef {
+                    path: std::mem::take(path),
+                    blob,
+                };
+            }
+        }
+        Ok(externalized)
+    }
+
+    /// 将变动中的哈希引用还原为内联文件内容
+    pub async fn hydrate(&self, change: &Change) -> anyhow::Result<Change> {
+        let mut hydrated = change.clone();
+        for op in &mut hydrated.operations {
+            if let Operation::FileWriteRef { path, blob } = op {
+                let content = self.get(blob).await?;
+                *op = Operation::FileWrite {
+                    path: std::mem::take(path),
+                    content,
+                };
+            }
+        }
+        Ok(hydrated)
+    }
+
+    /// 将变动序列物化到目录（如 Agent 构建使用的影子目录）
+    ///
+    /// 引用 Blob 的文件直接从存储复制到目标目录，不读入进程内存；
+    /// 只有内联内容与需要改写的 Notebook 单元格操作在内存中处理。
+    pub async fn materialize_to(
+        &self,
+        changes: &[Change],
+        dir: &str,
+    ) -> anyhow::Result<MaterializeStats> {
+        let mut files: BTreeMap<String, FileSource> = BTreeMap::new();
+        for mut change in MergeEngine::new().sort_changes(changes.to_vec()) {
+            for op in std::mem::take(&mut change.operations) {
+                match op {
+                    Operation::FileWrite { path, content } => {
+                        files.insert(normalize(&path).to_string(), FileSource::Inline(content));
+                    }
+                    Operation::FileWriteRef { path, blob } => {
+                        files.insert(normalize(&path).to_string(), FileSource::Blob(blob));
+                    }
+                    Operation::FileDelete { path } => {
+                        files.remove(normalize(&path));
+                    }
+                    Operation::NotebookCell { .. } | Operation::TextEdit { .. } => {
+                        let key = normalize(op.path().unwrap_or_default()).to_string();
+                        let content = match files.get(&key) {
+                            Some(FileSource::Inline(content)) => content.clone(),
+                            Some(FileSource::Blob(blob)) => self.get(blob).await?,
+                            None => {
+                                return Err(edit_error(
+                                    &key,
+                                    &change,
+                                    anyhow::anyhow!("file does not exist"),
+                                ));
+                            }
+                        };
+                        let updated = match &op {
+                            Operation::NotebookCell { op, .. } => {
+                                apply_cell_operation(&content, op)
+                            }
+                            Operation::TextEdit { op, .. } => apply_text_operation(&content, op),
+                            _ => unreachable!(),
+                        }
+                        .map_err(|e| edit_error(&key, &change, e))?;
+                        files.insert(key, FileSource::Inline(updated));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let dir = dir.trim_end_matches('/');
+        let mut stats = MaterializeStats::default();
+        for (path, source) in files {
+            let target = format!("{}/{}", dir, path);
+            match source {
+                FileSource::Inline(content) => {
+                    self.storage.write_file(&target, &content).await?;
+                    stats.written_bytes += content.len() as u64;
+                }
+                FileSource::Blob(blob) => {
+                    stats.copied_bytes += self
+                        .storage
+                        .copy_file(&self.blob_path(&blob), &target)
+                        .await?;
+                }
+            }
+            stats.files += 1;
+        }
+        Ok(stats)
+    }
+
+    fn blob_path(&self, hash: &str) -> String {
+        let (prefix, rest) = hash.split_at(hash.len().min(2));
+        format!("{}/{}/{}", self.root, prefix, rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::version::VectorClock;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use tempfile::tempdir;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_blob_store_deduplicates_across_changes() {
+        let dir = tempdir().unwrap();
+        let store = BlobStore::new(Arc::new(LocalFileSystem::new(dir.path())), "blobs");
+        let content = b"fn main() {}".to_vec();
+
+        let c1 = Change::new(
+            Uuid::new_v4(),
+            vec![Operation::file_write("a.rs".to_string(), content.clone())],
+            VectorClock::new(),
+            vec![],
+        );
+        let c2 = Change::new(
+            Uuid::new_v4(),
+            vec![Operation::file_write("b.rs".to_string(), content.clone())],
+            VectorClock::new(),
+            vec![],
+        );
+
+        let e1 = store.externalize(&c1).await.unwrap();
+        store.externalize(&c2).await.unwrap();
+
+        let stats = store.stats();
+        assert_eq!(stats.blobs, 1);
+        assert_eq!(stats.stored_bytes, content.len() as u64);
+        assert_eq!(stats.deduplicated_bytes, content.len() as u64);
+
+        match &e1.operations[0] {
+            Operation::FileWriteRef { blob, .. } => assert_eq!(blob, &BlobStore::hash(&content)),
+            other => panic!("Expected FileWriteRef, got {:?}", other),
+        }
+
+        let hydrated = store.hydrate(&e1).await.unwrap();
+        assert_eq!(hydrated, c1);
+        assert!(hydrated.verify_hash());
+    }
+
+    #[tokio::test]
+    async fn test_materialize_to_copies_blobs() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        let store = BlobStore::new(storage.clone(), "blobs");
+        storage
+            .write_file("build/out.bin", &[7u8; 4096])
+            .await
+            .unwrap();
+        let hash = store.put_file("build/out.bin").await.unwrap();
+        assert_eq!(hash, BlobStore::hash(&[7u8; 4096]));
+        assert!(store.verify(&hash).await.unwrap());
+
+        let base = Change::new(
+            Uuid::new_v4(),
+            vec![
+                Operation::file_write("src/lib.rs".to_string(), b"pub fn a() {}".to_vec()),
+                Operation::file_write("old.txt".to_string(), b"old".to_vec()),
+            ],
+            VectorClock::new(),
+            vec![],
+        );
+        let base = store.externalize(&base).await.unwrap();
+        let next = Change::new(
+            Uuid::new_v4(),
+            vec![
+                Operation::FileWriteRef {
+                    path: "./assets/out.bin".to_string(),
+                    blob: hash,
+                },
+                Operation::file_delete("old.txt".to_string()),
+                Operation::file_write("README.md".to_string(), b"# demo".to_vec()),
+            ],
+            VectorClock::new(),
+            vec![base.id],
+        );
+
+        let stats = store
+            .materialize_to(&[next, base], "shadow/")
+            .await
+            .unwrap();
+        assert_eq!(stats.files, 3);
+        assert_eq!(stats.copied_bytes, 4096 + 13);
+        assert_eq!(stats.written_bytes, 6);
+        assert_eq!(
+            storage.read_file("shadow/src/lib.rs").await.unwrap(),
+            b"pub fn a() {}"
+        );
+        assert_eq!(
+            storage.read_file("shadow/assets/out.bin").await.unwrap(),
+            vec![7u8; 4096]
+        );
+        assert!(!storage.exists("shadow/old.txt").await.unwrap());
+    }
+}
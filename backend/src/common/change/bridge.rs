@@ -0,0 +1,567 @@
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::snapshot::{apply_files, materialize_files};
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::change::{Change, Operation, VectorClock};
+use crate::common::provider::traits::{ExecuteOptions, ExecutionProvider, StorageProvider};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 桥接状态文件（位于 `.git` 内，不会被提交）
+const STATE_PATH: &str = ".git/zhiyun-bridge.json";
+const MESSAGE_PATH: &str = ".git/ZHIYUN_EXPORT_MSG";
+/// 导入时检出历史文件内容的临时目录与索引
+const SCRATCH_DIR: &str = ".git/zhiyun-import/";
+const SCRATCH_INDEX: &str = ".git/zhiyun-import.index";
+/// 导出提交的作者邮箱域名，导入时据此还原作者 ID
+pub(crate) const AUTHOR_DOMAIN: &str = "zhiyun.local";
+
+/// 导出粒度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportMode {
+    /// 每个 Change 一个提交
+    #[default]
+    PerChange,
+    /// 本次导出的全部 Change 合为一个提交（如每次保存时导出）
+    Squash,
+}
+
+/// 导出产生的一个 Git 提交
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedCommit {
+    pub sha: String,
+    pub changes: Vec<Uuid>,
+    pub written: usize,
+    pub deleted: usize,
+}
+
+/// 打包产生的 Git bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedBundle {
+    /// bundle 文件路径（相对仓库根目录）
+    pub path: String,
+    /// 线程分叉点对应的提交，bundle 以其为前提；主线为 `None`，包含完整历史
+    pub base: Option<String>,
+    pub commits: usize,
+}
+
+/// 导入产生的一个 Change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportedCommit {
+    pub sha: String,
+    pub change_id: Uuid,
+    pub operations: usize,
+}
+
+/// Change 与 Git 提交的对应关系
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BridgeState {
+    commits: BTreeMap<Uuid, String>,
+}
+
+impl BridgeState {
+    fn has_commit(&self, sha: &str) -> bool {
+        self.commits.values().any(|s| s == sha)
+    }
+}
+
+/// 线程历史与项目 Git 仓库之间的双向桥接
+///
+/// 导出时按因果顺序物化尚未导出的 Change，把文件差异写入工作区后提交；
+/// 导入时把仓库第一父链上尚未对应 Change 的外部提交转换为文件写入/删除操作并提交到线程。
+/// 两个方向共用同一份 Change ↔ 提交映射，已导出的提交不会被再次导入，反之亦然。
+///
+/// 命令经 `ExecutionProvider` 按空白拆分执行，因此不支持包含空白字符的路径。
+pub struct GitBridge {
+    storage: Arc<dyn StorageProvider>,
+    runner: Arc<dyn ExecutionProvider>,
+    /// 仓库根目录（执行 git 命令的工作目录，`storage` 的路径相对于它）
+    repo_dir: String,
+}
+
+impl GitBridge {
+    pub fn new(
+        storage: Arc<dyn StorageProvider>,
+        runner: Arc<dyn ExecutionProvider>,
+        repo_dir: &str,
+    ) -> Self {
+        Self {
+            storage,
+            runner,
+            repo_dir: repo_dir.to_string(),
+        }
+    }
+
+    /// 已导出或导入的 Change 对应的提交
+    pub async fn commit_of(&self, change_id: Uuid) -> anyhow::Result<Option<String>> {
+        Ok(self.load_state().await?.commits.get(&change_id).cloned())
+    }
+
+    /// 将线程中尚未导出的 Change 导出为 Git 提交
+    pub async fn export_thread(
+        &self,
+        threads: &ThreadManager,
+        thread_id: ThreadId,
+        mode: ExportMode,
+    ) -> anyhow::Result<Vec<ExportedCommit>> {
+        let thread = threads
+            .get_thread(thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+        let Some(head) = thread.head_change_id else {
+            return Ok(Vec::new());
+        };
+        let mut state = self.load_state().await?;
+        let history: Vec<Change> = threads
+            .ancestors(head)
+            .into_iter()
+            .filter_map(|id| threads.get_change(id))
+            .collect();
+        let (done, pending): (Vec<Change>, Vec<Change>) = MergeEngine::new()
+            .sort_changes(history)
+            .into_iter()
+            .partition(|c| state.commits.contains_key(&c.id));
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let groups: Vec<Vec<Change>> = match mode {
+            ExportMode::PerChange => pending.into_iter().map(|c| vec![c]).collect(),
+            ExportMode::Squash => vec![pending],
+        };
+        let mut previous = materialize_files(&done)?;
+        let mut exported = Vec::new();
+        for group in groups {
+            // 在上一个提交的文件树上增量应用本组操作，避免每次从头重放历史
+            let mut tree = previous.clone();
+            apply_files(&mut tree, &group, |_| true)?;
+            let (written, deleted) = self.write_tree(&previous, &tree).await?;
+            let sha = self.commit(&group).await?;
+            for change in &group {
+                state.commits.insert(change.id, sha.clone());
+            }
+            // 每个提交后立即保存映射，中途失败时已完成的提交不会被重复导出
+            self.save_state(&state).await?;
+            exported.push(ExportedCommit {
+                sha,
+                changes: group.iter().map(|c| c.id).collect(),
+                written,
+                deleted,
+            });
+            previous = tree;
+        }
+        Ok(exported)
+    }
+
+    /// 导出线程后将分叉点之后的提交打包为 Git bundle（`git bundle create`）
+    ///
+    /// 接收方在包含分叉点提交的仓库中 `git fetch <bundle>` 即可取得线程的工作。
+    pub async fn bundle(
+        &self,
+        threads: &ThreadManager,
+        thread_id: ThreadId,
+        output: &str,
+    ) -> anyhow::Result<ExportedBundle> {
+        check_path(output)?;
+        self.export_thread(threads, thread_id, ExportMode::PerChange)
+            .await?;
+        let thread = threads
+            .get_thread(thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+        let base = match thread.fork_point {
+            Some(fork) => self.commit_of(fork).await?,
+            None => None,
+        };
+        let range = match &base {
+            Some(sha) => format!("{}..HEAD", sha),
+            None => "HEAD".to_string(),
+        };
+        let commits = self.git(&["rev-list", "--count", &range]).await?;
+        self.git(&["bundle", "create", "-q", output, &range])
+            .await?;
+        Ok(ExportedBundle {
+            path: output.to_string(),
+            base,
+            commits: commits.trim().parse()?,
+        })
+    }
+
+    /// 将仓库 HEAD 第一父链上的外部提交导入为线程中的 Change
+    pub async fn import_commits(
+        &self,
+        threads: &ThreadManager,
+        thread_id: ThreadId,
+    ) -> anyhow::Result<Vec<ImportedCommit>> {
+        let mut state = self.load_state().await?;
+        let Ok(log) = self
+            .git(&[
+                "rev-list",
+                "--reverse",
+                "--first-parent",
+                "--parents",
+                "HEAD",
+            ])
+            .await
+        else {
+            // 仓库尚无提交
+            return Ok(Vec::new());
+        };
+
+        let mut imported = Vec::new();
+        for line in log.lines() {
+            let mut shas = line.split_whitespace();
+            let Some(sha) = shas.next() else { continue };
+            if state.has_commit(sha) {
+                continue;
+            }
+            let operations = self.commit_operations(sha, shas.next()).await?;
+            let (author_id, timestamp) = self.commit_author(sha).await?;
+
+            let head = threads
+                .get_thread(thread_id)
+                .ok_or_else(|| anyhow::anyhow!("Thread not found"))?
+                .head_change_id;
+            let mut version = head
+                .and_then(|id| threads.get_change(id))
+                .map(|c| c.version)
+                .unwrap_or_else(VectorClock::new);
+            version.increment(author_id);
+            let mut change =
+                Change::new(author_id, operations, version, head.into_iter().collect());
+            change.timestamp = timestamp;
+            change.hash = change.calculate_hash();
+
+            let record = ImportedCommit {
+                sha: sha.to_string(),
+                change_id: change.id,
+                operations: change.operations.len(),
+            };
+            threads.commit_change(thread_id, change)?;
+            state.commits.insert(record.change_id, record.sha.clone());
+            self.save_state(&state).await?;
+            imported.push(record);
+        }
+        let _ = self.storage.delete(SCRATCH_DIR, true).await;
+        let _ = self.storage.delete(SCRATCH_INDEX, false).await;
+        Ok(imported)
+    }
+
+    /// 把两次物化之间的差异写入工作区并暂存，返回写入与删除的文件数
+    async fn write_tree(
+        &self,
+        previous: &BTreeMap<String, Vec<u8>>,
+        tree: &BTreeMap<String, Vec<u8>>,
+    ) -> anyhow::Result<(usize, usize)> {
+        let written: Vec<&String> = tree
+            .iter()
+            .filter(|(path, content)| previous.get(*path) != Some(content))
+            .map(|(path, _)| path)
+            .collect();
+        let deleted: Vec<&String> = previous.keys().filter(|p| !tree.contains_key(*p)).collect();
+        for path in written.iter().chain(&deleted) {
+            check_path(path)?;
+        }
+
+        for path in &written {
+            self.storage.write_file(path, &tree[*path]).await?;
+        }
+        for path in &deleted {
+            if self.storage.exists(path).await? {
+                self.storage.delete(path, false).await?;
+            }
+        }
+        let paths: Vec<&str> = written.iter().chain(&deleted).map(|p| p.as_str()).collect();
+        if !paths.is_empty() {
+            let mut args = vec!["add", "-A", "--"];
+            args.extend(paths);
+            self.git(&args).await?;
+        }
+        Ok((written.len(), deleted.len()))
+    }
+
+    /// 以组内最后一个 Change 的作者与时间提交暂存区，返回提交哈希
+    async fn commit(&self, group: &[Change]) -> anyhow::Result<String> {
+        let last = group.last().expect("export group is never empty");
+        let mut message = match group {
+            [single] => format!("Zhiyun change {}\n\n", single.id),
+            _ => format!("Zhiyun: {} changes\n\n", group.len()),
+        };
+        for change in group {
+            message.push_str(&format!("Zhiyun-Change: {}\n", change.id));
+        }
+        self.storage
+            .write_file(MESSAGE_PATH, message.as_bytes())
+            .await?;
+
+        let name = last.author_id.to_string();
+        let email = format!("{}@{}", last.author_id, AUTHOR_DOMAIN);
+        let date = last.timestamp.to_rfc3339();
+        let mut env = HashMap::new();
+        for role in ["AUTHOR", "COMMITTER"] {
+            env.insert(format!("GIT_{}_NAME", role), name.clone());
+            env.insert(format!("GIT_{}_EMAIL", role), email.clone());
+            env.insert(format!("GIT_{}_DATE", role), date.clone());
+        }
+        self.git_with_env(
+            &[
+                "commit",
+                "-q",
+                "--allow-empty",
+                "--no-verify",
+                "-F",
+                MESSAGE_PATH,
+            ],
+            env,
+        )
+        .await?;
+        Ok(self.git(&["rev-parse", "HEAD"]).await?.trim().to_string())
+    }
+
+    /// 提交相对第一父提交的文件变化
+    async fn commit_operations(
+        &self,
+        sha: &str,
+        parent: Option<&str>,
+    ) -> anyhow::Result<Vec<Operation>> {
+        let mut args = vec![
+            "diff-tree",
+            "-r",
+            "--no-renames",
+            "--no-commit-id",
+            "--name-status",
+        ];
+        match parent {
+            Some(parent) => args.extend([parent, sha]),
+            None => args.extend(["--root", sha]),
+        }
+        let diff = self.git(&args).await?;
+
+        let mut written = Vec::new();
+        let mut operations = Vec::new();
+        for line in diff.lines() {
+            let Some((status, path)) = line.split_once('\t') else {
+                continue;
+            };
+            check_path(path)?;
+            if status.starts_with('D') {
+                operations.push(Operation::file_delete(path.to_string()));
+            } else {
+                written.push(path.to_string());
+            }
+        }
+        if written.is_empty() {
+            return Ok(operations);
+        }
+
+        // 在独立索引中读取提交的树，把需要的文件检出到临时目录后读取原始字节
+        let env = HashMap::from([("GIT_INDEX_FILE".to_string(), SCRATCH_INDEX.to_string())]);
+        self.git_with_env(&["read-tree", sha], env.clone()).await?;
+        let prefix = format!("--prefix={}", SCRATCH_DIR);
+        let mut args = vec!["checkout-index", "-f", prefix.as_str(), "--"];
+        args.extend(written.iter().map(|p| p.as_str()));
+        self.git_with_env(&args, env).await?;
+        for path in written {
+            let content = self
+                .storage
+                .read_file(&format!("{}{}", SCRATCH_DIR, path))
+                .await?;
+            operations.push(Operation::file_write(path, content));
+        }
+        Ok(operations)
+    }
+
+    /// 提交的作者 ID 与时间；由桥接导出的提交还原原作者，其他作者按邮箱生成稳定 ID
+    async fn commit_author(&self, sha: &str) -> anyhow::Result<(Uuid, DateTime<Utc>)> {
+        let output = self
+            .git(&["show", "-s", "--format=%ae%x09%at", sha])
+            .await?;
+        let (email, seconds) = output
+            .trim()
+            .split_once('\t')
+            .ok_or_else(|| anyhow::anyhow!("Unexpected git show output: {}", output.trim()))?;
+        let author_id = email
+            .strip_suffix(&format!("@{}", AUTHOR_DOMAIN))
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .unwrap_or_else(|| author_from_email(email));
+        let timestamp = Utc
+            .timestamp_opt(seconds.parse()?, 0)
+            .single()
+            .unwrap_or_else(Utc::now);
+        Ok((author_id, timestamp))
+    }
+
+    async fn git(&self, args: &[&str]) -> anyhow::Result<String> {
+        self.git_with_env(args, HashMap::new()).await
+    }
+
+    async fn git_with_env(
+        &self,
+        args: &[&str],
+        env: HashMap<String, String>,
+    ) -> anyhow::Result<String> {
+        let command = format!("git {}", args.join(" "));
+        let result = self
+            .runner
+            .execute(
+                &command,
+                ExecuteOptions {
+                    cwd: Some(self.repo_dir.clone()),
+                    env,
+                    timeout_ms: None,
+                },
+            )
+            .await?;
+        if result.exit_code != 0 {
+            return Err(anyhow::anyhow!(
+                "git {} failed: {}",
+                args.first().copied().unwrap_or_default(),
+                result.stderr.trim()
+            ));
+        }
+        Ok(result.stdout)
+    }
+
+    async fn load_state(&self) -> anyhow::Result<BridgeState> {
+        if !self.storage.exists(STATE_PATH).await? {
+            return Ok(BridgeState::default());
+        }
+        Ok(serde_json::from_slice(
+            &self.storage.read_file(STATE_PATH).await?,
+        )?)
+    }
+
+    async fn save_state(&self, state: &BridgeState) -> anyhow::Result<()> {
+        self.storage
+            .write_file(STATE_PATH, &serde_json::to_vec_pretty(state)?)
+            .await
+    }
+}
+
+/// 外部作者的稳定 ID：邮箱 SHA-256 的前 16 字节
+fn author_from_email(email: &str) -> Uuid {
+    let digest = Sha256::digest(email.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_custom_bytes(bytes).into_uuid()
+}
+
+fn check_path(path: &str) -> anyhow::Result<()> {
+    if path.chars().any(char::is_whitespace) {
+        return Err(anyhow::anyhow!(
+            "Paths containing whitespace are not supported by the git bridge: {}",
+            path
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::common::provider::local::process::LocalProcess;
+
+    #[tokio::test]
+    async fn test_export_and_import_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_str().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let bridge = GitBridge::new(storage.clone(), Arc::new(LocalProcess), repo);
+        bridge.git(&["init", "-q"]).await.unwrap();
+
+        let threads = ThreadManager::new();
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let first = threads.commit_test(
+            main,
+            vec![Operation::file_write("src/a.txt".into(), b"a1".to_vec())],
+        );
+        threads.commit_test(
+            main,
+            vec![Operation::file_write("b.txt".into(), b"b1".to_vec())],
+        );
+
+        let exported = bridge
+            .export_thread(&threads, main, ExportMode::PerChange)
+            .await
+            .unwrap();
+        assert_eq!(exported.len(), 2);
+        assert_eq!(
+            bridge.commit_of(first).await.unwrap(),
+            Some(exported[0].sha.clone())
+        );
+        assert_eq!(storage.read_file("src/a.txt").await.unwrap(), b"a1");
+
+        // 两个 Change 合并为一个提交；删除也会同步到工作区
+        threads.commit_test(main, vec![Operation::file_delete("b.txt".into())]);
+        threads.commit_test(
+            main,
+            vec![Operation::file_write("src/a.txt".into(), b"a2".to_vec())],
+        );
+        let squashed = bridge
+            .export_thread(&threads, main, ExportMode::Squash)
+            .await
+            .unwrap();
+        assert_eq!(squashed.len(), 1);
+        assert_eq!((squashed[0].written, squashed[0].deleted), (1, 1));
+        assert!(!storage.exists("b.txt").await.unwrap());
+
+        // 已导出的提交不会被导入
+        assert!(
+            bridge
+                .import_commits(&threads, main)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // 外部提交：新增二进制文件并删除已有文件
+        storage
+            .write_file("img.bin", &[0, 159, 146, 150])
+            .await
+            .unwrap();
+        storage.delete("src/a.txt", false).await.unwrap();
+        bridge.git(&["add", "-A"]).await.unwrap();
+        let env = HashMap::from([
+            ("GIT_AUTHOR_NAME".to_string(), "dev".to_string()),
+            (
+                "GIT_AUTHOR_EMAIL".to_string(),
+                "dev@example.com".to_string(),
+            ),
+            ("GIT_COMMITTER_NAME".to_string(), "dev".to_string()),
+            (
+                "GIT_COMMITTER_EMAIL".to_string(),
+                "dev@example.com".to_string(),
+            ),
+        ]);
+        bridge
+            .git_with_env(&["commit", "-q", "-m", "external"], env)
+            .await
+            .unwrap();
+
+        let imported = bridge.import_commits(&threads, main).await.unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].operations, 2);
+        let change = threads.get_change(imported[0].change_id).unwrap();
+        assert_eq!(change.author_id, author_from_email("dev@example.com"));
+        let history: Vec<Change> = threads
+            .ancestors(change.id)
+            .into_iter()
+            .filter_map(|id| threads.get_change(id))
+            .collect();
+        let files = materialize_files(&history).unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["img.bin"]);
+        assert_eq!(files["img.bin"], vec![0, 159, 146, 150]);
+
+        // 导入后再导出没有新的提交
+        assert!(
+            bridge
+                .export_thread(&threads, main, ExportMode::PerChange)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use crate::common::change::Change;
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::thread::ThreadManager;
+use crate::common::change::version::VectorClock;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+#[derive(Default)]
+struct Inner {
+    delivered: HashSet<Uuid>,
+    /// 已交付 Change 的向量时钟合并值
+    clock: VectorClock,
+    pending: HashMap<Uuid, Change>,
+}
+
+impl Inner {
+    /// 父 Change 均已交付，且除作者外各节点的时钟不超过已交付的时钟、作者最多领先一步
+    fn is_ready(&self, change: &Change) -> bool {
+        change.parents.iter().all(|p| self.delivered.contains(p))
+            && change.version.clocks.iter().all(|(node, clock)| {
+                let limit = self.clock.get(node) + u64::from(*node == change.author_id);
+                *clock <= limit
+            })
+    }
+
+    fn deliver(&mut self, change: &Change) {
+        self.delivered.insert(change.id);
+        self.clock.merge(&change.version);
+    }
+}
+
+/// 远端 Change 的因果交付缓冲区
+///
+/// 父 Change 或向量时钟上的前驱尚未到达的 Change 暂存在缓冲区中，
+/// 前驱到齐后按因果顺序释放，保证提交到本地线程时其历史已经完整。
+#[derive(Default)]
+pub struct CausalBuffer {
+    inner: Mutex<Inner>,
+}
+
+impl CausalBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 以线程管理器中已有的 Change 作为已交付的起点
+    pub fn from_threads(threads: &ThreadManager) -> Self {
+        let buffer = Self::new();
+        for change in threads.list_changes() {
+            buffer.mark_delivered(&change);
+        }
+        buffer
+    }
+
+    /// 记录本地产生或已通过其他途径应用的 Change
+    pub fn mark_delivered(&self, change: &Change) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.pending.remove(&change.id);
+        inner.deliver(change);
+    }
+
+    /// 接收一个远端 Change，返回因此可以交付的全部 Change（按因果顺序）
+    ///
+    /// 已交付或已在缓冲区中的 Change 会被忽略；哈希校验失败时返回错误。
+    pub fn receive(&self, change: Change) -> anyhow::Result<Vec<Change>> {
+        if !change.verify_hash() {
+            return Err(anyhow::anyhow!("Invalid change hash: {}", change.id));
+        }
+        let mut inner = self.inner.lock().unwrap();
+        if inner.delivered.contains(&change.id) || inner.pending.contains_key(&change.id) {
+            return Ok(Vec::new());
+        }
+        inner.pending.insert(change.id, change);
+
+        let engine = MergeEngine::new();
+        let mut released = Vec::new();
+        loop {
+            let ready: Vec<Uuid> = inner
+                .pending
+                .values()
+                .filter(|c| inner.is_ready(c))
+                .map(|c| c.id)
+                .collect();
+            if ready.is_empty() {
+                break;
+            }
+            let ready: Vec<Change> = ready
+                .iter()
+                .filter_map(|id| inner.pending.remove(id))
+                .collect();
+            for change in engine.sort_changes(ready) {
+                inner.deliver(&change);
+                released.push(change);
+            }
+        }
+        Ok(released)
+    }
+
+    /// 缓冲区中等待前驱的 Change 数
+    pub fn pending(&self) -> usize {
+        self.inner.lock().unwrap().pending.len()
+    }
+
+    /// 缓冲区中的 Change 引用、但既未交付也未到达的父 Change（用于向对端补拉）
+    pub fn missing(&self) -> Vec<Uuid> {
+        let inner = self.inner.lock().unwrap();
+        let missing: BTreeSet<Uuid> = inner
+            .pending
+            .values()
+            .flat_map(|c| c.parents.iter().copied())
+            .filter(|p| !inner.delivered.contains(p) && !inner.pending.contains_key(p))
+            .collect();
+        missing.into_iter().collect()
+    }
+
+    pub fn is_delivered(&self, id: Uuid) -> bool {
+        self.inner.lock().unwrap().delivered.contains(&id)
+    }
+
+    /// 已交付 Change 的向量时钟
+    pub fn clock(&self) -> VectorClock {
+        self.inner.lock().unwrap().clock.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next(author: Uuid, parent: Option<&Change>) -> Change {
+        let mut version = parent.map(|p| p.version.clone()).unwrap_or_default();
+        version.increment(author);
+        Change::new(
+            author,
+            vec![],
+            version,
+            parent.map(|p| p.id).into_iter().collect(),
+        )
+    }
+
+    #[test]
+    fn test_causal_buffer_releases_in_order() {
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let a1 = next(alice, None);
+        let a2 = next(alice, Some(&a1));
+        let b1 = next(bob, Some(&a2));
+        let mut merge = next(alice, Some(&b1));
+        merge.parents = vec![b1.id, a2.id];
+        merge.hash = merge.calculate_hash();
+
+        let buffer = CausalBuffer::new();
+        assert!(buffer.receive(merge.clone()).unwrap().is_empty());
+        assert!(buffer.receive(b1.clone()).unwrap().is_empty());
+        assert!(buffer.receive(a2.clone()).unwrap().is_empty());
+        assert_eq!(buffer.pending(), 3);
+        assert_eq!(buffer.missing(), vec![a1.id]);
+
+        let released: Vec<Uuid> = buffer
+            .receive(a1.clone())
+            .unwrap()
+            .iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(released, vec![a1.id, a2.id, b1.id, merge.id]);
+        assert_eq!(buffer.pending(), 0);
+        assert_eq!(buffer.clock(), merge.version);
+        // 重复到达的 Change 被忽略
+        assert!(buffer.receive(a1).unwrap().is_empty());
+
+        let mut tampered = next(bob, Some(&merge));
+        tampered.hash = "bad".to_string();
+        assert!(buffer.receive(tampered).is_err());
+    }
+
+    #[test]
+    fn test_causal_buffer_waits_for_clock_predecessors() {
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let threads = ThreadManager::new();
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let base = next(alice, None);
+        threads.commit_change(main, base.clone()).unwrap();
+        let buffer = CausalBuffer::from_threads(&threads);
+
+        // b2 的父节点已交付，但 bob 的第一个 Change 尚未到达
+        let b1 = next(bob, Some(&base));
+        let b2 = next(bob, Some(&b1));
+        let mut skipped = b2.clone();
+        skipped.parents = vec![base.id];
+        skipped.id = Uuid::new_v4();
+        skipped.hash = skipped.calculate_hash();
+        assert!(buffer.receive(skipped.clone()).unwrap().is_empty());
+        let released = buffer.receive(b1.clone()).unwrap();
+        assert_eq!(
+            released.iter().map(|c| c.id).collect::<Vec<_>>(),
+            vec![b1.id, skipped.id]
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use crate::common::change::operation::Operation;
+use crate::common::change::signing::ChangeSignature;
 use crate::common::change::version::VectorClock;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 /// 变动数据结构
@@ -22,6 +24,9 @@ pub struct Change {
     pub parents: Vec<Uuid>,
     /// 内容哈希，用于完整性校验
     pub hash: String,
+    /// 作者签名（可选，不参与哈希计算）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ChangeSignature>,
 }
 
 impl Change {
@@ -40,6 +45,7 @@ impl Change {
             version,
             parents,
             hash: String::new(),
+            signature: None,
         };
         change.hash = change.calculate_hash();
         change
@@ -58,8 +64,9 @@ impl Change {
             hasher.update(ops_json.as_bytes());
         }
 
-        // 序列化版本和父节点
-        if let Ok(version_json) = serde_json::to_string(&self.version) {
+        // 序列化版本和父节点（时钟按节点排序，哈希不受 HashMap 迭代顺序影响）
+        let clocks: BTreeMap<_, _> = self.version.clocks.iter().collect();
+        if let Ok(version_json) = serde_json::to_string(&serde_json::json!({ "clocks": clocks })) {
             hasher.update(version_json.as_bytes());
         }
         for parent in &self.parents {
@@ -0,0 +1,324 @@
+use crate::common::change::snapshot::materialize_file;
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::change::threeway::line_edits;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 评论锚定的代码范围：`change_id` 应用后文件中 `[start_line, end_line)` 的行（从 0 开始）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeAnchor {
+    pub path: String,
+    pub change_id: Uuid,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Comment {
+    pub id: Uuid,
+    pub author_id: Uuid,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 锚定在代码范围上的评论串
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommentThread {
+    pub id: Uuid,
+    /// 评论所属的线程，重新锚定时跟随其 Head
+    pub thread_id: ThreadId,
+    pub anchor: CodeAnchor,
+    pub comments: Vec<Comment>,
+    pub resolved: bool,
+    /// 锚定的行已全部被改写或文件已删除，锚点停留在最后一次有效的位置
+    pub outdated: bool,
+}
+
+impl ThreadManager {
+    /// 在 `thread_id` 上的代码范围发起评论串
+    ///
+    /// 锚点的 Change 必须在该线程的历史中，范围不能超出该 Change 应用后的文件。
+    pub fn add_comment(
+        &self,
+        thread_id: ThreadId,
+        anchor: CodeAnchor,
+        author_id: Uuid,
+        body: &str,
+    ) -> anyhow::Result<CommentThread> {
+        let head = self
+            .get_thread(thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?
+            .head_change_id;
+        if !head.is_some_and(|head| self.is_ancestor(anchor.change_id, head)) {
+            return Err(anyhow::anyhow!(
+                "Change {} is not in the thread history",
+                anchor.change_id
+            ));
+        }
+        let lines = self
+            .file_lines_at(anchor.change_id, &anchor.path)?
+            .ok_or_else(|| anyhow::anyhow!("File not found: {}", anchor.path))?;
+        if anchor.start_line >= anchor.end_line || anchor.end_line > lines.len() {
+            return Err(anyhow::anyhow!(
+                "Invalid range {}..{} for {} ({} lines)",
+                anchor.start_line,
+                anchor.end_line,
+                anchor.path,
+                lines.len()
+            ));
+        }
+
+        let thread = CommentThread {
+            id: Uuid::new_v4(),
+            thread_id,
+            anchor,
+            comments: vec![Comment::new(author_id, body)],
+            resolved: false,
+            outdated: false,
+        };
+        self.comments
+            .write()
+            .unwrap()
+            .insert(thread.id, thread.clone());
+        Ok(thread)
+    }
+
+    /// 回复评论串；已解决的评论串收到回复后重新打开
+    pub fn reply_comment(
+        &self,
+        comment_id: Uuid,
+        author_id: Uuid,
+        body: &str,
+    ) -> anyhow::Result<CommentThread> {
+        self.update_comment(comment_id, |thread| {
+            thread.comments.push(Comment::new(author_id, body));
+            thread.resolved = false;
+        })
+    }
+
+    /// 标记评论串为已解决或重新打开
+    pub fn resolve_comment(
+        &self,
+        comment_id: Uuid,
+        resolved: bool,
+    ) -> anyhow::Result<CommentThread> {
+        self.update_comment(comment_id, |thread| thread.resolved = resolved)
+    }
+
+    pub fn get_comment(&self, comment_id: Uuid) -> Option<CommentThread> {
+        self.comments.read().unwrap().get(&comment_id).cloned()
+    }
+
+    /// 线程上的全部评论串（按锚定的文件与行排序），先重新锚定到线程当前的 Head
+    pub fn list_comments(&self, thread_id: ThreadId) -> anyhow::Result<Vec<CommentThread>> {
+        self.reanchor_comments(thread_id)?;
+        let mut threads: Vec<CommentThread> = self
+            .comments
+            .read()
+            .unwrap()
+            .values()
+            .filter(|c| c.thread_id == thread_id)
+            .cloned()
+            .collect();
+        threads.sort_by(|a, b| {
+            (&a.anchor.path, a.anchor.start_line, a.id).cmp(&(
+                &b.anchor.path,
+                b.anchor.start_line,
+                b.id,
+            ))
+        });
+        Ok(threads)
+    }
+
+    /// 将线程上的评论串沿之后的编辑移动到 Head，返回锚点发生变化的数量
+    ///
+    /// 范围内保留下来的行决定新的范围，范围内部的插入会被包含；
+    /// 范围内的行全部被改写或文件被删除时标记为过期，锚点保持不动。
+    pub fn reanchor_comments(&self, thread_id: ThreadId) -> anyhow::Result<usize> {
+        let head = self
+            .get_thread(thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?
+            .head_change_id;
+        let Some(head) = head else {
+            return Ok(0);
+        };
+
+        let mut comments = self.comments.write().unwrap();
+        let mut moved = 0;
+        for thread in comments.values_mut() {
+            if thread.thread_id != thread_id || thread.outdated || thread.anchor.change_id == head {
+                continue;
+            }
+            let anchor = &thread.anchor;
+            let mapped = match (
+                self.file_lines_at(anchor.change_id, &anchor.path)?,
+                self.file_lines_at(head, &anchor.path)?,
+            ) {
+                (Some(before), Some(after)) => {
+                    map_range(&before, &after, anchor.start_line, anchor.end_line)
+                }
+                _ => None,
+            };
+            match mapped {
+                Some((start_line, end_line)) => {
+                    thread.anchor.change_id = head;
+                    thread.anchor.start_line = start_line;
+                    thread.anchor.end_line = end_line;
+                }
+                None => thread.outdated = true,
+            }
+            moved += 1;
+        }
+        Ok(moved)
+    }
+
+    /// 直接写入评论串（用于崩溃恢复）
+    pub fn restore_comment(&self, thread: CommentThread) {
+        self.comments.write().unwrap().insert(thread.id, thread);
+    }
+
+    /// 列出所有评论串
+    pub fn list_all_comments(&self) -> Vec<CommentThread> {
+        self.comments.read().unwrap().values().cloned().collect()
+    }
+
+    fn update_comment(
+        &self,
+        comment_id: Uuid,
+        update: impl FnOnce(&mut CommentThread),
+    ) -> anyhow::Result<CommentThread> {
+        let mut comments = self.comments.write().unwrap();
+        let thread = comments
+            .get_mut(&comment_id)
+            .ok_or_else(|| anyhow::anyhow!("Comment thread not found: {}", comment_id))?;
+        update(thread);
+        Ok(thread.clone())
+    }
+
+    /// `change_id` 应用后文件的各行
+    fn file_lines_at(&self, change_id: Uuid, path: &str) -> anyhow::Result<Option<Vec<String>>> {
+        let history: Vec<_> = self
+            .ancestors(change_id)
+            .into_iter()
+            .filter_map(|id| self.get_change(id))
+            .collect();
+        let content = materialize_file(&history, path)?;
+        Ok(content.map(|content| {
+            String::from_utf8_lossy(&content)
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }))
+    }
+}
+
+impl Comment {
+    fn new(author_id: Uuid, body: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            author_id,
+            body: body.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// 将 `before` 中的行范围映射到 `after`，范围内没有保留下来的行时返回 `None`
+fn map_range(
+    before: &[String],
+    after: &[String],
+    start: usize,
+    end: usize,
+) -> Option<(usize, usize)> {
+    let before: Vec<&str> = before.iter().map(String::as_str).collect();
+    let after: Vec<&str> = after.iter().map(String::as_str).collect();
+    let edits = line_edits(&before, &after);
+
+    // 逐段走过未改动的行，记录范围内首末保留行在新文件中的位置
+    let mut kept: Option<(usize, usize)> = None;
+    let (mut old, mut new) = (0, 0);
+    let mut keep = |from: usize, to: usize, at: usize| {
+        let (lo, hi) = (from.max(start), to.min(end));
+        if lo < hi {
+            let first = at + (lo - from);
+            let last = at + (hi - from);
+            let range = kept.get_or_insert((first, last));
+            range.1 = last;
+        }
+    };
+    for edit in &edits {
+        keep(old, edit.start, new);
+        new += edit.start - old + edit.lines.len();
+        old = edit.end;
+    }
+    keep(old, before.len(), new);
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::operation::Operation;
+
+    fn write(content: &str) -> Vec<Operation> {
+        vec![Operation::file_write("a.rs".into(), content.into())]
+    }
+
+    fn anchor(change_id: Uuid, start_line: usize, end_line: usize) -> CodeAnchor {
+        CodeAnchor {
+            path: "a.rs".into(),
+            change_id,
+            start_line,
+            end_line,
+        }
+    }
+
+    #[test]
+    fn test_comments_follow_edits() {
+        let threads = ThreadManager::new();
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let reviewer = Uuid::new_v4();
+        let base = threads.commit_test(main, write("fn a() {\n    x();\n}\nfn b() {}\n"));
+
+        assert!(
+            threads
+                .add_comment(main, anchor(base, 2, 9), reviewer, "?")
+                .is_err()
+        );
+        let unrelated = threads.create_branch(main, "other").unwrap();
+        let elsewhere = threads.commit_test(unrelated, write("x\n"));
+        assert!(
+            threads
+                .add_comment(main, anchor(elsewhere, 0, 1), reviewer, "?")
+                .is_err()
+        );
+
+        let body = threads
+            .add_comment(main, anchor(base, 1, 3), reviewer, "why x?")
+            .unwrap();
+        let last = threads
+            .add_comment(main, anchor(base, 3, 4), reviewer, "unused")
+            .unwrap();
+        threads.resolve_comment(body.id, true).unwrap();
+        let reopened = threads
+            .reply_comment(body.id, Uuid::new_v4(), "fixed")
+            .unwrap();
+        assert!(!reopened.resolved);
+        assert_eq!(reopened.comments.len(), 2);
+
+        // 上方插入两行、范围内插入一行、删除 b
+        let head = threads.commit_test(
+            main,
+            write("// doc\n// doc\nfn a() {\n    x();\n    y();\n}\n"),
+        );
+        let comments = threads.list_comments(main).unwrap();
+        let body = comments.iter().find(|c| c.id == body.id).unwrap();
+        assert_eq!(body.anchor, anchor(head, 3, 6));
+        assert!(!body.outdated);
+        let last = comments.iter().find(|c| c.id == last.id).unwrap();
+        assert!(last.outdated);
+        assert_eq!(last.anchor, anchor(base, 3, 4));
+        assert_eq!(threads.reanchor_comments(main).unwrap(), 0);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::common::change::Change;
+use crate::common::change::operation::Operation;
+use crate::common::change::thread::ThreadId;
+use crate::common::change::version::VectorClock;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// 压缩策略
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactionPolicy {
+    /// 只压缩早于该时长的 Change
+    pub horizon_secs: i64,
+    /// 连续段至少包含的 Change 数
+    pub min_run: usize,
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        Self {
+            horizon_secs: 7 * 24 * 3600,
+            min_run: 2,
+        }
+    }
+}
+
+impl CompactionPolicy {
+    pub fn with_horizon(mut self, horizon: Duration) -> Self {
+        self.horizon_secs = horizon.num_seconds();
+        self
+    }
+
+    pub fn with_min_run(mut self, min_run: usize) -> Self {
+        self.min_run = min_run.max(2);
+        self
+    }
+
+    fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - Duration::seconds(self.horizon_secs)
+    }
+}
+
+/// 一次压缩的统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactionStats {
+    pub thread_id: ThreadId,
+    /// 折叠的连续段数（每段生成一个检查点 Change）
+    pub runs: usize,
+    /// 被回收的 Change ID（持久化存储中也应一并删除）
+    pub removed: Vec<Uuid>,
+    pub operations_before: usize,
+    pub operations_after: usize,
+    /// 改写到检查点的线程分叉点数
+    pub rewritten_fork_points: usize,
+}
+
+impl CompactionStats {
+    /// 回收的 Change 数
+    pub fn reclaimed(&self) -> usize {
+        self.removed.len()
+    }
+}
+
+/// 在线程的首父链（从根到 Head）中找出可折叠的连续段
+///
+/// 可折叠的 Change 早于策略的时间界限、不是合并 Change 且未签名；被链外 Change 引用为父节点、
+/// 作为线程 Head 或出现在合并记录中的 Change 必须保留其 ID，只能作为段的末尾。
+/// 检查点无法携带原作者的签名，因此带签名的 Change 总是原样保留。
+pub(crate) fn find_runs(
+    chain: &[Uuid],
+    changes: &HashMap<Uuid, Change>,
+    pinned: &HashSet<Uuid>,
+    policy: &CompactionPolicy,
+    now: DateTime<Utc>,
+) -> Vec<Vec<Uuid>> {
+    let cutoff = policy.cutoff(now);
+    let mut runs = Vec::new();
+    let mut current: Vec<Uuid> = Vec::new();
+    let mut flush = |current: &mut Vec<Uuid>| {
+        if current.len() >= policy.min_run {
+            runs.push(std::mem::take(current));
+        }
+        current.clear();
+    };
+    for id in chain {
+        let Some(change) = changes.get(id) else {
+            flush(&mut current);
+            continue;
+        };
+        if change.parents.len() > 1 || change.timestamp >= cutoff || change.signature.is_some() {
+            flush(&mut current);
+            continue;
+        }
+        current.push(*id);
+        if pinned.contains(id) {
+            flush(&mut current);
+        }
+    }
+    flush(&mut current);
+    runs
+}
+
+/// 将一段线性历史折叠为一个检查点 Change
+///
+/// 检查点沿用段末 Change 的 ID，因此子节点、线程 Head 与合并记录无需改写；
+/// 父节点取段首的父节点，操作只保留净效果（被之后的整文件写入或删除覆盖的文件操作被丢弃）。
+/// 检查点以相同 ID 携带不同内容，只在本地有效：持有原版本的同步对端会与之分歧。
+pub(crate) fn fold(run: &[Change]) -> Change {
+    let last = run.last().expect("non-empty run");
+    let operations: Vec<Operation> = run.iter().flat_map(|c| c.operations.clone()).collect();
+    let mut superseded = HashSet::new();
+    let mut overwritten: HashSet<&str> = HashSet::new();
+    for (index, op) in operations.iter().enumerate().rev() {
+        let Some(path) = op.path() else {
+            continue;
+        };
+        if overwritten.contains(path) {
+            superseded.insert(index);
+        }
+        if matches!(
+            op,
+            Operation::FileWrite { .. }
+                | Operation::FileWriteRef { .. }
+                | Operation::FileDelete { .. }
+        ) {
+            overwritten.insert(path);
+        }
+    }
+    let operations = operations
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !superseded.contains(index))
+        .map(|(_, op)| op.clone())
+        .collect();
+
+    let mut version = VectorClock::new();
+    for change in run {
+        version.merge(&change.version);
+    }
+    let mut checkpoint = Change {
+        id: last.id,
+        author_id: last.author_id,
+        timestamp: last.timestamp,
+        operations,
+        version,
+        parents: run[0].parents.clone(),
+        hash: String::new(),
+        signature: None,
+    };
+    checkpoint.hash = checkpoint.calculate_hash();
+    checkpoint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(change: Change, days_ago: i64) -> Change {
+        let mut change = Change {
+            timestamp: Utc::now() - Duration::days(days_ago),
+            ..change
+        };
+        change.hash = change.calculate_hash();
+        change
+    }
+
+    #[test]
+    fn test_fold_keeps_net_effect() {
+        let author = Uuid::new_v4();
+        let root = Change::mock(author, vec![]);
+        let a = Change::new(
+            author,
+            vec![
+                Operation::file_write("a.txt".to_string(), b"1".to_vec()),
+                Operation::mock("node", "x"),
+            ],
+            VectorClock::new(),
+            vec![root.id],
+        );
+        let b = Change::new(
+            author,
+            vec![
+                Operation::file_write("a.txt".to_string(), b"2".to_vec()),
+                Operation::file_write("b.txt".to_string(), b"b".to_vec()),
+            ],
+            VectorClock::new(),
+            vec![a.id],
+        );
+        let checkpoint = fold(&[a, b.clone()]);
+        assert_eq!(checkpoint.id, b.id);
+        assert_eq!(checkpoint.parents, vec![root.id]);
+        assert_eq!(
+            checkpoint.operations,
+            vec![
+                Operation::mock("node", "x"),
+                Operation::file_write("a.txt".to_string(), b"2".to_vec()),
+                Operation::file_write("b.txt".to_string(), b"b".to_vec()),
+            ]
+        );
+        assert!(checkpoint.verify_hash());
+    }
+
+    #[test]
+    fn test_find_runs_respects_horizon_and_pins() {
+        let author = Uuid::new_v4();
+        let mut chain = Vec::new();
+        let mut changes = HashMap::new();
+        let mut parents = Vec::new();
+        for days_ago in [30, 29, 28, 27, 26, 0] {
+            let change = at(
+                Change::new(author, vec![], VectorClock::new(), parents),
+                days_ago,
+            );
+            parents = vec![change.id];
+            chain.push(change.id);
+            changes.insert(change.id, change);
+        }
+        let policy = CompactionPolicy::default();
+        let pinned = HashSet::from([chain[1]]);
+        let runs = find_runs(&chain, &changes, &pinned, &policy, Utc::now());
+        // 被固定的 Change 结束一段；最近的 Change 不在界限内
+        assert_eq!(runs, vec![chain[0..2].to_vec(), chain[2..5].to_vec()]);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::common::change::Change;
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::sparse::normalize;
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 时间线查询范围与过滤条件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryRange {
+    /// 跳过最新的若干条（过滤后计数）
+    pub offset: usize,
+    /// 本页最多返回的条数
+    pub limit: usize,
+    /// 仅返回该作者的 Change
+    #[serde(default)]
+    pub author: Option<Uuid>,
+    /// 仅返回涉及该路径（文件或目录前缀）的 Change
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+impl HistoryRange {
+    pub fn new(offset: usize, limit: usize) -> Self {
+        Self {
+            offset,
+            limit,
+            author: None,
+            path: None,
+        }
+    }
+
+    pub fn with_author(mut self, author: Uuid) -> Self {
+        self.author = Some(author);
+        self
+    }
+
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = Some(path.trim_end_matches('/').to_string());
+        self
+    }
+
+    fn matches(&self, change: &Change) -> bool {
+        if self.author.is_some_and(|author| author != change.author_id) {
+            return false;
+        }
+        // 过滤条件可能来自反序列化，两侧都去掉开头的 `./` 与 `/` 后再比较
+        match &self.path {
+            Some(prefix) => {
+                let prefix = normalize(prefix).trim_end_matches('/');
+                change
+                    .operations
+                    .iter()
+                    .filter_map(|op| op.path())
+                    .any(|path| under(normalize(path), prefix))
+            }
+            None => true,
+        }
+    }
+}
+
+/// Change 时刻的快照句柄，交给 `SnapshotGenerator::generate` 或物化接口按需生成内容
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SnapshotHandle {
+    pub change_id: Uuid,
+    /// Change 内容哈希，前端可据此缓存已加载的快照
+    pub hash: String,
+}
+
+/// 时间线中的一条记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub change_id: Uuid,
+    pub author_id: Uuid,
+    /// 已登记的作者显示名称（见 `ThreadManager::set_author_name`）
+    pub author_name: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub parents: Vec<Uuid>,
+    /// 逐操作的单行摘要
+    pub operations: Vec<String>,
+    /// 涉及的文件路径（去重，保持操作顺序）
+    pub paths: Vec<String>,
+    pub snapshot: SnapshotHandle,
+}
+
+/// 一页时间线
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryPage {
+    /// 从新到旧排列
+    pub entries: Vec<HistoryEntry>,
+    /// 过滤后的总条数
+    pub total: usize,
+    /// 下一页的 `offset`，已到末尾时为 `None`
+    pub next_offset: Option<usize>,
+}
+
+impl ThreadManager {
+    /// 登记作者的显示名称
+    pub fn set_author_name(&self, author_id: Uuid, name: &str) {
+        self.authors
+            .write()
+            .unwrap()
+            .insert(author_id, name.to_string());
+    }
+
+    pub fn author_name(&self, author_id: Uuid) -> Option<String> {
+        self.authors.read().unwrap().get(&author_id).cloned()
+    }
+
+    /// 查询线程时间线：Head 可达的全部 Change 按因果顺序从新到旧排列，经过滤后分页返回
+    pub fn history(&self, thread_id: ThreadId, range: HistoryRange) -> anyhow::Result<HistoryPage> {
+        let thread = self
+            .get_thread(thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+        let reachable: Vec<Change> = thread
+            .head_change_id
+            .map(|head| self.ancestors(head))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|id| self.get_change(id))
+            .filter(|change| range.matches(change))
+            .collect();
+
+        let mut ordered = MergeEngine::new().sort_changes(reachable);
+        ordered.reverse();
+        let total = ordered.len();
+        let entries: Vec<HistoryEntry> = ordered
+            .into_iter()
+            .skip(range.offset)
+            .take(range.limit)
+            .map(|change| self.history_entry(change))
+            .collect();
+        let end = range.offset + entries.len();
+
+        Ok(HistoryPage {
+            entries,
+            total,
+            next_offset: (end < total).then_some(end),
+        })
+    }
+
+    fn history_entry(&self, change: Change) -> HistoryEntry {
+        let mut paths: Vec<String> = Vec::new();
+        for path in change.operations.iter().filter_map(|op| op.path()) {
+            if !paths.iter().any(|p| p == path) {
+                paths.push(path.to_string());
+            }
+        }
+        HistoryEntry {
+            change_id: change.id,
+            author_id: change.author_id,
+            author_name: self.author_name(change.author_id),
+            timestamp: change.timestamp,
+            operations: change.operations.iter().map(|op| op.summary()).collect(),
+            paths,
+            snapshot: SnapshotHandle {
+                change_id: change.id,
+                hash: change.hash,
+            },
+            parents: change.parents,
+        }
+    }
+}
+
+fn under(path: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::operation::Operation;
+    use crate::common::change::version::VectorClock;
+
+    #[test]
+    fn test_history_filters_and_paginates() {
+        let threads = ThreadManager::new();
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        threads.set_author_name(alice, "alice");
+
+        let mut parents = Vec::new();
+        let mut clock = VectorClock::new();
+        let mut ids = Vec::new();
+        for (author, path) in [
+            (alice, "src/main.rs"),
+            (bob, "src/lib.rs"),
+            (alice, "docs/guide.md"),
+            (bob, "src/main.rs"),
+        ] {
+            clock.increment(author);
+            let change = Change::new(
+                author,
+                vec![Operation::file_write(path.to_string(), b"x".to_vec())],
+                clock.clone(),
+                parents,
+            );
+            parents = vec![change.id];
+            ids.push(change.id);
+            threads.commit_change(main, change).unwrap();
+        }
+
+        let page = threads.history(main, HistoryRange::new(0, 3)).unwrap();
+        assert_eq!(page.total, 4);
+        assert_eq!(page.next_offset, Some(3));
+        assert_eq!(page.entries[0].change_id, ids[3]);
+        assert_eq!(page.entries[0].author_name, None);
+        assert_eq!(
+            page.entries[1].operations,
+            vec!["write docs/guide.md (1 bytes)"]
+        );
+        let rest = threads.history(main, HistoryRange::new(3, 3)).unwrap();
+        assert_eq!(rest.entries[0].change_id, ids[0]);
+        assert_eq!(rest.entries[0].author_name.as_deref(), Some("alice"));
+        assert_eq!(rest.next_offset, None);
+
+        let by_alice = threads
+            .history(main, HistoryRange::new(0, 10).with_author(alice))
+            .unwrap();
+        assert_eq!(by_alice.total, 2);
+        let in_src = threads
+            .history(main, HistoryRange::new(0, 10).with_path("src/"))
+            .unwrap();
+        let in_src: Vec<_> = in_src.entries.iter().map(|e| e.change_id).collect();
+        assert_eq!(in_src, vec![ids[3], ids[1], ids[0]]);
+        let main_rs = threads
+            .history(main, HistoryRange::new(0, 10).with_path("src/main.rs"))
+            .unwrap();
+        assert_eq!(main_rs.total, 2);
+        for path in ["./src/main.rs", "/src/main.rs"] {
+            let page = threads
+                .history(main, HistoryRange::new(0, 10).with_path(path))
+                .unwrap();
+            assert_eq!(page.total, 2, "{}", path);
+        }
+    }
+}
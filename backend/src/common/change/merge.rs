@@ -2,10 +2,118 @@ use crate::common::change::change::Change;
 use crate::common::change::operation::Operation;
 use crate::common::change::version::Relation;
 use crate::common::meta::ast::MetaNode;
+use std::collections::HashMap;
 use uuid::Uuid;
 
+/// 同级节点序列中的一个元素（RGA），删除后保留为墓碑以便定位并发插入
+#[derive(Debug, Clone)]
+struct Slot {
+    id: Uuid,
+    /// 插入该元素的变更在排序后的下标；初始状态中的元素为 `None`
+    inserted_by: Option<usize>,
+    /// 删除（或移走）该元素的变更下标
+    deleted_by: Vec<usize>,
+}
+
+/// 合并过程中正在应用的变更及其所在的全序
+struct Frame<'a> {
+    seq: usize,
+    changes: &'a [Change],
+}
+
+impl Frame<'_> {
+    /// 下标为 `other` 的变更是否在当前变更的因果历史中（或就是当前变更）
+    fn knows(&self, other: usize) -> bool {
+        other == self.seq
+            || self.changes[other]
+                .version
+                .compare(&self.changes[self.seq].version)
+                != Relation::Concurrent
+    }
+
+    /// 元素在当前变更作者的视图中是否可见
+    fn sees(&self, slot: &Slot) -> bool {
+        slot.inserted_by.is_none_or(|by| self.knows(by))
+            && !slot.deleted_by.iter().any(|&by| self.knows(by))
+    }
+}
+
+/// 各父节点下子节点序列的 RGA 状态
+#[derive(Default)]
+struct Sequences {
+    lists: HashMap<Uuid, Vec<Slot>>,
+}
+
+impl Sequences {
+    /// 取父节点的序列；与实际子节点不一致（例如父节点被整体更新）时以实际子节点重建
+    fn slots(&mut self, parent: &MetaNode) -> &mut Vec<Slot> {
+        let live: Vec<Uuid> = children(parent)
+            .map(|c| c.iter().map(MetaNode::id).collect())
+            .unwrap_or_default();
+        let slots = self.lists.entry(parent.id()).or_default();
+        if !slots
+            .iter()
+            .filter(|s| s.deleted_by.is_empty())
+            .map(|s| s.id)
+            .eq(live.iter().copied())
+        {
+            *slots = live
+                .into_iter()
+                .map(|id| Slot {
+                    id,
+                    inserted_by: None,
+                    deleted_by: Vec::new(),
+                })
+                .collect();
+        }
+        slots
+    }
+
+    /// 将作者视图中的下标转换为实际子节点列表中的下标
+    ///
+    /// 新元素紧跟在作者视图中下标前一个元素（左邻）之后，位于作者未见过的并发插入之前；
+    /// 作者已删除、他人并发删除的元素都不会影响定位。
+    fn integrate(&mut self, parent: &MetaNode, index: usize, id: Uuid, frame: &Frame) -> usize {
+        let slots = self.slots(parent);
+        let view: Vec<usize> = (0..slots.len())
+            .filter(|&i| frame.sees(&slots[i]))
+            .collect();
+        let at = match index.min(view.len()) {
+            0 => 0,
+            i => view[i - 1] + 1,
+        };
+        slots.insert(
+            at,
+            Slot {
+                id,
+                inserted_by: Some(frame.seq),
+                deleted_by: Vec::new(),
+            },
+        );
+        slots[..at]
+            .iter()
+            .filter(|s| s.deleted_by.is_empty())
+            .count()
+    }
+
+    /// 将元素标记为墓碑
+    fn remove(&mut self, parent: &MetaNode, id: Uuid, frame: &Frame) {
+        if let Some(slot) = self
+            .slots(parent)
+            .iter_mut()
+            .find(|s| s.id == id && s.deleted_by.is_empty())
+        {
+            slot.deleted_by.push(frame.seq);
+        }
+    }
+}
+
 /// CRDT 合并引擎
 /// 采用因果排序 (Causal Ordering) 和 LWW (Last-Write-Wins) 策略
+///
+/// 插入与移动中的下标是相对于作者当时所见的子节点序列的：合并时按 RGA 的方式，
+/// 将下标解析为作者视图中的左邻节点并插在其后，因此并发的插入与删除不会使彼此的位置错位，
+/// 任意到达顺序都收敛到同一结果。
 pub struct MergeEngine {}
 
 impl Default for MergeEngine {
@@ -43,30 +151,38 @@ impl MergeEngine {
     pub fn merge(&self, initial_state: MetaNode, changes: &[Change]) -> anyhow::Result<MetaNode> {
         let mut root = initial_state;
         let sorted_changes = self.sort_changes(changes.to_vec());
+        let mut sequences = Sequences::default();
 
-        for change in sorted_changes {
+        for (seq, change) in sorted_changes.iter().enumerate() {
+            let frame = Frame {
+                seq,
+                changes: &sorted_changes,
+            };
             for op in &change.operations {
-                self.apply_operation(&mut root, op)?;
+                self.apply_operation(&mut root, op, &mut sequences, &frame)?;
             }
         }
 
         Ok(root)
     }
 
-    fn apply_operation(&self, root: &mut MetaNode, op: &Operation) -> anyhow::Result<()> {
+    fn apply_operation(
+        &self,
+        root: &mut MetaNode,
+        op: &Operation,
+        sequences: &mut Sequences,
+        frame: &Frame,
+    ) -> anyhow::Result<()> {
         match op {
             Operation::Insert {
                 parent_id,
                 index,
                 node,
             } => {
-                if let Some(pid) = parent_id {
-                    if let Some(parent) = self.find_node_mut(root, *pid) {
-                        self.insert_into_node(parent, *index, node.clone())?;
-                    }
-                } else {
-                    // 如果没有 parent_id，尝试插入到根节点的子列表中（如果根节点支持子节点）
-                    self.insert_into_node(root, *index, node.clone())?;
+                // 没有 parent_id 时插入到根节点的子列表中（如果根节点支持子节点）
+                let pid = parent_id.unwrap_or_else(|| root.id());
+                if let Some(parent) = self.find_node_mut(root, pid) {
+                    self.insert_into_node(parent, *index, node.clone(), sequences, frame)?;
                 }
             }
             Operation::Update { node_id, new_node } => {
@@ -75,27 +191,27 @@ impl MergeEngine {
                 }
             }
             Operation::Delete { node_id } => {
-                self.delete_node(root, *node_id);
+                self.take_node(root, *node_id, sequences, frame);
             }
             Operation::Move {
                 node_id,
                 new_parent_id,
                 new_index,
             } => {
-                // 简化实现：先删除再插入
-                if let Some(node) = self.take_node(root, *node_id) {
-                    if let Some(pid) = new_parent_id {
-                        if let Some(parent) = self.find_node_mut(root, *pid) {
-                            self.insert_into_node(parent, *new_index, node)?;
-                        }
-                    } else {
-                        self.insert_into_node(root, *new_index, node)?;
+                // 先删除再插入：原位置留下墓碑，新下标相对于移除后的视图解析
+                if let Some(node) = self.take_node(root, *node_id, sequences, frame) {
+                    let pid = new_parent_id.unwrap_or_else(|| root.id());
+                    if let Some(parent) = self.find_node_mut(root, pid) {
+                        self.insert_into_node(parent, *new_index, node, sequences, frame)?;
                     }
                 }
             }
             Operation::Mock { .. } => {}
             Operation::FileWrite { .. } => {} // 文件系统操作在 Meta AST 合并中暂不处理
+            Operation::FileWriteRef { .. } => {}
             Operation::FileDelete { .. } => {}
+            Operation::NotebookCell { .. } => {}
+            Operation::TextEdit { .. } => {}
         }
         Ok(())
     }
@@ -149,103 +265,60 @@ impl MergeEngine {
         parent: &mut MetaNode,
         index: usize,
         node: MetaNode,
+        sequences: &mut Sequences,
+        frame: &Frame,
     ) -> anyhow::Result<()> {
-        match parent {
-            MetaNode::Module { children, .. } => {
-                let idx = index.min(children.len());
-                children.insert(idx, node);
-            }
-            MetaNode::Block { statements, .. } => {
-                let idx = index.min(statements.len());
-                statements.insert(idx, node);
-            }
-            MetaNode::Class { members, .. } => {
-                let idx = index.min(members.len());
-                members.insert(idx, node);
-            }
-            _ => return Err(anyhow::anyhow!("Node does not support children")),
+        if children(parent).is_none() {
+            return Err(anyhow::anyhow!("Node does not support children"));
+        }
+        let idx = sequences.integrate(parent, index, node.id(), frame);
+        if let Some(children) = children_mut(parent) {
+            children.insert(idx.min(children.len()), node);
         }
         Ok(())
     }
 
-    fn delete_node(&self, parent: &mut MetaNode, id: Uuid) -> bool {
-        match parent {
-            MetaNode::Module { children, .. } => {
-                if let Some(pos) = children.iter().position(|c| c.id() == id) {
-                    children.remove(pos);
-                    return true;
-                }
-                for child in children {
-                    if self.delete_node(child, id) {
-                        return true;
-                    }
-                }
-            }
-            MetaNode::Block { statements, .. } => {
-                if let Some(pos) = statements.iter().position(|c| c.id() == id) {
-                    statements.remove(pos);
-                    return true;
-                }
-                for stmt in statements {
-                    if self.delete_node(stmt, id) {
-                        return true;
-                    }
-                }
-            }
-            MetaNode::Class { members, .. } => {
-                if let Some(pos) = members.iter().position(|c| c.id() == id) {
-                    members.remove(pos);
-                    return true;
-                }
-                for member in members {
-                    if self.delete_node(member, id) {
-                        return true;
-                    }
-                }
-            }
-            _ => {}
+    /// 从所在的子节点列表中移除节点，并在序列中留下墓碑
+    fn take_node(
+        &self,
+        parent: &mut MetaNode,
+        id: Uuid,
+        sequences: &mut Sequences,
+        frame: &Frame,
+    ) -> Option<MetaNode> {
+        let pos = children(parent)?.iter().position(|c| c.id() == id);
+        if let Some(pos) = pos {
+            sequences.remove(parent, id, frame);
+            return children_mut(parent).map(|c| c.remove(pos));
         }
-        false
-    }
-
-    fn take_node(&self, parent: &mut MetaNode, id: Uuid) -> Option<MetaNode> {
-        match parent {
-            MetaNode::Module { children, .. } => {
-                if let Some(pos) = children.iter().position(|c| c.id() == id) {
-                    return Some(children.remove(pos));
-                }
-                for child in children {
-                    if let Some(found) = self.take_node(child, id) {
-                        return Some(found);
-                    }
-                }
+        for child in children_mut(parent)? {
+            if let Some(found) = self.take_node(child, id, sequences, frame) {
+                return Some(found);
             }
-            MetaNode::Block { statements, .. } => {
-                if let Some(pos) = statements.iter().position(|c| c.id() == id) {
-                    return Some(statements.remove(pos));
-                }
-                for stmt in statements {
-                    if let Some(found) = self.take_node(stmt, id) {
-                        return Some(found);
-                    }
-                }
-            }
-            MetaNode::Class { members, .. } => {
-                if let Some(pos) = members.iter().position(|c| c.id() == id) {
-                    return Some(members.remove(pos));
-                }
-                for member in members {
-                    if let Some(found) = self.take_node(member, id) {
-                        return Some(found);
-                    }
-                }
-            }
-            _ => {}
         }
         None
     }
 }
 
+/// 支持有序子节点的节点的子节点列表
+pub(crate) fn children(node: &MetaNode) -> Option<&Vec<MetaNode>> {
+    match node {
+        MetaNode::Module { children, .. } => Some(children),
+        MetaNode::Block { statements, .. } => Some(statements),
+        MetaNode::Class { members, .. } => Some(members),
+        _ => None,
+    }
+}
+
+fn children_mut(node: &mut MetaNode) -> Option<&mut Vec<MetaNode>> {
+    match node {
+        MetaNode::Module { children, .. } => Some(children),
+        MetaNode::Block { statements, .. } => Some(statements),
+        MetaNode::Class { members, .. } => Some(members),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -357,4 +430,89 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_merge_rebases_concurrent_positions() {
+        let engine = MergeEngine::new();
+        let (user_a, user_b) = (Uuid::new_v4(), Uuid::new_v4());
+        let [a, b, c] = ["a", "b", "c"].map(MetaNode::identifier);
+        let root = match MetaNode::module("root") {
+            MetaNode::Module {
+                id, name, metadata, ..
+            } => MetaNode::Module {
+                id,
+                name,
+                children: vec![a.clone(), b.clone(), c.clone()],
+                metadata,
+            },
+            other => other,
+        };
+        let root_id = root.id();
+        let names = |node: &MetaNode| match node {
+            MetaNode::Module { children, .. } => children
+                .iter()
+                .map(|c| match c {
+                    MetaNode::Identifier { name, .. } => name.clone(),
+                    _ => String::new(),
+                })
+                .collect::<Vec<_>>()
+                .join(""),
+            _ => String::new(),
+        };
+
+        // 线程 A 基于 [a, b, c]：删除 a，再在末尾插入 x（此时视图为 [b, c]）
+        let mut v_a = VectorClock::new();
+        v_a.increment(user_a);
+        let c_a = Change::new(
+            user_a,
+            vec![
+                Operation::delete(a.id()),
+                Operation::insert(Some(root_id), 2, MetaNode::identifier("x")),
+            ],
+            v_a,
+            vec![],
+        );
+        // 线程 B 基于 [a, b, c]：在 b 与 c 之间插入 y，在开头插入 z
+        let mut v_b = VectorClock::new();
+        v_b.increment(user_b);
+        let mut c_b = Change::new(
+            user_b,
+            vec![
+                Operation::insert(Some(root_id), 2, MetaNode::identifier("y")),
+                Operation::insert(Some(root_id), 0, MetaNode::identifier("z")),
+            ],
+            v_b,
+            vec![],
+        );
+        c_b.timestamp = c_a.timestamp + chrono::Duration::seconds(1);
+
+        let ab = engine
+            .merge(root.clone(), &[c_a.clone(), c_b.clone()])
+            .unwrap();
+        let ba = engine
+            .merge(root.clone(), &[c_b.clone(), c_a.clone()])
+            .unwrap();
+        assert_eq!(ab, ba);
+        // 未做位置变换时 y 会落在 c 之后、x 会落在 c 之前
+        assert_eq!(names(&ab), "zbycx");
+
+        // 因果后继看到合并后的视图，其下标不再变换
+        let mut v_c = VectorClock::new();
+        v_c.merge(&c_a.version);
+        v_c.merge(&c_b.version);
+        v_c.increment(user_a);
+        let mut c_c = Change::new(
+            user_a,
+            vec![Operation::insert(
+                Some(root_id),
+                1,
+                MetaNode::identifier("w"),
+            )],
+            v_c,
+            vec![c_a.id, c_b.id],
+        );
+        c_c.timestamp = c_b.timestamp + chrono::Duration::seconds(1);
+        let merged = engine.merge(root, &[c_c, c_b, c_a]).unwrap();
+        assert_eq!(names(&merged), "zwbycx");
+    }
 }
@@ -4,25 +4,88 @@
 //!
 //! ## 模块
 //!
+//! - [`blame`] - 基于变更图的逐行追溯（每行由哪个 Change/作者引入）
+//! - [`blob`] - 内容寻址的 Blob 存储（文件内容去重）
+//! - [`causal`] - 远端 Change 的因果交付缓冲区
 //! - [`change`] - 核心变动数据结构
+//! - [`comment`] - 锚定在代码范围上的评论串（随编辑重新锚定）
+//! - [`compaction`] - 变更图压缩（折叠旧的线性历史）
+//! - [`history`] - 线程时间线查询（分页、作者与路径过滤）
 //! - [`operation`] - 不同变动的操作类型
 //! - [`version`] - 用于因果追踪的向量时钟（版本）
+//! - [`testing`] - 基于 proptest 的收敛性测试工具（`test-util` 特性）
 //! - [`thread`] - 线程管理（分叉、合并）
+//! - [`bridge`] - 线程历史与项目 Git 仓库的双向桥接（导出为提交、导入外部提交）
 //! - [`merge`] - CRDT 合并引擎
-//! - [`snapshot`] - 从变动序列生成快照
+//! - [`notebook`] - Jupyter Notebook 的单元格级操作与差异
+//! - [`presence`] - 协作者与 Agent 的临时在线状态（活动文件、光标、选区，自动过期）
+//! - [`patch`] - 将线程自分叉点以来的 Change 导出为补丁系列（mbox / `git format-patch` 风格）
+//! - [`revert`] - 单个 Change 的挑选（cherry-pick）与撤销（revert）
+//! - [`signing`] - Change 的 Ed25519 签名与作者身份
+//! - [`snapshot`] - 从变动序列生成快照（带字节预算的 LRU 缓存）
+//! - [`sparse`] - 稀疏检出（仅物化配置的路径前缀）
+//! - [`text`] - 文本文件的字符级插入与删除操作
+//! - [`store`] - 变更图持久化（线程、Change 与快照，启动时恢复）
+//! - [`threeway`] - 基于共同祖先的三方合并与合并报告
+//! - [`sync`] - 实例间的变更同步协议（交换向量时钟、补拉缺失 Change、推送新提交）
+//! - [`synthetic`] - 合成变更图生成器（基准测试与压力测试）
+//! - [`topology`] - 线程拓扑图与合并状态查询
+//! - [`wal`] - 预写日志（崩溃恢复时重放）
 
+pub mod blame;
+pub mod blob;
+pub mod bridge;
+pub mod causal;
 #[allow(clippy::module_inception)]
 pub mod change;
+pub mod comment;
+pub mod compaction;
+pub mod history;
 pub mod merge;
+pub mod notebook;
 pub mod operation;
+pub mod patch;
+pub mod presence;
+pub mod revert;
+pub mod signing;
 pub mod snapshot;
+pub mod sparse;
+pub mod store;
+pub mod sync;
+pub mod synthetic;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+pub mod text;
 pub mod thread;
+pub mod threeway;
+pub mod topology;
 pub mod version;
+pub mod wal;
 
 // 为了方便重新导出主要类型
+pub use blame::{Blame, BlameHunk, FileBlame};
+pub use blob::{BlobStore, MaterializeStats};
+pub use bridge::{ExportMode, ExportedBundle, ExportedCommit, GitBridge, ImportedCommit};
+pub use causal::CausalBuffer;
 pub use change::Change;
+pub use comment::{CodeAnchor, Comment, CommentThread};
+pub use compaction::{CompactionPolicy, CompactionStats};
+pub use history::{HistoryEntry, HistoryPage, HistoryRange, SnapshotHandle};
 pub use merge::MergeEngine;
+pub use notebook::{CellOperation, Notebook};
 pub use operation::Operation;
-pub use snapshot::Snapshot;
-pub use thread::Thread;
+pub use patch::{Patch, PatchExporter, PatchFile, PatchFileStatus, PatchSeries};
+pub use presence::{
+    CursorPosition, ParticipantKind, Presence, PresenceEvent, PresenceTracker, Selection,
+};
+pub use signing::{AuthorIdentity, ChangeSignature, PublicIdentity, SignaturePolicy, TrustedKeys};
+pub use snapshot::{Snapshot, SnapshotCacheConfig, SnapshotCacheStats, SnapshotGenerator};
+pub use sparse::{SparseCheckout, SparseConfig};
+pub use store::{ChangeStore, CheckpointStats, FileChangeStore, StoredGraph};
+pub use sync::{SyncMessage, SyncSession, SyncStats};
+pub use text::TextOperation;
+pub use thread::{MergeOutcome, Thread};
+pub use threeway::{FileMerge, FileMergeStatus, MergeReport, ThreeWayMerge};
+pub use topology::{MergeStatus, ThreadTopology};
 pub use version::VectorClock;
+pub use wal::{WalRecord, WriteAheadLog};
@@ -0,0 +1,474 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// 单元格类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CellType {
+    Code,
+    Markdown,
+    Raw,
+}
+
+/// 单元格中由本模块解析的字段，其余字段原样保存在 `extra` 中
+const CELL_FIELDS: [&str; 6] = [
+    "id",
+    "cell_type",
+    "source",
+    "metadata",
+    "outputs",
+    "execution_count",
+];
+
+/// Notebook 顶层由本模块解析的字段
+const NOTEBOOK_FIELDS: [&str; 4] = ["cells", "metadata", "nbformat", "nbformat_minor"];
+
+/// Notebook 单元格
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NotebookCell {
+    /// 单元格 ID（nbformat 4.5+；旧文件按位置生成 `cell-<index>`，只在内存中使用，不写回文件）
+    pub id: String,
+    pub cell_type: CellType,
+    pub source: String,
+    #[serde(default)]
+    pub metadata: Value,
+    /// 仅代码单元格有输出
+    #[serde(default)]
+    pub outputs: Vec<Value>,
+    #[serde(default)]
+    pub execution_count: Option<i64>,
+    /// 未识别的字段（如 `attachments`），序列化时原样写回
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl NotebookCell {
+    pub fn new(id: &str, cell_type: CellType, source: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            cell_type,
+            source: source.to_string(),
+            metadata: json!({}),
+            outputs: Vec::new(),
+            execution_count: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+/// 单元格级别的操作，作为 `Operation::NotebookCell` 的载荷
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CellOperation {
+    /// 在指定位置插入单元格
+    InsertCell { index: usize, cell: NotebookCell },
+    /// 替换单元格内容（源码、输出、元数据）
+    UpdateCell { cell: NotebookCell },
+    /// 删除单元格
+    DeleteCell { cell_id: String },
+    /// 移动单元格到新位置
+    MoveCell { cell_id: String, new_index: usize },
+}
+
+/// 单元格级别的差异，供前端展示
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CellDiff {
+    Added {
+        index: usize,
+        cell: NotebookCell,
+    },
+    Removed {
+        index: usize,
+        cell: NotebookCell,
+    },
+    Modified {
+        cell_id: String,
+        old_source: String,
+        new_source: String,
+        outputs_changed: bool,
+    },
+    Moved {
+        cell_id: String,
+        from: usize,
+        to: usize,
+    },
+}
+
+/// Jupyter Notebook (.ipynb) 的结构化表示
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notebook {
+    pub cells: Vec<NotebookCell>,
+    pub metadata: Value,
+    pub nbformat: u64,
+    pub nbformat_minor: u64,
+    /// 未识别的顶层字段，序列化时原样写回
+    pub extra: Map<String, Value>,
+}
+
+impl Notebook {
+    /// 路径是否为 Notebook 文件
+    pub fn is_notebook(path: &str) -> bool {
+        path.to_lowercase().ends_with(".ipynb")
+    }
+
+    /// 文件格式是否带有稳定的单元格 ID（nbformat 4.5+）
+    ///
+    /// 旧格式的 ID 按位置生成，增删单元格后会变化，不能跨多次解析引用同一单元格。
+    pub fn has_cell_ids(&self) -> bool {
+        (self.nbformat, self.nbformat_minor) >= (4, 5)
+    }
+
+    /// 解析 .ipynb 内容
+    pub fn parse(content: &[u8]) -> anyhow::Result<Self> {
+        let value: Value = serde_json::from_slice(content)?;
+        let raw_cells = value
+            .get("cells")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow::anyhow!("Notebook has no cells array"))?;
+
+        let mut cells = Vec::with_capacity(raw_cells.len());
+        for (index, raw) in raw_cells.iter().enumerate() {
+            let cell_type =
+                serde_json::from_value(raw.get("cell_type").cloned().unwrap_or_default())?;
+            let source = match raw.get("source") {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+                _ => String::new(),
+            };
+            cells.push(NotebookCell {
+                id: raw
+                    .get("id")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("cell-{}", index)),
+                cell_type,
+                source,
+                metadata: raw.get("metadata").cloned().unwrap_or_else(|| json!({})),
+                outputs: raw
+                    .get("outputs")
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default(),
+                execution_count: raw.get("execution_count").and_then(Value::as_i64),
+                extra: unknown_fields(raw, &CELL_FIELDS),
+            });
+        }
+
+        Ok(Self {
+            cells,
+            metadata: value.get("metadata").cloned().unwrap_or_else(|| json!({})),
+            nbformat: value.get("nbformat").and_then(Value::as_u64).unwrap_or(4),
+            nbformat_minor: value
+                .get("nbformat_minor")
+                .and_then(Value::as_u64)
+                .unwrap_or(5),
+            extra: unknown_fields(&value, &NOTEBOOK_FIELDS),
+        })
+    }
+
+    /// 序列化为 .ipynb 内容（单空格缩进，与 Jupyter 保持一致）
+    ///
+    /// 保留原有的格式版本与未识别字段；旧格式（4.5 之前）不写出单元格 ID。
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let with_ids = self.has_cell_ids();
+        let cells: Vec<Value> = self
+            .cells
+            .iter()
+            .map(|cell| {
+                let mut raw = cell.extra.clone();
+                raw.insert("cell_type".to_string(), json!(cell.cell_type));
+                if with_ids {
+                    raw.insert("id".to_string(), json!(cell.id));
+                }
+                raw.insert("metadata".to_string(), cell.metadata.clone());
+                let lines: Vec<&str> = cell.source.split_inclusive('\n').collect();
+                raw.insert("source".to_string(), json!(lines));
+                if cell.cell_type == CellType::Code {
+                    raw.insert("execution_count".to_string(), json!(cell.execution_count));
+                    raw.insert("outputs".to_string(), json!(cell.outputs));
+                }
+                Value::Object(raw)
+            })
+            .collect();
+
+        let mut notebook = self.extra.clone();
+        notebook.insert("cells".to_string(), json!(cells));
+        notebook.insert("metadata".to_string(), self.metadata.clone());
+        notebook.insert("nbformat".to_string(), json!(self.nbformat));
+        notebook.insert("nbformat_minor".to_string(), json!(self.nbformat_minor));
+
+        let mut buffer = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+        let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+        notebook.serialize(&mut serializer)?;
+        buffer.push(b'\n');
+        Ok(buffer)
+    }
+
+    /// 单元格 ID 列表
+    pub fn cell_ids(&self) -> Vec<String> {
+        self.cells.iter().map(|c| c.id.clone()).collect()
+    }
+
+    /// 查找单元格位置
+    pub fn position(&self, cell_id: &str) -> Option<usize> {
+        self.cells.iter().position(|c| c.id == cell_id)
+    }
+
+    /// 应用单元格操作
+    pub fn apply(&mut self, op: &CellOperation) -> anyhow::Result<()> {
+        match op {
+            CellOperation::InsertCell { index, cell } => {
+                if self.position(&cell.id).is_some() {
+                    return Err(anyhow::anyhow!("Cell already exists: {}", cell.id));
+                }
+                let index = (*index).min(self.cells.len());
+                self.cells.insert(index, cell.clone());
+            }
+            CellOperation::UpdateCell { cell } => {
+                let index = self.require(&cell.id)?;
+                self.cells[index] = cell.clone();
+            }
+            CellOperation::DeleteCell { cell_id } => {
+                let index = self.require(cell_id)?;
+                self.cells.remove(index);
+            }
+            CellOperation::MoveCell { cell_id, new_index } => {
+                let index = self.require(cell_id)?;
+                let cell = self.cells.remove(index);
+                let new_index = (*new_index).min(self.cells.len());
+                self.cells.insert(new_index, cell);
+            }
+        }
+        Ok(())
+    }
+
+    /// 计算将 `self` 变为 `target` 所需的单元格操作（按顺序应用）
+    pub fn operations_to(&self, target: &Notebook) -> Vec<CellOperation> {
+        let mut ops = Vec::new();
+        let mut current: Vec<String> = self.cell_ids();
+
+        for cell in &self.cells {
+            if target.position(&cell.id).is_none() {
+                ops.push(CellOperation::DeleteCell {
+                    cell_id: cell.id.clone(),
+                });
+                current.retain(|id| id != &cell.id);
+            }
+        }
+
+        for (index, cell) in target.cells.iter().enumerate() {
+            match current.iter().position(|id| id == &cell.id) {
+                Some(pos) if pos == index => {}
+                Some(pos) => {
+                    let id = current.remove(pos);
+                    current.insert(index, id);
+                    ops.push(CellOperation::MoveCell {
+                        cell_id: cell.id.clone(),
+                        new_index: index,
+                    });
+                }
+                None => {
+                    current.insert(index, cell.id.clone());
+                    ops.push(CellOperation::InsertCell {
+                        index,
+                        cell: cell.clone(),
+                    });
+                    continue;
+                }
+            }
+            if let Some(old) = self.position(&cell.id).map(|i| &self.cells[i])
+                && old != cell
+            {
+                ops.push(CellOperation::UpdateCell { cell: cell.clone() });
+            }
+        }
+
+        ops
+    }
+
+    /// 计算两个 Notebook 之间的单元格级差异
+    pub fn diff(&self, target: &Notebook) -> Vec<CellDiff> {
+        let mut diffs = Vec::new();
+
+        for (index, cell) in self.cells.iter().enumerate() {
+            if target.position(&cell.id).is_none() {
+                diffs.push(CellDiff::Removed {
+                    index,
+                    cell: cell.clone(),
+                });
+            }
+        }
+
+        // 仅在双方共有的单元格之间比较相对顺序，避免插入/删除导致的误报
+        let common_old: Vec<&str> = self
+            .cells
+            .iter()
+            .filter(|c| target.position(&c.id).is_some())
+            .map(|c| c.id.as_str())
+            .collect();
+        let common_new: Vec<&str> = target
+            .cells
+            .iter()
+            .filter(|c| self.position(&c.id).is_some())
+            .map(|c| c.id.as_str())
+            .collect();
+
+        for (index, cell) in target.cells.iter().enumerate() {
+            let Some(old_index) = self.position(&cell.id) else {
+                diffs.push(CellDiff::Added {
+                    index,
+                    cell: cell.clone(),
+                });
+                continue;
+            };
+            let old = &self.cells[old_index];
+            let from = common_old.iter().position(|id| *id == cell.id);
+            let to = common_new.iter().position(|id| *id == cell.id);
+            if from != to {
+                diffs.push(CellDiff::Moved {
+                    cell_id: cell.id.clone(),
+                    from: old_index,
+                    to: index,
+                });
+            }
+            if old.source != cell.source || old.outputs != cell.outputs {
+                diffs.push(CellDiff::Modified {
+                    cell_id: cell.id.clone(),
+                    old_source: old.source.clone(),
+                    new_source: cell.source.clone(),
+                    outputs_changed: old.outputs != cell.outputs,
+                });
+            }
+        }
+
+        diffs
+    }
+
+    fn require(&self, cell_id: &str) -> anyhow::Result<usize> {
+        self.position(cell_id)
+            .ok_or_else(|| anyhow::anyhow!("Cell not found: {}", cell_id))
+    }
+}
+
+/// 对象中不属于 `known` 的字段
+fn unknown_fields(value: &Value, known: &[&str]) -> Map<String, Value> {
+    value
+        .as_object()
+        .map(|object| {
+            object
+                .iter()
+                .filter(|(key, _)| !known.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 将单元格操作应用到 .ipynb 文件内容上
+pub fn apply_cell_operation(content: &[u8], op: &CellOperation) -> anyhow::Result<Vec<u8>> {
+    let mut notebook = Notebook::parse(content)?;
+    notebook.apply(op)?;
+    notebook.to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r##"{
+ "cells": [
+  {"cell_type": "markdown", "metadata": {}, "source": ["# Title\n", "intro"]},
+  {"cell_type": "code", "execution_count": 1, "metadata": {}, "outputs": [], "source": "print(1)"},
+  {"cell_type": "code", "execution_count": 2, "metadata": {}, "outputs": [], "source": "print(2)"}
+ ],
+ "metadata": {"kernelspec": {"name": "python3"}},
+ "nbformat": 4,
+ "nbformat_minor": 4
+}"##;
+
+    #[test]
+    fn test_notebook_roundtrip() {
+        let notebook = Notebook::parse(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(notebook.cells.len(), 3);
+        assert_eq!(notebook.cells[0].source, "# Title\nintro");
+        assert_eq!(notebook.cells[1].id, "cell-1");
+        assert_eq!(notebook.cells[1].execution_count, Some(1));
+
+        let bytes = notebook.to_bytes().unwrap();
+        let reparsed = Notebook::parse(&bytes).unwrap();
+        assert_eq!(reparsed.cells, notebook.cells);
+        // 4.4 格式保持原版本，不写出生成的 ID
+        assert_eq!(reparsed.nbformat_minor, 4);
+        assert!(!notebook.has_cell_ids());
+        assert!(!String::from_utf8(bytes).unwrap().contains("\"id\""));
+    }
+
+    #[test]
+    fn test_notebook_preserves_unknown_fields() {
+        let content = r##"{
+ "cells": [
+  {"attachments": {"a.png": {"image/png": "AAAA"}}, "cell_type": "markdown", "id": "m1", "metadata": {}, "source": "![a](attachment:a.png)"}
+ ],
+ "metadata": {},
+ "nbformat": 4,
+ "nbformat_minor": 5,
+ "signature": "sha256:1"
+}"##;
+        let notebook = Notebook::parse(content.as_bytes()).unwrap();
+        assert!(notebook.has_cell_ids());
+        assert!(notebook.cells[0].extra.contains_key("attachments"));
+
+        let written: Value = serde_json::from_slice(&notebook.to_bytes().unwrap()).unwrap();
+        assert_eq!(written["signature"], "sha256:1");
+        assert_eq!(written["cells"][0]["id"], "m1");
+        assert_eq!(
+            written["cells"][0]["attachments"]["a.png"]["image/png"],
+            "AAAA"
+        );
+        // 单元格操作的载荷同样保留这些字段
+        let op = CellOperation::UpdateCell {
+            cell: notebook.cells[0].clone(),
+        };
+        let json = serde_json::to_value(&op).unwrap();
+        assert_eq!(serde_json::from_value::<CellOperation>(json).unwrap(), op);
+    }
+
+    #[test]
+    fn test_notebook_cell_operations_and_diff() {
+        let old = Notebook::parse(SAMPLE.as_bytes()).unwrap();
+        let mut new = old.clone();
+        new.cells.swap(1, 2);
+        new.cells[2].source = "print(100)".to_string();
+        new.cells.remove(0);
+        new.cells
+            .push(NotebookCell::new("extra", CellType::Markdown, "notes"));
+
+        let ops = old.operations_to(&new);
+        let mut patched = old.clone();
+        for op in &ops {
+            patched.apply(op).unwrap();
+        }
+        assert_eq!(patched.cells, new.cells);
+
+        let diffs = old.diff(&new);
+        assert!(diffs.contains(&CellDiff::Modified {
+            cell_id: "cell-1".to_string(),
+            old_source: "print(1)".to_string(),
+            new_source: "print(100)".to_string(),
+            outputs_changed: false,
+        }));
+        assert!(
+            diffs
+                .iter()
+                .any(|d| matches!(d, CellDiff::Removed { index: 0, .. }))
+        );
+        assert!(
+            diffs
+                .iter()
+                .any(|d| matches!(d, CellDiff::Added { index: 2, .. }))
+        );
+        assert!(diffs.iter().any(|d| matches!(d, CellDiff::Moved { .. })));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::common::change::notebook::CellOperation;
+use crate::common::change::text::TextOperation;
 use crate::common::meta::ast::MetaNode;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -24,8 +26,14 @@ pub enum Operation {
     },
     /// 文件写入操作
     FileWrite { path: String, content: Vec<u8> },
+    /// 文件写入操作（内容以哈希引用 Blob 存储）
+    FileWriteRef { path: String, blob: String },
     /// 文件删除操作
     FileDelete { path: String },
+    /// Notebook 单元格级操作
+    NotebookCell { path: String, op: CellOperation },
+    /// 文本文件的字符级编辑
+    TextEdit { path: String, op: TextOperation },
     /// 自定义 Mock 操作
     Mock { kind: String, data: String },
 }
@@ -69,12 +77,79 @@ impl Operation {
         Operation::FileDelete { path }
     }
 
+    /// 创建 Notebook 单元格操作
+    pub fn notebook_cell(path: String, op: CellOperation) -> Self {
+        Operation::NotebookCell { path, op }
+    }
+
+    /// 创建文本编辑操作
+    pub fn text_edit(path: String, op: TextOperation) -> Self {
+        Operation::TextEdit { path, op }
+    }
+
     pub fn mock(kind: &str, data: &str) -> Self {
         Operation::Mock {
             kind: kind.to_string(),
             data: data.to_string(),
         }
     }
+
+    /// 文件级操作涉及的路径（节点操作没有路径）
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Operation::FileWrite { path, .. }
+            | Operation::FileWriteRef { path, .. }
+            | Operation::FileDelete { path }
+            | Operation::NotebookCell { path, .. }
+            | Operation::TextEdit { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// 单行操作摘要（不含文件内容），供时间线等界面展示
+    pub fn summary(&self) -> String {
+        match self {
+            Operation::Insert {
+                parent_id, index, ..
+            } => match parent_id {
+                Some(parent) => format!("insert node at {} under {}", index, parent),
+                None => format!("insert root node at {}", index),
+            },
+            Operation::Update { node_id, .. } => format!("update node {}", node_id),
+            Operation::Delete { node_id } => format!("delete node {}", node_id),
+            Operation::Move {
+                node_id, new_index, ..
+            } => format!("move node {} to {}", node_id, new_index),
+            Operation::FileWrite { path, content } => {
+                format!("write {} ({} bytes)", path, content.len())
+            }
+            Operation::FileWriteRef { path, .. } => format!("write {}", path),
+            Operation::FileDelete { path } => format!("delete {}", path),
+            Operation::NotebookCell { path, op } => {
+                let action = match op {
+                    CellOperation::InsertCell { .. } => "insert cell",
+                    CellOperation::UpdateCell { .. } => "update cell",
+                    CellOperation::DeleteCell { .. } => "delete cell",
+                    CellOperation::MoveCell { .. } => "move cell",
+                };
+                format!("{} in {}", action, path)
+            }
+            Operation::TextEdit { path, op } => match op {
+                TextOperation::Insert { offset, text, .. } => {
+                    format!(
+                        "insert {} chars at {} in {}",
+                        text.chars().count(),
+                        offset,
+                        path
+                    )
+                }
+                TextOperation::Delete { offset, len, .. } => {
+                    format!("delete {} chars at {} in {}", len, offset, path)
+                }
+            },
+            Operation::Mock { kind, .. } => kind.clone(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,487 @@
+use crate::common::change::Change;
+use crate::common::change::bridge::AUTHOR_DOMAIN;
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::snapshot::{apply_files, materialize_files};
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::change::threeway::line_edits;
+use crate::common::provider::traits::StorageProvider;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 差异块前后保留的上下文行数（与 `git diff` 默认一致）
+const CONTEXT: usize = 3;
+
+/// 补丁中单个文件的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatchFileStatus {
+    Added,
+    Modified,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatchFile {
+    pub path: String,
+    pub status: PatchFileStatus,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// 非 UTF-8 内容，补丁中只标记为二进制差异
+    pub binary: bool,
+}
+
+/// 一个 Change 对应的补丁
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Patch {
+    /// 在补丁系列中的序号（从 1 开始）
+    pub number: usize,
+    pub change_id: Uuid,
+    pub author_id: Uuid,
+    pub author_name: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub subject: String,
+    pub files: Vec<PatchFile>,
+    /// `diff --git` 格式的统一差异
+    pub diff: String,
+}
+
+impl Patch {
+    /// `git format-patch` 风格的文件名，如 `0001-write-src-lib.rs.patch`
+    pub fn file_name(&self) -> String {
+        let mut slug = String::new();
+        for c in self.subject.chars() {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+                slug.push(c);
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let slug: String = slug.trim_end_matches('-').chars().take(52).collect();
+        format!("{:04}-{}.patch", self.number, slug.trim_end_matches('-'))
+    }
+
+    /// 邮件格式的补丁，`total` 为系列中的补丁总数
+    pub fn to_email(&self, total: usize) -> String {
+        let name = self
+            .author_name
+            .clone()
+            .unwrap_or_else(|| self.author_id.to_string());
+        let tag = if total > 1 {
+            format!("[PATCH {}/{}]", self.number, total)
+        } else {
+            "[PATCH]".to_string()
+        };
+        let mut email = format!(
+            "From {} Mon Sep 17 00:00:00 2001\nFrom: {} <{}@{}>\nDate: {}\nSubject: {} {}\n\nZhiyun-Change: {}\n---\n",
+            self.change_id.simple(),
+            name,
+            self.author_id,
+            AUTHOR_DOMAIN,
+            self.timestamp.to_rfc2822(),
+            tag,
+            self.subject,
+            self.change_id
+        );
+        for file in &self.files {
+            let stat = if file.binary {
+                "Bin".to_string()
+            } else {
+                format!(
+                    "{} {}{}",
+                    file.insertions + file.deletions,
+                    "+".repeat(file.insertions.min(40)),
+                    "-".repeat(file.deletions.min(40))
+                )
+            };
+            email.push_str(&format!(" {} | {}\n", file.path, stat));
+        }
+        let (insertions, deletions) = self
+            .files
+            .iter()
+            .fold((0, 0), |(i, d), f| (i + f.insertions, d + f.deletions));
+        email.push_str(&format!(
+            " {} file{} changed, {} insertions(+), {} deletions(-)\n\n",
+            self.files.len(),
+            if self.files.len() == 1 { "" } else { "s" },
+            insertions,
+            deletions
+        ));
+        email.push_str(&self.diff);
+        email.push_str("-- \nZhiyun\n\n");
+        email
+    }
+}
+
+/// 线程自分叉点以来的补丁系列
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatchSeries {
+    pub thread_id: ThreadId,
+    /// 补丁所基于的 Change（线程的分叉点；主线为 `None`，即从空仓库开始）
+    pub base: Option<Uuid>,
+    pub patches: Vec<Patch>,
+}
+
+impl PatchSeries {
+    /// 整个系列拼接为单个 mbox，可直接用 `git am` 应用
+    pub fn to_mbox(&self) -> String {
+        self.patches
+            .iter()
+            .map(|p| p.to_email(self.patches.len()))
+            .collect()
+    }
+
+    /// 按 `git format-patch` 的方式每个补丁写入一个文件，返回写入的路径
+    pub async fn write_to(
+        &self,
+        storage: &dyn StorageProvider,
+        dir: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        let dir = dir.trim_end_matches('/');
+        let mut paths = Vec::new();
+        for patch in &self.patches {
+            let path = format!("{}/{}", dir, patch.file_name());
+            storage
+                .write_file(&path, patch.to_email(self.patches.len()).as_bytes())
+                .await?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+/// 将线程的工作导出为补丁系列，供不使用 Zhiyun 的协作者以 `git am` 应用
+///
+/// 自分叉点以来的 Change 按因果顺序逐个物化，每个 Change 生成一个补丁；
+/// 不涉及文件内容变化的 Change（如仅有节点操作）不产生补丁。
+/// 需要 Git bundle 时使用 `GitBridge::bundle`。
+pub struct PatchExporter {
+    threads: Arc<ThreadManager>,
+}
+
+impl PatchExporter {
+    pub fn new(threads: Arc<ThreadManager>) -> Self {
+        Self { threads }
+    }
+
+    pub fn series(&self, thread_id: ThreadId) -> anyhow::Result<PatchSeries> {
+        let thread = self
+            .threads
+            .get_thread(thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+        let base_ids: HashSet<Uuid> = thread
+            .fork_point
+            .map(|fork| self.threads.ancestors(fork))
+            .unwrap_or_default();
+        let pending: Vec<Change> = thread
+            .head_change_id
+            .map(|head| self.threads.ancestors(head))
+            .unwrap_or_default()
+            .difference(&base_ids)
+            .filter_map(|id| self.threads.get_change(*id))
+            .collect();
+
+        let base: Vec<Change> = base_ids
+            .iter()
+            .filter_map(|id| self.threads.get_change(*id))
+            .collect();
+        let mut previous = materialize_files(&base)?;
+        let mut patches = Vec::new();
+        for change in MergeEngine::new().sort_changes(pending) {
+            // 在上一个补丁的文件树上应用本次 Change，避免每次从分叉点之前重放全部历史
+            let mut tree = previous.clone();
+            apply_files(&mut tree, std::slice::from_ref(&change), |_| true)?;
+            let (files, diff) = diff_trees(&previous, &tree);
+            previous = tree;
+            if files.is_empty() {
+                continue;
+            }
+            patches.push(Patch {
+                number: patches.len() + 1,
+                change_id: change.id,
+                author_id: change.author_id,
+                author_name: self.threads.author_name(change.author_id),
+                timestamp: change.timestamp,
+                subject: subject(&change),
+                files,
+                diff,
+            });
+        }
+        Ok(PatchSeries {
+            thread_id,
+            base: thread.fork_point,
+            patches,
+        })
+    }
+}
+
+/// 补丁标题：首个操作的摘要，多个操作时附带其余数量
+fn subject(change: &Change) -> String {
+    match change.operations.as_slice() {
+        [] => format!("Zhiyun change {}", change.id),
+        [only] => only.summary(),
+        [first, rest @ ..] => format!("{} and {} more", first.summary(), rest.len()),
+    }
+}
+
+/// 两次物化之间的逐文件差异
+pub fn diff_trees(
+    before: &BTreeMap<String, Vec<u8>>,
+    after: &BTreeMap<String, Vec<u8>>,
+) -> (Vec<PatchFile>, String) {
+    let paths: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut files = Vec::new();
+    let mut diff = String::new();
+    for path in paths {
+        let (old, new) = (before.get(path), after.get(path));
+        if old == new {
+            continue;
+        }
+        let status = match (old, new) {
+            (None, _) => PatchFileStatus::Added,
+            (_, None) => PatchFileStatus::Deleted,
+            _ => PatchFileStatus::Modified,
+        };
+        diff.push_str(&format!("diff --git a/{} b/{}\n", path, path));
+        match status {
+            PatchFileStatus::Added => diff.push_str("new file mode 100644\n"),
+            PatchFileStatus::Deleted => diff.push_str("deleted file mode 100644\n"),
+            PatchFileStatus::Modified => {}
+        }
+        let mut file = PatchFile {
+            path: path.clone(),
+            status,
+            insertions: 0,
+            deletions: 0,
+            binary: false,
+        };
+        match (as_text(old), as_text(new)) {
+            (Some(a), Some(b)) => {
+                let old_name = old.map_or("/dev/null".to_string(), |_| format!("a/{}", path));
+                let new_name = new.map_or("/dev/null".to_string(), |_| format!("b/{}", path));
+                diff.push_str(&format!("--- {}\n+++ {}\n", old_name, new_name));
+                let (hunks, insertions, deletions) = unified_hunks(a, b);
+                diff.push_str(&hunks);
+                file.insertions = insertions;
+                file.deletions = deletions;
+            }
+            _ => {
+                file.binary = true;
+                diff.push_str(&format!("Binary files a/{} and b/{} differ\n", path, path));
+            }
+        }
+        files.push(file);
+    }
+    (files, diff)
+}
+
+/// 文本内容；含 NUL 或非 UTF-8 时视为二进制，返回 `None`
+fn as_text(content: Option<&Vec<u8>>) -> Option<&str> {
+    match content {
+        Some(bytes) if !bytes.contains(&0) => std::str::from_utf8(bytes).ok(),
+        Some(_) => None,
+        None => Some(""),
+    }
+}
+
+/// 统一差异格式的差异块，返回（文本，新增行数，删除行数）
+fn unified_hunks(before: &str, after: &str) -> (String, usize, usize) {
+    let a: Vec<&str> = before.split_inclusive('\n').collect();
+    let b: Vec<&str> = after.split_inclusive('\n').collect();
+    let edits = line_edits(&a, &b);
+
+    let mut out = String::new();
+    let (mut insertions, mut deletions) = (0, 0);
+    // 新旧文件行号之差（新 - 旧），随已处理的编辑累积
+    let mut offset: isize = 0;
+    let mut i = 0;
+    while i < edits.len() {
+        // 间隔不超过两倍上下文的编辑合并为同一个块
+        let mut j = i;
+        while j + 1 < edits.len() && edits[j + 1].start - edits[j].end <= 2 * CONTEXT {
+            j += 1;
+        }
+        let group = &edits[i..=j];
+        let old_start = group[0].start.saturating_sub(CONTEXT);
+        let old_end = (group[group.len() - 1].end + CONTEXT).min(a.len());
+        let delta: isize = group
+            .iter()
+            .map(|e| e.lines.len() as isize - (e.end - e.start) as isize)
+            .sum();
+        let old_len = old_end - old_start;
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_len),
+            range(
+                (old_start as isize + offset) as usize,
+                (old_len as isize + delta) as usize
+            )
+        ));
+
+        let mut pos = old_start;
+        for edit in group {
+            for line in &a[pos..edit.start] {
+                emit(&mut out, ' ', line);
+            }
+            for line in &a[edit.start..edit.end] {
+                emit(&mut out, '-', line);
+            }
+            for line in edit.lines {
+                emit(&mut out, '+', line);
+            }
+            deletions += edit.end - edit.start;
+            insertions += edit.lines.len();
+            pos = edit.end;
+        }
+        for line in &a[pos..old_end] {
+            emit(&mut out, ' ', line);
+        }
+        offset += delta;
+        i = j + 1;
+    }
+    (out, insertions, deletions)
+}
+
+/// 块头中的行范围；空范围的起点为其前一行
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+fn emit(out: &mut String, prefix: char, line: &str) {
+    out.push(prefix);
+    out.push_str(line);
+    if !line.ends_with('\n') {
+        out.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::bridge::GitBridge;
+    use crate::common::change::operation::Operation;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::common::provider::local::process::LocalProcess;
+    use crate::common::provider::traits::{ExecuteOptions, ExecutionProvider};
+    use std::collections::HashMap;
+
+    async fn git(dir: &str, args: &str) -> String {
+        let env = HashMap::from([
+            ("GIT_AUTHOR_NAME".to_string(), "dev".to_string()),
+            (
+                "GIT_AUTHOR_EMAIL".to_string(),
+                "dev@example.com".to_string(),
+            ),
+            ("GIT_COMMITTER_NAME".to_string(), "dev".to_string()),
+            (
+                "GIT_COMMITTER_EMAIL".to_string(),
+                "dev@example.com".to_string(),
+            ),
+        ]);
+        let result = LocalProcess
+            .execute(
+                &format!("git {}", args),
+                ExecuteOptions {
+                    cwd: Some(dir.to_string()),
+                    env,
+                    timeout_ms: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.exit_code, 0, "git {}: {}", args, result.stderr);
+        result.stdout
+    }
+
+    #[tokio::test]
+    async fn test_patch_series_applies_with_git_am_and_bundles() {
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let original: String = (1..=12).map(|i| format!("line {}\n", i)).collect();
+        threads.commit_test(
+            main,
+            vec![
+                Operation::file_write("src/lib.rs".into(), original.clone().into()),
+                Operation::file_write("old.txt".into(), b"bye".to_vec()),
+            ],
+        );
+        let branch = threads.create_branch(main, "agent").unwrap();
+        let edited = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 11\n", "line 11\nline 11.5\n");
+        threads.commit_test(
+            branch,
+            vec![Operation::file_write(
+                "src/lib.rs".into(),
+                edited.clone().into(),
+            )],
+        );
+        threads.commit_test(
+            branch,
+            vec![
+                Operation::file_delete("old.txt".into()),
+                Operation::file_write("notes.md".into(), b"no newline".to_vec()),
+            ],
+        );
+
+        let series = PatchExporter::new(threads.clone()).series(branch).unwrap();
+        assert_eq!(series.patches.len(), 2);
+        let first = &series.patches[0];
+        assert_eq!(first.file_name(), "0001-write-src-lib.rs-99-bytes.patch");
+        assert_eq!(
+            (first.files[0].insertions, first.files[0].deletions),
+            (2, 1)
+        );
+        // 两处编辑相距较远，生成两个差异块
+        assert_eq!(first.diff.matches("@@ -").count(), 2);
+        let mbox = series.to_mbox();
+        assert!(mbox.contains("Subject: [PATCH 2/2] delete old.txt and 1 more"));
+        assert!(mbox.contains("\\ No newline at end of file"));
+
+        // 在分叉点的仓库上以 git am 应用补丁系列
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_str().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        git(repo, "init -q").await;
+        storage
+            .write_file("src/lib.rs", original.as_bytes())
+            .await
+            .unwrap();
+        storage.write_file("old.txt", b"bye").await.unwrap();
+        git(repo, "add -A").await;
+        git(repo, "commit -q -m base").await;
+        let written = series.write_to(storage.as_ref(), "patches").await.unwrap();
+        git(repo, &format!("am -q {} {}", written[0], written[1])).await;
+        assert_eq!(
+            storage.read_file("src/lib.rs").await.unwrap(),
+            edited.as_bytes()
+        );
+        assert_eq!(storage.read_file("notes.md").await.unwrap(), b"no newline");
+        assert!(!storage.exists("old.txt").await.unwrap());
+        let author = series.patches[1].author_id.to_string();
+        assert_eq!(git(repo, "log --format=%an -1").await.trim(), author);
+
+        // Git bundle：导出线程后打包分叉点之后的提交
+        let bundle_dir = tempfile::tempdir().unwrap();
+        let bundle_repo = bundle_dir.path().to_str().unwrap();
+        let bridge = GitBridge::new(
+            Arc::new(LocalFileSystem::new(bundle_dir.path())),
+            Arc::new(LocalProcess),
+            bundle_repo,
+        );
+        git(bundle_repo, "init -q").await;
+        let bundle = bridge
+            .bundle(&threads, branch, "agent.bundle")
+            .await
+            .unwrap();
+        assert_eq!(bundle.commits, 2);
+        assert!(bundle.base.is_some());
+        git(bundle_repo, "bundle verify -q agent.bundle").await;
+    }
+}
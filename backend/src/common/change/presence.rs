@@ -0,0 +1,354 @@
+use crate::common::change::thread::ThreadId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// 光标配色，按参与者 ID 稳定分配
+const PALETTE: [&str; 8] = [
+    "#e06c75", "#61afef", "#98c379", "#c678dd", "#e5c07b", "#56b6c2", "#d19a66", "#be5046",
+];
+
+/// 参与者类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParticipantKind {
+    Human,
+    Agent,
+}
+
+/// 文本位置（行、列均从 0 开始）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CursorPosition {
+    pub line: u32,
+    pub column: u32,
+}
+
+/// 选区：`anchor` 为起点，`head` 为光标所在端
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selection {
+    pub anchor: CursorPosition,
+    pub head: CursorPosition,
+}
+
+/// 参与者在某个线程中的临时在线状态（不进入变更图）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Presence {
+    pub peer_id: Uuid,
+    pub thread_id: ThreadId,
+    pub kind: ParticipantKind,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub color: String,
+    #[serde(default)]
+    pub active_file: Option<String>,
+    #[serde(default)]
+    pub cursor: Option<CursorPosition>,
+    #[serde(default)]
+    pub selection: Option<Selection>,
+    /// 多光标编辑时的全部选区（首个为主选区，与 `cursor`、`selection` 一致）
+    #[serde(default)]
+    pub selections: Vec<Selection>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Presence {
+    pub fn new(peer_id: Uuid, thread_id: ThreadId, kind: ParticipantKind) -> Self {
+        Self {
+            peer_id,
+            thread_id,
+            kind,
+            name: None,
+            color: color_for(peer_id).to_string(),
+            active_file: None,
+            cursor: None,
+            selection: None,
+            selections: Vec::new(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.color = color.to_string();
+        self
+    }
+
+    /// 切换文件时清除旧文件中的光标与选区
+    pub fn with_file(mut self, path: &str) -> Self {
+        if self.active_file.as_deref() != Some(path) {
+            self.cursor = None;
+            self.selection = None;
+            self.selections.clear();
+        }
+        self.active_file = Some(path.to_string());
+        self
+    }
+
+    pub fn with_cursor(mut self, line: u32, column: u32) -> Self {
+        let position = CursorPosition { line, column };
+        self.cursor = Some(position);
+        self.selections = vec![Selection {
+            anchor: position,
+            head: position,
+        }];
+        self
+    }
+
+    pub fn with_selection(self, anchor: CursorPosition, head: CursorPosition) -> Self {
+        self.with_selections(vec![Selection { anchor, head }])
+    }
+
+    /// 设置多光标选区，首个为主选区；为空时清除光标
+    pub fn with_selections(mut self, selections: Vec<Selection>) -> Self {
+        self.selection = selections.first().copied();
+        self.cursor = self.selection.map(|s| s.head);
+        self.selections = selections;
+        self
+    }
+}
+
+/// 参与者 ID 对应的默认颜色
+pub fn color_for(peer_id: Uuid) -> &'static str {
+    PALETTE[(peer_id.as_u128() % PALETTE.len() as u128) as usize]
+}
+
+/// 在线状态变化
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresenceEvent {
+    /// 新增或更新；`local` 表示由本实例发布（需要广播给对端）
+    Updated { presence: Presence, local: bool },
+    /// 主动离开或过期
+    Left {
+        thread_id: ThreadId,
+        peer_id: Uuid,
+        local: bool,
+    },
+}
+
+struct Entry {
+    presence: Presence,
+    local: bool,
+    seen: Instant,
+}
+
+/// 按线程维护参与者的在线状态
+///
+/// 状态只保存在内存中，超过 `ttl` 未更新即过期；本地发布与远端收到的更新都通过
+/// `subscribe` 广播，`SyncSession` 只把本地发布的更新转发给对端，避免回环。
+pub struct PresenceTracker {
+    ttl: Duration,
+    entries: Mutex<HashMap<(ThreadId, Uuid), Entry>>,
+    events: broadcast::Sender<PresenceEvent>,
+}
+
+impl PresenceTracker {
+    pub fn new(ttl: Duration) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            events,
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PresenceEvent> {
+        self.events.subscribe()
+    }
+
+    /// 发布本实例参与者的状态
+    pub fn publish(&self, mut presence: Presence) {
+        presence.updated_at = Utc::now();
+        self.upsert(presence, true);
+    }
+
+    /// 应用对端转发的状态，早于已知状态的更新被忽略
+    pub fn apply_remote(&self, presence: Presence) {
+        let stale = self
+            .entries
+            .lock()
+            .unwrap()
+            .get(&(presence.thread_id, presence.peer_id))
+            .is_some_and(|e| e.presence.updated_at > presence.updated_at);
+        if !stale {
+            self.upsert(presence, false);
+        }
+    }
+
+    fn upsert(&self, presence: Presence, local: bool) {
+        self.entries.lock().unwrap().insert(
+            (presence.thread_id, presence.peer_id),
+            Entry {
+                presence: presence.clone(),
+                local,
+                seen: Instant::now(),
+            },
+        );
+        let _ = self.events.send(PresenceEvent::Updated { presence, local });
+    }
+
+    /// 移除参与者；`local` 表示由本实例发起的离开
+    pub fn leave(&self, thread_id: ThreadId, peer_id: Uuid, local: bool) {
+        if self
+            .entries
+            .lock()
+            .unwrap()
+            .remove(&(thread_id, peer_id))
+            .is_some()
+        {
+            let _ = self.events.send(PresenceEvent::Left {
+                thread_id,
+                peer_id,
+                local,
+            });
+        }
+    }
+
+    /// 移除过期的状态并广播离开事件，返回被移除的状态
+    pub fn prune(&self) -> Vec<Presence> {
+        let expired: Vec<Entry> = {
+            let mut entries = self.entries.lock().unwrap();
+            let keys: Vec<(ThreadId, Uuid)> = entries
+                .iter()
+                .filter(|(_, e)| e.seen.elapsed() >= self.ttl)
+                .map(|(k, _)| *k)
+                .collect();
+            keys.iter().filter_map(|k| entries.remove(k)).collect()
+        };
+        expired
+            .into_iter()
+            .map(|entry| {
+                let _ = self.events.send(PresenceEvent::Left {
+                    thread_id: entry.presence.thread_id,
+                    peer_id: entry.presence.peer_id,
+                    local: entry.local,
+                });
+                entry.presence
+            })
+            .collect()
+    }
+
+    /// 线程中仍在线的参与者，按参与者 ID 排序
+    pub fn list(&self, thread_id: ThreadId) -> Vec<Presence> {
+        self.prune();
+        self.collect(|e| e.presence.thread_id == thread_id)
+    }
+
+    /// 线程中由本实例发布且仍在线的参与者（握手后发给新连接的对端）
+    pub fn local(&self, thread_id: ThreadId) -> Vec<Presence> {
+        self.prune();
+        self.collect(|e| e.local && e.presence.thread_id == thread_id)
+    }
+
+    fn collect(&self, filter: impl Fn(&Entry) -> bool) -> Vec<Presence> {
+        let mut list: Vec<Presence> = self
+            .entries
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|e| filter(e))
+            .map(|e| e.presence.clone())
+            .collect();
+        list.sort_by_key(|p| p.peer_id);
+        list
+    }
+}
+
+impl Default for PresenceTracker {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(30))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::sync::SyncSession;
+    use crate::common::change::thread::ThreadManager;
+    use std::sync::Arc;
+
+    async fn wait_for(check: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if check() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition not reached");
+    }
+
+    #[tokio::test]
+    async fn test_presence_is_shared_and_expires() {
+        let (alice, bob, agent) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::new_v4());
+        let session = |peer: Uuid, tracker: &Arc<PresenceTracker>| {
+            let threads = Arc::new(ThreadManager::new());
+            let main = threads.get_thread_id_by_name("main").unwrap();
+            let session = SyncSession::new(peer, threads, main)
+                .with_interval(Duration::from_millis(10))
+                .with_presence(tracker.clone());
+            (Arc::new(session), main)
+        };
+        let left = Arc::new(PresenceTracker::new(Duration::from_millis(800)));
+        let right = Arc::new(PresenceTracker::new(Duration::from_millis(800)));
+        let (left_session, left_main) = session(alice, &left);
+        let (right_session, right_main) = session(bob, &right);
+
+        // 握手前发布的状态在握手后发送
+        let human = Presence::new(alice, left_main, ParticipantKind::Human)
+            .with_name("alice")
+            .with_file("src/lib.rs");
+        left.publish(human.clone());
+        let (a, b) = tokio::io::duplex(1 << 16);
+        let left_task = tokio::spawn({
+            let session = left_session.clone();
+            async move { session.run(a).await }
+        });
+        let right_task = tokio::spawn({
+            let session = right_session.clone();
+            async move { session.run(b).await }
+        });
+        wait_for(|| right.list(right_main).len() == 1).await;
+        let seen = &right.list(right_main)[0];
+        assert_eq!(seen.active_file.as_deref(), Some("src/lib.rs"));
+        assert_eq!(seen.color, color_for(alice));
+
+        // 之后的更新实时转发，两端的线程 ID 不同
+        let mut events = left.subscribe();
+        right.publish(
+            Presence::new(agent, right_main, ParticipantKind::Agent)
+                .with_file("src/main.rs")
+                .with_cursor(3, 4),
+        );
+        wait_for(|| left.list(left_main).len() == 2).await;
+        let PresenceEvent::Updated { presence, local } = events.recv().await.unwrap() else {
+            panic!("expected an update");
+        };
+        assert!(!local);
+        assert_eq!(presence.thread_id, left_main);
+        assert_eq!(presence.cursor, Some(CursorPosition { line: 3, column: 4 }));
+
+        left.publish(human.with_cursor(1, 2));
+        wait_for(|| right.list(right_main).iter().any(|p| p.cursor.is_some())).await;
+
+        // 主动离开立即生效，不再更新的状态在 TTL 后过期
+        left.leave(left_main, alice, true);
+        wait_for(|| right.list(right_main).iter().all(|p| p.peer_id != alice)).await;
+        assert_eq!(left.list(left_main)[0].kind, ParticipantKind::Agent);
+        wait_for(|| left.list(left_main).is_empty()).await;
+
+        left_session.close();
+        left_task.await.unwrap().unwrap();
+        right_task.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,251 @@
+use crate::common::change::Change;
+use crate::common::change::merge::{MergeEngine, children};
+use crate::common::change::operation::Operation;
+use crate::common::change::snapshot::{Files, Snapshot, apply_files, materialize_files};
+use crate::common::change::sparse::normalize;
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::change::threeway::{FileMergeStatus, merge_file};
+use crate::common::change::version::VectorClock;
+use crate::common::meta::ast::MetaNode;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+impl ThreadManager {
+    /// 将单个 Change 的修改重放到 `onto` 线程，提交为新的 Change 并返回其 ID
+    ///
+    /// 节点操作原样复制；文件操作以该 Change 的父状态为基准与目标线程做三方合并，
+    /// 只带入该 Change 自身的改动，同一区域存在冲突时不提交。
+    pub fn cherry_pick(
+        &self,
+        change_id: Uuid,
+        onto: ThreadId,
+        author_id: Uuid,
+    ) -> anyhow::Result<Uuid> {
+        let change = self
+            .get_change(change_id)
+            .ok_or_else(|| anyhow::anyhow!("Change not found: {}", change_id))?;
+        let (before, after) = self.file_states(&change)?;
+        let current = self.thread_files(onto)?;
+
+        let mut operations: Vec<Operation> = change
+            .operations
+            .iter()
+            .filter(|op| op.path().is_none())
+            .cloned()
+            .collect();
+        operations.extend(file_operations(&change, &before, &current, &after)?);
+        self.commit_operations(onto, author_id, operations)
+    }
+
+    /// 在 `thread_id` 上提交一个撤销指定 Change 的新 Change，而不回滚其后的历史
+    ///
+    /// 节点操作按逆序取反（插入↔删除、更新恢复旧节点、移动回原位置）；文件操作以该 Change
+    /// 的结果为基准、父状态为目标做三方合并（新建↔删除），之后对同一区域的修改会导致冲突。
+    pub fn revert(
+        &self,
+        change_id: Uuid,
+        thread_id: ThreadId,
+        author_id: Uuid,
+    ) -> anyhow::Result<Uuid> {
+        let operations = self.inverse_operations(change_id, thread_id)?;
+        self.commit_operations(thread_id, author_id, operations)
+    }
+
+    /// 计算在 `thread_id` 当前状态上撤销指定 Change 的操作，不提交
+    ///
+    /// 供需要先写入预写日志与存储、再提交到线程的调用者（如编辑器的撤销）使用。
+    pub fn inverse_operations(
+        &self,
+        change_id: Uuid,
+        thread_id: ThreadId,
+    ) -> anyhow::Result<Vec<Operation>> {
+        let change = self
+            .get_change(change_id)
+            .ok_or_else(|| anyhow::anyhow!("Change not found: {}", change_id))?;
+        let (before, after) = self.file_states(&change)?;
+        let current = self.thread_files(thread_id)?;
+
+        let mut operations = self.inverse_node_operations(&change)?;
+        operations.extend(file_operations(&change, &after, &current, &before)?);
+        Ok(operations)
+    }
+
+    /// 多个 Head 的全部历史（包含自身）
+    fn reachable_changes(&self, heads: &[Uuid]) -> Vec<Change> {
+        let mut ids = HashSet::new();
+        for head in heads {
+            ids.extend(self.ancestors(*head));
+        }
+        ids.into_iter()
+            .filter_map(|id| self.get_change(id))
+            .collect()
+    }
+
+    /// Change 应用前后的文件快照
+    fn file_states(&self, change: &Change) -> anyhow::Result<(Files, Files)> {
+        let before = materialize_files(&self.reachable_changes(&change.parents))?;
+        let mut after = before.clone();
+        apply_files(&mut after, std::slice::from_ref(change), |_| true)?;
+        Ok((before, after))
+    }
+
+    fn thread_files(&self, thread_id: ThreadId) -> anyhow::Result<Files> {
+        let head = self
+            .get_thread(thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?
+            .head_change_id;
+        materialize_files(&self.reachable_changes(&head.into_iter().collect::<Vec<_>>()))
+    }
+
+    /// 在 Change 应用前的元 AST 上逐个求逆操作，按逆序返回
+    fn inverse_node_operations(&self, change: &Change) -> anyhow::Result<Vec<Operation>> {
+        let engine = MergeEngine::new();
+        // 以合成的根模块承载 `parent_id` 为空的插入
+        let root = MetaNode::module("");
+        let root_id = root.id();
+        let mut state = engine.merge(root, &self.reachable_changes(&change.parents))?;
+
+        let mut inverses = Vec::new();
+        for op in change.operations.iter().filter(|op| op.path().is_none()) {
+            let missing = |id: &Uuid| anyhow::anyhow!("Cannot revert: node {} not found", id);
+            let parent = |id: Option<Uuid>| id.filter(|id| *id != root_id);
+            let inverse = match op {
+                Operation::Insert { node, .. } => Some(Operation::delete(node.id())),
+                Operation::Delete { node_id } => {
+                    let (parent_id, index, node) =
+                        locate(&state, *node_id).ok_or_else(|| missing(node_id))?;
+                    Some(Operation::insert(parent(Some(parent_id)), index, node))
+                }
+                Operation::Update { node_id, .. } => {
+                    let old = Snapshot::mock(state.clone())
+                        .find_node(*node_id)
+                        .cloned()
+                        .ok_or_else(|| missing(node_id))?;
+                    Some(Operation::update(*node_id, old))
+                }
+                Operation::Move { node_id, .. } => {
+                    let (parent_id, index, _) =
+                        locate(&state, *node_id).ok_or_else(|| missing(node_id))?;
+                    Some(Operation::r#move(*node_id, parent(Some(parent_id)), index))
+                }
+                _ => None,
+            };
+            inverses.extend(inverse);
+            let step = Change::new(
+                change.author_id,
+                vec![op.clone()],
+                VectorClock::new(),
+                vec![],
+            );
+            state = engine.merge(state, &[step])?;
+        }
+        inverses.reverse();
+        Ok(inverses)
+    }
+}
+
+/// 对 Change 涉及的每个文件做三方合并（`from` → `to` 的改动带入 `current`），
+/// 返回使 `current` 变为合并结果的写入/删除操作
+fn file_operations(
+    change: &Change,
+    from: &Files,
+    current: &Files,
+    to: &Files,
+) -> anyhow::Result<Vec<Operation>> {
+    let mut paths: Vec<&str> = change
+        .operations
+        .iter()
+        .filter_map(Operation::path)
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut operations = Vec::new();
+    let mut conflicts = Vec::new();
+    for path in paths {
+        let path = normalize(path);
+        let (file, merged) = merge_file(path, from.get(path), current.get(path), to.get(path));
+        match merged {
+            _ if file.status == FileMergeStatus::Conflict => conflicts.push(file.path),
+            Some(merged) if merged.as_ref() != current.get(path) => operations.push(match merged {
+                Some(content) => Operation::file_write(path.to_string(), content),
+                None => Operation::file_delete(path.to_string()),
+            }),
+            _ => {}
+        }
+    }
+    if !conflicts.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Conflicting changes in: {}",
+            conflicts.join(", ")
+        ));
+    }
+    Ok(operations)
+}
+
+/// 节点所在的父节点、下标与节点本身
+fn locate(current: &MetaNode, id: Uuid) -> Option<(Uuid, usize, MetaNode)> {
+    let siblings = children(current)?;
+    if let Some(index) = siblings.iter().position(|c| c.id() == id) {
+        return Some((current.id(), index, siblings[index].clone()));
+    }
+    siblings.iter().find_map(|child| locate(child, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(threads: &ThreadManager, thread: ThreadId, path: &str) -> Option<String> {
+        threads
+            .thread_files(thread)
+            .unwrap()
+            .get(path)
+            .map(|c| String::from_utf8_lossy(c).into_owned())
+    }
+
+    #[test]
+    fn test_cherry_pick_and_revert() {
+        let threads = ThreadManager::new();
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let write = |content: &str| Operation::file_write("a.txt".into(), content.into());
+        threads.commit_test(main, vec![write("1\n2\n3\n")]);
+        let feature = threads.create_branch(main, "feature").unwrap();
+        let node = MetaNode::identifier("x");
+        let agent_edit = threads.commit_test(
+            feature,
+            vec![
+                write("1\n2\nthree\n"),
+                Operation::file_write("new.txt".into(), b"n\n".to_vec()),
+                Operation::insert(None, 0, node.clone()),
+            ],
+        );
+        threads.commit_test(main, vec![write("one\n2\n3\n")]);
+
+        // 只带入 agent_edit 自身的改动
+        threads
+            .cherry_pick(agent_edit, main, Uuid::new_v4())
+            .unwrap();
+        assert_eq!(read(&threads, main, "a.txt").unwrap(), "one\n2\nthree\n");
+        assert_eq!(read(&threads, main, "new.txt").unwrap(), "n\n");
+
+        // 撤销后保留之后的修改，新建的文件被删除，插入的节点被删除
+        threads.commit_test(feature, vec![write("0\n1\n2\nthree\n")]);
+        let revert = threads.revert(agent_edit, feature, Uuid::new_v4()).unwrap();
+        assert_eq!(read(&threads, feature, "a.txt").unwrap(), "0\n1\n2\n3\n");
+        assert_eq!(read(&threads, feature, "new.txt"), None);
+        let operations = threads.get_change(revert).unwrap().operations;
+        assert_eq!(operations[0], Operation::delete(node.id()));
+
+        // 撤销插入后再撤销该撤销，节点回到原位置
+        let reinsert = threads.revert(revert, feature, Uuid::new_v4()).unwrap();
+        assert_eq!(
+            threads.get_change(reinsert).unwrap().operations[0],
+            Operation::insert(None, 0, node)
+        );
+
+        // 同一区域被再次修改时撤销失败
+        threads.commit_test(main, vec![write("one\n2\nTHREE\n")]);
+        assert!(threads.revert(agent_edit, main, Uuid::new_v4()).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::common::change::Change;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// Change 上的 Ed25519 签名
+///
+/// 签名覆盖 Change ID 与内容哈希（哈希已涵盖作者、时间戳、操作、版本与父节点），
+/// 因此签名本身不参与哈希计算。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeSignature {
+    pub key_id: String,
+    /// Base64 编码的 64 字节签名
+    pub signature: String,
+}
+
+/// 可公开分发的作者身份（作者 ID 与公钥）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicIdentity {
+    pub author_id: Uuid,
+    pub key_id: String,
+    /// Base64 编码的 32 字节公钥
+    pub public_key: String,
+}
+
+/// 作者的签名身份，持有私钥
+pub struct AuthorIdentity {
+    pub author_id: Uuid,
+    key: SigningKey,
+}
+
+impl AuthorIdentity {
+    /// 为作者生成新的密钥对
+    pub fn generate(author_id: Uuid) -> Self {
+        Self {
+            author_id,
+            key: SigningKey::generate(&mut rand_core::OsRng),
+        }
+    }
+
+    /// 从保存的 32 字节私钥恢复
+    pub fn from_secret(author_id: Uuid, secret: &[u8; 32]) -> Self {
+        Self {
+            author_id,
+            key: SigningKey::from_bytes(secret),
+        }
+    }
+
+    /// 私钥字节（由调用方负责安全保存）
+    pub fn secret(&self) -> [u8; 32] {
+        self.key.to_bytes()
+    }
+
+    pub fn key_id(&self) -> String {
+        key_id(&self.key.verifying_key())
+    }
+
+    pub fn public(&self) -> PublicIdentity {
+        let verifying = self.key.verifying_key();
+        PublicIdentity {
+            author_id: self.author_id,
+            key_id: key_id(&verifying),
+            public_key: STANDARD.encode(verifying.as_bytes()),
+        }
+    }
+
+    /// 为 Change 签名；Change 的作者必须是该身份
+    pub fn sign(&self, change: &mut Change) -> anyhow::Result<()> {
+        if change.author_id != self.author_id {
+            return Err(anyhow::anyhow!(
+                "Cannot sign change {} authored by {}",
+                change.id,
+                change.author_id
+            ));
+        }
+        let signature = self.key.sign(&payload(change));
+        change.signature = Some(ChangeSignature {
+            key_id: self.key_id(),
+            signature: STANDARD.encode(signature.to_bytes()),
+        });
+        Ok(())
+    }
+}
+
+/// 未签名 Change 的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignaturePolicy {
+    /// 接受未签名的 Change，带签名的必须校验通过
+    #[default]
+    Optional,
+    /// 拒绝未签名的 Change
+    Required,
+}
+
+/// 受信任的作者公钥，用于校验提交的 Change
+#[derive(Default)]
+pub struct TrustedKeys {
+    keys: RwLock<HashMap<String, (Uuid, VerifyingKey)>>,
+    policy: SignaturePolicy,
+}
+
+impl TrustedKeys {
+    pub fn new(policy: SignaturePolicy) -> Self {
+        Self {
+            keys: RwLock::new(HashMap::new()),
+            policy,
+        }
+    }
+
+    pub fn policy(&self) -> SignaturePolicy {
+        self.policy
+    }
+
+    /// 信任一个作者公钥；公钥与 `key_id` 不符时返回错误
+    pub fn trust(&self, identity: &PublicIdentity) -> anyhow::Result<()> {
+        let bytes: [u8; 32] = STANDARD
+            .decode(&identity.public_key)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Public key must be 32 bytes"))?;
+        let key = VerifyingKey::from_bytes(&bytes)?;
+        if key_id(&key) != identity.key_id {
+            return Err(anyhow::anyhow!("Key id mismatch: {}", identity.key_id));
+        }
+        self.keys
+            .write()
+            .unwrap()
+            .insert(identity.key_id.clone(), (identity.author_id, key));
+        Ok(())
+    }
+
+    /// 撤销公钥，返回是否存在
+    pub fn revoke(&self, key_id: &str) -> bool {
+        self.keys.write().unwrap().remove(key_id).is_some()
+    }
+
+    /// 该公钥所属的作者
+    pub fn author_of(&self, key_id: &str) -> Option<Uuid> {
+        self.keys
+            .read()
+            .unwrap()
+            .get(key_id)
+            .map(|(author, _)| *author)
+    }
+
+    /// 校验 Change 的签名：公钥必须受信任且属于 Change 的作者
+    pub fn verify(&self, change: &Change) -> anyhow::Result<()> {
+        let Some(signed) = &change.signature else {
+            return match self.policy {
+                SignaturePolicy::Optional => Ok(()),
+                SignaturePolicy::Required => {
+                    Err(anyhow::anyhow!("Change {} is not signed", change.id))
+                }
+            };
+        };
+        let (author, key) = self
+            .keys
+            .read()
+            .unwrap()
+            .get(&signed.key_id)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Unknown signing key: {}", signed.key_id))?;
+        if author != change.author_id {
+            return Err(anyhow::anyhow!(
+                "Key {} does not belong to author {}",
+                signed.key_id,
+                change.author_id
+            ));
+        }
+        let bytes: [u8; 64] = STANDARD
+            .decode(&signed.signature)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Signature must be 64 bytes"))?;
+        key.verify(&payload(change), &Signature::from_bytes(&bytes))
+            .map_err(|_| anyhow::anyhow!("Invalid signature on change {}", change.id))
+    }
+}
+
+/// 公钥 SHA-256 的前 8 字节（十六进制）
+fn key_id(key: &VerifyingKey) -> String {
+    Sha256::digest(key.as_bytes())[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn payload(change: &Change) -> Vec<u8> {
+    let mut payload = change.id.as_bytes().to_vec();
+    payload.extend_from_slice(change.hash.as_bytes());
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::Operation;
+    use crate::common::change::thread::ThreadManager;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_signed_commits_are_verified() {
+        let alice = AuthorIdentity::generate(Uuid::new_v4());
+        let mallory = AuthorIdentity::generate(Uuid::new_v4());
+        let keys = Arc::new(TrustedKeys::new(SignaturePolicy::Required));
+        keys.trust(&alice.public()).unwrap();
+        assert_eq!(keys.author_of(&alice.key_id()), Some(alice.author_id));
+
+        let threads = ThreadManager::new().with_trusted_keys(keys.clone());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+
+        let mut change = Change::mock(alice.author_id, vec![Operation::mock("a", "1")]);
+        assert!(threads.commit_change(main, change.clone()).is_err());
+        assert!(mallory.sign(&mut change).is_err());
+        alice.sign(&mut change).unwrap();
+        threads.commit_change(main, change.clone()).unwrap();
+
+        // 签名随 Change 序列化，私钥可恢复出同一身份
+        let json = serde_json::to_string(&change).unwrap();
+        let restored: Change = serde_json::from_str(&json).unwrap();
+        assert!(keys.verify(&restored).is_ok());
+        let reloaded = AuthorIdentity::from_secret(alice.author_id, &alice.secret());
+        assert_eq!(reloaded.key_id(), alice.key_id());
+
+        // 挪用签名或使用未受信任的公钥都无法通过校验
+        let mut forged = Change::mock(alice.author_id, vec![]);
+        forged.signature = change.signature.clone();
+        assert!(threads.commit_change(main, forged).is_err());
+        let mut impostor = Change::mock(mallory.author_id, vec![]);
+        mallory.sign(&mut impostor).unwrap();
+        assert!(threads.commit_change(main, impostor.clone()).is_err());
+        keys.trust(&mallory.public()).unwrap();
+        threads.commit_change(main, impostor).unwrap();
+
+        keys.revoke(&alice.key_id());
+        assert!(keys.verify(&change).is_err());
+        assert!(
+            TrustedKeys::default()
+                .verify(&Change::mock(Uuid::new_v4(), vec![]))
+                .is_ok()
+        );
+    }
+}
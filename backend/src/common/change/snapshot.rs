@@ -1,14 +1,108 @@
+use crate::common::change::Change;
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::notebook::apply_cell_operation;
+use crate::common::change::operation::Operation;
+use crate::common::change::sparse::{SparseCheckout, normalize};
+use crate::common::change::text::apply_text_operation;
+use crate::common::change::thread::ThreadManager;
 use crate::common::change::version::VectorClock;
 use crate::common::meta::ast::MetaNode;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
 use uuid::Uuid;
 
+/// 文件快照：路径 -> 内容
+pub type Files = BTreeMap<String, Vec<u8>>;
+
+/// 将变动序列完整物化为文件快照，不受稀疏检出的锥限制
+///
+/// 供合并、回滚、补丁导出等需要完整文件内容的场景使用。引用 Blob 的写入操作需先经
+/// `BlobStore::hydrate` 还原。
+pub fn materialize_files(changes: &[Change]) -> anyhow::Result<Files> {
+    let mut files = Files::new();
+    apply_files(&mut files, changes, |_| true)?;
+    Ok(files)
+}
+
+/// 完整物化单个文件，文件不存在时返回 `None`
+pub fn materialize_file(changes: &[Change], path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    SparseCheckout::default().materialize_path(changes, path)
+}
+
+/// 按因果顺序把 Change 的文件操作应用到已有的文件快照上，只处理 `include` 接受的路径
+///
+/// 已物化的前一状态加上新的 Change 即可得到后一状态，不必从头重放。文本或单元格操作
+/// 无法应用（文件不存在、偏移越界或与锚定内容不一致）或遇到未还原的 Blob 引用时返回错误，
+/// 而不是跳过这次编辑。
+pub fn apply_files(
+    files: &mut Files,
+    changes: &[Change],
+    include: impl Fn(&str) -> bool,
+) -> anyhow::Result<()> {
+    for change in MergeEngine::new().sort_changes(changes.to_vec()) {
+        for op in &change.operations {
+            match op {
+                Operation::FileWrite { path, content } if include(path) => {
+                    files.insert(normalize(path).to_string(), content.clone());
+                }
+                Operation::FileDelete { path } if include(path) => {
+                    files.remove(normalize(path));
+                }
+                Operation::FileWriteRef { path, blob } if include(path) => {
+                    return Err(edit_error(
+                        path,
+                        &change,
+                        anyhow::anyhow!("blob {} must be hydrated first", blob),
+                    ));
+                }
+                Operation::NotebookCell { path, op } if include(path) => {
+                    let content = existing(files, path, &change)?;
+                    *content = apply_cell_operation(content, op)
+                        .map_err(|e| edit_error(path, &change, e))?;
+                }
+                Operation::TextEdit { path, op } if include(path) => {
+                    let content = existing(files, path, &change)?;
+                    *content = apply_text_operation(content, op)
+                        .map_err(|e| edit_error(path, &change, e))?;
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 增量编辑的目标文件，不存在时报错
+fn existing<'a>(
+    files: &'a mut Files,
+    path: &str,
+    change: &Change,
+) -> anyhow::Result<&'a mut Vec<u8>> {
+    files
+        .get_mut(normalize(path))
+        .ok_or_else(|| edit_error(path, change, anyhow::anyhow!("file does not exist")))
+}
+
+/// 增量编辑无法应用的错误，指明所属的 Change
+pub(crate) fn edit_error(path: &str, change: &Change, error: anyhow::Error) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Change {} cannot be applied to {}: {}",
+        change.id,
+        normalize(path),
+        error
+    )
+}
+
 /// 快照数据结构，表示某一时刻的完整状态
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Snapshot {
     pub id: Uuid,
     pub root: MetaNode,
     pub version: VectorClock,
+    /// 已物化的文件（稀疏模式下只含锥内与按需展开的文件）
+    #[serde(default)]
+    pub files: Files,
 }
 
 impl Snapshot {
@@ -18,6 +112,7 @@ impl Snapshot {
             id: Uuid::new_v4(),
             root,
             version,
+            files: Files::new(),
         }
     }
 
@@ -76,6 +171,240 @@ impl Snapshot {
     }
 }
 
+/// 快照缓存配置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotCacheConfig {
+    /// 缓存快照的总字节预算（按序列化大小估算），超出时淘汰最久未使用的快照
+    pub max_bytes: usize,
+}
+
+impl Default for SnapshotCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// 快照缓存统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// 未命中时从已缓存的祖先快照增量生成的次数
+    pub incremental: u64,
+    /// 未命中时从空状态完整重放的次数
+    pub full: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+struct CachedSnapshot {
+    snapshot: Snapshot,
+    size: usize,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct SnapshotCache {
+    entries: HashMap<Uuid, CachedSnapshot>,
+    /// 单调递增的访问序号，用于 LRU 淘汰
+    clock: u64,
+    stats: SnapshotCacheStats,
+}
+
+/// 按 Change ID 生成快照，并在字节预算内缓存最近使用的快照
+///
+/// 快照 ID 即其对应的 Change ID。未命中时沿父链寻找最近的已缓存祖先，
+/// 只重放该祖先之后的 Change；找不到时从空的根模块完整重放。
+/// 配置稀疏检出后快照只物化锥内（及按需展开的）文件。
+pub struct SnapshotGenerator {
+    threads: Arc<ThreadManager>,
+    config: SnapshotCacheConfig,
+    sparse: RwLock<SparseCheckout>,
+    cache: Mutex<SnapshotCache>,
+}
+
+impl SnapshotGenerator {
+    pub fn new(threads: Arc<ThreadManager>) -> Self {
+        Self::with_config(threads, SnapshotCacheConfig::default())
+    }
+
+    pub fn with_config(threads: Arc<ThreadManager>, config: SnapshotCacheConfig) -> Self {
+        Self {
+            threads,
+            config,
+            sparse: RwLock::new(SparseCheckout::default()),
+            cache: Mutex::new(SnapshotCache::default()),
+        }
+    }
+
+    /// 只物化稀疏检出锥内的文件
+    pub fn with_sparse(self, sparse: SparseCheckout) -> Self {
+        *self.sparse.write().unwrap() = sparse;
+        self
+    }
+
+    pub fn config(&self) -> &SnapshotCacheConfig {
+        &self.config
+    }
+
+    /// 按需展开锥外文件；发生展开时已缓存的快照缺少该文件，全部丢弃
+    pub fn expand(&self, path: &str) -> bool {
+        let expanded = self.sparse.write().unwrap().expand(path);
+        if expanded {
+            self.clear();
+        }
+        expanded
+    }
+
+    /// 生成 `change_id` 应用后的快照
+    pub fn generate(&self, change_id: Uuid) -> anyhow::Result<Snapshot> {
+        let change = self
+            .threads
+            .get_change(change_id)
+            .ok_or_else(|| anyhow::anyhow!("Change not found: {}", change_id))?;
+        if let Some(snapshot) = self.lookup(change_id) {
+            return Ok(snapshot);
+        }
+
+        let history = self.threads.ancestors(change_id);
+        let base = self.nearest_cached(&change);
+        let incremental = base.is_some();
+        let (root, mut files, replay) = match base {
+            Some(base) => {
+                let covered = self.threads.ancestors(base.id);
+                let replay = history.difference(&covered).copied().collect();
+                (base.root, base.files, replay)
+            }
+            None => (MetaNode::module(""), Files::new(), history),
+        };
+        let changes: Vec<Change> = replay
+            .into_iter()
+            .filter_map(|id| self.threads.get_change(id))
+            .collect();
+        let root = MergeEngine::new().merge(root, &changes)?;
+        {
+            let sparse = self.sparse.read().unwrap();
+            apply_files(&mut files, &changes, |path| sparse.contains(path))?;
+        }
+        let snapshot = Snapshot {
+            id: change_id,
+            root,
+            version: change.version,
+            files,
+        };
+
+        let mut cache = self.cache.lock().unwrap();
+        if incremental {
+            cache.stats.incremental += 1;
+        } else {
+            cache.stats.full += 1;
+        }
+        self.insert(&mut cache, snapshot.clone());
+        Ok(snapshot)
+    }
+
+    pub fn contains(&self, change_id: Uuid) -> bool {
+        self.cache.lock().unwrap().entries.contains_key(&change_id)
+    }
+
+    /// 丢弃缓存的快照（如 Change 被压缩回收后）
+    pub fn invalidate(&self, change_id: Uuid) {
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(entry) = cache.entries.remove(&change_id) {
+            cache.stats.bytes -= entry.size;
+            cache.stats.entries -= 1;
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.entries.clear();
+        cache.stats.bytes = 0;
+        cache.stats.entries = 0;
+    }
+
+    pub fn stats(&self) -> SnapshotCacheStats {
+        self.cache.lock().unwrap().stats.clone()
+    }
+
+    fn lookup(&self, change_id: Uuid) -> Option<Snapshot> {
+        let mut cache = self.cache.lock().unwrap();
+        cache.clock += 1;
+        let clock = cache.clock;
+        let Some(entry) = cache.entries.get_mut(&change_id) else {
+            cache.stats.misses += 1;
+            return None;
+        };
+        entry.last_used = clock;
+        let snapshot = entry.snapshot.clone();
+        cache.stats.hits += 1;
+        Some(snapshot)
+    }
+
+    /// 按广度优先沿父链查找最近的已缓存祖先
+    fn nearest_cached(&self, change: &Change) -> Option<Snapshot> {
+        let mut cache = self.cache.lock().unwrap();
+        let mut visited = HashSet::new();
+        let mut queue: VecDeque<Uuid> = change.parents.iter().copied().collect();
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id) {
+                continue;
+            }
+            cache.clock += 1;
+            let clock = cache.clock;
+            if let Some(entry) = cache.entries.get_mut(&id) {
+                entry.last_used = clock;
+                return Some(entry.snapshot.clone());
+            }
+            if let Some(parent) = self.threads.get_change(id) {
+                queue.extend(parent.parents);
+            }
+        }
+        None
+    }
+
+    /// 写入快照并淘汰最久未使用的条目直到回到预算内；超过整个预算的快照不缓存
+    fn insert(&self, cache: &mut SnapshotCache, snapshot: Snapshot) {
+        let size = serde_json::to_vec(&snapshot.root).map_or(0, |bytes| bytes.len())
+            + snapshot
+                .files
+                .iter()
+                .map(|(path, content)| path.len() + content.len())
+                .sum::<usize>();
+        if size > self.config.max_bytes {
+            return;
+        }
+        while cache.stats.bytes + size > self.config.max_bytes {
+            let oldest = cache
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(id, _)| *id);
+            let Some(oldest) = oldest else { break };
+            if let Some(entry) = cache.entries.remove(&oldest) {
+                cache.stats.bytes -= entry.size;
+                cache.stats.entries -= 1;
+                cache.stats.evictions += 1;
+            }
+        }
+        cache.clock += 1;
+        let last_used = cache.clock;
+        cache.stats.bytes += size;
+        cache.stats.entries += 1;
+        cache.entries.insert(
+            snapshot.id,
+            CachedSnapshot {
+                snapshot,
+                size,
+                last_used,
+            },
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -99,4 +428,111 @@ mod tests {
             panic!("Expected Identifier");
         }
     }
+
+    #[test]
+    fn test_snapshot_generator_lru_and_incremental() {
+        use crate::common::change::operation::Operation;
+
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let mut ids = Vec::new();
+        let mut nodes = Vec::new();
+        for name in ["a", "b", "c"] {
+            let node = MetaNode::identifier(name);
+            nodes.push(node.id());
+            let change = Change::new(
+                Uuid::new_v4(),
+                vec![Operation::insert(None, ids.len(), node)],
+                VectorClock::new(),
+                ids.last().copied().into_iter().collect(),
+            );
+            ids.push(change.id);
+            threads.commit_change(main, change).unwrap();
+        }
+
+        let size = |count: usize| {
+            let changes: Vec<Change> = ids[..count]
+                .iter()
+                .filter_map(|id| threads.get_change(*id))
+                .collect();
+            let root = MergeEngine::new()
+                .merge(MetaNode::module(""), &changes)
+                .unwrap();
+            serde_json::to_vec(&root).unwrap().len()
+        };
+        // 预算只够容纳第一个快照与第三个快照之一
+        let generator = SnapshotGenerator::with_config(
+            threads.clone(),
+            SnapshotCacheConfig {
+                max_bytes: size(1) + size(3) - 1,
+            },
+        );
+
+        let first = generator.generate(ids[0]).unwrap();
+        assert_eq!(first.id, ids[0]);
+        let third = generator.generate(ids[2]).unwrap();
+        assert!(nodes.iter().all(|id| third.find_node(*id).is_some()));
+        // 第三个快照从已缓存的第一个快照增量生成，写入时淘汰了第一个
+        let stats = generator.stats();
+        assert_eq!((stats.full, stats.incremental), (1, 1));
+        assert_eq!(stats.evictions, 1);
+        assert!(!generator.contains(ids[0]) && generator.contains(ids[2]));
+        assert!(stats.bytes <= generator.config().max_bytes);
+
+        generator.generate(ids[2]).unwrap();
+        let stats = generator.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 2));
+
+        generator.invalidate(ids[2]);
+        assert_eq!(generator.stats().bytes, 0);
+        assert!(generator.generate(Uuid::new_v4()).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_generator_respects_sparse_cone() {
+        use crate::common::change::operation::Operation;
+        use crate::common::change::sparse::SparseConfig;
+
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let change = Change::new(
+            Uuid::new_v4(),
+            vec![
+                Operation::file_write("services/api/main.rs".into(), b"api".to_vec()),
+                Operation::file_write("docs/guide.md".into(), b"guide".to_vec()),
+            ],
+            VectorClock::new(),
+            vec![],
+        );
+        let id = change.id;
+        threads.commit_change(main, change).unwrap();
+
+        let generator = SnapshotGenerator::new(threads.clone()).with_sparse(SparseCheckout::new(
+            SparseConfig {
+                enabled: true,
+                include_prefixes: vec!["services/api".into()],
+                max_expanded: None,
+            },
+        ));
+        let snapshot = generator.generate(id).unwrap();
+        assert_eq!(
+            snapshot.files.keys().collect::<Vec<_>>(),
+            vec!["services/api/main.rs"]
+        );
+
+        // 展开锥外文件后重新生成，快照随之包含该文件
+        assert!(generator.expand("docs/guide.md"));
+        assert!(!generator.contains(id));
+        let snapshot = generator.generate(id).unwrap();
+        assert_eq!(
+            snapshot.files.get("docs/guide.md"),
+            Some(&b"guide".to_vec())
+        );
+        assert_eq!(
+            materialize_files(&[threads.get_change(id).unwrap()])
+                .unwrap()
+                .len(),
+            2
+        );
+    }
 }
@@ -0,0 +1,226 @@
+use crate::common::change::Change;
+use crate::common::change::operation::Operation;
+use crate::common::change::snapshot::{Files, apply_files};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// 稀疏检出配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SparseConfig {
+    /// 是否启用稀疏模式（关闭时所有路径均被物化）
+    pub enabled: bool,
+    /// 被物化与索引的路径前缀（"锥"）
+    pub include_prefixes: Vec<String>,
+    /// 按需展开的锥外文件上限，超过后淘汰最早展开的文件
+    pub max_expanded: Option<usize>,
+}
+
+/// 稀疏检出：仅物化配置前缀下的文件，锥外文件在打开时按需展开
+///
+/// 用于超大型 Monorepo，使快照与索引的内存占用保持有界。
+#[derive(Debug, Clone, Default)]
+pub struct SparseCheckout {
+    config: SparseConfig,
+    /// 按需展开的锥外文件，按展开顺序排列
+    expanded: VecDeque<String>,
+}
+
+impl SparseCheckout {
+    pub fn new(config: SparseConfig) -> Self {
+        Self {
+            config,
+            expanded: VecDeque::new(),
+        }
+    }
+
+    /// 获取配置
+    pub fn config(&self) -> &SparseConfig {
+        &self.config
+    }
+
+    /// 路径是否位于配置的前缀锥内
+    pub fn in_cone(&self, path: &str) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+        let path = normalize(path);
+        self.config.include_prefixes.iter().any(|prefix| {
+            let prefix = normalize(prefix).trim_end_matches('/');
+            prefix.is_empty()
+                || path == prefix
+                || path
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// 路径当前是否被物化（锥内或已按需展开）
+    pub fn contains(&self, path: &str) -> bool {
+        self.in_cone(path) || self.expanded.iter().any(|p| p == normalize(path))
+    }
+
+    /// 按需展开锥外文件，返回是否发生了新的展开
+    pub fn expand(&mut self, path: &str) -> bool {
+        if self.contains(path) {
+            return false;
+        }
+        self.expanded.push_back(normalize(path).to_string());
+        if let Some(max) = self.config.max_expanded {
+            while self.expanded.len() > max {
+                self.expanded.pop_front();
+            }
+        }
+        true
+    }
+
+    /// 收起所有按需展开的文件
+    pub fn collapse(&mut self) {
+        self.expanded.clear();
+    }
+
+    /// 当前按需展开的文件列表
+    pub fn expanded_paths(&self) -> Vec<String> {
+        self.expanded.iter().cloned().collect()
+    }
+
+    /// 过滤出作用于已物化路径的操作（非文件操作总是保留）
+    pub fn filter_operations(&self, operations: &[Operation]) -> Vec<Operation> {
+        operations
+            .iter()
+            .filter(|op| op.path().is_none_or(|path| self.contains(path)))
+            .cloned()
+            .collect()
+    }
+
+    /// 将变动序列物化为文件快照（路径 -> 内容），仅包含已物化路径
+    ///
+    /// 引用 Blob 的写入操作需先经 `BlobStore::hydrate` 还原。文本或单元格操作无法应用
+    /// （文件不存在、偏移越界或与锚定内容不一致）时返回错误，而不是跳过这次编辑。
+    /// 不受锥限制的完整物化见 `snapshot::materialize_files`。
+    pub fn materialize(&self, changes: &[Change]) -> anyhow::Result<Files> {
+        let mut files = Files::new();
+        apply_files(&mut files, changes, |path| self.contains(path))?;
+        Ok(files)
+    }
+
+    /// 物化单个文件（用于按需展开后加载锥外文件内容）
+    pub fn materialize_path(
+        &self,
+        changes: &[Change],
+        path: &str,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        let target = normalize(path);
+        let mut files = Files::new();
+        apply_files(&mut files, changes, |p| normalize(p) == target)?;
+        Ok(files.into_values().next())
+    }
+}
+
+pub(crate) fn normalize(path: &str) -> &str {
+    path.trim_start_matches("./").trim_start_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::version::VectorClock;
+    use uuid::Uuid;
+
+    fn sparse(prefixes: &[&str]) -> SparseCheckout {
+        SparseCheckout::new(SparseConfig {
+            enabled: true,
+            include_prefixes: prefixes.iter().map(|p| p.to_string()).collect(),
+            max_expanded: Some(1),
+        })
+    }
+
+    #[test]
+    fn test_sparse_cone_and_expansion() {
+        let mut checkout = sparse(&["services/api/"]);
+        assert!(checkout.in_cone("services/api/main.rs"));
+        assert!(checkout.in_cone("/services/api"));
+        assert!(!checkout.in_cone("services/apiv2/main.rs"));
+
+        assert!(checkout.expand("libs/util.rs"));
+        assert!(checkout.contains("libs/util.rs"));
+        assert!(!checkout.expand("services/api/lib.rs"));
+
+        // 超过上限时淘汰最早展开的文件
+        checkout.expand("libs/other.rs");
+        assert!(!checkout.contains("libs/util.rs"));
+        assert_eq!(checkout.expanded_paths(), vec!["libs/other.rs".to_string()]);
+    }
+
+    #[test]
+    fn test_sparse_materialize() {
+        let checkout = sparse(&["src"]);
+        let change = Change::new(
+            Uuid::new_v4(),
+            vec![
+                Operation::file_write("src/lib.rs".to_string(), b"lib".to_vec()),
+                Operation::file_write("docs/guide.md".to_string(), b"guide".to_vec()),
+            ],
+            VectorClock::new(),
+            vec![],
+        );
+        let changes = vec![change];
+
+        let files = checkout.materialize(&changes).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files.contains_key("src/lib.rs"));
+
+        assert_eq!(
+            checkout
+                .materialize_path(&changes, "docs/guide.md")
+                .unwrap(),
+            Some(b"guide".to_vec())
+        );
+        assert_eq!(checkout.filter_operations(&changes[0].operations).len(), 1);
+    }
+
+    #[test]
+    fn test_materialize_reports_edits_that_do_not_apply() {
+        use crate::common::change::text::TextOperation;
+
+        let change = |ops: Vec<Operation>, parents: Vec<Uuid>| {
+            Change::new(Uuid::new_v4(), ops, VectorClock::new(), parents)
+        };
+        let base = change(
+            vec![Operation::file_write("a.txt".to_string(), b"abc".to_vec())],
+            vec![],
+        );
+        // 两个并发分支各自基于 "abc" 编辑，第二个编辑重放时内容已不同
+        let left = change(
+            vec![Operation::text_edit(
+                "a.txt".to_string(),
+                TextOperation::insert(0, "X").anchored("abc"),
+            )],
+            vec![base.id],
+        );
+        let right = change(
+            vec![Operation::text_edit(
+                "a.txt".to_string(),
+                TextOperation::delete(2, 1).anchored("abc"),
+            )],
+            vec![base.id],
+        );
+        let checkout = SparseCheckout::default();
+        assert_eq!(
+            checkout.materialize(&[base.clone(), left.clone()]).unwrap()["a.txt"],
+            b"Xabc"
+        );
+        let error = checkout
+            .materialize(&[base.clone(), left, right])
+            .unwrap_err();
+        assert!(error.to_string().contains("a.txt"));
+
+        let orphan = change(
+            vec![Operation::text_edit(
+                "b.txt".to_string(),
+                TextOperation::insert(0, "X"),
+            )],
+            vec![base.id],
+        );
+        assert!(checkout.materialize(&[base, orphan]).is_err());
+    }
+}
@@ -0,0 +1,448 @@
+use crate::common::change::Change;
+use crate::common::change::blob::BlobStore;
+use crate::common::change::comment::CommentThread;
+use crate::common::change::snapshot::Snapshot;
+use crate::common::change::thread::{Thread, ThreadManager};
+use crate::common::change::wal::WriteAheadLog;
+use crate::common::provider::traits::StorageProvider;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// 从持久化存储加载的变更图
+#[derive(Debug, Clone, Default)]
+pub struct StoredGraph {
+    pub threads: Vec<Thread>,
+    pub changes: Vec<Change>,
+    pub comments: Vec<CommentThread>,
+}
+
+impl StoredGraph {
+    /// 写入线程管理器（覆盖同名的空占位线程），返回签名校验未通过而被跳过的 Change 及原因
+    pub fn restore_into(self, threads: &ThreadManager) -> Vec<(Uuid, String)> {
+        let mut rejected = Vec::new();
+        for change in self.changes {
+            let id = change.id;
+            if let Err(e) = threads.restore_change(change) {
+                rejected.push((id, e.to_string()));
+            }
+        }
+        for thread in self.threads {
+            threads.restore_thread(thread);
+        }
+        for comment in self.comments {
+            threads.restore_comment(comment);
+        }
+        rejected
+    }
+}
+
+/// 一次检查点的统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointStats {
+    /// 新写入的 Change 数
+    pub changes: usize,
+    pub threads: usize,
+    pub comments: usize,
+    /// 检查点覆盖后从预写日志删除的记录数
+    pub truncated_wal: usize,
+}
+
+/// 变更图的持久化存储
+///
+/// Change 以 ID 为键且不可变，重复写入同一 Change 是幂等的；线程状态按 ID 覆盖写入。
+#[async_trait]
+pub trait ChangeStore: Send + Sync {
+    async fn put_change(&self, change: &Change) -> anyhow::Result<()>;
+
+    async fn put_thread(&self, thread: &Thread) -> anyhow::Result<()>;
+
+    async fn remove_change(&self, id: Uuid) -> anyhow::Result<()>;
+
+    async fn put_comment(&self, comment: &CommentThread) -> anyhow::Result<()>;
+
+    async fn put_snapshot(&self, snapshot: &Snapshot) -> anyhow::Result<()>;
+
+    async fn get_snapshot(&self, id: Uuid) -> anyhow::Result<Option<Snapshot>>;
+
+    /// 已保存快照的 ID
+    async fn list_snapshots(&self) -> anyhow::Result<Vec<Uuid>>;
+
+    /// 已保存的 Change 是否包含该 ID
+    async fn contains_change(&self, id: Uuid) -> anyhow::Result<bool>;
+
+    /// 加载全部线程与 Change
+    async fn load(&self) -> anyhow::Result<StoredGraph>;
+
+    /// 将线程管理器的当前状态写入存储，随后删除已被覆盖的预写日志记录
+    ///
+    /// 日志序号在写入前记录，检查点期间追加的提交保留在日志中，重放时幂等。
+    async fn checkpoint(
+        &self,
+        threads: &ThreadManager,
+        wal: Option<&WriteAheadLog>,
+    ) -> anyhow::Result<CheckpointStats> {
+        let covered = match wal {
+            Some(wal) => wal.last_seq().await,
+            None => None,
+        };
+        let mut stats = CheckpointStats::default();
+        for change in threads.list_changes() {
+            if !self.contains_change(change.id).await? {
+                self.put_change(&change).await?;
+                stats.changes += 1;
+            }
+        }
+        for thread in threads.list_threads() {
+            self.put_thread(&thread).await?;
+            stats.threads += 1;
+        }
+        for comment in threads.list_all_comments() {
+            self.put_comment(&comment).await?;
+            stats.comments += 1;
+        }
+        if let (Some(wal), Some(seq)) = (wal, covered) {
+            stats.truncated_wal = wal.truncate_through(seq).await?;
+        }
+        Ok(stats)
+    }
+}
+
+/// 基于存储提供者的变更图存储
+///
+/// 每个 Change、线程、评论串与快照各存为 `<root>/{changes,threads,comments,snapshots}/<id>.json`，
+/// 单次写入只涉及一个小文件，崩溃最多丢失正在写入的那一条（由预写日志补齐）。
+/// 配置 Blob 存储后文件内容以 `FileWriteRef` 持久化，加载时还原为内联内容。
+pub struct FileChangeStore {
+    storage: Arc<dyn StorageProvider>,
+    root: String,
+    blobs: Option<Arc<BlobStore>>,
+    /// 已完整持久化的 Change ID，避免检查点重复写入
+    known: Mutex<HashSet<Uuid>>,
+}
+
+impl FileChangeStore {
+    /// 打开（必要时创建）存储目录
+    pub async fn open(storage: Arc<dyn StorageProvider>, root: &str) -> anyhow::Result<Self> {
+        let root = root.trim_end_matches('/').to_string();
+        for dir in ["changes", "threads", "comments", "snapshots"] {
+            let path = format!("{}/{}", root, dir);
+            if !storage.exists(&path).await? {
+                storage.create_dir(&path, true).await?;
+            }
+        }
+        let store = Self {
+            storage,
+            root,
+            blobs: None,
+            known: Mutex::new(HashSet::new()),
+        };
+        // 只登记能解析的记录：写入一半的记录视为缺失，下次检查点会重新写入
+        let mut known = HashSet::new();
+        for id in store.ids("changes").await? {
+            if store.read::<Change>("changes", id).await.is_ok() {
+                known.insert(id);
+            }
+        }
+        *store.known.lock().unwrap() = known;
+        Ok(store)
+    }
+
+    /// 将 Change 中的文件内容存入 Blob 存储，记录中只保留哈希引用
+    pub fn with_blob_store(mut self, blobs: Arc<BlobStore>) -> Self {
+        self.blobs = Some(blobs);
+        self
+    }
+
+    /// 还原 Blob 引用；Blob 缺失时原样返回，由恢复流程的哈希校验报告
+    async fn hydrate(&self, change: Change) -> Change {
+        match &self.blobs {
+            Some(blobs) => blobs.hydrate(&change).await.unwrap_or(change),
+            None => change,
+        }
+    }
+
+    fn path(&self, dir: &str, id: Uuid) -> String {
+        format!("{}/{}/{}.json", self.root, dir, id)
+    }
+
+    async fn write<T: Serialize + Sync>(
+        &self,
+        dir: &str,
+        id: Uuid,
+        value: &T,
+    ) -> anyhow::Result<()> {
+        self.storage
+            .write_file_durable(&self.path(dir, id), &serde_json::to_vec(value)?)
+            .await
+    }
+
+    async fn read<T: DeserializeOwned>(&self, dir: &str, id: Uuid) -> anyhow::Result<T> {
+        Ok(serde_json::from_slice(
+            &self.storage.read_file(&self.path(dir, id)).await?,
+        )?)
+    }
+
+    /// 目录下全部记录的 ID（按 ID 排序）
+    async fn ids(&self, dir: &str) -> anyhow::Result<Vec<Uuid>> {
+        let mut ids: Vec<Uuid> = self
+            .storage
+            .list_dir(&format!("{}/{}", self.root, dir))
+            .await?
+            .into_iter()
+            .filter(|meta| !meta.is_dir)
+            .filter_map(|meta| {
+                let name = meta.path.rsplit(['/', '\\']).next()?;
+                name.strip_suffix(".json")?.parse().ok()
+            })
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+#[async_trait]
+impl ChangeStore for FileChangeStore {
+    async fn put_change(&self, change: &Change) -> anyhow::Result<()> {
+        match &self.blobs {
+            Some(blobs) => {
+                let externalized = blobs.externalize(change).await?;
+                self.write("changes", change.id, &externalized).await?
+            }
+            None => self.write("changes", change.id, change).await?,
+        }
+        self.known.lock().unwrap().insert(change.id);
+        Ok(())
+    }
+
+    async fn put_thread(&self, thread: &Thread) -> anyhow::Result<()> {
+        self.write("threads", thread.id, thread).await
+    }
+
+    async fn remove_change(&self, id: Uuid) -> anyhow::Result<()> {
+        self.known.lock().unwrap().remove(&id);
+        let path = self.path("changes", id);
+        if self.storage.exists(&path).await? {
+            self.storage.delete(&path, false).await?;
+        }
+        Ok(())
+    }
+
+    async fn put_comment(&self, comment: &CommentThread) -> anyhow::Result<()> {
+        self.write("comments", comment.id, comment).await
+    }
+
+    async fn put_snapshot(&self, snapshot: &Snapshot) -> anyhow::Result<()> {
+        self.write("snapshots", snapshot.id, snapshot).await
+    }
+
+    async fn get_snapshot(&self, id: Uuid) -> anyhow::Result<Option<Snapshot>> {
+        if !self.storage.exists(&self.path("snapshots", id)).await? {
+            return Ok(None);
+        }
+        Ok(Some(self.read("snapshots", id).await?))
+    }
+
+    async fn list_snapshots(&self) -> anyhow::Result<Vec<Uuid>> {
+        self.ids("snapshots").await
+    }
+
+    async fn contains_change(&self, id: Uuid) -> anyhow::Result<bool> {
+        Ok(self.known.lock().unwrap().contains(&id))
+    }
+
+    /// 无法解析的记录（崩溃时写入了一半）被跳过，由预写日志重放或完整性校验处理
+    async fn load(&self) -> anyhow::Result<StoredGraph> {
+        let mut graph = StoredGraph::default();
+        for id in self.ids("changes").await? {
+            if let Ok(change) = self.read("changes", id).await {
+                graph.changes.push(self.hydrate(change).await);
+            }
+        }
+        for id in self.ids("threads").await? {
+            if let Ok(thread) = self.read("threads", id).await {
+                graph.threads.push(thread);
+            }
+        }
+        for id in self.ids("comments").await? {
+            if let Ok(comment) = self.read("comments", id).await {
+                graph.comments.push(comment);
+            }
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::Operation;
+    use crate::common::change::wal::WalRecord;
+    use crate::common::meta::ast::MetaNode;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_checkpoint_persists_graph_and_truncates_wal() {
+        let dir = tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let wal = WriteAheadLog::open(storage.clone(), ".zhiyun/wal")
+            .await
+            .unwrap();
+        let store = FileChangeStore::open(storage.clone(), ".zhiyun/store")
+            .await
+            .unwrap();
+
+        let threads = ThreadManager::new();
+        let main_id = threads.get_thread_id_by_name("main").unwrap();
+        let first = Change::mock(Uuid::new_v4(), vec![Operation::mock("test", "a")]);
+        threads.commit_change(main_id, first.clone()).unwrap();
+        wal.append(WalRecord::Commit {
+            thread: threads.get_thread(main_id).unwrap(),
+            change: Box::new(first.clone()),
+        })
+        .await
+        .unwrap();
+        let feature = threads.create_branch(main_id, "feature").unwrap();
+
+        let stats = store.checkpoint(&threads, Some(&wal)).await.unwrap();
+        assert_eq!(
+            stats,
+            CheckpointStats {
+                changes: 1,
+                threads: 2,
+                comments: 0,
+                truncated_wal: 1,
+            }
+        );
+        // 已持久化的 Change 不会重复写入
+        let stats = store.checkpoint(&threads, None).await.unwrap();
+        assert_eq!(stats.changes, 0);
+
+        let snapshot = Snapshot::mock(MetaNode::module("root"));
+        store.put_snapshot(&snapshot).await.unwrap();
+        storage
+            .write_file(
+                &format!(".zhiyun/store/changes/{}.json", Uuid::new_v4()),
+                b"{",
+            )
+            .await
+            .unwrap();
+
+        // 重新打开后恢复到新的线程管理器，默认的空 main 被替换
+        let store = FileChangeStore::open(storage, ".zhiyun/store")
+            .await
+            .unwrap();
+        let restored = ThreadManager::new();
+        store.load().await.unwrap().restore_into(&restored);
+        assert_eq!(restored.list_threads().len(), 2);
+        assert_eq!(restored.get_thread_id_by_name("main"), Some(main_id));
+        assert_eq!(
+            restored.get_thread(feature).unwrap().head_change_id,
+            Some(first.id)
+        );
+        assert_eq!(restored.get_change(first.id).unwrap().hash, first.hash);
+        assert_eq!(store.list_snapshots().await.unwrap(), vec![snapshot.id]);
+        assert!(store.get_snapshot(snapshot.id).await.unwrap().is_some());
+        assert!(store.get_snapshot(Uuid::new_v4()).await.unwrap().is_none());
+
+        store.remove_change(first.id).await.unwrap();
+        assert!(!store.contains_change(first.id).await.unwrap());
+        assert!(store.load().await.unwrap().changes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_torn_change_record_is_rewritten_by_checkpoint() {
+        let dir = tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let wal = WriteAheadLog::open(storage.clone(), ".zhiyun/wal")
+            .await
+            .unwrap();
+        let store = FileChangeStore::open(storage.clone(), ".zhiyun/store")
+            .await
+            .unwrap();
+        let threads = ThreadManager::new();
+        let main_id = threads.get_thread_id_by_name("main").unwrap();
+        let change = Change::mock(Uuid::new_v4(), vec![Operation::mock("test", "a")]);
+        threads.commit_change(main_id, change.clone()).unwrap();
+        store.checkpoint(&threads, None).await.unwrap();
+
+        // 记录在崩溃中只写了一半，而预写日志仍覆盖这次提交
+        let path = format!(".zhiyun/store/changes/{}.json", change.id);
+        storage.write_file(&path, b"{\"id\"").await.unwrap();
+        wal.append(WalRecord::Commit {
+            thread: threads.get_thread(main_id).unwrap(),
+            change: Box::new(change.clone()),
+        })
+        .await
+        .unwrap();
+
+        let store = FileChangeStore::open(storage, ".zhiyun/store")
+            .await
+            .unwrap();
+        assert!(!store.contains_change(change.id).await.unwrap());
+        let stats = store.checkpoint(&threads, Some(&wal)).await.unwrap();
+        assert_eq!(stats.changes, 1);
+        assert_eq!(stats.truncated_wal, 1);
+        let loaded = store.load().await.unwrap();
+        assert_eq!(loaded.changes.len(), 1);
+        assert_eq!(loaded.changes[0].hash, change.hash);
+    }
+
+    #[tokio::test]
+    async fn test_file_contents_are_persisted_as_blobs() {
+        let dir = tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let blobs = Arc::new(BlobStore::new(storage.clone(), ".zhiyun/blobs"));
+        let wal = WriteAheadLog::open(storage.clone(), ".zhiyun/wal")
+            .await
+            .unwrap()
+            .with_blob_store(blobs.clone());
+        let store = FileChangeStore::open(storage.clone(), ".zhiyun/store")
+            .await
+            .unwrap()
+            .with_blob_store(blobs.clone());
+
+        let threads = ThreadManager::new();
+        let main_id = threads.get_thread_id_by_name("main").unwrap();
+        let content = b"fn main() {}".to_vec();
+        let change = Change::mock(
+            Uuid::new_v4(),
+            vec![Operation::file_write("src/main.rs".into(), content.clone())],
+        );
+        threads.commit_change(main_id, change.clone()).unwrap();
+        wal.append(WalRecord::Commit {
+            thread: threads.get_thread(main_id).unwrap(),
+            change: Box::new(change.clone()),
+        })
+        .await
+        .unwrap();
+        let (entries, _) = wal.read_all().await.unwrap();
+        let WalRecord::Commit { change: logged, .. } = &entries[0].record else {
+            panic!("Expected commit");
+        };
+        assert_eq!(logged.hash, change.hash);
+        assert!(logged.verify_hash());
+        store.checkpoint(&threads, None).await.unwrap();
+
+        // 记录中只有哈希引用，内容只在 Blob 存储中保存一次
+        let record = storage
+            .read_file(&format!(".zhiyun/store/changes/{}.json", change.id))
+            .await
+            .unwrap();
+        let record = String::from_utf8(record).unwrap();
+        assert!(record.contains(&BlobStore::hash(&content)));
+        assert!(!record.contains("fn main"));
+        assert_eq!(blobs.stats().blobs, 1);
+
+        let loaded = store.load().await.unwrap();
+        assert_eq!(
+            loaded.changes[0].operations,
+            vec![Operation::file_write("src/main.rs".into(), content)]
+        );
+        assert!(loaded.changes[0].verify_hash());
+    }
+}
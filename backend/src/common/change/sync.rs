@@ -0,0 +1,604 @@
+use crate::common::change::Change;
+use crate::common::change::causal::CausalBuffer;
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::presence::{Presence, PresenceEvent, PresenceTracker};
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::change::version::{Relation, VectorClock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{Notify, broadcast, mpsc};
+use uuid::Uuid;
+
+/// 单帧最大字节数，超出视为协议错误
+const MAX_FRAME: u32 = 64 * 1024 * 1024;
+
+/// 同步协议消息
+///
+/// 每帧为 4 字节大端长度前缀加 JSON 消息体。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncMessage {
+    /// 握手：对端据此计算本端缺少的 Change
+    Hello {
+        peer_id: Uuid,
+        clock: VectorClock,
+        heads: Vec<Uuid>,
+    },
+    /// 请求指定的 Change（通常是缓冲区中 Change 缺失的父节点）
+    Request { ids: Vec<Uuid> },
+    /// 一批 Change，按因果顺序排列
+    Changes { changes: Vec<Change> },
+    /// 参与者在本会话线程中的在线状态（临时，不进入变更图）
+    Presence { presence: Presence },
+    /// 参与者离开本会话线程
+    Leave { peer_id: Uuid },
+    /// 结束会话
+    Bye,
+}
+
+/// 写入一帧消息
+pub async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &SyncMessage,
+) -> anyhow::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    let len = u32::try_from(body.len())
+        .ok()
+        .filter(|len| *len <= MAX_FRAME)
+        .ok_or_else(|| anyhow::anyhow!("Sync frame too large: {} bytes", body.len()))?;
+    writer.write_all(&len.to_be_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// 读取一帧消息，连接在帧边界关闭时返回 `None`
+pub async fn read_message<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> anyhow::Result<Option<SyncMessage>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME {
+        return Err(anyhow::anyhow!("Sync frame too large: {} bytes", len));
+    }
+    let mut body = vec![0u8; len as usize];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// 一次会话的统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncStats {
+    pub sent: usize,
+    pub received: usize,
+    /// 双方分叉时本端生成的合并 Change 数
+    pub merges: usize,
+}
+
+#[derive(Default)]
+struct Remote {
+    peer_id: Option<Uuid>,
+    clock: VectorClock,
+    /// 对端已拥有的 Change
+    known: HashSet<Uuid>,
+}
+
+/// 两个实例之间同一线程的实时同步会话
+///
+/// 握手交换向量时钟与 Head 后，双方发送对端缺少的 Change，并按间隔推送之后的本地提交。
+/// 收到的 Change 经 `CausalBuffer` 按因果顺序交付：能接在本地 Head 之后的直接提交（快进），
+/// 与本地并发的先保存；双方分叉时由 ID 较小的一端生成合并 Change，另一端随后快进，
+/// 避免双方同时合并而来回产生新的分叉。
+///
+/// 配置 `PresenceTracker` 后同时转发本线程的在线状态：握手时发送本地参与者，之后实时转发
+/// 本地发布的更新与离开，收到的远端状态按 TTL 自动过期。
+pub struct SyncSession {
+    peer_id: Uuid,
+    threads: Arc<ThreadManager>,
+    thread_id: ThreadId,
+    buffer: CausalBuffer,
+    remote: Mutex<Remote>,
+    stats: Mutex<SyncStats>,
+    interval: Duration,
+    closed: Notify,
+    presence: Option<Arc<PresenceTracker>>,
+}
+
+impl SyncSession {
+    pub fn new(peer_id: Uuid, threads: Arc<ThreadManager>, thread_id: ThreadId) -> Self {
+        let buffer = CausalBuffer::from_threads(&threads);
+        Self {
+            peer_id,
+            threads,
+            thread_id,
+            buffer,
+            remote: Mutex::new(Remote::default()),
+            stats: Mutex::new(SyncStats::default()),
+            interval: Duration::from_millis(200),
+            closed: Notify::new(),
+            presence: None,
+        }
+    }
+
+    /// 推送本地新提交的轮询间隔
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// 同步在线状态
+    pub fn with_presence(mut self, presence: Arc<PresenceTracker>) -> Self {
+        self.presence = Some(presence);
+        self
+    }
+
+    pub fn stats(&self) -> SyncStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// 通知 `run` 发送 `Bye` 并结束
+    pub fn close(&self) {
+        self.closed.notify_one();
+    }
+
+    /// 在连接上运行会话，直到对端结束、连接关闭或调用 `close`
+    pub async fn run<S>(&self, stream: S) -> anyhow::Result<SyncStats>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        // 读取放在独立任务中，避免与定时推送竞争时丢失半帧
+        let (tx, mut incoming) = mpsc::channel(16);
+        let reading = tokio::spawn(async move {
+            loop {
+                let message = read_message(&mut reader).await;
+                let done = !matches!(message, Ok(Some(_)));
+                if tx.send(message).await.is_err() || done {
+                    break;
+                }
+            }
+        });
+
+        let mut presence = self.presence.as_ref().map(|p| p.subscribe());
+        let result = async {
+            write_message(&mut writer, &self.hello()).await?;
+            let mut ticker = tokio::time::interval(self.interval);
+            loop {
+                tokio::select! {
+                    message = incoming.recv() => {
+                        let Some(message) = message.transpose()?.flatten() else { break };
+                        if !self.handle(message, &mut writer).await? {
+                            break;
+                        }
+                    }
+                    _ = ticker.tick() => {
+                        if let Some(presence) = &self.presence {
+                            presence.prune();
+                        }
+                        self.push(&mut writer).await?
+                    }
+                    event = next_presence(&mut presence) => self.forward(event, &mut writer).await?,
+                    _ = self.closed.notified() => {
+                        self.push(&mut writer).await?;
+                        write_message(&mut writer, &SyncMessage::Bye).await?;
+                        break;
+                    }
+                }
+            }
+            anyhow::Ok(())
+        }
+        .await;
+        reading.abort();
+        result?;
+        Ok(self.stats())
+    }
+
+    fn hello(&self) -> SyncMessage {
+        let heads = self.head().into_iter().collect();
+        SyncMessage::Hello {
+            peer_id: self.peer_id,
+            clock: self.buffer.clock(),
+            heads,
+        }
+    }
+
+    fn head(&self) -> Option<Uuid> {
+        self.threads
+            .get_thread(self.thread_id)
+            .and_then(|t| t.head_change_id)
+    }
+
+    /// 处理一条消息，返回是否继续会话
+    async fn handle<W: AsyncWrite + Unpin>(
+        &self,
+        message: SyncMessage,
+        writer: &mut W,
+    ) -> anyhow::Result<bool> {
+        match message {
+            SyncMessage::Hello {
+                peer_id,
+                clock,
+                heads,
+            } => {
+                {
+                    let mut remote = self.remote.lock().unwrap();
+                    remote.peer_id = Some(peer_id);
+                    remote.clock = clock;
+                    for head in heads {
+                        if self.threads.get_change(head).is_some() {
+                            remote.known.extend(self.threads.ancestors(head));
+                        }
+                    }
+                }
+                self.push(writer).await?;
+                if let Some(presence) = &self.presence {
+                    for presence in presence.local(self.thread_id) {
+                        write_message(writer, &SyncMessage::Presence { presence }).await?;
+                    }
+                }
+            }
+            SyncMessage::Request { ids } => {
+                let mut history = HashSet::new();
+                {
+                    // 对端明确请求的 Change 即使曾发送过也要重发
+                    let mut remote = self.remote.lock().unwrap();
+                    for id in &ids {
+                        remote.known.remove(id);
+                    }
+                }
+                for id in ids {
+                    history.extend(self.threads.ancestors(id));
+                }
+                self.send(history, writer).await?;
+            }
+            SyncMessage::Changes { changes } => {
+                self.receive(changes)?;
+                let missing = self.buffer.missing();
+                if !missing.is_empty() {
+                    write_message(writer, &SyncMessage::Request { ids: missing }).await?;
+                }
+            }
+            // 两端的线程 ID 不一定相同，在线状态归属本会话同步的线程
+            SyncMessage::Presence { mut presence } => {
+                if let Some(tracker) = &self.presence {
+                    presence.thread_id = self.thread_id;
+                    tracker.apply_remote(presence);
+                }
+            }
+            SyncMessage::Leave { peer_id } => {
+                if let Some(tracker) = &self.presence {
+                    tracker.leave(self.thread_id, peer_id, false);
+                }
+            }
+            SyncMessage::Bye => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// 将本地发布的本线程在线状态变化转发给对端
+    async fn forward<W: AsyncWrite + Unpin>(
+        &self,
+        event: PresenceEvent,
+        writer: &mut W,
+    ) -> anyhow::Result<()> {
+        let message = match event {
+            PresenceEvent::Updated {
+                presence,
+                local: true,
+            } if presence.thread_id == self.thread_id => SyncMessage::Presence { presence },
+            PresenceEvent::Left {
+                thread_id,
+                peer_id,
+                local: true,
+            } if thread_id == self.thread_id => SyncMessage::Leave { peer_id },
+            _ => return Ok(()),
+        };
+        write_message(writer, &message).await
+    }
+
+    /// 发送本地线程历史中对端尚未拥有的 Change（握手之前不发送）
+    async fn push<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> anyhow::Result<()> {
+        if self.remote.lock().unwrap().peer_id.is_none() {
+            return Ok(());
+        }
+        let history = self
+            .head()
+            .map(|head| self.threads.ancestors(head))
+            .unwrap_or_default();
+        self.send(history, writer).await
+    }
+
+    async fn send<W: AsyncWrite + Unpin>(
+        &self,
+        ids: HashSet<Uuid>,
+        writer: &mut W,
+    ) -> anyhow::Result<()> {
+        let changes: Vec<Change> = {
+            let mut remote = self.remote.lock().unwrap();
+            let changes: Vec<Change> = ids
+                .into_iter()
+                .filter(|id| !remote.known.contains(id))
+                .filter_map(|id| self.threads.get_change(id))
+                .filter(|c| {
+                    // 向量时钟已被对端覆盖的 Change 无需发送（空时钟无法判断，照常发送）
+                    c.version.clocks.is_empty()
+                        || matches!(
+                            c.version.compare(&remote.clock),
+                            Relation::After | Relation::Concurrent
+                        )
+                })
+                .collect();
+            remote.known.extend(changes.iter().map(|c| c.id));
+            changes
+        };
+        if changes.is_empty() {
+            return Ok(());
+        }
+        self.stats.lock().unwrap().sent += changes.len();
+        let changes = MergeEngine::new().sort_changes(changes);
+        write_message(writer, &SyncMessage::Changes { changes }).await
+    }
+
+    /// 经因果缓冲区交付收到的 Change 并应用到本地线程
+    ///
+    /// 全部 Change 先按本地提交的规则校验（哈希与签名），任一失败时整批拒绝且不做任何改动；
+    /// 与本地 Head 并发的 Change 同样经过校验后才存入。
+    fn receive(&self, changes: Vec<Change>) -> anyhow::Result<()> {
+        if self.threads.get_thread(self.thread_id).is_none() {
+            return Err(anyhow::anyhow!("Thread not found"));
+        }
+        for change in &changes {
+            self.threads
+                .verify(change)
+                .map_err(|e| anyhow::anyhow!("Rejected change {} from peer: {}", change.id, e))?;
+        }
+        // 会话开始后的本地提交也需要标记为已交付，否则依赖它们的远端 Change 会一直等待
+        for change in self.threads.list_changes() {
+            if !self.buffer.is_delivered(change.id) {
+                self.buffer.mark_delivered(&change);
+            }
+        }
+        let mut released = Vec::new();
+        {
+            let mut remote = self.remote.lock().unwrap();
+            for change in changes {
+                remote.known.insert(change.id);
+                remote.clock.merge(&change.version);
+                released.extend(self.buffer.receive(change)?);
+            }
+        }
+        self.stats.lock().unwrap().received += released.len();
+
+        let mut head = self.head();
+        let mut concurrent = Vec::new();
+        for change in released {
+            if self.threads.get_change(change.id).is_some() {
+                continue;
+            }
+            let follows = match head {
+                None => true,
+                Some(head) => change
+                    .parents
+                    .iter()
+                    .any(|p| self.threads.is_ancestor(head, *p)),
+            };
+            if follows {
+                head = Some(change.id);
+                self.threads.commit_change(self.thread_id, change)?;
+            } else {
+                concurrent.push(change.id);
+                self.threads.restore_change(change)?;
+            }
+        }
+
+        // 仍未被本地 Head 包含的远端分支末端
+        let ancestors = head.map(|h| self.threads.ancestors(h)).unwrap_or_default();
+        let tips: Vec<Uuid> = concurrent
+            .iter()
+            .copied()
+            .filter(|id| !ancestors.contains(id))
+            .filter(|id| {
+                !concurrent
+                    .iter()
+                    .any(|other| other != id && self.threads.is_ancestor(*id, *other))
+            })
+            .collect();
+        let remote_peer = self.remote.lock().unwrap().peer_id;
+        if let (Some(head), false) = (head, tips.is_empty())
+            && remote_peer.is_some_and(|peer| self.peer_id < peer)
+        {
+            self.merge(head, tips)?;
+        }
+        Ok(())
+    }
+
+    fn merge(&self, head: Uuid, tips: Vec<Uuid>) -> anyhow::Result<()> {
+        let mut version = VectorClock::new();
+        let mut parents = vec![head];
+        parents.extend(tips);
+        for parent in &parents {
+            if let Some(change) = self.threads.get_change(*parent) {
+                version.merge(&change.version);
+            }
+        }
+        version.increment(self.peer_id);
+        let change = Change::new(self.peer_id, Vec::new(), version, parents);
+        self.buffer.mark_delivered(&change);
+        self.threads.commit_change(self.thread_id, change)?;
+        self.stats.lock().unwrap().merges += 1;
+        Ok(())
+    }
+}
+
+/// 下一个在线状态事件；未配置在线状态时永不就绪
+async fn next_presence(receiver: &mut Option<broadcast::Receiver<PresenceEvent>>) -> PresenceEvent {
+    if let Some(receiver) = receiver {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return event,
+                // 落后时跳过旧事件，之后的更新会覆盖
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+    std::future::pending().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::Operation;
+    use crate::common::change::snapshot::materialize_files;
+
+    fn write(path: &str) -> Vec<Operation> {
+        vec![Operation::file_write(
+            path.to_string(),
+            path.as_bytes().to_vec(),
+        )]
+    }
+
+    fn files(threads: &ThreadManager, thread: ThreadId) -> Vec<String> {
+        let head = threads.get_thread(thread).unwrap().head_change_id.unwrap();
+        let history: Vec<Change> = threads
+            .ancestors(head)
+            .into_iter()
+            .filter_map(|id| threads.get_change(id))
+            .collect();
+        materialize_files(&history).unwrap().into_keys().collect()
+    }
+
+    async fn wait_for(check: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if check() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition not reached");
+    }
+
+    #[tokio::test]
+    async fn test_frame_round_trip() {
+        let (mut a, mut b) = tokio::io::duplex(1024);
+        let message = SyncMessage::Request {
+            ids: vec![Uuid::new_v4()],
+        };
+        write_message(&mut a, &message).await.unwrap();
+        drop(a);
+        assert_eq!(read_message(&mut b).await.unwrap(), Some(message));
+        assert_eq!(read_message(&mut b).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_receive_rejects_unverified_batches() {
+        use crate::common::change::signing::{AuthorIdentity, SignaturePolicy, TrustedKeys};
+
+        let peer = AuthorIdentity::generate(Uuid::from_u128(2));
+        let keys = Arc::new(TrustedKeys::new(SignaturePolicy::Required));
+        keys.trust(&peer.public()).unwrap();
+        let threads = Arc::new(ThreadManager::new().with_trusted_keys(keys));
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let session = SyncSession::new(Uuid::from_u128(1), threads.clone(), main);
+
+        let change = |parents: Vec<Uuid>| {
+            let mut version = VectorClock::new();
+            version.increment(peer.author_id);
+            Change::new(
+                peer.author_id,
+                vec![Operation::file_write("a.txt".to_string(), b"a".to_vec())],
+                version,
+                parents,
+            )
+        };
+        let mut signed = change(Vec::new());
+        peer.sign(&mut signed).unwrap();
+        let unsigned = change(vec![signed.id]);
+
+        // 一个未签名的 Change 使整批被拒绝，已签名的也不会存入
+        assert!(
+            session
+                .receive(vec![signed.clone(), unsigned.clone()])
+                .is_err()
+        );
+        assert!(threads.get_change(signed.id).is_none());
+        assert!(threads.get_change(unsigned.id).is_none());
+
+        session.receive(vec![signed.clone()]).unwrap();
+        assert_eq!(
+            threads.get_thread(main).unwrap().head_change_id,
+            Some(signed.id)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_sessions_converge() {
+        let (alice, bob) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let left = Arc::new(ThreadManager::new());
+        let left_main = left.get_thread_id_by_name("main").unwrap();
+        left.commit_operations(left_main, alice, write("base.txt"))
+            .unwrap();
+
+        // 右侧从空线程开始，并在握手前已有一个并发提交
+        let right = Arc::new(ThreadManager::new());
+        let right_main = right.get_thread_id_by_name("main").unwrap();
+
+        let (a, b) = tokio::io::duplex(1 << 16);
+        let left_session = Arc::new(
+            SyncSession::new(alice, left.clone(), left_main)
+                .with_interval(Duration::from_millis(10)),
+        );
+        let right_session = Arc::new(
+            SyncSession::new(bob, right.clone(), right_main)
+                .with_interval(Duration::from_millis(10)),
+        );
+        let left_task = tokio::spawn({
+            let session = left_session.clone();
+            async move { session.run(a).await }
+        });
+        let right_task = tokio::spawn({
+            let session = right_session.clone();
+            async move { session.run(b).await }
+        });
+
+        wait_for(|| {
+            right
+                .get_thread(right_main)
+                .unwrap()
+                .head_change_id
+                .is_some()
+        })
+        .await;
+        assert_eq!(files(&right, right_main), vec!["base.txt"]);
+
+        // 双方并发提交后分叉，由 ID 较小的一端合并，另一端快进
+        left.commit_operations(left_main, alice, write("left.txt"))
+            .unwrap();
+        right
+            .commit_operations(right_main, bob, write("right.txt"))
+            .unwrap();
+        let expected = vec!["base.txt", "left.txt", "right.txt"];
+        wait_for(|| {
+            files(&left, left_main) == expected
+                && left.get_thread(left_main).unwrap().head_change_id
+                    == right.get_thread(right_main).unwrap().head_change_id
+        })
+        .await;
+
+        left_session.close();
+        let left_stats = left_task.await.unwrap().unwrap();
+        let right_stats = right_task.await.unwrap().unwrap();
+        assert_eq!(left_stats.merges, 1);
+        assert_eq!(right_stats.merges, 0);
+        assert_eq!(right_stats.received, 3);
+        assert_eq!(files(&right, right_main), expected);
+    }
+}
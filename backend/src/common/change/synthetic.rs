@@ -0,0 +1,279 @@
+use crate::common::change::Change;
+use crate::common::change::operation::Operation;
+use crate::common::change::version::VectorClock;
+use crate::common::meta::ast::MetaNode;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// 合成变更图的形状参数
+#[derive(Debug, Clone)]
+pub struct GraphSpec {
+    /// 并发作者（分支）数量
+    pub width: usize,
+    /// 每个作者的线性变更数量
+    pub depth: usize,
+    /// 每个变更包含的操作数量
+    pub ops_per_change: usize,
+    /// 操作落在共享节点/文件上（产生冲突）的概率，取值 0.0 ~ 1.0
+    pub conflict_rate: f64,
+    /// 共享节点与共享文件的数量
+    pub shared_targets: usize,
+    /// 文件写入的内容大小（字节）
+    pub file_size: usize,
+    /// 随机种子，相同参数与种子总是生成相同的图
+    pub seed: u64,
+}
+
+impl Default for GraphSpec {
+    fn default() -> Self {
+        Self {
+            width: 4,
+            depth: 16,
+            ops_per_change: 4,
+            conflict_rate: 0.1,
+            shared_targets: 8,
+            file_size: 256,
+            seed: 42,
+        }
+    }
+}
+
+/// 合成的变更图
+#[derive(Debug, Clone)]
+pub struct SyntheticGraph {
+    /// 所有变更共同的初始 AST
+    pub root: MetaNode,
+    /// 基础变更（创建共享节点与文件），所有分支均从此分叉
+    pub base: Change,
+    /// 每个作者的线性变更链，按作者分组
+    pub branches: Vec<Vec<Change>>,
+}
+
+impl SyntheticGraph {
+    /// 按参数生成变更图
+    pub fn generate(spec: &GraphSpec) -> Self {
+        let mut rng = SplitMix64(spec.seed);
+        let epoch = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap_or_default();
+
+        let base_author = rng.uuid();
+        let shared: Vec<MetaNode> = (0..spec.shared_targets)
+            .map(|i| identifier(&mut rng, &format!("shared_{}", i)))
+            .collect();
+        let mut base_ops: Vec<Operation> = shared
+            .iter()
+            .enumerate()
+            .map(|(i, node)| Operation::insert(None, i, node.clone()))
+            .collect();
+        base_ops.extend(
+            (0..spec.shared_targets)
+                .map(|i| Operation::file_write(shared_path(i), content(&mut rng, spec.file_size))),
+        );
+        let mut base_version = VectorClock::new();
+        base_version.increment(base_author);
+        let base = build(
+            &mut rng,
+            base_author,
+            epoch,
+            base_ops,
+            base_version.clone(),
+            vec![],
+        );
+
+        let mut root = MetaNode::module("synthetic");
+        if let MetaNode::Module { id, .. } = &mut root {
+            *id = rng.uuid();
+        }
+
+        let mut branches = Vec::with_capacity(spec.width);
+        for branch in 0..spec.width {
+            let author = rng.uuid();
+            let mut version = base_version.clone();
+            let mut parent = base.id;
+            let mut chain = Vec::with_capacity(spec.depth);
+            // 本分支插入的私有节点，作为后续更新的目标
+            let mut own: Vec<Uuid> = Vec::new();
+
+            for step in 0..spec.depth {
+                let mut operations = Vec::with_capacity(spec.ops_per_change);
+                for op in 0..spec.ops_per_change {
+                    let conflicting = !shared.is_empty() && rng.chance(spec.conflict_rate);
+                    let operation = if conflicting {
+                        let target = rng.below(shared.len());
+                        match rng.below(2) {
+                            0 => Operation::update(
+                                shared[target].id(),
+                                identifier(&mut rng, &format!("shared_{}_b{}", target, branch)),
+                            ),
+                            _ => Operation::file_write(
+                                shared_path(target),
+                                content(&mut rng, spec.file_size),
+                            ),
+                        }
+                    } else {
+                        match rng.below(3) {
+                            0 if !own.is_empty() => {
+                                let target = own[rng.below(own.len())];
+                                Operation::update(
+                                    target,
+                                    identifier(&mut rng, &format!("b{}_s{}_u{}", branch, step, op)),
+                                )
+                            }
+                            1 => Operation::file_write(
+                                format!("branch_{}/file_{}.rs", branch, rng.below(16)),
+                                content(&mut rng, spec.file_size),
+                            ),
+                            _ => {
+                                let node =
+                                    identifier(&mut rng, &format!("b{}_s{}_n{}", branch, step, op));
+                                own.push(node.id());
+                                Operation::insert(None, usize::MAX, node)
+                            }
+                        }
+                    };
+                    operations.push(operation);
+                }
+
+                version.increment(author);
+                let timestamp = epoch + Duration::milliseconds((step * spec.width + branch) as i64);
+                let change = build(
+                    &mut rng,
+                    author,
+                    timestamp,
+                    operations,
+                    version.clone(),
+                    vec![parent],
+                );
+                parent = change.id;
+                chain.push(change);
+            }
+            branches.push(chain);
+        }
+
+        Self {
+            root,
+            base,
+            branches,
+        }
+    }
+
+    /// 所有变更（含基础变更），按分支顺序排列
+    pub fn changes(&self) -> Vec<Change> {
+        std::iter::once(self.base.clone())
+            .chain(self.branches.iter().flatten().cloned())
+            .collect()
+    }
+
+    /// 以确定性方式打乱的全部变更，模拟不同到达顺序
+    pub fn shuffled_changes(&self, seed: u64) -> Vec<Change> {
+        let mut rng = SplitMix64(seed);
+        let mut changes = self.changes();
+        for i in (1..changes.len()).rev() {
+            changes.swap(i, rng.below(i + 1));
+        }
+        changes
+    }
+
+    /// 各分支的 Head（用于构造合并场景）
+    pub fn heads(&self) -> HashMap<usize, Uuid> {
+        self.branches
+            .iter()
+            .enumerate()
+            .filter_map(|(i, chain)| chain.last().map(|c| (i, c.id)))
+            .collect()
+    }
+}
+
+fn build(
+    rng: &mut SplitMix64,
+    author_id: Uuid,
+    timestamp: DateTime<Utc>,
+    operations: Vec<Operation>,
+    version: VectorClock,
+    parents: Vec<Uuid>,
+) -> Change {
+    let mut change = Change::new(author_id, operations, version, parents);
+    change.id = rng.uuid();
+    change.timestamp = timestamp;
+    change.hash = change.calculate_hash();
+    change
+}
+
+fn identifier(rng: &mut SplitMix64, name: &str) -> MetaNode {
+    MetaNode::Identifier {
+        id: rng.uuid(),
+        name: name.to_string(),
+        scope_id: None,
+    }
+}
+
+fn shared_path(index: usize) -> String {
+    format!("shared/file_{}.rs", index)
+}
+
+fn content(rng: &mut SplitMix64, size: usize) -> Vec<u8> {
+    (0..size).map(|_| b'a' + rng.below(26) as u8).collect()
+}
+
+/// 无外部依赖的确定性伪随机数生成器
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound.max(1) as u64) as usize
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    fn uuid(&mut self) -> Uuid {
+        uuid::Builder::from_random_bytes(
+            ((u128::from(self.next()) << 64) | u128::from(self.next())).to_le_bytes(),
+        )
+        .into_uuid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::merge::MergeEngine;
+
+    #[test]
+    fn test_synthetic_graph_is_deterministic() {
+        let spec = GraphSpec {
+            width: 3,
+            depth: 5,
+            conflict_rate: 0.5,
+            ..Default::default()
+        };
+        let a = SyntheticGraph::generate(&spec);
+        let b = SyntheticGraph::generate(&spec);
+        let shape = |g: &SyntheticGraph| {
+            g.changes()
+                .into_iter()
+                .map(|c| (c.id, c.operations, c.version, c.parents))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(shape(&a), shape(&b));
+        assert_eq!(a.changes().len(), 1 + 3 * 5);
+        assert!(a.changes().iter().all(Change::verify_hash));
+
+        // 到达顺序不影响合并结果
+        let engine = MergeEngine::new();
+        let merged = engine.merge(a.root.clone(), &a.changes()).unwrap();
+        let shuffled = engine
+            .merge(a.root.clone(), &a.shuffled_changes(7))
+            .unwrap();
+        assert_eq!(merged, shuffled);
+    }
+}
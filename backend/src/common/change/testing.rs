@@ -0,0 +1,249 @@
+use crate::common::change::Change;
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::operation::Operation;
+use crate::common::change::snapshot::materialize_files;
+use crate::common::change::version::VectorClock;
+use crate::common::meta::ast::MetaNode;
+use chrono::{DateTime, Duration, Utc};
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// 共享路径池的大小（越小越容易在同一文件上并发写入）
+const PATH_POOL: usize = 4;
+
+/// 一组并发作者产生的变更及其初始状态
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    /// 所有变更共同的初始 AST
+    pub root: MetaNode,
+    /// 按生成顺序排列的变更（生成顺序即一种合法的因果顺序）
+    pub changes: Vec<Change>,
+}
+
+/// 合并后的可比较状态：AST 与物化的文件快照
+#[derive(Debug, Clone, PartialEq)]
+pub struct Converged {
+    pub tree: MetaNode,
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+impl Scenario {
+    /// 以给定顺序合并并物化
+    pub fn merge(&self, changes: &[Change]) -> anyhow::Result<Converged> {
+        Ok(Converged {
+            tree: MergeEngine::new().merge(self.root.clone(), changes)?,
+            files: materialize_files(changes)?,
+        })
+    }
+
+    /// 按生成顺序合并
+    pub fn merge_all(&self) -> anyhow::Result<Converged> {
+        self.merge(&self.changes)
+    }
+}
+
+/// 单个作者的一步：写入若干操作，并可选择先同步（观察到此前所有变更）
+#[derive(Debug, Clone)]
+struct Step {
+    author: usize,
+    sync: bool,
+    ops: Vec<OpSeed>,
+}
+
+/// 与具体 ID 无关的操作种子，生成时再解析到已存在的节点
+#[derive(Debug, Clone)]
+enum OpSeed {
+    Insert { index: usize, node: u128 },
+    Update { target: usize, node: u128 },
+    Delete { target: usize },
+    Move { target: usize, index: usize },
+    FileWrite { path: usize, content: Vec<u8> },
+    FileDelete { path: usize },
+}
+
+fn arb_op() -> impl Strategy<Value = OpSeed> {
+    prop_oneof![
+        3 => (0..4usize, any::<u128>()).prop_map(|(index, node)| OpSeed::Insert { index, node }),
+        2 => (any::<usize>(), any::<u128>()).prop_map(|(target, node)| OpSeed::Update { target, node }),
+        1 => any::<usize>().prop_map(|target| OpSeed::Delete { target }),
+        1 => (any::<usize>(), 0..4usize).prop_map(|(target, index)| OpSeed::Move { target, index }),
+        3 => (0..PATH_POOL, proptest::collection::vec(any::<u8>(), 0..8))
+            .prop_map(|(path, content)| OpSeed::FileWrite { path, content }),
+        1 => (0..PATH_POOL).prop_map(|path| OpSeed::FileDelete { path }),
+    ]
+}
+
+/// 生成最多 `max_authors` 个作者、最多 `max_changes` 个变更的随机并发场景
+///
+/// 每个作者的变更构成一条因果链；作者在某一步可以先“同步”，使其向量时钟
+/// 合并此前所有变更，从而产生交错的因果/并发关系。时间戳沿生成顺序严格递增。
+pub fn arb_scenario(max_authors: usize, max_changes: usize) -> impl Strategy<Value = Scenario> {
+    let authors = 1..=max_authors.max(1);
+    authors.prop_flat_map(move |authors| {
+        let step = (
+            0..authors,
+            any::<bool>(),
+            proptest::collection::vec(arb_op(), 1..4),
+        )
+            .prop_map(|(author, sync, ops)| Step { author, sync, ops });
+        (
+            proptest::collection::vec(any::<u128>(), authors),
+            proptest::collection::vec(step, 1..=max_changes.max(1)),
+            any::<u128>(),
+        )
+            .prop_map(|(author_ids, steps, root_id)| build(&author_ids, &steps, root_id))
+    })
+}
+
+/// 生成场景及其变更的一个随机排列（模拟不同的到达顺序）
+pub fn arb_scenario_with_permutation(
+    max_authors: usize,
+    max_changes: usize,
+) -> impl Strategy<Value = (Scenario, Vec<Change>)> {
+    arb_scenario(max_authors, max_changes).prop_flat_map(|scenario| {
+        let shuffled = Just(scenario.changes.clone()).prop_shuffle();
+        (Just(scenario), shuffled)
+    })
+}
+
+/// 断言任意到达顺序下合并结果收敛
+pub fn assert_converges(scenario: &Scenario, permuted: &[Change]) -> Result<(), TestCaseError> {
+    let expected = scenario
+        .merge_all()
+        .map_err(|e| TestCaseError::fail(e.to_string()))?;
+    let actual = scenario
+        .merge(permuted)
+        .map_err(|e| TestCaseError::fail(e.to_string()))?;
+    prop_assert_eq!(expected, actual);
+    Ok(())
+}
+
+/// 断言将变更拆分为两个副本各自接收的部分后，以任一顺序交换合并结果相同
+pub fn assert_merge_commutes(scenario: &Scenario, split: usize) -> Result<(), TestCaseError> {
+    let split = split.min(scenario.changes.len());
+    let (left, right) = scenario.changes.split_at(split);
+    let ab: Vec<Change> = left.iter().chain(right).cloned().collect();
+    let ba: Vec<Change> = right.iter().chain(left).cloned().collect();
+    let ab = scenario
+        .merge(&ab)
+        .map_err(|e| TestCaseError::fail(e.to_string()))?;
+    let ba = scenario
+        .merge(&ba)
+        .map_err(|e| TestCaseError::fail(e.to_string()))?;
+    prop_assert_eq!(ab, ba);
+    Ok(())
+}
+
+fn build(author_ids: &[u128], steps: &[Step], root_id: u128) -> Scenario {
+    let epoch = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap_or_default();
+    let authors: Vec<Uuid> = author_ids.iter().map(|id| Uuid::from_u128(*id)).collect();
+    let mut clocks = vec![VectorClock::new(); authors.len()];
+    let mut heads: Vec<Option<Uuid>> = vec![None; authors.len()];
+    let mut observed = VectorClock::new();
+    // 已插入过的节点（可能已被删除），作为更新/删除/移动的目标
+    let mut nodes: Vec<Uuid> = Vec::new();
+    let mut changes: Vec<Change> = Vec::with_capacity(steps.len());
+
+    for (i, step) in steps.iter().enumerate() {
+        let author = step.author;
+        let mut parents: Vec<Uuid> = heads[author].into_iter().collect();
+        if step.sync {
+            clocks[author].merge(&observed);
+            if let Some(last) = changes.last().map(|c| c.id)
+                && !parents.contains(&last)
+            {
+                parents.push(last);
+            }
+        }
+
+        let operations = step
+            .ops
+            .iter()
+            .map(|op| resolve(op, &mut nodes, i))
+            .collect();
+
+        clocks[author].increment(authors[author]);
+        observed.merge(&clocks[author]);
+
+        let mut change = Change::new(authors[author], operations, clocks[author].clone(), parents);
+        change.id = Uuid::from_u128((i as u128) << 64 | author_ids[author] >> 64);
+        change.timestamp = epoch + Duration::milliseconds(i as i64);
+        change.hash = change.calculate_hash();
+        heads[author] = Some(change.id);
+        changes.push(change);
+    }
+
+    let mut root = MetaNode::module("scenario");
+    if let MetaNode::Module { id, .. } = &mut root {
+        *id = Uuid::from_u128(root_id);
+    }
+    Scenario { root, changes }
+}
+
+fn resolve(op: &OpSeed, nodes: &mut Vec<Uuid>, step: usize) -> Operation {
+    let identifier = |id: Uuid, name: String| MetaNode::Identifier {
+        id,
+        name,
+        scope_id: None,
+    };
+    let target = match op {
+        OpSeed::Update { target, .. } | OpSeed::Delete { target } | OpSeed::Move { target, .. } => {
+            // 尚无节点可操作时退化为插入
+            if nodes.is_empty() {
+                let id = Uuid::from_u128((step as u128) << 64 | *target as u128);
+                nodes.push(id);
+                return Operation::insert(None, 0, identifier(id, format!("n{}", step)));
+            }
+            nodes[*target % nodes.len()]
+        }
+        _ => Uuid::nil(),
+    };
+
+    match op {
+        OpSeed::Insert { index, node } => {
+            let id = Uuid::from_u128(*node);
+            nodes.push(id);
+            Operation::insert(None, *index, identifier(id, format!("n{}", step)))
+        }
+        // 保持节点 ID 不变，否则后续针对该节点的操作将失效
+        OpSeed::Update { node, .. } => {
+            Operation::update(target, identifier(target, format!("u{:x}", node & 0xffff)))
+        }
+        OpSeed::Delete { .. } => Operation::delete(target),
+        OpSeed::Move { index, .. } => Operation::r#move(target, None, *index),
+        OpSeed::FileWrite { path, content } => {
+            Operation::file_write(pool_path(*path), content.clone())
+        }
+        OpSeed::FileDelete { path } => Operation::file_delete(pool_path(*path)),
+    }
+}
+
+fn pool_path(index: usize) -> String {
+    format!("src/file_{}.rs", index % PATH_POOL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn prop_merge_converges_under_any_arrival_order(
+            (scenario, permuted) in arb_scenario_with_permutation(4, 24)
+        ) {
+            assert_converges(&scenario, &permuted)?;
+        }
+
+        #[test]
+        fn prop_merge_commutes_across_replicas(
+            scenario in arb_scenario(3, 16),
+            split in any::<usize>(),
+        ) {
+            let split = split % (scenario.changes.len() + 1);
+            assert_merge_commutes(&scenario, split)?;
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 文本文件的字符级操作，作为 `Operation::TextEdit` 的载荷
+///
+/// 偏移与长度均以 Unicode 标量值（`char`）计，与前端光标位置一致，
+/// 不会把多字节字符切开。偏移是相对编辑时文本的绝对位置，因此操作可携带
+/// 该文本的校验和（`base`）：在不同内容上重放（如并发分支合并后）时报错，
+/// 而不是把编辑落到错误的位置。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TextOperation {
+    /// 在 `offset` 处插入文本
+    Insert {
+        offset: usize,
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        base: Option<String>,
+    },
+    /// 删除从 `offset` 开始的 `len` 个字符
+    Delete {
+        offset: usize,
+        len: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        base: Option<String>,
+    },
+}
+
+impl TextOperation {
+    pub fn insert(offset: usize, text: &str) -> Self {
+        TextOperation::Insert {
+            offset,
+            text: text.to_string(),
+            base: None,
+        }
+    }
+
+    pub fn delete(offset: usize, len: usize) -> Self {
+        TextOperation::Delete {
+            offset,
+            len,
+            base: None,
+        }
+    }
+
+    /// 将操作锚定到编辑前的文本，之后只能应用到相同的内容上
+    pub fn anchored(mut self, text: &str) -> Self {
+        let checksum = Some(checksum(text));
+        match &mut self {
+            TextOperation::Insert { base, .. } | TextOperation::Delete { base, .. } => {
+                *base = checksum
+            }
+        }
+        self
+    }
+
+    /// 将操作应用到文本，越界或文本与锚定内容不一致时报错且不修改文本
+    pub fn apply(&self, text: &mut String) -> anyhow::Result<()> {
+        let (TextOperation::Insert { base, .. } | TextOperation::Delete { base, .. }) = self;
+        if let Some(base) = base
+            && *base != checksum(text)
+        {
+            return Err(anyhow::anyhow!(
+                "Text edit was made against different content ({} expected)",
+                base
+            ));
+        }
+        match self {
+            TextOperation::Insert {
+                offset,
+                text: inserted,
+                ..
+            } => {
+                let at = byte_offset(text, *offset)?;
+                text.insert_str(at, inserted);
+            }
+            TextOperation::Delete { offset, len, .. } => {
+                let start = byte_offset(text, *offset)?;
+                let end = byte_offset(text, offset + len)?;
+                text.replace_range(start..end, "");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 文本 SHA-256 的前 8 字节（十六进制）
+fn checksum(text: &str) -> String {
+    Sha256::digest(text.as_bytes())[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 字符偏移对应的字节偏移
+fn byte_offset(text: &str, offset: usize) -> anyhow::Result<usize> {
+    text.char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .nth(offset)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Offset {} is out of range for {} characters",
+                offset,
+                text.chars().count()
+            )
+        })
+}
+
+/// 将文本操作应用到以 UTF-8 存储的文件内容
+pub fn apply_text_operation(content: &[u8], op: &TextOperation) -> anyhow::Result<Vec<u8>> {
+    let mut text = String::from_utf8(content.to_vec())?;
+    op.apply(&mut text)?;
+    Ok(text.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_operations_use_char_offsets() {
+        let mut text = "héllo".to_string();
+        TextOperation::insert(2, "✓").apply(&mut text).unwrap();
+        assert_eq!(text, "hé✓llo");
+        TextOperation::delete(1, 2).apply(&mut text).unwrap();
+        assert_eq!(text, "hllo");
+
+        let overflow = TextOperation::delete(3, 2);
+        assert!(overflow.apply(&mut text).is_err());
+        assert_eq!(text, "hllo");
+        assert_eq!(
+            apply_text_operation(b"fn main() {}", &TextOperation::insert(11, " run(); ")).unwrap(),
+            b"fn main() { run(); }"
+        );
+        assert!(apply_text_operation(&[0xff], &overflow).is_err());
+    }
+
+    #[test]
+    fn test_anchored_operation_rejects_other_content() {
+        let op = TextOperation::insert(1, "X").anchored("abc");
+        let mut text = "abc".to_string();
+        op.apply(&mut text).unwrap();
+        assert_eq!(text, "aXbc");
+
+        // 同样长度但内容不同（如并发编辑后）的文本不会被静默改错
+        let mut other = "zbc".to_string();
+        assert!(op.apply(&mut other).is_err());
+        assert_eq!(other, "zbc");
+
+        // 未锚定的旧操作仍按偏移应用
+        let json = serde_json::json!({ "kind": "delete", "offset": 0, "len": 1 });
+        let legacy: TextOperation = serde_json::from_value(json).unwrap();
+        legacy.apply(&mut other).unwrap();
+        assert_eq!(other, "bc");
+    }
+}
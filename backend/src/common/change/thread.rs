@@ -1,22 +1,69 @@
 use crate::common::change::Change;
+use crate::common::change::comment::CommentThread;
+use crate::common::change::compaction::{self, CompactionPolicy, CompactionStats};
+use crate::common::change::operation::Operation;
+use crate::common::change::signing::{AuthorIdentity, TrustedKeys};
+use crate::common::change::version::VectorClock;
+use crate::common::event::{BackendEvent, EventBus};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::RwLock;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
 use uuid::Uuid;
 
 pub type ThreadId = Uuid;
 
 /// 线程（分支）管理
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Thread {
     pub id: ThreadId,
     pub name: String,
     pub head_change_id: Option<Uuid>,
+    /// 分叉来源线程（主线为 `None`）
+    #[serde(default)]
+    pub parent_id: Option<ThreadId>,
+    /// 分叉时父线程的 Head
+    #[serde(default)]
+    pub fork_point: Option<Uuid>,
+}
+
+/// 线程间的合并记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MergeRecord {
+    pub source: ThreadId,
+    pub target: ThreadId,
+    /// 合并后目标线程的 Head
+    pub change_id: Option<Uuid>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 线程合并结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MergeOutcome {
+    /// 目标线程已包含源线程的全部变更，无需任何操作
+    UpToDate,
+    /// 目标线程是源线程的祖先，仅移动 Head 指针
+    FastForward { head: Uuid },
+    /// 双方均有新变更，生成了一个合并 Change
+    Merged { change_id: Uuid },
 }
 
 pub struct ThreadManager {
     threads: RwLock<HashMap<ThreadId, Thread>>,
     changes: RwLock<HashMap<Uuid, Change>>,
+    merges: RwLock<Vec<MergeRecord>>,
+    /// 锚定在代码范围上的评论串
+    pub(super) comments: RwLock<HashMap<Uuid, CommentThread>>,
+    /// 作者 ID 到显示名称的映射（时间线展示用）
+    pub(super) authors: RwLock<HashMap<Uuid, String>>,
+    /// 提交成功后向其发布 `ChangeCommitted` 事件
+    events: Option<Arc<EventBus>>,
+    compaction: CompactionPolicy,
+    /// 配置后提交时校验 Change 的作者签名
+    trusted_keys: Option<Arc<TrustedKeys>>,
+    /// 作者 ID 到签名身份：提交该作者未签名的 Change 时自动签名
+    signers: RwLock<HashMap<Uuid, Arc<AuthorIdentity>>>,
 }
 
 impl Default for ThreadManager {
@@ -35,12 +82,65 @@ impl ThreadManager {
                 id: main_thread_id,
                 name: "main".to_string(),
                 head_change_id: None,
+                parent_id: None,
+                fork_point: None,
             },
         );
 
         Self {
             threads: RwLock::new(threads),
             changes: RwLock::new(HashMap::new()),
+            merges: RwLock::new(Vec::new()),
+            comments: RwLock::new(HashMap::new()),
+            authors: RwLock::new(HashMap::new()),
+            events: None,
+            compaction: CompactionPolicy::default(),
+            trusted_keys: None,
+            signers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 将每次提交发布到事件总线
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 设置 `compact` 使用的压缩策略
+    pub fn with_compaction(mut self, policy: CompactionPolicy) -> Self {
+        self.compaction = policy;
+        self
+    }
+
+    /// 提交时按受信任的公钥校验签名
+    pub fn with_trusted_keys(mut self, keys: Arc<TrustedKeys>) -> Self {
+        self.trusted_keys = Some(keys);
+        self
+    }
+
+    /// 提交该身份作为作者的 Change 时自动签名
+    pub fn with_signer(self, identity: Arc<AuthorIdentity>) -> Self {
+        self.add_signer(identity);
+        self
+    }
+
+    /// 注册签名身份；编辑器保存、撤销、合并、同步与 Git 导入等内部生成的 Change 经此签名
+    pub fn add_signer(&self, identity: Arc<AuthorIdentity>) {
+        self.signers
+            .write()
+            .unwrap()
+            .insert(identity.author_id, identity);
+    }
+
+    /// 为尚未签名的 Change 签名（作者注册了签名身份时），否则保持不变
+    pub fn sign(&self, change: &mut Change) -> anyhow::Result<()> {
+        if change.signature.is_some() {
+            return Ok(());
+        }
+        let signer = self.signers.read().unwrap().get(&change.author_id).cloned();
+        match signer {
+            Some(signer) => signer.sign(change),
+            None => Ok(()),
         }
     }
 
@@ -55,6 +155,8 @@ impl ThreadManager {
             id: new_id,
             name: name.to_string(),
             head_change_id: parent.head_change_id,
+            parent_id: Some(parent_id),
+            fork_point: parent.head_change_id,
         };
 
         threads.insert(new_id, new_thread);
@@ -62,7 +164,8 @@ impl ThreadManager {
     }
 
     /// 提交一个新的 Change 到指定 Thread
-    pub fn commit_change(&self, thread_id: ThreadId, change: Change) -> anyhow::Result<()> {
+    pub fn commit_change(&self, thread_id: ThreadId, mut change: Change) -> anyhow::Result<()> {
+        self.sign(&mut change)?;
         let mut threads = self.threads.write().unwrap();
         let mut changes = self.changes.write().unwrap();
 
@@ -70,15 +173,52 @@ impl ThreadManager {
             .get_mut(&thread_id)
             .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
 
-        // 校验 Change 的合法性（MVP 简化：仅校验 Hash）
-        if !change.verify_hash() {
-            return Err(anyhow::anyhow!("Invalid change hash"));
-        }
+        self.verify(&change)?;
 
+        let event = self
+            .events
+            .as_ref()
+            .map(|_| BackendEvent::change_committed(thread_id, &change));
         let change_id = change.id;
         changes.insert(change_id, change);
         thread.head_change_id = Some(change_id);
 
+        if let (Some(events), Some(event)) = (&self.events, event) {
+            events.emit(event);
+        }
+        Ok(())
+    }
+
+    /// 以线程 Head 为父、递增作者时钟，将 `operations` 提交为新的 Change 并返回其 ID
+    pub fn commit_operations(
+        &self,
+        thread_id: ThreadId,
+        author_id: Uuid,
+        operations: Vec<Operation>,
+    ) -> anyhow::Result<Uuid> {
+        let head = self
+            .get_thread(thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?
+            .head_change_id;
+        let mut version = head
+            .and_then(|id| self.get_change(id))
+            .map(|c| c.version)
+            .unwrap_or_default();
+        version.increment(author_id);
+        let change = Change::new(author_id, operations, version, head.into_iter().collect());
+        let id = change.id;
+        self.commit_change(thread_id, change)?;
+        Ok(id)
+    }
+
+    /// 校验 Change 的哈希，配置了受信任公钥时同时校验签名
+    pub fn verify(&self, change: &Change) -> anyhow::Result<()> {
+        if !change.verify_hash() {
+            return Err(anyhow::anyhow!("Invalid change hash"));
+        }
+        if let Some(keys) = &self.trusted_keys {
+            keys.verify(change)?;
+        }
         Ok(())
     }
 
@@ -98,6 +238,276 @@ impl ThreadManager {
             .find(|t| t.name == name)
             .map(|t| t.id)
     }
+
+    /// 列出所有线程
+    pub fn list_threads(&self) -> Vec<Thread> {
+        self.threads.read().unwrap().values().cloned().collect()
+    }
+
+    /// 获取指定 Change 的所有祖先（包含自身）
+    pub fn ancestors(&self, change_id: Uuid) -> HashSet<Uuid> {
+        let changes = self.changes.read().unwrap();
+        let mut visited = HashSet::new();
+        let mut stack = vec![change_id];
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if let Some(change) = changes.get(&id) {
+                stack.extend(change.parents.iter().copied());
+            }
+        }
+        visited
+    }
+
+    /// 记录一次线程合并
+    pub fn record_merge(&self, source: ThreadId, target: ThreadId) -> anyhow::Result<()> {
+        let head = self
+            .get_thread(target)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?
+            .head_change_id;
+        self.merges.write().unwrap().push(MergeRecord {
+            source,
+            target,
+            change_id: head,
+            timestamp: Utc::now(),
+        });
+        Ok(())
+    }
+
+    /// 判断 `ancestor` 是否为 `descendant` 的祖先（或相同）
+    pub fn is_ancestor(&self, ancestor: Uuid, descendant: Uuid) -> bool {
+        ancestor == descendant || self.ancestors(descendant).contains(&ancestor)
+    }
+
+    /// 两个 Change 的最近公共祖先（三方合并的基准）
+    ///
+    /// 存在多个互不为祖先的公共祖先时取历史最长的一个；没有公共历史时返回 `None`。
+    pub fn find_common_ancestor(&self, a: Uuid, b: Uuid) -> Option<Uuid> {
+        let left = self.ancestors(a);
+        let right = self.ancestors(b);
+        let common: Vec<Uuid> = {
+            let changes = self.changes.read().unwrap();
+            left.intersection(&right)
+                .filter(|id| changes.contains_key(id))
+                .copied()
+                .collect()
+        };
+        let histories: Vec<(Uuid, HashSet<Uuid>)> =
+            common.iter().map(|id| (*id, self.ancestors(*id))).collect();
+        histories
+            .iter()
+            .filter(|(id, _)| {
+                !histories
+                    .iter()
+                    .any(|(other, history)| other != id && history.contains(id))
+            })
+            .max_by_key(|(id, history)| (history.len(), *id))
+            .map(|(id, _)| *id)
+    }
+
+    /// 判断将 `source` 合并到 `target` 能否快进完成
+    pub fn can_fast_forward(&self, source: ThreadId, target: ThreadId) -> anyhow::Result<bool> {
+        let source_head = self.head_of(source)?;
+        let target_head = self.head_of(target)?;
+        Ok(match (source_head, target_head) {
+            (Some(s), Some(t)) => self.is_ancestor(t, s),
+            (Some(_), None) => true,
+            (None, _) => false,
+        })
+    }
+
+    /// 将 `source` 线程合并到 `target` 线程
+    ///
+    /// 先基于祖先关系检测无操作合并与快进合并，只有双方分叉时才会
+    /// 生成一个只记录双亲、不含操作的合并 Change。
+    pub fn merge_thread(
+        &self,
+        source: ThreadId,
+        target: ThreadId,
+        author_id: Uuid,
+    ) -> anyhow::Result<MergeOutcome> {
+        let source_head = self.head_of(source)?;
+        let target_head = self.head_of(target)?;
+
+        let outcome = match (source_head, target_head) {
+            (None, _) => MergeOutcome::UpToDate,
+            (Some(s), Some(t)) if self.is_ancestor(s, t) => MergeOutcome::UpToDate,
+            (Some(s), None) => self.fast_forward(target, s)?,
+            (Some(s), Some(t)) if self.is_ancestor(t, s) => self.fast_forward(target, s)?,
+            (Some(s), Some(t)) => {
+                let mut version = VectorClock::new();
+                if let Some(head) = self.get_change(t) {
+                    version.merge(&head.version);
+                }
+                if let Some(head) = self.get_change(s) {
+                    version.merge(&head.version);
+                }
+                version.increment(author_id);
+
+                // 物化时会重放全部祖先，源线程的变更经第二个父节点即已包含，不再复制其操作
+                let change = Change::new(author_id, Vec::new(), version, vec![t, s]);
+                let change_id = change.id;
+                self.commit_change(target, change)?;
+                MergeOutcome::Merged { change_id }
+            }
+        };
+
+        if outcome != MergeOutcome::UpToDate {
+            self.record_merge(source, target)?;
+        }
+        Ok(outcome)
+    }
+
+    fn fast_forward(&self, target: ThreadId, head: Uuid) -> anyhow::Result<MergeOutcome> {
+        let mut threads = self.threads.write().unwrap();
+        let thread = threads
+            .get_mut(&target)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+        thread.head_change_id = Some(head);
+        Ok(MergeOutcome::FastForward { head })
+    }
+
+    fn head_of(&self, thread_id: ThreadId) -> anyhow::Result<Option<Uuid>> {
+        self.get_thread(thread_id)
+            .map(|t| t.head_change_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))
+    }
+
+    /// 获取所有合并记录
+    pub fn merge_records(&self) -> Vec<MergeRecord> {
+        self.merges.read().unwrap().clone()
+    }
+
+    /// 列出所有已知的 Change
+    pub fn list_changes(&self) -> Vec<Change> {
+        self.changes.read().unwrap().values().cloned().collect()
+    }
+
+    /// 恢复线程状态（用于崩溃恢复）
+    ///
+    /// 同名但尚无任何提交的占位线程（如默认创建的 `main`）会被替换。
+    pub fn restore_thread(&self, thread: Thread) {
+        let mut threads = self.threads.write().unwrap();
+        threads.retain(|id, t| {
+            *id == thread.id || t.name != thread.name || t.head_change_id.is_some()
+        });
+        threads.insert(thread.id, thread);
+    }
+
+    /// 直接写入 Change 而不移动任何线程的 Head（用于崩溃恢复与同步）
+    ///
+    /// 配置了受信任公钥时按签名策略校验，未通过时拒绝写入；哈希不在此校验，
+    /// 由 `CrashRecovery` 检查变更图时报告并修复。
+    pub fn restore_change(&self, change: Change) -> anyhow::Result<()> {
+        if let Some(keys) = &self.trusted_keys {
+            keys.verify(&change)?;
+        }
+        self.changes.write().unwrap().insert(change.id, change);
+        Ok(())
+    }
+
+    /// 移除 Change，返回被移除的 Change
+    pub fn remove_change(&self, id: Uuid) -> Option<Change> {
+        self.changes.write().unwrap().remove(&id)
+    }
+
+    /// 压缩线程的历史：将早于策略界限的线性 Change 段折叠为检查点 Change
+    ///
+    /// 检查点沿用段末 Change 的 ID，线程 Head、子节点与合并记录保持有效；
+    /// 指向被回收 Change 的线程分叉点改写为所在段的检查点。带签名的 Change 不会被折叠。
+    ///
+    /// 压缩只适用于本地变更图：检查点以已有 ID 携带不同的内容，已通过 `sync`
+    /// 交换给对端的历史不应压缩，否则双方对同一 ID 的内容会产生分歧。
+    pub fn compact(&self, thread_id: ThreadId) -> anyhow::Result<CompactionStats> {
+        let mut threads = self.threads.write().unwrap();
+        let mut changes = self.changes.write().unwrap();
+        let head = threads
+            .get(&thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?
+            .head_change_id;
+
+        // 首父链，从根到 Head
+        let mut chain = Vec::new();
+        let mut visited = HashSet::new();
+        let mut current = head;
+        while let Some(id) = current {
+            if !visited.insert(id) {
+                break;
+            }
+            chain.push(id);
+            current = changes.get(&id).and_then(|c| c.parents.first().copied());
+        }
+        chain.reverse();
+
+        let on_chain: HashSet<Uuid> = chain.iter().copied().collect();
+        let mut pinned: HashSet<Uuid> = threads
+            .values()
+            .filter_map(|t| t.head_change_id)
+            .chain(
+                self.merges
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .filter_map(|m| m.change_id),
+            )
+            .collect();
+        for change in changes.values() {
+            for (index, parent) in change.parents.iter().enumerate() {
+                // 链上 Change 的首父关系由折叠处理，其余引用都要求保留父节点 ID
+                if index > 0 || !on_chain.contains(&change.id) {
+                    pinned.insert(*parent);
+                }
+            }
+        }
+
+        let runs = compaction::find_runs(&chain, &changes, &pinned, &self.compaction, Utc::now());
+        let mut stats = CompactionStats {
+            thread_id,
+            ..Default::default()
+        };
+        for run in runs {
+            let run: Vec<Change> = run
+                .iter()
+                .filter_map(|id| changes.get(id).cloned())
+                .collect();
+            let checkpoint = compaction::fold(&run);
+            stats.runs += 1;
+            stats.operations_before += run.iter().map(|c| c.operations.len()).sum::<usize>();
+            stats.operations_after += checkpoint.operations.len();
+            for change in &run[..run.len() - 1] {
+                changes.remove(&change.id);
+                stats.removed.push(change.id);
+                for thread in threads.values_mut() {
+                    if thread.fork_point == Some(change.id) {
+                        thread.fork_point = Some(checkpoint.id);
+                        stats.rewritten_fork_points += 1;
+                    }
+                }
+            }
+            changes.insert(checkpoint.id, checkpoint);
+        }
+        Ok(stats)
+    }
+
+    /// 直接设置线程 Head
+    pub fn set_head(&self, thread_id: ThreadId, head: Option<Uuid>) -> anyhow::Result<()> {
+        let mut threads = self.threads.write().unwrap();
+        let thread = threads
+            .get_mut(&thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+        thread.head_change_id = head;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl ThreadManager {
+    /// 测试用：以随机作者在线程 Head 上提交 `operations`，返回 Change ID
+    pub(crate) fn commit_test(&self, thread_id: ThreadId, operations: Vec<Operation>) -> Uuid {
+        self.commit_operations(thread_id, Uuid::new_v4(), operations)
+            .unwrap()
+    }
 }
 
 #[cfg(test)]
@@ -113,4 +523,219 @@ mod tests {
         assert_eq!(thread.name, "main");
         assert!(thread.head_change_id.is_none());
     }
+
+    #[test]
+    fn test_commit_change_publishes_event() {
+        use crate::common::event::{EventEnvelope, EventFilter, EventSink};
+
+        struct Discard;
+        #[async_trait::async_trait]
+        impl EventSink for Discard {
+            fn name(&self) -> &str {
+                "discard"
+            }
+            async fn publish(&self, _events: &[EventEnvelope]) -> anyhow::Result<()> {
+                Ok(())
+            }
+        }
+
+        let bus = Arc::new(EventBus::new());
+        bus.subscribe(Arc::new(Discard), EventFilter::all());
+        let manager = ThreadManager::new().with_events(bus.clone());
+        let main_id = manager.get_thread_id_by_name("main").unwrap();
+        assert!(
+            manager
+                .commit_change(main_id, Change::mock(Uuid::new_v4(), vec![]))
+                .is_ok()
+        );
+        let mut tampered = Change::mock(Uuid::new_v4(), vec![]);
+        tampered.hash = "bad".to_string();
+        assert!(manager.commit_change(main_id, tampered).is_err());
+        assert_eq!(bus.pending(), 1);
+    }
+
+    #[test]
+    fn test_merge_thread_fast_forward_and_no_op() {
+        let manager = ThreadManager::new();
+        let main_id = manager.get_thread_id_by_name("main").unwrap();
+        manager.commit_test(main_id, vec![Operation::mock("test", "base")]);
+        let feature = manager.create_branch(main_id, "feature").unwrap();
+        let head = manager.commit_test(feature, vec![Operation::mock("test", "feature")]);
+
+        assert!(manager.can_fast_forward(feature, main_id).unwrap());
+        let outcome = manager
+            .merge_thread(feature, main_id, Uuid::new_v4())
+            .unwrap();
+        assert_eq!(outcome, MergeOutcome::FastForward { head });
+        assert_eq!(
+            manager.get_thread(main_id).unwrap().head_change_id,
+            Some(head)
+        );
+
+        // 再次合并为无操作
+        let outcome = manager
+            .merge_thread(feature, main_id, Uuid::new_v4())
+            .unwrap();
+        assert_eq!(outcome, MergeOutcome::UpToDate);
+        assert_eq!(manager.merge_records().len(), 1);
+    }
+
+    #[test]
+    fn test_merge_thread_diverged_creates_merge_change() {
+        let manager = ThreadManager::new();
+        let main_id = manager.get_thread_id_by_name("main").unwrap();
+        manager.commit_test(main_id, vec![Operation::mock("test", "base")]);
+        let feature = manager.create_branch(main_id, "feature").unwrap();
+        let feature_head = manager.commit_test(feature, vec![Operation::mock("test", "feature")]);
+        let main_head = manager.commit_test(main_id, vec![Operation::mock("test", "main")]);
+
+        assert!(!manager.can_fast_forward(feature, main_id).unwrap());
+        let outcome = manager
+            .merge_thread(feature, main_id, Uuid::new_v4())
+            .unwrap();
+        let MergeOutcome::Merged { change_id } = outcome else {
+            panic!("Expected merge change");
+        };
+        let merge = manager.get_change(change_id).unwrap();
+        assert_eq!(merge.parents, vec![main_head, feature_head]);
+        assert!(merge.operations.is_empty());
+        assert!(manager.is_ancestor(feature_head, change_id));
+    }
+
+    #[test]
+    fn test_merge_thread_diverged_applies_source_edits_once() {
+        use crate::common::change::Operation;
+        use crate::common::change::snapshot::materialize_files;
+        use crate::common::change::text::TextOperation;
+
+        let manager = ThreadManager::new();
+        let main_id = manager.get_thread_id_by_name("main").unwrap();
+        manager.commit_test(
+            main_id,
+            vec![Operation::file_write("a.txt".to_string(), b"abc".to_vec())],
+        );
+        let feature = manager.create_branch(main_id, "feature").unwrap();
+        manager.commit_test(
+            feature,
+            vec![Operation::text_edit(
+                "a.txt".to_string(),
+                TextOperation::insert(0, "X").anchored("abc"),
+            )],
+        );
+        manager.commit_test(
+            main_id,
+            vec![Operation::file_write("b.txt".to_string(), b"b".to_vec())],
+        );
+
+        let MergeOutcome::Merged { change_id } = manager
+            .merge_thread(feature, main_id, Uuid::new_v4())
+            .unwrap()
+        else {
+            panic!("Expected merge change");
+        };
+        let history: Vec<Change> = manager
+            .ancestors(change_id)
+            .into_iter()
+            .filter_map(|id| manager.get_change(id))
+            .collect();
+        let files = materialize_files(&history).unwrap();
+        assert_eq!(files["a.txt"], b"Xabc");
+        assert_eq!(files["b.txt"], b"b");
+    }
+
+    #[test]
+    fn test_compact_folds_old_linear_history() {
+        use crate::common::change::Operation;
+
+        let manager = ThreadManager::new();
+        let main_id = manager.get_thread_id_by_name("main").unwrap();
+        let author = Uuid::new_v4();
+        let mut ids = Vec::new();
+        for (index, days_ago) in [30, 29, 28, 27, 26, 0].into_iter().enumerate() {
+            let mut change = Change::new(
+                author,
+                vec![Operation::file_write(
+                    "notes.md".to_string(),
+                    format!("v{}", index).into_bytes(),
+                )],
+                VectorClock::new(),
+                ids.last().copied().into_iter().collect(),
+            );
+            change.timestamp = Utc::now() - chrono::Duration::days(days_ago);
+            change.hash = change.calculate_hash();
+            ids.push(change.id);
+            manager.commit_change(main_id, change).unwrap();
+            if index == 1 {
+                // feature 在 c1 分叉并有自己的提交，c1 必须保留
+                let feature = manager.create_branch(main_id, "feature").unwrap();
+                manager
+                    .commit_change(
+                        feature,
+                        Change::new(author, vec![], VectorClock::new(), vec![ids[1]]),
+                    )
+                    .unwrap();
+            }
+            if index == 2 {
+                manager.create_branch(main_id, "stale").unwrap();
+            }
+        }
+        // stale 快进到 c4，分叉点 c2 会被回收
+        let stale = manager.get_thread_id_by_name("stale").unwrap();
+        manager.set_head(stale, Some(ids[4])).unwrap();
+
+        let stats = manager.compact(main_id).unwrap();
+        assert_eq!(stats.runs, 2);
+        assert_eq!(stats.removed, vec![ids[0], ids[2], ids[3]]);
+        assert_eq!((stats.operations_before, stats.operations_after), (5, 2));
+        assert_eq!(stats.rewritten_fork_points, 1);
+        assert_eq!(manager.get_thread(stale).unwrap().fork_point, Some(ids[4]));
+
+        let checkpoint = manager.get_change(ids[4]).unwrap();
+        assert!(checkpoint.verify_hash());
+        assert_eq!(checkpoint.parents, vec![ids[1]]);
+        assert_eq!(
+            checkpoint.operations,
+            vec![Operation::file_write(
+                "notes.md".to_string(),
+                b"v4".to_vec()
+            )]
+        );
+        assert!(manager.get_change(ids[1]).unwrap().parents.is_empty());
+        assert_eq!(manager.ancestors(ids[5]).len(), 3);
+
+        // 再次压缩没有可回收的 Change
+        assert_eq!(manager.compact(main_id).unwrap().reclaimed(), 0);
+    }
+
+    #[test]
+    fn test_compact_keeps_signed_changes() {
+        use crate::common::change::signing::AuthorIdentity;
+
+        let manager = ThreadManager::new();
+        let main_id = manager.get_thread_id_by_name("main").unwrap();
+        let identity = AuthorIdentity::generate(Uuid::new_v4());
+        let mut ids = Vec::new();
+        for (index, days_ago) in [30, 29, 28, 27, 0].into_iter().enumerate() {
+            let mut change = Change::new(
+                identity.author_id,
+                vec![],
+                VectorClock::new(),
+                ids.last().copied().into_iter().collect(),
+            );
+            change.timestamp = Utc::now() - chrono::Duration::days(days_ago);
+            change.hash = change.calculate_hash();
+            if index == 1 {
+                identity.sign(&mut change).unwrap();
+            }
+            ids.push(change.id);
+            manager.commit_change(main_id, change).unwrap();
+        }
+
+        // c1 已签名，把历史分成 [c0] 与 [c2, c3] 两段，只有后者可以折叠
+        let stats = manager.compact(main_id).unwrap();
+        assert_eq!(stats.removed, vec![ids[2]]);
+        let signed = manager.get_change(ids[1]).unwrap();
+        assert!(signed.signature.is_some());
+        assert_eq!(manager.get_change(ids[3]).unwrap().parents, vec![ids[1]]);
+    }
 }
@@ -0,0 +1,548 @@
+use crate::common::change::Change;
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::operation::Operation;
+use crate::common::change::snapshot::materialize_files;
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// 合并块的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HunkOrigin {
+    /// 仅左侧修改
+    Left,
+    /// 仅右侧修改
+    Right,
+    /// 双方做了相同的修改
+    Both,
+    /// 双方对同一区域做了不同的修改
+    Conflict,
+}
+
+/// 相对共同祖先的一处改动
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeHunk {
+    pub origin: HunkOrigin,
+    /// 在祖先版本中的起始行（从 1 开始）
+    pub base_start: usize,
+    pub base: Vec<String>,
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+}
+
+/// 单个文件的合并结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileMergeStatus {
+    /// 仅左侧修改，保持左侧内容
+    Left,
+    /// 仅右侧修改，采用右侧内容
+    Right,
+    /// 双方修改结果相同
+    Identical,
+    /// 双方修改了不同区域，已自动合并
+    Merged,
+    /// 存在冲突（同一区域的不同修改、修改与删除、二进制内容），保持左侧内容
+    Conflict,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileMerge {
+    pub path: String,
+    pub status: FileMergeStatus,
+    /// 文本文件的逐块差异（二进制文件或删除冲突为空）
+    pub hunks: Vec<MergeHunk>,
+}
+
+/// 三方合并报告，供合并审阅界面逐文件、逐块展示
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeReport {
+    /// 共同祖先 Change（无公共历史时为 `None`）
+    pub base: Option<Uuid>,
+    pub left: Option<Uuid>,
+    pub right: Option<Uuid>,
+    /// 至少一侧有改动的文件，按路径排序
+    pub files: Vec<FileMerge>,
+}
+
+impl MergeReport {
+    pub fn has_conflicts(&self) -> bool {
+        self.conflicts().next().is_some()
+    }
+
+    pub fn conflicts(&self) -> impl Iterator<Item = &FileMerge> {
+        self.files
+            .iter()
+            .filter(|f| f.status == FileMergeStatus::Conflict)
+    }
+}
+
+/// `MergeEngine::merge_threads` 的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreeWayMerge {
+    /// 应用到左侧即得到合并结果的操作：右侧独有的节点操作与自动合并后的文件写入/删除
+    ///
+    /// 冲突文件不在其中，需审阅后另行提交。
+    pub operations: Vec<Operation>,
+    pub report: MergeReport,
+}
+
+impl MergeEngine {
+    /// 以两个线程的共同祖先为基准做三方合并
+    ///
+    /// 分别物化祖先、左侧与右侧的文件快照，对每个改动过的文件按行求两侧相对祖先的差异并合并；
+    /// 不修改任何线程。引用 Blob 的写入操作需先经 `BlobStore::hydrate` 还原。
+    pub fn merge_threads(
+        &self,
+        threads: &ThreadManager,
+        left: ThreadId,
+        right: ThreadId,
+    ) -> anyhow::Result<ThreeWayMerge> {
+        let head = |id: ThreadId| {
+            threads
+                .get_thread(id)
+                .map(|t| t.head_change_id)
+                .ok_or_else(|| anyhow::anyhow!("Thread not found"))
+        };
+        let left_head = head(left)?;
+        let right_head = head(right)?;
+        let base = match (left_head, right_head) {
+            (Some(l), Some(r)) => threads.find_common_ancestor(l, r),
+            _ => None,
+        };
+
+        let history = |head: Option<Uuid>| -> Vec<Change> {
+            head.map(|h| threads.ancestors(h))
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|id| threads.get_change(id))
+                .collect()
+        };
+        let left_history = history(left_head);
+        let right_history = history(right_head);
+        let base_files = materialize_files(&history(base))?;
+        let left_files = materialize_files(&left_history)?;
+        let right_files = materialize_files(&right_history)?;
+
+        // 右侧独有的非文件操作按因果顺序沿用
+        let left_ids: BTreeSet<Uuid> = left_history.iter().map(|c| c.id).collect();
+        let right_only = right_history
+            .into_iter()
+            .filter(|c| !left_ids.contains(&c.id))
+            .collect();
+        let mut operations: Vec<Operation> = self
+            .sort_changes(right_only)
+            .into_iter()
+            .flat_map(|c| c.operations)
+            .filter(|op| op.path().is_none())
+            .collect();
+
+        let paths: BTreeSet<&String> = base_files
+            .keys()
+            .chain(left_files.keys())
+            .chain(right_files.keys())
+            .collect();
+        let mut files = Vec::new();
+        for path in paths {
+            let sides = [&base_files, &left_files, &right_files].map(|f| f.get(path));
+            let [b, l, r] = sides;
+            if b == l && b == r {
+                continue;
+            }
+            let (file, merged) = merge_file(path, b, l, r);
+            if let Some(merged) = merged
+                && merged.as_ref() != l
+            {
+                operations.push(match merged {
+                    Some(content) => Operation::file_write(path.clone(), content.clone()),
+                    None => Operation::file_delete(path.clone()),
+                });
+            }
+            files.push(file);
+        }
+
+        Ok(ThreeWayMerge {
+            operations,
+            report: MergeReport {
+                base,
+                left: left_head,
+                right: right_head,
+                files,
+            },
+        })
+    }
+}
+
+/// 合并单个文件，返回报告与合并后的内容（冲突时为 `None`，`Some(None)` 表示删除）
+pub(crate) fn merge_file(
+    path: &str,
+    base: Option<&Vec<u8>>,
+    left: Option<&Vec<u8>>,
+    right: Option<&Vec<u8>>,
+) -> (FileMerge, Option<Option<Vec<u8>>>) {
+    fn text(content: Option<&Vec<u8>>) -> Option<&str> {
+        match content {
+            Some(bytes) => std::str::from_utf8(bytes).ok(),
+            None => Some(""),
+        }
+    }
+    let merged = match (text(base), text(left), text(right)) {
+        (Some(b), Some(l), Some(r)) => Some(diff3(b, l, r)),
+        _ => None,
+    };
+    let hunks = merged
+        .as_ref()
+        .map(|(hunks, _)| hunks.clone())
+        .unwrap_or_default();
+
+    let (status, content) = if left == right {
+        (FileMergeStatus::Identical, Some(left.cloned()))
+    } else if left == base {
+        (FileMergeStatus::Right, Some(right.cloned()))
+    } else if right == base {
+        (FileMergeStatus::Left, Some(left.cloned()))
+    } else {
+        match merged {
+            Some((hunks, content))
+                if left.is_some()
+                    && right.is_some()
+                    && hunks.iter().all(|h| h.origin != HunkOrigin::Conflict) =>
+            {
+                (FileMergeStatus::Merged, Some(Some(content.into_bytes())))
+            }
+            _ => (FileMergeStatus::Conflict, None),
+        }
+    };
+    let file = FileMerge {
+        path: path.to_string(),
+        status,
+        hunks,
+    };
+    (file, content)
+}
+
+/// 相对祖先的一处编辑：将 `base[start..end]` 替换为 `lines`
+pub(crate) struct Edit<'a> {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) lines: &'a [&'a str],
+}
+
+/// 行级差异的最大编辑距离，超过后把整个差异区域视为一处替换
+///
+/// Myers 算法的时间为 O((n + m) · d)、回溯记录为 O(d²)，上限使两者保持有界；
+/// 双方都改动了同一大块区域时，三方合并因此退化为整块冲突。
+const MAX_EDIT_DISTANCE: usize = 2048;
+
+/// 基于 Myers 算法（最短编辑脚本）的行级差异
+pub(crate) fn line_edits<'a>(base: &[&str], other: &'a [&'a str]) -> Vec<Edit<'a>> {
+    let prefix = base.iter().zip(other).take_while(|(a, b)| a == b).count();
+    let suffix = base[prefix..]
+        .iter()
+        .rev()
+        .zip(other[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &base[prefix..base.len() - suffix];
+    let b = &other[prefix..other.len() - suffix];
+    if a.is_empty() && b.is_empty() {
+        return Vec::new();
+    }
+    let Some(matches) = common_lines(a, b) else {
+        return vec![Edit {
+            start: prefix,
+            end: prefix + a.len(),
+            lines: b,
+        }];
+    };
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (mi, mj) in matches.into_iter().chain([(a.len(), b.len())]) {
+        if mi > i || mj > j {
+            edits.push(Edit {
+                start: prefix + i,
+                end: prefix + mi,
+                lines: &b[j..mj],
+            });
+        }
+        (i, j) = (mi + 1, mj + 1);
+    }
+    edits
+}
+
+/// 按顺序返回 `a` 与 `b` 中匹配的行号对；编辑距离超过上限时返回 `None`
+fn common_lines(a: &[&str], b: &[&str]) -> Option<Vec<(usize, usize)>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (a.len() + b.len()).min(MAX_EDIT_DISTANCE) as isize;
+    // v[k]：对角线 k = x - y 上能到达的最远 x；trace[d] 保存第 d 步开始前 [-d-1, d+1] 的部分
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let at = |k: isize| (k + offset) as usize;
+    let mut found = false;
+    'search: for d in 0..=max {
+        trace.push(v[at(-d - 1)..=at(d + 1)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+                v[at(k + 1)]
+            } else {
+                v[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                found = true;
+                break 'search;
+            }
+        }
+    }
+    if !found {
+        return None;
+    }
+
+    let mut matches = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| (k + d + 1) as usize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[at(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            matches.push((x as usize, y as usize));
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    matches.reverse();
+    Some(matches)
+}
+
+/// 将区域 `[start, end)` 内的编辑应用到祖先行上
+fn render<'a>(base: &[&'a str], start: usize, end: usize, edits: &[&Edit<'a>]) -> Vec<&'a str> {
+    let mut out = Vec::new();
+    let mut pos = start;
+    for edit in edits {
+        out.extend_from_slice(&base[pos..edit.start]);
+        out.extend_from_slice(edit.lines);
+        pos = edit.end;
+    }
+    out.extend_from_slice(&base[pos..end]);
+    out
+}
+
+/// 行级三方合并，返回改动块与合并后的文本（冲突区域保留左侧内容）
+fn diff3(base: &str, left: &str, right: &str) -> (Vec<MergeHunk>, String) {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let left: Vec<&str> = left.split_inclusive('\n').collect();
+    let right: Vec<&str> = right.split_inclusive('\n').collect();
+    let left_edits = line_edits(&base, &left);
+    let right_edits = line_edits(&base, &right);
+    let owned = |lines: &[&str]| -> Vec<String> {
+        lines
+            .iter()
+            .map(|l| l.trim_end_matches('\n').to_string())
+            .collect()
+    };
+
+    let mut hunks = Vec::new();
+    let mut merged = String::new();
+    let mut pos = 0;
+    let (mut li, mut ri) = (0, 0);
+    while li < left_edits.len() || ri < right_edits.len() {
+        let take_left = ri == right_edits.len()
+            || (li < left_edits.len() && left_edits[li].start <= right_edits[ri].start);
+        let first = if take_left {
+            &left_edits[li]
+        } else {
+            &right_edits[ri]
+        };
+        let (start, mut end) = (first.start, first.end);
+        let (mut lefts, mut rights): (Vec<&Edit>, Vec<&Edit>) = (Vec::new(), Vec::new());
+        // 吸收与当前区域重叠（或在同一位置插入）的编辑
+        loop {
+            let overlaps = |edit: &Edit| {
+                edit.start < end || (edit.start == end && (edit.start == edit.end || start == end))
+            };
+            if li < left_edits.len() && overlaps(&left_edits[li]) {
+                end = end.max(left_edits[li].end);
+                lefts.push(&left_edits[li]);
+                li += 1;
+            } else if ri < right_edits.len() && overlaps(&right_edits[ri]) {
+                end = end.max(right_edits[ri].end);
+                rights.push(&right_edits[ri]);
+                ri += 1;
+            } else {
+                break;
+            }
+        }
+
+        merged.push_str(&base[pos..start].concat());
+        let left_lines = render(&base, start, end, &lefts);
+        let right_lines = render(&base, start, end, &rights);
+        let origin = if rights.is_empty() {
+            HunkOrigin::Left
+        } else if lefts.is_empty() {
+            HunkOrigin::Right
+        } else if left_lines == right_lines {
+            HunkOrigin::Both
+        } else {
+            HunkOrigin::Conflict
+        };
+        merged.push_str(
+            &if origin == HunkOrigin::Right {
+                &right_lines
+            } else {
+                &left_lines
+            }
+            .concat(),
+        );
+        hunks.push(MergeHunk {
+            origin,
+            base_start: start + 1,
+            base: owned(&base[start..end]),
+            left: owned(&left_lines),
+            right: owned(&right_lines),
+        });
+        pos = end;
+    }
+    merged.push_str(&base[pos..].concat());
+    (hunks, merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn writes(files: &[(&str, &str)]) -> Vec<Operation> {
+        files
+            .iter()
+            .map(|(path, content)| {
+                Operation::file_write(path.to_string(), content.as_bytes().to_vec())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_line_edits_scale_and_fall_back_beyond_the_edit_limit() {
+        let spans = |edits: &[Edit]| -> Vec<(usize, usize, Vec<String>)> {
+            edits
+                .iter()
+                .map(|e| {
+                    (
+                        e.start,
+                        e.end,
+                        e.lines.iter().map(|l| l.to_string()).collect(),
+                    )
+                })
+                .collect()
+        };
+        let base = ["a", "b", "c", "d"];
+        let other = ["a", "x", "c", "d", "e"];
+        assert_eq!(
+            spans(&line_edits(&base, &other)),
+            vec![(1, 2, vec!["x".to_string()]), (4, 4, vec!["e".to_string()])]
+        );
+
+        // 大文件中的少量修改无需平方级的内存
+        let base: Vec<String> = (0..20_000).map(|i| format!("line {}", i)).collect();
+        let mut other = base.clone();
+        other[1] = "changed".to_string();
+        other.remove(15_000);
+        let base: Vec<&str> = base.iter().map(String::as_str).collect();
+        let other: Vec<&str> = other.iter().map(String::as_str).collect();
+        assert_eq!(
+            spans(&line_edits(&base, &other)),
+            vec![
+                (1, 2, vec!["changed".to_string()]),
+                (15_000, 15_001, vec![])
+            ]
+        );
+
+        // 编辑距离超过上限时整个差异区域作为一处替换
+        let left: Vec<String> = (0..MAX_EDIT_DISTANCE).map(|i| format!("l{}", i)).collect();
+        let right: Vec<String> = (0..MAX_EDIT_DISTANCE).map(|i| format!("r{}", i)).collect();
+        let left: Vec<&str> = left.iter().map(String::as_str).collect();
+        let right: Vec<&str> = right.iter().map(String::as_str).collect();
+        let edits = line_edits(&left, &right);
+        assert_eq!(edits.len(), 1);
+        assert_eq!((edits[0].start, edits[0].end), (0, MAX_EDIT_DISTANCE));
+        assert_eq!(edits[0].lines.len(), MAX_EDIT_DISTANCE);
+    }
+
+    #[test]
+    fn test_merge_threads() {
+        let threads = ThreadManager::new();
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let base = threads.commit_test(
+            main,
+            writes(&[("a.txt", "1\n2\n3\n4\n5\n"), ("b.txt", "x\ny\nz\n")]),
+        );
+        let feature = threads.create_branch(main, "feature").unwrap();
+        threads.commit_test(main, writes(&[("a.txt", "one\n2\n3\n4\n5\n")]));
+        threads.commit_test(main, writes(&[("b.txt", "x\nleft\nz\n")]));
+        threads.commit_test(
+            feature,
+            writes(&[
+                ("a.txt", "1\n2\n3\n4\nfive\n"),
+                ("b.txt", "x\nright\nz\n"),
+                ("c.txt", "new\n"),
+            ]),
+        );
+
+        let result = MergeEngine::new()
+            .merge_threads(&threads, main, feature)
+            .unwrap();
+        let report = &result.report;
+        assert_eq!(report.base, Some(base));
+        assert_eq!(
+            report
+                .files
+                .iter()
+                .map(|f| (f.path.as_str(), f.status))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a.txt", FileMergeStatus::Merged),
+                ("b.txt", FileMergeStatus::Conflict),
+                ("c.txt", FileMergeStatus::Right),
+            ]
+        );
+        let a = &report.files[0];
+        assert_eq!(
+            a.hunks.iter().map(|h| h.origin).collect::<Vec<_>>(),
+            vec![HunkOrigin::Left, HunkOrigin::Right]
+        );
+        assert_eq!(a.hunks[1].base_start, 5);
+        let conflict = &report.conflicts().next().unwrap().hunks[0];
+        assert_eq!(
+            (
+                conflict.base.clone(),
+                conflict.left.clone(),
+                conflict.right.clone()
+            ),
+            (vec!["y".into()], vec!["left".into()], vec!["right".into()])
+        );
+
+        // 冲突文件不进入合并结果
+        assert_eq!(
+            result.operations,
+            vec![
+                Operation::file_write("a.txt".into(), b"one\n2\n3\n4\nfive\n".to_vec()),
+                Operation::file_write("c.txt".into(), b"new\n".to_vec()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::common::change::thread::{MergeRecord, Thread, ThreadId, ThreadManager};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// 线程相对于主线的合并状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStatus {
+    /// 与主线一致
+    UpToDate,
+    /// 仅领先主线，可以快进合并
+    Ahead,
+    /// 仅落后主线
+    Behind,
+    /// 双方均有新的变更
+    Diverged,
+}
+
+impl MergeStatus {
+    fn from_counts(ahead: usize, behind: usize) -> Self {
+        match (ahead > 0, behind > 0) {
+            (false, false) => MergeStatus::UpToDate,
+            (true, false) => MergeStatus::Ahead,
+            (false, true) => MergeStatus::Behind,
+            (true, true) => MergeStatus::Diverged,
+        }
+    }
+}
+
+/// 拓扑图中的线程节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadNode {
+    pub id: ThreadId,
+    pub name: String,
+    pub parent_id: Option<ThreadId>,
+    pub fork_point: Option<Uuid>,
+    pub head_change_id: Option<Uuid>,
+    /// 主线没有的变更数量
+    pub ahead: usize,
+    /// 主线中本线程没有的变更数量
+    pub behind: usize,
+    pub status: MergeStatus,
+}
+
+/// 完整的线程拓扑图，供前端分支可视化使用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadTopology {
+    pub main_thread: ThreadId,
+    pub threads: Vec<ThreadNode>,
+    pub merges: Vec<MergeRecord>,
+}
+
+impl ThreadManager {
+    /// 计算 `thread_id` 相对于 `base_id` 的领先/落后变更数
+    pub fn ahead_behind(
+        &self,
+        thread_id: ThreadId,
+        base_id: ThreadId,
+    ) -> anyhow::Result<(usize, usize)> {
+        let thread = self
+            .get_thread(thread_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+        let base = self
+            .get_thread(base_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+
+        let ours = self.head_ancestors(&thread);
+        let theirs = self.head_ancestors(&base);
+        Ok((
+            ours.difference(&theirs).count(),
+            theirs.difference(&ours).count(),
+        ))
+    }
+
+    /// 获取线程相对于主线的合并状态
+    pub fn merge_status(&self, thread_id: ThreadId) -> anyhow::Result<MergeStatus> {
+        let main_id = self.main_thread_id()?;
+        let (ahead, behind) = self.ahead_behind(thread_id, main_id)?;
+        Ok(MergeStatus::from_counts(ahead, behind))
+    }
+
+    /// 导出完整的线程拓扑图
+    pub fn topology(&self) -> anyhow::Result<ThreadTopology> {
+        let main_id = self.main_thread_id()?;
+        let main = self
+            .get_thread(main_id)
+            .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+        let main_ancestors = self.head_ancestors(&main);
+
+        let mut threads: Vec<ThreadNode> = self
+            .list_threads()
+            .into_iter()
+            .map(|thread| {
+                let ours = self.head_ancestors(&thread);
+                let ahead = ours.difference(&main_ancestors).count();
+                let behind = main_ancestors.difference(&ours).count();
+                ThreadNode {
+                    id: thread.id,
+                    name: thread.name,
+                    parent_id: thread.parent_id,
+                    fork_point: thread.fork_point,
+                    head_change_id: thread.head_change_id,
+                    ahead,
+                    behind,
+                    status: MergeStatus::from_counts(ahead, behind),
+                }
+            })
+            .collect();
+        threads.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(ThreadTopology {
+            main_thread: main_id,
+            threads,
+            merges: self.merge_records(),
+        })
+    }
+
+    /// 建议的合并顺序：可快进的分支优先，其次按落后数量、领先数量升序
+    pub fn suggest_merge_order(&self) -> anyhow::Result<Vec<ThreadId>> {
+        let topology = self.topology()?;
+        let mut candidates: Vec<_> = topology
+            .threads
+            .into_iter()
+            .filter(|t| t.id != topology.main_thread && t.ahead > 0)
+            .collect();
+        candidates.sort_by_key(|t| (t.behind, t.ahead));
+        Ok(candidates.into_iter().map(|t| t.id).collect())
+    }
+
+    fn main_thread_id(&self) -> anyhow::Result<ThreadId> {
+        self.get_thread_id_by_name("main")
+            .ok_or_else(|| anyhow::anyhow!("Main thread not found"))
+    }
+
+    fn head_ancestors(&self, thread: &Thread) -> HashSet<Uuid> {
+        thread
+            .head_change_id
+            .map(|head| self.ancestors(head))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::operation::Operation;
+
+    #[test]
+    fn test_topology_ahead_behind() {
+        let manager = ThreadManager::new();
+        let main_id = manager.get_thread_id_by_name("main").unwrap();
+        let base = manager.commit_test(main_id, vec![Operation::mock("test", "data")]);
+
+        let feature = manager.create_branch(main_id, "feature").unwrap();
+        let stale = manager.create_branch(main_id, "stale").unwrap();
+        manager.commit_test(feature, vec![Operation::mock("test", "data")]);
+        manager.commit_test(feature, vec![Operation::mock("test", "data")]);
+        manager.commit_test(main_id, vec![Operation::mock("test", "data")]);
+        manager.commit_test(stale, vec![Operation::mock("test", "data")]);
+
+        let topology = manager.topology().unwrap();
+        let node = topology.threads.iter().find(|t| t.id == feature).unwrap();
+        assert_eq!(node.fork_point, Some(base));
+        assert_eq!(node.parent_id, Some(main_id));
+        assert_eq!((node.ahead, node.behind), (2, 1));
+        assert_eq!(node.status, MergeStatus::Diverged);
+
+        assert_eq!(
+            manager.merge_status(main_id).unwrap(),
+            MergeStatus::UpToDate
+        );
+
+        // stale 只领先 1 个变更，应排在 feature 之前
+        assert_eq!(manager.suggest_merge_order().unwrap(), vec![stale, feature]);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::common::change::Change;
+use crate::common::change::blob::BlobStore;
+use crate::common::change::thread::Thread;
+use crate::common::provider::traits::StorageProvider;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// 预写日志记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WalRecord {
+    /// 线程创建或 Head 移动（分叉、快进等）
+    Thread(Thread),
+    /// 向线程提交 Change，`thread` 为提交后的线程状态
+    Commit { thread: Thread, change: Box<Change> },
+}
+
+/// 带序号的日志条目
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WalEntry {
+    pub seq: u64,
+    pub record: WalRecord,
+}
+
+/// 无法解析的日志条目（通常是崩溃时写入了一半）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptEntry {
+    pub path: String,
+    pub error: String,
+}
+
+/// 预写日志（WAL）
+///
+/// 每条记录单独存为 `<root>/<seq>.json`，以持久写入（临时文件、落盘、原子改名）完成追加，
+/// `append` 返回后的记录在断电后依然完整；启动时由恢复流程重放并校验。
+/// 配置 Blob 存储后提交记录中的文件内容以 `FileWriteRef` 写入，读取时还原。
+pub struct WriteAheadLog {
+    storage: Arc<dyn StorageProvider>,
+    root: String,
+    blobs: Option<Arc<BlobStore>>,
+    next_seq: Mutex<u64>,
+}
+
+impl WriteAheadLog {
+    /// 打开日志目录，从已有记录之后继续编号
+    pub async fn open(storage: Arc<dyn StorageProvider>, root: &str) -> anyhow::Result<Self> {
+        let root = root.trim_end_matches('/').to_string();
+        if !storage.exists(&root).await? {
+            storage.create_dir(&root, true).await?;
+        }
+        let wal = Self {
+            storage,
+            root,
+            blobs: None,
+            next_seq: Mutex::new(0),
+        };
+        let next = wal
+            .entry_paths()
+            .await?
+            .last()
+            .map(|(seq, _)| seq + 1)
+            .unwrap_or(0);
+        *wal.next_seq.lock().await = next;
+        Ok(wal)
+    }
+
+    /// 将提交记录中的文件内容存入 Blob 存储，日志中只保留哈希引用
+    pub fn with_blob_store(mut self, blobs: Arc<BlobStore>) -> Self {
+        self.blobs = Some(blobs);
+        self
+    }
+
+    /// 追加一条记录，返回其序号
+    pub async fn append(&self, mut record: WalRecord) -> anyhow::Result<u64> {
+        if let (Some(blobs), WalRecord::Commit { change, .. }) = (&self.blobs, &mut record) {
+            **change = blobs.externalize(change).await?;
+        }
+        let mut next_seq = self.next_seq.lock().await;
+        let entry = WalEntry {
+            seq: *next_seq,
+            record,
+        };
+        self.storage
+            .write_file_durable(&self.entry_path(entry.seq), &serde_json::to_vec(&entry)?)
+            .await?;
+        *next_seq += 1;
+        Ok(entry.seq)
+    }
+
+    /// 最近一次追加的序号，日志为空时为 `None`
+    pub async fn last_seq(&self) -> Option<u64> {
+        self.next_seq.lock().await.checked_sub(1)
+    }
+
+    /// 按序读取所有记录，同时返回无法解析的条目
+    pub async fn read_all(&self) -> anyhow::Result<(Vec<WalEntry>, Vec<CorruptEntry>)> {
+        let mut entries = Vec::new();
+        let mut corrupt = Vec::new();
+        for (_, path) in self.entry_paths().await? {
+            let parsed = self
+                .storage
+                .read_file(&path)
+                .await
+                .and_then(|bytes| Ok(serde_json::from_slice::<WalEntry>(&bytes)?));
+            match parsed {
+                Ok(mut entry) => {
+                    // Blob 缺失时保留引用，由恢复流程的哈希校验报告
+                    if let (Some(blobs), WalRecord::Commit { change, .. }) =
+                        (&self.blobs, &mut entry.record)
+                        && let Ok(hydrated) = blobs.hydrate(change).await
+                    {
+                        **change = hydrated;
+                    }
+                    entries.push(entry);
+                }
+                Err(e) => corrupt.push(CorruptEntry {
+                    path,
+                    error: e.to_string(),
+                }),
+            }
+        }
+        Ok((entries, corrupt))
+    }
+
+    /// 删除单个条目（用于丢弃损坏的记录）
+    pub async fn discard(&self, path: &str) -> anyhow::Result<()> {
+        self.storage.delete(path, false).await
+    }
+
+    /// 撤回一条已追加但未生效的记录（例如写入存储失败的提交），恢复时不再重放
+    pub async fn retract(&self, seq: u64) -> anyhow::Result<()> {
+        let path = self.entry_path(seq);
+        if self.storage.exists(&path).await? {
+            self.storage.delete(&path, false).await?;
+        }
+        Ok(())
+    }
+
+    /// 删除序号不大于 `seq` 的所有记录（持久化检查点之后调用）
+    pub async fn truncate_through(&self, seq: u64) -> anyhow::Result<usize> {
+        let mut removed = 0;
+        for (entry_seq, path) in self.entry_paths().await? {
+            if entry_seq > seq {
+                break;
+            }
+            self.storage.delete(&path, false).await?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// 已有条目的 (序号, 路径)，按序号升序
+    async fn entry_paths(&self) -> anyhow::Result<Vec<(u64, String)>> {
+        let mut paths: Vec<(u64, String)> = self
+            .storage
+            .list_dir(&self.root)
+            .await?
+            .into_iter()
+            .filter(|meta| !meta.is_dir)
+            .filter_map(|meta| {
+                let name = meta.path.rsplit(['/', '\\']).next()?;
+                let seq = name.strip_suffix(".json")?.parse().ok()?;
+                Some((seq, self.entry_path(seq)))
+            })
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn entry_path(&self, seq: u64) -> String {
+        format!("{}/{:020}.json", self.root, seq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::Operation;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use tempfile::tempdir;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_wal_append_reopen_and_truncate() {
+        let dir = tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let wal = WriteAheadLog::open(storage.clone(), "wal").await.unwrap();
+
+        let change = Change::mock(Uuid::new_v4(), vec![Operation::mock("test", "a")]);
+        let thread = Thread {
+            id: Uuid::new_v4(),
+            name: "main".to_string(),
+            head_change_id: Some(change.id),
+            parent_id: None,
+            fork_point: None,
+        };
+        assert_eq!(
+            wal.append(WalRecord::Commit {
+                thread: thread.clone(),
+                change: Box::new(change)
+            })
+            .await
+            .unwrap(),
+            0
+        );
+        assert_eq!(wal.append(WalRecord::Thread(thread)).await.unwrap(), 1);
+        storage
+            .write_file("wal/00000000000000000002.json", b"{\"seq\":2,\"rec")
+            .await
+            .unwrap();
+
+        // 重新打开后从已有记录之后继续编号
+        let wal = WriteAheadLog::open(storage, "wal").await.unwrap();
+        let (entries, corrupt) = wal.read_all().await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(corrupt.len(), 1);
+        assert_eq!(
+            wal.append(WalRecord::Thread(Thread {
+                id: Uuid::new_v4(),
+                name: "feature".to_string(),
+                head_change_id: None,
+                parent_id: None,
+                fork_point: None,
+            }))
+            .await
+            .unwrap(),
+            3
+        );
+
+        assert_eq!(wal.truncate_through(1).await.unwrap(), 2);
+        let (entries, _) = wal.read_all().await.unwrap();
+        assert_eq!(entries.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![3]);
+    }
+}
@@ -0,0 +1,470 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::stream::{
+    ChatDelta, ChatResponse, ChatStreamEvent, Choice, ProviderAdapter, ProviderConfig, SseEvent,
+    StreamState, insert_opt,
+};
+use crate::common::endpoint::traits::{
+    ChatMessage, ChatOptions, ContentPart, FileDeletionStatus, FileObject, FileUploadRequest,
+    FunctionCall, MessageContent, MessageRole, ToolCall, Usage,
+};
+use serde_json::{Map, Value, json};
+
+/// Anthropic Messages API 版本
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Files API 所需的 beta 标志
+const FILES_API_BETA: &str = "files-api-2025-04-14";
+
+/// 未指定 `max_tokens` 时的默认值（Messages API 要求必填）
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Anthropic Messages API 适配器
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnthropicAdapter;
+
+impl ProviderAdapter for AnthropicAdapter {
+    fn id(&self) -> &str {
+        "anthropic"
+    }
+
+    fn default_base_url(&self) -> &str {
+        "https://api.anthropic.com/v1"
+    }
+
+    fn chat_path(&self, _model: &str, _stream: bool) -> String {
+        "/messages".to_string()
+    }
+
+    fn headers(&self, config: &ProviderConfig) -> Vec<(String, String)> {
+        vec![
+            ("x-api-key".to_string(), config.api_key.clone()),
+            (
+                "anthropic-version".to_string(),
+                ANTHROPIC_VERSION.to_string(),
+            ),
+        ]
+    }
+
+    fn build_chat_request(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        stream: bool,
+    ) -> EndpointResult<Value> {
+        // 系统消息提升为顶层 `system` 字段
+        let system: Vec<String> = messages
+            .iter()
+            .filter(|m| m.role == MessageRole::System)
+            .map(|m| m.content.as_text())
+            .collect();
+
+        // 连续的同角色消息需合并（如多条工具结果）
+        let mut wire: Vec<Value> = Vec::new();
+        for message in messages.iter().filter(|m| m.role != MessageRole::System) {
+            let role = match message.role {
+                MessageRole::Assistant => "assistant",
+                _ => "user",
+            };
+            let blocks = content_blocks(message)?;
+            match wire.last_mut() {
+                Some(last) if last["role"] == role => {
+                    last["content"]
+                        .as_array_mut()
+                        .expect("content is an array")
+                        .extend(blocks);
+                }
+                _ => wire.push(json!({ "role": role, "content": blocks })),
+            }
+        }
+
+        let mut body = Map::new();
+        body.insert("model".to_string(), json!(model));
+        body.insert("messages".to_string(), Value::Array(wire));
+        body.insert(
+            "max_tokens".to_string(),
+            json!(options.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS)),
+        );
+        if !system.is_empty() {
+            body.insert("system".to_string(), json!(system.join("\n\n")));
+        }
+        if stream {
+            body.insert("stream".to_string(), json!(true));
+        }
+        insert_opt(&mut body, "temperature", &options.temperature);
+        insert_opt(&mut body, "top_p", &options.top_p);
+        insert_opt(&mut body, "stop_sequences", &options.stop);
+        if let Some(user) = &options.user {
+            body.insert("metadata".to_string(), json!({ "user_id": user }));
+        }
+        if let Some(tools) = &options.tools {
+            let tools: Vec<Value> = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.function.name,
+                        "description": tool.function.description,
+                        "input_schema": tool.function.parameters,
+                    })
+                })
+                .collect();
+            body.insert("tools".to_string(), Value::Array(tools));
+        }
+        Ok(Value::Object(body))
+    }
+
+    fn parse_chat_response(&self, body: Value) -> EndpointResult<ChatResponse> {
+        if let Some(message) = body.pointer("/error/message").and_then(Value::as_str) {
+            return Err(EndpointError::ProviderError(message.to_string()));
+        }
+        let blocks = body
+            .get("content")
+            .and_then(Value::as_array)
+            .ok_or_else(|| EndpointError::ProviderError("Missing content".to_string()))?;
+
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for block in blocks {
+            match block.get("type").and_then(Value::as_str) {
+                Some("text") => text.push_str(block["text"].as_str().unwrap_or_default()),
+                Some("tool_use") => tool_calls.push(ToolCall {
+                    id: block["id"].as_str().unwrap_or_default().to_string(),
+                    r#type: "function".to_string(),
+                    function: FunctionCall {
+                        name: block["name"].as_str().unwrap_or_default().to_string(),
+                        arguments: block["input"].to_string(),
+                    },
+                }),
+                _ => {}
+            }
+        }
+
+        Ok(ChatResponse {
+            id: body["id"].as_str().unwrap_or_default().to_string(),
+            model: body["model"].as_str().unwrap_or_default().to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: ChatMessage {
+                    role: MessageRole::Assistant,
+                    content: MessageContent::Text(text),
+                    tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                    tool_call_id: None,
+                },
+                finish_reason: body["stop_reason"].as_str().map(finish_reason),
+            }],
+            usage: body.get("usage").map(|usage| {
+                let input = usage["input_tokens"].as_u64().unwrap_or(0) as u32;
+                let output = usage["output_tokens"].as_u64().unwrap_or(0) as u32;
+                Usage {
+                    prompt_tokens: input,
+                    completion_tokens: output,
+                    total_tokens: input + output,
+                }
+            }),
+            safety: Vec::new(),
+        })
+    }
+
+    fn files_path(&self) -> Option<String> {
+        Some("/files".to_string())
+    }
+
+    fn max_file_size(&self) -> Option<u64> {
+        Some(500 * 1024 * 1024)
+    }
+
+    fn file_headers(&self) -> Vec<(String, String)> {
+        vec![("anthropic-beta".to_string(), FILES_API_BETA.to_string())]
+    }
+
+    /// Files API 不区分用途，仅上传 `file` 字段
+    fn build_upload_form(&self, request: &FileUploadRequest) -> reqwest::multipart::Form {
+        let file = reqwest::multipart::Part::bytes(request.content.clone())
+            .file_name(request.filename.clone());
+        reqwest::multipart::Form::new().part("file", file)
+    }
+
+    fn parse_file_object(
+        &self,
+        request: &FileUploadRequest,
+        body: Value,
+    ) -> EndpointResult<FileObject> {
+        let id = body["id"]
+            .as_str()
+            .ok_or_else(|| EndpointError::ProviderError("Missing file id".to_string()))?;
+        Ok(FileObject {
+            id: id.to_string(),
+            bytes: body["size_bytes"]
+                .as_u64()
+                .unwrap_or(request.content.len() as u64) as u32,
+            filename: body["filename"]
+                .as_str()
+                .unwrap_or(&request.filename)
+                .to_string(),
+            purpose: request.purpose.clone(),
+        })
+    }
+
+    fn parse_file_deletion(&self, file_id: &str, body: Value) -> FileDeletionStatus {
+        FileDeletionStatus {
+            id: body["id"].as_str().unwrap_or(file_id).to_string(),
+            deleted: body["type"] == "file_deleted",
+        }
+    }
+
+    fn parse_stream_event(
+        &self,
+        state: &mut StreamState,
+        event: &SseEvent,
+    ) -> EndpointResult<Vec<ChatStreamEvent>> {
+        let data: Value = serde_json::from_str(&event.data)?;
+        let kind = event
+            .event
+            .as_deref()
+            .or_else(|| data["type"].as_str())
+            .unwrap_or_default();
+
+        let events = match kind {
+            "message_start" => {
+                state.started = true;
+                state.usage.prompt_tokens = data
+                    .pointer("/message/usage/input_tokens")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0) as u32;
+                vec![ChatStreamEvent::Start]
+            }
+            "content_block_start" => {
+                let block = &data["content_block"];
+                if block["type"] == "tool_use" {
+                    let index = data["index"].as_u64().unwrap_or(0);
+                    let id = block["id"].as_str().unwrap_or_default().to_string();
+                    let name = block["name"].as_str().unwrap_or_default().to_string();
+                    state.tool_calls.insert(index, (id.clone(), name.clone()));
+                    vec![tool_delta(id, name, String::new())]
+                } else {
+                    vec![]
+                }
+            }
+            "content_block_delta" => {
+                let delta = &data["delta"];
+                match delta["type"].as_str() {
+                    Some("text_delta") => vec![ChatStreamEvent::Delta(ChatDelta {
+                        content: delta["text"].as_str().map(str::to_string),
+                        ..Default::default()
+                    })],
+                    Some("input_json_delta") => {
+                        let index = data["index"].as_u64().unwrap_or(0);
+                        let (id, name) = state.tool_calls.get(&index).cloned().unwrap_or_default();
+                        let partial = delta["partial_json"].as_str().unwrap_or_default();
+                        vec![tool_delta(id, name, partial.to_string())]
+                    }
+                    _ => vec![],
+                }
+            }
+            "message_delta" => {
+                let output = data
+                    .pointer("/usage/output_tokens")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0) as u32;
+                state.usage.completion_tokens = output;
+                state.usage.total_tokens = state.usage.prompt_tokens + output;
+                vec![ChatStreamEvent::Usage(state.usage.clone())]
+            }
+            "message_stop" => vec![ChatStreamEvent::Done],
+            "error" => vec![ChatStreamEvent::Error(
+                data.pointer("/error/message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Unknown stream error")
+                    .to_string(),
+            )],
+            // ping 与 content_block_stop 无需转发
+            _ => vec![],
+        };
+        Ok(events)
+    }
+}
+
+/// 将统一消息转换为 Anthropic 内容块
+fn content_blocks(message: &ChatMessage) -> EndpointResult<Vec<Value>> {
+    if message.role == MessageRole::Tool {
+        let tool_use_id = message.tool_call_id.as_ref().ok_or_else(|| {
+            EndpointError::InvalidRequest("Tool message without tool_call_id".to_string())
+        })?;
+        return Ok(vec![json!({
+            "type": "tool_result",
+            "tool_use_id": tool_use_id,
+            "content": message.content.as_text(),
+        })]);
+    }
+
+    let mut blocks = match &message.content {
+        MessageContent::Text(text) if text.is_empty() => vec![],
+        MessageContent::Text(text) => vec![json!({ "type": "text", "text": text })],
+        MessageContent::Parts(parts) => parts
+            .iter()
+            .map(|part| match part {
+                ContentPart::Text { text } => json!({ "type": "text", "text": text }),
+                ContentPart::ImageUrl { url, .. } => image_block(url),
+                ContentPart::File { file_id, mime_type } => {
+                    let kind = match mime_type.as_deref() {
+                        Some(mime) if mime.starts_with("image/") => "image",
+                        _ => "document",
+                    };
+                    json!({ "type": kind, "source": { "type": "file", "file_id": file_id } })
+                }
+            })
+            .collect(),
+    };
+
+    for call in message.tool_calls.iter().flatten() {
+        let input: Value =
+            serde_json::from_str(&call.function.arguments).unwrap_or_else(|_| json!({}));
+        blocks.push(json!({
+            "type": "tool_use",
+            "id": call.id,
+            "name": call.function.name,
+            "input": input,
+        }));
+    }
+    Ok(blocks)
+}
+
+/// 图像：data URL 转为 base64 源，其余作为 URL 源
+fn image_block(url: &str) -> Value {
+    if let Some(rest) = url.strip_prefix("data:")
+        && let Some((media_type, data)) = rest.split_once(";base64,")
+    {
+        return json!({
+            "type": "image",
+            "source": { "type": "base64", "media_type": media_type, "data": data },
+        });
+    }
+    json!({ "type": "image", "source": { "type": "url", "url": url } })
+}
+
+fn tool_delta(id: String, name: String, arguments: String) -> ChatStreamEvent {
+    ChatStreamEvent::Delta(ChatDelta {
+        tool_calls: Some(vec![ToolCall {
+            id,
+            r#type: "function".to_string(),
+            function: FunctionCall { name, arguments },
+        }]),
+        ..Default::default()
+    })
+}
+
+/// 将停止原因映射为 OpenAI 风格的 `finish_reason`
+fn finish_reason(stop_reason: &str) -> String {
+    match stop_reason {
+        "end_turn" | "stop_sequence" => "stop",
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        other => other,
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anthropic_request_mapping() {
+        let adapter = AnthropicAdapter;
+        let assistant = ChatMessage {
+            tool_calls: Some(vec![ToolCall {
+                id: "toolu_1".to_string(),
+                r#type: "function".to_string(),
+                function: FunctionCall {
+                    name: "read_file".to_string(),
+                    arguments: r#"{"path":"a.rs"}"#.to_string(),
+                },
+            }]),
+            ..ChatMessage::text(MessageRole::Assistant, "")
+        };
+        let messages = vec![
+            ChatMessage::text(MessageRole::System, "You are helpful."),
+            ChatMessage::text(MessageRole::User, "Read a.rs"),
+            assistant,
+            ChatMessage::tool_result("toolu_1", "fn main() {}"),
+            ChatMessage {
+                content: MessageContent::Parts(vec![ContentPart::ImageUrl {
+                    url: "data:image/png;base64,AAAA".to_string(),
+                    detail: None,
+                }]),
+                ..ChatMessage::text(MessageRole::User, "")
+            },
+        ];
+
+        let body = adapter
+            .build_chat_request("claude-sonnet", &messages, &ChatOptions::default(), false)
+            .unwrap();
+        assert_eq!(body["system"], "You are helpful.");
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
+
+        let wire = body["messages"].as_array().unwrap();
+        assert_eq!(wire.len(), 3);
+        assert_eq!(wire[1]["content"][0]["type"], "tool_use");
+        assert_eq!(wire[1]["content"][0]["input"]["path"], "a.rs");
+        // 工具结果与随后的用户图像合并为一条 user 消息
+        assert_eq!(wire[2]["content"][0]["type"], "tool_result");
+        assert_eq!(wire[2]["content"][1]["source"]["media_type"], "image/png");
+    }
+
+    #[test]
+    fn test_anthropic_response_and_stream() {
+        let adapter = AnthropicAdapter;
+        let response = adapter
+            .parse_chat_response(json!({
+                "id": "msg_1",
+                "model": "claude-sonnet",
+                "content": [
+                    { "type": "text", "text": "Reading." },
+                    { "type": "tool_use", "id": "toolu_1", "name": "read_file", "input": { "path": "a.rs" } }
+                ],
+                "stop_reason": "tool_use",
+                "usage": { "input_tokens": 10, "output_tokens": 5 }
+            }))
+            .unwrap();
+        let choice = &response.choices[0];
+        assert_eq!(choice.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(choice.message.tool_calls.as_ref().unwrap()[0].id, "toolu_1");
+        assert_eq!(response.usage.unwrap().total_tokens, 15);
+
+        let mut state = StreamState::default();
+        let mut parse = |event: &str, data: Value| {
+            adapter
+                .parse_stream_event(
+                    &mut state,
+                    &SseEvent {
+                        event: Some(event.to_string()),
+                        data: data.to_string(),
+                    },
+                )
+                .unwrap()
+        };
+        parse(
+            "message_start",
+            json!({ "message": { "usage": { "input_tokens": 7 } } }),
+        );
+        parse(
+            "content_block_start",
+            json!({ "index": 1, "content_block": { "type": "tool_use", "id": "toolu_2", "name": "grep" } }),
+        );
+        let events = parse(
+            "content_block_delta",
+            json!({ "index": 1, "delta": { "type": "input_json_delta", "partial_json": "{\"q\":" } }),
+        );
+        match &events[0] {
+            ChatStreamEvent::Delta(delta) => {
+                assert_eq!(delta.tool_calls.as_ref().unwrap()[0].id, "toolu_2")
+            }
+            other => panic!("Unexpected event: {:?}", other),
+        }
+        let events = parse(
+            "message_delta",
+            json!({ "delta": { "stop_reason": "end_turn" }, "usage": { "output_tokens": 3 } }),
+        );
+        assert!(matches!(&events[0], ChatStreamEvent::Usage(u) if u.total_tokens == 10));
+    }
+}
@@ -0,0 +1,261 @@
+use crate::common::endpoint::stream::ChatResponse;
+use crate::common::endpoint::traits::{ChatMessage, ChatOptions, ModelCost};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 响应缓存配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheConfig {
+    /// 条目有效期
+    pub ttl: Duration,
+    /// 最多保留的条目数，超出时淘汰最久未使用的条目
+    pub max_entries: usize,
+    /// 仅缓存 `temperature == 0` 的确定性调用
+    pub deterministic_only: bool,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(600),
+            max_entries: 256,
+            deterministic_only: true,
+        }
+    }
+}
+
+/// 缓存命中统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// 命中时免于消耗的 token 数
+    pub saved_tokens: u64,
+    /// 命中时按模型单价节省的费用
+    pub saved_cost: ModelCost,
+}
+
+struct Entry {
+    response: ChatResponse,
+    inserted: Instant,
+    last_used: Instant,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    stats: CacheStats,
+}
+
+/// 聊天补全的响应缓存，键为（端点，模型，消息，选项）的哈希
+pub struct ResponseCache {
+    config: CacheConfig,
+    inner: Mutex<Inner>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(CacheConfig::default())
+    }
+}
+
+impl ResponseCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    /// 该调用是否可缓存（流式请求与非确定性调用不缓存）
+    pub fn is_cacheable(&self, options: &ChatOptions) -> bool {
+        if options.stream == Some(true) {
+            return false;
+        }
+        !self.config.deterministic_only || options.temperature == Some(0.0)
+    }
+
+    /// 计算缓存键；`routine_id` 等仅用于本地记账的选项不参与计算
+    pub fn key(
+        provider: &str,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> String {
+        let options = ChatOptions {
+            routine_id: None,
+            context_strategy: None,
+            ..options.clone()
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(provider.as_bytes());
+        hasher.update([0]);
+        hasher.update(model.as_bytes());
+        hasher.update([0]);
+        hasher.update(serde_json::to_vec(messages).unwrap_or_default());
+        hasher.update([0]);
+        hasher.update(serde_json::to_vec(&options).unwrap_or_default());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// 查找未过期的响应，并更新命中统计
+    ///
+    /// `cost_of` 用于计算命中响应原本的费用，计入 `saved_cost`。
+    pub fn get(
+        &self,
+        key: &str,
+        cost_of: impl FnOnce(&ChatResponse) -> ModelCost,
+    ) -> Option<ChatResponse> {
+        let mut inner = self.inner.lock().unwrap();
+        let now = Instant::now();
+        let expired = inner
+            .entries
+            .get(key)
+            .is_some_and(|e| now.duration_since(e.inserted) >= self.config.ttl);
+        if expired {
+            inner.entries.remove(key);
+            inner.stats.evictions += 1;
+        }
+        let Some(entry) = inner.entries.get_mut(key) else {
+            inner.stats.misses += 1;
+            return None;
+        };
+        entry.last_used = now;
+        let response = entry.response.clone();
+        inner.stats.hits += 1;
+        inner.stats.saved_tokens += response
+            .usage
+            .as_ref()
+            .map_or(0, |u| u64::from(u.total_tokens));
+        inner.stats.saved_cost += cost_of(&response);
+        Some(response)
+    }
+
+    /// 写入响应，必要时淘汰过期或最久未使用的条目
+    pub fn insert(&self, key: String, response: ChatResponse) {
+        if self.config.max_entries == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let now = Instant::now();
+        let before = inner.entries.len();
+        inner
+            .entries
+            .retain(|_, e| now.duration_since(e.inserted) < self.config.ttl);
+        inner.stats.evictions += (before - inner.entries.len()) as u64;
+
+        while inner.entries.len() >= self.config.max_entries && !inner.entries.contains_key(&key) {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone());
+            let Some(oldest) = oldest else { break };
+            inner.entries.remove(&oldest);
+            inner.stats.evictions += 1;
+        }
+        inner.entries.insert(
+            key,
+            Entry {
+                response,
+                inserted: now,
+                last_used: now,
+            },
+        );
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().entries.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.inner.lock().unwrap().stats.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::traits::{MessageRole, Usage};
+
+    fn response(id: &str) -> ChatResponse {
+        ChatResponse {
+            id: id.to_string(),
+            model: "m".to_string(),
+            choices: vec![],
+            usage: Some(Usage {
+                prompt_tokens: 10,
+                completion_tokens: 5,
+                total_tokens: 15,
+            }),
+            safety: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_cache_key_ignores_bookkeeping_options() {
+        let messages = vec![ChatMessage::text(MessageRole::User, "route this")];
+        let options = ChatOptions {
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+        let with_routine = ChatOptions {
+            routine_id: Some(uuid::Uuid::new_v4()),
+            ..options.clone()
+        };
+        let key = ResponseCache::key("openai", "gpt-4o", &messages, &options);
+        assert_eq!(
+            key,
+            ResponseCache::key("openai", "gpt-4o", &messages, &with_routine)
+        );
+        assert_ne!(
+            key,
+            ResponseCache::key("anthropic", "gpt-4o", &messages, &options)
+        );
+
+        let cache = ResponseCache::default();
+        assert!(cache.is_cacheable(&options));
+        assert!(!cache.is_cacheable(&ChatOptions::default()));
+    }
+
+    #[test]
+    fn test_cache_ttl_lru_and_stats() {
+        let cache = ResponseCache::new(CacheConfig {
+            ttl: Duration::from_millis(50),
+            max_entries: 2,
+            deterministic_only: true,
+        });
+        cache.insert("a".to_string(), response("a"));
+        cache.insert("b".to_string(), response("b"));
+        assert!(cache.get("a", |_| 0.5).is_some());
+        // `b` 最久未使用，被淘汰
+        cache.insert("c".to_string(), response("c"));
+        assert!(cache.get("b", |_| 0.5).is_none());
+        assert_eq!(cache.len(), 2);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 1, 1));
+        assert_eq!(stats.saved_tokens, 15);
+        assert!((stats.saved_cost - 0.5).abs() < 1e-9);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(cache.get("a", |_| 0.5).is_none());
+        assert_eq!(cache.stats().evictions, 2);
+    }
+}
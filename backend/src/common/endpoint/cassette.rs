@@ -0,0 +1,237 @@
+use crate::common::endpoint::cache::ResponseCache;
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::stream::ChatResponse;
+use crate::common::endpoint::traits::{ChatMessage, ChatOptions, EmbeddingResponse, LLMClient};
+use crate::common::intent::journal::JournalEntry;
+use crate::common::provider::traits::StorageProvider;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+
+/// 录制的模型响应（错误同样录制，保证回放时走相同的分支）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedResponse {
+    Chat { response: ChatResponse },
+    Embedding { response: EmbeddingResponse },
+    Error { message: String },
+}
+
+/// 一次录制的调用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    /// 请求摘要（提供者、模型、消息与选项），回放时用于检测偏离
+    pub key: String,
+    pub model: String,
+    pub response: RecordedResponse,
+}
+
+/// 一次完整 Agent 运行的录制结果
+///
+/// 包含采样种子、按顺序的模型调用与意图日志，可保存后用于调试、回归测试与问题报告。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    /// 录制时的提供者 ID
+    pub provider: String,
+    pub seed: Option<u64>,
+    pub interactions: Vec<Interaction>,
+    #[serde(default)]
+    pub intents: Vec<JournalEntry>,
+}
+
+impl Cassette {
+    pub async fn load(storage: &dyn StorageProvider, path: &str) -> EndpointResult<Self> {
+        let bytes = storage
+            .read_file(path)
+            .await
+            .map_err(|e| EndpointError::StorageError(e.to_string()))?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    pub async fn save(&self, storage: &dyn StorageProvider, path: &str) -> EndpointResult<()> {
+        storage
+            .write_file(path, &serde_json::to_vec_pretty(self)?)
+            .await
+            .map_err(|e| EndpointError::StorageError(e.to_string()))
+    }
+}
+
+enum Mode {
+    Record(Arc<dyn LLMClient>),
+    Replay { cursor: usize },
+}
+
+/// 录制/回放模型调用的客户端
+///
+/// 录制模式下透传到真实客户端并按顺序记录请求摘要与响应；
+/// 回放模式下不访问网络，按顺序返回录制的响应，请求与录制时不一致时返回错误，
+/// 从而保证整个运行逐字节复现，或在偏离处立即失败。
+pub struct CassetteClient {
+    provider_id: String,
+    mode: Mutex<Mode>,
+    cassette: Mutex<Cassette>,
+}
+
+impl CassetteClient {
+    /// 录制对 `inner` 的调用
+    pub fn record(inner: Arc<dyn LLMClient>, seed: Option<u64>) -> Self {
+        let provider = inner.provider_id().to_string();
+        Self {
+            provider_id: provider.clone(),
+            mode: Mutex::new(Mode::Record(inner)),
+            cassette: Mutex::new(Cassette {
+                provider,
+                seed,
+                ..Cassette::default()
+            }),
+        }
+    }
+
+    /// 回放录制结果
+    pub fn replay(cassette: Cassette) -> Self {
+        Self {
+            provider_id: cassette.provider.clone(),
+            mode: Mutex::new(Mode::Replay { cursor: 0 }),
+            cassette: Mutex::new(cassette),
+        }
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(*self.mode.lock().unwrap(), Mode::Replay { .. })
+    }
+
+    /// 当前录制内容（录制模式）或正在回放的内容
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+
+    /// 回放模式下尚未被消费的调用数
+    pub fn remaining(&self) -> usize {
+        match *self.mode.lock().unwrap() {
+            Mode::Record(_) => 0,
+            Mode::Replay { cursor } => self.cassette.lock().unwrap().interactions.len() - cursor,
+        }
+    }
+
+    fn embed_key(&self, model: &str, input: &[String]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.provider_id.as_bytes());
+        hasher.update([0]);
+        hasher.update(model.as_bytes());
+        hasher.update([0]);
+        hasher.update(serde_json::to_vec(input).unwrap_or_default());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// 回放模式下取出下一条录制；录制模式返回内部客户端
+    fn next(&self, key: &str) -> EndpointResult<Result<RecordedResponse, Arc<dyn LLMClient>>> {
+        let mut mode = self.mode.lock().unwrap();
+        match &mut *mode {
+            Mode::Record(inner) => Ok(Err(inner.clone())),
+            Mode::Replay { cursor } => {
+                let cassette = self.cassette.lock().unwrap();
+                let interaction = cassette.interactions.get(*cursor).ok_or_else(|| {
+                    EndpointError::InvalidRequest(format!(
+                        "Replay exhausted after {} interactions",
+                        cursor
+                    ))
+                })?;
+                if interaction.key != key {
+                    return Err(EndpointError::InvalidRequest(format!(
+                        "Replay diverged at interaction {}",
+                        cursor
+                    )));
+                }
+                *cursor += 1;
+                Ok(Ok(interaction.response.clone()))
+            }
+        }
+    }
+
+    fn push(&self, key: String, model: &str, response: RecordedResponse) {
+        self.cassette
+            .lock()
+            .unwrap()
+            .interactions
+            .push(Interaction {
+                key,
+                model: model.to_string(),
+                response,
+            });
+    }
+}
+
+#[async_trait]
+impl LLMClient for CassetteClient {
+    fn provider_id(&self) -> &str {
+        &self.provider_id
+    }
+
+    async fn chat(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> EndpointResult<ChatResponse> {
+        let key = ResponseCache::key(&self.provider_id, model, messages, options);
+        let inner = match self.next(&key)? {
+            Ok(RecordedResponse::Chat { response }) => return Ok(response),
+            Ok(RecordedResponse::Error { message }) => {
+                return Err(EndpointError::ProviderError(message));
+            }
+            Ok(RecordedResponse::Embedding { .. }) => {
+                return Err(EndpointError::InvalidRequest(
+                    "Replay expected an embedding request".to_string(),
+                ));
+            }
+            Err(inner) => inner,
+        };
+        let result = inner.chat(model, messages, options).await;
+        let recorded = match &result {
+            Ok(response) => RecordedResponse::Chat {
+                response: response.clone(),
+            },
+            Err(e) => RecordedResponse::Error {
+                message: e.to_string(),
+            },
+        };
+        self.push(key, model, recorded);
+        result
+    }
+
+    async fn embed(&self, model: &str, input: &[String]) -> EndpointResult<EmbeddingResponse> {
+        let key = self.embed_key(model, input);
+        let inner = match self.next(&key)? {
+            Ok(RecordedResponse::Embedding { response }) => return Ok(response),
+            Ok(RecordedResponse::Error { message }) => {
+                return Err(EndpointError::ProviderError(message));
+            }
+            Ok(RecordedResponse::Chat { .. }) => {
+                return Err(EndpointError::InvalidRequest(
+                    "Replay expected a chat request".to_string(),
+                ));
+            }
+            Err(inner) => inner,
+        };
+        let result = inner.embed(model, input).await;
+        let recorded = match &result {
+            Ok(response) => RecordedResponse::Embedding {
+                response: response.clone(),
+            },
+            Err(e) => RecordedResponse::Error {
+                message: e.to_string(),
+            },
+        };
+        self.push(key, model, recorded);
+        result
+    }
+
+    async fn health_check(&self) -> EndpointResult<()> {
+        let inner = match &*self.mode.lock().unwrap() {
+            Mode::Record(inner) => inner.clone(),
+            Mode::Replay { .. } => return Ok(()),
+        };
+        inner.health_check().await
+    }
+}
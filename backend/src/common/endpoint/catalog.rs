@@ -0,0 +1,275 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::traits::{ModelInfo, ProviderInfo};
+use crate::common::endpoint::usage::ModelPricing;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// models.dev 目录的默认地址
+pub const MODELS_DEV_URL: &str = "https://models.dev/api.json";
+
+/// 随程序发布的目录快照，离线且没有缓存时使用
+const BUNDLED_CATALOG: &str = include_str!("models.dev.json");
+
+/// 目录数据的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatalogSource {
+    /// 从网络获取了新数据
+    Network,
+    /// 服务器返回 304，沿用本地缓存
+    Revalidated,
+    /// 网络不可用，使用本地缓存
+    Cache,
+    /// 网络与缓存均不可用，使用内置快照
+    Bundled,
+    /// 由调用方指定的文件
+    File,
+}
+
+/// 一个提供者及其模型
+#[derive(Debug, Clone)]
+pub struct CatalogProvider {
+    pub info: ProviderInfo,
+    pub models: Vec<ModelInfo>,
+}
+
+/// 解析后的模型目录
+#[derive(Debug, Clone)]
+pub struct ModelCatalog {
+    pub source: CatalogSource,
+    pub providers: Vec<CatalogProvider>,
+}
+
+#[derive(Deserialize)]
+struct RawProvider {
+    id: String,
+    name: String,
+    #[serde(default)]
+    api: Option<String>,
+    #[serde(default)]
+    models: BTreeMap<String, RawModel>,
+}
+
+#[derive(Deserialize)]
+struct RawModel {
+    id: String,
+    name: String,
+    #[serde(default)]
+    reasoning: bool,
+    #[serde(default)]
+    tool_call: bool,
+    #[serde(default)]
+    cost: Option<ModelPricing>,
+    #[serde(default)]
+    limit: Option<RawLimit>,
+    #[serde(default)]
+    modalities: Option<RawModalities>,
+}
+
+#[derive(Deserialize)]
+struct RawLimit {
+    #[serde(default)]
+    context: u32,
+}
+
+#[derive(Deserialize)]
+struct RawModalities {
+    #[serde(default)]
+    input: Vec<String>,
+}
+
+impl ModelCatalog {
+    /// 解析 models.dev `api.json` 格式的数据
+    pub fn parse(json: &[u8], source: CatalogSource) -> EndpointResult<Self> {
+        let raw: BTreeMap<String, RawProvider> = serde_json::from_slice(json)?;
+        let providers = raw
+            .into_values()
+            .map(|p| CatalogProvider {
+                models: p
+                    .models
+                    .into_values()
+                    .map(|m| ModelInfo {
+                        id: m.id,
+                        name: m.name,
+                        provider: p.id.clone(),
+                        context_window: m.limit.map_or(0, |l| l.context),
+                        supports_vision: m
+                            .modalities
+                            .is_some_and(|md| md.input.iter().any(|i| i == "image")),
+                        supports_tools: m.tool_call,
+                        supports_reasoning: m.reasoning,
+                        cost: m.cost,
+                    })
+                    .collect(),
+                info: ProviderInfo {
+                    id: p.id,
+                    name: p.name,
+                    base_url: p.api,
+                },
+            })
+            .collect();
+        Ok(Self { source, providers })
+    }
+
+    /// 内置快照
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_CATALOG.as_bytes(), CatalogSource::Bundled)
+            .expect("bundled models.dev catalog is valid")
+    }
+
+    /// 从本地文件加载（用于隔离网络环境中手动分发的目录）
+    pub async fn from_file(path: &std::path::Path) -> EndpointResult<Self> {
+        Self::parse(&tokio::fs::read(path).await?, CatalogSource::File)
+    }
+}
+
+/// 带磁盘缓存的目录获取器：使用 ETag 重新验证，网络不可用时回退到缓存或内置快照
+pub struct CatalogCache {
+    url: String,
+    cache_dir: PathBuf,
+}
+
+impl CatalogCache {
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            url: MODELS_DEV_URL.to_string(),
+            cache_dir: cache_dir.into(),
+        }
+    }
+
+    /// 使用自定义地址（如内网镜像）
+    pub fn with_url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    fn data_path(&self) -> PathBuf {
+        self.cache_dir.join("models.dev.json")
+    }
+
+    fn etag_path(&self) -> PathBuf {
+        self.cache_dir.join("models.dev.etag")
+    }
+
+    /// 获取目录：网络 -> 缓存 -> 内置快照
+    pub async fn load(&self) -> ModelCatalog {
+        let cached = tokio::fs::read(self.data_path()).await.ok();
+        let cached = cached.and_then(|bytes| {
+            ModelCatalog::parse(&bytes, CatalogSource::Cache)
+                .ok()
+                .map(|c| (bytes, c))
+        });
+        match self.fetch(cached.is_some()).await {
+            Ok(Some(catalog)) => catalog,
+            Ok(None) => {
+                let (_, mut catalog) = cached.expect("revalidated without cache");
+                catalog.source = CatalogSource::Revalidated;
+                catalog
+            }
+            Err(_) => cached.map(|(_, c)| c).unwrap_or_else(ModelCatalog::bundled),
+        }
+    }
+
+    /// 发起请求；服务器返回 304 时为 `Ok(None)`
+    async fn fetch(&self, has_cache: bool) -> EndpointResult<Option<ModelCatalog>> {
+        let mut request = reqwest::Client::new().get(&self.url);
+        if has_cache && let Ok(etag) = tokio::fs::read_to_string(self.etag_path()).await {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag.trim());
+        }
+        let response = request.send().await?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED && has_cache {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(EndpointError::Http {
+                status: response.status().as_u16(),
+                message: "fetching model catalog".to_string(),
+            });
+        }
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = response.bytes().await?;
+        let catalog = ModelCatalog::parse(&body, CatalogSource::Network)?;
+
+        tokio::fs::create_dir_all(&self.cache_dir).await?;
+        tokio::fs::write(self.data_path(), &body).await?;
+        match etag {
+            Some(etag) => tokio::fs::write(self.etag_path(), etag).await?,
+            None => {
+                let _ = tokio::fs::remove_file(self.etag_path()).await;
+            }
+        }
+        Ok(Some(catalog))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_bundled_catalog_parses_capabilities() {
+        let catalog = ModelCatalog::bundled();
+        let openai = catalog
+            .providers
+            .iter()
+            .find(|p| p.info.id == "openai")
+            .unwrap();
+        let o3 = openai.models.iter().find(|m| m.id == "o3-mini").unwrap();
+        assert!(o3.supports_reasoning && o3.supports_tools && !o3.supports_vision);
+        assert_eq!(o3.context_window, 200_000);
+        assert_eq!(o3.cost.unwrap().output, 4.4);
+        assert_eq!(o3.provider, "openai");
+    }
+
+    #[tokio::test]
+    async fn test_catalog_cache_revalidates_and_falls_back() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for _ in 0..2 {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let response = if request.contains("if-none-match: \"v1\"") {
+                    "HTTP/1.1 304 Not Modified\r\netag: \"v1\"\r\nconnection: close\r\n\r\n"
+                        .to_string()
+                } else {
+                    let body = r#"{"local":{"id":"local","name":"Local","models":{"m":{"id":"m","name":"M","tool_call":true}}}}"#;
+                    format!(
+                        "HTTP/1.1 200 OK\r\netag: \"v1\"\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = CatalogCache::new(dir.path()).with_url(&format!("http://{}/api.json", addr));
+        let first = cache.load().await;
+        assert_eq!(first.source, CatalogSource::Network);
+        assert_eq!(first.providers[0].models[0].id, "m");
+        let second = cache.load().await;
+        assert_eq!(second.source, CatalogSource::Revalidated);
+        assert_eq!(second.providers[0].info.id, "local");
+        server.await.unwrap();
+
+        // 服务器已关闭：使用缓存；没有缓存时使用内置快照
+        assert_eq!(cache.load().await.source, CatalogSource::Cache);
+        let empty = tempfile::tempdir().unwrap();
+        let offline =
+            CatalogCache::new(empty.path()).with_url(&format!("http://{}/api.json", addr));
+        assert_eq!(offline.load().await.source, CatalogSource::Bundled);
+    }
+}
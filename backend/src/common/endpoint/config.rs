@@ -0,0 +1,367 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::oauth::OAuthConfig;
+use crate::common::endpoint::registry::ModelRegistry;
+use crate::common::endpoint::traits::ModelInfo;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// 持久化的提供者配置（API Key 不写入配置文件，而是保存在 `SecretStore` 中）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderEntry {
+    pub id: String,
+    /// 协议类型（`openai` / `anthropic` / `gemini` / `ollama`），默认与 `id` 相同
+    #[serde(default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub organization: Option<String>,
+    /// 手动声明的模型
+    #[serde(default)]
+    pub models: Vec<ModelInfo>,
+    /// 使用 OAuth 设备码授权（令牌保存在 `SecretStore` 中），未保存 API Key 时使用其访问令牌
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth: Option<OAuthConfig>,
+    /// 旧版配置中的明文 Key：加载时迁移到 `SecretStore`，保存时不再写出
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+}
+
+impl ProviderEntry {
+    pub fn kind(&self) -> &str {
+        self.kind.as_deref().unwrap_or(&self.id)
+    }
+}
+
+/// 模型注册表的配置文件内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistryConfig {
+    #[serde(default)]
+    pub providers: Vec<ProviderEntry>,
+}
+
+impl RegistryConfig {
+    /// 读取 JSON 配置文件，文件不存在时返回空配置
+    pub async fn load(path: &Path) -> EndpointResult<Self> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 写入 JSON 配置文件（自动创建上级目录）
+    pub async fn save(&self, path: &Path) -> EndpointResult<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+}
+
+/// 提供者 API Key 在 `SecretStore` 中的名称
+pub fn api_key_name(provider: &str) -> String {
+    format!("provider:{}:api_key", provider)
+}
+
+/// 密钥存储；可由系统钥匙串或加密文件实现
+pub trait SecretStore: Send + Sync {
+    fn get(&self, name: &str) -> EndpointResult<Option<String>>;
+
+    fn set(&self, name: &str, value: &str) -> EndpointResult<()>;
+
+    fn delete(&self, name: &str) -> EndpointResult<()>;
+
+    /// 已保存的密钥名称
+    fn names(&self) -> EndpointResult<Vec<String>>;
+}
+
+/// 使用 AES-256-GCM 加密保存的密钥文件
+///
+/// 主密钥保存在单独的密钥文件中（首次使用时生成，Unix 下权限为 0600），
+/// 存储文件内容为 `名称 -> base64(nonce || 密文)` 的 JSON。
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    cipher: Aes256Gcm,
+    entries: Mutex<BTreeMap<String, String>>,
+}
+
+impl EncryptedFileStore {
+    /// 打开（或创建）加密存储
+    pub fn open(path: impl Into<PathBuf>, key_path: &Path) -> EndpointResult<Self> {
+        let path = path.into();
+        let key = match std::fs::read(key_path) {
+            Ok(key) if key.len() == 32 => key,
+            Ok(_) => {
+                return Err(EndpointError::StorageError(format!(
+                    "Invalid master key file: {}",
+                    key_path.display()
+                )));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = Aes256Gcm::generate_key(OsRng).to_vec();
+                write_private(key_path, &key)?;
+                key
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let entries = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+            entries: Mutex::new(entries),
+        })
+    }
+
+    fn persist(&self, entries: &BTreeMap<String, String>) -> EndpointResult<()> {
+        write_private(&self.path, &serde_json::to_vec_pretty(entries)?)
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn get(&self, name: &str) -> EndpointResult<Option<String>> {
+        let entries = self.entries.lock().unwrap();
+        let Some(encoded) = entries.get(name) else {
+            return Ok(None);
+        };
+        let sealed = STANDARD
+            .decode(encoded)
+            .map_err(|e| EndpointError::StorageError(e.to_string()))?;
+        if sealed.len() < 12 {
+            return Err(EndpointError::StorageError(format!(
+                "Corrupted secret: {}",
+                name
+            )));
+        }
+        let (nonce, ciphertext) = sealed.split_at(12);
+        let plain = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| EndpointError::StorageError(format!("Cannot decrypt secret: {}", name)))?;
+        String::from_utf8(plain)
+            .map(Some)
+            .map_err(|e| EndpointError::StorageError(e.to_string()))
+    }
+
+    fn set(&self, name: &str, value: &str) -> EndpointResult<()> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, value.as_bytes())
+            .map_err(|_| EndpointError::StorageError(format!("Cannot encrypt secret: {}", name)))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(name.to_string(), STANDARD.encode(sealed));
+        self.persist(&entries)
+    }
+
+    fn delete(&self, name: &str) -> EndpointResult<()> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.remove(name).is_some() {
+            self.persist(&entries)?;
+        }
+        Ok(())
+    }
+
+    fn names(&self) -> EndpointResult<Vec<String>> {
+        Ok(self.entries.lock().unwrap().keys().cloned().collect())
+    }
+}
+
+/// 写入仅所有者可读写的文件
+///
+/// 先以 0o600 创建同目录下的临时文件，写入并落盘后原子改名，
+/// 文件在任何时刻都不会以默认权限或半写入的状态出现在目标路径。
+fn write_private(path: &Path, content: &[u8]) -> EndpointResult<()> {
+    use std::io::Write;
+
+    let parent = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(parent)?;
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let temp = path.with_file_name(name);
+    // 上次中断遗留的临时文件权限未知，不复用
+    if temp.exists() {
+        std::fs::remove_file(&temp)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&temp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    std::fs::rename(&temp, path)?;
+    #[cfg(unix)]
+    std::fs::File::open(parent)?.sync_all()?;
+    Ok(())
+}
+
+/// 轮询配置文件的修改时间，变化时重新加载到注册表
+pub struct ConfigWatcher;
+
+impl ConfigWatcher {
+    /// 启动后台轮询任务；加载失败时保留当前配置，等待下一次修改
+    pub fn spawn(
+        path: PathBuf,
+        registry: Arc<RwLock<ModelRegistry>>,
+        secrets: Arc<dyn SecretStore>,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        // 在启动任务前记录基准，避免任务首次运行前发生的修改被当作基准而漏掉
+        let mut last = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let current = modified_at(&path).await;
+                if current == last {
+                    continue;
+                }
+                last = current;
+                if let Ok(config) = RegistryConfig::load(&path).await {
+                    let _ = registry
+                        .write()
+                        .await
+                        .apply_config(config, secrets.as_ref());
+                }
+            }
+        })
+    }
+}
+
+async fn modified_at(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.ok()?.modified().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("master.key");
+        let store_path = dir.path().join("secrets.json");
+        let store = EncryptedFileStore::open(&store_path, &key_path).unwrap();
+        store.set(&api_key_name("openai"), "sk-secret").unwrap();
+
+        let raw = std::fs::read_to_string(&store_path).unwrap();
+        assert!(!raw.contains("sk-secret"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            for path in [&key_path, &store_path] {
+                let mode = std::fs::metadata(path).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+            assert!(!dir.path().join("secrets.json.tmp").exists());
+        }
+
+        let reopened = EncryptedFileStore::open(&store_path, &key_path).unwrap();
+        assert_eq!(
+            reopened.get(&api_key_name("openai")).unwrap().as_deref(),
+            Some("sk-secret")
+        );
+        reopened.delete(&api_key_name("openai")).unwrap();
+        assert_eq!(reopened.get(&api_key_name("openai")).unwrap(), None);
+
+        // 换用其他主密钥无法解密
+        store.set("other", "value").unwrap();
+        let foreign = EncryptedFileStore::open(&store_path, &dir.path().join("other.key")).unwrap();
+        assert!(foreign.get("other").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_registry_config_migration_and_hot_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config/models.json");
+        let secrets: Arc<dyn SecretStore> = Arc::new(
+            EncryptedFileStore::open(
+                dir.path().join("secrets.json"),
+                &dir.path().join("master.key"),
+            )
+            .unwrap(),
+        );
+        tokio::fs::create_dir_all(config_path.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(
+            &config_path,
+            r#"{"providers": [{"id": "work", "kind": "openai", "api_key": "sk-plain",
+                "models": [{"id": "gpt-4o", "name": "GPT-4o", "provider": "",
+                            "context_window": 128000, "supports_vision": true,
+                            "supports_tools": true}]}]}"#,
+        )
+        .await
+        .unwrap();
+
+        let registry = ModelRegistry::from_config_file(&config_path, secrets.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(registry.provider("work").unwrap().name, "openai");
+        assert_eq!(registry.list_by_provider("work")[0].id, "gpt-4o");
+        // 明文 Key 迁移到加密存储并从配置文件中移除
+        let saved = tokio::fs::read_to_string(&config_path).await.unwrap();
+        assert!(!saved.contains("sk-plain"));
+        assert_eq!(
+            secrets.get(&api_key_name("work")).unwrap().as_deref(),
+            Some("sk-plain")
+        );
+
+        let registry = Arc::new(RwLock::new(registry));
+        let watcher = ConfigWatcher::spawn(
+            config_path.clone(),
+            registry.clone(),
+            secrets.clone(),
+            Duration::from_millis(10),
+        );
+        let mut config = registry.read().await.config();
+        config.providers[0].models.clear();
+        config.providers.push(ProviderEntry {
+            id: "local".to_string(),
+            kind: Some("ollama".to_string()),
+            base_url: Some("http://127.0.0.1:11434".to_string()),
+            organization: None,
+            models: vec![],
+            oauth: None,
+            api_key: None,
+        });
+        config.save(&config_path).await.unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&config_path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(5))
+            .unwrap();
+
+        for _ in 0..200 {
+            if registry.read().await.provider("local").is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        watcher.abort();
+        let registry = registry.read().await;
+        assert!(registry.provider("local").is_some());
+        assert!(registry.list_by_provider("work").is_empty());
+        assert_eq!(registry.config().providers.len(), 2);
+    }
+}
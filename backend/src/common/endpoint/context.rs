@@ -0,0 +1,450 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::traits::{
+    ChatMessage, ChatOptions, ContentPart, ImageDetail, LLMClient, MessageContent, MessageRole,
+    ModelLimit, ToolDefinition,
+};
+use serde::{Deserialize, Serialize};
+use tiktoken_rs::CoreBPE;
+use tiktoken_rs::tokenizer::{Tokenizer, get_tokenizer};
+
+/// 每条消息的固定开销（角色与分隔符）
+const MESSAGE_OVERHEAD: u32 = 4;
+/// 回复前缀的固定开销
+const REPLY_PRIMING: u32 = 3;
+/// 低细节图像的 token 数
+const IMAGE_LOW_TOKENS: u32 = 85;
+/// 其他图像按高细节估算的 token 数
+const IMAGE_HIGH_TOKENS: u32 = 765;
+/// 文件引用的估算 token 数（实际大小由提供商决定）
+const FILE_TOKENS: u32 = 256;
+/// 未指定 `max_tokens` 时为输出预留的 token 数
+const DEFAULT_OUTPUT_RESERVE: u32 = 1024;
+/// 摘要消息的 token 上限
+pub const SUMMARY_MAX_TOKENS: u32 = 512;
+/// 摘要作为系统消息插入时的前缀
+pub const SUMMARY_PREFIX: &str = "Summary of the earlier conversation:\n";
+const SUMMARY_PROMPT: &str = "Summarize the following conversation so it can replace the \
+original messages, merging the previous summary into it if one is given. Keep decisions, facts, \
+file names, tool results that matter and open questions; be concise. Reply with the summary only.";
+
+/// 提示词超出上下文窗口时的处理策略
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextStrategy {
+    /// 原样发送，由提供商判定
+    #[default]
+    Passthrough,
+    /// 在本地检测超长并返回 `ContextWindowExceeded`，不发送请求
+    Reject,
+    /// 丢弃最早的对话轮次（保留系统消息与最后一轮）
+    TruncateOldest,
+    /// 丢弃最早的对话轮次，并用模型生成的摘要替代
+    Summarize,
+}
+
+/// tiktoken 风格的 token 计数器
+///
+/// OpenAI 模型使用其对应的编码；其他提供商没有公开的分词器，使用 `cl100k_base` 近似。
+pub struct TokenCounter {
+    bpe: &'static CoreBPE,
+}
+
+impl TokenCounter {
+    pub fn for_model(model: &str) -> Self {
+        let bpe = match get_tokenizer(model) {
+            Some(Tokenizer::O200kBase) => tiktoken_rs::o200k_base_singleton(),
+            Some(Tokenizer::P50kBase) => tiktoken_rs::p50k_base_singleton(),
+            Some(Tokenizer::P50kEdit) => tiktoken_rs::p50k_edit_singleton(),
+            Some(Tokenizer::R50kBase | Tokenizer::Gpt2) => tiktoken_rs::r50k_base_singleton(),
+            Some(Tokenizer::Cl100kBase) | None => tiktoken_rs::cl100k_base_singleton(),
+        };
+        Self { bpe }
+    }
+
+    /// 文本的 token 数
+    pub fn count_text(&self, text: &str) -> u32 {
+        self.bpe.encode_with_special_tokens(text).len() as u32
+    }
+
+    /// 单条消息的 token 数（含固定开销）
+    pub fn count_message(&self, message: &ChatMessage) -> u32 {
+        let content = match &message.content {
+            MessageContent::Text(text) => self.count_text(text),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    ContentPart::Text { text } => self.count_text(text),
+                    ContentPart::ImageUrl {
+                        detail: Some(ImageDetail::Low),
+                        ..
+                    } => IMAGE_LOW_TOKENS,
+                    ContentPart::ImageUrl { .. } => IMAGE_HIGH_TOKENS,
+                    ContentPart::File { .. } => FILE_TOKENS,
+                })
+                .sum(),
+        };
+        let tool_calls: u32 = message
+            .tool_calls
+            .iter()
+            .flatten()
+            .map(|call| {
+                self.count_text(&call.function.name) + self.count_text(&call.function.arguments)
+            })
+            .sum();
+        MESSAGE_OVERHEAD + content + tool_calls
+    }
+
+    /// 一组消息作为提示词的 token 数
+    pub fn count_messages(&self, messages: &[ChatMessage]) -> u32 {
+        messages.iter().map(|m| self.count_message(m)).sum::<u32>() + REPLY_PRIMING
+    }
+
+    /// 工具定义的 token 数
+    pub fn count_tools(&self, tools: &[ToolDefinition]) -> u32 {
+        tools
+            .iter()
+            .map(|tool| self.count_text(&serde_json::to_string(tool).unwrap_or_default()))
+            .sum()
+    }
+}
+
+/// 裁剪结果
+#[derive(Debug, Clone)]
+pub struct Fitted {
+    /// 实际发送的消息
+    pub messages: Vec<ChatMessage>,
+    /// 被丢弃（或被摘要替代）的消息
+    pub dropped: Vec<ChatMessage>,
+    /// 发送消息的 token 数
+    pub prompt_tokens: u32,
+}
+
+/// 按模型上下文窗口裁剪提示词
+pub struct PromptBudget {
+    counter: TokenCounter,
+    limit: ModelLimit,
+}
+
+impl PromptBudget {
+    pub fn new(model: &str, limit: ModelLimit) -> Self {
+        Self {
+            counter: TokenCounter::for_model(model),
+            limit,
+        }
+    }
+
+    pub fn counter(&self) -> &TokenCounter {
+        &self.counter
+    }
+
+    /// 提示词可用的 token 数：上下文窗口减去输出预留与工具定义
+    pub fn budget(&self, options: &ChatOptions) -> u32 {
+        let reserve = options
+            .max_tokens
+            .or(self.limit.output)
+            .unwrap_or(DEFAULT_OUTPUT_RESERVE);
+        let tools = options
+            .tools
+            .as_deref()
+            .map(|t| self.counter.count_tools(t))
+            .unwrap_or(0);
+        self.limit.context.saturating_sub(reserve + tools)
+    }
+
+    /// 按 `options.context_strategy` 裁剪（`Summarize` 只丢弃，不生成摘要）
+    pub fn fit(&self, messages: &[ChatMessage], options: &ChatOptions) -> EndpointResult<Fitted> {
+        let strategy = options.context_strategy.unwrap_or_default();
+        let budget = self.budget(options);
+        let prompt_tokens = self.counter.count_messages(messages);
+        if strategy == ContextStrategy::Passthrough || prompt_tokens <= budget {
+            return Ok(Fitted {
+                messages: messages.to_vec(),
+                dropped: Vec::new(),
+                prompt_tokens,
+            });
+        }
+        if strategy == ContextStrategy::Reject {
+            return Err(self.exceeded(prompt_tokens, options));
+        }
+        self.truncate(messages, budget)
+            .ok_or_else(|| self.exceeded(prompt_tokens, options))
+    }
+
+    /// 按策略准备发送的消息；`Summarize` 策略会调用 `client` 为被丢弃的轮次生成摘要
+    pub async fn prepare(
+        &self,
+        client: &dyn LLMClient,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> EndpointResult<Vec<ChatMessage>> {
+        if options.context_strategy != Some(ContextStrategy::Summarize) {
+            return self.fit(messages, options).map(|f| f.messages);
+        }
+
+        let budget = self.budget(options);
+        let prompt_tokens = self.counter.count_messages(messages);
+        if prompt_tokens <= budget {
+            return Ok(messages.to_vec());
+        }
+        // 为摘要消息预留空间
+        let summary_tokens = SUMMARY_MAX_TOKENS.min(budget / 4);
+        let reserve = summary_tokens + MESSAGE_OVERHEAD + self.counter.count_text(SUMMARY_PREFIX);
+        let fitted = self
+            .truncate(messages, budget.saturating_sub(reserve))
+            .ok_or_else(|| self.exceeded(prompt_tokens, options))?;
+
+        let summary = self
+            .summarize(client, model, None, &fitted.dropped, budget, summary_tokens)
+            .await?;
+        let mut result = fitted.messages;
+        let head = result
+            .iter()
+            .take_while(|m| m.role == MessageRole::System)
+            .count();
+        result.insert(
+            head,
+            ChatMessage::text(
+                MessageRole::System,
+                &format!("{}{}", SUMMARY_PREFIX, summary),
+            ),
+        );
+        Ok(result)
+    }
+
+    /// 从最早的轮次开始丢弃直到不超过 `budget`
+    ///
+    /// 开头的系统消息和最后一轮始终保留；一轮从用户消息开始，包含其后的助手消息与工具结果，
+    /// 整体丢弃以避免留下孤立的工具结果。
+    fn truncate(&self, messages: &[ChatMessage], budget: u32) -> Option<Fitted> {
+        let head = messages
+            .iter()
+            .take_while(|m| m.role == MessageRole::System)
+            .count();
+        let turns = turns(&messages[head..]);
+        let mut total = self.counter.count_messages(messages);
+        let mut dropped_turns = 0;
+        for turn in turns.iter().take(turns.len().saturating_sub(1)) {
+            if total <= budget {
+                break;
+            }
+            total -= turn
+                .iter()
+                .map(|m| self.counter.count_message(m))
+                .sum::<u32>();
+            dropped_turns += 1;
+        }
+        if total > budget {
+            return None;
+        }
+
+        let mut kept = messages[..head].to_vec();
+        kept.extend(
+            turns[dropped_turns..]
+                .iter()
+                .flat_map(|t| t.iter().cloned()),
+        );
+        Some(Fitted {
+            messages: kept,
+            dropped: turns[..dropped_turns].concat(),
+            prompt_tokens: total,
+        })
+    }
+
+    /// 将被替代的消息（及上一份摘要）合并为一份新摘要
+    ///
+    /// 摘要请求本身不超过 `budget`：放不下时从最近的消息开始尽量多地保留；
+    /// 回复不超过 `max_tokens`。
+    pub async fn summarize(
+        &self,
+        client: &dyn LLMClient,
+        model: &str,
+        previous: Option<&str>,
+        messages: &[ChatMessage],
+        budget: u32,
+        max_tokens: u32,
+    ) -> EndpointResult<String> {
+        let header = match previous {
+            Some(summary) => format!("Previous summary:\n{}\n\nNew messages:\n", summary),
+            None => String::new(),
+        };
+        let available = budget.saturating_sub(
+            self.counter.count_text(SUMMARY_PROMPT)
+                + self.counter.count_text(&header)
+                + 2 * MESSAGE_OVERHEAD
+                + REPLY_PRIMING,
+        );
+        let mut transcript: Vec<String> = Vec::new();
+        let mut used = 0;
+        for message in messages.iter().rev() {
+            let mut entry = format!("{:?}: {}", message.role, message.content.as_text());
+            for call in message.tool_calls.iter().flatten() {
+                entry.push_str(&format!(
+                    "\n  called {}({})",
+                    call.function.name, call.function.arguments
+                ));
+            }
+            let tokens = self.counter.count_text(&entry);
+            if used + tokens > available {
+                break;
+            }
+            used += tokens;
+            transcript.push(entry);
+        }
+        transcript.reverse();
+
+        let request = [
+            ChatMessage::text(MessageRole::System, SUMMARY_PROMPT),
+            ChatMessage::text(
+                MessageRole::User,
+                &format!("{}{}", header, transcript.join("\n")),
+            ),
+        ];
+        let options = ChatOptions {
+            max_tokens: Some(max_tokens),
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+        let response = client.chat(model, &request, &options).await?;
+        response
+            .choices
+            .first()
+            .map(|c| c.message.content.as_text().trim().to_string())
+            .filter(|text| !text.is_empty())
+            .ok_or_else(|| EndpointError::ProviderError("Empty summary response".to_string()))
+    }
+
+    fn exceeded(&self, prompt_tokens: u32, options: &ChatOptions) -> EndpointError {
+        EndpointError::ContextWindowExceeded {
+            limit: self.limit.context,
+            requested: prompt_tokens + (self.limit.context - self.budget(options)),
+        }
+    }
+}
+
+/// 按轮次分组：每轮从一条用户消息开始
+fn turns(messages: &[ChatMessage]) -> Vec<Vec<ChatMessage>> {
+    let mut turns: Vec<Vec<ChatMessage>> = Vec::new();
+    for message in messages {
+        match turns.last_mut() {
+            Some(turn) if message.role != MessageRole::User => turn.push(message.clone()),
+            _ => turns.push(vec![message.clone()]),
+        }
+    }
+    turns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::common::endpoint::traits::{FunctionCall, ToolCall};
+
+    fn conversation() -> Vec<ChatMessage> {
+        let filler = "lorem ipsum dolor sit amet ".repeat(40);
+        let mut messages = vec![ChatMessage::text(MessageRole::System, "You are helpful.")];
+        for i in 0..6 {
+            messages.push(ChatMessage::text(
+                MessageRole::User,
+                &format!("{} {}", i, filler),
+            ));
+            messages.push(ChatMessage {
+                tool_calls: Some(vec![ToolCall {
+                    id: format!("call_{}", i),
+                    r#type: "function".to_string(),
+                    function: FunctionCall {
+                        name: "read_file".to_string(),
+                        arguments: "{}".to_string(),
+                    },
+                }]),
+                ..ChatMessage::text(MessageRole::Assistant, "")
+            });
+            messages.push(ChatMessage::tool_result(&format!("call_{}", i), &filler));
+        }
+        messages.push(ChatMessage::text(MessageRole::User, "What now?"));
+        messages
+    }
+
+    fn with_strategy(strategy: ContextStrategy) -> ChatOptions {
+        ChatOptions {
+            max_tokens: Some(100),
+            context_strategy: Some(strategy),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_token_counter() {
+        let counter = TokenCounter::for_model("gpt-4o");
+        assert_eq!(counter.count_text("hello world"), 2);
+        let message = ChatMessage::text(MessageRole::User, "hello world");
+        assert_eq!(counter.count_message(&message), 2 + MESSAGE_OVERHEAD);
+    }
+
+    #[test]
+    fn test_truncate_oldest_keeps_system_and_tool_pairs() {
+        let messages = conversation();
+        let manager = PromptBudget::new(
+            "gpt-4o",
+            ModelLimit {
+                context: 1500,
+                output: None,
+            },
+        );
+        let options = with_strategy(ContextStrategy::TruncateOldest);
+        let fitted = manager.fit(&messages, &options).unwrap();
+
+        assert!(fitted.prompt_tokens <= manager.budget(&options));
+        assert_eq!(
+            fitted.prompt_tokens,
+            manager.counter().count_messages(&fitted.messages)
+        );
+        assert_eq!(fitted.messages[0].role, MessageRole::System);
+        assert_eq!(fitted.messages[1].role, MessageRole::User);
+        assert_eq!(fitted.messages.last(), messages.last());
+        assert_eq!(fitted.dropped.len() + fitted.messages.len(), messages.len());
+
+        let rejected = manager.fit(&messages, &with_strategy(ContextStrategy::Reject));
+        assert!(matches!(
+            rejected,
+            Err(EndpointError::ContextWindowExceeded { limit: 1500, .. })
+        ));
+        let passthrough = manager
+            .fit(&messages, &with_strategy(ContextStrategy::Passthrough))
+            .unwrap();
+        assert_eq!(passthrough.messages.len(), messages.len());
+    }
+
+    #[tokio::test]
+    async fn test_summarize_replaces_dropped_turns() {
+        let messages = conversation();
+        let manager = PromptBudget::new(
+            "claude-sonnet-4",
+            ModelLimit {
+                context: 1500,
+                output: None,
+            },
+        );
+        let options = with_strategy(ContextStrategy::Summarize);
+        let summarizer = ScriptedClient::new("mock").with_replies(["user asked about files"]);
+        let prepared = manager
+            .prepare(&summarizer, "claude-sonnet-4", &messages, &options)
+            .await
+            .unwrap();
+
+        assert!(prepared.len() < messages.len());
+        assert!(
+            prepared[1]
+                .content
+                .as_text()
+                .contains("user asked about files")
+        );
+        assert!(manager.counter().count_messages(&prepared) <= manager.budget(&options));
+        assert!(
+            summarizer.requests()[0].messages[1]
+                .content
+                .as_text()
+                .contains("lorem")
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::common::i18n::{Locale, Localize, translate};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -8,6 +9,14 @@ pub enum EndpointError {
     #[error("Provider error: {0}")]
     ProviderError(String),
 
+    /// 提供者返回的非成功 HTTP 状态（未被映射为更具体变体的部分）
+    #[error("HTTP {status}: {message}")]
+    Http { status: u16, message: String },
+
+    /// 连接失败、超时等未得到 HTTP 响应的传输错误
+    #[error("Network error: {0}")]
+    Network(String),
+
     #[error("Authentication failed: {0}")]
     AuthenticationError(String),
 
@@ -23,6 +32,15 @@ pub enum EndpointError {
     #[error("Stream error: {0}")]
     StreamError(String),
 
+    #[error("Storage error: {0}")]
+    StorageError(String),
+
+    #[error("Content blocked: {0}")]
+    ContentBlocked(String),
+
+    #[error("Model does not support image input: {0}")]
+    VisionUnsupported(String),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -35,6 +53,59 @@ pub enum EndpointError {
 
 pub type EndpointResult<T> = Result<T, EndpointError>;
 
+impl From<reqwest::Error> for EndpointError {
+    fn from(error: reqwest::Error) -> Self {
+        match error.status() {
+            Some(status) => EndpointError::Http {
+                status: status.as_u16(),
+                message: error.to_string(),
+            },
+            // 响应体无法解码说明提供者返回了意外的内容，重试没有意义
+            None if error.is_decode() => EndpointError::ProviderError(error.to_string()),
+            None => EndpointError::Network(error.to_string()),
+        }
+    }
+}
+
+impl Localize for EndpointError {
+    fn localize(&self, locale: Locale) -> String {
+        let (key, args): (&str, Vec<(&str, String)>) = match self {
+            EndpointError::ModelNotFound(m) => ("model_not_found", vec![("model", m.clone())]),
+            EndpointError::ProviderError(d) => ("provider", vec![("detail", d.clone())]),
+            EndpointError::Http { status, message } => (
+                "http",
+                vec![("status", status.to_string()), ("detail", message.clone())],
+            ),
+            EndpointError::Network(d) => ("network", vec![("detail", d.clone())]),
+            EndpointError::AuthenticationError(d) => {
+                ("authentication", vec![("detail", d.clone())])
+            }
+            EndpointError::RateLimitExceeded => ("rate_limit", vec![]),
+            EndpointError::ContextWindowExceeded { limit, requested } => (
+                "context_window",
+                vec![
+                    ("limit", limit.to_string()),
+                    ("requested", requested.to_string()),
+                ],
+            ),
+            EndpointError::InvalidRequest(d) => ("invalid_request", vec![("detail", d.clone())]),
+            EndpointError::StreamError(d) => ("stream", vec![("detail", d.clone())]),
+            EndpointError::StorageError(d) => ("storage", vec![("detail", d.clone())]),
+            EndpointError::ContentBlocked(d) => ("content_blocked", vec![("detail", d.clone())]),
+            EndpointError::VisionUnsupported(m) => {
+                ("vision_unsupported", vec![("model", m.clone())])
+            }
+            EndpointError::IoError(e) => ("io", vec![("detail", e.to_string())]),
+            EndpointError::SerializationError(e) => {
+                ("serialization", vec![("detail", e.to_string())])
+            }
+            EndpointError::Unknown(d) => ("unknown", vec![("detail", d.clone())]),
+        };
+        let args: Vec<(&str, &str)> = args.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        translate(locale, &format!("error.endpoint.{}", key), &args)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -47,5 +118,10 @@ mod tests {
         };
         assert!(err.to_string().contains("limit 4096"));
         assert!(err.to_string().contains("requested 5000"));
+        assert_eq!(err.localize(Locale::EnUs), err.to_string());
+        assert_eq!(
+            err.localize(Locale::ZhCn),
+            "超出上下文窗口：上限 4096，请求 5000"
+        );
     }
 }
@@ -0,0 +1,521 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::stream::{
+    ChatDelta, ChatResponse, ChatStreamEvent, Choice, ProviderAdapter, ProviderConfig, SseEvent,
+    StreamState, insert_opt,
+};
+use crate::common::endpoint::traits::{
+    ChatMessage, ChatOptions, ContentPart, EmbeddingResponse, FunctionCall, MessageContent,
+    MessageRole, ToolCall, Usage,
+};
+use serde_json::{Map, Value, json};
+
+/// Gemini File API 的文件地址前缀（`files/<id>` 形式的引用需补全）
+const FILE_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta/";
+
+/// Google Gemini `generateContent` 协议适配器
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GeminiAdapter;
+
+impl ProviderAdapter for GeminiAdapter {
+    fn id(&self) -> &str {
+        "gemini"
+    }
+
+    fn default_base_url(&self) -> &str {
+        "https://generativelanguage.googleapis.com/v1beta"
+    }
+
+    fn chat_path(&self, model: &str, stream: bool) -> String {
+        if stream {
+            format!("/models/{}:streamGenerateContent?alt=sse", model)
+        } else {
+            format!("/models/{}:generateContent", model)
+        }
+    }
+
+    fn headers(&self, config: &ProviderConfig) -> Vec<(String, String)> {
+        vec![("x-goog-api-key".to_string(), config.api_key.clone())]
+    }
+
+    fn build_chat_request(
+        &self,
+        _model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        _stream: bool,
+    ) -> EndpointResult<Value> {
+        // 系统消息提升为 `systemInstruction`
+        let system: Vec<Value> = messages
+            .iter()
+            .filter(|m| m.role == MessageRole::System)
+            .map(|m| json!({ "text": m.content.as_text() }))
+            .collect();
+
+        // 连续的同角色消息需合并（如多条工具结果）
+        let mut contents: Vec<Value> = Vec::new();
+        for (i, message) in messages.iter().enumerate() {
+            let role = match message.role {
+                MessageRole::System => continue,
+                MessageRole::Assistant => "model",
+                MessageRole::User | MessageRole::Tool => "user",
+            };
+            let parts = content_parts(message, &messages[..i])?;
+            if parts.is_empty() {
+                continue;
+            }
+            match contents.last_mut() {
+                Some(last) if last["role"] == role => {
+                    last["parts"]
+                        .as_array_mut()
+                        .expect("parts is an array")
+                        .extend(parts);
+                }
+                _ => contents.push(json!({ "role": role, "parts": parts })),
+            }
+        }
+
+        let mut body = Map::new();
+        body.insert("contents".to_string(), Value::Array(contents));
+        if !system.is_empty() {
+            body.insert("systemInstruction".to_string(), json!({ "parts": system }));
+        }
+
+        let mut config = Map::new();
+        insert_opt(&mut config, "temperature", &options.temperature);
+        insert_opt(&mut config, "topP", &options.top_p);
+        insert_opt(&mut config, "maxOutputTokens", &options.max_tokens);
+        insert_opt(&mut config, "stopSequences", &options.stop);
+        insert_opt(&mut config, "presencePenalty", &options.presence_penalty);
+        insert_opt(&mut config, "frequencyPenalty", &options.frequency_penalty);
+        if !config.is_empty() {
+            body.insert("generationConfig".to_string(), Value::Object(config));
+        }
+
+        if let Some(tools) = &options.tools {
+            let declarations: Vec<Value> = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.function.name,
+                        "description": tool.function.description,
+                        "parameters": tool.function.parameters,
+                    })
+                })
+                .collect();
+            body.insert(
+                "tools".to_string(),
+                json!([{ "functionDeclarations": declarations }]),
+            );
+        }
+        Ok(Value::Object(body))
+    }
+
+    fn parse_chat_response(&self, body: Value) -> EndpointResult<ChatResponse> {
+        if let Some(message) = body.pointer("/error/message").and_then(Value::as_str) {
+            return Err(EndpointError::ProviderError(message.to_string()));
+        }
+        let candidates = body
+            .get("candidates")
+            .and_then(Value::as_array)
+            .ok_or_else(|| {
+                let reason = body
+                    .pointer("/promptFeedback/blockReason")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Missing candidates");
+                EndpointError::ProviderError(reason.to_string())
+            })?;
+
+        let choices = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| {
+                let mut text = String::new();
+                let mut tool_calls = Vec::new();
+                for part in parts_of(candidate) {
+                    if let Some(t) = part["text"].as_str() {
+                        text.push_str(t);
+                    } else if let Some(call) = part.get("functionCall") {
+                        tool_calls.push(tool_call(call, tool_calls.len()));
+                    }
+                }
+                Choice {
+                    index: candidate["index"].as_u64().unwrap_or(i as u64) as u32,
+                    message: ChatMessage {
+                        role: MessageRole::Assistant,
+                        content: MessageContent::Text(text),
+                        tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                        tool_call_id: None,
+                    },
+                    finish_reason: candidate["finishReason"].as_str().map(finish_reason),
+                }
+            })
+            .collect();
+
+        Ok(ChatResponse {
+            id: body["responseId"].as_str().unwrap_or_default().to_string(),
+            model: body["modelVersion"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            choices,
+            usage: body.get("usageMetadata").map(parse_usage),
+            safety: Vec::new(),
+        })
+    }
+
+    fn parse_stream_event(
+        &self,
+        state: &mut StreamState,
+        event: &SseEvent,
+    ) -> EndpointResult<Vec<ChatStreamEvent>> {
+        let chunk: Value = serde_json::from_str(&event.data)?;
+        if let Some(message) = chunk.pointer("/error/message").and_then(Value::as_str) {
+            return Ok(vec![ChatStreamEvent::Error(message.to_string())]);
+        }
+
+        let mut events = Vec::new();
+        if !state.started {
+            state.started = true;
+            events.push(ChatStreamEvent::Start);
+        }
+
+        let candidate = chunk.pointer("/candidates/0").unwrap_or(&Value::Null);
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        for part in parts_of(candidate) {
+            if let Some(text) = part["text"].as_str() {
+                content.push_str(text);
+            } else if let Some(call) = part.get("functionCall") {
+                // Gemini 一次性给出完整的函数调用，按出现顺序编号
+                let index = state.tool_calls.len() as u64;
+                let call = tool_call(call, index as usize);
+                state
+                    .tool_calls
+                    .insert(index, (call.id.clone(), call.function.name.clone()));
+                tool_calls.push(call);
+            }
+        }
+        if !content.is_empty() || !tool_calls.is_empty() {
+            events.push(ChatStreamEvent::Delta(ChatDelta {
+                role: None,
+                content: (!content.is_empty()).then_some(content),
+                tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            }));
+        }
+
+        if let Some(usage) = chunk.get("usageMetadata") {
+            state.usage = parse_usage(usage);
+        }
+        // SSE 流没有结束标记，以携带停止原因的分片作为结束
+        if candidate.get("finishReason").is_some() {
+            events.push(ChatStreamEvent::Usage(state.usage.clone()));
+            events.push(ChatStreamEvent::Done);
+        }
+        Ok(events)
+    }
+
+    fn embeddings_path(&self, model: &str) -> Option<String> {
+        Some(format!("/models/{}:batchEmbedContents", model))
+    }
+
+    fn build_embedding_request(&self, model: &str, input: &[String]) -> EndpointResult<Value> {
+        let requests: Vec<Value> = input
+            .iter()
+            .map(|text| {
+                json!({
+                    "model": format!("models/{}", model),
+                    "content": { "parts": [{ "text": text }] },
+                })
+            })
+            .collect();
+        Ok(json!({ "requests": requests }))
+    }
+
+    fn parse_embedding_response(&self, body: Value) -> EndpointResult<EmbeddingResponse> {
+        let data = body
+            .get("embeddings")
+            .and_then(Value::as_array)
+            .ok_or_else(|| EndpointError::ProviderError("Missing embeddings".to_string()))?
+            .iter()
+            .map(|item| {
+                item["values"]
+                    .as_array()
+                    .map(|values| {
+                        values
+                            .iter()
+                            .filter_map(Value::as_f64)
+                            .map(|v| v as f32)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+        Ok(EmbeddingResponse {
+            data,
+            usage: Usage::default(),
+        })
+    }
+}
+
+/// 将统一消息转换为 Gemini 内容部分；`history` 用于为工具结果找回函数名
+fn content_parts(message: &ChatMessage, history: &[ChatMessage]) -> EndpointResult<Vec<Value>> {
+    if message.role == MessageRole::Tool {
+        let id = message.tool_call_id.as_ref().ok_or_else(|| {
+            EndpointError::InvalidRequest("Tool message without tool_call_id".to_string())
+        })?;
+        let name = history
+            .iter()
+            .rev()
+            .flat_map(|m| m.tool_calls.iter().flatten())
+            .find(|call| &call.id == id)
+            .map(|call| call.function.name.as_str())
+            .unwrap_or(id);
+        // `response` 必须是对象，非 JSON 对象的结果包装为 `{ "content": ... }`
+        let text = message.content.as_text();
+        let response = match serde_json::from_str::<Value>(&text) {
+            Ok(value) if value.is_object() => value,
+            _ => json!({ "content": text }),
+        };
+        return Ok(vec![json!({
+            "functionResponse": { "name": name, "response": response },
+        })]);
+    }
+
+    let mut parts = match &message.content {
+        MessageContent::Text(text) if text.is_empty() => vec![],
+        MessageContent::Text(text) => vec![json!({ "text": text })],
+        MessageContent::Parts(parts) => parts.iter().map(part_to_wire).collect(),
+    };
+
+    for call in message.tool_calls.iter().flatten() {
+        let args: Value =
+            serde_json::from_str(&call.function.arguments).unwrap_or_else(|_| json!({}));
+        parts.push(json!({
+            "functionCall": { "name": call.function.name, "args": args },
+        }));
+    }
+    Ok(parts)
+}
+
+fn part_to_wire(part: &ContentPart) -> Value {
+    match part {
+        ContentPart::Text { text } => json!({ "text": text }),
+        ContentPart::ImageUrl { url, .. } => {
+            // data URL 内联传输，其余作为文件 URI 引用
+            if let Some(rest) = url.strip_prefix("data:")
+                && let Some((mime_type, data)) = rest.split_once(";base64,")
+            {
+                return json!({ "inlineData": { "mimeType": mime_type, "data": data } });
+            }
+            json!({
+                "fileData": { "mimeType": guess_mime_type(url), "fileUri": url },
+            })
+        }
+        ContentPart::File { file_id, mime_type } => {
+            let uri = if file_id.contains("://") {
+                file_id.clone()
+            } else if file_id.starts_with("files/") {
+                format!("{}{}", FILE_API_BASE, file_id)
+            } else {
+                format!("{}files/{}", FILE_API_BASE, file_id)
+            };
+            let mime_type = mime_type
+                .clone()
+                .unwrap_or_else(|| guess_mime_type(file_id).to_string());
+            json!({ "fileData": { "mimeType": mime_type, "fileUri": uri } })
+        }
+    }
+}
+
+/// 按扩展名推断 MIME 类型（Gemini 的 `fileData` 要求必填）
+fn guess_mime_type(uri: &str) -> &'static str {
+    let path = uri.split(['?', '#']).next().unwrap_or(uri);
+    let ext = path.rsplit('.').next().unwrap_or_default().to_lowercase();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "txt" | "md" => "text/plain",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+fn parts_of(candidate: &Value) -> impl Iterator<Item = &Value> {
+    candidate
+        .pointer("/content/parts")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+/// 转换函数调用；旧版本接口不返回调用 ID 时按序号生成
+fn tool_call(call: &Value, index: usize) -> ToolCall {
+    ToolCall {
+        id: call["id"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("call_{}", index)),
+        r#type: "function".to_string(),
+        function: FunctionCall {
+            name: call["name"].as_str().unwrap_or_default().to_string(),
+            arguments: call.get("args").unwrap_or(&json!({})).to_string(),
+        },
+    }
+}
+
+fn parse_usage(usage: &Value) -> Usage {
+    let field = |name: &str| usage[name].as_u64().unwrap_or(0) as u32;
+    Usage {
+        prompt_tokens: field("promptTokenCount"),
+        completion_tokens: field("candidatesTokenCount"),
+        total_tokens: field("totalTokenCount"),
+    }
+}
+
+/// 将停止原因映射为 OpenAI 风格的 `finish_reason`
+fn finish_reason(reason: &str) -> String {
+    match reason {
+        "STOP" => "stop",
+        "MAX_TOKENS" => "length",
+        "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => "content_filter",
+        other => other,
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gemini_request_mapping() {
+        let adapter = GeminiAdapter;
+        let assistant = ChatMessage {
+            tool_calls: Some(vec![ToolCall {
+                id: "call_0".to_string(),
+                r#type: "function".to_string(),
+                function: FunctionCall {
+                    name: "read_file".to_string(),
+                    arguments: r#"{"path":"a.rs"}"#.to_string(),
+                },
+            }]),
+            ..ChatMessage::text(MessageRole::Assistant, "")
+        };
+        let messages = vec![
+            ChatMessage::text(MessageRole::System, "You are helpful."),
+            ChatMessage {
+                content: MessageContent::Parts(vec![
+                    ContentPart::Text {
+                        text: "Describe these".to_string(),
+                    },
+                    ContentPart::ImageUrl {
+                        url: "data:image/png;base64,AAAA".to_string(),
+                        detail: None,
+                    },
+                    ContentPart::File {
+                        file_id: "files/abc".to_string(),
+                        mime_type: Some("application/pdf".to_string()),
+                    },
+                ]),
+                ..ChatMessage::text(MessageRole::User, "")
+            },
+            assistant,
+            ChatMessage::tool_result("call_0", "fn main() {}"),
+        ];
+
+        let body = adapter
+            .build_chat_request(
+                "gemini-2.5-pro",
+                &messages,
+                &ChatOptions {
+                    max_tokens: Some(64),
+                    ..Default::default()
+                },
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            body["systemInstruction"]["parts"][0]["text"],
+            "You are helpful."
+        );
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 64);
+
+        let contents = body["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 3);
+        let parts = &contents[0]["parts"];
+        assert_eq!(parts[1]["inlineData"]["mimeType"], "image/png");
+        assert_eq!(
+            parts[2]["fileData"]["fileUri"],
+            "https://generativelanguage.googleapis.com/v1beta/files/abc"
+        );
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(
+            contents[1]["parts"][0]["functionCall"]["args"]["path"],
+            "a.rs"
+        );
+        assert_eq!(
+            contents[2]["parts"][0]["functionResponse"]["name"],
+            "read_file"
+        );
+        assert_eq!(
+            contents[2]["parts"][0]["functionResponse"]["response"]["content"],
+            "fn main() {}"
+        );
+    }
+
+    #[test]
+    fn test_gemini_response_and_stream() {
+        let adapter = GeminiAdapter;
+        let response = adapter
+            .parse_chat_response(json!({
+                "candidates": [{
+                    "content": { "role": "model", "parts": [
+                        { "text": "Reading." },
+                        { "functionCall": { "name": "read_file", "args": { "path": "a.rs" } } }
+                    ] },
+                    "finishReason": "STOP"
+                }],
+                "usageMetadata": { "promptTokenCount": 10, "candidatesTokenCount": 5, "totalTokenCount": 15 },
+                "modelVersion": "gemini-2.5-pro"
+            }))
+            .unwrap();
+        let choice = &response.choices[0];
+        assert_eq!(choice.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(choice.message.content.as_text(), "Reading.");
+        let call = &choice.message.tool_calls.as_ref().unwrap()[0];
+        assert_eq!(call.id, "call_0");
+        assert_eq!(call.function.arguments, r#"{"path":"a.rs"}"#);
+        assert_eq!(response.usage.unwrap().total_tokens, 15);
+
+        let mut state = StreamState::default();
+        let mut parse = |data: Value| {
+            adapter
+                .parse_stream_event(
+                    &mut state,
+                    &SseEvent {
+                        event: None,
+                        data: data.to_string(),
+                    },
+                )
+                .unwrap()
+        };
+        let events = parse(json!({
+            "candidates": [{ "content": { "parts": [{ "text": "Hel" }] } }]
+        }));
+        assert!(matches!(events[0], ChatStreamEvent::Start));
+        assert!(
+            matches!(&events[1], ChatStreamEvent::Delta(d) if d.content.as_deref() == Some("Hel"))
+        );
+        let events = parse(json!({
+            "candidates": [{ "content": { "parts": [{ "text": "lo" }] }, "finishReason": "MAX_TOKENS" }],
+            "usageMetadata": { "promptTokenCount": 3, "candidatesTokenCount": 2, "totalTokenCount": 5 }
+        }));
+        assert!(matches!(&events[1], ChatStreamEvent::Usage(u) if u.total_tokens == 5));
+        assert!(matches!(events[2], ChatStreamEvent::Done));
+    }
+}
@@ -0,0 +1,441 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::redaction::{Mask, Redactor, mask_message};
+use crate::common::endpoint::stream::ChatResponse;
+use crate::common::endpoint::traits::{
+    ChatMessage, ChatOptions, EmbeddingResponse, FileContentResponse, FileDeletionStatus,
+    FileObject, FileUploadRequest, LLMClient, ModelInfo,
+};
+use crate::common::provider::traits::StorageProvider;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// 脱敏规则：匹配 `pattern` 的文本会被替换为 `replacement`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionRule {
+    pub name: String,
+    pub pattern: String,
+    pub replacement: String,
+}
+
+impl RedactionRule {
+    pub fn new(name: &str, pattern: &str, replacement: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+        }
+    }
+}
+
+/// 日志保留策略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// 最多保留的条目数
+    pub max_entries: Option<usize>,
+    /// 条目最长保留时间（秒）
+    pub max_age_secs: Option<i64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(1000),
+            max_age_secs: Some(7 * 24 * 3600),
+        }
+    }
+}
+
+/// 提示词日志配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptLogConfig {
+    /// 是否启用日志（默认关闭）
+    pub enabled: bool,
+    pub redaction_rules: Vec<RedactionRule>,
+    pub retention: RetentionPolicy,
+}
+
+impl Default for PromptLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            redaction_rules: vec![
+                RedactionRule::new("api_key", r"sk-[A-Za-z0-9_\-]{16,}", "[REDACTED_API_KEY]"),
+                RedactionRule::new(
+                    "bearer_token",
+                    r"(?i)bearer\s+[A-Za-z0-9._\-]+",
+                    "Bearer [REDACTED_TOKEN]",
+                ),
+                RedactionRule::new(
+                    "email",
+                    r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}",
+                    "[REDACTED_EMAIL]",
+                ),
+            ],
+            retention: RetentionPolicy::default(),
+        }
+    }
+}
+
+/// 一次模型调用的日志记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptLogEntry {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub provider: String,
+    pub model: String,
+    /// 发起调用的 Agent Routine（如有）
+    pub routine_id: Option<Uuid>,
+    pub messages: Vec<ChatMessage>,
+    pub response: Option<ChatResponse>,
+    pub error: Option<String>,
+    pub latency_ms: u64,
+}
+
+/// 日志查询条件
+#[derive(Debug, Clone, Default)]
+pub struct PromptLogQuery {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub routine_id: Option<Uuid>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// 仅返回消息或响应中包含该文本的条目
+    pub contains: Option<String>,
+    /// 仅返回出错的调用
+    pub errors_only: bool,
+    pub limit: Option<usize>,
+}
+
+/// 本地提示词/响应日志
+///
+/// 所有内容在写入前经过脱敏，以 JSON Lines 形式保存在存储提供者中，
+/// 数据不会离开本机，便于用户排查不理想的生成结果。
+pub struct PromptLogger {
+    storage: Arc<dyn StorageProvider>,
+    path: String,
+    config: PromptLogConfig,
+    redactor: Redactor,
+    /// 在脱敏规则之后应用的动态掩码（如项目密钥）
+    mask: Option<Arc<dyn Mask>>,
+    entries: RwLock<Vec<PromptLogEntry>>,
+}
+
+impl PromptLogger {
+    /// 打开位于 `path` 的日志文件
+    pub async fn open(
+        storage: Arc<dyn StorageProvider>,
+        path: &str,
+        config: PromptLogConfig,
+    ) -> EndpointResult<Self> {
+        let mut redactor = Redactor::new();
+        for rule in &config.redaction_rules {
+            redactor = redactor
+                .with_rule(&rule.pattern, &rule.replacement)
+                .map_err(|e| {
+                    EndpointError::InvalidRequest(format!(
+                        "Invalid redaction rule '{}': {}",
+                        rule.name, e
+                    ))
+                })?;
+        }
+
+        let mut entries = Vec::new();
+        if storage.exists(path).await.map_err(storage_error)? {
+            let bytes = storage.read_file(path).await.map_err(storage_error)?;
+            for line in String::from_utf8_lossy(&bytes).lines() {
+                if !line.trim().is_empty() {
+                    entries.push(serde_json::from_str(line)?);
+                }
+            }
+        }
+
+        Ok(Self {
+            storage,
+            path: path.to_string(),
+            config,
+            redactor,
+            mask: None,
+            entries: RwLock::new(entries),
+        })
+    }
+
+    /// 写入前额外应用 `mask`（如 `SecretsManager`），使密钥值不会落盘
+    pub fn with_mask(mut self, mask: Arc<dyn Mask>) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// 日志是否启用
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 对文本应用所有脱敏规则与掩码
+    pub fn redact(&self, text: &str) -> String {
+        let text = self.redactor.redact(text);
+        match &self.mask {
+            Some(mask) => mask.mask(&text),
+            None => text,
+        }
+    }
+
+    /// 记录一次调用；日志未启用时返回 `None`
+    pub async fn record(&self, mut entry: PromptLogEntry) -> EndpointResult<Option<Uuid>> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+
+        for message in &mut entry.messages {
+            mask_message(message, |text| self.redact(text));
+        }
+        if let Some(response) = &mut entry.response {
+            for choice in &mut response.choices {
+                mask_message(&mut choice.message, |text| self.redact(text));
+            }
+        }
+        entry.error = entry.error.map(|e| self.redact(&e));
+
+        let id = entry.id;
+        let mut entries = self.entries.write().await;
+        entries.push(entry);
+        self.apply_retention(&mut entries, Utc::now());
+        self.persist(&entries).await?;
+        Ok(Some(id))
+    }
+
+    /// 按条件查询日志，结果按时间倒序
+    pub async fn query(&self, query: &PromptLogQuery) -> Vec<PromptLogEntry> {
+        let entries = self.entries.read().await;
+        let needle = query.contains.as_ref().map(|c| c.to_lowercase());
+        entries
+            .iter()
+            .rev()
+            .filter(|e| query.provider.as_ref().is_none_or(|p| &e.provider == p))
+            .filter(|e| query.model.as_ref().is_none_or(|m| &e.model == m))
+            .filter(|e| query.routine_id.is_none_or(|r| e.routine_id == Some(r)))
+            .filter(|e| query.since.is_none_or(|t| e.timestamp >= t))
+            .filter(|e| query.until.is_none_or(|t| e.timestamp <= t))
+            .filter(|e| !query.errors_only || e.error.is_some())
+            .filter(|e| {
+                needle.as_ref().is_none_or(|n| {
+                    serde_json::to_string(&(&e.messages, &e.response))
+                        .map(|s| s.to_lowercase().contains(n))
+                        .unwrap_or(false)
+                })
+            })
+            .take(query.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    /// 立即执行一次保留策略清理，返回被移除的条目数
+    pub async fn prune(&self) -> EndpointResult<usize> {
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        self.apply_retention(&mut entries, Utc::now());
+        self.persist(&entries).await?;
+        Ok(before - entries.len())
+    }
+
+    /// 清空所有日志
+    pub async fn clear(&self) -> EndpointResult<()> {
+        let mut entries = self.entries.write().await;
+        entries.clear();
+        self.persist(&entries).await
+    }
+
+    fn apply_retention(&self, entries: &mut Vec<PromptLogEntry>, now: DateTime<Utc>) {
+        let retention = &self.config.retention;
+        if let Some(max_age) = retention.max_age_secs {
+            let cutoff = now - Duration::seconds(max_age);
+            entries.retain(|e| e.timestamp >= cutoff);
+        }
+        if let Some(max_entries) = retention.max_entries
+            && entries.len() > max_entries
+        {
+            let excess = entries.len() - max_entries;
+            entries.drain(..excess);
+        }
+    }
+
+    async fn persist(&self, entries: &[PromptLogEntry]) -> EndpointResult<()> {
+        let mut buffer = Vec::new();
+        for entry in entries {
+            serde_json::to_writer(&mut buffer, entry)?;
+            buffer.push(b'\n');
+        }
+        self.storage
+            .write_file(&self.path, &buffer)
+            .await
+            .map_err(storage_error)
+    }
+}
+
+/// 将聊天调用记入 `PromptLogger` 的客户端包装，由 `ModelRegistry::with_prompt_logger` 安装
+///
+/// 成功与失败的调用都会记录；日志写入失败不影响调用结果。其余接口原样转发。
+pub struct LoggedClient {
+    inner: Arc<dyn LLMClient>,
+    logger: Arc<PromptLogger>,
+    provider: String,
+}
+
+impl LoggedClient {
+    /// `provider` 为客户端在注册表中的键，记入日志条目
+    pub fn new(inner: Arc<dyn LLMClient>, logger: Arc<PromptLogger>, provider: &str) -> Self {
+        Self {
+            inner,
+            logger,
+            provider: provider.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl LLMClient for LoggedClient {
+    fn provider_id(&self) -> &str {
+        self.inner.provider_id()
+    }
+
+    async fn chat(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> EndpointResult<ChatResponse> {
+        let started = Instant::now();
+        let result = self.inner.chat(model, messages, options).await;
+        if self.logger.is_enabled() {
+            let _ = self
+                .logger
+                .record(PromptLogEntry {
+                    id: Uuid::new_v4(),
+                    timestamp: Utc::now(),
+                    provider: self.provider.clone(),
+                    model: model.to_string(),
+                    routine_id: options.routine_id,
+                    messages: messages.to_vec(),
+                    response: result.as_ref().ok().cloned(),
+                    error: result.as_ref().err().map(|e| e.to_string()),
+                    latency_ms: started.elapsed().as_millis() as u64,
+                })
+                .await;
+        }
+        result
+    }
+
+    async fn embed(&self, model: &str, input: &[String]) -> EndpointResult<EmbeddingResponse> {
+        self.inner.embed(model, input).await
+    }
+
+    async fn health_check(&self) -> EndpointResult<()> {
+        self.inner.health_check().await
+    }
+
+    async fn list_models(&self) -> EndpointResult<Vec<ModelInfo>> {
+        self.inner.list_models().await
+    }
+
+    async fn upload_file(&self, request: &FileUploadRequest) -> EndpointResult<FileObject> {
+        self.inner.upload_file(request).await
+    }
+
+    async fn delete_file(&self, file_id: &str) -> EndpointResult<FileDeletionStatus> {
+        self.inner.delete_file(file_id).await
+    }
+
+    async fn get_file_content(&self, file_id: &str) -> EndpointResult<FileContentResponse> {
+        self.inner.get_file_content(file_id).await
+    }
+}
+
+fn storage_error(err: anyhow::Error) -> EndpointError {
+    EndpointError::StorageError(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::traits::{MessageContent, MessageRole};
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use tempfile::tempdir;
+
+    fn entry(model: &str, text: &str) -> PromptLogEntry {
+        PromptLogEntry {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            provider: "openai".to_string(),
+            model: model.to_string(),
+            routine_id: None,
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: MessageContent::Text(text.to_string()),
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            response: None,
+            error: None,
+            latency_ms: 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prompt_logger_redaction_and_retention() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        let config = PromptLogConfig {
+            enabled: true,
+            retention: RetentionPolicy {
+                max_entries: Some(2),
+                max_age_secs: None,
+            },
+            ..Default::default()
+        };
+
+        let logger = PromptLogger::open(storage.clone(), "prompts.jsonl", config.clone())
+            .await
+            .unwrap();
+        logger
+            .record(entry("gpt-4", "key is sk-abcdefghijklmnopqrstuv"))
+            .await
+            .unwrap();
+        logger.record(entry("gpt-4", "second")).await.unwrap();
+        logger.record(entry("claude", "third")).await.unwrap();
+
+        // 重新打开后只保留最近的两条
+        let logger = PromptLogger::open(storage, "prompts.jsonl", config)
+            .await
+            .unwrap();
+        let all = logger.query(&PromptLogQuery::default()).await;
+        assert_eq!(all.len(), 2);
+
+        let by_model = logger
+            .query(&PromptLogQuery {
+                model: Some("claude".to_string()),
+                ..Default::default()
+            })
+            .await;
+        assert_eq!(by_model.len(), 1);
+
+        assert_eq!(
+            logger.redact("token sk-abcdefghijklmnopqrstuv"),
+            "token [REDACTED_API_KEY]"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prompt_logger_disabled_by_default() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        let logger = PromptLogger::open(storage, "prompts.jsonl", PromptLogConfig::default())
+            .await
+            .unwrap();
+        assert!(logger.record(entry("gpt-4", "hi")).await.unwrap().is_none());
+        assert!(logger.query(&PromptLogQuery::default()).await.is_empty());
+    }
+}
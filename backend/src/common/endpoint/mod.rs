@@ -1,15 +1,68 @@
+pub mod anthropic;
+pub mod cache;
+pub mod cassette;
+pub mod catalog;
+pub mod config;
+pub mod context;
 pub mod error;
+pub mod gemini;
+pub mod interceptor;
+pub mod logging;
+pub mod oauth;
+pub mod ollama;
+pub mod openai;
+pub mod preferences;
+pub mod queue;
+pub mod redaction;
 pub mod registry;
+pub mod retry;
+pub mod safety;
+#[cfg(test)]
+pub mod scripted;
 pub mod stream;
+pub mod toolloop;
 pub mod traits;
+pub mod usage;
 
+pub use anthropic::AnthropicAdapter;
+pub use cache::{CacheConfig, CacheStats, ResponseCache};
+pub use cassette::{Cassette, CassetteClient, Interaction, RecordedResponse};
+pub use catalog::{CatalogCache, CatalogProvider, CatalogSource, ModelCatalog};
+pub use config::{
+    ConfigWatcher, EncryptedFileStore, ProviderEntry, RegistryConfig, SecretStore, api_key_name,
+};
+pub use context::{ContextStrategy, PromptBudget, TokenCounter};
 pub use error::EndpointError;
+pub use gemini::GeminiAdapter;
+pub use interceptor::{
+    InjectionGuard, Intercept, InterceptRequest, InterceptedClient, Interceptor,
+    RedactionInterceptor,
+};
+pub use logging::{LoggedClient, PromptLogConfig, PromptLogQuery, PromptLogger};
+pub use oauth::{DeviceAuthorization, DeviceCodeFlow, OAuthConfig, OAuthToken, oauth_token_name};
+pub use ollama::OllamaAdapter;
+pub use openai::OpenAiAdapter;
+pub use preferences::{MODEL_PREFERENCES_PATH, ModelPin, ModelPreferences};
+pub use queue::{QueueStatus, QueuedRequest, RequestQueue};
+pub use redaction::{Mask, Redactor};
 pub use registry::{FileManager, ModelRegistry};
-pub use stream::{ChatDelta, ChatResponse, ChatStreamEvent, Choice, Endpoint, ProviderConfig};
+pub use retry::RetryPolicy;
+pub use safety::{
+    DenyListHook, LicenseHeaderHook, ModerationHook, SafetyAction, SafetyFinding, SafetyHook,
+    SafetyPipeline,
+};
+pub use stream::{
+    ChatDelta, ChatResponse, ChatStream, ChatStreamEvent, Choice, Endpoint, ProviderAdapter,
+    ProviderConfig, StreamFormat, UsageDelta,
+};
+pub use toolloop::{AgentLoop, AgentLoopOutcome, LoopFinish, ToolExecutor, ToolInvocation};
 pub use traits::{
     ChatMessage, ChatOptions, ContentPart, CostBreakdown, Embedding, EmbeddingResponse,
     EmbeddingUsage, FileContentResponse, FileDeletionStatus, FileObject, FilePurpose, FileState,
-    FileUploadRequest, FunctionCall, FunctionDefinition, ImageDetail, MessageContent, MessageRole,
-    ModelCost, ModelInfo, ModelLimit, ModelRoutingResult, ProviderFileState, ProviderInfo,
-    TaskCategory, ToolCall, ToolDefinition, Usage,
+    FileUploadRequest, FunctionCall, FunctionDefinition, ImageDetail, LLMClient, MessageContent,
+    MessageRole, ModelCapabilities, ModelCost, ModelInfo, ModelLimit, ModelRoutingResult,
+    ProviderFileState, ProviderInfo, TaskCategory, ToolCall, ToolDefinition, Usage,
+};
+pub use usage::{
+    MeteredClient, ModelPricing, StreamMeter, UsageLedger, UsageRecord, UsageReport, UsageSummary,
 };
@@ -0,0 +1,351 @@
+use crate::common::endpoint::config::SecretStore;
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 令牌在到期前多久视为过期并刷新
+const EXPIRY_SKEW_SECS: i64 = 60;
+
+/// RFC 8628 未给出 `interval` 时的默认轮询间隔
+const DEFAULT_INTERVAL_SECS: u64 = 5;
+
+/// 提供者的 OAuth 设备码授权配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OAuthConfig {
+    pub client_id: String,
+    pub device_authorization_url: String,
+    pub token_url: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+/// 设备授权响应：用户需在 `verification_uri` 输入 `user_code`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+    #[serde(default)]
+    pub interval: Option<u64>,
+}
+
+/// 保存在 `SecretStore` 中的令牌（加密存储，不写入配置文件）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OAuthToken {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl OAuthToken {
+    /// 是否已过期（或即将过期）
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|at| at - chrono::Duration::seconds(EXPIRY_SKEW_SECS) <= Utc::now())
+    }
+}
+
+/// 提供者 OAuth 令牌在 `SecretStore` 中的名称
+pub fn oauth_token_name(provider: &str) -> String {
+    format!("provider:{}:oauth_token", provider)
+}
+
+/// 读取已保存的令牌
+pub fn load_token(secrets: &dyn SecretStore, provider: &str) -> EndpointResult<Option<OAuthToken>> {
+    secrets
+        .get(&oauth_token_name(provider))?
+        .map(|raw| serde_json::from_str(&raw).map_err(EndpointError::from))
+        .transpose()
+}
+
+pub fn save_token(
+    secrets: &dyn SecretStore,
+    provider: &str,
+    token: &OAuthToken,
+) -> EndpointResult<()> {
+    secrets.set(&oauth_token_name(provider), &serde_json::to_string(token)?)
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+impl TokenResponse {
+    fn into_token(self, previous_refresh: Option<String>) -> EndpointResult<OAuthToken> {
+        let access_token = self.access_token.ok_or_else(|| {
+            EndpointError::AuthenticationError("Token response without access_token".to_string())
+        })?;
+        Ok(OAuthToken {
+            access_token,
+            // 刷新响应可能不返回新的刷新令牌，此时沿用旧值
+            refresh_token: self.refresh_token.or(previous_refresh),
+            expires_at: self
+                .expires_in
+                .map(|secs| Utc::now() + chrono::Duration::seconds(secs)),
+        })
+    }
+
+    fn error(&self) -> EndpointError {
+        let code = self.error.as_deref().unwrap_or("unknown_error");
+        EndpointError::AuthenticationError(match &self.error_description {
+            Some(description) => format!("{}: {}", code, description),
+            None => code.to_string(),
+        })
+    }
+}
+
+/// OAuth 2.0 设备码授权（RFC 8628），供无浏览器、无系统钥匙串的远程或 CLI 部署使用
+///
+/// 用户在其他设备上打开验证地址并输入用户码，本端轮询令牌端点；获得的令牌加密保存在
+/// `SecretStore` 中，过期时以刷新令牌静默续期，不需要手动粘贴 API Key。
+pub struct DeviceCodeFlow {
+    http: reqwest::Client,
+    config: OAuthConfig,
+}
+
+impl DeviceCodeFlow {
+    pub fn new(config: OAuthConfig) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &OAuthConfig {
+        &self.config
+    }
+
+    /// 请求设备码与用户码
+    pub async fn start(&self) -> EndpointResult<DeviceAuthorization> {
+        let scope = self.config.scopes.join(" ");
+        let response = self
+            .http
+            .post(&self.config.device_authorization_url)
+            .header("accept", "application/json")
+            .form(&[
+                ("client_id", self.config.client_id.as_str()),
+                ("scope", scope.as_str()),
+            ])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(EndpointError::AuthenticationError(format!(
+                "Device authorization failed: {}",
+                response.status()
+            )));
+        }
+        response.json().await.map_err(EndpointError::from)
+    }
+
+    /// 轮询令牌端点直到用户完成授权、拒绝或设备码过期
+    pub async fn poll(&self, authorization: &DeviceAuthorization) -> EndpointResult<OAuthToken> {
+        let mut interval = authorization.interval.unwrap_or(DEFAULT_INTERVAL_SECS);
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(authorization.expires_in);
+        loop {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            if tokio::time::Instant::now() >= deadline {
+                return Err(EndpointError::AuthenticationError(
+                    "Device code expired".to_string(),
+                ));
+            }
+            let response = self
+                .request_token(&[
+                    ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                    ("device_code", authorization.device_code.as_str()),
+                ])
+                .await?;
+            match response.error.as_deref() {
+                None => return response.into_token(None),
+                Some("authorization_pending") => {}
+                Some("slow_down") => interval += 5,
+                Some(_) => return Err(response.error()),
+            }
+        }
+    }
+
+    /// 以刷新令牌换取新的访问令牌
+    pub async fn refresh(&self, refresh_token: &str) -> EndpointResult<OAuthToken> {
+        let response = self
+            .request_token(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+            ])
+            .await?;
+        if response.error.is_some() {
+            return Err(response.error());
+        }
+        response.into_token(Some(refresh_token.to_string()))
+    }
+
+    /// 完整的设备码授权：`prompt` 负责向用户展示验证地址与用户码，成功后保存令牌
+    pub async fn authorize(
+        &self,
+        provider: &str,
+        secrets: &dyn SecretStore,
+        prompt: impl FnOnce(&DeviceAuthorization),
+    ) -> EndpointResult<OAuthToken> {
+        let authorization = self.start().await?;
+        prompt(&authorization);
+        let token = self.poll(&authorization).await?;
+        save_token(secrets, provider, &token)?;
+        Ok(token)
+    }
+
+    /// 确保已保存的令牌有效：过期时刷新并保存，返回是否发生了刷新
+    ///
+    /// 没有已保存的令牌，或令牌已过期且没有刷新令牌时返回认证错误，需要重新授权。
+    pub async fn ensure_fresh(
+        &self,
+        provider: &str,
+        secrets: &dyn SecretStore,
+    ) -> EndpointResult<bool> {
+        let token = load_token(secrets, provider)?.ok_or_else(|| {
+            EndpointError::AuthenticationError(format!("Provider {} is not authorized", provider))
+        })?;
+        if !token.is_expired() {
+            return Ok(false);
+        }
+        let refresh_token = token.refresh_token.ok_or_else(|| {
+            EndpointError::AuthenticationError(format!(
+                "Token for {} expired and cannot be refreshed",
+                provider
+            ))
+        })?;
+        let token = self.refresh(&refresh_token).await?;
+        save_token(secrets, provider, &token)?;
+        Ok(true)
+    }
+
+    async fn request_token(&self, params: &[(&str, &str)]) -> EndpointResult<TokenResponse> {
+        let mut form = vec![("client_id", self.config.client_id.as_str())];
+        form.extend_from_slice(params);
+        let response = self
+            .http
+            .post(&self.config.token_url)
+            .header("accept", "application/json")
+            .form(&form)
+            .send()
+            .await?;
+        // 错误（包括 `authorization_pending`）以 4xx 状态与 JSON 错误体返回
+        response.json().await.map_err(EndpointError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::config::EncryptedFileStore;
+    use axum::Router;
+    use axum::extract::Form;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    async fn serve() -> String {
+        let polls = Arc::new(AtomicU32::new(0));
+        let app = Router::new()
+            .route(
+                "/device",
+                post(|| async {
+                    axum::Json(json!({
+                        "device_code": "dev-1",
+                        "user_code": "ABCD-EFGH",
+                        "verification_uri": "https://example.com/device",
+                        "expires_in": 60,
+                        "interval": 0,
+                    }))
+                }),
+            )
+            .route(
+                "/token",
+                post(move |Form(form): Form<HashMap<String, String>>| {
+                    let polls = polls.clone();
+                    async move {
+                        let reply = |status: StatusCode, body: Value| (status, axum::Json(body));
+                        match form["grant_type"].as_str() {
+                            "refresh_token" if form["refresh_token"] == "refresh-1" => reply(
+                                StatusCode::OK,
+                                json!({ "access_token": "access-2", "expires_in": 3600 }),
+                            ),
+                            "refresh_token" => {
+                                reply(StatusCode::BAD_REQUEST, json!({ "error": "invalid_grant" }))
+                            }
+                            _ if polls.fetch_add(1, Ordering::SeqCst) < 2 => reply(
+                                StatusCode::BAD_REQUEST,
+                                json!({ "error": "authorization_pending" }),
+                            ),
+                            _ => reply(
+                                StatusCode::OK,
+                                json!({
+                                    "access_token": "access-1",
+                                    "refresh_token": "refresh-1",
+                                    "expires_in": 30,
+                                }),
+                            ),
+                        }
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_device_code_flow_and_refresh() {
+        let base = serve().await;
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = EncryptedFileStore::open(
+            dir.path().join("secrets.json"),
+            &dir.path().join("master.key"),
+        )
+        .unwrap();
+        let flow = DeviceCodeFlow::new(OAuthConfig {
+            client_id: "zhiyun".to_string(),
+            device_authorization_url: format!("{}/device", base),
+            token_url: format!("{}/token", base),
+            scopes: vec!["read:user".to_string()],
+        });
+
+        assert!(flow.ensure_fresh("copilot", &secrets).await.is_err());
+        let mut shown = None;
+        let token = flow
+            .authorize("copilot", &secrets, |auth| {
+                shown = Some(auth.user_code.clone())
+            })
+            .await
+            .unwrap();
+        assert_eq!(shown.as_deref(), Some("ABCD-EFGH"));
+        assert_eq!(token.access_token, "access-1");
+        // 30 秒后到期，在提前量内视为过期
+        assert!(token.is_expired());
+        let raw = std::fs::read_to_string(dir.path().join("secrets.json")).unwrap();
+        assert!(!raw.contains("refresh-1"));
+
+        assert!(flow.ensure_fresh("copilot", &secrets).await.unwrap());
+        let refreshed = load_token(&secrets, "copilot").unwrap().unwrap();
+        assert_eq!(refreshed.access_token, "access-2");
+        assert_eq!(refreshed.refresh_token.as_deref(), Some("refresh-1"));
+        assert!(!flow.ensure_fresh("copilot", &secrets).await.unwrap());
+
+        let error = flow.refresh("stale").await.unwrap_err();
+        assert!(error.to_string().contains("invalid_grant"));
+    }
+}
@@ -0,0 +1,436 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::stream::{
+    ChatDelta, ChatResponse, ChatStreamEvent, Choice, ProviderAdapter, ProviderConfig, SseEvent,
+    StreamFormat, StreamState, insert_opt,
+};
+use crate::common::endpoint::traits::{
+    ChatMessage, ChatOptions, ContentPart, EmbeddingResponse, FunctionCall, MessageContent,
+    MessageRole, ModelInfo, ToolCall, Usage,
+};
+use serde_json::{Map, Value, json};
+
+/// 模型列表不包含上下文长度时使用的默认值（Ollama 默认的 `num_ctx`）
+const DEFAULT_CONTEXT_WINDOW: u32 = 4096;
+
+/// Ollama 原生接口适配器，用于本地托管的模型
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OllamaAdapter;
+
+impl ProviderAdapter for OllamaAdapter {
+    fn id(&self) -> &str {
+        "ollama"
+    }
+
+    fn default_base_url(&self) -> &str {
+        "http://localhost:11434"
+    }
+
+    fn chat_path(&self, _model: &str, _stream: bool) -> String {
+        "/api/chat".to_string()
+    }
+
+    fn models_path(&self) -> String {
+        "/api/tags".to_string()
+    }
+
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::NdJson
+    }
+
+    fn headers(&self, config: &ProviderConfig) -> Vec<(String, String)> {
+        // 本地服务通常无需认证；经反向代理暴露时可配置令牌
+        if config.api_key.is_empty() {
+            return Vec::new();
+        }
+        vec![(
+            "Authorization".to_string(),
+            format!("Bearer {}", config.api_key),
+        )]
+    }
+
+    fn build_chat_request(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        stream: bool,
+    ) -> EndpointResult<Value> {
+        let messages = messages
+            .iter()
+            .map(message_to_wire)
+            .collect::<EndpointResult<Vec<_>>>()?;
+
+        let mut body = Map::new();
+        body.insert("model".to_string(), json!(model));
+        body.insert("messages".to_string(), Value::Array(messages));
+        body.insert("stream".to_string(), json!(stream));
+
+        let mut model_options = Map::new();
+        insert_opt(&mut model_options, "temperature", &options.temperature);
+        insert_opt(&mut model_options, "top_p", &options.top_p);
+        insert_opt(&mut model_options, "num_predict", &options.max_tokens);
+        insert_opt(&mut model_options, "stop", &options.stop);
+        insert_opt(&mut model_options, "seed", &options.seed);
+        insert_opt(
+            &mut model_options,
+            "presence_penalty",
+            &options.presence_penalty,
+        );
+        insert_opt(
+            &mut model_options,
+            "frequency_penalty",
+            &options.frequency_penalty,
+        );
+        if !model_options.is_empty() {
+            body.insert("options".to_string(), Value::Object(model_options));
+        }
+        if let Some(tools) = &options.tools {
+            body.insert("tools".to_string(), json!(tools));
+        }
+        Ok(Value::Object(body))
+    }
+
+    fn parse_chat_response(&self, body: Value) -> EndpointResult<ChatResponse> {
+        if let Some(error) = body.get("error").and_then(Value::as_str) {
+            return Err(EndpointError::ProviderError(error.to_string()));
+        }
+        let message = body
+            .get("message")
+            .ok_or_else(|| EndpointError::ProviderError("Missing message".to_string()))?;
+        let tool_calls = tool_calls(message, &mut 0);
+
+        Ok(ChatResponse {
+            id: body["created_at"].as_str().unwrap_or_default().to_string(),
+            model: body["model"].as_str().unwrap_or_default().to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: ChatMessage {
+                    role: MessageRole::Assistant,
+                    content: MessageContent::Text(
+                        message["content"].as_str().unwrap_or_default().to_string(),
+                    ),
+                    tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                    tool_call_id: None,
+                },
+                finish_reason: body["done_reason"].as_str().map(finish_reason),
+            }],
+            usage: Some(parse_usage(&body)),
+            safety: Vec::new(),
+        })
+    }
+
+    fn parse_stream_event(
+        &self,
+        state: &mut StreamState,
+        event: &SseEvent,
+    ) -> EndpointResult<Vec<ChatStreamEvent>> {
+        let chunk: Value = serde_json::from_str(&event.data)?;
+        if let Some(error) = chunk.get("error").and_then(Value::as_str) {
+            return Ok(vec![ChatStreamEvent::Error(error.to_string())]);
+        }
+
+        let mut events = Vec::new();
+        if !state.started {
+            state.started = true;
+            events.push(ChatStreamEvent::Start);
+        }
+
+        if let Some(message) = chunk.get("message") {
+            // 工具调用在单个分片中完整给出，按出现顺序编号
+            let mut next = state.tool_calls.len();
+            let calls = tool_calls(message, &mut next);
+            for call in &calls {
+                state.tool_calls.insert(
+                    state.tool_calls.len() as u64,
+                    (call.id.clone(), call.function.name.clone()),
+                );
+            }
+            let content = message["content"]
+                .as_str()
+                .filter(|c| !c.is_empty())
+                .map(str::to_string);
+            if content.is_some() || !calls.is_empty() {
+                events.push(ChatStreamEvent::Delta(ChatDelta {
+                    role: None,
+                    content,
+                    tool_calls: (!calls.is_empty()).then_some(calls),
+                }));
+            }
+        }
+
+        if chunk["done"].as_bool().unwrap_or(false) {
+            state.usage = parse_usage(&chunk);
+            events.push(ChatStreamEvent::Usage(state.usage.clone()));
+            events.push(ChatStreamEvent::Done);
+        }
+        Ok(events)
+    }
+
+    fn embeddings_path(&self, _model: &str) -> Option<String> {
+        Some("/api/embed".to_string())
+    }
+
+    fn build_embedding_request(&self, model: &str, input: &[String]) -> EndpointResult<Value> {
+        Ok(json!({ "model": model, "input": input }))
+    }
+
+    fn parse_embedding_response(&self, body: Value) -> EndpointResult<EmbeddingResponse> {
+        let data = body
+            .get("embeddings")
+            .and_then(Value::as_array)
+            .ok_or_else(|| EndpointError::ProviderError("Missing embeddings".to_string()))?
+            .iter()
+            .map(|values| {
+                values
+                    .as_array()
+                    .map(|values| {
+                        values
+                            .iter()
+                            .filter_map(Value::as_f64)
+                            .map(|v| v as f32)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+        Ok(EmbeddingResponse {
+            data,
+            usage: parse_usage(&body),
+        })
+    }
+
+    fn parse_models_response(&self, provider: &str, body: Value) -> EndpointResult<Vec<ModelInfo>> {
+        let models = body
+            .get("models")
+            .and_then(Value::as_array)
+            .ok_or_else(|| EndpointError::ProviderError("Missing model list".to_string()))?
+            .iter()
+            .filter_map(|model| {
+                let id = model["model"].as_str().or_else(|| model["name"].as_str())?;
+                let families: Vec<&str> = model
+                    .pointer("/details/families")
+                    .and_then(Value::as_array)
+                    .map(|f| f.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+                Some(ModelInfo {
+                    id: id.to_string(),
+                    name: model["name"].as_str().unwrap_or(id).to_string(),
+                    provider: provider.to_string(),
+                    context_window: DEFAULT_CONTEXT_WINDOW,
+                    // 多模态模型会带有视觉编码器家族
+                    supports_vision: families.iter().any(|f| matches!(*f, "clip" | "mllama")),
+                    supports_tools: false,
+                    supports_reasoning: false,
+                    cost: None,
+                })
+            })
+            .collect();
+        Ok(models)
+    }
+
+    fn map_error(&self, status: u16, body: &str) -> EndpointError {
+        let message = serde_json::from_str::<Value>(body)
+            .ok()
+            .and_then(|v| v.get("error").and_then(Value::as_str).map(str::to_string))
+            .unwrap_or_else(|| body.to_string());
+        match status {
+            401 | 403 => EndpointError::AuthenticationError(message),
+            404 => EndpointError::ModelNotFound(message),
+            400 | 422 => EndpointError::InvalidRequest(message),
+            _ => EndpointError::Http { status, message },
+        }
+    }
+}
+
+/// 将统一消息转换为 Ollama 消息；图像需以 base64 内联
+fn message_to_wire(message: &ChatMessage) -> EndpointResult<Value> {
+    let mut text = Vec::new();
+    let mut images = Vec::new();
+    match &message.content {
+        MessageContent::Text(t) => text.push(t.as_str()),
+        MessageContent::Parts(parts) => {
+            for part in parts {
+                match part {
+                    ContentPart::Text { text: t } => text.push(t.as_str()),
+                    ContentPart::ImageUrl { url, .. } => {
+                        let data = url
+                            .strip_prefix("data:")
+                            .and_then(|rest| rest.split_once(";base64,"))
+                            .map(|(_, data)| data)
+                            .ok_or_else(|| {
+                                EndpointError::InvalidRequest(
+                                    "Ollama only accepts base64 data URL images".to_string(),
+                                )
+                            })?;
+                        images.push(data);
+                    }
+                    ContentPart::File { file_id, .. } => {
+                        return Err(EndpointError::InvalidRequest(format!(
+                            "Ollama does not support file references: {}",
+                            file_id
+                        )));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut wire = json!({ "role": message.role, "content": text.join("\n") });
+    if !images.is_empty() {
+        wire["images"] = json!(images);
+    }
+    if let Some(calls) = &message.tool_calls {
+        let calls: Vec<Value> = calls
+            .iter()
+            .map(|call| {
+                let arguments: Value =
+                    serde_json::from_str(&call.function.arguments).unwrap_or_else(|_| json!({}));
+                json!({ "function": { "name": call.function.name, "arguments": arguments } })
+            })
+            .collect();
+        wire["tool_calls"] = Value::Array(calls);
+    }
+    Ok(wire)
+}
+
+/// 提取工具调用；Ollama 不返回调用 ID，按序号生成
+fn tool_calls(message: &Value, next: &mut usize) -> Vec<ToolCall> {
+    message
+        .get("tool_calls")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|call| {
+            let id = format!("call_{}", *next);
+            *next += 1;
+            ToolCall {
+                id,
+                r#type: "function".to_string(),
+                function: FunctionCall {
+                    name: call
+                        .pointer("/function/name")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    arguments: call
+                        .pointer("/function/arguments")
+                        .map(Value::to_string)
+                        .unwrap_or_else(|| "{}".to_string()),
+                },
+            }
+        })
+        .collect()
+}
+
+fn parse_usage(body: &Value) -> Usage {
+    let prompt = body["prompt_eval_count"].as_u64().unwrap_or(0) as u32;
+    let completion = body["eval_count"].as_u64().unwrap_or(0) as u32;
+    Usage {
+        prompt_tokens: prompt,
+        completion_tokens: completion,
+        total_tokens: prompt + completion,
+    }
+}
+
+/// 将停止原因映射为 OpenAI 风格的 `finish_reason`
+fn finish_reason(reason: &str) -> String {
+    match reason {
+        "length" => "length",
+        "stop" | "unload" => "stop",
+        other => other,
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::stream::NdjsonParser;
+
+    #[test]
+    fn test_ollama_request_and_models() {
+        let adapter = OllamaAdapter;
+        let messages = vec![ChatMessage {
+            content: MessageContent::Parts(vec![
+                ContentPart::Text {
+                    text: "What is this?".to_string(),
+                },
+                ContentPart::ImageUrl {
+                    url: "data:image/png;base64,AAAA".to_string(),
+                    detail: None,
+                },
+            ]),
+            ..ChatMessage::text(MessageRole::User, "")
+        }];
+        let body = adapter
+            .build_chat_request(
+                "llava",
+                &messages,
+                &ChatOptions {
+                    max_tokens: Some(32),
+                    ..Default::default()
+                },
+                true,
+            )
+            .unwrap();
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["options"]["num_predict"], 32);
+        assert_eq!(body["messages"][0]["images"][0], "AAAA");
+
+        let remote = vec![ChatMessage {
+            content: MessageContent::Parts(vec![ContentPart::ImageUrl {
+                url: "https://example.com/a.png".to_string(),
+                detail: None,
+            }]),
+            ..ChatMessage::text(MessageRole::User, "")
+        }];
+        assert!(
+            adapter
+                .build_chat_request("llava", &remote, &ChatOptions::default(), false)
+                .is_err()
+        );
+
+        let models = adapter
+            .parse_models_response(
+                "ollama",
+                json!({ "models": [
+                    { "name": "llava:7b", "model": "llava:7b", "details": { "families": ["llama", "clip"] } },
+                    { "name": "qwen2.5-coder:7b", "model": "qwen2.5-coder:7b", "details": { "families": ["qwen2"] } }
+                ] }),
+            )
+            .unwrap();
+        assert_eq!(models.len(), 2);
+        assert!(models[0].supports_vision);
+        assert!(!models[1].supports_vision);
+        assert_eq!(models[1].provider, "ollama");
+    }
+
+    #[test]
+    fn test_ollama_ndjson_stream() {
+        let adapter = OllamaAdapter;
+        let mut parser = NdjsonParser::new();
+        let mut lines = parser.feed(b"{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n{\"message\":{\"content\":\"lo\"},");
+        lines.extend(parser.feed(b"\"done\":false}\n{\"message\":{\"content\":\"\",\"tool_calls\":[{\"function\":{\"name\":\"grep\",\"arguments\":{\"q\":\"x\"}}}]},\"done\":true,\"done_reason\":\"stop\",\"prompt_eval_count\":4,\"eval_count\":6}\n"));
+        assert_eq!(lines.len(), 3);
+
+        let mut state = StreamState::default();
+        let events: Vec<ChatStreamEvent> = lines
+            .iter()
+            .flat_map(|line| adapter.parse_stream_event(&mut state, line).unwrap())
+            .collect();
+        assert!(matches!(events[0], ChatStreamEvent::Start));
+        assert!(
+            matches!(&events[2], ChatStreamEvent::Delta(d) if d.content.as_deref() == Some("lo"))
+        );
+        match &events[3] {
+            ChatStreamEvent::Delta(delta) => {
+                let call = &delta.tool_calls.as_ref().unwrap()[0];
+                assert_eq!(call.id, "call_0");
+                assert_eq!(call.function.arguments, r#"{"q":"x"}"#);
+            }
+            other => panic!("Unexpected event: {:?}", other),
+        }
+        assert!(matches!(&events[4], ChatStreamEvent::Usage(u) if u.total_tokens == 10));
+        assert!(matches!(events[5], ChatStreamEvent::Done));
+    }
+}
@@ -0,0 +1,336 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::stream::{
+    ChatDelta, ChatResponse, ChatStreamEvent, Choice, ProviderAdapter, ProviderConfig, SseEvent,
+    StreamState, insert_opt,
+};
+use crate::common::endpoint::traits::{
+    ChatMessage, ChatOptions, ContentPart, EmbeddingResponse, FunctionCall, MessageContent,
+    MessageRole, ToolCall, Usage,
+};
+use serde_json::{Map, Value, json};
+
+/// OpenAI Chat Completions 协议适配器（亦适用于各类 OpenAI 兼容服务）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenAiAdapter;
+
+impl ProviderAdapter for OpenAiAdapter {
+    fn id(&self) -> &str {
+        "openai"
+    }
+
+    fn default_base_url(&self) -> &str {
+        "https://api.openai.com/v1"
+    }
+
+    fn chat_path(&self, _model: &str, _stream: bool) -> String {
+        "/chat/completions".to_string()
+    }
+
+    fn headers(&self, config: &ProviderConfig) -> Vec<(String, String)> {
+        let mut headers = vec![(
+            "Authorization".to_string(),
+            format!("Bearer {}", config.api_key),
+        )];
+        if let Some(org) = &config.organization {
+            headers.push(("OpenAI-Organization".to_string(), org.clone()));
+        }
+        headers
+    }
+
+    fn build_chat_request(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        stream: bool,
+    ) -> EndpointResult<Value> {
+        let mut body = Map::new();
+        body.insert("model".to_string(), json!(model));
+        body.insert(
+            "messages".to_string(),
+            Value::Array(messages.iter().map(message_to_wire).collect()),
+        );
+        if stream {
+            body.insert("stream".to_string(), json!(true));
+            body.insert(
+                "stream_options".to_string(),
+                json!({ "include_usage": true }),
+            );
+        }
+        insert_opt(&mut body, "temperature", &options.temperature);
+        insert_opt(&mut body, "top_p", &options.top_p);
+        insert_opt(&mut body, "max_tokens", &options.max_tokens);
+        insert_opt(&mut body, "stop", &options.stop);
+        insert_opt(&mut body, "presence_penalty", &options.presence_penalty);
+        insert_opt(&mut body, "frequency_penalty", &options.frequency_penalty);
+        insert_opt(&mut body, "user", &options.user);
+        insert_opt(&mut body, "seed", &options.seed);
+        insert_opt(&mut body, "tools", &options.tools);
+        Ok(Value::Object(body))
+    }
+
+    fn parse_chat_response(&self, body: Value) -> EndpointResult<ChatResponse> {
+        let choices = body
+            .get("choices")
+            .and_then(Value::as_array)
+            .ok_or_else(|| EndpointError::ProviderError("Missing choices".to_string()))?
+            .iter()
+            .enumerate()
+            .map(|(i, choice)| Choice {
+                index: choice
+                    .get("index")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(i as u64) as u32,
+                message: message_from_wire(choice.get("message").unwrap_or(&Value::Null)),
+                finish_reason: str_field(choice, "finish_reason"),
+            })
+            .collect();
+
+        Ok(ChatResponse {
+            id: str_field(&body, "id").unwrap_or_default(),
+            model: str_field(&body, "model").unwrap_or_default(),
+            choices,
+            usage: body.get("usage").and_then(parse_usage),
+            safety: Vec::new(),
+        })
+    }
+
+    fn parse_stream_event(
+        &self,
+        state: &mut StreamState,
+        event: &SseEvent,
+    ) -> EndpointResult<Vec<ChatStreamEvent>> {
+        if event.data.trim() == "[DONE]" {
+            return Ok(vec![ChatStreamEvent::Done]);
+        }
+        let chunk: Value = serde_json::from_str(&event.data)?;
+        if let Some(message) = chunk.pointer("/error/message").and_then(Value::as_str) {
+            return Ok(vec![ChatStreamEvent::Error(message.to_string())]);
+        }
+
+        let mut events = Vec::new();
+        if !state.started {
+            state.started = true;
+            events.push(ChatStreamEvent::Start);
+        }
+
+        if let Some(delta) = chunk.pointer("/choices/0/delta") {
+            let tool_calls = delta
+                .get("tool_calls")
+                .and_then(Value::as_array)
+                .map(|calls| {
+                    calls
+                        .iter()
+                        .map(|call| {
+                            let index = call.get("index").and_then(Value::as_u64).unwrap_or(0);
+                            let name = call.pointer("/function/name").and_then(Value::as_str);
+                            // 后续分片只带 index，需要补全首个分片中的 ID 与函数名
+                            let entry = state.tool_calls.entry(index).or_default();
+                            if let Some(id) = call.get("id").and_then(Value::as_str) {
+                                entry.0 = id.to_string();
+                            }
+                            if let Some(name) = name {
+                                entry.1 = name.to_string();
+                            }
+                            ToolCall {
+                                id: entry.0.clone(),
+                                r#type: "function".to_string(),
+                                function: FunctionCall {
+                                    name: entry.1.clone(),
+                                    arguments: call
+                                        .pointer("/function/arguments")
+                                        .and_then(Value::as_str)
+                                        .unwrap_or_default()
+                                        .to_string(),
+                                },
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                });
+            let content = str_field(delta, "content");
+            let role = str_field(delta, "role");
+            if content.is_some() || tool_calls.is_some() || role.is_some() {
+                events.push(ChatStreamEvent::Delta(ChatDelta {
+                    role,
+                    content,
+                    tool_calls,
+                }));
+            }
+        }
+
+        if let Some(usage) = chunk.get("usage").and_then(parse_usage) {
+            state.usage = usage.clone();
+            events.push(ChatStreamEvent::Usage(usage));
+        }
+        Ok(events)
+    }
+
+    fn files_path(&self) -> Option<String> {
+        Some("/files".to_string())
+    }
+
+    fn max_file_size(&self) -> Option<u64> {
+        Some(512 * 1024 * 1024)
+    }
+
+    fn embeddings_path(&self, _model: &str) -> Option<String> {
+        Some("/embeddings".to_string())
+    }
+
+    fn build_embedding_request(&self, model: &str, input: &[String]) -> EndpointResult<Value> {
+        Ok(json!({ "model": model, "input": input }))
+    }
+
+    fn parse_embedding_response(&self, body: Value) -> EndpointResult<EmbeddingResponse> {
+        let data = body
+            .get("data")
+            .and_then(Value::as_array)
+            .ok_or_else(|| EndpointError::ProviderError("Missing embedding data".to_string()))?
+            .iter()
+            .map(|item| {
+                item.get("embedding")
+                    .and_then(Value::as_array)
+                    .map(|values| {
+                        values
+                            .iter()
+                            .filter_map(Value::as_f64)
+                            .map(|v| v as f32)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+        Ok(EmbeddingResponse {
+            data,
+            usage: body.get("usage").and_then(parse_usage).unwrap_or_default(),
+        })
+    }
+}
+
+/// 将统一消息转换为 OpenAI 线上格式
+fn message_to_wire(message: &ChatMessage) -> Value {
+    let content = match &message.content {
+        MessageContent::Text(text) => json!(text),
+        MessageContent::Parts(parts) => Value::Array(
+            parts
+                .iter()
+                .map(|part| match part {
+                    ContentPart::Text { text } => json!({ "type": "text", "text": text }),
+                    ContentPart::ImageUrl { url, detail } => json!({
+                        "type": "image_url",
+                        "image_url": { "url": url, "detail": detail },
+                    }),
+                    ContentPart::File { file_id, .. } => json!({
+                        "type": "file",
+                        "file": { "file_id": file_id },
+                    }),
+                })
+                .collect(),
+        ),
+    };
+    let mut wire = json!({ "role": message.role, "content": content });
+    if let Some(calls) = &message.tool_calls {
+        wire["tool_calls"] = json!(calls);
+    }
+    if let Some(id) = &message.tool_call_id {
+        wire["tool_call_id"] = json!(id);
+    }
+    wire
+}
+
+fn message_from_wire(value: &Value) -> ChatMessage {
+    let role = value
+        .get("role")
+        .cloned()
+        .and_then(|r| serde_json::from_value(r).ok())
+        .unwrap_or(MessageRole::Assistant);
+    ChatMessage {
+        role,
+        content: MessageContent::Text(str_field(value, "content").unwrap_or_default()),
+        tool_calls: value
+            .get("tool_calls")
+            .cloned()
+            .and_then(|c| serde_json::from_value(c).ok()),
+        tool_call_id: str_field(value, "tool_call_id"),
+    }
+}
+
+fn parse_usage(value: &Value) -> Option<Usage> {
+    if value.is_null() {
+        return None;
+    }
+    let field = |name: &str| value.get(name).and_then(Value::as_u64).unwrap_or(0) as u32;
+    Some(Usage {
+        prompt_tokens: field("prompt_tokens"),
+        completion_tokens: field("completion_tokens"),
+        total_tokens: field("total_tokens"),
+    })
+}
+
+fn str_field(value: &Value, name: &str) -> Option<String> {
+    value.get(name).and_then(Value::as_str).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_request_and_stream() {
+        let adapter = OpenAiAdapter;
+        let body = adapter
+            .build_chat_request(
+                "gpt-4o",
+                &[ChatMessage {
+                    role: MessageRole::User,
+                    content: MessageContent::Parts(vec![ContentPart::ImageUrl {
+                        url: "https://example.com/a.png".to_string(),
+                        detail: None,
+                    }]),
+                    tool_calls: None,
+                    tool_call_id: None,
+                }],
+                &ChatOptions {
+                    max_tokens: Some(10),
+                    ..Default::default()
+                },
+                false,
+            )
+            .unwrap();
+        assert_eq!(body["max_tokens"], 10);
+        assert_eq!(
+            body["messages"][0]["content"][0]["image_url"]["url"],
+            "https://example.com/a.png"
+        );
+
+        let mut state = StreamState::default();
+        let first = SseEvent {
+            event: None,
+            data: r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_1","function":{"name":"search","arguments":"{\"q\""}}]}}]}"#.to_string(),
+        };
+        let second = SseEvent {
+            event: None,
+            data: r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":":1}"}}]}}]}"#.to_string(),
+        };
+        let events = adapter.parse_stream_event(&mut state, &first).unwrap();
+        assert!(matches!(events[0], ChatStreamEvent::Start));
+        let events = adapter.parse_stream_event(&mut state, &second).unwrap();
+        match &events[0] {
+            ChatStreamEvent::Delta(delta) => {
+                let call = &delta.tool_calls.as_ref().unwrap()[0];
+                assert_eq!(call.id, "call_1");
+                assert_eq!(call.function.name, "search");
+                assert_eq!(call.function.arguments, ":1}");
+            }
+            other => panic!("Unexpected event: {:?}", other),
+        }
+        let done = SseEvent {
+            event: None,
+            data: "[DONE]".to_string(),
+        };
+        assert!(matches!(
+            adapter.parse_stream_event(&mut state, &done).unwrap()[..],
+            [ChatStreamEvent::Done]
+        ));
+    }
+}
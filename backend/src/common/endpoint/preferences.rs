@@ -0,0 +1,201 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::registry::ModelRegistry;
+use crate::common::endpoint::traits::TaskCategory;
+use crate::common::provider::traits::StorageProvider;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 项目级模型偏好配置的默认路径（相对项目根目录）
+pub const MODEL_PREFERENCES_PATH: &str = ".zhiyun/models.json";
+
+/// 固定到某个任务类别的模型
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelPin {
+    pub model: String,
+    /// 期望的提供者；设置后校验模型确实由该提供者提供
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// 首选模型失败时按顺序尝试的后备模型
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<String>,
+    /// 仅使用固定的模型，不追加注册表自动选择的后备模型
+    #[serde(default)]
+    pub exclusive: bool,
+}
+
+/// 项目级模型偏好
+///
+/// 按任务类别固定模型，优先级为：调用方显式指定的模型 > 项目固定的模型 > 全局路由
+/// （`ModelRegistry::route_task` 的路由模型选择）。保存在仓库中，团队成员共享同一套模型。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelPreferences {
+    /// 任务类别 -> 固定的模型
+    #[serde(default)]
+    pub pins: BTreeMap<TaskCategory, ModelPin>,
+}
+
+impl ModelPreferences {
+    /// 行内补全
+    pub const COMPLETION: &'static str = "completion";
+    /// 对话
+    pub const CHAT: &'static str = "chat";
+    /// 嵌入
+    pub const EMBEDDING: &'static str = "embedding";
+    /// `route_task` 使用的路由模型
+    pub const ROUTING: &'static str = "routing";
+
+    /// 读取 JSON 配置，文件不存在时返回空配置
+    pub async fn load(storage: &dyn StorageProvider, path: &str) -> EndpointResult<Self> {
+        let storage_error = |e: anyhow::Error| EndpointError::StorageError(e.to_string());
+        if !storage.exists(path).await.map_err(storage_error)? {
+            return Ok(Self::default());
+        }
+        let bytes = storage.read_file(path).await.map_err(storage_error)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    pub async fn save(&self, storage: &dyn StorageProvider, path: &str) -> EndpointResult<()> {
+        storage
+            .write_file(path, &serde_json::to_vec_pretty(self)?)
+            .await
+            .map_err(|e| EndpointError::StorageError(e.to_string()))
+    }
+
+    pub fn pin(mut self, category: &str, model: &str) -> Self {
+        self.pins.insert(
+            category.to_string(),
+            ModelPin {
+                model: model.to_string(),
+                provider: None,
+                fallbacks: Vec::new(),
+                exclusive: false,
+            },
+        );
+        self
+    }
+
+    pub fn get(&self, category: &str) -> Option<&ModelPin> {
+        self.pins.get(category)
+    }
+
+    /// 对照注册表校验：模型已注册、提供者一致且已配置客户端、具备该类别要求的能力
+    ///
+    /// 返回全部问题，而不是遇到第一个就停止。
+    pub fn validate(&self, registry: &ModelRegistry) -> EndpointResult<()> {
+        let mut problems = Vec::new();
+        for (category, pin) in &self.pins {
+            if let Some(provider) = &pin.provider {
+                match registry.list().into_iter().find(|m| m.id == pin.model) {
+                    Some(model) if &model.provider != provider => problems.push(format!(
+                        "{}: model '{}' is served by '{}', not '{}'",
+                        category, pin.model, model.provider, provider
+                    )),
+                    _ => {}
+                }
+            }
+            let catalog = registry.catalog(category);
+            for model in std::iter::once(&pin.model).chain(&pin.fallbacks) {
+                if catalog.iter().any(|m| &m.id == model) {
+                    continue;
+                }
+                let reason = match registry.list().into_iter().find(|m| &m.id == model) {
+                    None => "is not registered",
+                    Some(m) if !registry.has_client(&m.provider) => "has no configured client",
+                    Some(_) => "lacks the capabilities required by this category",
+                };
+                problems.push(format!("{}: model '{}' {}", category, model, reason));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(EndpointError::InvalidRequest(format!(
+                "Invalid model preferences: {}",
+                problems.join("; ")
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::common::endpoint::traits::{ModelCapabilities, ModelInfo};
+    use std::sync::Arc;
+
+    fn model(id: &str, provider: &str, supports_tools: bool) -> ModelInfo {
+        ModelInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            provider: provider.to_string(),
+            context_window: 8_000,
+            supports_vision: false,
+            supports_tools,
+            supports_reasoning: false,
+            cost: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pins_are_validated_and_override_routing() {
+        let mut registry = ModelRegistry::new();
+        registry.register(model("fast", "openai", false));
+        registry.register(model("smart", "openai", true));
+        registry.register(model("offline", "ollama", true));
+        let router = Arc::new(ScriptedClient::new("openai"));
+        registry.set_client("openai", router.clone());
+        registry.set_category_requirements(
+            ModelPreferences::CHAT,
+            ModelCapabilities {
+                tool_call: true,
+                ..Default::default()
+            },
+        );
+
+        let mut invalid = ModelPreferences::default()
+            .pin(ModelPreferences::CHAT, "fast")
+            .pin(ModelPreferences::EMBEDDING, "missing")
+            .pin(ModelPreferences::COMPLETION, "offline");
+        invalid
+            .pins
+            .get_mut(ModelPreferences::COMPLETION)
+            .unwrap()
+            .provider = Some("openai".to_string());
+        let err = registry.set_preferences(invalid).unwrap_err().to_string();
+        assert!(err.contains("'fast' lacks the capabilities"));
+        assert!(err.contains("'missing' is not registered"));
+        assert!(err.contains("served by 'ollama'"));
+        assert!(err.contains("'offline' has no configured client"));
+        assert!(registry.preferences().is_none());
+
+        let mut prefs = ModelPreferences::default().pin(ModelPreferences::CHAT, "smart");
+        prefs
+            .pins
+            .get_mut(ModelPreferences::CHAT)
+            .unwrap()
+            .exclusive = true;
+        registry.set_preferences(prefs).unwrap();
+
+        // 项目固定的模型优先于路由模型，显式指定的模型优先于项目固定
+        let routes = registry
+            .route_task("fast", ModelPreferences::CHAT, "refactor")
+            .await
+            .unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].model_id, "smart");
+        // 固定的模型不经过路由模型
+        assert_eq!(router.calls(), 0);
+        let explicit = registry
+            .resolve_routes(ModelPreferences::CHAT, Some("fast"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(explicit[0].model_id, "fast");
+        assert!(
+            registry
+                .resolve_routes(ModelPreferences::COMPLETION, None)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,424 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::stream::ChatResponse;
+use crate::common::endpoint::traits::{ChatMessage, ChatOptions, EmbeddingResponse, LLMClient};
+use crate::common::provider::traits::StorageProvider;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+/// 排队等待发送的请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QueuedRequest {
+    /// 聊天补全请求
+    Chat {
+        model: String,
+        messages: Vec<ChatMessage>,
+        #[serde(default)]
+        options: ChatOptions,
+    },
+    /// 嵌入请求
+    Embedding { model: String, input: Vec<String> },
+}
+
+impl QueuedRequest {
+    /// 请求的目标模型
+    pub fn model(&self) -> &str {
+        match self {
+            QueuedRequest::Chat { model, .. } | QueuedRequest::Embedding { model, .. } => model,
+        }
+    }
+}
+
+/// 请求成功后得到的响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QueuedResponse {
+    Chat(ChatResponse),
+    Embedding(EmbeddingResponse),
+}
+
+/// 队列条目的状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueEntryState {
+    /// 等待发送
+    Pending,
+    /// 已完成，响应等待调用方取走
+    Completed,
+    /// 超过最大重试次数后放弃
+    Failed,
+}
+
+/// 队列中的单个请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub id: Uuid,
+    /// 提交该请求的后台任务（如 "indexer"）
+    pub source: String,
+    pub request: QueuedRequest,
+    pub state: QueueEntryState,
+    pub attempts: u32,
+    pub enqueued_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub response: Option<QueuedResponse>,
+}
+
+/// 队列状态摘要
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QueueStatus {
+    pub offline: bool,
+    pub pending: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub last_flush_at: Option<DateTime<Utc>>,
+}
+
+/// 一次 flush 的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FlushReport {
+    /// 提供者是否可达（不可达时不会发送任何请求）
+    pub reachable: bool,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub remaining: usize,
+}
+
+/// 持久化文件格式
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QueueFile {
+    offline: bool,
+    entries: Vec<QueueEntry>,
+    last_flush_at: Option<DateTime<Utc>>,
+}
+
+/// 离线请求队列
+///
+/// 后台任务（索引、批量嵌入等）在离线模式下把请求写入磁盘，
+/// 待提供者可达时再统一 flush，从而支持在隔离网络环境中预先准备任务。
+pub struct RequestQueue {
+    storage: Arc<dyn StorageProvider>,
+    path: String,
+    max_attempts: u32,
+    state: RwLock<QueueFile>,
+    /// 串行化 flush，避免同一条目被并发发送两次
+    flushing: Mutex<()>,
+}
+
+impl RequestQueue {
+    /// 默认最大尝试次数
+    pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+    /// 打开（或新建）位于 `path` 的队列文件
+    pub async fn open(storage: Arc<dyn StorageProvider>, path: &str) -> EndpointResult<Self> {
+        let state = if storage.exists(path).await.map_err(storage_error)? {
+            let bytes = storage.read_file(path).await.map_err(storage_error)?;
+            serde_json::from_slice(&bytes)?
+        } else {
+            QueueFile::default()
+        };
+
+        Ok(Self {
+            storage,
+            path: path.to_string(),
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+            state: RwLock::new(state),
+            flushing: Mutex::new(()),
+        })
+    }
+
+    /// 设置最大尝试次数
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// 切换离线模式
+    pub async fn set_offline(&self, offline: bool) -> EndpointResult<()> {
+        let mut state = self.state.write().await;
+        state.offline = offline;
+        self.persist(&state).await
+    }
+
+    /// 将请求加入队列并立即落盘
+    pub async fn enqueue(&self, source: &str, request: QueuedRequest) -> EndpointResult<Uuid> {
+        let id = Uuid::new_v4();
+        let mut state = self.state.write().await;
+        state.entries.push(QueueEntry {
+            id,
+            source: source.to_string(),
+            request,
+            state: QueueEntryState::Pending,
+            attempts: 0,
+            enqueued_at: Utc::now(),
+            last_error: None,
+            response: None,
+        });
+        self.persist(&state).await?;
+        Ok(id)
+    }
+
+    /// 获取队列状态摘要
+    pub async fn status(&self) -> QueueStatus {
+        let state = self.state.read().await;
+        let count = |s: QueueEntryState| state.entries.iter().filter(|e| e.state == s).count();
+        QueueStatus {
+            offline: state.offline,
+            pending: count(QueueEntryState::Pending),
+            completed: count(QueueEntryState::Completed),
+            failed: count(QueueEntryState::Failed),
+            last_flush_at: state.last_flush_at,
+        }
+    }
+
+    /// 获取指定条目
+    pub async fn get(&self, id: Uuid) -> Option<QueueEntry> {
+        let state = self.state.read().await;
+        state.entries.iter().find(|e| e.id == id).cloned()
+    }
+
+    /// 列出某个来源提交的所有条目
+    pub async fn list_by_source(&self, source: &str) -> Vec<QueueEntry> {
+        let state = self.state.read().await;
+        state
+            .entries
+            .iter()
+            .filter(|e| e.source == source)
+            .cloned()
+            .collect()
+    }
+
+    /// 取走指定来源已完成的条目（从队列中移除）
+    pub async fn take_completed(&self, source: &str) -> EndpointResult<Vec<QueueEntry>> {
+        let mut state = self.state.write().await;
+        let (taken, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut state.entries)
+            .into_iter()
+            .partition(|e| e.source == source && e.state == QueueEntryState::Completed);
+        state.entries = kept;
+        self.persist(&state).await?;
+        Ok(taken)
+    }
+
+    /// 将失败的条目重新标记为待发送
+    pub async fn retry_failed(&self) -> EndpointResult<usize> {
+        let mut state = self.state.write().await;
+        let mut count = 0;
+        for entry in state
+            .entries
+            .iter_mut()
+            .filter(|e| e.state == QueueEntryState::Failed)
+        {
+            entry.state = QueueEntryState::Pending;
+            entry.attempts = 0;
+            count += 1;
+        }
+        self.persist(&state).await?;
+        Ok(count)
+    }
+
+    /// 尝试通过 `client` 发送所有待发送的请求
+    ///
+    /// 离线模式或提供者健康检查失败时直接返回 `reachable = false`，队列保持不变。
+    pub async fn flush(&self, client: &dyn LLMClient) -> EndpointResult<FlushReport> {
+        self.flush_where(client, |_| true).await
+    }
+
+    /// 只发送 `model` 满足条件的待发送请求（如属于 `client` 所在提供者的模型）
+    ///
+    /// 发送请求时不持有队列状态的锁，其间仍可入队与查询；每个条目完成后立即落盘，
+    /// flush 中途崩溃不会重复发送已完成的请求。
+    pub async fn flush_where(
+        &self,
+        client: &dyn LLMClient,
+        model: impl Fn(&str) -> bool,
+    ) -> EndpointResult<FlushReport> {
+        let _flushing = self.flushing.lock().await;
+        let mut report = FlushReport::default();
+        let pending: Vec<(Uuid, QueuedRequest)> = {
+            let state = self.state.read().await;
+            state
+                .entries
+                .iter()
+                .filter(|e| e.state == QueueEntryState::Pending && model(e.request.model()))
+                .map(|e| (e.id, e.request.clone()))
+                .collect()
+        };
+        let offline = self.state.read().await.offline;
+        if offline || client.health_check().await.is_err() {
+            report.remaining = pending.len();
+            return Ok(report);
+        }
+        report.reachable = true;
+
+        for (id, request) in pending {
+            let result = match &request {
+                QueuedRequest::Chat {
+                    model,
+                    messages,
+                    options,
+                } => client
+                    .chat(model, messages, options)
+                    .await
+                    .map(QueuedResponse::Chat),
+                QueuedRequest::Embedding { model, input } => client
+                    .embed(model, input)
+                    .await
+                    .map(QueuedResponse::Embedding),
+            };
+
+            let mut state = self.state.write().await;
+            let Some(entry) = state.entries.iter_mut().find(|e| e.id == id) else {
+                continue;
+            };
+            entry.attempts += 1;
+            match result {
+                Ok(response) => {
+                    entry.state = QueueEntryState::Completed;
+                    entry.response = Some(response);
+                    entry.last_error = None;
+                    report.succeeded += 1;
+                }
+                Err(err) => {
+                    entry.last_error = Some(err.to_string());
+                    if entry.attempts >= self.max_attempts {
+                        entry.state = QueueEntryState::Failed;
+                        report.failed += 1;
+                    } else {
+                        report.remaining += 1;
+                    }
+                }
+            }
+            self.persist(&state).await?;
+        }
+
+        let mut state = self.state.write().await;
+        state.last_flush_at = Some(Utc::now());
+        self.persist(&state).await?;
+        Ok(report)
+    }
+
+    async fn persist(&self, state: &QueueFile) -> EndpointResult<()> {
+        let bytes = serde_json::to_vec_pretty(state)?;
+        self.storage
+            .write_file(&self.path, &bytes)
+            .await
+            .map_err(storage_error)
+    }
+}
+
+fn storage_error(err: anyhow::Error) -> EndpointError {
+    EndpointError::StorageError(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::common::provider::traits::FileMetadata;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStorage {
+        files: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl StorageProvider for MemoryStorage {
+        fn id(&self) -> &str {
+            "memory"
+        }
+        async fn read_file(&self, path: &str) -> anyhow::Result<Vec<u8>> {
+            self.files
+                .lock()
+                .unwrap()
+                .get(path)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("not found"))
+        }
+        async fn write_file(&self, path: &str, content: &[u8]) -> anyhow::Result<()> {
+            self.files
+                .lock()
+                .unwrap()
+                .insert(path.to_string(), content.to_vec());
+            Ok(())
+        }
+        async fn delete(&self, path: &str, _recursive: bool) -> anyhow::Result<()> {
+            self.files.lock().unwrap().remove(path);
+            Ok(())
+        }
+        async fn list_dir(&self, _path: &str) -> anyhow::Result<Vec<FileMetadata>> {
+            Ok(vec![])
+        }
+        async fn get_metadata(&self, path: &str) -> anyhow::Result<FileMetadata> {
+            Ok(FileMetadata {
+                path: path.to_string(),
+                size: 0,
+                is_dir: false,
+                modified_at: 0,
+                created_at: 0,
+            })
+        }
+        async fn exists(&self, path: &str) -> anyhow::Result<bool> {
+            Ok(self.files.lock().unwrap().contains_key(path))
+        }
+        async fn create_dir(&self, _path: &str, _recursive: bool) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_persists_and_flushes_when_reachable() {
+        let storage = Arc::new(MemoryStorage::default());
+        let queue = RequestQueue::open(storage.clone(), "queue.json")
+            .await
+            .unwrap();
+        queue.set_offline(true).await.unwrap();
+
+        let id = queue
+            .enqueue(
+                "indexer",
+                QueuedRequest::Embedding {
+                    model: "embed".to_string(),
+                    input: vec!["fn main() {}".to_string()],
+                },
+            )
+            .await
+            .unwrap();
+
+        // 重新打开后队列内容仍然存在
+        let queue = RequestQueue::open(storage.clone(), "queue.json")
+            .await
+            .unwrap();
+        assert_eq!(queue.status().await.pending, 1);
+        assert!(queue.status().await.offline);
+
+        let client = ScriptedClient::new("mock").with_embedder(|_| vec![0.0, 1.0]);
+        let report = queue.flush(&client).await.unwrap();
+        assert!(!report.reachable);
+
+        queue.set_offline(false).await.unwrap();
+        client.set_reachable(false);
+        assert!(!queue.flush(&client).await.unwrap().reachable);
+
+        client.set_reachable(true);
+        let report = queue.flush(&client).await.unwrap();
+        assert!(report.reachable);
+        assert_eq!(report.succeeded, 1);
+        assert_eq!(
+            queue.get(id).await.unwrap().state,
+            QueueEntryState::Completed
+        );
+
+        let completed = queue.take_completed("indexer").await.unwrap();
+        assert_eq!(completed.len(), 1);
+        assert!(matches!(
+            completed[0].response,
+            Some(QueuedResponse::Embedding(_))
+        ));
+        assert_eq!(queue.status().await.completed, 0);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::common::endpoint::traits::{ChatMessage, ContentPart, MessageContent};
+use regex::Regex;
+
+/// 随外部状态变化的文本掩码（如项目密钥），在写入日志、记录或交给模型前应用
+pub trait Mask: Send + Sync {
+    fn mask(&self, text: &str) -> String;
+}
+
+/// 对消息的文本内容与工具调用参数应用 `mask`
+pub fn mask_message(message: &mut ChatMessage, mask: impl Fn(&str) -> String) {
+    match &mut message.content {
+        MessageContent::Text(text) => *text = mask(text),
+        MessageContent::Parts(parts) => {
+            for part in parts {
+                if let ContentPart::Text { text } = part {
+                    *text = mask(text);
+                }
+            }
+        }
+    }
+    for call in message.tool_calls.iter_mut().flatten() {
+        call.function.arguments = mask(&call.function.arguments);
+    }
+}
+
+/// 按顺序应用的文本替换规则，供 `RedactionInterceptor` 与 `PromptLogger` 共用
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Redactor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加规则，`replacement` 支持 `$1` 等捕获组引用
+    pub fn with_rule(mut self, pattern: &str, replacement: &str) -> Result<Self, regex::Error> {
+        self.rules
+            .push((Regex::new(pattern)?, replacement.to_string()));
+        Ok(self)
+    }
+
+    /// 对文本依次应用所有规则
+    pub fn redact(&self, text: &str) -> String {
+        self.rules
+            .iter()
+            .fold(text.to_string(), |text, (regex, replacement)| {
+                regex.replace_all(&text, replacement.as_str()).into_owned()
+            })
+    }
+
+    /// 替换消息的文本内容与工具调用参数
+    pub fn redact_message(&self, message: &mut ChatMessage) {
+        mask_message(message, |text| self.redact(text));
+    }
+}
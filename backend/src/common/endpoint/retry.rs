@@ -0,0 +1,101 @@
+use crate::common::endpoint::error::EndpointError;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 重试与故障转移策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetryPolicy {
+    /// 单个模型的最大重试次数（不含首次请求）
+    pub max_retries: u32,
+    /// 首次重试前的等待时间（毫秒）
+    pub initial_backoff_ms: u64,
+    /// 等待时间上限（毫秒）
+    pub max_backoff_ms: u64,
+    /// 每次重试后等待时间的倍数
+    pub multiplier: f64,
+    /// 当前模型重试耗尽或出现非瞬时错误时，是否切换到后备模型
+    pub failover: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 8_000,
+            multiplier: 2.0,
+            failover: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 不重试、不切换
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            failover: false,
+            ..Self::default()
+        }
+    }
+
+    /// 第 `attempt` 次重试（从 0 开始）前的等待时间
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ms = self.initial_backoff_ms as f64 * self.multiplier.max(1.0).powi(attempt as i32);
+        Duration::from_millis(ms.min(self.max_backoff_ms as f64) as u64)
+    }
+
+    /// 是否为值得在同一模型上重试的瞬时错误（限流、5xx、网络/流中断）
+    pub fn is_transient(error: &EndpointError) -> bool {
+        match error {
+            EndpointError::RateLimitExceeded
+            | EndpointError::Network(_)
+            | EndpointError::StreamError(_)
+            | EndpointError::IoError(_) => true,
+            EndpointError::Http { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// 是否应切换到后备模型重试（请求本身无效时换模型也无济于事）
+    pub fn should_failover(&self, error: &EndpointError) -> bool {
+        self.failover
+            && !matches!(
+                error,
+                EndpointError::InvalidRequest(_) | EndpointError::SerializationError(_)
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_policy_backoff_and_classification() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_millis(2_000));
+        assert_eq!(policy.backoff(10), Duration::from_millis(8_000));
+
+        assert!(RetryPolicy::is_transient(&EndpointError::RateLimitExceeded));
+        let http = |status| EndpointError::Http {
+            status,
+            message: "overloaded".to_string(),
+        };
+        assert!(RetryPolicy::is_transient(&http(503)));
+        assert!(!RetryPolicy::is_transient(&http(409)));
+        assert!(RetryPolicy::is_transient(&EndpointError::Network(
+            "connection refused".to_string()
+        )));
+        // 响应格式错误之类的提供者错误不会在同一模型上反复重试
+        assert!(!RetryPolicy::is_transient(&EndpointError::ProviderError(
+            "Missing choices".to_string()
+        )));
+        assert!(!RetryPolicy::is_transient(
+            &EndpointError::AuthenticationError("bad key".to_string())
+        ));
+        assert!(policy.should_failover(&EndpointError::ModelNotFound("x".to_string())));
+        assert!(!policy.should_failover(&EndpointError::InvalidRequest("x".to_string())));
+    }
+}
@@ -0,0 +1,359 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::stream::ChatResponse;
+use crate::common::endpoint::traits::{
+    ChatMessage, ChatOptions, ContentPart, LLMClient, MessageContent, MessageRole,
+};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// 命中规则后的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyAction {
+    /// 仅在响应中记录发现
+    Warn,
+    /// 在助手输出末尾附加说明
+    Annotate,
+    /// 拒绝整个响应
+    Block,
+}
+
+/// 一条安全检查发现
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SafetyFinding {
+    /// 产生发现的钩子名称
+    pub hook: String,
+    pub message: String,
+    pub action: SafetyAction,
+}
+
+/// 生成后检查钩子，对助手输出的文本进行校验
+#[async_trait]
+pub trait SafetyHook: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn check(&self, content: &str) -> EndpointResult<Vec<SafetyFinding>>;
+}
+
+/// 正则拒绝列表
+pub struct DenyListHook {
+    rules: Vec<(Regex, String)>,
+    action: SafetyAction,
+}
+
+impl DenyListHook {
+    pub fn new(action: SafetyAction) -> Self {
+        Self {
+            rules: Vec::new(),
+            action,
+        }
+    }
+
+    /// 添加规则，`label` 用于描述命中原因
+    pub fn with_rule(mut self, pattern: &str, label: &str) -> EndpointResult<Self> {
+        let regex =
+            Regex::new(pattern).map_err(|e| EndpointError::InvalidRequest(e.to_string()))?;
+        self.rules.push((regex, label.to_string()));
+        Ok(self)
+    }
+}
+
+#[async_trait]
+impl SafetyHook for DenyListHook {
+    fn name(&self) -> &str {
+        "deny-list"
+    }
+
+    async fn check(&self, content: &str) -> EndpointResult<Vec<SafetyFinding>> {
+        Ok(self
+            .rules
+            .iter()
+            .filter(|(regex, _)| regex.is_match(content))
+            .map(|(_, label)| SafetyFinding {
+                hook: self.name().to_string(),
+                message: format!("Matched deny-list rule: {}", label),
+                action: self.action,
+            })
+            .collect())
+    }
+}
+
+/// 检测大段逐字复制、带许可证头的代码块
+pub struct LicenseHeaderHook {
+    /// 代码块至少达到该行数才检查
+    min_lines: usize,
+    action: SafetyAction,
+}
+
+/// 常见许可证头的特征文本
+const LICENSE_MARKERS: &[&str] = &[
+    "SPDX-License-Identifier",
+    "Licensed under the Apache License",
+    "GNU General Public License",
+    "GNU Lesser General Public License",
+    "Permission is hereby granted, free of charge",
+    "Mozilla Public License",
+    "All rights reserved",
+];
+
+impl LicenseHeaderHook {
+    pub fn new(min_lines: usize, action: SafetyAction) -> Self {
+        Self { min_lines, action }
+    }
+}
+
+#[async_trait]
+impl SafetyHook for LicenseHeaderHook {
+    fn name(&self) -> &str {
+        "license-header"
+    }
+
+    async fn check(&self, content: &str) -> EndpointResult<Vec<SafetyFinding>> {
+        let findings = code_blocks(content)
+            .into_iter()
+            .filter(|block| block.lines().count() >= self.min_lines)
+            .filter_map(|block| LICENSE_MARKERS.iter().find(|m| block.contains(**m)))
+            .map(|marker| SafetyFinding {
+                hook: self.name().to_string(),
+                message: format!("Large code block contains a license header ({})", marker),
+                action: self.action,
+            })
+            .collect();
+        Ok(findings)
+    }
+}
+
+/// 使用审核模型进行分类，模型需返回 `{"flagged": bool, "reason": string}`
+///
+/// 与注册表一起使用时，传入 `ModelRegistry::intercepted_client` 取得的客户端，
+/// 使审核请求同样经过拦截器链。
+pub struct ModerationHook {
+    client: Arc<dyn LLMClient>,
+    model: String,
+    action: SafetyAction,
+}
+
+const MODERATION_PROMPT: &str = "You are a content moderation classifier for a coding assistant. \
+Decide whether the following assistant output contains harmful, abusive or policy-violating content. \
+Reply with JSON only: {\"flagged\": true|false, \"reason\": \"...\"}.";
+
+impl ModerationHook {
+    pub fn new(client: Arc<dyn LLMClient>, model: &str, action: SafetyAction) -> Self {
+        Self {
+            client,
+            model: model.to_string(),
+            action,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ModerationVerdict {
+    flagged: bool,
+    #[serde(default)]
+    reason: String,
+}
+
+#[async_trait]
+impl SafetyHook for ModerationHook {
+    fn name(&self) -> &str {
+        "moderation"
+    }
+
+    async fn check(&self, content: &str) -> EndpointResult<Vec<SafetyFinding>> {
+        let messages = [
+            ChatMessage::text(MessageRole::System, MODERATION_PROMPT),
+            ChatMessage::text(MessageRole::User, content),
+        ];
+        let options = ChatOptions {
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+        let response = self.client.chat(&self.model, &messages, &options).await?;
+        let text = response
+            .choices
+            .first()
+            .map(|c| c.message.content.as_text())
+            .unwrap_or_default();
+        let json = match (text.find('{'), text.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &text[start..=end],
+            _ => {
+                return Err(EndpointError::ProviderError(format!(
+                    "Unparseable moderation verdict: {}",
+                    text
+                )));
+            }
+        };
+        let verdict: ModerationVerdict = serde_json::from_str(json)?;
+        Ok(verdict
+            .flagged
+            .then(|| SafetyFinding {
+                hook: self.name().to_string(),
+                message: verdict.reason,
+                action: self.action,
+            })
+            .into_iter()
+            .collect())
+    }
+}
+
+/// 依次执行的生成后检查链
+#[derive(Default)]
+pub struct SafetyPipeline {
+    hooks: Vec<Arc<dyn SafetyHook>>,
+}
+
+impl SafetyPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_hook(mut self, hook: Arc<dyn SafetyHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// 检查一段文本，返回全部钩子的发现
+    pub async fn review(&self, content: &str) -> EndpointResult<Vec<SafetyFinding>> {
+        let mut findings = Vec::new();
+        for hook in &self.hooks {
+            findings.extend(hook.check(content).await?);
+        }
+        Ok(findings)
+    }
+
+    /// 检查响应中每条助手消息：任一发现要求拦截时返回 `ContentBlocked`，
+    /// 需要标注的发现附加到消息末尾，所有发现记录在 `ChatResponse::safety` 中
+    pub async fn apply(&self, response: &mut ChatResponse) -> EndpointResult<()> {
+        for choice in &mut response.choices {
+            let findings = self.review(&choice.message.content.as_text()).await?;
+            if let Some(blocking) = findings.iter().find(|f| f.action == SafetyAction::Block) {
+                return Err(EndpointError::ContentBlocked(format!(
+                    "[{}] {}",
+                    blocking.hook, blocking.message
+                )));
+            }
+            let notes: Vec<String> = findings
+                .iter()
+                .filter(|f| f.action == SafetyAction::Annotate)
+                .map(|f| format!("> [{}] {}", f.hook, f.message))
+                .collect();
+            if !notes.is_empty() {
+                annotate(&mut choice.message.content, &notes.join("\n"));
+            }
+            response.safety.extend(findings);
+        }
+        Ok(())
+    }
+}
+
+fn annotate(content: &mut MessageContent, note: &str) {
+    match content {
+        MessageContent::Text(text) => {
+            text.push_str("\n\n");
+            text.push_str(note);
+        }
+        MessageContent::Parts(parts) => parts.push(ContentPart::Text {
+            text: note.to_string(),
+        }),
+    }
+}
+
+/// 提取 Markdown 围栏代码块的内容
+fn code_blocks(content: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            match current.take() {
+                Some(lines) => blocks.push(lines.join("\n")),
+                None => current = Some(Vec::new()),
+            }
+        } else if let Some(lines) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::common::endpoint::stream::Choice;
+
+    fn response(text: &str) -> ChatResponse {
+        ChatResponse {
+            id: "r".to_string(),
+            model: "m".to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: ChatMessage::text(MessageRole::Assistant, text),
+                finish_reason: Some("stop".to_string()),
+            }],
+            usage: None,
+            safety: Vec::new(),
+        }
+    }
+
+    /// 总是给出同一审核结论的模型
+    fn moderator(verdict: &'static str) -> Arc<ScriptedClient> {
+        Arc::new(ScriptedClient::new("moderator").with_responder(move |_, _| verdict.to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_warns_annotates_and_blocks() {
+        let gpl_block = format!(
+            "Here you go:\n```c\n/* GNU General Public License v2 */\n{}```\n",
+            "int x;\n".repeat(20)
+        );
+        let pipeline = SafetyPipeline::new()
+            .with_hook(Arc::new(
+                DenyListHook::new(SafetyAction::Warn)
+                    .with_rule(r"(?i)api[_-]?key\s*=", "hard-coded API key")
+                    .unwrap(),
+            ))
+            .with_hook(Arc::new(LicenseHeaderHook::new(10, SafetyAction::Annotate)));
+
+        let mut warned = response("let api_key = \"sk-123\";");
+        pipeline.apply(&mut warned).await.unwrap();
+        assert_eq!(warned.safety.len(), 1);
+        assert_eq!(warned.safety[0].hook, "deny-list");
+        assert_eq!(
+            warned.choices[0].message.content.as_text(),
+            "let api_key = \"sk-123\";"
+        );
+
+        let mut annotated = response(&gpl_block);
+        pipeline.apply(&mut annotated).await.unwrap();
+        assert!(
+            annotated.choices[0]
+                .message
+                .content
+                .as_text()
+                .ends_with("> [license-header] Large code block contains a license header (GNU General Public License)")
+        );
+
+        // 短代码块不触发
+        let mut short = response("```\n// SPDX-License-Identifier: MIT\n```");
+        pipeline.apply(&mut short).await.unwrap();
+        assert!(short.safety.is_empty());
+
+        let blocking = SafetyPipeline::new().with_hook(Arc::new(ModerationHook::new(
+            moderator(r#"Verdict: {"flagged": true, "reason": "abusive"}"#),
+            "moderation",
+            SafetyAction::Block,
+        )));
+        let error = blocking.apply(&mut response("...")).await.unwrap_err();
+        assert!(matches!(error, EndpointError::ContentBlocked(m) if m == "[moderation] abusive"));
+
+        let passing = SafetyPipeline::new().with_hook(Arc::new(ModerationHook::new(
+            moderator(r#"{"flagged": false}"#),
+            "moderation",
+            SafetyAction::Block,
+        )));
+        assert!(passing.review("fine").await.unwrap().is_empty());
+    }
+}
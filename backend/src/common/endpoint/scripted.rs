@@ -0,0 +1,213 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::stream::{ChatResponse, Choice};
+use crate::common::endpoint::traits::{
+    ChatMessage, ChatOptions, EmbeddingResponse, LLMClient, MessageRole, Usage,
+};
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+type Responder = Box<dyn Fn(usize, &[ChatMessage]) -> String + Send + Sync>;
+type Embedder = Box<dyn Fn(&str) -> Vec<f32> + Send + Sync>;
+
+/// 脚本化客户端收到的一次聊天请求
+#[derive(Debug, Clone)]
+pub struct ScriptedRequest {
+    pub messages: Vec<ChatMessage>,
+    pub options: ChatOptions,
+}
+
+/// 测试用的脚本化 `LLMClient`
+///
+/// 聊天调用依次返回预设结果；脚本用尽后交给 `with_responder` 生成回复，
+/// 都没有时回复 `"ok"`。嵌入由 `with_embedder` 逐条计算，未设置时返回
+/// `InvalidRequest`。每次聊天请求都被记录下来供断言。
+pub struct ScriptedClient {
+    id: String,
+    script: Mutex<VecDeque<EndpointResult<ChatResponse>>>,
+    responder: Option<Responder>,
+    embedder: Option<Embedder>,
+    usage: Option<Usage>,
+    requests: Mutex<Vec<ScriptedRequest>>,
+    embedded: AtomicUsize,
+    reachable: AtomicBool,
+}
+
+impl ScriptedClient {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            script: Mutex::new(VecDeque::new()),
+            responder: None,
+            embedder: None,
+            usage: None,
+            requests: Mutex::new(Vec::new()),
+            embedded: AtomicUsize::new(0),
+            reachable: AtomicBool::new(true),
+        }
+    }
+
+    /// 依次返回的文本回复
+    pub fn with_replies<'a>(self, replies: impl IntoIterator<Item = &'a str>) -> Self {
+        self.with_messages(
+            replies
+                .into_iter()
+                .map(|reply| ChatMessage::text(MessageRole::Assistant, reply)),
+        )
+    }
+
+    /// 依次返回的助手消息（带工具调用时结束原因为 `tool_calls`）
+    pub fn with_messages(self, messages: impl IntoIterator<Item = ChatMessage>) -> Self {
+        let responses: Vec<_> = messages
+            .into_iter()
+            .map(|message| Ok(self.response("", message)))
+            .collect();
+        self.with_results(responses)
+    }
+
+    /// 依次返回的完整结果（可包含错误）
+    pub fn with_results(
+        self,
+        results: impl IntoIterator<Item = EndpointResult<ChatResponse>>,
+    ) -> Self {
+        self.script.lock().unwrap().extend(results);
+        self
+    }
+
+    /// 脚本用尽后按调用序号（从 0 开始）与请求消息生成回复
+    pub fn with_responder(
+        mut self,
+        responder: impl Fn(usize, &[ChatMessage]) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.responder = Some(Box::new(responder));
+        self
+    }
+
+    /// 逐条计算嵌入向量
+    pub fn with_embedder(
+        mut self,
+        embedder: impl Fn(&str) -> Vec<f32> + Send + Sync + 'static,
+    ) -> Self {
+        self.embedder = Some(Box::new(embedder));
+        self
+    }
+
+    /// 每个回复报告的用量（预设结果自带用量时以其为准）
+    pub fn with_usage(mut self, prompt_tokens: u32, completion_tokens: u32) -> Self {
+        self.usage = Some(Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        });
+        self
+    }
+
+    /// 切换连通性：不可达时所有调用返回网络错误
+    pub fn set_reachable(&self, reachable: bool) {
+        self.reachable.store(reachable, Ordering::SeqCst);
+    }
+
+    /// 已收到的聊天请求
+    pub fn requests(&self) -> Vec<ScriptedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// 每次聊天请求最后一条消息的文本
+    pub fn prompts(&self) -> Vec<String> {
+        self.requests()
+            .iter()
+            .filter_map(|r| r.messages.last().map(|m| m.content.as_text()))
+            .collect()
+    }
+
+    /// 聊天调用次数
+    pub fn calls(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// 成功嵌入的文本条数
+    pub fn embedded(&self) -> usize {
+        self.embedded.load(Ordering::SeqCst)
+    }
+
+    fn response(&self, model: &str, message: ChatMessage) -> ChatResponse {
+        let finish = if message.tool_calls.is_some() {
+            "tool_calls"
+        } else {
+            "stop"
+        };
+        ChatResponse {
+            id: self.id.clone(),
+            model: model.to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message,
+                finish_reason: Some(finish.to_string()),
+            }],
+            usage: self.usage.clone(),
+            safety: Vec::new(),
+        }
+    }
+
+    fn check_reachable(&self) -> EndpointResult<()> {
+        if self.reachable.load(Ordering::SeqCst) {
+            Ok(())
+        } else {
+            Err(EndpointError::Network("unreachable".to_string()))
+        }
+    }
+}
+
+#[async_trait]
+impl LLMClient for ScriptedClient {
+    fn provider_id(&self) -> &str {
+        &self.id
+    }
+
+    async fn chat(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> EndpointResult<ChatResponse> {
+        self.check_reachable()?;
+        let call = {
+            let mut requests = self.requests.lock().unwrap();
+            requests.push(ScriptedRequest {
+                messages: messages.to_vec(),
+                options: options.clone(),
+            });
+            requests.len() - 1
+        };
+        if let Some(result) = self.script.lock().unwrap().pop_front() {
+            return result.map(|mut response| {
+                response.model = model.to_string();
+                response.usage = response.usage.or_else(|| self.usage.clone());
+                response
+            });
+        }
+        let reply = self
+            .responder
+            .as_ref()
+            .map_or_else(|| "ok".to_string(), |respond| respond(call, messages));
+        Ok(self.response(model, ChatMessage::text(MessageRole::Assistant, &reply)))
+    }
+
+    async fn embed(&self, _model: &str, input: &[String]) -> EndpointResult<EmbeddingResponse> {
+        self.check_reachable()?;
+        let embedder = self
+            .embedder
+            .as_ref()
+            .ok_or_else(|| EndpointError::InvalidRequest("unsupported".to_string()))?;
+        self.embedded.fetch_add(input.len(), Ordering::SeqCst);
+        Ok(EmbeddingResponse {
+            data: input.iter().map(|text| embedder(text)).collect(),
+            usage: Usage::default(),
+        })
+    }
+
+    async fn health_check(&self) -> EndpointResult<()> {
+        self.check_reachable()
+    }
+}
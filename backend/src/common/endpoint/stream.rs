@@ -1,5 +1,21 @@
-use crate::common::endpoint::traits::{ChatMessage, ToolCall, Usage};
+use crate::common::endpoint::anthropic::AnthropicAdapter;
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::gemini::GeminiAdapter;
+use crate::common::endpoint::ollama::OllamaAdapter;
+use crate::common::endpoint::openai::OpenAiAdapter;
+use crate::common::endpoint::safety::SafetyFinding;
+use crate::common::endpoint::traits::{
+    ChatMessage, ChatOptions, EmbeddingResponse, FileContentResponse, FileDeletionStatus,
+    FileObject, FileUploadRequest, LLMClient, ModelInfo, ToolCall, Usage, unsupported_files,
+};
+use crate::common::endpoint::usage::StreamMeter;
+use async_trait::async_trait;
+use futures::Stream;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
 
 /// 聊天流增量内容
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -9,13 +25,27 @@ pub struct ChatDelta {
     pub tool_calls: Option<Vec<ToolCall>>,
 }
 
+/// 流式过程中的用量增量（见 `StreamMeter`）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageDelta {
+    /// 相对上一次增量的变化；按提供商报告校正时可能为负
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    /// 截至目前的累计用量，可用 `Usage::cost` 换算为费用
+    pub total: Usage,
+    /// 累计用量来自提供商报告；为 `false` 时是分词器估算
+    pub reconciled: bool,
+}
+
 /// 聊天流事件
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum ChatStreamEvent {
     Start,
     Delta(ChatDelta),
+    /// 提供商报告的用量
     Usage(Usage),
+    UsageDelta(UsageDelta),
     Error(String),
     Done,
 }
@@ -27,6 +57,9 @@ pub struct ChatResponse {
     pub model: String,
     pub choices: Vec<Choice>,
     pub usage: Option<Usage>,
+    /// 生成后安全检查的发现
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub safety: Vec<SafetyFinding>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +78,568 @@ pub struct ProviderConfig {
     pub organization: Option<String>,
 }
 
+/// 流式聊天响应
+pub type ChatStream = Pin<Box<dyn Stream<Item = EndpointResult<ChatStreamEvent>> + Send>>;
+
+/// 一条 Server-Sent Events 事件
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// 按 `\n` 切分的字节缓冲：只解码完整的行，跨块切开的多字节字符不会被替换成乱码
+#[derive(Debug, Default)]
+struct LineBuffer {
+    buffer: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// 追加字节块，返回其中已完整的行（去掉行尾的 `\r\n`）
+    fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let Some(end) = self.buffer.iter().rposition(|&b| b == b'\n') else {
+            return Vec::new();
+        };
+        let rest = self.buffer.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.buffer, rest);
+        complete[..end]
+            .split(|&b| b == b'\n')
+            .map(|line| {
+                String::from_utf8_lossy(line)
+                    .trim_end_matches('\r')
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+/// 增量 SSE 解析器：喂入任意切分的字节块，产出完整事件
+#[derive(Debug, Default)]
+pub struct SseParser {
+    lines: LineBuffer,
+    current: SseEvent,
+    has_data: bool,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加字节块，返回其中已完整的事件
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for line in self.lines.feed(chunk) {
+            if line.is_empty() {
+                if self.has_data {
+                    events.push(std::mem::take(&mut self.current));
+                    self.has_data = false;
+                } else {
+                    self.current = SseEvent::default();
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                if self.has_data {
+                    self.current.data.push('\n');
+                }
+                self.current
+                    .data
+                    .push_str(value.strip_prefix(' ').unwrap_or(value));
+                self.has_data = true;
+            } else if let Some(value) = line.strip_prefix("event:") {
+                self.current.event = Some(value.trim().to_string());
+            }
+            // 注释行（以 `:` 开头）与其他字段忽略
+        }
+        events
+    }
+}
+
+/// 换行分隔 JSON（NDJSON）解析器：每个非空行作为一条事件的 `data`
+#[derive(Debug, Default)]
+pub struct NdjsonParser {
+    lines: LineBuffer,
+}
+
+impl NdjsonParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加字节块，返回其中已完整的行
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.lines
+            .feed(chunk)
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| SseEvent {
+                event: None,
+                data: line.to_string(),
+            })
+            .collect()
+    }
+}
+
+/// 流式响应的传输格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamFormat {
+    /// Server-Sent Events
+    #[default]
+    Sse,
+    /// 换行分隔 JSON（如 Ollama 原生接口）
+    NdJson,
+}
+
+/// 单次流式请求内、跨事件保留的解析状态
+#[derive(Debug, Default)]
+pub struct StreamState {
+    /// 是否已产出 `Start` 事件
+    pub started: bool,
+    pub usage: Usage,
+    /// 内容块/工具调用索引 -> (调用 ID, 函数名)
+    pub tool_calls: HashMap<u64, (String, String)>,
+}
+
+/// 提供商协议适配器
+///
+/// 负责在统一的消息模型与各提供商的线上格式之间转换，`Endpoint` 只负责 HTTP 传输。
+pub trait ProviderAdapter: Send + Sync {
+    /// 适配器标识符（如 `openai`、`anthropic`）
+    fn id(&self) -> &str;
+
+    /// 未配置 `base_url` 时使用的默认地址
+    fn default_base_url(&self) -> &str;
+
+    /// 聊天接口相对路径
+    fn chat_path(&self, model: &str, stream: bool) -> String;
+
+    /// 健康检查使用的相对路径
+    fn models_path(&self) -> String {
+        "/models".to_string()
+    }
+
+    /// 模型列表响应的解析（OpenAI 兼容的 `{ "data": [{ "id": ... }] }`）
+    fn parse_models_response(&self, provider: &str, body: Value) -> EndpointResult<Vec<ModelInfo>> {
+        let models = body
+            .get("data")
+            .and_then(Value::as_array)
+            .ok_or_else(|| EndpointError::ProviderError("Missing model list".to_string()))?
+            .iter()
+            .filter_map(|model| model.get("id").and_then(Value::as_str))
+            .map(|id| ModelInfo {
+                id: id.to_string(),
+                name: id.to_string(),
+                provider: provider.to_string(),
+                context_window: 0,
+                supports_vision: false,
+                supports_tools: false,
+                supports_reasoning: false,
+                cost: None,
+            })
+            .collect();
+        Ok(models)
+    }
+
+    /// 流式响应的传输格式
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::Sse
+    }
+
+    /// 认证及协议版本等请求头
+    fn headers(&self, config: &ProviderConfig) -> Vec<(String, String)>;
+
+    /// 构造聊天请求体
+    fn build_chat_request(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        stream: bool,
+    ) -> EndpointResult<Value>;
+
+    /// 解析非流式聊天响应
+    fn parse_chat_response(&self, body: Value) -> EndpointResult<ChatResponse>;
+
+    /// 将一条流事件转换为零个或多个统一事件
+    fn parse_stream_event(
+        &self,
+        state: &mut StreamState,
+        event: &SseEvent,
+    ) -> EndpointResult<Vec<ChatStreamEvent>>;
+
+    /// 嵌入接口相对路径；不支持嵌入时为 `None`
+    fn embeddings_path(&self, _model: &str) -> Option<String> {
+        None
+    }
+
+    /// 构造嵌入请求体
+    fn build_embedding_request(&self, _model: &str, _input: &[String]) -> EndpointResult<Value> {
+        Err(EndpointError::InvalidRequest(format!(
+            "Provider '{}' does not support embeddings",
+            self.id()
+        )))
+    }
+
+    /// 解析嵌入响应
+    fn parse_embedding_response(&self, _body: Value) -> EndpointResult<EmbeddingResponse> {
+        Err(EndpointError::InvalidRequest(format!(
+            "Provider '{}' does not support embeddings",
+            self.id()
+        )))
+    }
+
+    /// 文件接口相对路径；不支持文件上传时为 `None`
+    fn files_path(&self) -> Option<String> {
+        None
+    }
+
+    /// 单个文件的大小上限（字节）
+    fn max_file_size(&self) -> Option<u64> {
+        None
+    }
+
+    /// 文件接口额外需要的请求头
+    fn file_headers(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// 构造上传文件的 multipart 表单（OpenAI 格式：`purpose` + `file`）
+    fn build_upload_form(&self, request: &FileUploadRequest) -> reqwest::multipart::Form {
+        let file = reqwest::multipart::Part::bytes(request.content.clone())
+            .file_name(request.filename.clone());
+        reqwest::multipart::Form::new()
+            .text("purpose", request.purpose.clone())
+            .part("file", file)
+    }
+
+    /// 解析上传响应（OpenAI 格式），响应缺失的字段取自请求
+    fn parse_file_object(
+        &self,
+        request: &FileUploadRequest,
+        body: Value,
+    ) -> EndpointResult<FileObject> {
+        let id = body
+            .get("id")
+            .and_then(Value::as_str)
+            .ok_or_else(|| EndpointError::ProviderError("Missing file id".to_string()))?;
+        Ok(FileObject {
+            id: id.to_string(),
+            bytes: body
+                .get("bytes")
+                .and_then(Value::as_u64)
+                .unwrap_or(request.content.len() as u64) as u32,
+            filename: body
+                .get("filename")
+                .and_then(Value::as_str)
+                .unwrap_or(&request.filename)
+                .to_string(),
+            purpose: body
+                .get("purpose")
+                .and_then(Value::as_str)
+                .unwrap_or(&request.purpose)
+                .to_string(),
+        })
+    }
+
+    /// 解析删除响应（OpenAI 格式 `{ "id", "deleted" }`）
+    fn parse_file_deletion(&self, file_id: &str, body: Value) -> FileDeletionStatus {
+        FileDeletionStatus {
+            id: body
+                .get("id")
+                .and_then(Value::as_str)
+                .unwrap_or(file_id)
+                .to_string(),
+            deleted: body
+                .get("deleted")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        }
+    }
+
+    /// 将 HTTP 错误映射为 `EndpointError`
+    fn map_error(&self, status: u16, body: &str) -> EndpointError {
+        let message = serde_json::from_str::<Value>(body)
+            .ok()
+            .and_then(|v| {
+                v.pointer("/error/message")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| body.to_string());
+        match status {
+            401 | 403 => EndpointError::AuthenticationError(message),
+            404 => EndpointError::ModelNotFound(message),
+            429 => EndpointError::RateLimitExceeded,
+            400 | 422 => EndpointError::InvalidRequest(message),
+            _ => EndpointError::Http { status, message },
+        }
+    }
+}
+
+/// 值存在时写入请求体字段，供各适配器构造请求使用
+pub(crate) fn insert_opt<T: Serialize>(
+    body: &mut serde_json::Map<String, Value>,
+    key: &str,
+    value: &Option<T>,
+) {
+    if let Some(value) = value {
+        body.insert(key.to_string(), serde_json::json!(value));
+    }
+}
+
+/// 基于 HTTP 的模型端点，通过 `ProviderAdapter` 支持不同的线上协议
+#[derive(Clone)]
+pub struct Endpoint {
+    config: ProviderConfig,
+    adapter: Arc<dyn ProviderAdapter>,
+    client: reqwest::Client,
+}
+
+impl Endpoint {
+    pub fn new(config: ProviderConfig, adapter: Arc<dyn ProviderAdapter>) -> Self {
+        Self {
+            config,
+            adapter,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// 根据配置名称选择适配器（未知名称按 OpenAI 兼容协议处理）
+    pub fn from_config(config: ProviderConfig) -> Self {
+        let adapter: Arc<dyn ProviderAdapter> = match config.name.to_lowercase().as_str() {
+            "anthropic" | "claude" => Arc::new(AnthropicAdapter),
+            "gemini" | "google" => Arc::new(GeminiAdapter),
+            "ollama" => Arc::new(OllamaAdapter),
+            _ => Arc::new(OpenAiAdapter),
+        };
+        Self::new(config, adapter)
+    }
+
+    /// 当前使用的适配器
+    pub fn adapter(&self) -> &dyn ProviderAdapter {
+        self.adapter.as_ref()
+    }
+
+    /// 端点配置
+    pub fn config(&self) -> &ProviderConfig {
+        &self.config
+    }
+
+    /// 发起非流式聊天补全
+    pub async fn chat_completion(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> EndpointResult<ChatResponse> {
+        let body = self
+            .adapter
+            .build_chat_request(model, messages, options, false)?;
+        let response = self
+            .post(&self.adapter.chat_path(model, false), &body)
+            .await?;
+        let body = response.json::<Value>().await?;
+        self.adapter.parse_chat_response(body)
+    }
+
+    /// 发起流式聊天补全
+    pub async fn chat_completion_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> EndpointResult<ChatStream> {
+        let body = self
+            .adapter
+            .build_chat_request(model, messages, options, true)?;
+        let mut response = self
+            .post(&self.adapter.chat_path(model, true), &body)
+            .await?;
+        let adapter = self.adapter.clone();
+        let format = adapter.stream_format();
+        let mut meter = StreamMeter::new(model, messages, options);
+
+        Ok(Box::pin(async_stream::stream! {
+            let mut sse = SseParser::new();
+            let mut ndjson = NdjsonParser::new();
+            let mut state = StreamState::default();
+            loop {
+                let chunk = match response.chunk().await {
+                    Ok(Some(chunk)) => chunk,
+                    Ok(None) => break,
+                    Err(e) => {
+                        yield Err(EndpointError::StreamError(e.to_string()));
+                        break;
+                    }
+                };
+                let events = match format {
+                    StreamFormat::Sse => sse.feed(&chunk),
+                    StreamFormat::NdJson => ndjson.feed(&chunk),
+                };
+                for event in events {
+                    match adapter.parse_stream_event(&mut state, &event) {
+                        Ok(events) => {
+                            for event in events.into_iter().flat_map(|e| meter.observe(e)) {
+                                yield Ok(event);
+                            }
+                        }
+                        Err(e) => yield Err(e),
+                    }
+                }
+            }
+        }))
+    }
+
+    /// 上传文件，超过提供商大小上限时直接拒绝
+    pub async fn upload_file(&self, request: &FileUploadRequest) -> EndpointResult<FileObject> {
+        let path = self.files_path()?;
+        if let Some(limit) = self.adapter.max_file_size()
+            && request.content.len() as u64 > limit
+        {
+            return Err(EndpointError::InvalidRequest(format!(
+                "File '{}' is {} bytes, exceeding the {} byte limit of provider '{}'",
+                request.filename,
+                request.content.len(),
+                limit,
+                self.adapter.id()
+            )));
+        }
+        let form = self.adapter.build_upload_form(request);
+        let builder = self
+            .file_request(reqwest::Method::POST, &path)
+            .multipart(form);
+        let body = self.send(builder).await?.json::<Value>().await?;
+        self.adapter.parse_file_object(request, body)
+    }
+
+    /// 删除已上传的文件
+    pub async fn delete_file(&self, file_id: &str) -> EndpointResult<FileDeletionStatus> {
+        let path = format!("{}/{}", self.files_path()?, file_id);
+        let body = self
+            .send(self.file_request(reqwest::Method::DELETE, &path))
+            .await?
+            .json::<Value>()
+            .await?;
+        Ok(self.adapter.parse_file_deletion(file_id, body))
+    }
+
+    /// 下载已上传文件的内容
+    pub async fn get_file_content(&self, file_id: &str) -> EndpointResult<FileContentResponse> {
+        let path = format!("{}/{}/content", self.files_path()?, file_id);
+        let bytes = self
+            .send(self.file_request(reqwest::Method::GET, &path))
+            .await?
+            .bytes()
+            .await?;
+        Ok(bytes.to_vec())
+    }
+
+    fn files_path(&self) -> EndpointResult<String> {
+        self.adapter
+            .files_path()
+            .ok_or_else(|| unsupported_files(self.adapter.id()))
+    }
+
+    async fn post(&self, path: &str, body: &Value) -> EndpointResult<reqwest::Response> {
+        self.send(self.request(reqwest::Method::POST, path).json(body))
+            .await
+    }
+
+    async fn get(&self, path: &str) -> EndpointResult<reqwest::Response> {
+        self.send(self.request(reqwest::Method::GET, path)).await
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let mut request = self.client.request(method, self.url(path));
+        for (name, value) in self.adapter.headers(&self.config) {
+            request = request.header(name, value);
+        }
+        request
+    }
+
+    fn file_request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let mut request = self.request(method, path);
+        for (name, value) in self.adapter.file_headers() {
+            request = request.header(name, value);
+        }
+        request
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> EndpointResult<reqwest::Response> {
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let text = response.text().await.unwrap_or_default();
+            return Err(self.adapter.map_error(status, &text));
+        }
+        Ok(response)
+    }
+
+    fn url(&self, path: &str) -> String {
+        let base = self
+            .config
+            .base_url
+            .as_deref()
+            .unwrap_or(self.adapter.default_base_url());
+        format!("{}{}", base.trim_end_matches('/'), path)
+    }
+}
+
+#[async_trait]
+impl LLMClient for Endpoint {
+    fn provider_id(&self) -> &str {
+        &self.config.name
+    }
+
+    async fn chat(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> EndpointResult<ChatResponse> {
+        self.chat_completion(model, messages, options).await
+    }
+
+    async fn embed(&self, model: &str, input: &[String]) -> EndpointResult<EmbeddingResponse> {
+        let path = self.adapter.embeddings_path(model).ok_or_else(|| {
+            EndpointError::InvalidRequest(format!(
+                "Provider '{}' does not support embeddings",
+                self.adapter.id()
+            ))
+        })?;
+        let body = self.adapter.build_embedding_request(model, input)?;
+        let response = self.post(&path, &body).await?;
+        let body = response.json::<Value>().await?;
+        self.adapter.parse_embedding_response(body)
+    }
+
+    async fn health_check(&self) -> EndpointResult<()> {
+        self.get(&self.adapter.models_path()).await.map(|_| ())
+    }
+
+    async fn list_models(&self) -> EndpointResult<Vec<ModelInfo>> {
+        let body = self
+            .get(&self.adapter.models_path())
+            .await?
+            .json::<Value>()
+            .await?;
+        self.adapter.parse_models_response(&self.config.name, body)
+    }
+
+    async fn upload_file(&self, request: &FileUploadRequest) -> EndpointResult<FileObject> {
+        Endpoint::upload_file(self, request).await
+    }
+
+    async fn delete_file(&self, file_id: &str) -> EndpointResult<FileDeletionStatus> {
+        Endpoint::delete_file(self, file_id).await
+    }
+
+    async fn get_file_content(&self, file_id: &str) -> EndpointResult<FileContentResponse> {
+        Endpoint::get_file_content(self, file_id).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,7 +654,189 @@ mod tests {
         assert!(json.contains("\"type\":\"delta\""));
         assert!(json.contains("\"content\":\"hello\""));
     }
-}
 
-// 占位符
-pub struct Endpoint;
+    #[test]
+    fn test_default_error_mapping_reports_missing_models() {
+        let adapter = OpenAiAdapter;
+        let body = r#"{"error": {"message": "The model `gpt-9` does not exist"}}"#;
+        assert!(matches!(
+            adapter.map_error(404, body),
+            EndpointError::ModelNotFound(m) if m == "The model `gpt-9` does not exist"
+        ));
+        assert!(matches!(
+            adapter.map_error(400, body),
+            EndpointError::InvalidRequest(_)
+        ));
+        assert!(matches!(
+            adapter.map_error(429, body),
+            EndpointError::RateLimitExceeded
+        ));
+    }
+
+    #[test]
+    fn test_sse_parser_handles_split_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"event: ping\ndata: {\"a\"").is_empty());
+        let events = parser.feed(b":1}\n\n: comment\ndata: x\ndata: y\n\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: Some("ping".to_string()),
+                    data: "{\"a\":1}".to_string(),
+                },
+                SseEvent {
+                    event: None,
+                    data: "x\ny".to_string(),
+                },
+            ]
+        );
+
+        // 多字节字符被切在两个块之间
+        let bytes = "data: 你好\n\n".as_bytes();
+        assert!(parser.feed(&bytes[..8]).is_empty());
+        let events = parser.feed(&bytes[8..]);
+        assert_eq!(events[0].data, "你好");
+    }
+
+    #[test]
+    fn test_ndjson_parser_keeps_split_characters() {
+        let mut parser = NdjsonParser::new();
+        let bytes = "{\"a\":\"你好\"}\r\n\n{\"b\":1}".as_bytes();
+        assert!(parser.feed(&bytes[..8]).is_empty());
+        let events = parser.feed(&bytes[8..]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "{\"a\":\"你好\"}");
+        assert_eq!(parser.feed(b"\n")[0].data, "{\"b\":1}");
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_stream_over_http() {
+        use futures::StreamExt;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 8192];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let body = concat!(
+                "event: message_start\n",
+                "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"model\":\"claude\",\"usage\":{\"input_tokens\":5,\"output_tokens\":0}}}\n\n",
+                "event: content_block_delta\n",
+                "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n",
+                "event: message_stop\n",
+                "data: {\"type\":\"message_stop\"}\n\n",
+            );
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let endpoint = Endpoint::from_config(ProviderConfig {
+            name: "anthropic".to_string(),
+            api_key: "test".to_string(),
+            base_url: Some(format!("http://{}", addr)),
+            organization: None,
+        });
+        let events: Vec<_> = endpoint
+            .chat_completion_stream(
+                "claude",
+                &[ChatMessage::text(
+                    crate::common::endpoint::traits::MessageRole::User,
+                    "hello",
+                )],
+                &ChatOptions::default(),
+            )
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        let events: Vec<_> = events.into_iter().map(Result::unwrap).collect();
+        assert!(matches!(events[0], ChatStreamEvent::Start));
+        assert!(
+            matches!(&events[1], ChatStreamEvent::Delta(d) if d.content.as_deref() == Some("Hi"))
+        );
+        assert!(matches!(events.last(), Some(ChatStreamEvent::Done)));
+        // 提供商未报告输出用量，结束前给出估算
+        assert!(matches!(
+            &events[events.len() - 2],
+            ChatStreamEvent::UsageDelta(d) if !d.reconciled && d.total.completion_tokens == 1
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_upload_rejects_oversized_and_unsupported_files() {
+        use crate::common::endpoint::traits::FileUploadRequest;
+
+        /// 文件上限为 4 字节的 OpenAI 适配器
+        struct TinyFiles;
+        impl ProviderAdapter for TinyFiles {
+            fn id(&self) -> &str {
+                "tiny"
+            }
+            fn default_base_url(&self) -> &str {
+                "http://127.0.0.1:9"
+            }
+            fn chat_path(&self, model: &str, stream: bool) -> String {
+                OpenAiAdapter.chat_path(model, stream)
+            }
+            fn headers(&self, config: &ProviderConfig) -> Vec<(String, String)> {
+                OpenAiAdapter.headers(config)
+            }
+            fn build_chat_request(
+                &self,
+                model: &str,
+                messages: &[ChatMessage],
+                options: &ChatOptions,
+                stream: bool,
+            ) -> EndpointResult<Value> {
+                OpenAiAdapter.build_chat_request(model, messages, options, stream)
+            }
+            fn parse_chat_response(&self, body: Value) -> EndpointResult<ChatResponse> {
+                OpenAiAdapter.parse_chat_response(body)
+            }
+            fn parse_stream_event(
+                &self,
+                state: &mut StreamState,
+                event: &SseEvent,
+            ) -> EndpointResult<Vec<ChatStreamEvent>> {
+                OpenAiAdapter.parse_stream_event(state, event)
+            }
+            fn files_path(&self) -> Option<String> {
+                Some("/files".to_string())
+            }
+            fn max_file_size(&self) -> Option<u64> {
+                Some(4)
+            }
+        }
+
+        let config = ProviderConfig {
+            name: "tiny".to_string(),
+            api_key: String::new(),
+            base_url: None,
+            organization: None,
+        };
+        let request = FileUploadRequest {
+            filename: "big.bin".to_string(),
+            purpose: "assistants".to_string(),
+            content: vec![0; 5],
+        };
+        let error = Endpoint::new(config.clone(), Arc::new(TinyFiles))
+            .upload_file(&request)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, EndpointError::InvalidRequest(m) if m.contains("4 byte limit")));
+
+        let ollama = Endpoint::new(config, Arc::new(OllamaAdapter));
+        assert!(matches!(
+            ollama.delete_file("file-1").await,
+            Err(EndpointError::InvalidRequest(_))
+        ));
+    }
+}
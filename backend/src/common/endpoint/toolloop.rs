@@ -0,0 +1,274 @@
+use crate::common::endpoint::error::EndpointResult;
+use crate::common::endpoint::stream::ChatResponse;
+use crate::common::endpoint::traits::{
+    ChatMessage, ChatOptions, LLMClient, MessageRole, ToolCall, ToolDefinition, Usage,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// 可供模型调用的一组工具
+#[async_trait(?Send)]
+pub trait ToolExecutor {
+    /// 发送给模型的工具定义
+    fn definitions(&self) -> Vec<ToolDefinition>;
+
+    /// 执行工具，返回作为工具结果消息内容的文本
+    async fn call(&self, name: &str, arguments: Value) -> anyhow::Result<String>;
+}
+
+/// 循环结束的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopFinish {
+    /// 模型给出了不含工具调用的回复
+    Stop,
+    /// 达到步数上限时模型仍在请求工具
+    StepBudget,
+}
+
+/// 一次工具调用及其结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolInvocation {
+    pub call: ToolCall,
+    pub output: String,
+    /// 工具报错（错误信息同样作为结果返回给模型）
+    pub is_error: bool,
+}
+
+/// 工具调用循环的结果
+#[derive(Debug, Clone)]
+pub struct AgentLoopOutcome {
+    /// 最后一次模型回复
+    pub response: ChatResponse,
+    pub finish: LoopFinish,
+    /// 调用模型的次数
+    pub steps: usize,
+    pub invocations: Vec<ToolInvocation>,
+    /// 各步用量之和
+    pub usage: Usage,
+}
+
+/// 工具调用循环：调用模型、执行其请求的工具、追加 `MessageRole::Tool` 结果，
+/// 直到模型不再请求工具或达到步数上限
+pub struct AgentLoop {
+    client: Arc<dyn LLMClient>,
+    model: String,
+    options: ChatOptions,
+    max_steps: usize,
+}
+
+impl AgentLoop {
+    pub fn new(client: Arc<dyn LLMClient>, model: &str) -> Self {
+        Self {
+            client,
+            model: model.to_string(),
+            options: ChatOptions::default(),
+            max_steps: 8,
+        }
+    }
+
+    /// 每次调用使用的选项（`tools` 由 `ToolExecutor` 提供）
+    pub fn with_options(mut self, options: ChatOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// 最多调用模型的次数
+    pub fn with_max_steps(mut self, steps: usize) -> Self {
+        self.max_steps = steps.max(1);
+        self
+    }
+
+    /// 运行循环；模型回复与工具结果会追加到 `messages`
+    ///
+    /// 工具报错或参数不是合法 JSON 时，错误信息作为工具结果交给模型处理，不会中断循环。
+    pub async fn run(
+        &self,
+        messages: &mut Vec<ChatMessage>,
+        tools: &dyn ToolExecutor,
+    ) -> EndpointResult<AgentLoopOutcome> {
+        let definitions = tools.definitions();
+        let options = ChatOptions {
+            tools: (!definitions.is_empty()).then_some(definitions),
+            ..self.options.clone()
+        };
+        let mut invocations = Vec::new();
+        let mut usage = Usage::default();
+        let mut steps = 0;
+        loop {
+            let response = self.client.chat(&self.model, messages, &options).await?;
+            steps += 1;
+            if let Some(step) = &response.usage {
+                usage.prompt_tokens += step.prompt_tokens;
+                usage.completion_tokens += step.completion_tokens;
+                usage.total_tokens += step.total_tokens;
+            }
+            let message = response
+                .choices
+                .first()
+                .map(|c| c.message.clone())
+                .unwrap_or_else(|| ChatMessage::text(MessageRole::Assistant, ""));
+            let calls = message.tool_calls.clone().unwrap_or_default();
+            messages.push(message);
+
+            let finish = if calls.is_empty() {
+                Some(LoopFinish::Stop)
+            } else if steps >= self.max_steps {
+                Some(LoopFinish::StepBudget)
+            } else {
+                None
+            };
+            if let Some(finish) = finish {
+                return Ok(AgentLoopOutcome {
+                    response,
+                    finish,
+                    steps,
+                    invocations,
+                    usage,
+                });
+            }
+
+            for call in calls {
+                let result = match serde_json::from_str::<Value>(&call.function.arguments) {
+                    Ok(arguments) => tools.call(&call.function.name, arguments).await,
+                    Err(e) => Err(anyhow::anyhow!("Invalid arguments: {}", e)),
+                };
+                let (output, is_error) = match result {
+                    Ok(output) => (output, false),
+                    Err(e) => (format!("Error: {}", e), true),
+                };
+                messages.push(ChatMessage::tool_result(&call.id, &output));
+                invocations.push(ToolInvocation {
+                    call,
+                    output,
+                    is_error,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::common::endpoint::traits::{FunctionCall, FunctionDefinition};
+    use serde_json::json;
+
+    fn call(id: &str, name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            r#type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    fn requesting(calls: Vec<ToolCall>) -> ChatMessage {
+        ChatMessage {
+            tool_calls: Some(calls),
+            ..ChatMessage::text(MessageRole::Assistant, "")
+        }
+    }
+
+    struct Calculator;
+
+    #[async_trait(?Send)]
+    impl ToolExecutor for Calculator {
+        fn definitions(&self) -> Vec<ToolDefinition> {
+            vec![ToolDefinition {
+                r#type: "function".to_string(),
+                function: FunctionDefinition {
+                    name: "add".to_string(),
+                    description: None,
+                    parameters: json!({"type": "object"}),
+                },
+            }]
+        }
+
+        async fn call(&self, name: &str, arguments: Value) -> anyhow::Result<String> {
+            match name {
+                "add" => Ok((arguments["a"].as_i64().unwrap_or(0)
+                    + arguments["b"].as_i64().unwrap_or(0))
+                .to_string()),
+                other => anyhow::bail!("Unknown tool: {}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_loop_executes_tools_until_stop() {
+        let client = Arc::new(
+            ScriptedClient::new("scripted")
+                .with_usage(10, 2)
+                .with_messages([
+                    requesting(vec![
+                        call("c1", "add", r#"{"a": 1, "b": 2}"#),
+                        call("c2", "mul", "{}"),
+                    ]),
+                    requesting(vec![call("c3", "add", "not json")]),
+                    ChatMessage::text(MessageRole::Assistant, "1 + 2 = 3"),
+                ]),
+        );
+        let mut messages = vec![ChatMessage::text(MessageRole::User, "add 1 and 2")];
+        let outcome = AgentLoop::new(client.clone(), "model")
+            .run(&mut messages, &Calculator)
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.finish, LoopFinish::Stop);
+        assert_eq!(outcome.steps, 3);
+        assert_eq!(outcome.usage.total_tokens, 36);
+        assert_eq!(outcome.invocations.len(), 3);
+        assert_eq!(outcome.invocations[0].output, "3");
+        assert!(outcome.invocations[1].is_error);
+        assert!(
+            outcome.invocations[2]
+                .output
+                .starts_with("Error: Invalid arguments")
+        );
+        let seen: Vec<(usize, usize)> = client
+            .requests()
+            .iter()
+            .map(|r| {
+                (
+                    r.messages.len(),
+                    r.options.tools.as_ref().map_or(0, Vec::len),
+                )
+            })
+            .collect();
+        assert_eq!(seen, vec![(1, 1), (4, 1), (6, 1)]);
+
+        // user, assistant, tool x2, assistant, tool, assistant
+        assert_eq!(messages.len(), 7);
+        assert_eq!(messages[2].role, MessageRole::Tool);
+        assert_eq!(messages[2].tool_call_id.as_deref(), Some("c1"));
+        assert_eq!(messages[6].content.as_text(), "1 + 2 = 3");
+    }
+
+    #[tokio::test]
+    async fn test_agent_loop_stops_at_step_budget() {
+        let client = Arc::new(ScriptedClient::new("scripted").with_messages([
+            requesting(vec![call("c1", "add", "{}")]),
+            requesting(vec![call("c2", "add", "{}")]),
+        ]));
+        let mut messages = vec![ChatMessage::text(MessageRole::User, "loop forever")];
+        let outcome = AgentLoop::new(client, "model")
+            .with_max_steps(2)
+            .run(&mut messages, &Calculator)
+            .await
+            .unwrap();
+        assert_eq!(outcome.finish, LoopFinish::StepBudget);
+        assert_eq!(outcome.steps, 2);
+        assert_eq!(outcome.invocations.len(), 1);
+        // 未执行的工具调用保留在最后一条助手消息中
+        assert_eq!(
+            messages.last().unwrap().tool_calls.as_ref().unwrap()[0].id,
+            "c2"
+        );
+    }
+}
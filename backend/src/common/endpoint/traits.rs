@@ -1,3 +1,10 @@
+use crate::common::endpoint::context::ContextStrategy;
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::stream::ChatResponse;
+use crate::common::endpoint::usage::ModelPricing;
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -22,6 +29,40 @@ pub enum ContentPart {
         url: String,
         detail: Option<ImageDetail>,
     },
+    /// 已上传到提供商的文件引用（见 `FileManager`）
+    File {
+        file_id: String,
+        mime_type: Option<String>,
+    },
+}
+
+impl ContentPart {
+    /// 远程图像
+    pub fn image_url(url: &str, detail: Option<ImageDetail>) -> Self {
+        ContentPart::ImageUrl {
+            url: url.to_string(),
+            detail,
+        }
+    }
+
+    /// 内联图像，编码为 base64 data URL
+    pub fn image_data(mime_type: &str, bytes: &[u8], detail: Option<ImageDetail>) -> Self {
+        ContentPart::ImageUrl {
+            url: format!("data:{};base64,{}", mime_type, STANDARD.encode(bytes)),
+            detail,
+        }
+    }
+
+    /// 是否为图像（包括 MIME 类型为 `image/*` 的文件引用）
+    pub fn is_image(&self) -> bool {
+        match self {
+            ContentPart::Text { .. } => false,
+            ContentPart::ImageUrl { .. } => true,
+            ContentPart::File { mime_type, .. } => mime_type
+                .as_deref()
+                .is_some_and(|mime| mime.starts_with("image/")),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -38,6 +79,62 @@ pub struct ChatMessage {
     pub role: MessageRole,
     pub content: MessageContent,
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// 工具结果消息对应的调用 ID（仅 `MessageRole::Tool`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    /// 创建纯文本消息
+    pub fn text(role: MessageRole, text: &str) -> Self {
+        Self {
+            role,
+            content: MessageContent::Text(text.to_string()),
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    /// 创建由文本与图像等部分组成的消息
+    pub fn parts(role: MessageRole, parts: Vec<ContentPart>) -> Self {
+        Self {
+            content: MessageContent::Parts(parts),
+            ..Self::text(role, "")
+        }
+    }
+
+    /// 创建工具结果消息
+    pub fn tool_result(tool_call_id: &str, content: &str) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.to_string()),
+            ..Self::text(MessageRole::Tool, content)
+        }
+    }
+}
+
+impl MessageContent {
+    /// 拼接所有文本部分
+    pub fn as_text(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// 是否包含图像输入
+    pub fn has_images(&self) -> bool {
+        match self {
+            MessageContent::Text(_) => false,
+            MessageContent::Parts(parts) => parts.iter().any(ContentPart::is_image),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -72,10 +169,21 @@ pub struct ChatOptions {
     pub presence_penalty: Option<f32>,
     pub frequency_penalty: Option<f32>,
     pub user: Option<String>,
+    /// 可供模型调用的工具
+    pub tools: Option<Vec<ToolDefinition>>,
+    /// 提示词超出模型上下文窗口时的处理方式（见 `PromptBudget`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_strategy: Option<ContextStrategy>,
+    /// 采样种子（支持的提供商据此复现输出，见 `CassetteClient`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// 发起调用的 Agent Routine（仅用于 `UsageLedger` 记账，不发送给提供商）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routine_id: Option<uuid::Uuid>,
 }
 
 /// 模型使用统计
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
@@ -91,6 +199,56 @@ pub struct ModelInfo {
     pub context_window: u32,
     pub supports_vision: bool,
     pub supports_tools: bool,
+    #[serde(default)]
+    pub supports_reasoning: bool,
+    /// 单价（未知时为 `None`）
+    #[serde(default)]
+    pub cost: Option<ModelPricing>,
+}
+
+/// 任务对模型能力的要求
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    pub tool_call: bool,
+    pub vision: bool,
+    pub reasoning: bool,
+}
+
+impl ModelCapabilities {
+    /// 同时满足两者要求的能力
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            tool_call: self.tool_call || other.tool_call,
+            vision: self.vision || other.vision,
+            reasoning: self.reasoning || other.reasoning,
+        }
+    }
+}
+
+impl ModelInfo {
+    /// 模型是否具备 `required` 中要求的全部能力
+    pub fn satisfies(&self, required: &ModelCapabilities) -> bool {
+        self.supports_tools >= required.tool_call
+            && self.supports_vision >= required.vision
+            && self.supports_reasoning >= required.reasoning
+    }
+
+    /// 模型具备的能力
+    pub fn capabilities(&self) -> ModelCapabilities {
+        ModelCapabilities {
+            tool_call: self.supports_tools,
+            vision: self.supports_vision,
+            reasoning: self.supports_reasoning,
+        }
+    }
+
+    /// 模型的 token 限制，上下文窗口未知（为 0）时返回 `None`
+    pub fn limit(&self) -> Option<ModelLimit> {
+        (self.context_window > 0).then_some(ModelLimit {
+            context: self.context_window,
+            output: None,
+        })
+    }
 }
 
 /// 提供者信息
@@ -101,36 +259,73 @@ pub struct ProviderInfo {
     pub base_url: Option<String>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// LLM 客户端接口
+///
+/// 每个具体的模型供应商（或兼容协议的代理）实现此接口，上层模块只依赖该抽象。
+#[async_trait]
+pub trait LLMClient: Send + Sync {
+    /// 提供者标识符（与 `ProviderInfo::id` 对应）
+    fn provider_id(&self) -> &str;
 
-    #[test]
-    fn test_chat_message_serialization() {
-        let msg = ChatMessage {
-            role: MessageRole::User,
-            content: MessageContent::Text("hello".to_string()),
-            tool_calls: None,
-        };
-        let json = serde_json::to_string(&msg).unwrap();
-        assert!(json.contains("\"role\":\"user\""));
-        assert!(json.contains("\"content\":\"hello\""));
+    /// 发起一次完整的聊天补全请求
+    async fn chat(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> EndpointResult<ChatResponse>;
+
+    /// 计算一组文本的嵌入向量
+    async fn embed(&self, model: &str, input: &[String]) -> EndpointResult<EmbeddingResponse>;
+
+    /// 检查提供者当前是否可达
+    async fn health_check(&self) -> EndpointResult<()>;
+
+    /// 列出提供者当前可用的模型（不支持发现的提供者返回空列表）
+    async fn list_models(&self) -> EndpointResult<Vec<ModelInfo>> {
+        Ok(Vec::new())
+    }
+
+    /// 上传文件，返回提供商分配的文件对象
+    async fn upload_file(&self, _request: &FileUploadRequest) -> EndpointResult<FileObject> {
+        Err(unsupported_files(self.provider_id()))
+    }
+
+    /// 删除已上传的文件
+    async fn delete_file(&self, _file_id: &str) -> EndpointResult<FileDeletionStatus> {
+        Err(unsupported_files(self.provider_id()))
+    }
+
+    /// 下载已上传文件的内容
+    async fn get_file_content(&self, _file_id: &str) -> EndpointResult<FileContentResponse> {
+        Err(unsupported_files(self.provider_id()))
     }
 }
 
+pub(crate) fn unsupported_files(provider: &str) -> EndpointError {
+    EndpointError::InvalidRequest(format!(
+        "Provider '{}' does not support file uploads",
+        provider
+    ))
+}
+
 // 剩余占位符，保持接口完整性
 pub type CostBreakdown = HashMap<String, f64>;
 pub type Embedding = Vec<f32>;
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingResponse {
     pub data: Vec<Embedding>,
     pub usage: Usage,
 }
 pub type EmbeddingUsage = Usage;
 pub type FileContentResponse = Vec<u8>;
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct FileDeletionStatus {
     pub id: String,
     pub deleted: bool,
 }
+/// 已上传到提供商的文件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct FileObject {
     pub id: String,
     pub bytes: u32,
@@ -139,25 +334,57 @@ pub struct FileObject {
 }
 pub type FilePurpose = String;
 pub type FileState = String;
+#[derive(Debug, Clone)]
 pub struct FileUploadRequest {
     pub filename: String,
     pub purpose: String,
     pub content: Vec<u8>,
 }
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionDefinition {
     pub name: String,
     pub description: Option<String>,
     pub parameters: serde_json::Value,
 }
 pub type ModelCost = f64;
-pub type ModelLimit = u32;
+/// 模型的 token 限制
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModelLimit {
+    /// 上下文窗口（提示词 + 输出）
+    pub context: u32,
+    /// 单次输出上限
+    pub output: Option<u32>,
+}
+/// 路由候选模型
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ModelRoutingResult {
     pub model_id: String,
+    /// 处理该模型的提供者
+    pub provider_id: String,
     pub priority: u32,
 }
 pub type ProviderFileState = String;
 pub type TaskCategory = String;
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolDefinition {
     pub r#type: String,
     pub function: FunctionDefinition,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_message_serialization() {
+        let msg = ChatMessage {
+            role: MessageRole::User,
+            content: MessageContent::Text("hello".to_string()),
+            tool_calls: None,
+            tool_call_id: None,
+        };
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("\"role\":\"user\""));
+        assert!(json.contains("\"content\":\"hello\""));
+    }
+}
@@ -0,0 +1,493 @@
+use crate::common::endpoint::context::TokenCounter;
+use crate::common::endpoint::error::EndpointResult;
+use crate::common::endpoint::stream::{ChatResponse, ChatStreamEvent, UsageDelta};
+use crate::common::endpoint::traits::{
+    ChatMessage, ChatOptions, CostBreakdown, EmbeddingResponse, FileContentResponse,
+    FileDeletionStatus, FileObject, FileUploadRequest, LLMClient, ModelCost, ModelInfo, Usage,
+};
+use crate::common::provider::traits::StorageProvider;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// 模型单价（每百万 token 的费用）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input: ModelCost,
+    pub output: ModelCost,
+}
+
+impl Usage {
+    /// 按单价计算费用，键为 `input` / `output`
+    pub fn cost(&self, pricing: &ModelPricing) -> CostBreakdown {
+        let per_token = |price: ModelCost, tokens: u32| price * f64::from(tokens) / 1_000_000.0;
+        CostBreakdown::from([
+            (
+                "input".to_string(),
+                per_token(pricing.input, self.prompt_tokens),
+            ),
+            (
+                "output".to_string(),
+                per_token(pricing.output, self.completion_tokens),
+            ),
+        ])
+    }
+}
+
+/// 一次调用的用量记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    pub model: String,
+    /// 处理该请求的端点（提供者 ID）
+    pub provider: String,
+    /// 发起调用的 Agent Routine
+    pub routine_id: Option<Uuid>,
+    pub usage: Usage,
+    pub cost: CostBreakdown,
+}
+
+impl UsageRecord {
+    pub fn total_cost(&self) -> ModelCost {
+        self.cost.values().sum()
+    }
+}
+
+/// 一组记录的汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: ModelCost,
+}
+
+impl UsageSummary {
+    fn add(&mut self, record: &UsageRecord) {
+        self.requests += 1;
+        self.prompt_tokens += u64::from(record.usage.prompt_tokens);
+        self.completion_tokens += u64::from(record.usage.completion_tokens);
+        self.cost += record.total_cost();
+    }
+}
+
+/// 费用看板所需的全部汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub total: UsageSummary,
+    pub by_day: BTreeMap<NaiveDate, UsageSummary>,
+    pub by_provider: BTreeMap<String, UsageSummary>,
+    pub by_model: BTreeMap<String, UsageSummary>,
+    pub by_routine: BTreeMap<Uuid, UsageSummary>,
+}
+
+/// 用量账本：记录每次调用的用量与费用，并按日期、提供者、模型、Routine 汇总
+#[derive(Default)]
+pub struct UsageLedger {
+    records: RwLock<Vec<UsageRecord>>,
+    pricing: RwLock<HashMap<String, ModelPricing>>,
+}
+
+impl UsageLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置模型单价（未设置单价的模型费用记为 0）
+    pub fn set_pricing(&self, model: &str, pricing: ModelPricing) {
+        self.pricing
+            .write()
+            .unwrap()
+            .insert(model.to_string(), pricing);
+    }
+
+    /// 模型单价（未设置时为 0）
+    pub fn pricing(&self, model: &str) -> ModelPricing {
+        self.pricing
+            .read()
+            .unwrap()
+            .get(model)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// 记录一次调用
+    pub fn record(
+        &self,
+        model: &str,
+        provider: &str,
+        routine_id: Option<Uuid>,
+        usage: &Usage,
+    ) -> UsageRecord {
+        let pricing = self.pricing(model);
+        let record = UsageRecord {
+            timestamp: Utc::now(),
+            model: model.to_string(),
+            provider: provider.to_string(),
+            routine_id,
+            usage: usage.clone(),
+            cost: usage.cost(&pricing),
+        };
+        self.records.write().unwrap().push(record.clone());
+        record
+    }
+
+    pub fn records(&self) -> Vec<UsageRecord> {
+        self.records.read().unwrap().clone()
+    }
+
+    /// 时间范围 `[from, to)` 内的记录
+    pub fn between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<UsageRecord> {
+        self.records
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| r.timestamp >= from && r.timestamp < to)
+            .cloned()
+            .collect()
+    }
+
+    pub fn total(&self) -> UsageSummary {
+        let mut total = UsageSummary::default();
+        self.records
+            .read()
+            .unwrap()
+            .iter()
+            .for_each(|r| total.add(r));
+        total
+    }
+
+    /// 按 UTC 日期汇总
+    pub fn by_day(&self) -> BTreeMap<NaiveDate, UsageSummary> {
+        self.group_by(|r| Some(r.timestamp.date_naive()))
+    }
+
+    pub fn by_provider(&self) -> BTreeMap<String, UsageSummary> {
+        self.group_by(|r| Some(r.provider.clone()))
+    }
+
+    pub fn by_model(&self) -> BTreeMap<String, UsageSummary> {
+        self.group_by(|r| Some(r.model.clone()))
+    }
+
+    /// 按 Routine 汇总（不属于任何 Routine 的调用不计入）
+    pub fn by_routine(&self) -> BTreeMap<Uuid, UsageSummary> {
+        self.group_by(|r| r.routine_id)
+    }
+
+    /// 供前端费用看板使用的完整报告
+    pub fn report(&self) -> UsageReport {
+        UsageReport {
+            total: self.total(),
+            by_day: self.by_day(),
+            by_provider: self.by_provider(),
+            by_model: self.by_model(),
+            by_routine: self.by_routine(),
+        }
+    }
+
+    /// 将全部记录保存为 JSON
+    pub async fn save(&self, storage: &dyn StorageProvider, path: &str) -> anyhow::Result<()> {
+        let json = serde_json::to_vec_pretty(&self.records())?;
+        storage.write_file(path, &json).await
+    }
+
+    /// 从 JSON 加载记录，替换当前记录（重复加载不会重复计数）
+    pub async fn load(&self, storage: &dyn StorageProvider, path: &str) -> anyhow::Result<usize> {
+        if !storage.exists(path).await? {
+            return Ok(0);
+        }
+        let records: Vec<UsageRecord> = serde_json::from_slice(&storage.read_file(path).await?)?;
+        let count = records.len();
+        *self.records.write().unwrap() = records;
+        Ok(count)
+    }
+
+    fn group_by<K: Ord>(
+        &self,
+        key: impl Fn(&UsageRecord) -> Option<K>,
+    ) -> BTreeMap<K, UsageSummary> {
+        let mut groups: BTreeMap<K, UsageSummary> = BTreeMap::new();
+        for record in self.records.read().unwrap().iter() {
+            if let Some(k) = key(record) {
+                groups.entry(k).or_default().add(record);
+            }
+        }
+        groups
+    }
+}
+
+/// 流式用量计量：按分词器估算已生成的 token，定期在流中插入 `UsageDelta`，
+/// 收到提供商报告的 `Usage` 时以其为准校正累计值
+pub struct StreamMeter {
+    counter: TokenCounter,
+    /// 估算的提示词 token 数
+    prompt_tokens: u32,
+    /// 累计估算达到该 token 数时产出一次增量
+    interval: u32,
+    /// 已通过增量报告的累计用量
+    reported: Usage,
+    /// 已估算但尚未报告的输出 token
+    pending: u32,
+}
+
+impl StreamMeter {
+    pub fn new(model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Self {
+        let counter = TokenCounter::for_model(model);
+        let prompt_tokens = counter.count_messages(messages)
+            + options
+                .tools
+                .as_deref()
+                .map_or(0, |tools| counter.count_tools(tools));
+        Self {
+            counter,
+            prompt_tokens,
+            interval: 16,
+            reported: Usage::default(),
+            pending: 0,
+        }
+    }
+
+    pub fn with_interval(mut self, tokens: u32) -> Self {
+        self.interval = tokens.max(1);
+        self
+    }
+
+    /// 处理一个流事件，返回依次产出的事件（包含原事件）
+    pub fn observe(&mut self, event: ChatStreamEvent) -> Vec<ChatStreamEvent> {
+        match &event {
+            ChatStreamEvent::Delta(delta) => {
+                self.pending += delta
+                    .content
+                    .as_deref()
+                    .map_or(0, |text| self.counter.count_text(text));
+                for call in delta.tool_calls.iter().flatten() {
+                    self.pending += self.counter.count_text(&call.function.arguments);
+                }
+                if self.pending >= self.interval {
+                    let tick = self.estimate();
+                    return vec![event, tick];
+                }
+                vec![event]
+            }
+            ChatStreamEvent::Usage(usage) => {
+                let tick = self.reconcile(usage.clone());
+                vec![event, tick]
+            }
+            // 提供商没有报告用量时，结束前给出最终估算
+            ChatStreamEvent::Done if self.pending > 0 || self.reported == Usage::default() => {
+                vec![self.estimate(), event]
+            }
+            _ => vec![event],
+        }
+    }
+
+    fn estimate(&mut self) -> ChatStreamEvent {
+        let prompt_tokens = if self.reported.prompt_tokens == 0 {
+            self.prompt_tokens
+        } else {
+            self.reported.prompt_tokens
+        };
+        let completion_tokens = self.reported.completion_tokens + self.pending;
+        self.pending = 0;
+        self.advance(
+            Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            },
+            false,
+        )
+    }
+
+    fn reconcile(&mut self, usage: Usage) -> ChatStreamEvent {
+        self.pending = 0;
+        self.advance(usage, true)
+    }
+
+    fn advance(&mut self, total: Usage, reconciled: bool) -> ChatStreamEvent {
+        let delta = |now: u32, before: u32| i64::from(now) - i64::from(before);
+        let event = ChatStreamEvent::UsageDelta(UsageDelta {
+            prompt_tokens: delta(total.prompt_tokens, self.reported.prompt_tokens),
+            completion_tokens: delta(total.completion_tokens, self.reported.completion_tokens),
+            total: total.clone(),
+            reconciled,
+        });
+        self.reported = total;
+        event
+    }
+}
+
+/// 计量客户端：包装任意 `LLMClient`，把每次成功调用报告的用量记入账本
+///
+/// 由 `ModelRegistry` 在设置账本后安装到所有提供商客户端上，
+/// 因此回退链、排队刷新或直接取用客户端的调用都会被计量。
+pub struct MeteredClient {
+    inner: Arc<dyn LLMClient>,
+    ledger: Arc<UsageLedger>,
+    /// 记入账本的提供商名称（注册表中的键）
+    provider: String,
+}
+
+impl MeteredClient {
+    pub fn new(inner: Arc<dyn LLMClient>, ledger: Arc<UsageLedger>, provider: &str) -> Self {
+        Self {
+            inner,
+            ledger,
+            provider: provider.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl LLMClient for MeteredClient {
+    fn provider_id(&self) -> &str {
+        self.inner.provider_id()
+    }
+
+    async fn chat(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> EndpointResult<ChatResponse> {
+        let response = self.inner.chat(model, messages, options).await?;
+        if let Some(usage) = &response.usage {
+            self.ledger
+                .record(model, &self.provider, options.routine_id, usage);
+        }
+        Ok(response)
+    }
+
+    async fn embed(&self, model: &str, input: &[String]) -> EndpointResult<EmbeddingResponse> {
+        let response = self.inner.embed(model, input).await?;
+        self.ledger
+            .record(model, &self.provider, None, &response.usage);
+        Ok(response)
+    }
+
+    async fn health_check(&self) -> EndpointResult<()> {
+        self.inner.health_check().await
+    }
+
+    async fn list_models(&self) -> EndpointResult<Vec<ModelInfo>> {
+        self.inner.list_models().await
+    }
+
+    async fn upload_file(&self, request: &FileUploadRequest) -> EndpointResult<FileObject> {
+        self.inner.upload_file(request).await
+    }
+
+    async fn delete_file(&self, file_id: &str) -> EndpointResult<FileDeletionStatus> {
+        self.inner.delete_file(file_id).await
+    }
+
+    async fn get_file_content(&self, file_id: &str) -> EndpointResult<FileContentResponse> {
+        self.inner.get_file_content(file_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+
+    fn usage(prompt: u32, completion: u32) -> Usage {
+        Usage {
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            total_tokens: prompt + completion,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_usage_ledger_aggregation_and_persistence() {
+        let ledger = UsageLedger::new();
+        ledger.set_pricing(
+            "gpt-4o",
+            ModelPricing {
+                input: 2.5,
+                output: 10.0,
+            },
+        );
+        let routine = Uuid::new_v4();
+        let record = ledger.record(
+            "gpt-4o",
+            "openai",
+            Some(routine),
+            &usage(1_000_000, 100_000),
+        );
+        assert!((record.total_cost() - 3.5).abs() < 1e-9);
+        ledger.record("llama3", "ollama", None, &usage(500, 50));
+
+        let report = ledger.report();
+        assert_eq!(report.total.requests, 2);
+        assert_eq!(report.by_provider["ollama"].cost, 0.0);
+        assert_eq!(report.by_routine.len(), 1);
+        assert_eq!(report.by_routine[&routine].prompt_tokens, 1_000_000);
+        assert_eq!(report.by_day.len(), 1);
+        assert!(serde_json::to_value(&report).unwrap()["by_model"]["gpt-4o"].is_object());
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalFileSystem::new(dir.path());
+        ledger.save(&storage, "usage.json").await.unwrap();
+        let restored = UsageLedger::new();
+        assert_eq!(restored.load(&storage, "usage.json").await.unwrap(), 2);
+        assert_eq!(restored.report(), report);
+        // 重复加载替换而非追加
+        assert_eq!(restored.load(&storage, "usage.json").await.unwrap(), 2);
+        assert_eq!(restored.report(), report);
+    }
+
+    #[test]
+    fn test_stream_meter_estimates_then_reconciles() {
+        use crate::common::endpoint::stream::ChatDelta;
+        use crate::common::endpoint::traits::MessageRole;
+
+        let messages = [ChatMessage::text(MessageRole::User, "Write a haiku")];
+        let mut meter =
+            StreamMeter::new("gpt-4o", &messages, &ChatOptions::default()).with_interval(3);
+        let text = |t: &str| {
+            ChatStreamEvent::Delta(ChatDelta {
+                content: Some(t.to_string()),
+                ..Default::default()
+            })
+        };
+        let ticks = |events: Vec<ChatStreamEvent>| -> Vec<UsageDelta> {
+            events
+                .into_iter()
+                .filter_map(|e| match e {
+                    ChatStreamEvent::UsageDelta(d) => Some(d),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        assert!(ticks(meter.observe(ChatStreamEvent::Start)).is_empty());
+        assert!(ticks(meter.observe(text("Autumn"))).is_empty());
+        let first = ticks(meter.observe(text(" moonlight falls")));
+        assert_eq!(first.len(), 1);
+        assert!(!first[0].reconciled);
+        assert!(first[0].prompt_tokens > 0);
+        let counter = TokenCounter::for_model("gpt-4o");
+        let estimated = counter.count_text("Autumn") + counter.count_text(" moonlight falls");
+        assert_eq!(first[0].completion_tokens, i64::from(estimated));
+        let prompt = first[0].total.prompt_tokens;
+
+        // 提供商报告的用量覆盖估算，增量可能为负
+        let events = meter.observe(ChatStreamEvent::Usage(usage(prompt - 2, 5)));
+        assert!(matches!(events[0], ChatStreamEvent::Usage(_)));
+        let reconciled = ticks(events);
+        assert_eq!(reconciled[0].prompt_tokens, -2);
+        assert_eq!(reconciled[0].completion_tokens, 5 - i64::from(estimated));
+        assert!(reconciled[0].reconciled);
+        assert_eq!(reconciled[0].total, usage(prompt - 2, 5));
+
+        // 已校正且没有新的输出时，结束不再产出估算
+        let events = meter.observe(ChatStreamEvent::Done);
+        assert_eq!(events.len(), 1);
+    }
+}
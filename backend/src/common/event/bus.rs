@@ -0,0 +1,290 @@
+use crate::common::event::sink::EventSink;
+use crate::common::event::types::{BackendEvent, EventEnvelope, EventFilter};
+use crate::common::lifecycle::shutdown::{ShutdownHook, ShutdownPhase};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// 默认最多缓冲的事件数量
+const DEFAULT_CAPACITY: usize = 10_000;
+
+/// 单个接收端的投递统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SinkStats {
+    pub delivered: u64,
+    pub failed: u64,
+    pub last_error: Option<String>,
+}
+
+struct Subscription {
+    sink: Arc<dyn EventSink>,
+    filter: EventFilter,
+    stats: Mutex<SinkStats>,
+}
+
+/// 事件总线
+///
+/// `emit` 为同步且不阻塞的操作，仅将事件放入缓冲区；`flush` 将缓冲的事件
+/// 按过滤条件批量投递到各接收端。某个接收端失败不会影响其他接收端，
+/// 失败信息记录在其统计中。
+pub struct EventBus {
+    subscriptions: RwLock<Vec<Arc<Subscription>>>,
+    pending: Mutex<VecDeque<EventEnvelope>>,
+    capacity: usize,
+    dropped: AtomicU64,
+    notify: Notify,
+    /// 串行化投递，保证每个接收端看到的事件顺序与产生顺序一致
+    flushing: tokio::sync::Mutex<()>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// 缓冲区满时丢弃最旧的事件
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            subscriptions: RwLock::new(Vec::new()),
+            pending: Mutex::new(VecDeque::new()),
+            capacity: capacity.max(1),
+            dropped: AtomicU64::new(0),
+            notify: Notify::new(),
+            flushing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// 注册接收端
+    pub fn subscribe(&self, sink: Arc<dyn EventSink>, filter: EventFilter) {
+        self.subscriptions
+            .write()
+            .unwrap()
+            .push(Arc::new(Subscription {
+                sink,
+                filter,
+                stats: Mutex::new(SinkStats::default()),
+            }));
+    }
+
+    /// 按名称移除接收端，返回是否存在
+    pub fn unsubscribe(&self, name: &str) -> bool {
+        let mut subscriptions = self.subscriptions.write().unwrap();
+        let before = subscriptions.len();
+        subscriptions.retain(|s| s.sink.name() != name);
+        subscriptions.len() != before
+    }
+
+    /// 发出一个事件（没有接收端时直接忽略）
+    pub fn emit(&self, event: BackendEvent) {
+        if self.subscriptions.read().unwrap().is_empty() {
+            return;
+        }
+        let mut pending = self.pending.lock().unwrap();
+        if pending.len() >= self.capacity {
+            pending.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        pending.push_back(EventEnvelope::new(event));
+        drop(pending);
+        self.notify.notify_one();
+    }
+
+    /// 当前缓冲中尚未投递的事件数量
+    pub fn pending(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// 因缓冲区溢出而丢弃的事件数量
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 各接收端的投递统计，键为接收端名称
+    pub fn stats(&self) -> HashMap<String, SinkStats> {
+        self.subscriptions
+            .read()
+            .unwrap()
+            .iter()
+            .map(|s| (s.sink.name().to_string(), s.stats.lock().unwrap().clone()))
+            .collect()
+    }
+
+    /// 投递所有缓冲的事件，返回本次取出的事件数量
+    pub async fn flush(&self) -> usize {
+        let _guard = self.flushing.lock().await;
+        let events: Vec<EventEnvelope> = self.pending.lock().unwrap().drain(..).collect();
+        if events.is_empty() {
+            return 0;
+        }
+        let subscriptions = self.subscriptions.read().unwrap().clone();
+        let deliveries = subscriptions.iter().map(|subscription| {
+            let batch: Vec<EventEnvelope> = events
+                .iter()
+                .filter(|e| subscription.filter.matches(&e.event))
+                .cloned()
+                .collect();
+            async move {
+                if batch.is_empty() {
+                    return;
+                }
+                let result = subscription.sink.publish(&batch).await;
+                let mut stats = subscription.stats.lock().unwrap();
+                match result {
+                    Ok(()) => stats.delivered += batch.len() as u64,
+                    Err(e) => {
+                        stats.failed += batch.len() as u64;
+                        stats.last_error = Some(e.to_string());
+                    }
+                }
+            }
+        });
+        futures::future::join_all(deliveries).await;
+        events.len()
+    }
+
+    /// 启动后台投递任务：有新事件或每隔 `interval` 时刷新一次
+    pub fn spawn(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let bus = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                let _ = tokio::time::timeout(interval, bus.notify.notified()).await;
+                bus.flush().await;
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl ShutdownHook for EventBus {
+    fn name(&self) -> &str {
+        "event-bus"
+    }
+
+    fn phase(&self) -> ShutdownPhase {
+        ShutdownPhase::Flush
+    }
+
+    /// 投递剩余的事件
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        self.flush().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::types::EventKind;
+    use uuid::Uuid;
+
+    #[derive(Default)]
+    struct MemorySink {
+        name: String,
+        fail: bool,
+        events: Mutex<Vec<EventEnvelope>>,
+    }
+
+    #[async_trait]
+    impl EventSink for MemorySink {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn publish(&self, events: &[EventEnvelope]) -> anyhow::Result<()> {
+            if self.fail {
+                anyhow::bail!("unreachable");
+            }
+            self.events.lock().unwrap().extend_from_slice(events);
+            Ok(())
+        }
+    }
+
+    fn agent_event() -> BackendEvent {
+        BackendEvent::Agent {
+            routine_id: Uuid::new_v4(),
+            event: "status_changed".to_string(),
+            detail: serde_json::Value::Null,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_bus_filters_and_isolates_failures() {
+        let bus = EventBus::new();
+        bus.emit(agent_event());
+        assert_eq!(bus.pending(), 0, "no sinks, event is ignored");
+
+        let all = Arc::new(MemorySink {
+            name: "all".to_string(),
+            ..Default::default()
+        });
+        let diagnostics = Arc::new(MemorySink {
+            name: "diagnostics".to_string(),
+            ..Default::default()
+        });
+        let broken = Arc::new(MemorySink {
+            name: "broken".to_string(),
+            fail: true,
+            ..Default::default()
+        });
+        bus.subscribe(all.clone(), EventFilter::all());
+        bus.subscribe(
+            diagnostics.clone(),
+            EventFilter::only([EventKind::Diagnostics]),
+        );
+        bus.subscribe(broken, EventFilter::all());
+
+        bus.emit(agent_event());
+        bus.emit(BackendEvent::Diagnostics {
+            path: "a.rs".to_string(),
+            diagnostics: serde_json::json!([]),
+        });
+        assert_eq!(bus.flush().await, 2);
+        assert_eq!(bus.flush().await, 0);
+
+        assert_eq!(all.events.lock().unwrap().len(), 2);
+        assert_eq!(diagnostics.events.lock().unwrap().len(), 1);
+        let stats = bus.stats();
+        assert_eq!(stats["all"].delivered, 2);
+        assert_eq!(stats["broken"].failed, 2);
+        assert_eq!(stats["broken"].last_error.as_deref(), Some("unreachable"));
+
+        assert!(bus.unsubscribe("broken"));
+        assert!(!bus.unsubscribe("broken"));
+    }
+
+    #[tokio::test]
+    async fn test_event_bus_drops_oldest_when_full_and_flushes_in_background() {
+        let bus = Arc::new(EventBus::with_capacity(2));
+        let sink = Arc::new(MemorySink {
+            name: "memory".to_string(),
+            ..Default::default()
+        });
+        bus.subscribe(sink.clone(), EventFilter::all());
+        for _ in 0..3 {
+            bus.emit(agent_event());
+        }
+        assert_eq!(bus.pending(), 2);
+        assert_eq!(bus.dropped(), 1);
+
+        let handle = bus.spawn(Duration::from_millis(10));
+        for _ in 0..100 {
+            if sink.events.lock().unwrap().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        handle.abort();
+        assert_eq!(sink.events.lock().unwrap().len(), 2);
+    }
+}
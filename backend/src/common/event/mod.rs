@@ -0,0 +1,24 @@
+//! # 事件导出
+//!
+//! 将后端内部事件（变更提交、意图分发、Agent 事件、诊断、开发服务器状态）推送到外部系统，
+//! 供自动化与分析流水线订阅，无需轮询。
+//!
+//! ## 模块
+//!
+//! - [`types`] - 事件类型与信封
+//! - [`sink`] - 可插拔的事件接收端（文件、Webhook、NATS）
+//! - [`bus`] - 缓冲事件并按过滤条件分发到各接收端
+//! - [`notification`] - 汇总需要用户关注的事件（去重、已读状态、附带意图操作）
+
+pub mod bus;
+pub mod notification;
+pub mod sink;
+pub mod types;
+
+pub use bus::{EventBus, SinkStats};
+pub use notification::{
+    Notification, NotificationAction, NotificationCenter, NotificationKind, NotificationQuery,
+    NotificationRequest, NotificationSeverity,
+};
+pub use sink::{EventSink, FileSink, NatsSink, WebhookSink};
+pub use types::{BackendEvent, EventEnvelope, EventFilter, EventKind};
@@ -0,0 +1,499 @@
+use crate::agent::ReviewIntent;
+use crate::common::event::sink::EventSink;
+use crate::common::event::types::{BackendEvent, EventEnvelope};
+use crate::common::intent::traits::SystemIntent;
+use crate::common::intent::{IntentDispatcher, IntentOrigin};
+use crate::project::SecretIntent;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// 通知默认最多保留的条数，超出时丢弃最旧的已读通知
+const DEFAULT_CAPACITY: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// 等待用户批准的请求（变更审阅、密钥授权）
+    ApprovalRequest,
+    BuildFailure,
+    MergeConflict,
+    BudgetWarning,
+    AgentFailure,
+    DevServer,
+}
+
+/// 通知上附带的操作，触发时分发对应的意图
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationAction {
+    pub id: String,
+    pub label: String,
+    /// 意图摘要，供前端展示
+    pub summary: String,
+    #[serde(skip)]
+    intent: Option<SystemIntent>,
+}
+
+impl NotificationAction {
+    pub fn new(id: &str, label: &str, intent: SystemIntent) -> Self {
+        Self {
+            id: id.to_string(),
+            label: label.to_string(),
+            summary: intent.summary(),
+            intent: Some(intent),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: Uuid,
+    /// 去重键：同一键的后续通知更新已有条目而不是新增
+    pub key: String,
+    pub kind: NotificationKind,
+    pub severity: NotificationSeverity,
+    pub title: String,
+    pub body: String,
+    pub actions: Vec<NotificationAction>,
+    pub read: bool,
+    /// 同一键被通知的次数
+    pub occurrences: u32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 发布通知时提供的内容
+#[derive(Debug, Clone)]
+pub struct NotificationRequest {
+    pub key: String,
+    pub kind: NotificationKind,
+    pub severity: NotificationSeverity,
+    pub title: String,
+    pub body: String,
+    pub actions: Vec<NotificationAction>,
+}
+
+/// 通知查询条件
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NotificationQuery {
+    #[serde(default)]
+    pub unread_only: bool,
+    /// 只返回不低于该级别的通知
+    #[serde(default)]
+    pub min_severity: Option<NotificationSeverity>,
+    #[serde(default)]
+    pub kind: Option<NotificationKind>,
+}
+
+/// 通知中心：汇总需要用户关注的事件，供前端查询与处理
+///
+/// 作为事件总线的接收端订阅后，会将待审阅的变更与密钥请求、编译错误、开发服务器故障与
+/// Routine 失败转为通知，请求被处理或问题消失时自动移除；合并冲突与预算告警等由产生方
+/// 通过 `notify` 直接发布。
+pub struct NotificationCenter {
+    notifications: RwLock<HashMap<String, Notification>>,
+    capacity: usize,
+}
+
+impl Default for NotificationCenter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotificationCenter {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            notifications: RwLock::new(HashMap::new()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// 发布通知；同一键已存在时更新内容、累加次数并重新置为未读
+    pub fn notify(&self, request: NotificationRequest) -> Notification {
+        let mut notifications = self.notifications.write().unwrap();
+        let now = Utc::now();
+        let notification = match notifications.remove(&request.key) {
+            Some(existing) => Notification {
+                kind: request.kind,
+                severity: request.severity,
+                title: request.title,
+                body: request.body,
+                actions: request.actions,
+                read: false,
+                occurrences: existing.occurrences + 1,
+                updated_at: now,
+                ..existing
+            },
+            None => Notification {
+                id: Uuid::new_v4(),
+                key: request.key,
+                kind: request.kind,
+                severity: request.severity,
+                title: request.title,
+                body: request.body,
+                actions: request.actions,
+                read: false,
+                occurrences: 1,
+                created_at: now,
+                updated_at: now,
+            },
+        };
+        if notifications.len() >= self.capacity {
+            let oldest = notifications
+                .values()
+                .min_by_key(|n| (!n.read, n.updated_at))
+                .map(|n| n.key.clone());
+            if let Some(oldest) = oldest {
+                notifications.remove(&oldest);
+            }
+        }
+        notifications.insert(notification.key.clone(), notification.clone());
+        notification
+    }
+
+    /// 移除某个键的通知（对应的请求已处理或问题已消失）
+    pub fn dismiss_key(&self, key: &str) -> bool {
+        self.notifications.write().unwrap().remove(key).is_some()
+    }
+
+    pub fn dismiss(&self, id: Uuid) -> bool {
+        let mut notifications = self.notifications.write().unwrap();
+        let before = notifications.len();
+        notifications.retain(|_, n| n.id != id);
+        notifications.len() != before
+    }
+
+    pub fn mark_read(&self, id: Uuid) -> bool {
+        self.notifications
+            .write()
+            .unwrap()
+            .values_mut()
+            .find(|n| n.id == id)
+            .map(|n| n.read = true)
+            .is_some()
+    }
+
+    pub fn mark_all_read(&self) {
+        for notification in self.notifications.write().unwrap().values_mut() {
+            notification.read = true;
+        }
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<Notification> {
+        self.notifications
+            .read()
+            .unwrap()
+            .values()
+            .find(|n| n.id == id)
+            .cloned()
+    }
+
+    /// 按条件查询通知，按严重程度降序、时间倒序排列
+    pub fn list(&self, query: &NotificationQuery) -> Vec<Notification> {
+        let mut list: Vec<Notification> = self
+            .notifications
+            .read()
+            .unwrap()
+            .values()
+            .filter(|n| !query.unread_only || !n.read)
+            .filter(|n| query.min_severity.is_none_or(|s| n.severity >= s))
+            .filter(|n| query.kind.is_none_or(|k| n.kind == k))
+            .cloned()
+            .collect();
+        list.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then(b.updated_at.cmp(&a.updated_at))
+        });
+        list
+    }
+
+    pub fn unread_count(&self) -> usize {
+        self.notifications
+            .read()
+            .unwrap()
+            .values()
+            .filter(|n| !n.read)
+            .count()
+    }
+
+    /// 触发通知上的操作：分发其意图，成功后移除该通知
+    pub async fn act(
+        &self,
+        id: Uuid,
+        action_id: &str,
+        dispatcher: &IntentDispatcher,
+    ) -> anyhow::Result<()> {
+        let notification = self
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("Notification not found: {}", id))?;
+        let intent = notification
+            .actions
+            .into_iter()
+            .find(|a| a.id == action_id)
+            .and_then(|a| a.intent)
+            .ok_or_else(|| anyhow::anyhow!("Unknown action: {}", action_id))?;
+        dispatcher.dispatch_as(IntentOrigin::ui(), intent).await?;
+        self.dismiss(id);
+        Ok(())
+    }
+
+    /// 将后端事件转为通知或移除已失效的通知
+    pub fn observe(&self, event: &BackendEvent) {
+        match event {
+            BackendEvent::Agent {
+                routine_id,
+                event,
+                detail,
+            } => self.observe_agent(*routine_id, event, detail),
+            BackendEvent::Secret { name, detail } => {
+                let Some(request_id) = detail["request_id"].as_str().and_then(|s| s.parse().ok())
+                else {
+                    return;
+                };
+                let key = format!("secret:{}", request_id);
+                if detail["status"] != "pending" {
+                    self.dismiss_key(&key);
+                    return;
+                }
+                self.notify(NotificationRequest {
+                    key,
+                    kind: NotificationKind::ApprovalRequest,
+                    severity: NotificationSeverity::Warning,
+                    title: format!("Secret requested: {}", name),
+                    body: format!(
+                        "{}: {}",
+                        detail["task"].as_str().unwrap_or_default(),
+                        detail["reason"].as_str().unwrap_or_default()
+                    ),
+                    actions: vec![
+                        NotificationAction::new(
+                            "grant",
+                            "Grant",
+                            SystemIntent::Secret(SecretIntent::Grant {
+                                request_id,
+                                value: None,
+                            }),
+                        ),
+                        NotificationAction::new(
+                            "deny",
+                            "Deny",
+                            SystemIntent::Secret(SecretIntent::Deny { request_id }),
+                        ),
+                    ],
+                });
+            }
+            BackendEvent::Diagnostics { path, diagnostics } => {
+                let key = format!("diagnostics:{}", path);
+                let errors: Vec<&str> = diagnostics
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|d| d["severity"] == "Error")
+                    .filter_map(|d| d["message"].as_str())
+                    .collect();
+                if errors.is_empty() {
+                    self.dismiss_key(&key);
+                    return;
+                }
+                self.notify(NotificationRequest {
+                    key,
+                    kind: NotificationKind::BuildFailure,
+                    severity: NotificationSeverity::Error,
+                    title: format!("{} error(s) in {}", errors.len(), path),
+                    body: errors.join("\n"),
+                    actions: Vec::new(),
+                });
+            }
+            BackendEvent::DevServer { name, detail } => {
+                let key = format!("dev_server:{}", name);
+                let severity = match detail["status"].as_str() {
+                    Some("exited") => NotificationSeverity::Error,
+                    Some("unhealthy") => NotificationSeverity::Warning,
+                    _ => {
+                        self.dismiss_key(&key);
+                        return;
+                    }
+                };
+                self.notify(NotificationRequest {
+                    key,
+                    kind: NotificationKind::DevServer,
+                    severity,
+                    title: format!(
+                        "Dev server {} is {}",
+                        name,
+                        detail["status"].as_str().unwrap_or_default()
+                    ),
+                    body: String::new(),
+                    actions: Vec::new(),
+                });
+            }
+            BackendEvent::ChangeCommitted { .. } | BackendEvent::IntentDispatched { .. } => {}
+        }
+    }
+
+    fn observe_agent(&self, routine_id: Uuid, event: &str, detail: &Value) {
+        match event {
+            "review_submitted" => {
+                let Some(thread_id) = detail["thread_id"].as_str().and_then(|s| s.parse().ok())
+                else {
+                    return;
+                };
+                self.notify(NotificationRequest {
+                    key: format!("review:{}", thread_id),
+                    kind: NotificationKind::ApprovalRequest,
+                    severity: NotificationSeverity::Info,
+                    title: format!(
+                        "Review requested: {}",
+                        detail["title"].as_str().unwrap_or_default()
+                    ),
+                    body: String::new(),
+                    actions: vec![
+                        NotificationAction::new(
+                            "approve",
+                            "Approve",
+                            SystemIntent::Review(ReviewIntent::Approve { thread_id }),
+                        ),
+                        NotificationAction::new(
+                            "reject",
+                            "Reject",
+                            SystemIntent::Review(ReviewIntent::Reject {
+                                thread_id,
+                                reason: None,
+                            }),
+                        ),
+                    ],
+                });
+            }
+            "review_approved" | "review_rejected" => {
+                if let Some(thread_id) = detail["thread_id"].as_str() {
+                    self.dismiss_key(&format!("review:{}", thread_id));
+                }
+            }
+            "status_changed" => {
+                let key = format!("routine:{}", routine_id);
+                match detail["status"]["Failed"].as_str() {
+                    Some(reason) => {
+                        self.notify(NotificationRequest {
+                            key,
+                            kind: NotificationKind::AgentFailure,
+                            severity: NotificationSeverity::Error,
+                            title: format!("Routine {} failed", routine_id),
+                            body: reason.to_string(),
+                            actions: Vec::new(),
+                        });
+                    }
+                    None => {
+                        self.dismiss_key(&key);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for NotificationCenter {
+    fn name(&self) -> &str {
+        "notifications"
+    }
+
+    async fn publish(&self, events: &[EventEnvelope]) -> anyhow::Result<()> {
+        for envelope in events {
+            self.observe(&envelope.event);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::intent::IntentHandler;
+    use crate::common::intent::traits::IntentCategory;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl IntentHandler for Recorder {
+        async fn handle(&self, intent: SystemIntent) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(intent.summary());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notifications_from_events() {
+        let center = NotificationCenter::new();
+        let thread_id = Uuid::new_v4();
+        let review = |event: &str| BackendEvent::Agent {
+            routine_id: Uuid::new_v4(),
+            event: event.to_string(),
+            detail: json!({ "thread_id": thread_id, "title": "Fix parser" }),
+        };
+        let diagnostics = |severity: &str| BackendEvent::Diagnostics {
+            path: "src/lib.rs".to_string(),
+            diagnostics: json!([{ "message": "boom", "severity": severity, "line": 1, "column": 1 }]),
+        };
+
+        center.observe(&review("review_submitted"));
+        center.observe(&diagnostics("Error"));
+        center.observe(&diagnostics("Error"));
+        let list = center.list(&NotificationQuery::default());
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].kind, NotificationKind::BuildFailure);
+        assert_eq!(list[0].occurrences, 2);
+        assert_eq!(center.unread_count(), 2);
+
+        center.mark_read(list[0].id);
+        let unread = center.list(&NotificationQuery {
+            unread_only: true,
+            ..Default::default()
+        });
+        assert_eq!(unread.len(), 1);
+        assert_eq!(
+            unread[0].actions[0].summary,
+            format!("ApproveReview {}", thread_id)
+        );
+        let json = serde_json::to_value(&unread[0]).unwrap();
+        assert_eq!(json["actions"][1]["id"], "reject");
+
+        // 操作分发意图并移除通知
+        let dispatcher = IntentDispatcher::new();
+        let recorder = Arc::new(Recorder::default());
+        dispatcher
+            .register(IntentCategory::Review, recorder.clone())
+            .await;
+        center
+            .act(unread[0].id, "approve", &dispatcher)
+            .await
+            .unwrap();
+        assert_eq!(recorder.0.lock().unwrap().len(), 1);
+        assert!(center.get(unread[0].id).is_none());
+
+        // 问题消失后移除通知
+        center.observe(&diagnostics("Warning"));
+        assert!(center.list(&NotificationQuery::default()).is_empty());
+    }
+}
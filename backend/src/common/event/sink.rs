@@ -0,0 +1,284 @@
+use crate::common::event::types::EventEnvelope;
+use anyhow::{Context, bail};
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+/// 事件接收端
+///
+/// 接收端按批收到事件。Kafka 等其他消息系统可通过实现该 Trait 接入。
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    /// 接收端名称（用于统计与错误报告）
+    fn name(&self) -> &str;
+
+    /// 发布一批事件，批内顺序即事件产生顺序
+    async fn publish(&self, events: &[EventEnvelope]) -> anyhow::Result<()>;
+}
+
+/// 以 JSON Lines 格式追加写入本地文件
+pub struct FileSink {
+    path: PathBuf,
+    /// 串行化并发写入，避免行交错
+    lock: Mutex<()>,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for FileSink {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn publish(&self, events: &[EventEnvelope]) -> anyhow::Result<()> {
+        let mut buf = Vec::new();
+        for event in events {
+            serde_json::to_writer(&mut buf, event)?;
+            buf.push(b'\n');
+        }
+        let _guard = self.lock.lock().await;
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_context(|| format!("failed to open {}", self.path.display()))?;
+        file.write_all(&buf).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// 以 JSON 数组 POST 到 Webhook 地址
+pub struct WebhookSink {
+    url: String,
+    headers: Vec<(String, String)>,
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: Vec::new(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// 附加请求头（如鉴权令牌）
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn publish(&self, events: &[EventEnvelope]) -> anyhow::Result<()> {
+        let mut request = self.client.post(&self.url).json(events);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// 通过 NATS 核心协议发布，每个事件发往 `<prefix>.<kind>` 主题
+pub struct NatsSink {
+    addr: String,
+    prefix: String,
+}
+
+impl NatsSink {
+    /// `addr` 形如 `127.0.0.1:4222`
+    pub fn new(addr: impl Into<String>, prefix: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            prefix: prefix.into(),
+        }
+    }
+
+    fn subject(&self, event: &EventEnvelope) -> anyhow::Result<String> {
+        let kind = serde_json::to_value(event.event.kind())?;
+        Ok(format!(
+            "{}.{}",
+            self.prefix,
+            kind.as_str().unwrap_or_default()
+        ))
+    }
+}
+
+#[async_trait]
+impl EventSink for NatsSink {
+    fn name(&self) -> &str {
+        "nats"
+    }
+
+    async fn publish(&self, events: &[EventEnvelope]) -> anyhow::Result<()> {
+        let stream = TcpStream::connect(&self.addr).await?;
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+
+        let info = lines.next_line().await?.unwrap_or_default();
+        if !info.starts_with("INFO") {
+            bail!("unexpected NATS greeting: {}", info);
+        }
+
+        let mut buf = b"CONNECT {\"verbose\":false,\"pedantic\":false}\r\n".to_vec();
+        for event in events {
+            let payload = serde_json::to_vec(event)?;
+            buf.extend_from_slice(
+                format!("PUB {} {}\r\n", self.subject(event)?, payload.len()).as_bytes(),
+            );
+            buf.extend_from_slice(&payload);
+            buf.extend_from_slice(b"\r\n");
+        }
+        // 以 PING/PONG 往返确认服务器已处理之前的全部命令
+        buf.extend_from_slice(b"PING\r\n");
+        write.write_all(&buf).await?;
+        write.flush().await?;
+
+        while let Some(line) = lines.next_line().await? {
+            if line.starts_with("PONG") {
+                return Ok(());
+            }
+            if line.starts_with("-ERR") {
+                bail!("NATS error: {}", line);
+            }
+        }
+        bail!("NATS connection closed before acknowledgement")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::types::BackendEvent;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+
+    fn sample() -> Vec<EventEnvelope> {
+        vec![
+            EventEnvelope::new(BackendEvent::Agent {
+                routine_id: Uuid::new_v4(),
+                event: "status_changed".to_string(),
+                detail: serde_json::json!("Completed"),
+            }),
+            EventEnvelope::new(BackendEvent::Diagnostics {
+                path: "src/main.rs".to_string(),
+                diagnostics: serde_json::json!([]),
+            }),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_file_sink_appends_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events/log.jsonl");
+        let sink = FileSink::new(&path);
+        let events = sample();
+        sink.publish(&events).await.unwrap();
+        sink.publish(&events[..1]).await.unwrap();
+
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        let lines: Vec<EventEnvelope> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], events[0]);
+        assert_eq!(lines[2], events[0]);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_sink_posts_batch() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text
+                        .lines()
+                        .find_map(|l| l.strip_prefix("content-length: "))
+                        .and_then(|v| v.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            socket
+                .write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let sink = WebhookSink::new(format!("http://{}/hooks", addr))
+            .with_header("authorization", "Bearer secret");
+        sink.publish(&sample()).await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hooks"));
+        assert!(request.contains("authorization: Bearer secret"));
+        let body = &request[request.find("\r\n\r\n").unwrap() + 4..];
+        let events: Vec<EventEnvelope> = serde_json::from_str(body).unwrap();
+        assert_eq!(events.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_nats_sink_publishes_per_kind_subjects() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (read, mut write) = socket.into_split();
+            write.write_all(b"INFO {}\r\n").await.unwrap();
+            let mut lines = BufReader::new(read).lines();
+            let mut received = Vec::new();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                if line == "PING" {
+                    write.write_all(b"PONG\r\n").await.unwrap();
+                    break;
+                }
+                received.push(line);
+            }
+            received
+        });
+
+        NatsSink::new(addr.to_string(), "zhiyun.events")
+            .publish(&sample())
+            .await
+            .unwrap();
+
+        let received = server.await.unwrap();
+        assert!(received[0].starts_with("CONNECT"));
+        assert!(received[1].starts_with("PUB zhiyun.events.agent "));
+        assert!(received[3].starts_with("PUB zhiyun.events.diagnostics "));
+    }
+}
@@ -0,0 +1,149 @@
+use crate::common::change::Change;
+use crate::common::change::thread::ThreadId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// 事件种类，用于按类订阅
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    ChangeCommitted,
+    IntentDispatched,
+    Agent,
+    Diagnostics,
+    DevServer,
+    Secret,
+}
+
+/// 后端对外发布的事件
+///
+/// `common` 不依赖业务模块，因此 Agent 与诊断事件的负载以 JSON 形式携带，
+/// 由产生事件的模块负责序列化。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BackendEvent {
+    /// 一个 Change 被提交到 Thread
+    ChangeCommitted {
+        thread_id: ThreadId,
+        change_id: Uuid,
+        author_id: Uuid,
+        hash: String,
+        operations: usize,
+    },
+    /// 一个意图分发完成；`error` 为空表示处理成功
+    IntentDispatched {
+        category: String,
+        intent: String,
+        error: Option<String>,
+    },
+    /// Routine 生命周期事件（如状态变化）
+    Agent {
+        routine_id: Uuid,
+        event: String,
+        detail: serde_json::Value,
+    },
+    /// 某个文件的一组诊断
+    Diagnostics {
+        path: String,
+        diagnostics: serde_json::Value,
+    },
+    /// 开发服务器状态变化
+    DevServer {
+        name: String,
+        detail: serde_json::Value,
+    },
+    /// 密钥授权请求的创建与处理（负载不含密钥值）
+    Secret {
+        name: String,
+        detail: serde_json::Value,
+    },
+}
+
+impl BackendEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            BackendEvent::ChangeCommitted { .. } => EventKind::ChangeCommitted,
+            BackendEvent::IntentDispatched { .. } => EventKind::IntentDispatched,
+            BackendEvent::Agent { .. } => EventKind::Agent,
+            BackendEvent::Diagnostics { .. } => EventKind::Diagnostics,
+            BackendEvent::DevServer { .. } => EventKind::DevServer,
+            BackendEvent::Secret { .. } => EventKind::Secret,
+        }
+    }
+
+    pub fn change_committed(thread_id: ThreadId, change: &Change) -> Self {
+        BackendEvent::ChangeCommitted {
+            thread_id,
+            change_id: change.id,
+            author_id: change.author_id,
+            hash: change.hash.clone(),
+            operations: change.operations.len(),
+        }
+    }
+}
+
+/// 带唯一 ID 与时间戳的事件，是接收端实际收到的单位
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventEnvelope {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: BackendEvent,
+}
+
+impl EventEnvelope {
+    pub fn new(event: BackendEvent) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            event,
+        }
+    }
+}
+
+/// 接收端的订阅过滤条件，`kinds` 为空表示接收全部事件
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventFilter {
+    pub kinds: HashSet<EventKind>,
+}
+
+impl EventFilter {
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    pub fn only(kinds: impl IntoIterator<Item = EventKind>) -> Self {
+        Self {
+            kinds: kinds.into_iter().collect(),
+        }
+    }
+
+    pub fn matches(&self, event: &BackendEvent) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&event.kind())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_serialization_and_filter() {
+        let envelope = EventEnvelope::new(BackendEvent::IntentDispatched {
+            category: "Editor".to_string(),
+            intent: "Save".to_string(),
+            error: None,
+        });
+        let json = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(json["type"], "intent_dispatched");
+        assert_eq!(json["category"], "Editor");
+        let restored: EventEnvelope = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, envelope);
+
+        let filter = EventFilter::only([EventKind::ChangeCommitted]);
+        assert!(!filter.matches(&envelope.event));
+        assert!(EventFilter::all().matches(&envelope.event));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::common::i18n::locale::Locale;
+use std::collections::HashMap;
+
+/// 消息目录：按语言保存 `键 -> 模板`，模板中的 `{name}` 会被参数替换
+#[derive(Debug, Clone)]
+pub struct MessageCatalog {
+    messages: HashMap<Locale, HashMap<String, String>>,
+    /// 当前语言缺少某条消息时使用的语言
+    fallback: Locale,
+}
+
+impl Default for MessageCatalog {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl MessageCatalog {
+    /// 创建空目录
+    pub fn empty(fallback: Locale) -> Self {
+        Self {
+            messages: HashMap::new(),
+            fallback,
+        }
+    }
+
+    /// 加载内置的消息目录
+    pub fn builtin() -> Self {
+        let mut catalog = Self::empty(Locale::EnUs);
+        catalog
+            .merge_json(Locale::EnUs, include_str!("locales/en-US.json"))
+            .expect("builtin en-US catalog is valid");
+        catalog
+            .merge_json(Locale::ZhCn, include_str!("locales/zh-CN.json"))
+            .expect("builtin zh-CN catalog is valid");
+        catalog
+    }
+
+    /// 添加或覆盖一条消息
+    pub fn insert(&mut self, locale: Locale, key: &str, template: &str) {
+        self.messages
+            .entry(locale)
+            .or_default()
+            .insert(key.to_string(), template.to_string());
+    }
+
+    /// 合并 JSON 对象形式的目录（用于用户自定义或插件提供的翻译）
+    pub fn merge_json(&mut self, locale: Locale, json: &str) -> anyhow::Result<()> {
+        let entries: HashMap<String, String> = serde_json::from_str(json)?;
+        self.messages.entry(locale).or_default().extend(entries);
+        Ok(())
+    }
+
+    /// 是否包含指定语言的消息
+    pub fn contains(&self, locale: Locale, key: &str) -> bool {
+        self.messages
+            .get(&locale)
+            .is_some_and(|messages| messages.contains_key(key))
+    }
+
+    /// 查找消息模板，依次尝试 `locale` 与回退语言
+    pub fn lookup(&self, locale: Locale, key: &str) -> Option<&str> {
+        [locale, self.fallback].iter().find_map(|l| {
+            self.messages
+                .get(l)
+                .and_then(|messages| messages.get(key))
+                .map(String::as_str)
+        })
+    }
+
+    /// 翻译并填充参数；找不到消息时返回键本身
+    pub fn translate(&self, locale: Locale, key: &str, args: &[(&str, &str)]) -> String {
+        let Some(template) = self.lookup(locale, key) else {
+            return key.to_string();
+        };
+        args.iter()
+            .fold(template.to_string(), |acc, (name, value)| {
+                acc.replace(&format!("{{{}}}", name), value)
+            })
+    }
+
+    /// 回退语言中存在、但指定语言缺失的键（用于检查翻译完整性）
+    pub fn missing_keys(&self, locale: Locale) -> Vec<String> {
+        let mut missing: Vec<String> = self
+            .messages
+            .get(&self.fallback)
+            .map(|messages| {
+                messages
+                    .keys()
+                    .filter(|key| !self.contains(locale, key))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        missing.sort();
+        missing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_translate_and_fallback() {
+        let mut catalog = MessageCatalog::builtin();
+        for locale in Locale::ALL {
+            assert!(catalog.missing_keys(locale).is_empty(), "{}", locale);
+        }
+
+        assert_eq!(
+            catalog.translate(
+                Locale::ZhCn,
+                "error.endpoint.model_not_found",
+                &[("model", "gpt-4")]
+            ),
+            "未找到模型：gpt-4"
+        );
+
+        catalog.insert(Locale::EnUs, "only.english", "Hello {name}");
+        assert_eq!(
+            catalog.translate(Locale::ZhCn, "only.english", &[("name", "Zhiyun")]),
+            "Hello Zhiyun"
+        );
+        assert_eq!(
+            catalog.translate(Locale::ZhCn, "no.such.key", &[]),
+            "no.such.key"
+        );
+    }
+}
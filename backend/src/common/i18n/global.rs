@@ -0,0 +1,66 @@
+use crate::common::i18n::catalog::MessageCatalog;
+use crate::common::i18n::locale::Locale;
+use std::sync::{OnceLock, RwLock};
+
+/// 可本地化为用户语言的类型
+pub trait Localize {
+    /// 以指定语言渲染
+    fn localize(&self, locale: Locale) -> String;
+
+    /// 以当前全局语言渲染
+    fn localized(&self) -> String {
+        self.localize(current_locale())
+    }
+}
+
+/// 全局语言设置与消息目录
+struct I18nState {
+    locale: Locale,
+    catalog: MessageCatalog,
+}
+
+static GLOBAL_I18N: OnceLock<RwLock<I18nState>> = OnceLock::new();
+
+fn state() -> &'static RwLock<I18nState> {
+    GLOBAL_I18N.get_or_init(|| {
+        RwLock::new(I18nState {
+            locale: Locale::default(),
+            catalog: MessageCatalog::builtin(),
+        })
+    })
+}
+
+/// 获取当前语言
+pub fn current_locale() -> Locale {
+    state().read().unwrap().locale
+}
+
+/// 设置当前语言（通常来自用户设置）
+pub fn set_locale(locale: Locale) {
+    state().write().unwrap().locale = locale;
+}
+
+/// 合并额外的翻译到全局目录
+pub fn merge_catalog(locale: Locale, json: &str) -> anyhow::Result<()> {
+    state().write().unwrap().catalog.merge_json(locale, json)
+}
+
+/// 以指定语言翻译消息
+pub fn translate(locale: Locale, key: &str, args: &[(&str, &str)]) -> String {
+    state().read().unwrap().catalog.translate(locale, key, args)
+}
+
+/// 以当前语言翻译消息
+pub fn t(key: &str, args: &[(&str, &str)]) -> String {
+    translate(current_locale(), key, args)
+}
+
+/// 以指定语言查找消息，缺失时返回 `None`（用于带有内置默认文本的场景）
+pub fn lookup(locale: Locale, key: &str) -> Option<String> {
+    state()
+        .read()
+        .unwrap()
+        .catalog
+        .lookup(locale, key)
+        .map(str::to_string)
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 支持的界面语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en-US")]
+    EnUs,
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+}
+
+impl Locale {
+    /// 所有内置语言
+    pub const ALL: [Locale; 2] = [Locale::EnUs, Locale::ZhCn];
+
+    /// BCP 47 语言标签
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::ZhCn => "zh-CN",
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = anyhow::Error;
+
+    /// 宽松解析：接受 `zh`、`zh_CN`、`zh-Hans`、`en-GB.UTF-8` 等形式
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match language.as_str() {
+            "en" => Ok(Locale::EnUs),
+            "zh" => Ok(Locale::ZhCn),
+            _ => Err(anyhow::anyhow!("Unsupported locale: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_parse() {
+        assert_eq!("zh_CN.UTF-8".parse::<Locale>().unwrap(), Locale::ZhCn);
+        assert_eq!("en-GB".parse::<Locale>().unwrap(), Locale::EnUs);
+        assert!("fr".parse::<Locale>().is_err());
+        assert_eq!(serde_json::to_string(&Locale::ZhCn).unwrap(), "\"zh-CN\"");
+    }
+}
@@ -0,0 +1,17 @@
+//! # 国际化 (i18n)
+//!
+//! 为错误、工具描述与 Agent 状态等面向用户的文本提供统一的多语言支持。
+//!
+//! ## 模块
+//!
+//! - [`locale`] - 支持的语言及其解析
+//! - [`catalog`] - 消息目录与参数插值
+//! - [`global`] - 全局语言设置、翻译函数与 `Localize` trait
+
+pub mod catalog;
+pub mod global;
+pub mod locale;
+
+pub use catalog::MessageCatalog;
+pub use global::{Localize, current_locale, lookup, merge_catalog, set_locale, t, translate};
+pub use locale::Locale;
@@ -0,0 +1,376 @@
+use crate::common::intent::middleware::{
+    IntentContext, IntentMetrics, IntentMiddleware, IntentOrigin,
+};
+use crate::common::intent::traits::IntentCategory;
+use crate::common::provider::traits::StorageProvider;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// 审计日志的默认路径（相对项目根目录）
+pub const AUDIT_LOG_PATH: &str = ".zhiyun/audit/intents.jsonl";
+
+/// 一条审计记录：谁在何时发起了哪个意图，以及结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub origin: IntentOrigin,
+    pub category: String,
+    /// 意图摘要（不含文件内容等负载）
+    pub intent: String,
+    /// 处理失败或被中间件拒绝时的错误信息
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// `IntentAuditLog::records` 的查询条件，未设置的条件不限制
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub origin: Option<IntentOrigin>,
+    pub category: Option<IntentCategory>,
+    pub since: Option<DateTime<Utc>>,
+    pub failed_only: bool,
+}
+
+impl AuditFilter {
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// 只保留指定发起方（例如某个 Agent Routine）的记录
+    pub fn from(origin: IntentOrigin) -> Self {
+        Self {
+            origin: Some(origin),
+            ..Self::default()
+        }
+    }
+
+    pub fn category(mut self, category: IntentCategory) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn failed(mut self) -> Self {
+        self.failed_only = true;
+        self
+    }
+
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.origin.as_ref().is_none_or(|o| *o == record.origin)
+            && self
+                .category
+                .is_none_or(|c| format!("{:?}", c) == record.category)
+            && self.since.is_none_or(|t| record.timestamp >= t)
+            && (!self.failed_only || record.error.is_some())
+    }
+}
+
+/// 记录每个意图及其发起方的审计中间件
+///
+/// 以全部类别注册到 `IntentDispatcher` 后，处理完成、失败或被其他中间件拒绝的意图都会被记录；
+/// 配置存储后每条新记录以 JSON Lines 追加到日志，重启后经 `load` 继续追加；
+/// 写入失败的记录留在内存中，随下一次写入补齐，失败经 `with_metrics` 配置的统计汇报。
+#[derive(Default)]
+pub struct IntentAuditLog {
+    records: Mutex<Vec<AuditRecord>>,
+    storage: Option<(Arc<dyn StorageProvider>, String)>,
+    metrics: Option<Arc<IntentMetrics>>,
+    /// 已写入日志的记录条数；串行化写入，保证文件内容与内存中的顺序一致
+    persisted: tokio::sync::Mutex<usize>,
+}
+
+impl IntentAuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 持久化到 `AUDIT_LOG_PATH`
+    pub fn with_storage(self, storage: Arc<dyn StorageProvider>) -> Self {
+        self.with_storage_path(storage, AUDIT_LOG_PATH)
+    }
+
+    pub fn with_storage_path(mut self, storage: Arc<dyn StorageProvider>, path: &str) -> Self {
+        self.storage = Some((storage, path.to_string()));
+        self
+    }
+
+    /// 日志写入失败时汇报到 `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<IntentMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// 载入已保存的记录，返回载入的条数（本次运行中已写入日志的记录不重复载入）
+    pub async fn load(&self) -> Result<usize> {
+        let Some((storage, path)) = &self.storage else {
+            return Ok(0);
+        };
+        let mut persisted = self.persisted.lock().await;
+        if !storage.exists(path).await? {
+            return Ok(0);
+        }
+        let content = String::from_utf8(storage.read_file(path).await?)?;
+        let mut loaded = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<serde_json::Result<Vec<AuditRecord>>>()?;
+        let mut records = self.records.lock().unwrap();
+        loaded.retain(|l| !records.iter().any(|r| r.id == l.id));
+        let count = loaded.len();
+        *persisted += count;
+        loaded.append(&mut records);
+        *records = loaded;
+        Ok(count)
+    }
+
+    /// 按条件查询记录（按时间顺序）
+    pub fn records(&self, filter: &AuditFilter) -> Vec<AuditRecord> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| filter.matches(r))
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 追加尚未写入日志的记录
+    async fn persist(&self) -> Result<()> {
+        let Some((storage, path)) = &self.storage else {
+            return Ok(());
+        };
+        let mut persisted = self.persisted.lock().await;
+        let mut buf = Vec::new();
+        let pending = {
+            let records = self.records.lock().unwrap();
+            for record in &records[*persisted..] {
+                serde_json::to_writer(&mut buf, record)?;
+                buf.push(b'\n');
+            }
+            records.len()
+        };
+        if pending > *persisted {
+            storage.append_file(path, &buf).await?;
+            *persisted = pending;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl IntentMiddleware for IntentAuditLog {
+    fn name(&self) -> &str {
+        "audit"
+    }
+
+    async fn after(&self, context: &IntentContext, outcome: &Result<Value>) {
+        let record = AuditRecord {
+            id: context.id,
+            timestamp: context.received_at,
+            origin: context.origin.clone(),
+            category: format!("{:?}", context.category),
+            intent: context.summary.clone(),
+            error: outcome.as_ref().err().map(|e| e.to_string()),
+            duration_ms: (Utc::now() - context.received_at).num_milliseconds().max(0) as u64,
+        };
+        self.records.lock().unwrap().push(record);
+        // 审计写入失败不影响意图本身的结果；内存中的记录在下次写入时补齐
+        if let Err(e) = self.persist().await
+            && let Some(metrics) = &self.metrics
+        {
+            metrics.record_failure(self.name(), &e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::intent::traits::{EditorIntent, SystemIntent};
+    use crate::common::intent::{IntentDispatcher, IntentHandler};
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use anyhow::bail;
+
+    struct Accept;
+
+    #[async_trait]
+    impl IntentHandler for Accept {
+        async fn handle(&self, _intent: SystemIntent) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Agent 不能直接删除文件
+    struct NoAgentDeletes;
+
+    #[async_trait]
+    impl IntentMiddleware for NoAgentDeletes {
+        fn name(&self) -> &str {
+            "no-agent-deletes"
+        }
+
+        async fn before(&self, context: &IntentContext, intent: &SystemIntent) -> Result<()> {
+            if matches!(context.origin, IntentOrigin::Agent { .. })
+                && matches!(
+                    intent,
+                    SystemIntent::Editor(EditorIntent::DeleteFile { .. })
+                )
+            {
+                bail!("agents may not delete files");
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_records_origin_of_every_intent() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let audit = Arc::new(IntentAuditLog::new().with_storage(storage.clone()));
+        let metrics = Arc::new(IntentMetrics::new());
+        let dispatcher = IntentDispatcher::new();
+        dispatcher
+            .register(IntentCategory::Editor, Arc::new(Accept))
+            .await;
+        dispatcher.add_middleware(None, audit.clone()).await;
+        dispatcher.add_middleware(None, metrics.clone()).await;
+        dispatcher
+            .add_middleware(Some(IntentCategory::Editor), Arc::new(NoAgentDeletes))
+            .await;
+
+        let routine = Uuid::new_v4();
+        let open = SystemIntent::Editor(EditorIntent::OpenFile {
+            path: "src/lib.rs".to_string(),
+        });
+        let delete = SystemIntent::Editor(EditorIntent::DeleteFile {
+            path: "src/lib.rs".to_string(),
+        });
+        dispatcher
+            .dispatch_as(IntentOrigin::user("alice"), open.clone())
+            .await
+            .unwrap();
+        dispatcher
+            .dispatch_as(IntentOrigin::agent(routine), open)
+            .await
+            .unwrap();
+        let err = dispatcher
+            .dispatch_as(IntentOrigin::agent(routine), delete.clone())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("agents may not delete files"));
+        dispatcher.dispatch(delete).await.unwrap();
+        // 没有处理器的意图同样被记录
+        assert!(
+            dispatcher
+                .dispatch_as(
+                    IntentOrigin::ui(),
+                    SystemIntent::Agent(crate::agent::AgentIntent::Abort)
+                )
+                .await
+                .is_err()
+        );
+
+        assert_eq!(audit.len(), 5);
+        let by_agent = audit.records(&AuditFilter::from(IntentOrigin::agent(routine)));
+        assert_eq!(
+            by_agent
+                .iter()
+                .map(|r| r.intent.as_str())
+                .collect::<Vec<_>>(),
+            ["OpenFile src/lib.rs", "DeleteFile src/lib.rs"]
+        );
+        assert!(
+            by_agent[1]
+                .error
+                .as_ref()
+                .unwrap()
+                .contains("no-agent-deletes")
+        );
+        assert_eq!(
+            audit
+                .records(&AuditFilter::from(IntentOrigin::user("alice")))
+                .len(),
+            1
+        );
+        assert_eq!(audit.records(&AuditFilter::all().failed()).len(), 2);
+        assert_eq!(
+            audit
+                .records(&AuditFilter::from(IntentOrigin::System).category(IntentCategory::Editor))
+                .len(),
+            1
+        );
+
+        let editor = &metrics.snapshot()["Editor"];
+        assert_eq!((editor.count, editor.failures), (4, 1));
+
+        let reopened = IntentAuditLog::new().with_storage(storage);
+        assert_eq!(reopened.load().await.unwrap(), 5);
+        assert_eq!(
+            reopened.records(&AuditFilter::all()),
+            audit.records(&AuditFilter::all())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_writes_are_reported_and_appended_later() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let metrics = Arc::new(IntentMetrics::new());
+        let audit = Arc::new(
+            IntentAuditLog::new()
+                .with_storage(storage.clone())
+                .with_metrics(metrics.clone()),
+        );
+        let dispatcher = IntentDispatcher::new();
+        dispatcher
+            .register(IntentCategory::Editor, Arc::new(Accept))
+            .await;
+        dispatcher.add_middleware(None, audit.clone()).await;
+        let open = |path: &str| {
+            SystemIntent::Editor(EditorIntent::OpenFile {
+                path: path.to_string(),
+            })
+        };
+
+        // 日志目录被同名文件占用，写入失败
+        std::fs::write(dir.path().join(".zhiyun"), b"").unwrap();
+        dispatcher.dispatch(open("a.rs")).await.unwrap();
+        let failures = metrics.failures();
+        assert_eq!(failures["audit"].count, 1);
+        assert!(!failures["audit"].last_error.is_empty());
+
+        // 恢复后只追加尚未写入的记录，已有内容不被改写
+        std::fs::remove_file(dir.path().join(".zhiyun")).unwrap();
+        dispatcher.dispatch(open("b.rs")).await.unwrap();
+        let first = std::fs::read_to_string(dir.path().join(AUDIT_LOG_PATH)).unwrap();
+        assert_eq!(first.lines().count(), 2);
+        dispatcher.dispatch(open("c.rs")).await.unwrap();
+        let second = std::fs::read_to_string(dir.path().join(AUDIT_LOG_PATH)).unwrap();
+        assert!(second.starts_with(&first));
+        assert_eq!(second.lines().count(), 3);
+        assert_eq!(metrics.failures()["audit"].count, 1);
+
+        // 重新载入不会重复已在内存中的记录
+        assert_eq!(audit.load().await.unwrap(), 0);
+        assert_eq!(audit.len(), 3);
+    }
+}
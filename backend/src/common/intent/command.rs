@@ -0,0 +1,608 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::common::intent::dispatcher::IntentDispatcher;
+use crate::common::intent::middleware::IntentOrigin;
+use crate::common::intent::traits::{AgentIntent, EditorIntent, SystemIntent};
+
+/// 斜杠命令解析与执行过程中的错误。
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CommandError {
+    #[error("Unknown command: /{0}")]
+    UnknownCommand(String),
+
+    #[error("/{command}: missing required argument '{arg}'")]
+    MissingArgument { command: String, arg: String },
+
+    #[error("/{command}: argument '{arg}' expects {expected}, got '{value}'")]
+    InvalidArgument {
+        command: String,
+        arg: String,
+        expected: String,
+        value: String,
+    },
+
+    #[error("/{command}: unexpected argument '{arg}'")]
+    UnexpectedArgument { command: String, arg: String },
+
+    #[error("/{0} requires a selection in the editor")]
+    MissingSelection(String),
+
+    #[error("Unterminated quote in command input")]
+    UnterminatedQuote,
+}
+
+/// 命令参数的类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgKind {
+    String,
+    Integer,
+    Boolean,
+    /// 工作区内的文件路径
+    Path,
+}
+
+impl ArgKind {
+    /// 将原始文本转换为对应类型的值。
+    fn parse(&self, raw: &str) -> Option<Value> {
+        match self {
+            ArgKind::String | ArgKind::Path => Some(Value::String(raw.to_string())),
+            ArgKind::Integer => raw.parse::<i64>().ok().map(Value::from),
+            ArgKind::Boolean => match raw {
+                "true" | "yes" | "on" => Some(Value::Bool(true)),
+                "false" | "no" | "off" => Some(Value::Bool(false)),
+                _ => None,
+            },
+        }
+    }
+
+    fn expected(&self) -> &'static str {
+        match self {
+            ArgKind::String => "a string",
+            ArgKind::Integer => "an integer",
+            ArgKind::Boolean => "a boolean",
+            ArgKind::Path => "a path",
+        }
+    }
+}
+
+/// 命令参数定义。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandArg {
+    pub name: String,
+    pub kind: ArgKind,
+    pub required: bool,
+    pub description: String,
+}
+
+impl CommandArg {
+    pub fn new(name: &str, kind: ArgKind, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            required: false,
+            description: description.to_string(),
+        }
+    }
+
+    /// 将参数标记为必填。
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+}
+
+/// 命令的描述信息，供 UI 展示命令列表与帮助。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandSpec {
+    /// 命令名（不含 `/`）
+    pub name: String,
+    pub description: String,
+    /// 按位置顺序排列的参数
+    pub args: Vec<CommandArg>,
+    /// 是否需要编辑器中的选区
+    pub needs_selection: bool,
+}
+
+impl CommandSpec {
+    pub fn new(name: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            args: Vec::new(),
+            needs_selection: false,
+        }
+    }
+
+    pub fn with_arg(mut self, arg: CommandArg) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    pub fn with_selection(mut self) -> Self {
+        self.needs_selection = true;
+        self
+    }
+
+    /// 用法行，例如 `/goto <line> [column]`。
+    pub fn usage(&self) -> String {
+        let mut usage = format!("/{}", self.name);
+        for arg in &self.args {
+            if arg.required {
+                usage.push_str(&format!(" <{}>", arg.name));
+            } else {
+                usage.push_str(&format!(" [{}]", arg.name));
+            }
+        }
+        usage
+    }
+
+    /// 多行帮助文本：用法、说明与各参数说明。
+    pub fn help(&self) -> String {
+        let mut help = format!("{}\n  {}", self.usage(), self.description);
+        if self.needs_selection {
+            help.push_str("\n  Operates on the current editor selection.");
+        }
+        for arg in &self.args {
+            help.push_str(&format!(
+                "\n  {} ({:?}{}): {}",
+                arg.name,
+                arg.kind,
+                if arg.required { ", required" } else { "" },
+                arg.description
+            ));
+        }
+        help
+    }
+}
+
+/// 编辑器选区。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selection {
+    pub path: String,
+    pub text: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// 执行命令时的编辑器上下文。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandContext {
+    pub active_file: Option<String>,
+    pub selection: Option<Selection>,
+}
+
+/// 解析后的命令调用。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedCommand {
+    pub name: String,
+    /// 参数名 -> 已转换类型的值
+    pub args: Map<String, Value>,
+}
+
+/// 由解析后的参数与上下文构造意图。
+pub type IntentBuilder = Arc<dyn Fn(&ParsedCommand, &CommandContext) -> SystemIntent + Send + Sync>;
+
+struct RegisteredCommand {
+    spec: CommandSpec,
+    build: IntentBuilder,
+}
+
+/// 斜杠命令注册表。
+///
+/// 将聊天输入中的 `/name arg --key value` 解析为带类型的参数，
+/// 再映射为 `SystemIntent` 交给 `IntentDispatcher` 执行。
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: BTreeMap<String, RegisteredCommand>,
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册内置命令：`/test`、`/diff`、`/explain`。
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(
+            CommandSpec::new("test", "Run the project's tests")
+                .with_arg(CommandArg::new(
+                    "filter",
+                    ArgKind::String,
+                    "Only run tests whose name contains this text",
+                ))
+                .with_arg(CommandArg::new(
+                    "verbose",
+                    ArgKind::Boolean,
+                    "Show the full test output",
+                )),
+            Arc::new(|cmd, _| {
+                SystemIntent::Agent(AgentIntent::RunTests {
+                    filter: cmd
+                        .args
+                        .get("filter")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    verbose: cmd
+                        .args
+                        .get("verbose")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                })
+            }),
+        );
+        registry.register(
+            CommandSpec::new("diff", "Show unsaved changes").with_arg(CommandArg::new(
+                "path",
+                ArgKind::Path,
+                "Limit the diff to this file (defaults to the active file)",
+            )),
+            Arc::new(|cmd, ctx| {
+                let path = cmd
+                    .args
+                    .get("path")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .or_else(|| ctx.active_file.clone());
+                SystemIntent::Editor(EditorIntent::Diff { path })
+            }),
+        );
+        registry.register(
+            CommandSpec::new("explain", "Explain the selected code")
+                .with_arg(CommandArg::new(
+                    "question",
+                    ArgKind::String,
+                    "What to focus the explanation on",
+                ))
+                .with_selection(),
+            // `resolve` 已确认存在选区
+            Arc::new(|cmd, ctx| {
+                SystemIntent::Agent(AgentIntent::Explain {
+                    selection: ctx.selection.clone().expect("selection checked by resolve"),
+                    question: cmd
+                        .args
+                        .get("question")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                })
+            }),
+        );
+        registry
+    }
+
+    /// 注册命令；同名命令会被替换。
+    pub fn register(&mut self, spec: CommandSpec, build: IntentBuilder) {
+        self.commands
+            .insert(spec.name.clone(), RegisteredCommand { spec, build });
+    }
+
+    /// 列出全部命令，按名称排序。
+    pub fn list(&self) -> Vec<&CommandSpec> {
+        self.commands.values().map(|c| &c.spec).collect()
+    }
+
+    /// 名称以 `prefix` 开头的命令，用于输入 `/` 时的提示。
+    pub fn suggest(&self, prefix: &str) -> Vec<&CommandSpec> {
+        let prefix = prefix.trim_start_matches('/');
+        self.commands
+            .values()
+            .filter(|c| c.spec.name.starts_with(prefix))
+            .map(|c| &c.spec)
+            .collect()
+    }
+
+    pub fn help(&self, name: &str) -> Option<String> {
+        self.commands.get(name).map(|c| c.spec.help())
+    }
+
+    /// 解析聊天输入；不是斜杠命令时返回 `Ok(None)`。
+    ///
+    /// 位置参数按定义顺序赋值，也可使用 `--name value` 或 `--name=value`；
+    /// 布尔参数单独出现 `--name` 时视为 `true`。
+    pub fn parse(&self, input: &str) -> Result<Option<ParsedCommand>, CommandError> {
+        let Some(rest) = input.trim_start().strip_prefix('/') else {
+            return Ok(None);
+        };
+        let mut tokens = tokenize(rest)?.into_iter().peekable();
+        let Some(name) = tokens.next() else {
+            return Ok(None);
+        };
+        let command = self
+            .commands
+            .get(&name)
+            .ok_or_else(|| CommandError::UnknownCommand(name.clone()))?;
+        let spec = &command.spec;
+
+        let unexpected = |arg: &str| CommandError::UnexpectedArgument {
+            command: name.clone(),
+            arg: arg.to_string(),
+        };
+        let mut raw: Vec<(&CommandArg, String)> = Vec::new();
+        let mut positional = spec.args.iter();
+        while let Some(token) = tokens.next() {
+            if let Some(flag) = token.strip_prefix("--") {
+                let (key, inline) = match flag.split_once('=') {
+                    Some((key, value)) => (key, Some(value.to_string())),
+                    None => (flag, None),
+                };
+                let arg = spec
+                    .args
+                    .iter()
+                    .find(|a| a.name == key)
+                    .ok_or_else(|| unexpected(&token))?;
+                let value = match inline {
+                    Some(value) => value,
+                    None if arg.kind == ArgKind::Boolean
+                        && tokens.peek().is_none_or(|t| t.starts_with("--")) =>
+                    {
+                        "true".to_string()
+                    }
+                    None => tokens.next().ok_or_else(|| CommandError::MissingArgument {
+                        command: name.clone(),
+                        arg: arg.name.clone(),
+                    })?,
+                };
+                raw.push((arg, value));
+            } else {
+                let arg = positional
+                    .find(|a| !raw.iter().any(|(r, _)| r.name == a.name))
+                    .ok_or_else(|| unexpected(&token))?;
+                raw.push((arg, token));
+            }
+        }
+
+        let mut args = Map::new();
+        for (arg, value) in raw {
+            let parsed = arg
+                .kind
+                .parse(&value)
+                .ok_or_else(|| CommandError::InvalidArgument {
+                    command: name.clone(),
+                    arg: arg.name.clone(),
+                    expected: arg.kind.expected().to_string(),
+                    value: value.clone(),
+                })?;
+            args.insert(arg.name.clone(), parsed);
+        }
+        if let Some(missing) = spec
+            .args
+            .iter()
+            .find(|a| a.required && !args.contains_key(&a.name))
+        {
+            return Err(CommandError::MissingArgument {
+                command: name,
+                arg: missing.name.clone(),
+            });
+        }
+        Ok(Some(ParsedCommand { name, args }))
+    }
+
+    /// 将聊天输入解析为意图；不是斜杠命令时返回 `Ok(None)`。
+    pub fn resolve(
+        &self,
+        input: &str,
+        context: &CommandContext,
+    ) -> Result<Option<SystemIntent>, CommandError> {
+        let Some(parsed) = self.parse(input)? else {
+            return Ok(None);
+        };
+        let command = &self.commands[&parsed.name];
+        if command.spec.needs_selection && context.selection.is_none() {
+            return Err(CommandError::MissingSelection(parsed.name));
+        }
+        Ok(Some((command.build)(&parsed, context)))
+    }
+
+    /// 解析并通过分发器执行命令；输入不是斜杠命令时返回 `Ok(false)`。
+    pub async fn execute(
+        &self,
+        input: &str,
+        context: &CommandContext,
+        dispatcher: &IntentDispatcher,
+    ) -> anyhow::Result<bool> {
+        match self.resolve(input, context)? {
+            Some(intent) => {
+                dispatcher.dispatch_as(IntentOrigin::ui(), intent).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// 按空白切分，支持单引号与双引号包裹的参数。
+fn tokenize(input: &str) -> Result<Vec<String>, CommandError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote: Option<char> = None;
+    for c in input.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_token = true;
+            }
+            None if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(CommandError::UnterminatedQuote);
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::intent::handler::IntentHandler;
+    use crate::common::intent::traits::IntentCategory;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use tokio::sync::Mutex;
+
+    fn call_tool(name: &str, args: Value) -> SystemIntent {
+        SystemIntent::Agent(AgentIntent::CallTool {
+            name: name.to_string(),
+            args: args.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_parse_typed_arguments() {
+        let registry = CommandRegistry::with_defaults();
+        assert_eq!(registry.parse("just chatting").unwrap(), None);
+
+        let parsed = registry
+            .parse(r#"/test "parser tests" --verbose"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            Value::Object(parsed.args),
+            json!({ "filter": "parser tests", "verbose": true })
+        );
+        let parsed = registry.parse("/test --verbose=no smoke").unwrap().unwrap();
+        assert_eq!(
+            Value::Object(parsed.args),
+            json!({ "filter": "smoke", "verbose": false })
+        );
+
+        assert_eq!(
+            registry.parse("/test --verbose=maybe"),
+            Err(CommandError::InvalidArgument {
+                command: "test".to_string(),
+                arg: "verbose".to_string(),
+                expected: "a boolean".to_string(),
+                value: "maybe".to_string(),
+            })
+        );
+        assert_eq!(
+            registry.parse("/deploy"),
+            Err(CommandError::UnknownCommand("deploy".to_string()))
+        );
+        assert!(matches!(
+            registry.parse("/diff a.rs b.rs"),
+            Err(CommandError::UnexpectedArgument { arg, .. }) if arg == "b.rs"
+        ));
+        assert_eq!(
+            registry.parse("/test 'open"),
+            Err(CommandError::UnterminatedQuote)
+        );
+    }
+
+    #[test]
+    fn test_discovery_and_help() {
+        let mut registry = CommandRegistry::with_defaults();
+        registry.register(
+            CommandSpec::new("goto", "Jump to a line")
+                .with_arg(CommandArg::new("line", ArgKind::Integer, "Line number").required()),
+            Arc::new(|cmd, _| call_tool("goto", Value::Object(cmd.args.clone()))),
+        );
+        let names: Vec<&str> = registry.list().iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["diff", "explain", "goto", "test"]);
+        assert_eq!(registry.suggest("/te").len(), 1);
+        assert_eq!(
+            registry.help("goto").unwrap(),
+            "/goto <line>\n  Jump to a line\n  line (Integer, required): Line number"
+        );
+        assert_eq!(
+            registry.parse("/goto"),
+            Err(CommandError::MissingArgument {
+                command: "goto".to_string(),
+                arg: "line".to_string(),
+            })
+        );
+        assert_eq!(
+            registry.parse("/goto 42").unwrap().unwrap().args["line"],
+            json!(42)
+        );
+    }
+
+    struct RecordingHandler(Mutex<Vec<SystemIntent>>);
+
+    #[async_trait]
+    impl IntentHandler for RecordingHandler {
+        async fn handle(&self, intent: SystemIntent) -> anyhow::Result<()> {
+            self.0.lock().await.push(intent);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_and_execute_with_context() {
+        let registry = CommandRegistry::with_defaults();
+        let mut context = CommandContext {
+            active_file: Some("src/lib.rs".to_string()),
+            selection: None,
+        };
+
+        assert!(matches!(
+            registry.resolve("/diff", &context).unwrap().unwrap(),
+            SystemIntent::Editor(EditorIntent::Diff { path: Some(p) }) if p == "src/lib.rs"
+        ));
+        assert!(matches!(
+            registry.resolve("/test smoke", &context).unwrap().unwrap(),
+            SystemIntent::Agent(AgentIntent::RunTests { filter: Some(f), verbose: false })
+                if f == "smoke"
+        ));
+
+        assert_eq!(
+            registry.resolve("/explain", &context).unwrap_err(),
+            CommandError::MissingSelection("explain".to_string())
+        );
+        context.selection = Some(Selection {
+            path: "src/lib.rs".to_string(),
+            text: "fn main() {}".to_string(),
+            start_line: 1,
+            end_line: 1,
+        });
+
+        let handler = Arc::new(RecordingHandler(Mutex::new(Vec::new())));
+        let dispatcher = IntentDispatcher::new();
+        dispatcher
+            .register(IntentCategory::Agent, handler.clone())
+            .await;
+        assert!(
+            registry
+                .execute("/explain 'why no args?'", &context, &dispatcher)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !registry
+                .execute("hello", &context, &dispatcher)
+                .await
+                .unwrap()
+        );
+
+        let recorded = handler.0.lock().await.clone();
+        assert_eq!(recorded.len(), 1);
+        let SystemIntent::Agent(AgentIntent::Explain {
+            selection,
+            question,
+        }) = &recorded[0]
+        else {
+            panic!("unexpected intent {:?}", recorded[0]);
+        };
+        assert_eq!(selection.text, "fn main() {}");
+        assert_eq!(question.as_deref(), Some("why no args?"));
+    }
+}
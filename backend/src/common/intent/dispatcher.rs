@@ -1,10 +1,75 @@
 use anyhow::Result;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::future::{Future, IntoFuture};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, RwLock, mpsc, watch};
+use uuid::Uuid;
 
+use crate::common::endpoint::redaction::Mask;
+use crate::common::event::{BackendEvent, EventBus};
 use crate::common::intent::handler::IntentHandler;
+use crate::common::intent::journal::{IntentJournal, JournalEntry};
+use crate::common::intent::middleware::{IntentContext, IntentMiddleware, IntentOrigin};
+use crate::common::intent::queue::{DEFAULT_QUEUE_CAPACITY, QueueKey, QueuedBatch};
+use crate::common::intent::subscription::Subscriber;
 use crate::common::intent::traits::{IntentCategory, SystemIntent};
+use crate::common::lifecycle::shutdown::{ShutdownHook, ShutdownPhase};
+
+/// 经 `IntentDispatcher::submit` 提交的意图的关联 ID。
+pub type IntentId = Uuid;
+
+/// 处理结果；错误以消息保存，以便多个等待方共享
+type Outcome = Option<std::result::Result<Value, String>>;
+
+/// 注册的中间件及其适用的类别（`None` 为全部类别）
+type Registered = (Option<IntentCategory>, Arc<dyn IntentMiddleware>);
+
+/// 已提交意图的响应，可按处理器输出的类型 `await`。
+///
+/// 可以克隆后交给多个等待方；处理尚未完成时也可经 `IntentDispatcher::response`
+/// 按关联 ID 重新取得。
+pub struct IntentResponse<T> {
+    pub id: IntentId,
+    outcome: watch::Receiver<Outcome>,
+    _output: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for IntentResponse<T> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            outcome: self.outcome.clone(),
+            _output: PhantomData,
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Send + 'static> IntoFuture for IntentResponse<T> {
+    type Output = Result<T>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Result<T>> + Send>>;
+
+    fn into_future(mut self) -> Self::IntoFuture {
+        Box::pin(async move {
+            let outcome = self
+                .outcome
+                .wait_for(Option::is_some)
+                .await
+                .map_err(|_| anyhow::anyhow!("Intent {} was dropped before completing", self.id))?
+                .clone();
+            match outcome {
+                Some(Ok(value)) => Ok(serde_json::from_value(value)?),
+                Some(Err(error)) => Err(anyhow::anyhow!(error)),
+                None => unreachable!("wait_for returned an unresolved outcome"),
+            }
+        })
+    }
+}
 
 /// 意图分发器。
 ///
@@ -15,6 +80,28 @@ use crate::common::intent::traits::{IntentCategory, SystemIntent};
 pub struct IntentDispatcher {
     /// 处理器注册表，按类别路由。
     handlers: RwLock<HashMap<IntentCategory, Arc<dyn IntentHandler>>>,
+    /// 是否仍接收新的意图（关闭时置为 false）。
+    accepting: AtomicBool,
+    /// 正在处理中的意图数量。
+    in_flight: AtomicUsize,
+    /// 最后一个处理中的意图完成时通知。
+    idle: Notify,
+    /// 分发完成后向其发布 `IntentDispatched` 事件。
+    events: Option<Arc<EventBus>>,
+    /// 按分发顺序记录意图及其结果（录制/回放）。
+    journal: Option<Arc<IntentJournal>>,
+    /// 意图摘要与错误信息在交给中间件、日志与事件前应用的掩码。
+    mask: Option<Arc<dyn Mask>>,
+    /// 经 `submit` 提交、尚未处理完成的意图。
+    pending: Mutex<HashMap<IntentId, watch::Receiver<Outcome>>>,
+    /// 按注册顺序排列的中间件。
+    middlewares: RwLock<Vec<Registered>>,
+    /// 按会话/Routine 划分的 FIFO 队列（见 `enqueue`）。
+    pub(super) queues: Mutex<HashMap<QueueKey, mpsc::Sender<QueuedBatch>>>,
+    /// 每个队列最多积压的批次数。
+    pub(super) queue_capacity: usize,
+    /// 意图结果的订阅方（见 `subscribe`）。
+    pub(super) subscribers: Mutex<Vec<Subscriber>>,
 }
 
 impl Default for IntentDispatcher {
@@ -29,6 +116,63 @@ impl IntentDispatcher {
     pub fn new() -> Self {
         Self {
             handlers: RwLock::new(HashMap::new()),
+            accepting: AtomicBool::new(true),
+            in_flight: AtomicUsize::new(0),
+            idle: Notify::new(),
+            events: None,
+            journal: None,
+            mask: None,
+            pending: Mutex::new(HashMap::new()),
+            middlewares: RwLock::new(Vec::new()),
+            queues: Mutex::new(HashMap::new()),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 将每次分发的结果发布到事件总线。
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 将每次分发的结果记录到意图日志。
+    pub fn with_journal(mut self, journal: Arc<IntentJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// 对意图摘要与错误信息应用掩码（如项目密钥），
+    /// 审计日志、意图日志、事件与调用方看到的都是掩码后的文本。
+    pub fn with_mask(mut self, mask: Arc<dyn Mask>) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// 设置每个队列最多积压的批次数，超出时 `enqueue` 等待、`try_enqueue` 报错。
+    pub fn with_queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity.max(1);
+        self
+    }
+
+    /// 停止接收新的意图，已在处理中的意图不受影响。
+    pub fn close(&self) {
+        self.accepting.store(false, Ordering::SeqCst);
+    }
+
+    /// 是否仍接收新的意图。
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.load(Ordering::SeqCst)
+    }
+
+    /// 等待所有处理中的意图完成。
+    pub async fn wait_idle(&self) {
+        loop {
+            let notified = self.idle.notified();
+            if self.in_flight.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            notified.await;
         }
     }
 
@@ -42,6 +186,17 @@ impl IntentDispatcher {
         handlers.insert(category, handler);
     }
 
+    /// 注册一个中间件。
+    ///
+    /// `category` 为 `None` 时作用于全部类别，否则只作用于该类别的意图。
+    pub async fn add_middleware(
+        &self,
+        category: Option<IntentCategory>,
+        middleware: Arc<dyn IntentMiddleware>,
+    ) {
+        self.middlewares.write().await.push((category, middleware));
+    }
+
     /// 分发一个系统意图。
     ///
     /// 此方法会查找与意图类别匹配的处理器，并异步调用其 `handle` 方法。
@@ -53,6 +208,161 @@ impl IntentDispatcher {
     /// # 返回
     /// - `Result<()>`: 分发及处理成功返回 `Ok(())`，若无对应处理器或处理出错则返回 `Err`。
     pub async fn dispatch(&self, intent: SystemIntent) -> Result<()> {
+        self.dispatch_as(IntentOrigin::System, intent).await
+    }
+
+    /// 以指定发起方分发意图（中间件与审计日志据此区分界面操作与 Agent）。
+    pub async fn dispatch_as(&self, origin: IntentOrigin, intent: SystemIntent) -> Result<()> {
+        self.run(Uuid::new_v4(), origin, intent).await.map(|_| ())
+    }
+
+    /// 分发一个意图并返回处理器的输出（见 `IntentHandler::respond`）。
+    ///
+    /// 输出按 `T` 反序列化，例如打开文件时取得 `Uuid` 类型的 Tab ID。
+    pub async fn request<T: DeserializeOwned>(&self, intent: SystemIntent) -> Result<T> {
+        self.request_as(IntentOrigin::System, intent).await
+    }
+
+    /// 以指定发起方分发意图并返回处理器的输出。
+    pub async fn request_as<T: DeserializeOwned>(
+        &self,
+        origin: IntentOrigin,
+        intent: SystemIntent,
+    ) -> Result<T> {
+        let value = self.run(Uuid::new_v4(), origin, intent).await?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// 在后台分发意图，立即返回带关联 ID 的响应。
+    ///
+    /// 调用方可以先继续其他工作，之后再 `await` 响应，或把关联 ID 交给其他组件等待。
+    pub fn submit<T>(self: &Arc<Self>, intent: SystemIntent) -> IntentResponse<T> {
+        self.submit_as(IntentOrigin::System, intent)
+    }
+
+    /// 以指定发起方在后台分发意图；关联 ID 同时作为中间件上下文的 ID。
+    pub fn submit_as<T>(
+        self: &Arc<Self>,
+        origin: IntentOrigin,
+        intent: SystemIntent,
+    ) -> IntentResponse<T> {
+        let id = Uuid::new_v4();
+        let (tx, outcome) = watch::channel(None);
+        self.pending.lock().unwrap().insert(id, outcome.clone());
+        let dispatcher = self.clone();
+        tokio::spawn(async move {
+            let result = dispatcher
+                .run(id, origin, intent)
+                .await
+                .map_err(|e| e.to_string());
+            dispatcher.pending.lock().unwrap().remove(&id);
+            let _ = tx.send(Some(result));
+        });
+        IntentResponse {
+            id,
+            outcome,
+            _output: PhantomData,
+        }
+    }
+
+    /// 按关联 ID 取得仍在处理中的意图的响应。
+    pub fn response<T>(&self, id: IntentId) -> Option<IntentResponse<T>> {
+        self.pending
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|outcome| IntentResponse {
+                id,
+                outcome: outcome.clone(),
+                _output: PhantomData,
+            })
+    }
+
+    pub(super) async fn run(
+        &self,
+        id: IntentId,
+        origin: IntentOrigin,
+        intent: SystemIntent,
+    ) -> Result<Value> {
+        // 先计数再检查：与 `close` 交错时，关闭方的 `wait_idle` 一定能看到这次处理
+        let _guard = InFlight::enter(self);
+        if !self.is_accepting() {
+            return Err(anyhow::anyhow!("Dispatcher is shutting down"));
+        }
+        let context = IntentContext {
+            id,
+            origin,
+            category: intent.category(),
+            summary: self.masked(intent.summary()),
+            received_at: chrono::Utc::now(),
+        };
+        let middlewares: Vec<Arc<dyn IntentMiddleware>> = self
+            .middlewares
+            .read()
+            .await
+            .iter()
+            .filter(|(category, _)| category.is_none_or(|c| c == context.category))
+            .map(|(_, middleware)| middleware.clone())
+            .collect();
+        let mut result = Ok(Value::Null);
+        for middleware in &middlewares {
+            if let Err(e) = middleware.before(&context, &intent).await {
+                result = Err(anyhow::anyhow!("Rejected by {}: {}", middleware.name(), e));
+                break;
+            }
+        }
+        let recipients = self.recipients(&intent);
+        if result.is_ok() {
+            result = self.route(intent).await;
+        }
+        if let Err(e) = &result {
+            let text = e.to_string();
+            let masked = self.masked(text.clone());
+            if masked != text {
+                result = Err(anyhow::anyhow!(masked));
+            }
+        }
+        for middleware in middlewares.iter().rev() {
+            middleware.after(&context, &result).await;
+        }
+        if let Some(recipients) = recipients {
+            let output = match &result {
+                Ok(value) => Ok(value.clone()),
+                Err(e) => Err(e.to_string()),
+            };
+            recipients.publish(context.id, context.origin.clone(), output);
+        }
+
+        let described = (self.events.is_some() || self.journal.is_some())
+            .then(|| (format!("{:?}", context.category), context.summary));
+        if let Some((category, intent)) = described {
+            let error = result.as_ref().err().map(|e| e.to_string());
+            if let Some(journal) = &self.journal {
+                journal.record(JournalEntry {
+                    category: category.clone(),
+                    intent: intent.clone(),
+                    error: error.clone(),
+                });
+            }
+            if let Some(events) = &self.events {
+                events.emit(BackendEvent::IntentDispatched {
+                    category,
+                    intent,
+                    error,
+                });
+            }
+        }
+        result
+    }
+
+    fn masked(&self, text: String) -> String {
+        match &self.mask {
+            Some(mask) => mask.mask(&text),
+            None => text,
+        }
+    }
+
+    async fn route(&self, intent: SystemIntent) -> Result<Value> {
         let category = intent.category();
         let handler = {
             let handlers = self.handlers.read().await;
@@ -61,7 +371,7 @@ impl IntentDispatcher {
 
         if let Some(handler) = handler {
             // 在当前异步上下文中直接 await 处理器的执行，等待其返回结果
-            handler.handle(intent).await
+            handler.respond(intent).await
         } else {
             Err(anyhow::anyhow!(
                 "No handler registered for category: {:?}",
@@ -70,3 +380,138 @@ impl IntentDispatcher {
         }
     }
 }
+
+/// 处理中的意图计数，离开作用域（包括 future 被取消）时减一并在空闲时通知
+struct InFlight<'a>(&'a IntentDispatcher);
+
+impl<'a> InFlight<'a> {
+    fn enter(dispatcher: &'a IntentDispatcher) -> Self {
+        dispatcher.in_flight.fetch_add(1, Ordering::SeqCst);
+        Self(dispatcher)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+#[async_trait]
+impl ShutdownHook for IntentDispatcher {
+    fn name(&self) -> &str {
+        "intent-dispatcher"
+    }
+
+    fn phase(&self) -> ShutdownPhase {
+        ShutdownPhase::StopIntake
+    }
+
+    /// 拒绝新的意图，并等待处理中的意图完成。
+    async fn shutdown(&self) -> Result<()> {
+        self.close();
+        self.wait_idle().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::intent::traits::EditorIntent;
+    use serde_json::json;
+    use tokio::sync::Semaphore;
+
+    /// 打开文件时返回路径长度，等到放行后才完成
+    struct Lengths(Semaphore);
+
+    #[async_trait]
+    impl IntentHandler for Lengths {
+        async fn handle(&self, intent: SystemIntent) -> Result<()> {
+            self.respond(intent).await.map(|_| ())
+        }
+
+        async fn respond(&self, intent: SystemIntent) -> Result<Value> {
+            let _permit = self.0.acquire().await?;
+            match intent {
+                SystemIntent::Editor(EditorIntent::OpenFile { path }) if !path.is_empty() => {
+                    Ok(json!({ "path": path, "length": path.len() }))
+                }
+                _ => Err(anyhow::anyhow!("nothing to open")),
+            }
+        }
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Opened {
+        path: String,
+        length: usize,
+    }
+
+    fn open(path: &str) -> SystemIntent {
+        SystemIntent::Editor(EditorIntent::OpenFile {
+            path: path.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_request_and_submit_return_handler_output() {
+        let dispatcher = Arc::new(IntentDispatcher::new());
+        let handler = Arc::new(Lengths(Semaphore::new(0)));
+        dispatcher
+            .register(IntentCategory::Editor, handler.clone())
+            .await;
+
+        // 提交后立即得到关联 ID，处理完成前可按 ID 找回响应
+        let first = dispatcher.submit::<Opened>(open("src/lib.rs"));
+        let failed = dispatcher.submit::<Opened>(open(""));
+        assert_ne!(first.id, failed.id);
+        let first_id = first.id;
+        let again = dispatcher.response::<Opened>(first_id).unwrap();
+        handler.0.add_permits(3);
+
+        let opened = first.await.unwrap();
+        assert_eq!((opened.path.as_str(), opened.length), ("src/lib.rs", 10));
+        assert_eq!(again.await.unwrap().length, 10);
+        assert!(
+            failed
+                .await
+                .unwrap_err()
+                .to_string()
+                .contains("nothing to open")
+        );
+        assert!(dispatcher.response::<Opened>(first_id).is_none());
+
+        let opened: Opened = dispatcher.request(open("a.rs")).await.unwrap();
+        assert_eq!(opened.length, 4);
+        // 不关心输出时 `dispatch` 照常可用
+        dispatcher.dispatch(open("b.rs")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_intent_does_not_block_shutdown() {
+        let dispatcher = Arc::new(IntentDispatcher::new());
+        dispatcher
+            .register(IntentCategory::Editor, Arc::new(Lengths(Semaphore::new(0))))
+            .await;
+
+        let blocked = tokio::spawn({
+            let dispatcher = dispatcher.clone();
+            async move { dispatcher.dispatch(open("a.rs")).await }
+        });
+        while dispatcher.in_flight.load(Ordering::SeqCst) == 0 {
+            tokio::task::yield_now().await;
+        }
+        // 处理中的 future 被丢弃后计数照常归零，关闭不会一直等待
+        blocked.abort();
+        assert!(blocked.await.unwrap_err().is_cancelled());
+        tokio::time::timeout(std::time::Duration::from_secs(5), dispatcher.shutdown())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(dispatcher.dispatch(open("b.rs")).await.is_err());
+        assert_eq!(dispatcher.in_flight.load(Ordering::SeqCst), 0);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::common::intent::traits::SystemIntent;
 use anyhow::Result;
 use async_trait::async_trait;
+use serde_json::Value;
 
 /// 意图处理器接口。
 ///
@@ -16,4 +17,12 @@ pub trait IntentHandler: Send + Sync {
     /// # 返回
     /// - `Result<()>`: 处理成功返回 `Ok(())`，否则返回具体错误。
     async fn handle(&self, intent: SystemIntent) -> Result<()>;
+
+    /// 处理意图并返回处理结果（请求-响应模式，见 `IntentDispatcher::request`）。
+    ///
+    /// 默认调用 `handle`，结果为 `Value::Null`；能产出结果的处理器
+    /// （例如打开文件后返回 Tab ID）覆盖此方法，并让 `handle` 丢弃结果。
+    async fn respond(&self, intent: SystemIntent) -> Result<Value> {
+        self.handle(intent).await.map(|_| Value::Null)
+    }
 }
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// 意图日志中的一条记录。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// 意图类别。
+    pub category: String,
+    /// 意图摘要（见 `SystemIntent::summary`）。
+    pub intent: String,
+    /// 处理失败时的错误信息。
+    pub error: Option<String>,
+}
+
+/// 意图日志。
+///
+/// 按分发顺序记录每个意图及其处理结果，供录制/回放使用：
+/// 录制时作为运行记录保存；回放时载入录制的日志作为预期，
+/// 并通过 `divergence` 找出回放与录制第一次不一致的位置。
+#[derive(Debug, Default)]
+pub struct IntentJournal {
+    entries: Mutex<Vec<JournalEntry>>,
+    expected: Option<Vec<JournalEntry>>,
+}
+
+impl IntentJournal {
+    /// 创建一个空的日志。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建一个以录制日志为预期的日志，用于回放。
+    pub fn expecting(expected: Vec<JournalEntry>) -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+            expected: Some(expected),
+        }
+    }
+
+    /// 追加一条记录。
+    pub fn record(&self, entry: JournalEntry) {
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    /// 已记录的全部条目。
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// 回放时第一个与预期不一致的位置；一致或未设置预期时返回 `None`。
+    ///
+    /// 回放尚未结束时，仅比较已记录的前缀。
+    pub fn divergence(&self) -> Option<usize> {
+        let expected = self.expected.as_ref()?;
+        let entries = self.entries.lock().unwrap();
+        if entries.len() > expected.len() {
+            return Some(expected.len());
+        }
+        entries.iter().zip(expected).position(|(a, b)| a != b)
+    }
+}
@@ -0,0 +1,136 @@
+use crate::common::intent::dispatcher::IntentId;
+use crate::common::intent::traits::{IntentCategory, SystemIntent};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// 意图的发起方
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IntentOrigin {
+    /// 用户在界面上的操作
+    Ui {
+        #[serde(default)]
+        user: Option<String>,
+    },
+    /// Agent Routine
+    Agent { routine_id: Uuid },
+    /// 后端内部（未指明发起方的 `dispatch` 也归为此类）
+    System,
+}
+
+impl IntentOrigin {
+    pub fn ui() -> Self {
+        IntentOrigin::Ui { user: None }
+    }
+
+    pub fn user(user: &str) -> Self {
+        IntentOrigin::Ui {
+            user: Some(user.to_string()),
+        }
+    }
+
+    pub fn agent(routine_id: Uuid) -> Self {
+        IntentOrigin::Agent { routine_id }
+    }
+}
+
+/// 一次分发的上下文，中间件的前置与后置钩子都会收到
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntentContext {
+    pub id: IntentId,
+    pub origin: IntentOrigin,
+    pub category: IntentCategory,
+    /// 意图摘要（见 `SystemIntent::summary`）
+    pub summary: String,
+    pub received_at: DateTime<Utc>,
+}
+
+/// 意图中间件
+///
+/// 经 `IntentDispatcher::add_middleware` 按类别（或全部类别）注册。`before` 按注册顺序调用，
+/// 返回错误时拒绝该意图、不再到达处理器；`after` 按相反顺序调用，
+/// 无论意图被处理、处理失败还是被拒绝都会收到最终结果。
+#[async_trait]
+pub trait IntentMiddleware: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn before(&self, _context: &IntentContext, _intent: &SystemIntent) -> Result<()> {
+        Ok(())
+    }
+
+    async fn after(&self, _context: &IntentContext, _outcome: &Result<Value>) {}
+}
+
+/// 某一类别意图的统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntentStats {
+    pub count: u64,
+    /// 处理失败或被拒绝的次数
+    pub failures: u64,
+    pub total_ms: u64,
+    pub max_ms: u64,
+}
+
+/// 某个中间件自身操作（如写入审计日志）的失败
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MiddlewareFailures {
+    pub count: u64,
+    /// 最近一次失败的错误信息
+    pub last_error: String,
+}
+
+/// 统计各类别意图的数量、失败数与耗时的中间件
+///
+/// 其他中间件的内部失败不影响意图结果，经 `record_failure` 汇报到这里。
+#[derive(Default)]
+pub struct IntentMetrics {
+    stats: Mutex<BTreeMap<String, IntentStats>>,
+    failures: Mutex<BTreeMap<String, MiddlewareFailures>>,
+}
+
+impl IntentMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按类别名称排序的统计快照
+    pub fn snapshot(&self) -> BTreeMap<String, IntentStats> {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// 记录名为 `middleware` 的中间件的一次内部失败
+    pub fn record_failure(&self, middleware: &str, error: &anyhow::Error) {
+        let mut failures = self.failures.lock().unwrap();
+        let entry = failures.entry(middleware.to_string()).or_default();
+        entry.count += 1;
+        entry.last_error = error.to_string();
+    }
+
+    /// 按中间件名称排序的内部失败快照
+    pub fn failures(&self) -> BTreeMap<String, MiddlewareFailures> {
+        self.failures.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl IntentMiddleware for IntentMetrics {
+    fn name(&self) -> &str {
+        "metrics"
+    }
+
+    async fn after(&self, context: &IntentContext, outcome: &Result<Value>) {
+        let elapsed = (Utc::now() - context.received_at).num_milliseconds().max(0) as u64;
+        let mut stats = self.stats.lock().unwrap();
+        let entry = stats.entry(format!("{:?}", context.category)).or_default();
+        entry.count += 1;
+        entry.failures += outcome.is_err() as u64;
+        entry.total_ms += elapsed;
+        entry.max_ms = entry.max_ms.max(elapsed);
+    }
+}
@@ -4,16 +4,39 @@
 //! 主要组件包括：
 //! - `types`: 定义了系统中所有的意图类型及其分类。
 //! - `handler`: 定义了处理意图的统一接口。
-//! - `dispatcher`: 实现了意图的分发路由逻辑。
+//! - `dispatcher`: 实现了意图的分发路由逻辑，以及返回处理器输出、带关联 ID 的请求-响应模式。
+//! - `queue`: 按会话/Routine 划分的 FIFO 队列，保证同一批意图连续处理并提供背压。
+//! - `subscription`: 按类别或条件订阅意图的处理结果，供前端、索引等组件响应意图而无需分发器逐一路由。
+//! - `journal`: 按分发顺序记录意图及其结果，用于录制/回放 Agent 运行。
+//! - `middleware`: 按类别注册的前置/后置钩子（权限检查、指标等），以及意图的发起方。
+//! - `audit`: 持久化的审计日志，记录每个意图由哪个界面用户或 Agent Routine 发起。
+//! - `command`: 将聊天输入中的斜杠命令解析为带类型的参数并映射为意图。
 //!
 //! 该模块的设计目标是支持智能体（Agent）和 UI 操作发出统一的意图，
 //! 并通过异步等待机制确保操作执行的顺序性和一致性。
 
+pub mod audit;
+pub mod command;
 pub mod dispatcher;
 pub mod handler;
+pub mod journal;
+pub mod middleware;
+pub mod queue;
+pub mod subscription;
 pub mod traits;
 
 // 重新导出常用类型，方便外部调用
-pub use dispatcher::IntentDispatcher;
+pub use audit::{AuditFilter, AuditRecord, IntentAuditLog};
+pub use command::{
+    ArgKind, CommandArg, CommandContext, CommandError, CommandRegistry, CommandSpec, IntentBuilder,
+    ParsedCommand, Selection,
+};
+pub use dispatcher::{IntentDispatcher, IntentId, IntentResponse};
 pub use handler::IntentHandler;
+pub use journal::{IntentJournal, JournalEntry};
+pub use middleware::{
+    IntentContext, IntentMetrics, IntentMiddleware, IntentOrigin, IntentStats, MiddlewareFailures,
+};
+pub use queue::{DEFAULT_QUEUE_CAPACITY, QueueError, QueueKey};
+pub use subscription::{IntentOutcome, IntentSubscription, IntentTopic};
 pub use traits::{AgentIntent, EditorIntent, IntentCategory, SystemIntent};
@@ -0,0 +1,285 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot};
+use uuid::Uuid;
+
+use crate::common::intent::dispatcher::IntentDispatcher;
+use crate::common::intent::middleware::IntentOrigin;
+use crate::common::intent::traits::SystemIntent;
+
+/// 队列的键：编辑器会话 ID 或 Routine ID
+pub type QueueKey = Uuid;
+
+/// 每个队列默认最多积压的批次数
+pub const DEFAULT_QUEUE_CAPACITY: usize = 64;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QueueError {
+    #[error("Intent queue {0} is full")]
+    Full(QueueKey),
+    #[error("Intent queue {0} is closed")]
+    Closed(QueueKey),
+}
+
+/// 排队中的一批意图，整批连续处理
+pub(super) struct QueuedBatch {
+    origin: IntentOrigin,
+    intents: Vec<SystemIntent>,
+    reply: oneshot::Sender<Result<Vec<Value>>>,
+}
+
+impl IntentDispatcher {
+    /// 将一批意图放入 `key` 的 FIFO 队列，整批处理完成后返回各意图的输出
+    ///
+    /// 同一队列中的批次按入队顺序逐批处理，批内意图连续执行、不会被其他来源的意图插入，
+    /// 例如 `WriteFile` 后紧跟 `Save`；任一意图失败时跳过该批剩余的意图。
+    /// 队列已满时等待空位（背压）。不同键的队列互不阻塞；直接 `dispatch` 的意图不经过队列。
+    /// 队列在处理完最后一批后被回收，下次入队时重新创建。
+    pub async fn enqueue(
+        self: &Arc<Self>,
+        key: QueueKey,
+        origin: IntentOrigin,
+        intents: Vec<SystemIntent>,
+    ) -> Result<Vec<Value>> {
+        let (mut batch, reply) = Self::batch(origin, intents);
+        // 取得的队列可能恰好空闲回收，此时换用新建的队列
+        while let Err(mpsc::error::SendError(returned)) = self.lane(key).send(batch).await {
+            batch = returned;
+        }
+        reply.await.map_err(|_| QueueError::Closed(key))?
+    }
+
+    /// 同 `enqueue`，但队列已满时立即返回 `QueueError::Full`
+    pub async fn try_enqueue(
+        self: &Arc<Self>,
+        key: QueueKey,
+        origin: IntentOrigin,
+        intents: Vec<SystemIntent>,
+    ) -> Result<Vec<Value>> {
+        let (mut batch, reply) = Self::batch(origin, intents);
+        loop {
+            match self.lane(key).try_send(batch) {
+                Ok(()) => break,
+                Err(mpsc::error::TrySendError::Full(_)) => return Err(QueueError::Full(key).into()),
+                Err(mpsc::error::TrySendError::Closed(returned)) => batch = returned,
+            }
+        }
+        reply.await.map_err(|_| QueueError::Closed(key))?
+    }
+
+    /// 队列中等待处理的批次数（不含正在处理的批次）
+    pub fn queued(&self, key: &QueueKey) -> usize {
+        self.queues
+            .lock()
+            .unwrap()
+            .get(key)
+            .map_or(0, |lane| lane.max_capacity() - lane.capacity())
+    }
+
+    /// 关闭 `key` 的队列（例如会话关闭或 Routine 结束时），已入队的批次仍会处理完
+    pub fn close_queue(&self, key: &QueueKey) {
+        self.queues.lock().unwrap().remove(key);
+    }
+
+    fn batch(
+        origin: IntentOrigin,
+        intents: Vec<SystemIntent>,
+    ) -> (QueuedBatch, oneshot::Receiver<Result<Vec<Value>>>) {
+        let (reply, receiver) = oneshot::channel();
+        (
+            QueuedBatch {
+                origin,
+                intents,
+                reply,
+            },
+            receiver,
+        )
+    }
+
+    /// 取得 `key` 的队列，不存在时创建并启动处理任务
+    ///
+    /// 处理任务在队列排空后将其从表中移除并结束，空闲的队列不会常驻。
+    fn lane(self: &Arc<Self>, key: QueueKey) -> mpsc::Sender<QueuedBatch> {
+        let mut queues = self.queues.lock().unwrap();
+        if let Some(lane) = queues.get(&key) {
+            return lane.clone();
+        }
+        let (lane, mut batches) = mpsc::channel::<QueuedBatch>(self.queue_capacity);
+        queues.insert(key, lane.clone());
+        // 处理任务只持有弱引用，分发器被释放后随之结束
+        let dispatcher = Arc::downgrade(self);
+        let this = lane.downgrade();
+        tokio::spawn(async move {
+            while let Some(batch) = batches.recv().await {
+                let Some(dispatcher) = dispatcher.upgrade() else {
+                    break;
+                };
+                let mut outputs = Vec::with_capacity(batch.intents.len());
+                let mut result = Ok(());
+                for intent in batch.intents {
+                    match dispatcher
+                        .run(Uuid::new_v4(), batch.origin.clone(), intent)
+                        .await
+                    {
+                        Ok(output) => outputs.push(output),
+                        Err(e) => {
+                            result = Err(e);
+                            break;
+                        }
+                    }
+                }
+                let _ = batch.reply.send(result.map(|_| outputs));
+
+                let mut queues = dispatcher.queues.lock().unwrap();
+                if batches.is_empty() {
+                    let current = queues.get(&key).zip(this.upgrade());
+                    if current.is_some_and(|(lane, this)| lane.same_channel(&this)) {
+                        queues.remove(&key);
+                    }
+                    break;
+                }
+            }
+        });
+        lane
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::intent::IntentHandler;
+    use crate::common::intent::traits::{EditorIntent, IntentCategory};
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use tokio::sync::Semaphore;
+
+    /// 记录处理顺序；写入记录后需等待放行
+    struct Recorder {
+        log: Mutex<Vec<String>>,
+        writes: Semaphore,
+    }
+
+    #[async_trait]
+    impl IntentHandler for Recorder {
+        async fn handle(&self, intent: SystemIntent) -> Result<()> {
+            self.log.lock().unwrap().push(intent.summary());
+            if let SystemIntent::Editor(EditorIntent::WriteFile { .. }) = &intent {
+                self.writes.acquire().await?.forget();
+            }
+            Ok(())
+        }
+    }
+
+    fn write(path: &str) -> SystemIntent {
+        SystemIntent::Editor(EditorIntent::WriteFile {
+            path: path.to_string(),
+            content: Vec::new(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_batches_are_not_interleaved_and_queues_apply_backpressure() {
+        let dispatcher = Arc::new(IntentDispatcher::new().with_queue_capacity(1));
+        let recorder = Arc::new(Recorder {
+            log: Mutex::new(Vec::new()),
+            writes: Semaphore::new(0),
+        });
+        dispatcher
+            .register(IntentCategory::Editor, recorder.clone())
+            .await;
+        let session = Uuid::new_v4();
+        let save = SystemIntent::Editor(EditorIntent::Save);
+
+        // Agent 的写入+保存先入队并阻塞在写入上，界面的写入只能排在整批之后
+        let agent = tokio::spawn({
+            let dispatcher = dispatcher.clone();
+            let save = save.clone();
+            async move {
+                dispatcher
+                    .enqueue(
+                        session,
+                        IntentOrigin::agent(Uuid::new_v4()),
+                        vec![write("a.rs"), save],
+                    )
+                    .await
+            }
+        });
+        while recorder.log.lock().unwrap().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        let ui = tokio::spawn({
+            let dispatcher = dispatcher.clone();
+            async move {
+                dispatcher
+                    .enqueue(session, IntentOrigin::ui(), vec![write("b.rs")])
+                    .await
+            }
+        });
+        while dispatcher.queued(&session) == 0 {
+            tokio::task::yield_now().await;
+        }
+        // 一批处理中、一批排队，容量已满
+        assert_eq!(
+            dispatcher
+                .try_enqueue(session, IntentOrigin::ui(), vec![save.clone()])
+                .await
+                .unwrap_err()
+                .downcast::<QueueError>()
+                .unwrap(),
+            QueueError::Full(session)
+        );
+        // 其他会话的队列不受影响
+        dispatcher
+            .try_enqueue(
+                Uuid::new_v4(),
+                IntentOrigin::ui(),
+                vec![SystemIntent::Editor(EditorIntent::OpenFile {
+                    path: "c.rs".to_string(),
+                })],
+            )
+            .await
+            .unwrap();
+
+        recorder.writes.add_permits(2);
+        assert_eq!(agent.await.unwrap().unwrap().len(), 2);
+        ui.await.unwrap().unwrap();
+        assert_eq!(
+            *recorder.log.lock().unwrap(),
+            ["WriteFile a.rs", "OpenFile c.rs", "Save", "WriteFile b.rs"]
+        );
+        assert_eq!(dispatcher.queued(&session), 0);
+    }
+
+    #[tokio::test]
+    async fn test_idle_queues_are_reclaimed_and_recreated() {
+        let dispatcher = Arc::new(IntentDispatcher::new());
+        let recorder = Arc::new(Recorder {
+            log: Mutex::new(Vec::new()),
+            writes: Semaphore::new(2),
+        });
+        dispatcher
+            .register(IntentCategory::Editor, recorder.clone())
+            .await;
+        let routine = Uuid::new_v4();
+        let idle = |dispatcher: &IntentDispatcher| {
+            !dispatcher.queues.lock().unwrap().contains_key(&routine)
+        };
+
+        for path in ["a.rs", "b.rs"] {
+            dispatcher
+                .enqueue(routine, IntentOrigin::agent(routine), vec![write(path)])
+                .await
+                .unwrap();
+            // 最后一批处理完后队列被回收，下次入队时重新创建
+            while !idle(&dispatcher) {
+                tokio::task::yield_now().await;
+            }
+        }
+        assert_eq!(
+            *recorder.log.lock().unwrap(),
+            ["WriteFile a.rs", "WriteFile b.rs"]
+        );
+    }
+}
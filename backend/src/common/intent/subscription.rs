@@ -0,0 +1,227 @@
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::common::intent::dispatcher::{IntentDispatcher, IntentId};
+use crate::common::intent::middleware::IntentOrigin;
+use crate::common::intent::traits::{IntentCategory, SystemIntent};
+
+/// 意图处理完成后推送给订阅方的结果
+#[derive(Debug, Clone)]
+pub struct IntentOutcome {
+    pub id: IntentId,
+    pub origin: IntentOrigin,
+    pub intent: SystemIntent,
+    /// 处理器的输出，失败或被中间件拒绝时为错误信息
+    pub output: Result<Value, String>,
+}
+
+impl IntentOutcome {
+    pub fn is_ok(&self) -> bool {
+        self.output.is_ok()
+    }
+}
+
+type Predicate = Arc<dyn Fn(&SystemIntent) -> bool + Send + Sync>;
+
+/// 订阅条件，未设置的条件不限制
+#[derive(Clone, Default)]
+pub struct IntentTopic {
+    category: Option<IntentCategory>,
+    predicate: Option<Predicate>,
+    succeeded_only: bool,
+}
+
+impl IntentTopic {
+    /// 全部意图
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// 某一类别的意图，例如前端订阅 `Editor`
+    pub fn category(category: IntentCategory) -> Self {
+        Self {
+            category: Some(category),
+            ..Self::default()
+        }
+    }
+
+    /// 进一步按意图本身筛选，例如只订阅 `EditorIntent::Save`
+    pub fn matching(
+        mut self,
+        predicate: impl Fn(&SystemIntent) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.predicate = Some(Arc::new(predicate));
+        self
+    }
+
+    /// 只接收处理成功的意图
+    pub fn succeeded(mut self) -> Self {
+        self.succeeded_only = true;
+        self
+    }
+
+    pub fn matches(&self, intent: &SystemIntent) -> bool {
+        self.category.is_none_or(|c| c == intent.category())
+            && self.predicate.as_ref().is_none_or(|p| p(intent))
+    }
+}
+
+/// 订阅方持有的接收端；丢弃后自动取消订阅
+pub struct IntentSubscription {
+    outcomes: mpsc::UnboundedReceiver<IntentOutcome>,
+}
+
+impl IntentSubscription {
+    /// 等待下一个匹配的结果，分发器被释放后返回 `None`
+    pub async fn recv(&mut self) -> Option<IntentOutcome> {
+        self.outcomes.recv().await
+    }
+
+    /// 取得已到达的结果，不等待
+    pub fn try_recv(&mut self) -> Option<IntentOutcome> {
+        self.outcomes.try_recv().ok()
+    }
+}
+
+pub(super) struct Subscriber {
+    topic: IntentTopic,
+    outcomes: mpsc::UnboundedSender<IntentOutcome>,
+}
+
+/// 一次分发中匹配到的订阅方，在处理器消费意图之前确定
+pub(super) struct Recipients {
+    intent: SystemIntent,
+    subscribers: Vec<(bool, mpsc::UnboundedSender<IntentOutcome>)>,
+}
+
+impl Recipients {
+    pub(super) fn publish(self, id: IntentId, origin: IntentOrigin, output: Result<Value, String>) {
+        let outcome = IntentOutcome {
+            id,
+            origin,
+            intent: self.intent,
+            output,
+        };
+        for (succeeded_only, subscriber) in self.subscribers {
+            if !succeeded_only || outcome.is_ok() {
+                let _ = subscriber.send(outcome.clone());
+            }
+        }
+    }
+}
+
+impl IntentDispatcher {
+    /// 订阅符合 `topic` 的意图结果
+    ///
+    /// 结果在中间件的后置钩子之后按分发完成的顺序推送，订阅方处理缓慢不会阻塞分发。
+    pub fn subscribe(&self, topic: IntentTopic) -> IntentSubscription {
+        let (tx, outcomes) = mpsc::unbounded_channel();
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|s| !s.outcomes.is_closed());
+        subscribers.push(Subscriber {
+            topic,
+            outcomes: tx,
+        });
+        IntentSubscription { outcomes }
+    }
+
+    /// 当前有效的订阅数
+    pub fn subscribers(&self) -> usize {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|s| !s.outcomes.is_closed());
+        subscribers.len()
+    }
+
+    /// 找出关心 `intent` 的订阅方；没有时不复制意图
+    pub(super) fn recipients(&self, intent: &SystemIntent) -> Option<Recipients> {
+        let subscribers: Vec<_> = self
+            .subscribers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| !s.outcomes.is_closed() && s.topic.matches(intent))
+            .map(|s| (s.topic.succeeded_only, s.outcomes.clone()))
+            .collect();
+        (!subscribers.is_empty()).then(|| Recipients {
+            intent: intent.clone(),
+            subscribers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::intent::IntentHandler;
+    use crate::common::intent::traits::EditorIntent;
+    use anyhow::Result;
+    use async_trait::async_trait;
+
+    /// 删除文件总是失败
+    struct NoDeletes;
+
+    #[async_trait]
+    impl IntentHandler for NoDeletes {
+        async fn handle(&self, intent: SystemIntent) -> Result<()> {
+            match intent {
+                SystemIntent::Editor(EditorIntent::DeleteFile { .. }) => {
+                    Err(anyhow::anyhow!("read-only"))
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+
+    fn open(path: &str) -> SystemIntent {
+        SystemIntent::Editor(EditorIntent::OpenFile {
+            path: path.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_matching_outcomes() {
+        let dispatcher = IntentDispatcher::new();
+        dispatcher
+            .register(IntentCategory::Editor, Arc::new(NoDeletes))
+            .await;
+        let mut frontend = dispatcher.subscribe(IntentTopic::category(IntentCategory::Editor));
+        let mut indexer = dispatcher.subscribe(
+            IntentTopic::category(IntentCategory::Editor)
+                .matching(|i| matches!(i, SystemIntent::Editor(EditorIntent::Save)))
+                .succeeded(),
+        );
+        let dropped = dispatcher.subscribe(IntentTopic::all());
+        drop(dropped);
+        assert_eq!(dispatcher.subscribers(), 2);
+
+        dispatcher
+            .dispatch_as(IntentOrigin::ui(), open("a.rs"))
+            .await
+            .unwrap();
+        dispatcher
+            .dispatch(SystemIntent::Editor(EditorIntent::Save))
+            .await
+            .unwrap();
+        assert!(
+            dispatcher
+                .dispatch(SystemIntent::Editor(EditorIntent::DeleteFile {
+                    path: "a.rs".to_string(),
+                }))
+                .await
+                .is_err()
+        );
+
+        let first = frontend.recv().await.unwrap();
+        assert_eq!(first.origin, IntentOrigin::ui());
+        assert_eq!(first.intent.summary(), "OpenFile a.rs");
+        assert_eq!(frontend.recv().await.unwrap().intent.summary(), "Save");
+        let failed = frontend.recv().await.unwrap();
+        assert_eq!(failed.output, Err("read-only".to_string()));
+        assert!(frontend.try_recv().is_none());
+
+        let saved = indexer.recv().await.unwrap();
+        assert!(saved.is_ok() && matches!(saved.intent, SystemIntent::Editor(EditorIntent::Save)));
+        assert!(indexer.try_recv().is_none());
+    }
+}
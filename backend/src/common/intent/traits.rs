@@ -1,5 +1,9 @@
 pub use crate::agent::AgentIntent;
+pub use crate::agent::ApprovalIntent;
+pub use crate::agent::ReviewIntent;
+pub use crate::agent::TeamIntent;
 pub use crate::editor::EditorIntent;
+pub use crate::project::SecretIntent;
 
 /// 意图类别，用于路由分发。
 ///
@@ -10,6 +14,14 @@ pub enum IntentCategory {
     Editor,
     /// 智能体（Agent）相关的操作意图
     Agent,
+    /// 密钥授权意图
+    Secret,
+    /// 变更审阅意图
+    Review,
+    /// 高影响操作的批准意图
+    Approval,
+    /// Agent 团队成员间的协作意图
+    Team,
 }
 
 /// 系统统一意图包装器。
@@ -21,6 +33,14 @@ pub enum SystemIntent {
     Editor(EditorIntent),
     /// 智能体意图分支
     Agent(AgentIntent),
+    /// 密钥授权意图分支
+    Secret(SecretIntent),
+    /// 变更审阅意图分支
+    Review(ReviewIntent),
+    /// 操作批准意图分支
+    Approval(ApprovalIntent),
+    /// 团队协作意图分支
+    Team(TeamIntent),
 }
 
 impl SystemIntent {
@@ -29,6 +49,93 @@ impl SystemIntent {
         match self {
             SystemIntent::Editor(_) => IntentCategory::Editor,
             SystemIntent::Agent(_) => IntentCategory::Agent,
+            SystemIntent::Secret(_) => IntentCategory::Secret,
+            SystemIntent::Review(_) => IntentCategory::Review,
+            SystemIntent::Approval(_) => IntentCategory::Approval,
+            SystemIntent::Team(_) => IntentCategory::Team,
+        }
+    }
+
+    /// 意图的简短描述（不含文件内容、工具参数等负载），用于事件导出。
+    pub fn summary(&self) -> String {
+        match self {
+            SystemIntent::Editor(intent) => match intent {
+                EditorIntent::OpenFile { path } => format!("OpenFile {}", path),
+                EditorIntent::SwitchTab { tab_id } => format!("SwitchTab {}", tab_id),
+                EditorIntent::SplitGroup {
+                    group_id,
+                    direction,
+                } => format!("SplitGroup {} {:?}", group_id, direction),
+                EditorIntent::MoveTab {
+                    tab_id, group_id, ..
+                } => format!("MoveTab {} {}", tab_id, group_id),
+                EditorIntent::PinTab { tab_id, pinned } => format!("PinTab {} {}", tab_id, pinned),
+                EditorIntent::CloseTab { tab_id, .. } => format!("CloseTab {}", tab_id),
+                EditorIntent::UpdateCursor { peer_id, path, .. } => {
+                    format!("UpdateCursor {} {}", peer_id, path)
+                }
+                EditorIntent::WriteFile { path, .. } => format!("WriteFile {}", path),
+                EditorIntent::InsertText { path, offset, .. } => {
+                    format!("InsertText {}@{}", path, offset)
+                }
+                EditorIntent::DeleteRange { path, offset, .. } => {
+                    format!("DeleteRange {}@{}", path, offset)
+                }
+                EditorIntent::ReplaceRange { path, offset, .. } => {
+                    format!("ReplaceRange {}@{}", path, offset)
+                }
+                EditorIntent::EditCell { path, .. } => format!("EditCell {}", path),
+                EditorIntent::InspectAsset { path } => format!("InspectAsset {}", path),
+                EditorIntent::ReplaceAll { query, .. } => format!("ReplaceAll {}", query.pattern),
+                EditorIntent::DeleteFile { path } => format!("DeleteFile {}", path),
+                EditorIntent::FileChanged { path, kind } => {
+                    format!("FileChanged {} {:?}", path, kind)
+                }
+                EditorIntent::Save => "Save".to_string(),
+                EditorIntent::Undo => "Undo".to_string(),
+                EditorIntent::Redo => "Redo".to_string(),
+                EditorIntent::Diff { path } => match path {
+                    Some(path) => format!("Diff {}", path),
+                    None => "Diff".to_string(),
+                },
+            },
+            SystemIntent::Agent(intent) => match intent {
+                AgentIntent::CallTool { name, .. } => format!("CallTool {}", name),
+                AgentIntent::Abort => "Abort".to_string(),
+                AgentIntent::GuardrailExceeded { routine_id, .. } => {
+                    format!("GuardrailExceeded {}", routine_id)
+                }
+                AgentIntent::RequestApproval { request } => {
+                    format!("RequestApproval {}", request.id)
+                }
+                AgentIntent::RunTests { filter, .. } => match filter {
+                    Some(filter) => format!("RunTests {}", filter),
+                    None => "RunTests".to_string(),
+                },
+                AgentIntent::Explain { selection, .. } => format!(
+                    "Explain {}:{}-{}",
+                    selection.path, selection.start_line, selection.end_line
+                ),
+            },
+            SystemIntent::Secret(intent) => match intent {
+                SecretIntent::Grant { request_id, .. } => format!("GrantSecret {}", request_id),
+                SecretIntent::Deny { request_id } => format!("DenySecret {}", request_id),
+            },
+            SystemIntent::Review(intent) => match intent {
+                ReviewIntent::Approve { thread_id } => format!("ApproveReview {}", thread_id),
+                ReviewIntent::Reject { thread_id, .. } => format!("RejectReview {}", thread_id),
+                ReviewIntent::Comment { thread_id, .. } => format!("CommentReview {}", thread_id),
+            },
+            SystemIntent::Approval(intent) => match intent {
+                ApprovalIntent::Approve { request_id } => format!("Approve {}", request_id),
+                ApprovalIntent::Reject { request_id, .. } => format!("Reject {}", request_id),
+            },
+            SystemIntent::Team(intent) => match intent {
+                TeamIntent::Message(message) => format!("TeamMessage {}", message.from),
+                TeamIntent::Submit { role, .. } => format!("SubmitWork {}", role),
+                TeamIntent::Accept { role } => format!("AcceptWork {}", role),
+                TeamIntent::Reject { role, .. } => format!("RejectWork {}", role),
+            },
         }
     }
 }
@@ -0,0 +1,17 @@
+//! # 生命周期管理
+//!
+//! 负责后端整体的启动与关闭编排。
+//!
+//! ## 模块
+//!
+//! - [`recovery`] - 启动时的崩溃恢复与完整性校验
+//! - [`shutdown`] - 分阶段的优雅关闭协调器
+//! - [`startup`] - 启动耗时分析与子系统的延迟初始化
+
+pub mod recovery;
+pub mod shutdown;
+pub mod startup;
+
+pub use recovery::{CrashRecovery, IntegrityIssue, RecoveryReport, Repair};
+pub use shutdown::{ShutdownCoordinator, ShutdownHook, ShutdownPhase, ShutdownReport};
+pub use startup::{LazySubsystem, PhaseTiming, StartupProfiler, StartupProgress, StartupReport};
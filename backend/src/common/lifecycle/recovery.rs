@@ -0,0 +1,508 @@
+use crate::common::change::Change;
+use crate::common::change::blob::BlobStore;
+use crate::common::change::operation::Operation;
+use crate::common::change::snapshot::materialize_files;
+use crate::common::change::store::ChangeStore;
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::change::wal::{WalRecord, WriteAheadLog};
+use crate::common::provider::traits::StorageProvider;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 启动时发现的不一致
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IntegrityIssue {
+    /// 日志条目无法解析（崩溃时写入了一半）
+    CorruptWalEntry { path: String, error: String },
+    /// Change 内容与哈希不符
+    HashMismatch { change_id: Uuid },
+    /// Change 未通过签名校验，未载入变更图
+    UntrustedChange { change_id: Uuid, error: String },
+    /// Change 引用了不存在的父节点
+    DanglingParent { change_id: Uuid, parent: Uuid },
+    /// 线程 Head 指向不存在的 Change
+    DanglingHead { thread_id: ThreadId, head: Uuid },
+    /// 磁盘上的文件与线程 Head 物化的内容不一致（`None` 表示应不存在）
+    WorkspaceDivergence {
+        path: String,
+        expected: Option<String>,
+        actual: Option<String>,
+    },
+}
+
+/// 已执行的修复
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Repair {
+    /// 删除了损坏的日志条目
+    DiscardedWalEntry { path: String },
+    /// 从变更图中移除了无效 Change
+    DroppedChange { change_id: Uuid },
+    /// 线程 Head 回退到最近的有效祖先
+    ResetHead {
+        thread_id: ThreadId,
+        from: Uuid,
+        to: Option<Uuid>,
+    },
+    /// 按线程 Head 重写了磁盘文件
+    RestoredFile { path: String },
+    /// 删除了线程 Head 中不存在的磁盘文件
+    RemovedFile { path: String },
+}
+
+/// 恢复报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RecoveryReport {
+    /// 从持久化存储加载的 Change 数
+    #[serde(default)]
+    pub loaded: usize,
+    /// 重放的日志条目数
+    pub replayed: usize,
+    pub issues: Vec<IntegrityIssue>,
+    pub repairs: Vec<Repair>,
+}
+
+impl RecoveryReport {
+    /// 是否未发现任何不一致
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// 启动时的崩溃恢复
+///
+/// 依次加载持久化的变更图、重放预写日志、校验变更图（哈希链与悬空父节点）、修正线程 Head，
+/// 最后将工作区与线程 Head 对账。默认只报告问题，开启 `repair` 后才会修改状态，
+/// 以避免在不一致的状态上静默继续运行。
+pub struct CrashRecovery {
+    threads: Arc<ThreadManager>,
+    store: Option<Arc<dyn ChangeStore>>,
+    wal: Option<Arc<WriteAheadLog>>,
+    workspace: Option<(Arc<dyn StorageProvider>, ThreadId)>,
+    blobs: Option<Arc<BlobStore>>,
+    repair: bool,
+}
+
+impl CrashRecovery {
+    pub fn new(threads: Arc<ThreadManager>) -> Self {
+        Self {
+            threads,
+            store: None,
+            wal: None,
+            workspace: None,
+            blobs: None,
+            repair: false,
+        }
+    }
+
+    /// 配置持久化的变更图，在重放预写日志之前加载；修复时被移除的 Change 同时从中删除
+    pub fn with_store(mut self, store: Arc<dyn ChangeStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// 配置需要重放的预写日志
+    pub fn with_wal(mut self, wal: Arc<WriteAheadLog>) -> Self {
+        self.wal = Some(wal);
+        self
+    }
+
+    /// 配置需要与指定线程对账的工作区
+    pub fn with_workspace(mut self, storage: Arc<dyn StorageProvider>, thread: ThreadId) -> Self {
+        self.workspace = Some((storage, thread));
+        self
+    }
+
+    /// 配置用于解析 `FileWriteRef` 的 Blob 存储
+    pub fn with_blob_store(mut self, blobs: Arc<BlobStore>) -> Self {
+        self.blobs = Some(blobs);
+        self
+    }
+
+    /// 是否自动修复发现的问题
+    pub fn with_repair(mut self, repair: bool) -> Self {
+        self.repair = repair;
+        self
+    }
+
+    /// 执行恢复流程
+    pub async fn run(&self) -> anyhow::Result<RecoveryReport> {
+        let mut report = RecoveryReport::default();
+        if let Some(store) = &self.store {
+            let graph = store.load().await?;
+            report.loaded = graph.changes.len();
+            for (change_id, error) in graph.restore_into(&self.threads) {
+                report
+                    .issues
+                    .push(IntegrityIssue::UntrustedChange { change_id, error });
+            }
+        }
+        self.replay_wal(&mut report).await?;
+        self.check_graph(&mut report).await?;
+        self.reconcile_workspace(&mut report).await?;
+        Ok(report)
+    }
+
+    async fn replay_wal(&self, report: &mut RecoveryReport) -> anyhow::Result<()> {
+        let Some(wal) = &self.wal else {
+            return Ok(());
+        };
+        let (entries, corrupt) = wal.read_all().await?;
+        for entry in corrupt {
+            if self.repair {
+                wal.discard(&entry.path).await?;
+                report.repairs.push(Repair::DiscardedWalEntry {
+                    path: entry.path.clone(),
+                });
+            }
+            report.issues.push(IntegrityIssue::CorruptWalEntry {
+                path: entry.path,
+                error: entry.error,
+            });
+        }
+        for entry in entries {
+            match entry.record {
+                WalRecord::Thread(thread) => self.threads.restore_thread(thread),
+                WalRecord::Commit { thread, change } => {
+                    let change_id = change.id;
+                    if let Err(e) = self.threads.restore_change(*change) {
+                        report.issues.push(IntegrityIssue::UntrustedChange {
+                            change_id,
+                            error: e.to_string(),
+                        });
+                        continue;
+                    }
+                    self.threads.restore_thread(thread);
+                }
+            }
+            report.replayed += 1;
+        }
+        Ok(())
+    }
+
+    async fn check_graph(&self, report: &mut RecoveryReport) -> anyhow::Result<()> {
+        let changes: HashMap<Uuid, Change> = self
+            .threads
+            .list_changes()
+            .into_iter()
+            .map(|c| (c.id, c))
+            .collect();
+
+        let mut invalid = HashSet::new();
+        for change in changes.values() {
+            if !self.verify(change).await {
+                report.issues.push(IntegrityIssue::HashMismatch {
+                    change_id: change.id,
+                });
+                invalid.insert(change.id);
+            }
+        }
+        for change in changes.values() {
+            for parent in &change.parents {
+                if !changes.contains_key(parent) {
+                    report.issues.push(IntegrityIssue::DanglingParent {
+                        change_id: change.id,
+                        parent: *parent,
+                    });
+                    invalid.insert(change.id);
+                }
+            }
+        }
+        // 依赖无效 Change 的后代同样不可信
+        loop {
+            let tainted: Vec<Uuid> = changes
+                .values()
+                .filter(|c| !invalid.contains(&c.id))
+                .filter(|c| c.parents.iter().any(|p| invalid.contains(p)))
+                .map(|c| c.id)
+                .collect();
+            if tainted.is_empty() {
+                break;
+            }
+            invalid.extend(tainted);
+        }
+
+        for thread in self.threads.list_threads() {
+            let Some(head) = thread.head_change_id else {
+                continue;
+            };
+            if !changes.contains_key(&head) {
+                report.issues.push(IntegrityIssue::DanglingHead {
+                    thread_id: thread.id,
+                    head,
+                });
+            } else if !invalid.contains(&head) {
+                continue;
+            }
+            if self.repair {
+                let to = nearest_valid_ancestor(&changes, &invalid, head);
+                if self.threads.set_head(thread.id, to).is_ok() {
+                    report.repairs.push(Repair::ResetHead {
+                        thread_id: thread.id,
+                        from: head,
+                        to,
+                    });
+                }
+            }
+        }
+
+        if self.repair {
+            let mut dropped: Vec<Uuid> = invalid.into_iter().collect();
+            dropped.sort();
+            for change_id in dropped {
+                self.threads.remove_change(change_id);
+                if let Some(store) = &self.store {
+                    store.remove_change(change_id).await?;
+                }
+                report.repairs.push(Repair::DroppedChange { change_id });
+            }
+        }
+        Ok(())
+    }
+
+    /// 校验 Change 哈希；以 Blob 引用持久化的 Change 需还原后再校验
+    async fn verify(&self, change: &Change) -> bool {
+        if change.verify_hash() {
+            return true;
+        }
+        match &self.blobs {
+            Some(blobs) => blobs
+                .hydrate(change)
+                .await
+                .is_ok_and(|hydrated| hydrated.verify_hash()),
+            None => false,
+        }
+    }
+
+    async fn reconcile_workspace(&self, report: &mut RecoveryReport) -> anyhow::Result<()> {
+        let Some((storage, thread_id)) = &self.workspace else {
+            return Ok(());
+        };
+        let Some(head) = self
+            .threads
+            .get_thread(*thread_id)
+            .and_then(|t| t.head_change_id)
+        else {
+            return Ok(());
+        };
+
+        let mut history = Vec::new();
+        for id in self.threads.ancestors(head) {
+            let Some(change) = self.threads.get_change(id) else {
+                continue;
+            };
+            history.push(match &self.blobs {
+                Some(blobs) => blobs.hydrate(&change).await?,
+                None => change,
+            });
+        }
+
+        let touched: BTreeSet<String> = history
+            .iter()
+            .flat_map(|c| c.operations.iter())
+            .filter_map(|op| match op {
+                Operation::FileWrite { path, .. }
+                | Operation::FileWriteRef { path, .. }
+                | Operation::FileDelete { path }
+                | Operation::NotebookCell { path, .. }
+                | Operation::TextEdit { path, .. } => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+        let expected = materialize_files(&history)?;
+
+        for path in touched {
+            let want = expected.get(&path);
+            let have = if storage.exists(&path).await? {
+                Some(storage.read_file(&path).await?)
+            } else {
+                None
+            };
+            if want == have.as_ref() {
+                continue;
+            }
+            report.issues.push(IntegrityIssue::WorkspaceDivergence {
+                path: path.clone(),
+                expected: want.map(|c| BlobStore::hash(c)),
+                actual: have.as_ref().map(|c| BlobStore::hash(c)),
+            });
+            if !self.repair {
+                continue;
+            }
+            match want {
+                Some(content) => {
+                    storage.write_file(&path, content).await?;
+                    report.repairs.push(Repair::RestoredFile { path });
+                }
+                None => {
+                    storage.delete(&path, false).await?;
+                    report.repairs.push(Repair::RemovedFile { path });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 沿首个父节点回溯，找到最近的有效祖先
+fn nearest_valid_ancestor(
+    changes: &HashMap<Uuid, Change>,
+    invalid: &HashSet<Uuid>,
+    start: Uuid,
+) -> Option<Uuid> {
+    let mut current = Some(start);
+    let mut visited = HashSet::new();
+    while let Some(id) = current {
+        if !visited.insert(id) {
+            return None;
+        }
+        let change = changes.get(&id)?;
+        if !invalid.contains(&id) {
+            return Some(id);
+        }
+        current = change.parents.first().copied();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::version::VectorClock;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_recovery_replays_wal_and_repairs() {
+        let dir = tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let wal = Arc::new(
+            WriteAheadLog::open(storage.clone(), ".zhiyun/wal")
+                .await
+                .unwrap(),
+        );
+
+        // 崩溃前的会话：两次提交，第二次提交写入磁盘前进程退出
+        let before = ThreadManager::new();
+        let main_id = before.get_thread_id_by_name("main").unwrap();
+        let mut parents = Vec::new();
+        for content in ["v1", "v2"] {
+            let change = Change::new(
+                Uuid::new_v4(),
+                vec![Operation::file_write(
+                    "src/lib.rs".to_string(),
+                    content.as_bytes().to_vec(),
+                )],
+                VectorClock::new(),
+                parents,
+            );
+            parents = vec![change.id];
+            before.commit_change(main_id, change.clone()).unwrap();
+            wal.append(WalRecord::Commit {
+                thread: before.get_thread(main_id).unwrap(),
+                change: Box::new(change),
+            })
+            .await
+            .unwrap();
+        }
+        storage.write_file("src/lib.rs", b"v1").await.unwrap();
+        storage
+            .write_file(".zhiyun/wal/00000000000000000002.json", b"{\"seq\"")
+            .await
+            .unwrap();
+
+        let threads = Arc::new(ThreadManager::new());
+        let report = CrashRecovery::new(threads.clone())
+            .with_wal(wal.clone())
+            .with_workspace(storage.clone(), main_id)
+            .with_repair(true)
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(report.replayed, 2);
+        assert_eq!(report.issues.len(), 2);
+        assert_eq!(threads.list_threads().len(), 1);
+        assert_eq!(
+            threads.get_thread(main_id).unwrap().head_change_id,
+            Some(parents[0])
+        );
+        assert_eq!(storage.read_file("src/lib.rs").await.unwrap(), b"v2");
+        assert!(wal.read_all().await.unwrap().1.is_empty());
+
+        // 篡改 Head 后，Head 回退到最近的有效祖先
+        let mut tampered = threads.get_change(parents[0]).unwrap();
+        tampered.operations = vec![Operation::mock("test", "tampered")];
+        threads.restore_change(tampered.clone()).unwrap();
+        let report = CrashRecovery::new(threads.clone())
+            .with_repair(true)
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(
+            report.issues,
+            vec![IntegrityIssue::HashMismatch {
+                change_id: tampered.id
+            }]
+        );
+        let head = threads.get_thread(main_id).unwrap().head_change_id;
+        assert_eq!(head, Some(tampered.parents[0]));
+        assert!(threads.get_change(tampered.id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_recovery_loads_store_before_replaying_wal() {
+        use crate::common::change::store::FileChangeStore;
+
+        let dir = tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let wal = Arc::new(
+            WriteAheadLog::open(storage.clone(), ".zhiyun/wal")
+                .await
+                .unwrap(),
+        );
+        let store = Arc::new(
+            FileChangeStore::open(storage.clone(), ".zhiyun/store")
+                .await
+                .unwrap(),
+        );
+
+        // 第一次提交已检查点化，第二次提交只在预写日志中
+        let before = ThreadManager::new();
+        let main_id = before.get_thread_id_by_name("main").unwrap();
+        let first = Change::mock(Uuid::new_v4(), vec![Operation::mock("test", "a")]);
+        before.commit_change(main_id, first.clone()).unwrap();
+        store.checkpoint(&before, Some(&wal)).await.unwrap();
+        let second = Change::new(
+            Uuid::new_v4(),
+            vec![Operation::mock("test", "b")],
+            VectorClock::new(),
+            vec![first.id],
+        );
+        before.commit_change(main_id, second.clone()).unwrap();
+        wal.append(WalRecord::Commit {
+            thread: before.get_thread(main_id).unwrap(),
+            change: Box::new(second.clone()),
+        })
+        .await
+        .unwrap();
+
+        let threads = Arc::new(ThreadManager::new());
+        let report = CrashRecovery::new(threads.clone())
+            .with_store(store)
+            .with_wal(wal)
+            .run()
+            .await
+            .unwrap();
+        assert!(report.is_clean());
+        assert_eq!((report.loaded, report.replayed), (1, 1));
+        assert_eq!(
+            threads.get_thread(main_id).unwrap().head_change_id,
+            Some(second.id)
+        );
+        assert!(threads.get_change(first.id).is_some());
+    }
+}
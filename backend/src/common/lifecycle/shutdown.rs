@@ -0,0 +1,306 @@
+use crate::common::provider::traits::StorageProvider;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{RwLock, broadcast};
+
+/// 关闭阶段，按声明顺序依次执行
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownPhase {
+    /// 停止接收新的意图与请求
+    StopIntake,
+    /// 取消或检查点化运行中的 Routine
+    Routines,
+    /// 刷新变更日志与各类缓存
+    Flush,
+    /// 断开远程提供者连接
+    Disconnect,
+}
+
+impl ShutdownPhase {
+    pub const ALL: [ShutdownPhase; 4] = [
+        ShutdownPhase::StopIntake,
+        ShutdownPhase::Routines,
+        ShutdownPhase::Flush,
+        ShutdownPhase::Disconnect,
+    ];
+}
+
+/// 关闭钩子：参与关闭流程的组件实现此接口
+#[async_trait]
+pub trait ShutdownHook: Send + Sync {
+    /// 钩子名称（用于进度报告）
+    fn name(&self) -> &str;
+
+    /// 所属阶段
+    fn phase(&self) -> ShutdownPhase;
+
+    /// 执行关闭逻辑
+    async fn shutdown(&self) -> anyhow::Result<()>;
+}
+
+type HookFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+
+/// 由闭包构造的关闭钩子
+pub struct FnHook {
+    name: String,
+    phase: ShutdownPhase,
+    f: Box<dyn Fn() -> HookFuture + Send + Sync>,
+}
+
+impl FnHook {
+    pub fn new<F, Fut>(name: &str, phase: ShutdownPhase, f: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            phase,
+            f: Box::new(move || Box::pin(f())),
+        }
+    }
+}
+
+#[async_trait]
+impl ShutdownHook for FnHook {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn phase(&self) -> ShutdownPhase {
+        self.phase
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        (self.f)().await
+    }
+}
+
+/// 断开存储提供者连接的钩子
+pub struct DisconnectProvider(pub Arc<dyn StorageProvider>);
+
+#[async_trait]
+impl ShutdownHook for DisconnectProvider {
+    fn name(&self) -> &str {
+        self.0.id()
+    }
+
+    fn phase(&self) -> ShutdownPhase {
+        ShutdownPhase::Disconnect
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        self.0.disconnect().await
+    }
+}
+
+/// 单个钩子的执行结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "status", content = "detail", rename_all = "snake_case")]
+pub enum HookOutcome {
+    Completed,
+    Failed(String),
+    TimedOut,
+}
+
+/// 关闭进度事件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShutdownProgress {
+    PhaseStarted {
+        phase: ShutdownPhase,
+        hooks: usize,
+    },
+    HookFinished {
+        phase: ShutdownPhase,
+        name: String,
+        outcome: HookOutcome,
+        duration_ms: u64,
+    },
+    Finished {
+        clean: bool,
+    },
+}
+
+/// 关闭报告
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// 所有钩子的执行结果（阶段、名称、结果、耗时）
+    pub hooks: Vec<(ShutdownPhase, String, HookOutcome, u64)>,
+    pub duration_ms: u64,
+}
+
+impl ShutdownReport {
+    /// 是否所有钩子都成功完成
+    pub fn is_clean(&self) -> bool {
+        self.hooks
+            .iter()
+            .all(|(_, _, outcome, _)| *outcome == HookOutcome::Completed)
+    }
+}
+
+/// 整个后端的关闭协调器
+///
+/// 各组件注册关闭钩子，`shutdown` 按阶段依次执行：同一阶段内的钩子并发运行，
+/// 单个钩子失败或超时不会阻止后续阶段，保证强制退出前状态尽可能落盘。
+pub struct ShutdownCoordinator {
+    hooks: RwLock<Vec<Arc<dyn ShutdownHook>>>,
+    hook_timeout: Duration,
+    progress: broadcast::Sender<ShutdownProgress>,
+    report: RwLock<Option<ShutdownReport>>,
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        let (progress, _) = broadcast::channel(64);
+        Self {
+            hooks: RwLock::new(Vec::new()),
+            hook_timeout: Duration::from_secs(10),
+            progress,
+            report: RwLock::new(None),
+        }
+    }
+
+    /// 设置单个钩子的超时时间
+    pub fn with_hook_timeout(mut self, timeout: Duration) -> Self {
+        self.hook_timeout = timeout;
+        self
+    }
+
+    /// 注册关闭钩子
+    pub async fn register(&self, hook: Arc<dyn ShutdownHook>) {
+        self.hooks.write().await.push(hook);
+    }
+
+    /// 订阅关闭进度
+    pub fn subscribe(&self) -> broadcast::Receiver<ShutdownProgress> {
+        self.progress.subscribe()
+    }
+
+    /// 是否已完成关闭
+    pub async fn is_shut_down(&self) -> bool {
+        self.report.read().await.is_some()
+    }
+
+    /// 执行关闭流程；重复调用返回首次关闭的报告
+    pub async fn shutdown(&self) -> ShutdownReport {
+        let mut report_slot = self.report.write().await;
+        if let Some(report) = report_slot.as_ref() {
+            return report.clone();
+        }
+
+        let started = Instant::now();
+        let hooks = self.hooks.read().await.clone();
+        let mut results = Vec::new();
+
+        for phase in ShutdownPhase::ALL {
+            let phase_hooks: Vec<_> = hooks.iter().filter(|h| h.phase() == phase).collect();
+            let _ = self.progress.send(ShutdownProgress::PhaseStarted {
+                phase,
+                hooks: phase_hooks.len(),
+            });
+
+            let runs = phase_hooks.into_iter().map(|hook| async move {
+                let begin = Instant::now();
+                let outcome = match tokio::time::timeout(self.hook_timeout, hook.shutdown()).await {
+                    Ok(Ok(())) => HookOutcome::Completed,
+                    Ok(Err(e)) => HookOutcome::Failed(e.to_string()),
+                    Err(_) => HookOutcome::TimedOut,
+                };
+                (
+                    hook.name().to_string(),
+                    outcome,
+                    begin.elapsed().as_millis() as u64,
+                )
+            });
+
+            for (name, outcome, duration_ms) in futures::future::join_all(runs).await {
+                let _ = self.progress.send(ShutdownProgress::HookFinished {
+                    phase,
+                    name: name.clone(),
+                    outcome: outcome.clone(),
+                    duration_ms,
+                });
+                results.push((phase, name, outcome, duration_ms));
+            }
+        }
+
+        let report = ShutdownReport {
+            hooks: results,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        let _ = self.progress.send(ShutdownProgress::Finished {
+            clean: report.is_clean(),
+        });
+        *report_slot = Some(report.clone());
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_shutdown_runs_phases_in_order() {
+        let coordinator = ShutdownCoordinator::new().with_hook_timeout(Duration::from_millis(50));
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        for (name, phase) in [
+            ("flush", ShutdownPhase::Flush),
+            ("intake", ShutdownPhase::StopIntake),
+            ("routines", ShutdownPhase::Routines),
+        ] {
+            let order = order.clone();
+            coordinator
+                .register(Arc::new(FnHook::new(name, phase, move || {
+                    let order = order.clone();
+                    async move {
+                        order.lock().unwrap().push(name);
+                        Ok(())
+                    }
+                })))
+                .await;
+        }
+        coordinator
+            .register(Arc::new(FnHook::new(
+                "slow",
+                ShutdownPhase::Disconnect,
+                || async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    Ok(())
+                },
+            )))
+            .await;
+
+        let mut progress = coordinator.subscribe();
+        let report = coordinator.shutdown().await;
+
+        assert_eq!(*order.lock().unwrap(), vec!["intake", "routines", "flush"]);
+        assert!(!report.is_clean());
+        assert_eq!(report.hooks.last().unwrap().2, HookOutcome::TimedOut);
+        assert!(matches!(
+            progress.recv().await.unwrap(),
+            ShutdownProgress::PhaseStarted {
+                phase: ShutdownPhase::StopIntake,
+                hooks: 1
+            }
+        ));
+
+        // 重复关闭返回相同报告且不再执行钩子
+        assert_eq!(coordinator.shutdown().await, report);
+        assert_eq!(order.lock().unwrap().len(), 3);
+    }
+}
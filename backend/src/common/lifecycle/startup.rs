@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{OnceCell, broadcast};
+
+/// 启动阶段的计时记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PhaseTiming {
+    pub name: String,
+    /// 是否为首次使用时才初始化的子系统
+    pub lazy: bool,
+    pub duration_ms: u64,
+    /// 自启动起到该阶段结束的时间
+    pub finished_at_ms: u64,
+    pub error: Option<String>,
+}
+
+/// 启动进度事件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StartupProgress {
+    PhaseStarted {
+        name: String,
+        lazy: bool,
+    },
+    PhaseFinished(PhaseTiming),
+    /// 启动流程结束，剩余子系统推迟到首次使用
+    Ready {
+        elapsed_ms: u64,
+        deferred: Vec<String>,
+    },
+}
+
+/// 启动报告
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StartupReport {
+    pub phases: Vec<PhaseTiming>,
+    /// 尚未被使用、因此仍未初始化的子系统
+    pub deferred: Vec<String>,
+    pub elapsed_ms: u64,
+}
+
+impl StartupReport {
+    /// 启动路径上（非延迟初始化）阶段的总耗时
+    pub fn eager_ms(&self) -> u64 {
+        self.phases
+            .iter()
+            .filter(|p| !p.lazy)
+            .map(|p| p.duration_ms)
+            .sum()
+    }
+
+    /// 耗时最长的若干阶段，用于定位冷启动瓶颈
+    pub fn slowest(&self, limit: usize) -> Vec<&PhaseTiming> {
+        let mut phases: Vec<&PhaseTiming> = self.phases.iter().collect();
+        phases.sort_by_key(|p| std::cmp::Reverse(p.duration_ms));
+        phases.truncate(limit);
+        phases
+    }
+}
+
+/// 启动耗时分析器
+///
+/// 启动路径上的阶段（加载模型目录、打开索引、加载语法等）通过 `phase` 计时；
+/// 延迟初始化的子系统在首次使用时记录到同一份报告中，并广播进度供前端展示。
+pub struct StartupProfiler {
+    started: Instant,
+    phases: Mutex<Vec<PhaseTiming>>,
+    deferred: Mutex<Vec<String>>,
+    progress: broadcast::Sender<StartupProgress>,
+}
+
+impl Default for StartupProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StartupProfiler {
+    pub fn new() -> Self {
+        let (progress, _) = broadcast::channel(64);
+        Self {
+            started: Instant::now(),
+            phases: Mutex::new(Vec::new()),
+            deferred: Mutex::new(Vec::new()),
+            progress,
+        }
+    }
+
+    /// 订阅启动进度
+    pub fn subscribe(&self) -> broadcast::Receiver<StartupProgress> {
+        self.progress.subscribe()
+    }
+
+    /// 对启动路径上的一个阶段计时
+    pub async fn phase<T, Fut>(&self, name: &str, fut: Fut) -> anyhow::Result<T>
+    where
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        self.run(name, false, fut).await
+    }
+
+    /// 登记一个推迟到首次使用时初始化的子系统
+    pub fn defer(&self, name: &str) {
+        let mut deferred = self.deferred.lock().unwrap();
+        if !deferred.iter().any(|n| n == name) {
+            deferred.push(name.to_string());
+        }
+    }
+
+    /// 启动流程结束：广播 `Ready` 并返回当前报告
+    pub fn finish(&self) -> StartupReport {
+        let report = self.report();
+        let _ = self.progress.send(StartupProgress::Ready {
+            elapsed_ms: report.elapsed_ms,
+            deferred: report.deferred.clone(),
+        });
+        report
+    }
+
+    pub fn report(&self) -> StartupReport {
+        StartupReport {
+            phases: self.phases.lock().unwrap().clone(),
+            deferred: self.deferred.lock().unwrap().clone(),
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+        }
+    }
+
+    async fn run<T, Fut>(&self, name: &str, lazy: bool, fut: Fut) -> anyhow::Result<T>
+    where
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let _ = self.progress.send(StartupProgress::PhaseStarted {
+            name: name.to_string(),
+            lazy,
+        });
+        let begin = Instant::now();
+        let result = fut.await;
+        let timing = PhaseTiming {
+            name: name.to_string(),
+            lazy,
+            duration_ms: begin.elapsed().as_millis() as u64,
+            finished_at_ms: self.started.elapsed().as_millis() as u64,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        };
+        if lazy && result.is_ok() {
+            self.deferred.lock().unwrap().retain(|n| n != name);
+        }
+        self.phases.lock().unwrap().push(timing.clone());
+        let _ = self.progress.send(StartupProgress::PhaseFinished(timing));
+        result
+    }
+}
+
+type InitFuture<T> = Pin<Box<dyn Future<Output = anyhow::Result<T>> + Send>>;
+
+/// 首次使用时才初始化的子系统
+///
+/// 并发的首次访问只会触发一次初始化；初始化失败不会被缓存，下次访问时重试。
+pub struct LazySubsystem<T> {
+    name: String,
+    init: Box<dyn Fn() -> InitFuture<T> + Send + Sync>,
+    cell: OnceCell<T>,
+    profiler: Option<Arc<StartupProfiler>>,
+}
+
+impl<T> LazySubsystem<T> {
+    pub fn new<F, Fut>(name: &str, init: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            init: Box::new(move || Box::pin(init())),
+            cell: OnceCell::new(),
+            profiler: None,
+        }
+    }
+
+    /// 将初始化耗时与进度记录到启动分析器
+    pub fn with_profiler(mut self, profiler: Arc<StartupProfiler>) -> Self {
+        profiler.defer(&self.name);
+        self.profiler = Some(profiler);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.cell.initialized()
+    }
+
+    /// 已初始化时返回值，不触发初始化
+    pub fn get_if_ready(&self) -> Option<&T> {
+        self.cell.get()
+    }
+
+    /// 获取子系统，必要时先初始化
+    pub async fn get(&self) -> anyhow::Result<&T> {
+        self.cell
+            .get_or_try_init(|| async {
+                match &self.profiler {
+                    Some(profiler) => profiler.run(&self.name, true, (self.init)()).await,
+                    None => (self.init)().await,
+                }
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_lazy_subsystems_are_profiled_on_first_use() {
+        let profiler = Arc::new(StartupProfiler::new());
+        let mut progress = profiler.subscribe();
+        profiler
+            .phase("provider_catalog", async { Ok(()) })
+            .await
+            .unwrap();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let index = LazySubsystem::new("knowledge_index", {
+            let calls = calls.clone();
+            move || {
+                let calls = calls.clone();
+                async move {
+                    if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                        anyhow::bail!("index locked");
+                    }
+                    Ok(42)
+                }
+            }
+        })
+        .with_profiler(profiler.clone());
+
+        let report = profiler.finish();
+        assert_eq!(report.deferred, vec!["knowledge_index"]);
+        assert_eq!(report.phases.len(), 1);
+        assert!(index.get_if_ready().is_none());
+
+        // 失败不缓存，下次访问重试；成功后不再初始化
+        assert!(index.get().await.is_err());
+        let (a, b) = tokio::join!(index.get(), index.get());
+        assert_eq!((*a.unwrap(), *b.unwrap()), (42, 42));
+        assert_eq!(*index.get().await.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let report = profiler.report();
+        assert!(report.deferred.is_empty());
+        assert_eq!(report.eager_ms(), report.phases[0].duration_ms);
+        let lazy: Vec<_> = report.phases.iter().filter(|p| p.lazy).collect();
+        assert_eq!(lazy.len(), 2);
+        assert!(lazy[0].error.is_some() && lazy[1].error.is_none());
+
+        assert!(matches!(
+            progress.recv().await.unwrap(),
+            StartupProgress::PhaseStarted { lazy: false, .. }
+        ));
+        assert!(matches!(
+            progress.recv().await.unwrap(),
+            StartupProgress::PhaseFinished(_)
+        ));
+        assert!(matches!(
+            progress.recv().await.unwrap(),
+            StartupProgress::Ready { .. }
+        ));
+    }
+}
@@ -4,6 +4,6 @@ pub mod registry;
 pub mod service;
 
 pub use ast::MetaNode;
-pub use plugin::Plugin;
+pub use plugin::{Plugin, PluginManifest};
 pub use registry::{GLOBAL_REGISTRY, PluginRegistry};
 pub use service::{GLOBAL_SERVICE_MANAGER, Service, ServiceManager};
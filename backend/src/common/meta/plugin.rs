@@ -1,3 +1,40 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 插件清单（`plugin.yaml`）
+///
+/// 插件可以随附技能文件与提示词模板，加载时以 `<插件 ID>:<名称>` 为命名空间注册，
+/// 卸载时一并移除，避免与用户自定义的技能冲突。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 技能 YAML/JSON 文件（相对插件根目录）
+    #[serde(default)]
+    pub skills: Vec<String>,
+    /// 提示词模板：模板名称 -> 模板文件（相对插件根目录）
+    #[serde(default)]
+    pub prompts: BTreeMap<String, String>,
+}
+
+impl PluginManifest {
+    /// 解析 YAML 清单（JSON 是 YAML 的子集，同样适用）
+    pub fn from_yaml(content: &str) -> anyhow::Result<Self> {
+        let manifest: Self = serde_yaml::from_str(content)?;
+        if manifest.id.is_empty() || manifest.id.contains(':') {
+            anyhow::bail!("Invalid plugin id: '{}'", manifest.id);
+        }
+        Ok(manifest)
+    }
+
+    /// 插件贡献内容的命名空间名称
+    pub fn namespaced(&self, name: &str) -> String {
+        format!("{}:{}", self.id, name)
+    }
+}
+
 /// 插件基础接口
 pub trait Plugin: Send + Sync {
     /// 插件唯一名称
@@ -6,6 +43,11 @@ pub trait Plugin: Send + Sync {
     /// 插件版本
     fn version(&self) -> &str;
 
+    /// 插件清单（不随附技能与模板的插件可以没有清单）
+    fn manifest(&self) -> Option<&PluginManifest> {
+        None
+    }
+
     /// Mock 实现：获取元数据
     fn mock_metadata(&self) -> String {
         format!("{}:{}", self.name(), self.version())
@@ -30,5 +72,18 @@ mod tests {
     fn test_plugin_mock() {
         let plugin = MockPlugin;
         assert_eq!(plugin.mock_metadata(), "mock-plugin:1.0.0");
+        assert!(plugin.manifest().is_none());
+    }
+
+    #[test]
+    fn test_manifest_parsing() {
+        let manifest = PluginManifest::from_yaml(
+            "id: rust-extras\nversion: 0.2.0\nskills: [skills/clippy.yaml]\nprompts:\n  review: prompts/review.md\n",
+        )
+        .unwrap();
+        assert_eq!(manifest.skills, vec!["skills/clippy.yaml"]);
+        assert_eq!(manifest.prompts["review"], "prompts/review.md");
+        assert_eq!(manifest.namespaced("review"), "rust-extras:review");
+        assert!(PluginManifest::from_yaml("id: 'a:b'\nversion: '1'").is_err());
     }
 }
@@ -1,12 +1,9 @@
 use crate::common::meta::plugin::Plugin;
-use lazy_static::lazy_static;
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, LazyLock, RwLock};
 
-lazy_static! {
-    /// 全局插件注册表
-    pub static ref GLOBAL_REGISTRY: PluginRegistry = PluginRegistry::new();
-}
+/// 全局插件注册表（首次访问时初始化）
+pub static GLOBAL_REGISTRY: LazyLock<PluginRegistry> = LazyLock::new(PluginRegistry::new);
 
 /// 插件注册表，用于管理所有已加载的插件
 pub struct PluginRegistry {
@@ -1,14 +1,11 @@
 use crate::common::meta::ast::MetaNode;
 use async_trait::async_trait;
-use lazy_static::lazy_static;
 use std::any::Any;
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, LazyLock, RwLock};
 
-lazy_static! {
-    /// 全局服务管理器
-    pub static ref GLOBAL_SERVICE_MANAGER: ServiceManager = ServiceManager::new();
-}
+/// 全局服务管理器（首次访问时初始化）
+pub static GLOBAL_SERVICE_MANAGER: LazyLock<ServiceManager> = LazyLock::new(ServiceManager::new);
 
 /// 服务接口，所有后台服务都需要实现此接口
 #[async_trait]
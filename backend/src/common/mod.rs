@@ -1,5 +1,9 @@
 pub mod change;
 pub mod endpoint;
+pub mod event;
+pub mod i18n;
 pub mod intent;
+pub mod lifecycle;
 pub mod meta;
+pub mod prompt;
 pub mod provider;
@@ -0,0 +1,14 @@
+//! # 提示词模板
+//!
+//! Handlebars 风格的精简模板引擎，用于组装发送给模型的提示词。
+//!
+//! ## 模块
+//!
+//! - [`template`] - 模板解析与渲染（变量、局部模板、条件与循环段落）
+//! - [`store`] - 带版本的模板仓库
+
+pub mod store;
+pub mod template;
+
+pub use store::TemplateStore;
+pub use template::{Template, TemplateError};
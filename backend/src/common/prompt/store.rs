@@ -0,0 +1,168 @@
+use crate::common::prompt::template::{Template, TemplateError};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// 带版本的模板仓库
+///
+/// 同名模板的每次修改都会保存为新版本，旧版本保留以便回滚或比对；
+/// 渲染时局部模板使用其最新版本。
+#[derive(Debug, Clone, Default)]
+pub struct TemplateStore {
+    templates: BTreeMap<String, BTreeMap<u32, Template>>,
+}
+
+impl TemplateStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 解析并保存为该名称的下一个版本（从 1 开始），返回版本号
+    pub fn register(&mut self, name: &str, source: &str) -> Result<u32, TemplateError> {
+        let template = Template::parse(source)?;
+        let versions = self.templates.entry(name.to_string()).or_default();
+        let version = versions.keys().next_back().map_or(1, |v| v + 1);
+        versions.insert(version, template);
+        Ok(version)
+    }
+
+    /// 以指定版本号保存（如从文件加载历史版本），版本已存在时报错
+    pub fn register_version(
+        &mut self,
+        name: &str,
+        version: u32,
+        source: &str,
+    ) -> Result<(), TemplateError> {
+        let template = Template::parse(source)?;
+        let versions = self.templates.entry(name.to_string()).or_default();
+        if versions.contains_key(&version) {
+            return Err(TemplateError::VersionExists {
+                name: name.to_string(),
+                version,
+            });
+        }
+        versions.insert(version, template);
+        Ok(())
+    }
+
+    /// 移除模板的全部版本
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.templates.remove(name).is_some()
+    }
+
+    /// 最新版本
+    pub fn get(&self, name: &str) -> Option<&Template> {
+        self.templates.get(name)?.values().next_back()
+    }
+
+    pub fn get_version(&self, name: &str, version: u32) -> Option<&Template> {
+        self.templates.get(name)?.get(&version)
+    }
+
+    pub fn latest_version(&self, name: &str) -> Option<u32> {
+        self.templates.get(name)?.keys().next_back().copied()
+    }
+
+    /// 按升序列出版本号
+    pub fn versions(&self, name: &str) -> Vec<u32> {
+        self.templates
+            .get(name)
+            .map(|v| v.keys().copied().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.templates.keys().map(String::as_str)
+    }
+
+    /// 渲染最新版本
+    pub fn render(&self, name: &str, context: &Value) -> Result<String, TemplateError> {
+        let template = self
+            .get(name)
+            .ok_or_else(|| TemplateError::NotFound(name.to_string()))?;
+        self.render_template(template, context)
+    }
+
+    /// 渲染指定版本
+    pub fn render_version(
+        &self,
+        name: &str,
+        version: u32,
+        context: &Value,
+    ) -> Result<String, TemplateError> {
+        let template =
+            self.get_version(name, version)
+                .ok_or_else(|| TemplateError::UnknownVersion {
+                    name: name.to_string(),
+                    version,
+                })?;
+        self.render_template(template, context)
+    }
+
+    /// 渲染任意模板，局部模板从仓库中查找
+    pub fn render_template(
+        &self,
+        template: &Template,
+        context: &Value,
+    ) -> Result<String, TemplateError> {
+        template.render_with(context, &|name| self.get(name).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_versions_and_partials() {
+        let mut store = TemplateStore::new();
+        assert_eq!(store.register("header", "# {{title}}\n").unwrap(), 1);
+        assert_eq!(
+            store
+                .register("review", "{{> header}}\nReview {{file}}.")
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            store
+                .register("review", "{{> header}}\nReview {{file}} carefully.")
+                .unwrap(),
+            2
+        );
+        assert_eq!(store.versions("review"), vec![1, 2]);
+
+        let context = json!({"title": "Code review", "file": "main.rs"});
+        assert_eq!(
+            store.render("review", &context).unwrap(),
+            "# Code review\nReview main.rs carefully."
+        );
+        assert_eq!(
+            store.render_version("review", 1, &context).unwrap(),
+            "# Code review\nReview main.rs."
+        );
+        assert_eq!(
+            store.render_version("review", 3, &context),
+            Err(TemplateError::UnknownVersion {
+                name: "review".to_string(),
+                version: 3
+            })
+        );
+        assert!(matches!(
+            store.register_version("review", 2, "x"),
+            Err(TemplateError::VersionExists { .. })
+        ));
+        // 解析失败不会产生新版本
+        assert!(store.register("review", "{{#if x}}").is_err());
+        assert_eq!(store.latest_version("review"), Some(2));
+
+        store.register("loop", "{{> loop}}").unwrap();
+        assert_eq!(
+            store.render("loop", &context),
+            Err(TemplateError::RecursionLimit("loop".to_string()))
+        );
+        assert_eq!(
+            store.render("missing", &context),
+            Err(TemplateError::NotFound("missing".to_string()))
+        );
+    }
+}
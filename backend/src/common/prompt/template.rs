@@ -0,0 +1,561 @@
+use serde_json::Value;
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+/// 局部模板最大嵌套深度
+const MAX_PARTIAL_DEPTH: usize = 16;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("Template syntax error at byte {position}: {message}")]
+    Syntax { position: usize, message: String },
+
+    #[error("Section '{0}' is not closed")]
+    UnclosedSection(String),
+
+    #[error("Expected {{{{/{expected}}}}}, found {{{{/{found}}}}}")]
+    MismatchedClose { expected: String, found: String },
+
+    #[error("Unknown partial: {0}")]
+    MissingPartial(String),
+
+    #[error("Partial nesting too deep at '{0}'")]
+    RecursionLimit(String),
+
+    #[error("Template not found: {0}")]
+    NotFound(String),
+
+    #[error("Template '{name}' has no version {version}")]
+    UnknownVersion { name: String, version: u32 },
+
+    #[error("Template '{name}' version {version} already exists")]
+    VersionExists { name: String, version: u32 },
+}
+
+/// 块段落类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SectionKind {
+    If,
+    Unless,
+    Each,
+}
+
+impl SectionKind {
+    fn name(&self) -> &'static str {
+        match self {
+            SectionKind::If => "if",
+            SectionKind::Unless => "unless",
+            SectionKind::Each => "each",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Var(String),
+    Partial(String),
+    Section {
+        kind: SectionKind,
+        path: String,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// 解析中尚未闭合的段落：类型、参数、外层已解析的节点，以及遇到 `{{else}}` 前的主体
+type OpenSection = (SectionKind, String, Vec<Node>, Option<Vec<Node>>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Text(String),
+    Var(String),
+    Partial(String),
+    Open(SectionKind, String),
+    Else,
+    Close(String),
+    Comment,
+}
+
+impl Token {
+    /// 独占一行时是否连同所在行一起移除
+    fn is_standalone_candidate(&self) -> bool {
+        !matches!(self, Token::Text(_) | Token::Var(_))
+    }
+}
+
+/// Handlebars 风格的精简提示词模板
+///
+/// 支持的语法：
+/// - `{{name}}` / `{{a.b}}`：变量（缺失时为空）；`{{this}}` 为当前 `each` 元素，
+///   `{{@index}}` / `{{@first}}` / `{{@last}}` 为循环信息
+/// - `{{> partial}}`：局部模板（由 `TemplateStore` 提供）
+/// - `{{#if x}}...{{else}}...{{/if}}`、`{{#unless x}}...{{/unless}}`、`{{#each xs}}...{{/each}}`
+/// - `{{! 注释 }}`
+///
+/// 独占一行的块标签、局部模板与注释会连同换行一起移除，便于书写多行提示词。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    source: String,
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let tokens = strip_standalone(tokenize(source)?);
+        let mut stack: Vec<OpenSection> = Vec::new();
+        let mut current: Vec<Node> = Vec::new();
+        for token in tokens {
+            let node = match token {
+                Token::Text(text) if text.is_empty() => continue,
+                Token::Text(text) => Node::Text(text),
+                Token::Var(path) => Node::Var(path),
+                Token::Partial(name) => Node::Partial(name),
+                Token::Comment => continue,
+                Token::Open(kind, path) => {
+                    stack.push((kind, path, std::mem::take(&mut current), None));
+                    continue;
+                }
+                Token::Else => {
+                    let Some((kind, _, _, otherwise)) = stack.last_mut() else {
+                        return Err(syntax(source, "{{else}} outside of a section"));
+                    };
+                    if otherwise.is_some() {
+                        return Err(syntax(
+                            source,
+                            &format!("duplicate {{{{else}}}} in {}", kind.name()),
+                        ));
+                    }
+                    *otherwise = Some(std::mem::take(&mut current));
+                    continue;
+                }
+                Token::Close(name) => {
+                    let Some((kind, path, parent, then)) = stack.pop() else {
+                        return Err(syntax(source, &format!("unexpected {{{{/{}}}}}", name)));
+                    };
+                    if kind.name() != name {
+                        return Err(TemplateError::MismatchedClose {
+                            expected: kind.name().to_string(),
+                            found: name,
+                        });
+                    }
+                    let (body, otherwise) = match then {
+                        Some(body) => (body, std::mem::replace(&mut current, parent)),
+                        None => (std::mem::replace(&mut current, parent), Vec::new()),
+                    };
+                    Node::Section {
+                        kind,
+                        path,
+                        body,
+                        otherwise,
+                    }
+                }
+            };
+            current.push(node);
+        }
+        if let Some((kind, path, ..)) = stack.pop() {
+            return Err(TemplateError::UnclosedSection(format!(
+                "{} {}",
+                kind.name(),
+                path
+            )));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            nodes: current,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 模板引用的顶层变量名（不含 `this` 与 `@` 变量，也不展开局部模板）
+    pub fn variables(&self) -> BTreeSet<String> {
+        fn collect(nodes: &[Node], out: &mut BTreeSet<String>) {
+            for node in nodes {
+                match node {
+                    Node::Var(path) => insert(path, out),
+                    Node::Section {
+                        path,
+                        body,
+                        otherwise,
+                        ..
+                    } => {
+                        insert(path, out);
+                        collect(body, out);
+                        collect(otherwise, out);
+                    }
+                    Node::Text(_) | Node::Partial(_) => {}
+                }
+            }
+        }
+        fn insert(path: &str, out: &mut BTreeSet<String>) {
+            let head = path.split('.').next().unwrap_or_default();
+            if !head.is_empty() && head != "this" && !head.starts_with('@') {
+                out.insert(head.to_string());
+            }
+        }
+        let mut out = BTreeSet::new();
+        collect(&self.nodes, &mut out);
+        out
+    }
+
+    /// 是否引用了某个顶层变量
+    pub fn references(&self, name: &str) -> bool {
+        self.variables().contains(name)
+    }
+
+    /// 渲染（不支持局部模板）
+    pub fn render(&self, context: &Value) -> Result<String, TemplateError> {
+        self.render_with(context, &|_| None)
+    }
+
+    /// 渲染，局部模板由 `partials` 按名称提供
+    pub fn render_with(
+        &self,
+        context: &Value,
+        partials: &dyn Fn(&str) -> Option<Template>,
+    ) -> Result<String, TemplateError> {
+        let mut out = String::new();
+        let mut renderer = Renderer {
+            partials,
+            scopes: vec![Scope {
+                value: context,
+                meta: None,
+            }],
+            depth: 0,
+        };
+        renderer.render(&self.nodes, &mut out)?;
+        Ok(out)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Scope<'a> {
+    value: &'a Value,
+    /// `each` 中的 (索引, 总数)
+    meta: Option<(usize, usize)>,
+}
+
+struct Renderer<'a, 'p> {
+    partials: &'p dyn Fn(&str) -> Option<Template>,
+    scopes: Vec<Scope<'a>>,
+    depth: usize,
+}
+
+impl<'a> Renderer<'a, '_> {
+    fn render(&mut self, nodes: &[Node], out: &mut String) -> Result<(), TemplateError> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Var(path) => {
+                    if let Some(value) = self.lookup(path) {
+                        out.push_str(&display(&value));
+                    }
+                }
+                Node::Partial(name) => {
+                    let partial = (self.partials)(name)
+                        .ok_or_else(|| TemplateError::MissingPartial(name.clone()))?;
+                    if self.depth >= MAX_PARTIAL_DEPTH {
+                        return Err(TemplateError::RecursionLimit(name.clone()));
+                    }
+                    // 局部模板在当前作用域中渲染
+                    self.depth += 1;
+                    let result = self.render(&partial.nodes, out);
+                    self.depth -= 1;
+                    result?;
+                }
+                Node::Section {
+                    kind,
+                    path,
+                    body,
+                    otherwise,
+                } => {
+                    let value = self.lookup_ref(path);
+                    match kind {
+                        SectionKind::If | SectionKind::Unless => {
+                            let truthy = value.is_some_and(is_truthy) || self.meta_truthy(path);
+                            let branch = if truthy == (*kind == SectionKind::If) {
+                                body
+                            } else {
+                                otherwise
+                            };
+                            self.render(branch, out)?;
+                        }
+                        SectionKind::Each => {
+                            let items: Vec<&'a Value> = match value {
+                                Some(Value::Array(items)) => items.iter().collect(),
+                                Some(Value::Object(map)) => map.values().collect(),
+                                _ => Vec::new(),
+                            };
+                            if items.is_empty() {
+                                self.render(otherwise, out)?;
+                            }
+                            let total = items.len();
+                            for (index, item) in items.into_iter().enumerate() {
+                                self.scopes.push(Scope {
+                                    value: item,
+                                    meta: Some((index, total)),
+                                });
+                                let result = self.render(body, out);
+                                self.scopes.pop();
+                                result?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 查找变量；`@` 变量返回新建的值
+    fn lookup(&self, path: &str) -> Option<Value> {
+        if let Some(meta) = path.strip_prefix('@') {
+            let (index, total) = self.scopes.iter().rev().find_map(|s| s.meta)?;
+            return match meta {
+                "index" => Some(Value::from(index)),
+                "first" => Some(Value::Bool(index == 0)),
+                "last" => Some(Value::Bool(index + 1 == total)),
+                _ => None,
+            };
+        }
+        self.lookup_ref(path).cloned()
+    }
+
+    fn meta_truthy(&self, path: &str) -> bool {
+        path.starts_with('@') && self.lookup(path).is_some_and(|v| is_truthy(&v))
+    }
+
+    /// 从最内层作用域向外查找首段名称，再逐级取字段
+    fn lookup_ref(&self, path: &str) -> Option<&'a Value> {
+        let innermost = self.scopes.last()?.value;
+        if path == "this" || path == "." {
+            return Some(innermost);
+        }
+        let path = path.strip_prefix("this.").unwrap_or(path);
+        let mut segments = path.split('.');
+        let head = segments.next()?;
+        let mut value = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.value.get(head))?;
+        for segment in segments {
+            value = match value {
+                Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+                other => other.get(segment)?,
+            };
+        }
+        Some(value)
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn syntax(source: &str, message: &str) -> TemplateError {
+    TemplateError::Syntax {
+        position: source.len(),
+        message: message.to_string(),
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, TemplateError> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let position = source.len() - rest.len() + start;
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| TemplateError::Syntax {
+            position,
+            message: "unterminated tag".to_string(),
+        })?;
+        let tag = after[..end].trim();
+        let error = |message: &str| TemplateError::Syntax {
+            position,
+            message: message.to_string(),
+        };
+        let token = if tag.starts_with('!') {
+            Token::Comment
+        } else if let Some(name) = tag.strip_prefix('>') {
+            Token::Partial(name.trim().to_string())
+        } else if let Some(open) = tag.strip_prefix('#') {
+            let (kind, path) = open.split_once(char::is_whitespace).unwrap_or((open, ""));
+            let kind = match kind {
+                "if" => SectionKind::If,
+                "unless" => SectionKind::Unless,
+                "each" => SectionKind::Each,
+                other => return Err(error(&format!("unknown block helper '{}'", other))),
+            };
+            let path = path.trim();
+            if path.is_empty() {
+                return Err(error(&format!(
+                    "{{{{#{}}}}} requires an argument",
+                    kind.name()
+                )));
+            }
+            Token::Open(kind, path.to_string())
+        } else if let Some(name) = tag.strip_prefix('/') {
+            Token::Close(name.trim().to_string())
+        } else if tag == "else" {
+            Token::Else
+        } else if tag.is_empty() {
+            return Err(error("empty tag"));
+        } else {
+            Token::Var(tag.to_string())
+        };
+        tokens.push(token);
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+/// 移除独占一行的块标签所在行的缩进与换行
+fn strip_standalone(tokens: Vec<Token>) -> Vec<Token> {
+    let len = tokens.len();
+    // 每段文本保留的 [起, 止) 字节范围
+    let mut ranges: Vec<(usize, usize)> = tokens
+        .iter()
+        .map(|t| match t {
+            Token::Text(text) => (0, text.len()),
+            _ => (0, 0),
+        })
+        .collect();
+    for i in 0..len {
+        if !tokens[i].is_standalone_candidate() {
+            continue;
+        }
+        let left = match i.checked_sub(1).map(|p| &tokens[p]) {
+            None => Some(0),
+            Some(Token::Text(text)) => match text.rfind('\n') {
+                Some(nl) if text[nl + 1..].trim().is_empty() => Some(nl + 1),
+                None if i == 1 && text.trim().is_empty() => Some(0),
+                _ => None,
+            },
+            Some(_) => None,
+        };
+        let right = match tokens.get(i + 1) {
+            None => Some(0),
+            Some(Token::Text(text)) => match text.find('\n') {
+                Some(nl) if text[..nl].trim().is_empty() => Some(nl + 1),
+                None if i + 2 == len && text.trim().is_empty() => Some(text.len()),
+                _ => None,
+            },
+            Some(_) => None,
+        };
+        if let (Some(left), Some(right)) = (left, right) {
+            if i > 0 {
+                ranges[i - 1].1 = left;
+            }
+            if i + 1 < len {
+                ranges[i + 1].0 = right;
+            }
+        }
+    }
+    tokens
+        .into_iter()
+        .zip(ranges)
+        .map(|(token, (start, end))| match token {
+            Token::Text(text) => Token::Text(text[start.min(end)..end].to_string()),
+            other => other,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_variables_sections_and_loops() {
+        let template = Template::parse(
+            "Hello {{user.name}}!\n{{#if admin}}\nYou are an admin.\n{{else}}\nWelcome.\n{{/if}}\n\
+             {{#each files}}\n{{@index}}. {{this}}{{#unless @last}},{{/unless}}\n{{/each}}\n\
+             {{#each empty}}x{{else}}none{{/each}}{{! ignored }}",
+        )
+        .unwrap();
+        let out = template
+            .render(&json!({
+                "user": {"name": "Ada"},
+                "admin": false,
+                "files": ["a.rs", "b.rs"],
+                "empty": []
+            }))
+            .unwrap();
+        assert_eq!(out, "Hello Ada!\nWelcome.\n0. a.rs,\n1. b.rs\nnone");
+        assert_eq!(
+            template.variables().into_iter().collect::<Vec<_>>(),
+            vec!["admin", "empty", "files", "user"]
+        );
+    }
+
+    #[test]
+    fn test_outer_scope_lookup_and_values() {
+        let template = Template::parse(
+            "{{#each items}}{{name}}@{{lang}} {{/each}}[{{missing}}] {{count}} {{tags}}",
+        )
+        .unwrap();
+        let out = template
+            .render(&json!({
+                "lang": "rust",
+                "items": [{"name": "x"}, {"name": "y", "lang": "go"}],
+                "count": 2,
+                "tags": ["a"]
+            }))
+            .unwrap();
+        assert_eq!(out, "x@rust y@go [] 2 [\"a\"]");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Template::parse("{{#if a}}x"),
+            Err(TemplateError::UnclosedSection(_))
+        ));
+        assert!(matches!(
+            Template::parse("{{#if a}}x{{/each}}"),
+            Err(TemplateError::MismatchedClose { .. })
+        ));
+        assert!(matches!(
+            Template::parse("{{/if}}"),
+            Err(TemplateError::Syntax { .. })
+        ));
+        assert!(matches!(
+            Template::parse("{{name"),
+            Err(TemplateError::Syntax { position: 0, .. })
+        ));
+        assert!(matches!(
+            Template::parse("{{#with a}}{{/with}}"),
+            Err(TemplateError::Syntax { .. })
+        ));
+        assert_eq!(
+            Template::parse("{{> header}}").unwrap().render(&json!({})),
+            Err(TemplateError::MissingPartial("header".to_string()))
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use crate::common::provider::traits::{
+    ExecuteOptions, ExecuteResult, ExecutionProvider, OutputStream,
+};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// 在进程层限制可执行命令的执行提供者
+///
+/// 命令必须与白名单中的某项相同或以其加空格开头，且不含 shell 元字符，
+/// 否则在交给内部提供者之前返回错误；白名单为空时拒绝所有命令。
+/// 工具沙箱只能检查工具声明的命令，这一层兜住直接经执行提供者运行的命令。
+pub struct CommandAllowlist {
+    inner: Arc<dyn ExecutionProvider>,
+    allowed: Vec<String>,
+}
+
+impl CommandAllowlist {
+    pub fn new(inner: Arc<dyn ExecutionProvider>, allowed: Vec<String>) -> Self {
+        Self {
+            inner,
+            allowed: allowed.into_iter().map(|a| a.trim().to_string()).collect(),
+        }
+    }
+
+    fn check(&self, command: &str) -> anyhow::Result<()> {
+        if permits_command(&self.allowed, command) {
+            Ok(())
+        } else {
+            anyhow::bail!("command '{}' is not allowed", command.trim())
+        }
+    }
+}
+
+/// 命令与白名单项相同，或以白名单项加空格开头；含 shell 元字符的命令一律不允许
+pub fn permits_command(allowed: &[String], command: &str) -> bool {
+    let command = command.trim();
+    if command.contains([';', '|', '&', '$', '`', '<', '>', '\n', '\r']) {
+        return false;
+    }
+    allowed.iter().any(|a| {
+        command == a
+            || command
+                .strip_prefix(a.as_str())
+                .is_some_and(|rest| rest.starts_with(' '))
+    })
+}
+
+#[async_trait]
+impl ExecutionProvider for CommandAllowlist {
+    async fn execute(
+        &self,
+        command: &str,
+        options: ExecuteOptions,
+    ) -> anyhow::Result<ExecuteResult> {
+        self.check(command)?;
+        self.inner.execute(command, options).await
+    }
+
+    async fn execute_stream(
+        &self,
+        command: &str,
+        options: ExecuteOptions,
+    ) -> anyhow::Result<OutputStream> {
+        self.check(command)?;
+        self.inner.execute_stream(command, options).await
+    }
+
+    async fn kill(&self, task_id: &str) -> anyhow::Result<()> {
+        self.inner.kill(task_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo;
+
+    #[async_trait]
+    impl ExecutionProvider for Echo {
+        async fn execute(
+            &self,
+            command: &str,
+            _options: ExecuteOptions,
+        ) -> anyhow::Result<ExecuteResult> {
+            Ok(ExecuteResult {
+                exit_code: 0,
+                stdout: command.to_string(),
+                stderr: String::new(),
+            })
+        }
+
+        async fn kill(&self, _task_id: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allowlist_rejects_commands_before_they_run() {
+        let process = CommandAllowlist::new(Arc::new(Echo), vec!["cargo test".into()]);
+        let ok = process
+            .execute("cargo test --lib", ExecuteOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(ok.stdout, "cargo test --lib");
+
+        for command in [
+            "cargo testx",
+            "rm -rf /",
+            "cargo test; rm -rf /",
+            "cargo test $(id)",
+        ] {
+            assert!(
+                process
+                    .execute(command, ExecuteOptions::default())
+                    .await
+                    .is_err(),
+                "{}",
+                command
+            );
+        }
+        assert!(
+            process
+                .execute_stream("rm -rf /", ExecuteOptions::default())
+                .await
+                .is_err()
+        );
+
+        let none = CommandAllowlist::new(Arc::new(Echo), vec![]);
+        assert!(
+            none.execute("cargo test", ExecuteOptions::default())
+                .await
+                .is_err()
+        );
+    }
+}
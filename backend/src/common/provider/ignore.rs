@@ -0,0 +1,260 @@
+use crate::common::provider::traits::StorageProvider;
+use regex::Regex;
+
+/// 始终跳过的目录
+const ALWAYS_IGNORED: &[&str] = &[".git"];
+
+/// 一条 `.gitignore` 规则
+#[derive(Debug, Clone)]
+struct Rule {
+    /// 规则所在 `.gitignore` 的目录（工作区根目录为空）
+    base: String,
+    pattern: Regex,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl Rule {
+    /// 解析一行规则，空行与注释返回 `None`
+    fn parse(base: &str, line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        // 含 `/` 的模式相对于 `.gitignore` 所在目录，否则匹配任意层级的名称
+        let anchored = line.contains('/');
+        let glob = glob_to_regex(line.trim_start_matches('/'));
+        let pattern = if anchored {
+            format!("^{}$", glob)
+        } else {
+            format!("^(?:.*/)?{}$", glob)
+        };
+        Some(Self {
+            base: base.trim_matches('/').to_string(),
+            pattern: Regex::new(&pattern).ok()?,
+            negated,
+            dir_only,
+        })
+    }
+
+    /// 规则是否匹配 `path`（相对于工作区根目录）；不在规则目录下的路径返回 `false`
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let relative = if self.base.is_empty() {
+            Some(path)
+        } else {
+            path.strip_prefix(self.base.as_str())
+                .and_then(|rest| rest.strip_prefix('/'))
+        };
+        relative.is_some_and(|relative| self.pattern.is_match(relative))
+    }
+}
+
+/// 将 gitignore 通配符转换为正则表达式（不含首尾锚点）
+fn glob_to_regex(glob: &str) -> String {
+    let mut out = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    out.push_str("(?:.*/)?");
+                } else {
+                    out.push_str(".*");
+                }
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            '[' => {
+                let mut class = String::from("[");
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    class.push('^');
+                }
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        class.push('\\');
+                    }
+                    class.push(c);
+                }
+                if closed {
+                    class.push(']');
+                    out.push_str(&class);
+                } else {
+                    out.push_str(&regex::escape(&class));
+                }
+            }
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    out.push_str(&regex::escape(&escaped.to_string()));
+                }
+            }
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    out
+}
+
+/// 从各级 `.gitignore` 收集的忽略规则
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 加入 `base` 目录下 `.gitignore` 的内容；后加入（更深层）的规则优先
+    pub fn add(&mut self, base: &str, content: &str) {
+        self.rules
+            .extend(content.lines().filter_map(|line| Rule::parse(base, line)));
+    }
+
+    /// 路径是否被忽略：最后一条匹配的规则决定结果，`!` 规则重新包含
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        if is_dir && ALWAYS_IGNORED.contains(&name) {
+            return true;
+        }
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+/// 递归列出 `root` 下未被 `.gitignore` 忽略的文件（按路径排序）
+///
+/// 规则经存储提供者读取，本地与远程工作区行为一致；从子目录开始时同样遵循上层目录的规则。
+/// 被忽略的目录不再展开，与 git 一样无法通过 `!` 重新包含其中的文件。
+pub async fn walk(storage: &dyn StorageProvider, root: &str) -> anyhow::Result<Vec<String>> {
+    let mut rules = IgnoreRules::new();
+    let root = root.trim_matches('/');
+    let mut base = String::new();
+    for part in root.split('/').filter(|p| !p.is_empty()) {
+        let path = join(&base, ".gitignore");
+        if storage.exists(&path).await.unwrap_or(false)
+            && let Ok(content) = storage.read_file(&path).await
+        {
+            rules.add(&base, &String::from_utf8_lossy(&content));
+        }
+        base = join(&base, part);
+    }
+
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_string()];
+    while let Some(dir) = pending.pop() {
+        let entries = storage.list_dir(&dir).await?;
+        if let Some(gitignore) = entries
+            .iter()
+            .find(|e| !e.is_dir && e.path.rsplit('/').next() == Some(".gitignore"))
+        {
+            let base = gitignore
+                .path
+                .rsplit_once('/')
+                .map(|(base, _)| base)
+                .unwrap_or_default();
+            if let Ok(content) = storage.read_file(&gitignore.path).await {
+                rules.add(base, &String::from_utf8_lossy(&content));
+            }
+        }
+        for entry in entries {
+            if rules.is_ignored(&entry.path, entry.is_dir) {
+                continue;
+            }
+            if entry.is_dir {
+                pending.push(entry.path);
+            } else {
+                files.push(entry.path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn join(base: &str, name: &str) -> String {
+    if base.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", base, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+
+    #[test]
+    fn test_gitignore_patterns() {
+        let mut rules = IgnoreRules::new();
+        rules.add(
+            "",
+            "# build output\n/target\n*.log\n!keep.log\nbuild/\ndocs/**/*.tmp\n",
+        );
+        rules.add("web", "dist\n");
+
+        assert!(rules.is_ignored("target", true));
+        assert!(!rules.is_ignored("src/target", true));
+        assert!(rules.is_ignored("src/debug.log", false));
+        assert!(!rules.is_ignored("src/keep.log", false));
+        assert!(rules.is_ignored("app/build", true));
+        assert!(!rules.is_ignored("app/build", false));
+        assert!(rules.is_ignored("docs/a/b/c.tmp", false));
+        assert!(rules.is_ignored("docs/c.tmp", false));
+        assert!(rules.is_ignored("web/dist", true));
+        assert!(!rules.is_ignored("dist", true));
+        assert!(rules.is_ignored(".git", true));
+    }
+
+    #[tokio::test]
+    async fn test_walk_skips_ignored_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalFileSystem::new(dir.path());
+        for (path, content) in [
+            (".gitignore", "target/\n*.log\n"),
+            ("src/main.rs", "fn main() {}"),
+            ("src/trace.log", ""),
+            ("target/debug/app", ""),
+            ("web/.gitignore", "dist\n"),
+            ("web/dist/app.js", ""),
+            ("web/index.js", ""),
+            (".git/HEAD", ""),
+        ] {
+            storage.write_file(path, content.as_bytes()).await.unwrap();
+        }
+
+        assert_eq!(
+            walk(&storage, "").await.unwrap(),
+            vec![
+                ".gitignore",
+                "src/main.rs",
+                "web/.gitignore",
+                "web/index.js"
+            ]
+        );
+        // 从子目录开始时遵循上层目录的规则
+        assert_eq!(walk(&storage, "src").await.unwrap(), vec!["src/main.rs"]);
+    }
+}
@@ -1,8 +1,11 @@
 use crate::common::provider::traits::{FileMetadata, StorageProvider};
 use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::time::UNIX_EPOCH;
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 
 pub struct LocalFileSystem {
     base_path: PathBuf,
@@ -112,8 +115,85 @@ impl StorageProvider for LocalFileSystem {
         }
         Ok(())
     }
+
+    async fn read_range(&self, path: &str, offset: u64, len: usize) -> anyhow::Result<Vec<u8>> {
+        let full_path = self.full_path(path);
+        tokio::task::spawn_blocking(move || {
+            let mut file = std::fs::File::open(&full_path)?;
+            let size = file.metadata()?.len();
+            let start = offset.min(size);
+            let len = len.min((size - start) as usize);
+            file.seek(SeekFrom::Start(start))?;
+            let mut content = vec![0; len];
+            file.read_exact(&mut content)?;
+            Ok(content)
+        })
+        .await?
+    }
+
+    async fn hash_file(&self, path: &str) -> anyhow::Result<String> {
+        let full_path = self.full_path(path);
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::File::open(&full_path)?;
+            let mut reader = BufReader::with_capacity(HASH_CHUNK, file);
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut reader, &mut hasher)?;
+            Ok(format!("{:x}", hasher.finalize()))
+        })
+        .await?
+    }
+
+    async fn write_file_durable(&self, path: &str, content: &[u8]) -> anyhow::Result<()> {
+        let full_path = self.full_path(path);
+        let content = content.to_vec();
+        tokio::task::spawn_blocking(move || {
+            let parent = full_path
+                .parent()
+                .ok_or_else(|| anyhow::anyhow!("Invalid path: {}", full_path.display()))?;
+            std::fs::create_dir_all(parent)?;
+            let mut name = full_path.file_name().unwrap_or_default().to_os_string();
+            name.push(".tmp");
+            let temp = full_path.with_file_name(name);
+            let mut file = std::fs::File::create(&temp)?;
+            file.write_all(&content)?;
+            file.sync_all()?;
+            std::fs::rename(&temp, &full_path)?;
+            // 同步目录项，使改名本身在断电后依然有效
+            #[cfg(unix)]
+            std::fs::File::open(parent)?.sync_all()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    async fn append_file(&self, path: &str, content: &[u8]) -> anyhow::Result<()> {
+        let full_path = self.full_path(path);
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(full_path)
+            .await?;
+        file.write_all(content).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    async fn copy_file(&self, from: &str, to: &str) -> anyhow::Result<u64> {
+        let target = self.full_path(to);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        // Linux 上 `fs::copy` 使用 copy_file_range/sendfile，在内核态完成复制
+        Ok(fs::copy(self.full_path(from), target).await?)
+    }
 }
 
+/// 哈希时每次读取的块大小，内存占用与文件大小无关
+const HASH_CHUNK: usize = 1024 * 1024;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,6 +219,34 @@ mod tests {
         assert_eq!(list.len(), 1);
         assert_eq!(list[0].path, "test.txt");
 
+        // 测试按范围读取、哈希与复制
+        assert_eq!(fs.read_range("test.txt", 6, 100).await.unwrap(), b"world");
+        assert_eq!(
+            fs.hash_file("test.txt").await.unwrap(),
+            format!("{:x}", Sha256::digest(b"hello world"))
+        );
+        assert_eq!(fs.copy_file("test.txt", "copy/test.txt").await.unwrap(), 11);
+        assert_eq!(fs.read_file("copy/test.txt").await.unwrap(), b"hello world");
+        fs.write_file("empty.txt", b"").await.unwrap();
+        assert!(fs.read_range("empty.txt", 0, 10).await.unwrap().is_empty());
+        fs.delete("empty.txt", false).await.unwrap();
+        fs.delete("copy", true).await.unwrap();
+
+        // 持久写入覆盖原内容，且不残留临时文件
+        fs.write_file_durable("wal/1.json", b"first").await.unwrap();
+        fs.write_file_durable("wal/1.json", b"second")
+            .await
+            .unwrap();
+        assert_eq!(fs.read_file("wal/1.json").await.unwrap(), b"second");
+        assert_eq!(fs.list_dir("wal").await.unwrap().len(), 1);
+        fs.delete("wal", true).await.unwrap();
+
+        // 追加写入创建文件并保留原内容
+        fs.append_file("log/a.jsonl", b"one\n").await.unwrap();
+        fs.append_file("log/a.jsonl", b"two\n").await.unwrap();
+        assert_eq!(fs.read_file("log/a.jsonl").await.unwrap(), b"one\ntwo\n");
+        fs.delete("log", true).await.unwrap();
+
         // 测试删除
         fs.delete("test.txt", false).await.unwrap();
         assert!(!fs.exists("test.txt").await.unwrap());
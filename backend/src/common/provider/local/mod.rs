@@ -1,2 +1,3 @@
 pub mod filesystem;
 pub mod process;
+pub mod sandbox;
@@ -1,10 +1,73 @@
-use crate::common::provider::traits::{ExecuteOptions, ExecuteResult, ExecutionProvider};
+use crate::common::provider::traits::{
+    ExecuteOptions, ExecuteResult, ExecutionProvider, OutputLine, OutputStream,
+};
 use async_trait::async_trait;
 use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::Command;
+use tokio::sync::mpsc;
 
 pub struct LocalProcess;
 
+/// 按空白拆分命令并应用执行选项
+fn build_command(command: &str, options: ExecuteOptions) -> anyhow::Result<Command> {
+    let mut parts = command.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Empty command"))?;
+    let args: Vec<&str> = parts.collect();
+
+    let mut cmd = Command::new(program);
+    cmd.args(args).stdout(Stdio::piped()).stderr(Stdio::piped());
+
+    if let Some(cwd) = options.cwd {
+        cmd.current_dir(cwd);
+    }
+
+    for (key, value) in options.env {
+        cmd.env(key, value);
+    }
+    Ok(cmd)
+}
+
+/// 将管道中的各行转发到通道，读到 EOF 或接收端关闭时结束
+fn forward_lines<R>(
+    reader: R,
+    tx: mpsc::UnboundedSender<anyhow::Result<OutputLine>>,
+    wrap: fn(String) -> OutputLine,
+) where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        loop {
+            let item = match lines.next_line().await {
+                Ok(Some(line)) => Ok(wrap(line)),
+                Ok(None) => break,
+                Err(e) => Err(e.into()),
+            };
+            if tx.send(item).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// 被丢弃时终止以该进程为组长的整个进程组
+struct ProcessGroup(Option<u32>);
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.0 {
+            // SAFETY: killpg 只读取参数；进程组已不存在时返回 ESRCH，无需处理
+            unsafe {
+                libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+    }
+}
+
 #[async_trait]
 impl ExecutionProvider for LocalProcess {
     async fn execute(
@@ -12,24 +75,7 @@ impl ExecutionProvider for LocalProcess {
         command: &str,
         options: ExecuteOptions,
     ) -> anyhow::Result<ExecuteResult> {
-        let mut parts = command.split_whitespace();
-        let program = parts
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("Empty command"))?;
-        let args: Vec<&str> = parts.collect();
-
-        let mut cmd = Command::new(program);
-        cmd.args(args).stdout(Stdio::piped()).stderr(Stdio::piped());
-
-        if let Some(cwd) = options.cwd {
-            cmd.current_dir(cwd);
-        }
-
-        for (key, value) in options.env {
-            cmd.env(key, value);
-        }
-
-        let output = cmd.output().await?;
+        let output = build_command(command, options)?.output().await?;
 
         Ok(ExecuteResult {
             exit_code: output.status.code().unwrap_or(-1),
@@ -38,6 +84,37 @@ impl ExecutionProvider for LocalProcess {
         })
     }
 
+    /// stdout 与 stderr 按到达顺序交错产出；丢弃流时终止子进程
+    ///
+    /// Unix 上命令在新的进程组中运行，丢弃流时整组终止，
+    /// 由脚本或包管理器派生的子进程（如 `npm run dev` 启动的服务器）不会残留。
+    async fn execute_stream(
+        &self,
+        command: &str,
+        options: ExecuteOptions,
+    ) -> anyhow::Result<OutputStream> {
+        let mut command = build_command(command, options)?;
+        #[cfg(unix)]
+        command.process_group(0);
+        let mut child = command.kill_on_drop(true).spawn()?;
+        let group = ProcessGroup(child.id());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        if let Some(stdout) = child.stdout.take() {
+            forward_lines(stdout, tx.clone(), OutputLine::Stdout);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward_lines(stderr, tx, OutputLine::Stderr);
+        }
+        Ok(Box::pin(async_stream::stream! {
+            // 子进程随流一起被丢弃，进程组先于子进程终止
+            let _child = child;
+            let _group = group;
+            while let Some(item) = rx.recv().await {
+                yield item;
+            }
+        }))
+    }
+
     async fn kill(&self, _task_id: &str) -> anyhow::Result<()> {
         // 在本地进程实现中，kill 通常需要更复杂的任务追踪
         // 目前先做简单的 Mock
@@ -85,4 +162,56 @@ mod tests {
         let result = process.execute(cmd, options).await.unwrap();
         assert!(result.stdout.contains("TEST_VAR=test_value"));
     }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_local_process_stream() {
+        use futures::StreamExt;
+
+        let stream = LocalProcess
+            .execute_stream("echo hello stream", ExecuteOptions::default())
+            .await
+            .unwrap();
+        let lines: Vec<OutputLine> = stream.map(Result::unwrap).collect().await;
+        assert_eq!(lines, vec![OutputLine::Stdout("hello stream".to_string())]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_dropping_stream_kills_the_process_group() {
+        use futures::StreamExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("serve.sh");
+        std::fs::write(&script, "sleep 30 &\necho $!\nwait\n").unwrap();
+        let mut stream = LocalProcess
+            .execute_stream(
+                &format!("sh {}", script.display()),
+                ExecuteOptions::default(),
+            )
+            .await
+            .unwrap();
+        let Some(Ok(OutputLine::Stdout(pid))) = stream.next().await else {
+            panic!("missing pid");
+        };
+        let stat = format!("/proc/{}/stat", pid.trim());
+        let alive = || {
+            std::fs::read_to_string(&stat).is_ok_and(|s| {
+                s.rsplit(") ")
+                    .next()
+                    .is_some_and(|rest| !rest.starts_with('Z'))
+            })
+        };
+        assert!(alive());
+
+        // 丢弃流后 shell 派生的孙进程同样被终止
+        drop(stream);
+        for _ in 0..100 {
+            if !alive() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("grandchild {} survived", pid.trim());
+    }
 }
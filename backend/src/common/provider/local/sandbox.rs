@@ -0,0 +1,423 @@
+use crate::common::provider::local::filesystem::LocalFileSystem;
+use crate::common::provider::traits::{FileMetadata, StorageProvider};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+use thiserror::Error;
+
+/// 工作区内的沙箱配置文件
+pub const SANDBOX_CONFIG_PATH: &str = ".zhiyun/sandbox.json";
+
+static MOUNT_NAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_-]+$").unwrap());
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SandboxError {
+    #[error("Path escapes the workspace: {0}")]
+    OutsideWorkspace(String),
+
+    #[error("Path resolves through a symlink outside the workspace: {0}")]
+    SymlinkEscape(String),
+
+    #[error("Unknown mount: @{0}")]
+    UnknownMount(String),
+
+    #[error("Mount @{0} is read-only")]
+    ReadOnlyMount(String),
+
+    #[error("Invalid mount '{name}': {reason}")]
+    InvalidMount { name: String, reason: String },
+}
+
+/// 允许访问的工作区外目录，以 `@name/...` 的虚拟路径访问
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MountPoint {
+    pub name: String,
+    /// 宿主机上的目录
+    pub path: PathBuf,
+    #[serde(default = "default_read_only")]
+    pub read_only: bool,
+}
+
+fn default_read_only() -> bool {
+    true
+}
+
+/// 项目的沙箱配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    #[serde(default)]
+    pub mounts: Vec<MountPoint>,
+}
+
+impl SandboxConfig {
+    /// 读取 JSON 配置，文件不存在时返回空配置
+    pub async fn load(storage: &dyn StorageProvider, path: &str) -> anyhow::Result<Self> {
+        if !storage.exists(path).await? {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_slice(&storage.read_file(path).await?)?)
+    }
+}
+
+/// 解析后的路径
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPath {
+    /// 所在挂载点；`None` 表示工作区
+    pub mount: Option<String>,
+    /// 相对工作区（或挂载点）根目录的规范化路径，使用 `/` 分隔
+    pub relative: String,
+    /// 宿主机上的路径
+    pub host: PathBuf,
+}
+
+impl ResolvedPath {
+    /// 对 Agent 展示的虚拟路径
+    pub fn virtual_path(&self) -> String {
+        match &self.mount {
+            Some(name) if self.relative.is_empty() => format!("@{}", name),
+            Some(name) => format!("@{}/{}", name, self.relative),
+            None => self.relative.clone(),
+        }
+    }
+}
+
+/// 文件工具的路径沙箱
+///
+/// 所有路径都被映射到工作区根目录下：绝对路径若位于工作区（或挂载目录）内则转换为相对路径，
+/// 否则视为以工作区为根的虚拟路径；`..` 越过根目录、经符号链接指向根目录之外的路径被拒绝。
+/// 工作区外的目录只能通过显式配置的挂载点访问。
+#[derive(Debug, Clone)]
+pub struct PathSandbox {
+    root: PathBuf,
+    mounts: Vec<MountPoint>,
+}
+
+impl PathSandbox {
+    pub fn new(root: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(Self {
+            root: std::fs::canonicalize(root)?,
+            mounts: Vec::new(),
+        })
+    }
+
+    pub fn from_config(root: impl AsRef<Path>, config: &SandboxConfig) -> anyhow::Result<Self> {
+        config
+            .mounts
+            .iter()
+            .try_fold(Self::new(root)?, |sandbox, mount| {
+                sandbox.with_mount(mount.clone())
+            })
+    }
+
+    /// 添加挂载点；目录必须存在
+    pub fn with_mount(mut self, mut mount: MountPoint) -> anyhow::Result<Self> {
+        let invalid = |reason: String| SandboxError::InvalidMount {
+            name: mount.name.clone(),
+            reason,
+        };
+        if !MOUNT_NAME.is_match(&mount.name) {
+            return Err(invalid("name must be alphanumeric".to_string()).into());
+        }
+        if self.mounts.iter().any(|m| m.name == mount.name) {
+            return Err(invalid("duplicate name".to_string()).into());
+        }
+        mount.path = std::fs::canonicalize(&mount.path).map_err(|e| invalid(e.to_string()))?;
+        self.mounts.push(mount);
+        Ok(self)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn mounts(&self) -> &[MountPoint] {
+        &self.mounts
+    }
+
+    /// 将工具传入的路径解析到工作区或挂载点内
+    pub fn resolve(&self, path: &str) -> Result<ResolvedPath, SandboxError> {
+        let (mount, relative) = self.locate(path)?;
+        let base = match &mount {
+            Some(mount) => &mount.path,
+            None => &self.root,
+        };
+        let host = relative
+            .iter()
+            .fold(base.clone(), |host, part| host.join(part));
+        if !stays_within(base, &host) {
+            return Err(SandboxError::SymlinkEscape(path.to_string()));
+        }
+        Ok(ResolvedPath {
+            mount: mount.map(|m| m.name.clone()),
+            relative: relative.join("/"),
+            host,
+        })
+    }
+
+    /// 解析并要求目标可写
+    pub fn resolve_writable(&self, path: &str) -> Result<ResolvedPath, SandboxError> {
+        let resolved = self.resolve(path)?;
+        if let Some(name) = &resolved.mount
+            && self.mount(name).is_some_and(|m| m.read_only)
+        {
+            return Err(SandboxError::ReadOnlyMount(name.clone()));
+        }
+        Ok(resolved)
+    }
+
+    fn mount(&self, name: &str) -> Option<&MountPoint> {
+        self.mounts.iter().find(|m| m.name == name)
+    }
+
+    /// 确定路径所属的根目录，并按词法规范化为路径分量
+    fn locate(&self, path: &str) -> Result<(Option<&MountPoint>, Vec<String>), SandboxError> {
+        let host = Path::new(path);
+        if host.is_absolute() {
+            if let Ok(rest) = host.strip_prefix(&self.root) {
+                return Ok((None, normalize(rest, path)?));
+            }
+            for mount in &self.mounts {
+                if let Ok(rest) = host.strip_prefix(&mount.path) {
+                    return Ok((Some(mount), normalize(rest, path)?));
+                }
+            }
+        }
+        let trimmed = path.trim_start_matches(['/', '\\']);
+        if let Some(rest) = trimmed.strip_prefix('@') {
+            let (name, rest) = rest.split_once(['/', '\\']).unwrap_or((rest, ""));
+            let mount = self
+                .mount(name)
+                .ok_or_else(|| SandboxError::UnknownMount(name.to_string()))?;
+            return Ok((Some(mount), normalize(Path::new(rest), path)?));
+        }
+        Ok((None, normalize(Path::new(trimmed), path)?))
+    }
+}
+
+/// 按词法处理 `.` 与 `..`，越过根目录时报错
+fn normalize(path: &Path, original: &str) -> Result<Vec<String>, SandboxError> {
+    let mut parts: Vec<String> = Vec::new();
+    for part in path.to_string_lossy().split(['/', '\\']) {
+        match Path::new(part).components().next() {
+            None | Some(Component::CurDir) => {}
+            Some(Component::ParentDir) => {
+                parts
+                    .pop()
+                    .ok_or_else(|| SandboxError::OutsideWorkspace(original.to_string()))?;
+            }
+            Some(Component::Normal(_)) => parts.push(part.to_string()),
+            // 盘符等前缀只会出现在未落入任何根目录的绝对路径中
+            Some(Component::Prefix(_) | Component::RootDir) => {
+                return Err(SandboxError::OutsideWorkspace(original.to_string()));
+            }
+        }
+    }
+    Ok(parts)
+}
+
+/// 路径中已存在的最深部分经符号链接解析后是否仍位于 `base` 之内；
+/// 无法解析的符号链接（悬空链接）视为越界，避免写入时跟随到外部
+fn stays_within(base: &Path, host: &Path) -> bool {
+    let mut probe = host.to_path_buf();
+    loop {
+        if std::fs::symlink_metadata(&probe).is_ok() {
+            return std::fs::canonicalize(&probe).is_ok_and(|real| real.starts_with(base));
+        }
+        if !probe.pop() || !probe.starts_with(base) {
+            return true;
+        }
+    }
+}
+
+/// 经过路径沙箱的本地存储，供 Agent 文件工具使用
+pub struct SandboxedStorage {
+    sandbox: PathSandbox,
+    workspace: LocalFileSystem,
+    mounts: Vec<(String, LocalFileSystem)>,
+}
+
+impl SandboxedStorage {
+    pub fn new(sandbox: PathSandbox) -> Self {
+        Self {
+            workspace: LocalFileSystem::new(sandbox.root()),
+            mounts: sandbox
+                .mounts()
+                .iter()
+                .map(|m| (m.name.clone(), LocalFileSystem::new(&m.path)))
+                .collect(),
+            sandbox,
+        }
+    }
+
+    pub fn sandbox(&self) -> &PathSandbox {
+        &self.sandbox
+    }
+
+    fn storage(&self, resolved: &ResolvedPath) -> &LocalFileSystem {
+        resolved
+            .mount
+            .as_ref()
+            .and_then(|name| self.mounts.iter().find(|(n, _)| n == name))
+            .map_or(&self.workspace, |(_, storage)| storage)
+    }
+}
+
+#[async_trait]
+impl StorageProvider for SandboxedStorage {
+    fn id(&self) -> &str {
+        "sandboxed-fs"
+    }
+
+    async fn read_file(&self, path: &str) -> anyhow::Result<Vec<u8>> {
+        let resolved = self.sandbox.resolve(path)?;
+        self.storage(&resolved).read_file(&resolved.relative).await
+    }
+
+    async fn write_file(&self, path: &str, content: &[u8]) -> anyhow::Result<()> {
+        let resolved = self.sandbox.resolve_writable(path)?;
+        self.storage(&resolved)
+            .write_file(&resolved.relative, content)
+            .await
+    }
+
+    async fn write_file_durable(&self, path: &str, content: &[u8]) -> anyhow::Result<()> {
+        let resolved = self.sandbox.resolve_writable(path)?;
+        self.storage(&resolved)
+            .write_file_durable(&resolved.relative, content)
+            .await
+    }
+
+    async fn append_file(&self, path: &str, content: &[u8]) -> anyhow::Result<()> {
+        let resolved = self.sandbox.resolve_writable(path)?;
+        self.storage(&resolved)
+            .append_file(&resolved.relative, content)
+            .await
+    }
+
+    async fn delete(&self, path: &str, recursive: bool) -> anyhow::Result<()> {
+        let resolved = self.sandbox.resolve_writable(path)?;
+        if resolved.relative.is_empty() {
+            return Err(SandboxError::OutsideWorkspace(path.to_string()).into());
+        }
+        self.storage(&resolved)
+            .delete(&resolved.relative, recursive)
+            .await
+    }
+
+    /// 挂载点中的条目以 `@name/...` 形式返回
+    async fn list_dir(&self, path: &str) -> anyhow::Result<Vec<FileMetadata>> {
+        let resolved = self.sandbox.resolve(path)?;
+        let mut entries = self.storage(&resolved).list_dir(&resolved.relative).await?;
+        if let Some(name) = &resolved.mount {
+            for entry in &mut entries {
+                entry.path = format!("@{}/{}", name, entry.path);
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn get_metadata(&self, path: &str) -> anyhow::Result<FileMetadata> {
+        let resolved = self.sandbox.resolve(path)?;
+        let mut meta = self
+            .storage(&resolved)
+            .get_metadata(&resolved.relative)
+            .await?;
+        meta.path = resolved.virtual_path();
+        Ok(meta)
+    }
+
+    async fn exists(&self, path: &str) -> anyhow::Result<bool> {
+        let resolved = self.sandbox.resolve(path)?;
+        self.storage(&resolved).exists(&resolved.relative).await
+    }
+
+    async fn create_dir(&self, path: &str, recursive: bool) -> anyhow::Result<()> {
+        let resolved = self.sandbox.resolve_writable(path)?;
+        self.storage(&resolved)
+            .create_dir(&resolved.relative, recursive)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_virtualizes_and_rejects_traversal() {
+        let dir = tempdir().unwrap();
+        let sandbox = PathSandbox::new(dir.path()).unwrap();
+        let relative = |path: &str| sandbox.resolve(path).map(|r| r.relative);
+
+        assert_eq!(relative("src/./lib.rs").unwrap(), "src/lib.rs");
+        assert_eq!(relative("src/../Cargo.toml").unwrap(), "Cargo.toml");
+        // 工作区外的绝对路径被映射到工作区内
+        assert_eq!(relative("/etc/passwd").unwrap(), "etc/passwd");
+        let inside = sandbox.root().join("src").join("main.rs");
+        assert_eq!(relative(inside.to_str().unwrap()).unwrap(), "src/main.rs");
+        assert_eq!(
+            relative("src/../../secret"),
+            Err(SandboxError::OutsideWorkspace("src/../../secret".into()))
+        );
+        assert!(relative("..\\outside").is_err());
+        assert_eq!(
+            relative("@docs/readme.md"),
+            Err(SandboxError::UnknownMount("docs".into()))
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sandboxed_storage_blocks_symlink_escape_and_honours_mounts() {
+        let workspace = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        let shared = tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "top secret").unwrap();
+        std::fs::write(shared.path().join("api.md"), "docs").unwrap();
+        std::fs::create_dir(workspace.path().join("src")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), workspace.path().join("escape")).unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("missing.txt"),
+            workspace.path().join("dangling"),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(workspace.path().join("src"), workspace.path().join("alias"))
+            .unwrap();
+
+        let config: SandboxConfig = serde_json::from_value(serde_json::json!({
+            "mounts": [{"name": "shared", "path": shared.path()}]
+        }))
+        .unwrap();
+        let storage =
+            SandboxedStorage::new(PathSandbox::from_config(workspace.path(), &config).unwrap());
+
+        storage
+            .write_file("/src/lib.rs", b"fn main() {}")
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.read_file("alias/lib.rs").await.unwrap(),
+            b"fn main() {}"
+        );
+        let err = storage.read_file("escape/secret.txt").await.unwrap_err();
+        assert!(err.to_string().contains("symlink"));
+        assert!(storage.write_file("escape/new.txt", b"x").await.is_err());
+        assert!(storage.write_file("dangling", b"x").await.is_err());
+        assert!(!outside.path().join("missing.txt").exists());
+
+        // 挂载点默认只读
+        assert_eq!(storage.read_file("@shared/api.md").await.unwrap(), b"docs");
+        let listed = storage.list_dir("@shared").await.unwrap();
+        assert_eq!(listed[0].path, "@shared/api.md");
+        let err = storage
+            .write_file("@shared/api.md", b"x")
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Mount @shared is read-only");
+        assert!(storage.read_file("@shared/../escape").await.is_err());
+        assert!(storage.delete("", true).await.is_err());
+    }
+}
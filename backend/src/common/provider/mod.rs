@@ -1,3 +1,6 @@
+pub mod allowlist;
+pub mod ignore;
 pub mod local;
 pub mod remote;
 pub mod traits;
+pub mod watch;
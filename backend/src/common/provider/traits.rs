@@ -1,5 +1,7 @@
 use async_trait::async_trait;
+use futures::Stream;
 use serde::{Deserialize, Serialize};
+use std::pin::Pin;
 
 /// 文件元数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +39,59 @@ pub trait StorageProvider: Send + Sync {
 
     /// 创建目录
     async fn create_dir(&self, path: &str, recursive: bool) -> anyhow::Result<()>;
+
+    /// 读取文件的一段内容（大文件按需读取）
+    ///
+    /// 默认实现读取整个文件后截取，本地提供者定位到偏移处只读取请求的范围。
+    async fn read_range(&self, path: &str, offset: u64, len: usize) -> anyhow::Result<Vec<u8>> {
+        let content = self.read_file(path).await?;
+        let start = (offset as usize).min(content.len());
+        let end = start.saturating_add(len).min(content.len());
+        Ok(content[start..end].to_vec())
+    }
+
+    /// 计算文件内容的 SHA-256（十六进制）
+    ///
+    /// 默认实现读取整个文件，本地提供者在阻塞线程池中分块流式计算哈希。
+    async fn hash_file(&self, path: &str) -> anyhow::Result<String> {
+        use sha2::{Digest, Sha256};
+        Ok(format!("{:x}", Sha256::digest(self.read_file(path).await?)))
+    }
+
+    /// 复制文件（自动创建目标父目录），返回复制的字节数
+    ///
+    /// 默认实现经内存中转，本地提供者使用内核态复制而不把内容读入进程。
+    async fn copy_file(&self, from: &str, to: &str) -> anyhow::Result<u64> {
+        let content = self.read_file(from).await?;
+        self.write_file(to, &content).await?;
+        Ok(content.len() as u64)
+    }
+
+    /// 持久写入：返回时内容已落盘，断电也不会留下写了一半的文件
+    ///
+    /// 用于预写日志与变更图记录。默认实现等同于 `write_file`，本地提供者先写入临时文件并
+    /// `sync_all`，再原子改名并同步所在目录。
+    async fn write_file_durable(&self, path: &str, content: &[u8]) -> anyhow::Result<()> {
+        self.write_file(path, content).await
+    }
+
+    /// 在文件末尾追加内容（文件不存在时创建）
+    ///
+    /// 用于只增长的日志。默认实现读出原内容后整体写回，本地提供者以追加模式打开文件。
+    async fn append_file(&self, path: &str, content: &[u8]) -> anyhow::Result<()> {
+        let mut existing = if self.exists(path).await? {
+            self.read_file(path).await?
+        } else {
+            Vec::new()
+        };
+        existing.extend_from_slice(content);
+        self.write_file(path, &existing).await
+    }
+
+    /// 断开连接并释放资源（远程提供者在关闭时调用）
+    async fn disconnect(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
 
 /// 执行选项
@@ -55,6 +110,38 @@ pub struct ExecuteResult {
     pub stderr: String,
 }
 
+impl ExecuteResult {
+    /// 合并 stdout 与 stderr 并去除首尾空白，只保留末尾 `max_chars` 个字符
+    pub fn output_tail(&self, max_chars: usize) -> String {
+        let output = format!("{}\n{}", self.stdout, self.stderr);
+        let output = output.trim();
+        let count = output.chars().count();
+        if count <= max_chars {
+            return output.to_string();
+        }
+        output.chars().skip(count - max_chars).collect()
+    }
+}
+
+/// 命令输出中的一行
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "stream", content = "line", rename_all = "snake_case")]
+pub enum OutputLine {
+    Stdout(String),
+    Stderr(String),
+}
+
+impl OutputLine {
+    pub fn text(&self) -> &str {
+        match self {
+            OutputLine::Stdout(line) | OutputLine::Stderr(line) => line,
+        }
+    }
+}
+
+/// 逐行输出流；丢弃流即停止读取（支持的提供者会同时终止进程）
+pub type OutputStream = Pin<Box<dyn Stream<Item = anyhow::Result<OutputLine>> + Send>>;
+
 /// 执行提供者接口
 #[async_trait]
 pub trait ExecutionProvider: Send + Sync {
@@ -65,6 +152,30 @@ pub trait ExecutionProvider: Send + Sync {
         options: ExecuteOptions,
     ) -> anyhow::Result<ExecuteResult>;
 
+    /// 执行命令并逐行产出输出
+    ///
+    /// 默认实现等待命令结束后依次产出 stdout 与 stderr 的各行，
+    /// 长时间运行的命令（如 `tail -f`）需要提供者自行实现真正的流式读取。
+    async fn execute_stream(
+        &self,
+        command: &str,
+        options: ExecuteOptions,
+    ) -> anyhow::Result<OutputStream> {
+        let result = self.execute(command, options).await?;
+        let lines: Vec<anyhow::Result<OutputLine>> = result
+            .stdout
+            .lines()
+            .map(|l| Ok(OutputLine::Stdout(l.to_string())))
+            .chain(
+                result
+                    .stderr
+                    .lines()
+                    .map(|l| Ok(OutputLine::Stderr(l.to_string()))),
+            )
+            .collect();
+        Ok(Box::pin(futures::stream::iter(lines)))
+    }
+
     /// 终止当前运行的任务（如果支持）
     async fn kill(&self, task_id: &str) -> anyhow::Result<()>;
 }
@@ -0,0 +1,398 @@
+use async_trait::async_trait;
+use futures::{FutureExt, Stream, StreamExt};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::common::intent::{
+    EditorIntent, IntentCategory, IntentDispatcher, IntentOutcome, IntentTopic, SystemIntent,
+};
+use crate::common::provider::traits::StorageProvider;
+
+/// 文件变化的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+/// 工作区中一个文件的变化（路径相对于提供者根目录）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: FileChangeKind,
+}
+
+impl FileChange {
+    pub fn new(path: impl Into<String>, kind: FileChangeKind) -> Self {
+        Self {
+            path: path.into(),
+            kind,
+        }
+    }
+
+    /// 文件变化意图的订阅条件，供语法缓存、知识索引等组件订阅
+    ///
+    /// 不要求编辑器处理成功：没有会话打开该文件时其他订阅方仍需更新。
+    pub fn topic() -> IntentTopic {
+        IntentTopic::category(IntentCategory::Editor)
+            .matching(|intent| Self::from_intent(intent).is_some())
+    }
+
+    /// 从 `EditorIntent::FileChanged` 中取出文件变化
+    pub fn from_intent(intent: &SystemIntent) -> Option<Self> {
+        match intent {
+            SystemIntent::Editor(EditorIntent::FileChanged { path, kind }) => {
+                Some(Self::new(path.clone(), *kind))
+            }
+            _ => None,
+        }
+    }
+
+    /// 从订阅到的意图结果中取出文件变化
+    pub fn from_outcome(outcome: &IntentOutcome) -> Option<Self> {
+        Self::from_intent(&outcome.intent)
+    }
+
+    fn into_intent(self) -> SystemIntent {
+        SystemIntent::Editor(EditorIntent::FileChanged {
+            path: self.path,
+            kind: self.kind,
+        })
+    }
+}
+
+/// 文件变化流；丢弃流即停止监听
+pub type FileChangeStream = Pin<Box<dyn Stream<Item = FileChange> + Send>>;
+
+/// 文件监听提供者接口
+#[async_trait]
+pub trait WatchProvider: Send + Sync {
+    /// 递归监听 `path`（相对于提供者根目录）下的文件变化，不含目录本身的变化
+    async fn watch(&self, path: &str) -> anyhow::Result<FileChangeStream>;
+}
+
+/// 基于操作系统通知（inotify / FSEvents / ReadDirectoryChangesW）的本地监听
+pub struct LocalWatcher {
+    base_path: PathBuf,
+}
+
+impl LocalWatcher {
+    pub fn new(base_path: impl Into<PathBuf>) -> Self {
+        Self {
+            base_path: base_path.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl WatchProvider for LocalWatcher {
+    async fn watch(&self, path: &str) -> anyhow::Result<FileChangeStream> {
+        // 通知中的路径已解析符号链接（如 macOS 的 /private/var），按规范化的根目录截取
+        let base = tokio::fs::canonicalize(&self.base_path).await?;
+        let target = base.join(path.trim_start_matches('/').trim_start_matches('\\'));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let root = base.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            if let Ok(event) = event {
+                for change in changes_from_event(&root, event) {
+                    let _ = tx.send(change);
+                }
+            }
+        })?;
+        watcher.watch(&target, RecursiveMode::Recursive)?;
+
+        Ok(Box::pin(async_stream::stream! {
+            // 监听器随流一起释放
+            let _watcher = watcher;
+            let mut last: Option<FileChange> = None;
+            while let Some(change) = rx.recv().await {
+                // 一次写入常触发多个连续的修改通知，只保留第一个
+                if last.as_ref() == Some(&change) {
+                    continue;
+                }
+                last = Some(change.clone());
+                yield change;
+            }
+        }))
+    }
+}
+
+/// 将一个通知转换为相对路径上的文件变化，忽略访问事件与目录
+fn changes_from_event(root: &Path, event: Event) -> Vec<FileChange> {
+    let kinds: Vec<FileChangeKind> = match event.kind {
+        EventKind::Create(_) => vec![FileChangeKind::Created],
+        EventKind::Remove(_) => vec![FileChangeKind::Removed],
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => vec![FileChangeKind::Removed],
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => vec![FileChangeKind::Created],
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            vec![FileChangeKind::Removed, FileChangeKind::Created]
+        }
+        EventKind::Modify(ModifyKind::Name(_)) => event
+            .paths
+            .iter()
+            .map(|p| {
+                if p.exists() {
+                    FileChangeKind::Created
+                } else {
+                    FileChangeKind::Removed
+                }
+            })
+            .collect(),
+        EventKind::Modify(_) => vec![FileChangeKind::Modified],
+        _ => return Vec::new(),
+    };
+    event
+        .paths
+        .iter()
+        .zip(kinds.iter().cycle())
+        .filter(|(path, kind)| **kind == FileChangeKind::Removed || !path.is_dir())
+        .filter_map(|(path, kind)| {
+            let relative = path.strip_prefix(root).ok()?.to_string_lossy().into_owned();
+            (!relative.is_empty()).then(|| FileChange::new(relative, *kind))
+        })
+        .collect()
+}
+
+/// 默认轮询间隔
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 定期比较文件的修改时间与大小，适用于无法推送通知的远程存储
+pub struct PollingWatcher {
+    storage: Arc<dyn StorageProvider>,
+    interval: Duration,
+}
+
+impl PollingWatcher {
+    pub fn new(storage: Arc<dyn StorageProvider>) -> Self {
+        Self {
+            storage,
+            interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+/// 路径 -> (修改时间, 大小)
+type Snapshot = BTreeMap<String, (u64, u64)>;
+
+/// 递归列出 `path` 下的全部文件
+async fn snapshot(storage: &dyn StorageProvider, path: &str) -> anyhow::Result<Snapshot> {
+    let mut files = Snapshot::new();
+    let mut pending = vec![path.to_string()];
+    while let Some(dir) = pending.pop() {
+        for meta in storage.list_dir(&dir).await? {
+            if meta.is_dir {
+                pending.push(meta.path);
+            } else {
+                files.insert(meta.path, (meta.modified_at, meta.size));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// 两次快照之间的变化（按路径排序）
+fn diff(before: &Snapshot, after: &Snapshot) -> Vec<FileChange> {
+    let mut changes: Vec<FileChange> = after
+        .iter()
+        .filter_map(|(path, stamp)| match before.get(path) {
+            None => Some(FileChange::new(path.clone(), FileChangeKind::Created)),
+            Some(old) if old != stamp => {
+                Some(FileChange::new(path.clone(), FileChangeKind::Modified))
+            }
+            Some(_) => None,
+        })
+        .chain(
+            before
+                .keys()
+                .filter(|path| !after.contains_key(*path))
+                .map(|path| FileChange::new(path.clone(), FileChangeKind::Removed)),
+        )
+        .collect();
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+#[async_trait]
+impl WatchProvider for PollingWatcher {
+    async fn watch(&self, path: &str) -> anyhow::Result<FileChangeStream> {
+        let storage = self.storage.clone();
+        let interval = self.interval;
+        let root = path.to_string();
+        let mut previous = snapshot(storage.as_ref(), &root).await?;
+
+        Ok(Box::pin(async_stream::stream! {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                // 连接暂时中断时跳过本轮，恢复后与上次成功的快照比较
+                let Ok(current) = snapshot(storage.as_ref(), &root).await else {
+                    continue;
+                };
+                for change in diff(&previous, &current) {
+                    yield change;
+                }
+                previous = current;
+            }
+        }))
+    }
+}
+
+/// 将文件变化作为 `EditorIntent::FileChanged` 逐个分发，直到流结束或分发器关闭
+///
+/// 编辑器会话据此重新载入未修改的 Tab，其他组件经 `FileChange::topic` 订阅。
+pub fn forward(mut changes: FileChangeStream, dispatcher: Arc<IntentDispatcher>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        // 同一文件在处理期间积压的多个变化只分发最后一个
+        let mut backlog: HashMap<String, FileChangeKind> = HashMap::new();
+        while let Some(change) = changes.next().await {
+            backlog.insert(change.path, change.kind);
+            while let Some(Some(change)) = changes.next().now_or_never() {
+                backlog.insert(change.path, change.kind);
+            }
+            let mut batch: Vec<FileChange> = backlog
+                .drain()
+                .map(|(path, kind)| FileChange::new(path, kind))
+                .collect();
+            batch.sort_by(|a, b| a.path.cmp(&b.path));
+            for change in batch {
+                if !dispatcher.is_accepting() {
+                    return;
+                }
+                // 没有编辑器处理器时仍会推送给订阅方，错误无需处理
+                let _ = dispatcher.dispatch(change.into_intent()).await;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::intent::IntentHandler;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_diff_snapshots() {
+        let before = Snapshot::from([
+            ("a.rs".to_string(), (1, 10)),
+            ("b.rs".to_string(), (1, 10)),
+            ("c.rs".to_string(), (1, 10)),
+        ]);
+        let after = Snapshot::from([
+            ("a.rs".to_string(), (1, 10)),
+            ("b.rs".to_string(), (2, 12)),
+            ("d.rs".to_string(), (2, 1)),
+        ]);
+        assert_eq!(
+            diff(&before, &after),
+            vec![
+                FileChange::new("b.rs", FileChangeKind::Modified),
+                FileChange::new("c.rs", FileChangeKind::Removed),
+                FileChange::new("d.rs", FileChangeKind::Created),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_polling_watcher_reports_changes() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        storage
+            .write_file("src/lib.rs", b"fn a() {}")
+            .await
+            .unwrap();
+        storage.write_file("old.txt", b"x").await.unwrap();
+
+        let watcher = PollingWatcher::new(storage.clone()).with_interval(Duration::from_millis(20));
+        let mut changes = watcher.watch("").await.unwrap();
+        storage
+            .write_file("src/new.rs", b"fn b() {}")
+            .await
+            .unwrap();
+        storage.delete("old.txt", false).await.unwrap();
+
+        let mut seen = vec![changes.next().await.unwrap(), changes.next().await.unwrap()];
+        seen.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(
+            seen,
+            vec![
+                FileChange::new("old.txt", FileChangeKind::Removed),
+                FileChange::new("src/new.rs", FileChangeKind::Created),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_watcher_reports_changes() {
+        let dir = tempdir().unwrap();
+        let watcher = LocalWatcher::new(dir.path());
+        let mut changes = watcher.watch("").await.unwrap();
+        tokio::fs::write(dir.path().join("notes.md"), b"# hi")
+            .await
+            .unwrap();
+
+        let change = tokio::time::timeout(Duration::from_secs(5), changes.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.path, "notes.md");
+        assert_ne!(change.kind, FileChangeKind::Removed);
+    }
+
+    struct Accept;
+
+    #[async_trait]
+    impl IntentHandler for Accept {
+        async fn handle(&self, _intent: SystemIntent) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forward_dispatches_file_changed_intents() {
+        let dispatcher = Arc::new(IntentDispatcher::new());
+        dispatcher
+            .register(IntentCategory::Editor, Arc::new(Accept))
+            .await;
+        let mut subscription = dispatcher.subscribe(FileChange::topic());
+        let changes = futures::stream::iter(vec![
+            FileChange::new("a.rs", FileChangeKind::Created),
+            FileChange::new("a.rs", FileChangeKind::Modified),
+            FileChange::new("b.rs", FileChangeKind::Removed),
+        ]);
+        forward(Box::pin(changes), dispatcher.clone())
+            .await
+            .unwrap();
+
+        // 积压的同一文件的变化合并为最后一个
+        let mut received = Vec::new();
+        while let Some(outcome) = subscription.try_recv() {
+            received.push(FileChange::from_outcome(&outcome).unwrap());
+        }
+        assert_eq!(
+            received,
+            vec![
+                FileChange::new("a.rs", FileChangeKind::Modified),
+                FileChange::new("b.rs", FileChangeKind::Removed),
+            ]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::common::event::{BackendEvent, EventBus};
 use serde::{Deserialize, Serialize};
 
 /// 统一不同编译器的诊断格式
@@ -44,6 +45,15 @@ impl DiagnosticManager {
         &self.diagnostics
     }
 
+    /// 将当前诊断作为 `path` 的诊断集发布到事件总线
+    pub fn export(&self, events: &EventBus, path: &str) -> anyhow::Result<()> {
+        events.emit(BackendEvent::Diagnostics {
+            path: path.to_string(),
+            diagnostics: serde_json::to_value(&self.diagnostics)?,
+        });
+        Ok(())
+    }
+
     /// 清除诊断信息
     pub fn clear(&mut self) {
         self.diagnostics.clear();
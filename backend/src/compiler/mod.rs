@@ -1,7 +1,11 @@
 pub mod analyzer;
 pub mod diagnostic;
 pub mod registry;
+pub mod stacktrace;
+pub mod typecheck;
 
 pub use analyzer::ProjectAnalyzer;
 pub use diagnostic::DiagnosticManager;
 pub use registry::CompilerRegistry;
+pub use stacktrace::{NavigationTarget, ResolvedFrame, StackFrame, StackTrace, TraceLanguage};
+pub use typecheck::{FileDiagnostic, TypeCheckGate, TypeCheckReport, TypeCheckRun};
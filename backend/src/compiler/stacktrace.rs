@@ -0,0 +1,362 @@
+use crate::project::index::WorkspaceIndex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+/// 栈回溯的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceLanguage {
+    Rust,
+    Python,
+    JavaScript,
+}
+
+/// 栈帧（行号从 1 开始）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackFrame {
+    pub function: Option<String>,
+    /// 回溯中出现的原始路径
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+}
+
+/// 解析后的栈回溯，帧按从崩溃点向外的顺序排列
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackTrace {
+    pub language: TraceLanguage,
+    /// 异常或 panic 信息
+    pub message: String,
+    pub frames: Vec<StackFrame>,
+}
+
+/// 可点击跳转的源码位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NavigationTarget {
+    /// 工作区相对路径
+    pub path: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    /// 显示文本，如 `parse_config (src/config.rs:42)`
+    pub label: String,
+}
+
+/// 栈帧及其在工作区中的位置（第三方或标准库代码没有位置）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedFrame {
+    pub frame: StackFrame,
+    pub target: Option<NavigationTarget>,
+}
+
+/// 不属于用户代码的路径片段
+const EXTERNAL_MARKERS: &[&str] = &[
+    "/rustc/",
+    ".cargo/registry/",
+    "site-packages/",
+    "node_modules/",
+    "node:",
+    "<frozen ",
+];
+
+static RUST_PANIC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"thread '[^']*' panicked at (?:'(?P<old>.*)', )?(?P<file>[^\s:]+):(?P<line>\d+):(?P<col>\d+):?")
+        .unwrap()
+});
+static RUST_SYMBOL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*\d+:\s+(?:0x[0-9a-f]+ - )?(?P<func>\S.*)$").unwrap());
+static RUST_LOCATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s+at (?P<file>.+?):(?P<line>\d+)(?::(?P<col>\d+))?$").unwrap());
+static PYTHON_FRAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^\s*File "(?P<file>[^"]+)", line (?P<line>\d+)(?:, in (?P<func>.+))?$"#).unwrap()
+});
+static JS_FRAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*at (?:(?P<func>.+?) \()?(?P<file>[^()\s]+?):(?P<line>\d+):(?P<col>\d+)\)?$")
+        .unwrap()
+});
+
+impl StackTrace {
+    /// 从日志或终端输出中识别第一段 Rust panic、Python traceback 或 JS 栈
+    pub fn parse(text: &str) -> Option<Self> {
+        if text.contains("Traceback (most recent call last):") {
+            Self::parse_python(text)
+        } else if RUST_PANIC.is_match(text) {
+            Self::parse_rust(text)
+        } else {
+            Self::parse_javascript(text)
+        }
+    }
+
+    fn parse_rust(text: &str) -> Option<Self> {
+        let panic = RUST_PANIC.captures(text)?;
+        let panic_end = panic.get(0)?.end();
+        let message = match panic.name("old") {
+            Some(old) => old.as_str().to_string(),
+            None => text[panic_end..]
+                .lines()
+                .find(|l| !l.trim().is_empty())
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        };
+        let mut frames = vec![StackFrame {
+            function: None,
+            file: panic["file"].to_string(),
+            line: panic["line"].parse().ok()?,
+            column: panic["col"].parse().ok(),
+        }];
+
+        // `RUST_BACKTRACE=1`：符号行后跟 `at file:line:col`
+        let mut function = None;
+        for line in text[panic_end..].lines() {
+            if let Some(location) = RUST_LOCATION.captures(line) {
+                let Ok(line_no) = location["line"].parse() else {
+                    continue;
+                };
+                let frame = StackFrame {
+                    function: function.take(),
+                    file: location["file"].to_string(),
+                    line: line_no,
+                    column: location.name("col").and_then(|c| c.as_str().parse().ok()),
+                };
+                // panic 位置通常与回溯中的某一帧重复，保留带函数名的那条
+                if frames[0].function.is_none()
+                    && frames[0].line == frame.line
+                    && same_file(&frames[0].file, &frame.file)
+                {
+                    frames[0] = frame;
+                } else {
+                    frames.push(frame);
+                }
+            } else if let Some(symbol) = RUST_SYMBOL.captures(line) {
+                function = Some(strip_rust_hash(&symbol["func"]));
+            }
+        }
+        Some(Self {
+            language: TraceLanguage::Rust,
+            message,
+            frames,
+        })
+    }
+
+    fn parse_python(text: &str) -> Option<Self> {
+        let start = text.find("Traceback (most recent call last):")?;
+        let mut frames = Vec::new();
+        let mut message = String::new();
+        for line in text[start..].lines().skip(1) {
+            if let Some(frame) = PYTHON_FRAME.captures(line) {
+                frames.push(StackFrame {
+                    function: frame.name("func").map(|f| f.as_str().to_string()),
+                    file: frame["file"].to_string(),
+                    line: frame["line"].parse().ok()?,
+                    column: None,
+                });
+            } else if !line.starts_with(' ') && !line.trim().is_empty() {
+                message = line.trim().to_string();
+                break;
+            }
+        }
+        if frames.is_empty() {
+            return None;
+        }
+        // Python 最内层的帧在最后
+        frames.reverse();
+        Some(Self {
+            language: TraceLanguage::Python,
+            message,
+            frames,
+        })
+    }
+
+    fn parse_javascript(text: &str) -> Option<Self> {
+        let lines: Vec<&str> = text.lines().collect();
+        let first = lines.iter().position(|l| JS_FRAME.is_match(l))?;
+        let message = lines[..first]
+            .iter()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .map(|l| l.trim().to_string())
+            .unwrap_or_default();
+        let frames = lines[first..]
+            .iter()
+            .map_while(|l| JS_FRAME.captures(l))
+            .filter_map(|frame| {
+                Some(StackFrame {
+                    function: frame.name("func").map(|f| f.as_str().to_string()),
+                    file: frame["file"].to_string(),
+                    line: frame["line"].parse().ok()?,
+                    column: frame["col"].parse().ok(),
+                })
+            })
+            .collect();
+        Some(Self {
+            language: TraceLanguage::JavaScript,
+            message,
+            frames,
+        })
+    }
+
+    /// 将各帧映射到工作区文件
+    pub fn resolve(&self, index: &WorkspaceIndex) -> Vec<ResolvedFrame> {
+        self.frames
+            .iter()
+            .map(|frame| ResolvedFrame {
+                target: resolve_path(index, &frame.file).map(|path| NavigationTarget {
+                    label: match &frame.function {
+                        Some(function) => format!("{} ({}:{})", function, path, frame.line),
+                        None => format!("{}:{}", path, frame.line),
+                    },
+                    path,
+                    line: frame.line,
+                    column: frame.column,
+                }),
+                frame: frame.clone(),
+            })
+            .collect()
+    }
+}
+
+/// panic 位置是相对路径，回溯中可能是 `./` 前缀或绝对路径
+fn same_file(a: &str, b: &str) -> bool {
+    let a = a.trim_start_matches("./");
+    let b = b.trim_start_matches("./");
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    long.strip_suffix(short)
+        .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('/'))
+}
+
+/// 去掉 Rust 符号末尾的 `::h<hash>`
+fn strip_rust_hash(symbol: &str) -> String {
+    match symbol.rsplit_once("::h") {
+        Some((name, hash)) if hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()) => {
+            name.to_string()
+        }
+        _ => symbol.trim().to_string(),
+    }
+}
+
+/// 回溯中的路径可能是绝对路径、`./` 相对路径或 `file://` URL；
+/// 取工作区中与其后缀（按路径段）匹配的最长文件路径
+fn resolve_path(index: &WorkspaceIndex, file: &str) -> Option<String> {
+    let file = file.replace('\\', "/");
+    if EXTERNAL_MARKERS.iter().any(|m| file.contains(m)) {
+        return None;
+    }
+    let file = file.strip_prefix("file://").unwrap_or(&file);
+    let file = file.trim_start_matches("./");
+    if index.contains_file(file) {
+        return Some(file.to_string());
+    }
+    index
+        .files()
+        .filter(|candidate| {
+            file.strip_suffix(candidate)
+                .is_some_and(|prefix| prefix.ends_with('/'))
+        })
+        .max_by_key(|candidate| candidate.len())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> WorkspaceIndex {
+        let mut index = WorkspaceIndex::new();
+        for path in [
+            "src/main.rs",
+            "src/config.rs",
+            "app/server.py",
+            "web/app.js",
+        ] {
+            index.add_file(path);
+        }
+        index
+    }
+
+    #[test]
+    fn test_parse_rust_panic_with_backtrace() {
+        let text = "\
+thread 'main' panicked at src/config.rs:42:9:
+called `Option::unwrap()` on a `None` value
+stack backtrace:
+   0: rust_begin_unwind
+             at /rustc/90b35a623/library/std/src/panicking.rs:645:5
+   1: demo::config::load::h0123456789abcdef
+             at ./src/config.rs:42:9
+   2: demo::main
+             at /home/dev/demo/src/main.rs:7:5
+";
+        let trace = StackTrace::parse(text).unwrap();
+        assert_eq!(trace.language, TraceLanguage::Rust);
+        assert_eq!(trace.message, "called `Option::unwrap()` on a `None` value");
+        let functions: Vec<Option<&str>> =
+            trace.frames.iter().map(|f| f.function.as_deref()).collect();
+        assert_eq!(
+            functions,
+            vec![
+                Some("demo::config::load"),
+                Some("rust_begin_unwind"),
+                Some("demo::main")
+            ]
+        );
+
+        let resolved = trace.resolve(&index());
+        let targets: Vec<Option<&str>> = resolved
+            .iter()
+            .map(|r| r.target.as_ref().map(|t| t.path.as_str()))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![Some("src/config.rs"), None, Some("src/main.rs")]
+        );
+        assert_eq!(
+            resolved[0].target.as_ref().unwrap().label,
+            "demo::config::load (src/config.rs:42)"
+        );
+
+        let old = StackTrace::parse("thread 'main' panicked at 'boom', src/main.rs:3:5").unwrap();
+        assert_eq!(old.message, "boom");
+        assert_eq!(old.frames[0].line, 3);
+    }
+
+    #[test]
+    fn test_parse_python_and_javascript() {
+        let text = "\
+Traceback (most recent call last):
+  File \"/srv/project/app/server.py\", line 10, in <module>
+    main()
+  File \"/usr/lib/python3.12/site-packages/flask/app.py\", line 88, in run
+    handler()
+  File \"/srv/project/app/server.py\", line 4, in handler
+    raise ValueError(\"bad port\")
+ValueError: bad port
+";
+        let trace = StackTrace::parse(text).unwrap();
+        assert_eq!(trace.language, TraceLanguage::Python);
+        assert_eq!(trace.message, "ValueError: bad port");
+        assert_eq!(trace.frames[0].function.as_deref(), Some("handler"));
+        assert_eq!(trace.frames[0].line, 4);
+        let resolved = trace.resolve(&index());
+        assert_eq!(resolved[0].target.as_ref().unwrap().path, "app/server.py");
+        assert!(resolved[1].target.is_none());
+
+        let text = "\
+TypeError: Cannot read properties of undefined (reading 'id')
+    at render (file:///home/dev/site/web/app.js:12:17)
+    at /home/dev/site/web/app.js:30:3
+    at Module._compile (node:internal/modules/cjs/loader:1256:14)
+";
+        let trace = StackTrace::parse(text).unwrap();
+        assert_eq!(trace.language, TraceLanguage::JavaScript);
+        assert!(trace.message.starts_with("TypeError"));
+        assert_eq!(trace.frames.len(), 3);
+        assert_eq!(trace.frames[1].function, None);
+        assert_eq!(trace.frames[0].column, Some(17));
+        let resolved = trace.resolve(&index());
+        assert_eq!(resolved[0].target.as_ref().unwrap().path, "web/app.js");
+        assert!(resolved[2].target.is_none());
+
+        assert!(StackTrace::parse("all good").is_none());
+    }
+}
@@ -0,0 +1,424 @@
+use crate::common::provider::traits::{ExecuteOptions, ExecutionProvider, StorageProvider};
+use crate::compiler::diagnostic::{Diagnostic, Severity};
+use crate::compiler::registry::CompilerRegistry;
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, LazyLock};
+
+/// `cargo check --message-format short` 的诊断行：`src/lib.rs:3:5: error[E0308]: ...`
+static CARGO_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([^\s:][^:]*):(\d+):(\d+): (error|warning)(?:\[\w+\])?: (.*)$").unwrap()
+});
+
+/// `tsc` 的诊断行：`src/a.ts(3,5): error TS2322: ...`
+static TSC_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+?)\((\d+),(\d+)\): (error|warning) TS\d+: (.*)$").unwrap());
+
+/// 反馈给模型的输出最大字符数
+const MAX_OUTPUT_CHARS: usize = 4000;
+
+/// 带文件路径的诊断（路径按编译器输出）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileDiagnostic {
+    pub path: String,
+    #[serde(flatten)]
+    pub diagnostic: Diagnostic,
+}
+
+/// 一次类型检查命令的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeCheckRun {
+    pub command: String,
+    pub cwd: String,
+    pub exit_code: i32,
+    pub diagnostics: Vec<FileDiagnostic>,
+    /// 命令输出（截取末尾部分），诊断无法解析时供模型直接阅读
+    pub output: String,
+    /// 检查整个项目时，位于未修改文件中而被忽略的错误数
+    #[serde(default)]
+    pub ignored_errors: usize,
+}
+
+impl TypeCheckRun {
+    /// 命令成功，或失败完全由未修改文件中已有的错误造成
+    pub fn passed(&self) -> bool {
+        self.exit_code == 0
+            || (self.ignored_errors > 0
+                && !self
+                    .diagnostics
+                    .iter()
+                    .any(|d| d.diagnostic.severity == Severity::Error))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TypeCheckReport {
+    pub runs: Vec<TypeCheckRun>,
+}
+
+impl TypeCheckReport {
+    pub fn passed(&self) -> bool {
+        self.runs.iter().all(TypeCheckRun::passed)
+    }
+
+    /// 供修复循环使用的失败摘要：逐条列出错误，无法解析时附带原始输出
+    pub fn feedback(&self) -> String {
+        let mut out = String::new();
+        for run in self.runs.iter().filter(|r| !r.passed()) {
+            out.push_str(&format!(
+                "`{}` (in {}) failed with exit code {}:\n",
+                run.command,
+                if run.cwd.is_empty() { "." } else { &run.cwd },
+                run.exit_code
+            ));
+            let errors: Vec<&FileDiagnostic> = run
+                .diagnostics
+                .iter()
+                .filter(|d| d.diagnostic.severity == Severity::Error)
+                .collect();
+            if errors.is_empty() {
+                out.push_str(&format!("```\n{}\n```\n", run.output));
+            }
+            for error in errors {
+                out.push_str(&format!(
+                    "- {}:{}:{}: {}\n",
+                    error.path,
+                    error.diagnostic.line,
+                    error.diagnostic.column,
+                    error.diagnostic.message
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// Agent 写入后的快速类型检查
+///
+/// 只检查注册了编译器的语言：Rust 对被修改文件所属的包运行 `cargo check -p`，
+/// TypeScript 对被修改文件所属的项目（最近的 `tsconfig.json`）运行 `tsc --noEmit -p`，
+/// 只保留被修改文件的诊断；找不到 `tsconfig.json` 的文件直接交给 `tsc`。
+/// 编译器路径取自 `CompilerRegistry` 中 `rust` 与 `typescript` 的注册项。
+pub struct TypeCheckGate {
+    storage: Arc<dyn StorageProvider>,
+    executor: Arc<dyn ExecutionProvider>,
+    registry: Arc<CompilerRegistry>,
+    timeout_ms: u64,
+}
+
+impl TypeCheckGate {
+    pub fn new(
+        storage: Arc<dyn StorageProvider>,
+        executor: Arc<dyn ExecutionProvider>,
+        registry: Arc<CompilerRegistry>,
+    ) -> Self {
+        Self {
+            storage,
+            executor,
+            registry,
+            timeout_ms: 120_000,
+        }
+    }
+
+    /// 单条检查命令的超时时间
+    pub fn with_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// 检查被修改的文件；没有可检查的文件时返回空报告
+    pub async fn check(&self, paths: &[String]) -> Result<TypeCheckReport> {
+        let mut report = TypeCheckReport::default();
+
+        if let Some(cargo) = self.registry.get_compiler("rust") {
+            // 包目录 -> 包名
+            let mut packages = BTreeMap::new();
+            for path in paths.iter().filter(|p| p.ends_with(".rs")) {
+                if let Some(package) = self.cargo_package(path).await {
+                    packages.insert(package.0, package.1);
+                }
+            }
+            for (dir, name) in packages {
+                let command = format!("{} check -p {} --message-format short", cargo, name);
+                report
+                    .runs
+                    .push(self.run(command, dir, &CARGO_LINE, None).await?);
+            }
+        }
+
+        if let Some(tsc) = self.registry.get_compiler("typescript") {
+            // 项目目录 -> 项目内被修改文件的相对路径
+            let mut projects: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+            let mut loose = Vec::new();
+            for path in paths
+                .iter()
+                .filter(|p| p.ends_with(".ts") || p.ends_with(".tsx"))
+            {
+                match self.tsconfig_dir(path).await {
+                    Some(dir) => {
+                        let relative = path.strip_prefix(&format!("{}/", dir)).unwrap_or(path);
+                        projects
+                            .entry(dir)
+                            .or_default()
+                            .insert(relative.to_string());
+                    }
+                    None => loose.push(path.as_str()),
+                }
+            }
+            for (dir, files) in projects {
+                let command = format!("{} --noEmit --pretty false -p tsconfig.json", tsc);
+                report
+                    .runs
+                    .push(self.run(command, dir, &TSC_LINE, Some(&files)).await?);
+            }
+            if !loose.is_empty() {
+                let command = format!("{} --noEmit --pretty false {}", tsc, loose.join(" "));
+                report
+                    .runs
+                    .push(self.run(command, String::new(), &TSC_LINE, None).await?);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// 文件所属 Cargo 包的目录与包名（向上查找最近的带 `[package]` 的 Cargo.toml）
+    async fn cargo_package(&self, path: &str) -> Option<(String, String)> {
+        let mut dir = parent_dir(path);
+        loop {
+            let manifest = if dir.is_empty() {
+                "Cargo.toml".to_string()
+            } else {
+                format!("{}/Cargo.toml", dir)
+            };
+            if let Ok(bytes) = self.storage.read_file(&manifest).await
+                && let Some(name) = package_name(&String::from_utf8_lossy(&bytes))
+            {
+                return Some((dir.to_string(), name));
+            }
+            if dir.is_empty() {
+                return None;
+            }
+            dir = parent_dir(dir);
+        }
+    }
+
+    /// 文件所属 TypeScript 项目的目录（向上查找最近的 `tsconfig.json`）
+    async fn tsconfig_dir(&self, path: &str) -> Option<String> {
+        let mut dir = parent_dir(path);
+        loop {
+            let config = if dir.is_empty() {
+                "tsconfig.json".to_string()
+            } else {
+                format!("{}/tsconfig.json", dir)
+            };
+            if self.storage.exists(&config).await.unwrap_or(false) {
+                return Some(dir.to_string());
+            }
+            if dir.is_empty() {
+                return None;
+            }
+            dir = parent_dir(dir);
+        }
+    }
+
+    /// 运行检查命令并解析诊断；给出 `only` 时只保留这些文件（相对 `cwd`）的诊断
+    async fn run(
+        &self,
+        command: String,
+        cwd: String,
+        pattern: &Regex,
+        only: Option<&BTreeSet<String>>,
+    ) -> Result<TypeCheckRun> {
+        let result = self
+            .executor
+            .execute(
+                &command,
+                ExecuteOptions {
+                    cwd: Some(if cwd.is_empty() {
+                        ".".into()
+                    } else {
+                        cwd.clone()
+                    }),
+                    timeout_ms: Some(self.timeout_ms),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let output = format!("{}\n{}", result.stdout, result.stderr);
+        let mut diagnostics: Vec<FileDiagnostic> = output
+            .lines()
+            .filter_map(|line| pattern.captures(line.trim_end()))
+            .map(|c| FileDiagnostic {
+                path: c[1].to_string(),
+                diagnostic: Diagnostic {
+                    message: c[5].to_string(),
+                    severity: if &c[4] == "error" {
+                        Severity::Error
+                    } else {
+                        Severity::Warning
+                    },
+                    line: c[2].parse().unwrap_or(0),
+                    column: c[3].parse().unwrap_or(0),
+                },
+            })
+            .collect();
+        let mut ignored_errors = 0;
+        if let Some(only) = only {
+            diagnostics.retain(|d| {
+                let keep = only.contains(d.path.trim_start_matches("./"));
+                ignored_errors += (!keep && d.diagnostic.severity == Severity::Error) as usize;
+                keep
+            });
+        }
+        Ok(TypeCheckRun {
+            command,
+            cwd,
+            exit_code: result.exit_code,
+            diagnostics,
+            output: result.output_tail(MAX_OUTPUT_CHARS),
+            ignored_errors,
+        })
+    }
+}
+
+/// `[package]` 段中的 `name`
+fn package_name(manifest: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if in_package
+            && let Some((key, value)) = line.split_once('=')
+            && key.trim() == "name"
+        {
+            return Some(value.trim().trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::common::provider::traits::ExecuteResult;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    struct Recorder {
+        commands: Mutex<Vec<(String, Option<String>)>>,
+    }
+
+    #[async_trait]
+    impl ExecutionProvider for Recorder {
+        async fn execute(&self, cmd: &str, opts: ExecuteOptions) -> Result<ExecuteResult> {
+            self.commands
+                .lock()
+                .unwrap()
+                .push((cmd.to_string(), opts.cwd));
+            Ok(if cmd.starts_with("cargo") {
+                ExecuteResult {
+                    exit_code: 101,
+                    stdout: String::new(),
+                    stderr: "src/lib.rs:2:5: error[E0308]: mismatched types\nerror: could not compile `core`".into(),
+                }
+            } else if cmd.contains("-p tsconfig.json") {
+                ExecuteResult {
+                    exit_code: 2,
+                    stdout: "src/app.ts(3,5): error TS2322: Type 'string' is not assignable to type 'number'.\n\
+                             src/legacy.ts(1,1): error TS2304: Cannot find name 'x'."
+                        .into(),
+                    stderr: String::new(),
+                }
+            } else {
+                ExecuteResult {
+                    exit_code: 0,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                }
+            })
+        }
+        async fn kill(&self, _id: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_type_check_gate() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        storage
+            .write_file(
+                "crates/core/Cargo.toml",
+                b"[package]\nname = \"core\"\n\n[dependencies]\nname = \"x\"\n",
+            )
+            .await
+            .unwrap();
+        storage
+            .write_file("web/tsconfig.json", b"{}")
+            .await
+            .unwrap();
+        let executor = Arc::new(Recorder {
+            commands: Mutex::new(Vec::new()),
+        });
+        let mut registry = CompilerRegistry::new();
+        registry.register("rust", "cargo");
+        registry.register("typescript", "tsc");
+        let gate = TypeCheckGate::new(storage, executor.clone(), Arc::new(registry));
+
+        let report = gate
+            .check(&[
+                "crates/core/src/lib.rs".into(),
+                "crates/core/src/a/b.rs".into(),
+                "web/src/app.ts".into(),
+                "scripts/build.ts".into(),
+                "README.md".into(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            *executor.commands.lock().unwrap(),
+            vec![
+                (
+                    "cargo check -p core --message-format short".to_string(),
+                    Some("crates/core".to_string())
+                ),
+                (
+                    "tsc --noEmit --pretty false -p tsconfig.json".to_string(),
+                    Some("web".to_string())
+                ),
+                (
+                    "tsc --noEmit --pretty false scripts/build.ts".to_string(),
+                    Some(".".to_string())
+                ),
+            ]
+        );
+        // 只保留被修改文件的诊断
+        let project = &report.runs[1];
+        assert_eq!(project.diagnostics.len(), 1);
+        assert_eq!(project.diagnostics[0].path, "src/app.ts");
+        assert_eq!(project.ignored_errors, 1);
+        assert!(!project.passed());
+        assert!(!report.passed());
+        assert_eq!(report.runs[0].diagnostics[0].path, "src/lib.rs");
+        assert_eq!(report.runs[0].diagnostics[0].diagnostic.line, 2);
+        assert!(
+            report
+                .feedback()
+                .contains("- src/lib.rs:2:5: mismatched types")
+        );
+
+        // 错误全部位于未修改的文件时视为通过
+        let report = gate.check(&["web/src/clean.ts".into()]).await.unwrap();
+        assert!(report.runs[0].diagnostics.is_empty());
+        assert_eq!(report.runs[0].ignored_errors, 2);
+        assert!(report.passed());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::common::provider::traits::StorageProvider;
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use image::{ImageFormat, ImageReader};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::sync::Arc;
+
+/// 文件超过缩略图上限时，为解析格式与尺寸读取的开头字节数
+const HEADER_BYTES: usize = 64 * 1024;
+
+/// 资源文件的预览元数据
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AssetInfo {
+    pub path: String,
+    /// 格式名称（如 `png`、`jpeg`、`svg`）
+    pub format: String,
+    pub mime_type: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// 缩略图（`data:image/png;base64,...`），无法解码或文件过大时为空
+    pub thumbnail: Option<String>,
+}
+
+/// 资源检查器：通过存储提供者读取图像，返回尺寸、格式与缩略图
+///
+/// 与其他编辑器功能一样只依赖 `StorageProvider`，因此远程工作区同样适用。
+pub struct AssetInspector {
+    storage: Arc<dyn StorageProvider>,
+    /// 缩略图最长边（像素）
+    thumbnail_size: u32,
+    /// 超过该大小的文件不生成缩略图
+    max_decode_bytes: u64,
+}
+
+impl AssetInspector {
+    pub fn new(storage: Arc<dyn StorageProvider>) -> Self {
+        Self {
+            storage,
+            thumbnail_size: 256,
+            max_decode_bytes: 20 * 1024 * 1024,
+        }
+    }
+
+    /// 设置缩略图最长边
+    pub fn with_thumbnail_size(mut self, size: u32) -> Self {
+        self.thumbnail_size = size.max(1);
+        self
+    }
+
+    /// 设置生成缩略图的文件大小上限
+    pub fn with_max_decode_bytes(mut self, bytes: u64) -> Self {
+        self.max_decode_bytes = bytes;
+        self
+    }
+
+    /// 路径是否为可预览的图像资源
+    pub fn is_asset(path: &str) -> bool {
+        let lower = path.to_lowercase();
+        lower.ends_with(".svg")
+            || ImageFormat::from_path(&lower).is_ok_and(|format| format.reading_enabled())
+    }
+
+    /// 检查指定路径的资源
+    pub async fn inspect(&self, path: &str) -> Result<AssetInfo> {
+        let size = self.storage.get_metadata(path).await?.size;
+
+        if path.to_lowercase().ends_with(".svg") {
+            // SVG 由前端直接渲染，这里不解析尺寸
+            return Ok(AssetInfo {
+                path: path.to_string(),
+                format: "svg".to_string(),
+                mime_type: "image/svg+xml".to_string(),
+                size,
+                width: None,
+                height: None,
+                thumbnail: None,
+            });
+        }
+
+        // 超过缩略图上限的文件只读取开头部分解析格式与尺寸，不整个读入内存
+        let decode = size <= self.max_decode_bytes;
+        let content = if decode {
+            self.storage.read_file(path).await?
+        } else {
+            self.storage.read_range(path, 0, HEADER_BYTES).await?
+        };
+        let reader = ImageReader::new(Cursor::new(&content)).with_guessed_format()?;
+        let format = reader
+            .format()
+            .ok_or_else(|| anyhow::anyhow!("Unsupported asset format: {}", path))?;
+        let (width, height) = reader.into_dimensions()?;
+
+        let thumbnail = if decode {
+            self.thumbnail(&content).ok()
+        } else {
+            None
+        };
+
+        Ok(AssetInfo {
+            path: path.to_string(),
+            format: format
+                .extensions_str()
+                .first()
+                .copied()
+                .unwrap_or("unknown")
+                .to_string(),
+            mime_type: format.to_mime_type().to_string(),
+            size,
+            width: Some(width),
+            height: Some(height),
+            thumbnail,
+        })
+    }
+
+    fn thumbnail(&self, content: &[u8]) -> Result<String> {
+        let image = image::load_from_memory(content)?;
+        let thumbnail = image.thumbnail(self.thumbnail_size, self.thumbnail_size);
+        let mut buffer = Cursor::new(Vec::new());
+        thumbnail.write_to(&mut buffer, ImageFormat::Png)?;
+        Ok(format!(
+            "data:image/png;base64,{}",
+            STANDARD.encode(buffer.into_inner())
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use image::{Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_inspect_png_asset() {
+        let dir = tempdir().unwrap();
+        let image = RgbImage::from_pixel(64, 32, Rgb([255, 0, 0]));
+        image.save(dir.path().join("logo.png")).unwrap();
+
+        let inspector =
+            AssetInspector::new(Arc::new(LocalFileSystem::new(dir.path()))).with_thumbnail_size(16);
+        assert!(AssetInspector::is_asset("logo.png"));
+        assert!(!AssetInspector::is_asset("main.rs"));
+
+        let info = inspector.inspect("logo.png").await.unwrap();
+        assert_eq!(info.format, "png");
+        assert_eq!(info.mime_type, "image/png");
+        assert_eq!((info.width, info.height), (Some(64), Some(32)));
+
+        let encoded = info
+            .thumbnail
+            .unwrap()
+            .strip_prefix("data:image/png;base64,")
+            .unwrap()
+            .to_string();
+        let thumb = image::load_from_memory(&STANDARD.decode(encoded).unwrap()).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (16, 8));
+
+        // 超过上限的文件只读取开头部分，仍能得到尺寸但没有缩略图
+        let info = inspector
+            .with_max_decode_bytes(info.size - 1)
+            .inspect("logo.png")
+            .await
+            .unwrap();
+        assert_eq!((info.width, info.height), (Some(64), Some(32)));
+        assert_eq!(info.thumbnail, None);
+    }
+}
@@ -0,0 +1,462 @@
+use crate::common::change::Change;
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::operation::Operation;
+use crate::common::change::thread::{ThreadId, ThreadManager};
+use crate::common::change::wal::{WalRecord, WriteAheadLog};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// 自动保存策略：未保存的操作何时落为草稿通道上的微变更
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutosavePolicy {
+    /// 距上次落盘超过该时长时落盘
+    pub interval: Duration,
+    /// 未落盘的操作达到该数量时立即落盘
+    pub max_operations: usize,
+}
+
+impl Default for AutosavePolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(2),
+            max_operations: 64,
+        }
+    }
+}
+
+impl AutosavePolicy {
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_max_operations(mut self, max_operations: usize) -> Self {
+        self.max_operations = max_operations.max(1);
+        self
+    }
+}
+
+/// 微变更被提升为正式 Change 的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromotionReason {
+    /// 用户显式保存
+    Save,
+    /// Agent 完成一个步骤
+    AgentStep,
+}
+
+/// 一次提升：草稿通道上的若干微变更合并为目标线程上的一个 Change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Promotion {
+    pub change_id: Uuid,
+    pub reason: PromotionReason,
+    /// 被合并的微变更（按提交顺序），仍保留在变更图中供恢复与审计
+    pub micro_changes: Vec<Uuid>,
+}
+
+/// 会话的自动保存状态
+///
+/// 按键级别的操作按策略分组提交到草稿通道（名为 `<线程名>/autosave/<作者>` 的分支），
+/// 只有显式保存或 Agent 步骤完成时才在目标线程上生成一个用户可见的 Change，
+/// 之后草稿通道重新指向该 Change。崩溃后可从草稿通道恢复尚未提升的操作。
+/// 配置预写日志后，草稿通道的创建、微变更与 Head 移动都先写入日志，
+/// 进程重启后由 `CrashRecovery` 重放即可在新的 `ThreadManager` 中找回草稿通道。
+pub struct Autosave {
+    policy: AutosavePolicy,
+    lane: Option<ThreadId>,
+    wal: Option<Arc<WriteAheadLog>>,
+    /// 自上次提升以来的微变更
+    micro_changes: Vec<Uuid>,
+    /// 已落到微变更中的待提交操作数（从待提交列表开头计）
+    flushed: usize,
+    last_flush: Instant,
+    promotions: Vec<Promotion>,
+}
+
+impl Autosave {
+    pub fn new(policy: AutosavePolicy) -> Self {
+        Self {
+            policy,
+            lane: None,
+            wal: None,
+            micro_changes: Vec::new(),
+            flushed: 0,
+            last_flush: Instant::now(),
+            promotions: Vec::new(),
+        }
+    }
+
+    /// 将草稿通道的变化写入预写日志
+    pub fn with_wal(mut self, wal: Arc<WriteAheadLog>) -> Self {
+        self.wal = Some(wal);
+        self
+    }
+
+    pub fn policy(&self) -> &AutosavePolicy {
+        &self.policy
+    }
+
+    /// 草稿通道线程（首次落盘时创建）
+    pub fn lane(&self) -> Option<ThreadId> {
+        self.lane
+    }
+
+    pub fn micro_changes(&self) -> &[Uuid] {
+        &self.micro_changes
+    }
+
+    pub fn promotions(&self) -> &[Promotion] {
+        &self.promotions
+    }
+
+    /// 按策略判断 `pending` 个待提交操作是否应落盘
+    pub fn is_due(&self, pending: usize) -> bool {
+        let unflushed = pending.saturating_sub(self.flushed);
+        unflushed > 0
+            && (unflushed >= self.policy.max_operations
+                || self.last_flush.elapsed() >= self.policy.interval)
+    }
+
+    /// 将尚未落盘的待提交操作提交为草稿通道上的一个微变更
+    pub async fn flush(
+        &mut self,
+        threads: &ThreadManager,
+        thread_id: ThreadId,
+        author_id: Uuid,
+        pending: &[Operation],
+    ) -> anyhow::Result<Option<Uuid>> {
+        self.last_flush = Instant::now();
+        if pending.len() <= self.flushed {
+            return Ok(None);
+        }
+        let lane = self.open_lane(threads, thread_id, author_id).await?;
+        if self.micro_changes.is_empty() {
+            // 提升之后目标线程可能已前进，新的微变更从其最新 Head 开始
+            let head = threads.get_thread(thread_id).and_then(|t| t.head_change_id);
+            threads.set_head(lane, head)?;
+        }
+        let head = threads.get_thread(lane).and_then(|t| t.head_change_id);
+        let mut version = head
+            .and_then(|id| threads.get_change(id))
+            .map(|c| c.version)
+            .unwrap_or_default();
+        version.increment(author_id);
+        let mut change = Change::new(
+            author_id,
+            pending[self.flushed..].to_vec(),
+            version,
+            head.into_iter().collect(),
+        );
+        threads.sign(&mut change)?;
+        let id = change.id;
+        if let Some(wal) = &self.wal {
+            let mut thread = threads
+                .get_thread(lane)
+                .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+            thread.head_change_id = Some(id);
+            wal.append(WalRecord::Commit {
+                thread,
+                change: Box::new(change.clone()),
+            })
+            .await?;
+        }
+        threads.commit_change(lane, change)?;
+        self.micro_changes.push(id);
+        self.flushed = pending.len();
+        Ok(Some(id))
+    }
+
+    /// 记录 `change_id` 已在目标线程上提交了全部待提交操作，草稿通道重新指向它
+    pub async fn promoted(
+        &mut self,
+        threads: &ThreadManager,
+        change_id: Uuid,
+        reason: PromotionReason,
+    ) -> anyhow::Result<()> {
+        if let Some(lane) = self.lane {
+            self.move_lane(threads, lane, Some(change_id)).await?;
+        }
+        self.promotions.push(Promotion {
+            change_id,
+            reason,
+            micro_changes: std::mem::take(&mut self.micro_changes),
+        });
+        self.flushed = 0;
+        Ok(())
+    }
+
+    /// 待提交操作中有一部分被丢弃后，以剩余的操作 `remaining` 重建草稿通道
+    ///
+    /// 草稿通道退回目标线程的 Head，被丢弃的操作不会在崩溃恢复时重新出现。
+    pub async fn discard(
+        &mut self,
+        threads: &ThreadManager,
+        thread_id: ThreadId,
+        author_id: Uuid,
+        remaining: &[Operation],
+    ) -> anyhow::Result<()> {
+        if self.micro_changes.is_empty() {
+            self.flushed = 0;
+            return Ok(());
+        }
+        if let Some(lane) = self.lane {
+            let head = threads.get_thread(thread_id).and_then(|t| t.head_change_id);
+            self.move_lane(threads, lane, head).await?;
+        }
+        self.micro_changes.clear();
+        self.flushed = 0;
+        self.flush(threads, thread_id, author_id, remaining).await?;
+        Ok(())
+    }
+
+    /// 从草稿通道恢复尚未提升的操作（按因果顺序），恢复的操作视为已落盘
+    pub fn recover(
+        &mut self,
+        threads: &ThreadManager,
+        thread_id: ThreadId,
+        author_id: Uuid,
+    ) -> anyhow::Result<Vec<Operation>> {
+        let Some(lane) = threads.get_thread_id_by_name(&lane_name(threads, thread_id, author_id)?)
+        else {
+            return Ok(Vec::new());
+        };
+        self.lane = Some(lane);
+        let lane_head = threads.get_thread(lane).and_then(|t| t.head_change_id);
+        let thread_head = threads.get_thread(thread_id).and_then(|t| t.head_change_id);
+        let promoted = thread_head
+            .map(|head| threads.ancestors(head))
+            .unwrap_or_default();
+        let unpromoted: Vec<Change> = lane_head
+            .map(|head| threads.ancestors(head))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|id| !promoted.contains(id))
+            .filter_map(|id| threads.get_change(id))
+            .collect();
+
+        let unpromoted = MergeEngine::new().sort_changes(unpromoted);
+        self.micro_changes = unpromoted.iter().map(|c| c.id).collect();
+        let operations: Vec<Operation> =
+            unpromoted.into_iter().flat_map(|c| c.operations).collect();
+        self.flushed = operations.len();
+        Ok(operations)
+    }
+
+    async fn open_lane(
+        &mut self,
+        threads: &ThreadManager,
+        thread_id: ThreadId,
+        author_id: Uuid,
+    ) -> anyhow::Result<ThreadId> {
+        if let Some(lane) = self.lane {
+            return Ok(lane);
+        }
+        let name = lane_name(threads, thread_id, author_id)?;
+        let lane = match threads.get_thread_id_by_name(&name) {
+            Some(lane) => lane,
+            None => {
+                let lane = threads.create_branch(thread_id, &name)?;
+                self.log_thread(threads, lane).await?;
+                lane
+            }
+        };
+        self.lane = Some(lane);
+        Ok(lane)
+    }
+
+    /// 移动草稿通道的 Head 并写入日志
+    async fn move_lane(
+        &self,
+        threads: &ThreadManager,
+        lane: ThreadId,
+        head: Option<Uuid>,
+    ) -> anyhow::Result<()> {
+        threads.set_head(lane, head)?;
+        self.log_thread(threads, lane).await
+    }
+
+    async fn log_thread(&self, threads: &ThreadManager, lane: ThreadId) -> anyhow::Result<()> {
+        if let (Some(wal), Some(thread)) = (&self.wal, threads.get_thread(lane)) {
+            wal.append(WalRecord::Thread(thread)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// 作者在线程上的草稿通道名称
+fn lane_name(
+    threads: &ThreadManager,
+    thread_id: ThreadId,
+    author_id: Uuid,
+) -> anyhow::Result<String> {
+    let thread = threads
+        .get_thread(thread_id)
+        .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+    Ok(format!("{}/autosave/{}", thread.name, author_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::history::HistoryRange;
+    use crate::common::intent::{EditorIntent, IntentHandler, SystemIntent};
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::common::provider::traits::StorageProvider;
+    use crate::editor::session::EditorSession;
+    use std::sync::Arc;
+
+    async fn write(session: &EditorSession, path: &str, content: &str) {
+        session
+            .handle(SystemIntent::Editor(EditorIntent::WriteFile {
+                path: path.to_string(),
+                content: content.as_bytes().to_vec(),
+            }))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_micro_changes_are_promoted_and_recoverable() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let author = Uuid::new_v4();
+        let policy = AutosavePolicy::default()
+            .with_interval(Duration::from_secs(3600))
+            .with_max_operations(2);
+        let open = || async {
+            let session = EditorSession::new("/".into(), main, storage.clone(), threads.clone());
+            session.set_author(author).await;
+            session.set_autosave(policy.clone()).await;
+            session
+        };
+
+        // 每两个操作落为一个微变更，显式保存时剩余操作也落盘后整体提升
+        let session = open().await;
+        for text in ["a", "ab", "abc"] {
+            write(&session, "notes.txt", text).await;
+        }
+        {
+            let state = session.state.read().await;
+            let autosave = state.autosave.as_ref().unwrap();
+            assert_eq!(autosave.micro_changes().len(), 1);
+            let lane = threads.get_thread(autosave.lane().unwrap()).unwrap();
+            assert_eq!(lane.name, format!("main/autosave/{}", author));
+        }
+        session
+            .handle(SystemIntent::Editor(EditorIntent::Save))
+            .await
+            .unwrap();
+        {
+            let state = session.state.read().await;
+            let autosave = state.autosave.as_ref().unwrap();
+            let promotion = &autosave.promotions()[0];
+            assert_eq!(promotion.reason, PromotionReason::Save);
+            assert_eq!(promotion.micro_changes.len(), 2);
+            let micro: usize = promotion
+                .micro_changes
+                .iter()
+                .map(|id| threads.get_change(*id).unwrap().operations.len())
+                .sum();
+            assert_eq!(micro, 3);
+            let lane = threads.get_thread(autosave.lane().unwrap()).unwrap();
+            assert_eq!(lane.head_change_id, Some(promotion.change_id));
+        }
+        // 用户可见的线程只多了一个 Change
+        let page = threads.history(main, HistoryRange::new(0, 10)).unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.entries[0].operations.len(), 3);
+
+        // 崩溃前已落盘的操作在新会话中恢复，由 Agent 步骤完成时提升
+        write(&session, "notes.txt", "abcd").await;
+        write(&session, "todo.txt", "x").await;
+        write(&session, "todo.txt", "lost").await;
+        drop(session);
+        let session = open().await;
+        assert_eq!(session.recover_autosave().await.unwrap(), 2);
+        let promoted = session.complete_step().await.unwrap().unwrap();
+        assert_eq!(storage.read_file("notes.txt").await.unwrap(), b"abcd");
+        assert_eq!(storage.read_file("todo.txt").await.unwrap(), b"x");
+        let state = session.state.read().await;
+        let promotion = &state.autosave.as_ref().unwrap().promotions()[0];
+        assert_eq!(promotion.change_id, promoted);
+        assert_eq!(promotion.reason, PromotionReason::AgentStep);
+        assert_eq!(promotion.micro_changes.len(), 1);
+        assert_eq!(
+            threads
+                .history(main, HistoryRange::new(0, 10))
+                .unwrap()
+                .total,
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lanes_survive_a_restart_through_the_wal() {
+        use crate::common::change::wal::WriteAheadLog;
+        use crate::common::lifecycle::recovery::CrashRecovery;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let wal = Arc::new(WriteAheadLog::open(storage.clone(), "wal").await.unwrap());
+        let author = Uuid::new_v4();
+        let policy = AutosavePolicy::default()
+            .with_interval(Duration::from_secs(3600))
+            .with_max_operations(1);
+        let open = |threads: Arc<ThreadManager>, main| {
+            let (storage, wal, policy) = (storage.clone(), wal.clone(), policy.clone());
+            async move {
+                let session = EditorSession::new("/".into(), main, storage, threads);
+                session.set_author(author).await;
+                session.set_wal(wal).await;
+                session.set_autosave(policy).await;
+                session
+            }
+        };
+
+        let before = Arc::new(ThreadManager::new());
+        let main = before.get_thread_id_by_name("main").unwrap();
+        let session = open(before.clone(), main).await;
+        write(&session, "notes.txt", "saved").await;
+        session
+            .handle(SystemIntent::Editor(EditorIntent::Save))
+            .await
+            .unwrap();
+        write(&session, "notes.txt", "draft").await;
+        write(&session, "todo.txt", "x").await;
+        drop(session);
+
+        // 进程重启：新的 ThreadManager 只能从预写日志中找回草稿通道
+        let threads = Arc::new(ThreadManager::new());
+        let report = CrashRecovery::new(threads.clone())
+            .with_wal(wal.clone())
+            .run()
+            .await
+            .unwrap();
+        assert!(report.is_clean());
+        let lane = threads
+            .get_thread_id_by_name(&format!("main/autosave/{}", author))
+            .unwrap();
+        assert_eq!(threads.get_thread(lane).unwrap().parent_id, Some(main));
+
+        let session = open(threads.clone(), main).await;
+        assert_eq!(session.recover_autosave().await.unwrap(), 2);
+        session
+            .handle(SystemIntent::Editor(EditorIntent::Save))
+            .await
+            .unwrap();
+        assert_eq!(storage.read_file("notes.txt").await.unwrap(), b"draft");
+        assert_eq!(storage.read_file("todo.txt").await.unwrap(), b"x");
+        assert_eq!(
+            threads
+                .history(main, HistoryRange::new(0, 10))
+                .unwrap()
+                .total,
+            2
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use crate::common::change::text::TextOperation;
+use anyhow::Result;
+
+/// 已打开文件的内存文本缓冲区
+///
+/// 增量编辑先作用于缓冲区，同时产生对应的 `TextOperation` 暂存到会话中，
+/// 保存时才作为 `Operation::TextEdit` 提交并写入存储。偏移与长度以字符计。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextBuffer {
+    text: String,
+    chars: usize,
+}
+
+impl TextBuffer {
+    pub fn new(text: String) -> Self {
+        let chars = text.chars().count();
+        Self { text, chars }
+    }
+
+    /// 从文件内容创建，非 UTF-8 内容不能按文本编辑
+    pub fn from_bytes(content: &[u8]) -> Result<Self> {
+        Ok(Self::new(String::from_utf8(content.to_vec())?))
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// 字符数
+    pub fn len(&self) -> usize {
+        self.chars
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars == 0
+    }
+
+    /// 在 `offset` 处插入文本，返回对应的操作
+    pub fn insert(&mut self, offset: usize, text: &str) -> Result<TextOperation> {
+        self.apply(TextOperation::insert(offset, text))
+    }
+
+    /// 删除从 `offset` 开始的 `len` 个字符，返回对应的操作
+    pub fn delete(&mut self, offset: usize, len: usize) -> Result<TextOperation> {
+        self.apply(TextOperation::delete(offset, len))
+    }
+
+    /// 以 `text` 替换从 `offset` 开始的 `len` 个字符，拆分为先删除后插入的操作
+    ///
+    /// 范围越界时缓冲区保持不变。
+    pub fn replace(&mut self, offset: usize, len: usize, text: &str) -> Result<Vec<TextOperation>> {
+        if offset + len > self.chars {
+            return Err(out_of_range(offset + len, self.chars));
+        }
+        let mut ops = Vec::with_capacity(2);
+        if len > 0 {
+            ops.push(self.delete(offset, len)?);
+        }
+        if !text.is_empty() {
+            ops.push(self.insert(offset, text)?);
+        }
+        Ok(ops)
+    }
+
+    fn apply(&mut self, op: TextOperation) -> Result<TextOperation> {
+        let (end, added) = match &op {
+            TextOperation::Insert { offset, text, .. } => (*offset, text.chars().count() as isize),
+            TextOperation::Delete { offset, len, .. } => (offset + len, -(*len as isize)),
+        };
+        if end > self.chars {
+            return Err(out_of_range(end, self.chars));
+        }
+        // 锚定到编辑前的文本，提交后在其他内容上重放时能发现冲突
+        let op = op.anchored(&self.text);
+        op.apply(&mut self.text)?;
+        self.chars = self.chars.checked_add_signed(added).unwrap_or_default();
+        Ok(op)
+    }
+}
+
+fn out_of_range(offset: usize, chars: usize) -> anyhow::Error {
+    anyhow::anyhow!("Offset {} is out of range for {} characters", offset, chars)
+}
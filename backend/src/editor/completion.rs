@@ -0,0 +1,397 @@
+use crate::common::endpoint::{ChatMessage, MessageRole};
+use crate::common::provider::traits::StorageProvider;
+use crate::project::index::{SymbolKind, WorkspaceIndex};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// 聊天输入中的提及类型：`@file` 或 `#symbol`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MentionKind {
+    File,
+    Symbol,
+}
+
+impl MentionKind {
+    fn from_trigger(c: char) -> Option<Self> {
+        match c {
+            '@' => Some(MentionKind::File),
+            '#' => Some(MentionKind::Symbol),
+            _ => None,
+        }
+    }
+
+    fn trigger(&self) -> char {
+        match self {
+            MentionKind::File => '@',
+            MentionKind::Symbol => '#',
+        }
+    }
+}
+
+/// 输入中的一个提及，`start..end` 为包含触发字符的字节范围
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mention {
+    pub kind: MentionKind,
+    pub query: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 提及末尾不属于路径或符号的标点
+const TRAILING_PUNCTUATION: &[char] = &[',', '.', ';', ':', ')', '?', '!', '"', '\''];
+
+impl Mention {
+    /// 光标所在的、尚未完成的提及
+    pub fn at(input: &str, cursor: usize) -> Option<Self> {
+        let cursor = cursor.min(input.len());
+        let before = input.get(..cursor)?;
+        let start = before
+            .rfind(char::is_whitespace)
+            .map(|i| i + before[i..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(0);
+        let token = &before[start..];
+        let kind = MentionKind::from_trigger(token.chars().next()?)?;
+        Some(Self {
+            kind,
+            query: token[1..].to_string(),
+            start,
+            end: cursor,
+        })
+    }
+
+    /// 消息中全部已完成的提及
+    pub fn parse_all(input: &str) -> Vec<Self> {
+        let mut mentions = Vec::new();
+        let mut offset = 0;
+        for token in input.split_inclusive(char::is_whitespace) {
+            let start = offset;
+            offset += token.len();
+            let token = token.trim_end().trim_end_matches(TRAILING_PUNCTUATION);
+            let Some(kind) = token.chars().next().and_then(MentionKind::from_trigger) else {
+                continue;
+            };
+            if token.len() > 1 {
+                mentions.push(Self {
+                    kind,
+                    query: token[1..].to_string(),
+                    start,
+                    end: start + token.len(),
+                });
+            }
+        }
+        mentions
+    }
+}
+
+/// 补全候选
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletionCandidate {
+    pub kind: MentionKind,
+    pub label: String,
+    /// 符号所在文件及种类
+    pub detail: Option<String>,
+    /// 接受候选后替换提及的文本
+    pub insert_text: String,
+    pub score: u32,
+}
+
+/// 固定到请求上下文中的内容
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinnedContext {
+    /// 原始提及（含触发字符）
+    pub mention: String,
+    pub path: String,
+    /// 通过 `#symbol` 固定时的符号名
+    pub symbol: Option<String>,
+    pub content: String,
+    pub truncated: bool,
+}
+
+/// 展开提及后的聊天请求
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpandedMessage {
+    pub text: String,
+    pub pinned: Vec<PinnedContext>,
+    /// 无法在索引中解析的提及
+    pub unresolved: Vec<String>,
+}
+
+impl ExpandedMessage {
+    /// 固定上下文作为系统消息，随后是用户消息
+    pub fn to_messages(&self) -> Vec<ChatMessage> {
+        self.pinned
+            .iter()
+            .map(|p| {
+                let title = match &p.symbol {
+                    Some(symbol) => format!("Pinned `{}` from `{}`", symbol, p.path),
+                    None => format!("Pinned file `{}`", p.path),
+                };
+                let note = if p.truncated { "\n(truncated)" } else { "" };
+                ChatMessage::text(
+                    MessageRole::System,
+                    &format!("{}:\n```\n{}\n```{}", title, p.content, note),
+                )
+            })
+            .chain(std::iter::once(ChatMessage::text(
+                MessageRole::User,
+                &self.text,
+            )))
+            .collect()
+    }
+}
+
+/// 聊天输入框的提及补全：基于工作区索引为 `@file` / `#symbol` 排序候选，
+/// 并将发送时的提及展开为固定上下文
+pub struct MentionCompleter {
+    storage: Arc<dyn StorageProvider>,
+    index: Arc<RwLock<WorkspaceIndex>>,
+    /// 单个固定文件的最大字节数
+    max_pinned_bytes: usize,
+}
+
+impl MentionCompleter {
+    pub fn new(storage: Arc<dyn StorageProvider>, index: Arc<RwLock<WorkspaceIndex>>) -> Self {
+        Self {
+            storage,
+            index,
+            max_pinned_bytes: 32 * 1024,
+        }
+    }
+
+    /// 设置单个固定文件的最大字节数
+    pub fn with_max_pinned_bytes(mut self, bytes: usize) -> Self {
+        self.max_pinned_bytes = bytes;
+        self
+    }
+
+    /// 光标处提及的候选，按得分降序，最多 `limit` 个
+    pub fn complete(&self, input: &str, cursor: usize, limit: usize) -> Vec<CompletionCandidate> {
+        let Some(mention) = Mention::at(input, cursor) else {
+            return Vec::new();
+        };
+        let index = self.index.read().unwrap();
+        let mut candidates: Vec<CompletionCandidate> = match mention.kind {
+            MentionKind::File => index
+                .files()
+                .filter_map(|path| {
+                    Some(CompletionCandidate {
+                        kind: MentionKind::File,
+                        label: path.to_string(),
+                        detail: None,
+                        insert_text: format!("@{}", path),
+                        score: score_path(&mention.query, path)?,
+                    })
+                })
+                .collect(),
+            MentionKind::Symbol => index
+                .symbols()
+                .filter_map(|symbol| {
+                    // 定义（函数、类）优先于模块与变量
+                    let bonus = match symbol.kind {
+                        SymbolKind::Function | SymbolKind::Class => 10,
+                        SymbolKind::Module | SymbolKind::Declaration => 0,
+                    };
+                    Some(CompletionCandidate {
+                        kind: MentionKind::Symbol,
+                        label: symbol.name.clone(),
+                        detail: Some(format!("{:?} in {}", symbol.kind, symbol.path)),
+                        insert_text: format!("#{}", symbol.name),
+                        score: score(&mention.query, &symbol.name)? + bonus,
+                    })
+                })
+                .collect(),
+        };
+        candidates.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.label.len().cmp(&b.label.len()))
+                .then_with(|| a.label.cmp(&b.label))
+        });
+        candidates.dedup_by(|a, b| a.insert_text == b.insert_text);
+        candidates.truncate(limit);
+        candidates
+    }
+
+    /// 将消息中已接受的提及展开为固定上下文（同一文件只固定一次）
+    pub async fn expand(&self, message: &str) -> Result<ExpandedMessage> {
+        let mut targets: Vec<(String, String, Option<String>)> = Vec::new();
+        let mut unresolved = Vec::new();
+        for mention in Mention::parse_all(message) {
+            let raw = format!("{}{}", mention.kind.trigger(), mention.query);
+            let resolved = {
+                let index = self.index.read().unwrap();
+                match mention.kind {
+                    MentionKind::File => index
+                        .contains_file(&mention.query)
+                        .then(|| (mention.query.clone(), None)),
+                    MentionKind::Symbol => index
+                        .find_symbol(&mention.query)
+                        .first()
+                        .map(|s| (s.path.clone(), Some(s.name.clone()))),
+                }
+            };
+            match resolved {
+                Some((path, symbol)) => {
+                    if !targets.iter().any(|(_, p, _)| *p == path) {
+                        targets.push((raw, path, symbol));
+                    }
+                }
+                None => unresolved.push(raw),
+            }
+        }
+
+        let mut pinned = Vec::new();
+        for (mention, path, symbol) in targets {
+            let bytes = self.storage.read_file(&path).await?;
+            let truncated = bytes.len() > self.max_pinned_bytes;
+            let content = String::from_utf8_lossy(&bytes[..bytes.len().min(self.max_pinned_bytes)])
+                .into_owned();
+            pinned.push(PinnedContext {
+                mention,
+                path,
+                symbol,
+                content,
+                truncated,
+            });
+        }
+        Ok(ExpandedMessage {
+            text: message.to_string(),
+            pinned,
+            unresolved,
+        })
+    }
+}
+
+/// 文件路径得分：文件名匹配优先于目录匹配
+fn score_path(query: &str, path: &str) -> Option<u32> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    match (score(query, name), score(query, path)) {
+        (Some(n), Some(p)) => Some((n + 50).max(p)),
+        (None, p) => p,
+        (n, None) => n,
+    }
+}
+
+/// 模糊匹配得分（忽略大小写）：完全匹配 > 前缀 > 子串 > 子序列，不匹配时为 `None`
+fn score(query: &str, candidate: &str) -> Option<u32> {
+    let query = query.to_lowercase();
+    let candidate = candidate.to_lowercase();
+    if query.is_empty() {
+        return Some(1);
+    }
+    if candidate == query {
+        return Some(1000);
+    }
+    if candidate.starts_with(&query) {
+        return Some(800);
+    }
+    if candidate.contains(&query) {
+        return Some(600);
+    }
+    // 子序列匹配，字符间隔越大得分越低
+    let mut gaps = 0u32;
+    let mut chars = candidate.chars();
+    for q in query.chars() {
+        let mut skipped = 0u32;
+        loop {
+            match chars.next() {
+                Some(c) if c == q => break,
+                Some(_) => skipped += 1,
+                None => return None,
+            }
+        }
+        gaps += skipped;
+    }
+    Some(400u32.saturating_sub(gaps * 10).max(100))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::meta::ast::MetaNode;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn completer(dir: &std::path::Path) -> MentionCompleter {
+        std::fs::create_dir_all(dir.join("src/engine")).unwrap();
+        std::fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.join("src/engine/parser.rs"), "struct Parser;").unwrap();
+        std::fs::write(dir.join("src/engine/mod.rs"), "pub mod parser;").unwrap();
+
+        let mut index = WorkspaceIndex::new();
+        for path in ["src/main.rs", "src/engine/parser.rs", "src/engine/mod.rs"] {
+            index.add_file(path);
+        }
+        index.index_symbols(
+            "src/engine/parser.rs",
+            &MetaNode::Class {
+                id: Uuid::new_v4(),
+                name: "Parser".to_string(),
+                members: vec![],
+                bases: vec![],
+                metadata: HashMap::new(),
+            },
+        );
+        index.index_symbols("src/main.rs", &MetaNode::module("parse_args"));
+        MentionCompleter::new(
+            Arc::new(LocalFileSystem::new(dir)),
+            Arc::new(RwLock::new(index)),
+        )
+    }
+
+    #[test]
+    fn test_mention_parsing() {
+        let mention = Mention::at("explain @src/ma", 15).unwrap();
+        assert_eq!(mention.kind, MentionKind::File);
+        assert_eq!((mention.query.as_str(), mention.start), ("src/ma", 8));
+        assert!(Mention::at("issue#12 fixed", 8).is_none());
+
+        let all = Mention::parse_all("see @src/main.rs, and #Parser.");
+        let queries: Vec<&str> = all.iter().map(|m| m.query.as_str()).collect();
+        assert_eq!(queries, vec!["src/main.rs", "Parser"]);
+        assert_eq!(all[1].kind, MentionKind::Symbol);
+    }
+
+    #[tokio::test]
+    async fn test_complete_and_expand_mentions() {
+        let dir = tempfile::tempdir().unwrap();
+        let completer = completer(dir.path());
+
+        let files = completer.complete("look at @pars", 13, 5);
+        assert_eq!(files[0].insert_text, "@src/engine/parser.rs");
+        let files = completer.complete("look at @sem", 12, 5);
+        assert_eq!(files[0].label, "src/engine/mod.rs");
+
+        let symbols = completer.complete("#pars", 5, 5);
+        let labels: Vec<&str> = symbols.iter().map(|c| c.label.as_str()).collect();
+        assert_eq!(labels, vec!["Parser", "parse_args"]);
+        assert_eq!(
+            symbols[0].detail.as_deref(),
+            Some("Class in src/engine/parser.rs")
+        );
+
+        let expanded = completer
+            .with_max_pinned_bytes(6)
+            .expand("why does #Parser differ from @src/engine/parser.rs and @nope?")
+            .await
+            .unwrap();
+        assert_eq!(expanded.pinned.len(), 1);
+        assert_eq!(expanded.pinned[0].symbol.as_deref(), Some("Parser"));
+        assert_eq!(expanded.pinned[0].content, "struct");
+        assert!(expanded.pinned[0].truncated);
+        assert_eq!(expanded.unresolved, vec!["@nope"]);
+
+        let messages = expanded.to_messages();
+        assert_eq!(messages.len(), 2);
+        assert!(
+            messages[0]
+                .content
+                .as_text()
+                .starts_with("Pinned `Parser` from `src/engine/parser.rs`")
+        );
+        assert_eq!(messages[1].role, MessageRole::User);
+    }
+}
@@ -1,3 +1,8 @@
+use crate::common::change::notebook::CellOperation;
+use crate::common::change::presence::{ParticipantKind, Selection};
+use crate::common::provider::watch::FileChangeKind;
+use crate::editor::search::SearchQuery;
+use crate::editor::tab::SplitDirection;
 use uuid::Uuid;
 
 /// 编辑器特定的详细意图。
@@ -9,12 +14,85 @@ pub enum EditorIntent {
     /// 切换到指定的 Tab。
     SwitchTab { tab_id: Uuid },
 
+    /// 在指定 Tab 组旁按方向拆分出一个新的空组并聚焦。
+    SplitGroup {
+        group_id: Uuid,
+        direction: SplitDirection,
+    },
+
+    /// 将 Tab 移到指定组的 `index` 处（默认末尾），同一组内即为重新排序；移空的组随之关闭。
+    MoveTab {
+        tab_id: Uuid,
+        group_id: Uuid,
+        index: Option<usize>,
+    },
+
+    /// 固定或取消固定 Tab，固定的 Tab 排在组内最前。
+    PinTab { tab_id: Uuid, pinned: bool },
+
+    /// 关闭 Tab；有未保存修改时拒绝，除非 `force`（强制关闭文件的最后一个 Tab 会丢弃其未保存的修改）。
+    CloseTab { tab_id: Uuid, force: bool },
+
+    /// 更新参与者（用户或 Agent Routine）在文件中的光标与选区（多光标时首个为主选区），并广播给同一线程的其他会话。
+    UpdateCursor {
+        peer_id: Uuid,
+        kind: ParticipantKind,
+        path: String,
+        selections: Vec<Selection>,
+    },
+
     /// 写入内容到指定文件。
     WriteFile { path: String, content: Vec<u8> },
 
+    /// 在文件的 `offset`（字符偏移）处插入文本，保存前只修改内存缓冲区。
+    InsertText {
+        path: String,
+        offset: usize,
+        text: String,
+    },
+
+    /// 删除从 `offset` 开始的 `len` 个字符。
+    DeleteRange {
+        path: String,
+        offset: usize,
+        len: usize,
+    },
+
+    /// 以 `text` 替换从 `offset` 开始的 `len` 个字符。
+    ReplaceRange {
+        path: String,
+        offset: usize,
+        len: usize,
+        text: String,
+    },
+
+    /// 对 Notebook 文件执行单元格级编辑。
+    EditCell { path: String, op: CellOperation },
+
+    /// 检查图像资源，结果缓存在会话状态的 `assets` 中。
+    InspectAsset { path: String },
+
+    /// 替换工作区中的全部匹配，所有文件的修改作为当前 Thread 上的一个 Change 提交。
+    ReplaceAll {
+        query: SearchQuery,
+        replacement: String,
+    },
+
     /// 删除指定路径的文件。
     DeleteFile { path: String },
 
+    /// 文件在编辑器之外被修改（由文件监听发出），没有未保存修改的 Tab 从存储重新载入。
+    FileChanged { path: String, kind: FileChangeKind },
+
     /// 保存当前编辑器状态。
     Save,
+
+    /// 撤销本会话最近一次保存：提交其逆变更，不回滚之后他人（含 Agent）的提交。
+    Undo,
+
+    /// 重做最近一次撤销：提交撤销变更的逆变更。
+    Redo,
+
+    /// 显示尚未保存的修改（统一差异格式），`path` 为空时包含全部文件。
+    Diff { path: Option<String> },
 }
@@ -1,10 +1,24 @@
+pub mod asset;
+pub mod autosave;
+pub mod buffer;
+pub mod completion;
 pub mod intent;
+pub mod preview;
 pub mod reconciler;
+pub mod search;
 pub mod session;
 pub mod tab;
 
 pub use intent::EditorIntent;
 
+pub use asset::{AssetInfo, AssetInspector};
+pub use autosave::{Autosave, AutosavePolicy, Promotion, PromotionReason};
+pub use buffer::TextBuffer;
+pub use completion::{
+    CompletionCandidate, ExpandedMessage, Mention, MentionCompleter, MentionKind, PinnedContext,
+};
+pub use preview::{MarkdownPreview, RenderedPreview};
 pub use reconciler::Reconciler;
+pub use search::{FileMatches, ReplaceSummary, SearchMatch, SearchQuery, WorkspaceSearch};
 pub use session::SessionManager;
-pub use tab::{TabControl, TabState};
+pub use tab::{EditorLayout, LayoutNode, SplitDirection, TabControl, TabGroup, TabState};
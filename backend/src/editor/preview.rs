@@ -0,0 +1,282 @@
+use crate::common::change::Change;
+use crate::common::change::operation::Operation;
+use crate::common::provider::traits::StorageProvider;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd, html};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// 渲染后的预览结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RenderedPreview {
+    pub path: String,
+    /// 已净化的 HTML
+    pub html: String,
+    /// 源内容哈希，内容未变化时跳过重新渲染
+    pub content_hash: String,
+    /// `<div class="mermaid">` 块数量，前端据此决定是否加载 mermaid
+    pub mermaid_blocks: usize,
+    /// 数学公式数量，前端据此决定是否加载 KaTeX
+    pub math_blocks: usize,
+}
+
+/// Markdown 预览渲染服务
+///
+/// mermaid 代码块输出为 `<div class="mermaid">`，公式输出为带 `math` 类的元素，
+/// 由前端分别交给 mermaid 与 KaTeX 渲染；其余 HTML 经净化后输出。
+/// 已预览的文件在相关 Change 提交后增量重新渲染。
+pub struct MarkdownPreview {
+    sanitizer: ammonia::Builder<'static>,
+    previews: RwLock<HashMap<String, RenderedPreview>>,
+}
+
+impl Default for MarkdownPreview {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownPreview {
+    pub fn new() -> Self {
+        let mut sanitizer = ammonia::Builder::default();
+        sanitizer
+            .add_allowed_classes("div", &["mermaid", "math", "math-display"])
+            .add_allowed_classes("span", &["math", "math-inline"])
+            .add_tags(&["input"])
+            .add_tag_attributes("input", &["type", "checked", "disabled"])
+            .add_tag_attributes("code", &["class"]);
+        Self {
+            sanitizer,
+            previews: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 路径是否为 Markdown 文件
+    pub fn is_markdown(path: &str) -> bool {
+        let lower = path.to_lowercase();
+        lower.ends_with(".md") || lower.ends_with(".markdown")
+    }
+
+    /// 渲染 Markdown 文本为净化后的 HTML，返回 (html, mermaid 数量, 公式数量)
+    pub fn render_html(&self, markdown: &str) -> (String, usize, usize) {
+        let options = Options::ENABLE_TABLES
+            | Options::ENABLE_FOOTNOTES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_MATH;
+
+        let mut events = Vec::new();
+        let mut mermaid: Option<String> = None;
+        let mut mermaid_blocks = 0;
+        let mut math_blocks = 0;
+
+        for event in Parser::new_ext(markdown, options) {
+            match event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang)))
+                    if lang.trim() == "mermaid" =>
+                {
+                    mermaid = Some(String::new());
+                }
+                Event::Text(text) if mermaid.is_some() => {
+                    mermaid.as_mut().unwrap().push_str(&text);
+                }
+                Event::End(TagEnd::CodeBlock) if mermaid.is_some() => {
+                    let source = mermaid.take().unwrap();
+                    mermaid_blocks += 1;
+                    events.push(Event::Html(CowStr::from(format!(
+                        "<div class=\"mermaid\">{}</div>\n",
+                        escape_html(&source)
+                    ))));
+                }
+                Event::InlineMath(math) => {
+                    math_blocks += 1;
+                    events.push(Event::InlineHtml(CowStr::from(format!(
+                        "<span class=\"math math-inline\">{}</span>",
+                        escape_html(&math)
+                    ))));
+                }
+                Event::DisplayMath(math) => {
+                    math_blocks += 1;
+                    events.push(Event::Html(CowStr::from(format!(
+                        "<div class=\"math math-display\">{}</div>\n",
+                        escape_html(&math)
+                    ))));
+                }
+                other => events.push(other),
+            }
+        }
+
+        let mut raw = String::new();
+        html::push_html(&mut raw, events.into_iter());
+        (
+            self.sanitizer.clean(&raw).to_string(),
+            mermaid_blocks,
+            math_blocks,
+        )
+    }
+
+    /// 渲染文件预览；内容未变化时直接返回缓存结果
+    pub fn preview(&self, path: &str, content: &[u8]) -> RenderedPreview {
+        let content_hash = format!("{:x}", Sha256::digest(content));
+        if let Some(cached) = self.previews.read().unwrap().get(path)
+            && cached.content_hash == content_hash
+        {
+            return cached.clone();
+        }
+
+        let (html, mermaid_blocks, math_blocks) =
+            self.render_html(&String::from_utf8_lossy(content));
+        let preview = RenderedPreview {
+            path: path.to_string(),
+            html,
+            content_hash,
+            mermaid_blocks,
+            math_blocks,
+        };
+        self.previews
+            .write()
+            .unwrap()
+            .insert(path.to_string(), preview.clone());
+        preview
+    }
+
+    /// 获取已缓存的预览
+    pub fn get(&self, path: &str) -> Option<RenderedPreview> {
+        self.previews.read().unwrap().get(path).cloned()
+    }
+
+    /// 停止跟踪指定文件的预览
+    pub fn close(&self, path: &str) {
+        self.previews.write().unwrap().remove(path);
+    }
+
+    /// 处理已写入存储的 Change：仅重新渲染正在预览的文件，返回发生变化的预览
+    ///
+    /// 每个带路径的操作都会触发：整体写入直接使用操作中的内容，删除时停止预览，
+    /// 其余操作（Blob 引用、Notebook 单元格、文本编辑）从存储读取提交后的内容。
+    pub async fn on_change(
+        &self,
+        change: &Change,
+        storage: &dyn StorageProvider,
+    ) -> Vec<RenderedPreview> {
+        // 每个文件只按其最后一个操作处理一次
+        let mut latest: Vec<(&str, &Operation)> = Vec::new();
+        for op in &change.operations {
+            let Some(path) = op.path() else {
+                continue;
+            };
+            latest.retain(|(p, _)| *p != path);
+            latest.push((path, op));
+        }
+
+        let mut updated = Vec::new();
+        for (path, op) in latest {
+            let Some(before) = self.get(path) else {
+                continue;
+            };
+            let content = match op {
+                Operation::FileDelete { .. } => {
+                    self.close(path);
+                    continue;
+                }
+                Operation::FileWrite { content, .. } => content.clone(),
+                _ => match storage.read_file(path).await {
+                    Ok(content) => content,
+                    Err(_) => continue,
+                },
+            };
+            let preview = self.preview(path, &content);
+            if preview.content_hash != before.content_hash {
+                updated.push(preview);
+            }
+        }
+        updated
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::text::TextOperation;
+    use crate::common::change::version::VectorClock;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_markdown_render_sanitizes_and_hooks() {
+        let preview = MarkdownPreview::new();
+        let markdown = "# Title\n\n<script>alert(1)</script>\n\nInline $a<b$ math.\n\n```mermaid\ngraph TD; A-->B\n```\n\n```rust\nfn main() {}\n```\n";
+        let (html, mermaid, math) = preview.render_html(markdown);
+
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<div class=\"mermaid\">graph TD; A--&gt;B\n</div>"));
+        assert!(html.contains("<span class=\"math math-inline\">a&lt;b</span>"));
+        assert!(html.contains("<code class=\"language-rust\">"));
+        assert_eq!((mermaid, math), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_markdown_rerender_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalFileSystem::new(dir.path());
+        let preview = MarkdownPreview::new();
+        preview.preview("README.md", b"old");
+
+        let change = Change::new(
+            Uuid::new_v4(),
+            vec![
+                Operation::file_write("README.md".to_string(), b"**new**".to_vec()),
+                Operation::file_write("other.md".to_string(), b"ignored".to_vec()),
+            ],
+            VectorClock::new(),
+            vec![],
+        );
+        let updated = preview.on_change(&change, &storage).await;
+        assert_eq!(updated.len(), 1);
+        assert!(updated[0].html.contains("<strong>new</strong>"));
+        assert!(preview.get("other.md").is_none());
+
+        // 内容未变化时不重新渲染
+        assert!(preview.on_change(&change, &storage).await.is_empty());
+
+        // 内容不在操作中时从存储读取提交后的内容
+        storage.write_file("README.md", b"*edited*").await.unwrap();
+        let edit = Change::new(
+            Uuid::new_v4(),
+            vec![Operation::text_edit(
+                "README.md".to_string(),
+                TextOperation::insert(0, "*edited*").anchored("**new**"),
+            )],
+            VectorClock::new(),
+            vec![],
+        );
+        let updated = preview.on_change(&edit, &storage).await;
+        assert_eq!(updated.len(), 1);
+        assert!(updated[0].html.contains("<em>edited</em>"));
+
+        let delete = Change::new(
+            Uuid::new_v4(),
+            vec![Operation::file_delete("README.md".to_string())],
+            VectorClock::new(),
+            vec![],
+        );
+        assert!(preview.on_change(&delete, &storage).await.is_empty());
+        assert!(preview.get("README.md").is_none());
+    }
+}
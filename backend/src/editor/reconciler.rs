@@ -1,36 +1,140 @@
 use crate::common::change::Change;
+use crate::common::change::blob::BlobStore;
+use crate::common::change::notebook::apply_cell_operation;
 use crate::common::change::operation::Operation;
+use crate::common::change::text::apply_text_operation;
 use crate::common::provider::traits::StorageProvider;
 use anyhow::Result;
 use std::sync::Arc;
 
+/// Change 中某个子操作无法应用
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("operation {index} on {path} cannot be applied: {message}")]
+pub struct ApplyError {
+    /// 子操作在 Change 中的下标
+    pub index: usize,
+    pub path: String,
+    pub message: String,
+}
+
 /// 协调本地 UI 状态与 CRDT Thread 状态的一致性，并将变更应用到存储提供者
 pub struct Reconciler {
     storage: Arc<dyn StorageProvider>,
+    blobs: Option<Arc<BlobStore>>,
 }
 
 impl Reconciler {
     pub fn new(storage: Arc<dyn StorageProvider>) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            blobs: None,
+        }
+    }
+
+    /// 配置用于解析 `FileWriteRef` 的 Blob 存储
+    pub fn with_blob_store(mut self, blobs: Arc<BlobStore>) -> Self {
+        self.blobs = Some(blobs);
+        self
     }
 
-    /// 将 Change 应用到底层存储提供者
+    /// 将 Change 作为一个整体应用到底层存储提供者
+    ///
+    /// 先在内存中逐个校验并计算所有子操作的结果，任一子操作冲突（如删除不存在的文件、
+    /// 单元格操作失败）时返回 `ApplyError` 且不写入任何文件；写入阶段失败时恢复已写入文件的原内容。
     pub async fn apply_to_storage(&self, change: &Change) -> Result<()> {
-        for op in &change.operations {
-            match op {
-                Operation::FileWrite { path, content } => {
-                    self.storage.write_file(path, content).await?;
-                }
-                Operation::FileDelete { path } => {
-                    self.storage.delete(path, false).await?;
+        let staged = self.stage(change).await?;
+
+        let mut originals = Vec::with_capacity(staged.len());
+        for (path, _) in &staged {
+            let original = if self.storage.exists(path).await? {
+                Some(self.storage.read_file(path).await?)
+            } else {
+                None
+            };
+            originals.push(original);
+        }
+
+        for (written, ((path, content), original)) in staged.iter().zip(&originals).enumerate() {
+            let result = match (content, original) {
+                (Some(content), _) => self.storage.write_file(path, content).await,
+                (None, Some(_)) => self.storage.delete(path, false).await,
+                // 同一 Change 中新建后又删除的文件
+                (None, None) => Ok(()),
+            };
+            if let Err(e) = result {
+                for ((path, _), original) in staged.iter().zip(&originals).take(written) {
+                    // 尽力回滚，保留最初的错误
+                    let _ = match original {
+                        Some(content) => self.storage.write_file(path, content).await,
+                        None => self.storage.delete(path, false).await,
+                    };
                 }
-                // 目前 MVP 仅处理文件级操作
-                _ => {}
+                return Err(e);
             }
         }
         Ok(())
     }
 
+    /// 计算每个被触及文件的最终内容（`None` 表示删除），按首次触及的顺序排列，不写入存储
+    pub async fn stage(&self, change: &Change) -> Result<Vec<(String, Option<Vec<u8>>)>> {
+        let mut staged: Vec<(String, Option<Vec<u8>>)> = Vec::new();
+        for (index, op) in change.operations.iter().enumerate() {
+            let Some(path) = op.path() else {
+                // 目前 MVP 仅处理文件级操作
+                continue;
+            };
+            let conflict = |message: String| ApplyError {
+                index,
+                path: path.to_string(),
+                message,
+            };
+            let position = staged.iter().position(|(p, _)| p == path);
+            let current = match position {
+                Some(i) => staged[i].1.clone(),
+                None if matches!(
+                    op,
+                    Operation::FileWrite { .. } | Operation::FileWriteRef { .. }
+                ) =>
+                {
+                    None
+                }
+                None if self.storage.exists(path).await? => {
+                    Some(self.storage.read_file(path).await?)
+                }
+                None => None,
+            };
+
+            let content = match op {
+                Operation::FileWrite { content, .. } => Some(content.clone()),
+                Operation::FileWriteRef { blob, .. } => {
+                    let blobs = self
+                        .blobs
+                        .as_ref()
+                        .ok_or_else(|| conflict("no blob store configured".into()))?;
+                    Some(blobs.get(blob).await.map_err(|e| conflict(e.to_string()))?)
+                }
+                Operation::FileDelete { .. } => match current {
+                    Some(_) => None,
+                    None => return Err(conflict("file does not exist".into()).into()),
+                },
+                Operation::NotebookCell { op, .. } => {
+                    let content = current.ok_or_else(|| conflict("file does not exist".into()))?;
+                    Some(apply_cell_operation(&content, op).map_err(|e| conflict(e.to_string()))?)
+                }
+                Operation::TextEdit { op, .. } => {
+                    let content = current.ok_or_else(|| conflict("file does not exist".into()))?;
+                    Some(apply_text_operation(&content, op).map_err(|e| conflict(e.to_string()))?)
+                }
+                _ => continue,
+            };
+            match position {
+                Some(i) => staged[i].1 = content,
+                None => staged.push((path.to_string(), content)),
+            }
+        }
+        Ok(staged)
+    }
+
     /// 应用变更到本地 UI 状态（Mock）
     pub fn apply_to_ui(&self, _changes: Vec<Change>) -> Result<()> {
         Ok(())
@@ -105,4 +209,44 @@ mod tests {
         assert_eq!(written[0].0, "test.rs");
         assert_eq!(written[0].1, b"fn main() {}");
     }
+
+    #[tokio::test]
+    async fn test_apply_to_storage_is_atomic() {
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        storage.write_file("keep.txt", b"old").await.unwrap();
+        let reconciler = Reconciler::new(storage.clone());
+
+        // 第二个子操作删除不存在的文件，整个 Change 不生效
+        let change = Change::new(
+            Uuid::new_v4(),
+            vec![
+                Operation::file_write("keep.txt".to_string(), b"new".to_vec()),
+                Operation::file_delete("missing.txt".to_string()),
+            ],
+            VectorClock::new(),
+            Vec::new(),
+        );
+        let error = reconciler.apply_to_storage(&change).await.unwrap_err();
+        let error = error.downcast::<ApplyError>().unwrap();
+        assert_eq!((error.index, error.path.as_str()), (1, "missing.txt"));
+        assert_eq!(storage.read_file("keep.txt").await.unwrap(), b"old");
+
+        // 同一 Change 中先写后删的文件以最终结果为准
+        let change = Change::new(
+            Uuid::new_v4(),
+            vec![
+                Operation::file_write("tmp.txt".to_string(), b"x".to_vec()),
+                Operation::file_delete("tmp.txt".to_string()),
+                Operation::file_write("keep.txt".to_string(), b"new".to_vec()),
+            ],
+            VectorClock::new(),
+            Vec::new(),
+        );
+        reconciler.apply_to_storage(&change).await.unwrap();
+        assert_eq!(storage.read_file("keep.txt").await.unwrap(), b"new");
+        assert!(!storage.exists("tmp.txt").await.unwrap());
+    }
 }
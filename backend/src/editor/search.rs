@@ -0,0 +1,342 @@
+use crate::common::change::text::TextOperation;
+use crate::common::provider::ignore;
+use crate::common::provider::traits::StorageProvider;
+use crate::editor::buffer::TextBuffer;
+use anyhow::Result;
+use futures::Stream;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::pin::Pin;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 超过该大小的文件不参与搜索
+pub const MAX_FILE_BYTES: usize = 4 * 1024 * 1024;
+
+/// 工作区搜索条件
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub pattern: String,
+    /// 按正则表达式匹配，否则按字面文本匹配
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// 只匹配完整的单词
+    #[serde(default)]
+    pub whole_word: bool,
+    /// 只搜索该目录下的文件（默认整个工作区）
+    #[serde(default)]
+    pub root: String,
+}
+
+impl SearchQuery {
+    /// 按字面文本匹配
+    pub fn literal(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// 按正则表达式匹配，替换文本中可用 `$1`、`${name}` 引用捕获组
+    pub fn regex(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            regex: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn case_sensitive(mut self) -> Self {
+        self.case_sensitive = true;
+        self
+    }
+
+    pub fn whole_word(mut self) -> Self {
+        self.whole_word = true;
+        self
+    }
+
+    pub fn under(mut self, root: &str) -> Self {
+        self.root = root.to_string();
+        self
+    }
+
+    /// 编译为正则表达式，空模式与无效的正则表达式报错
+    pub fn compile(&self) -> Result<Regex> {
+        if self.pattern.is_empty() {
+            return Err(anyhow::anyhow!("Search pattern is empty"));
+        }
+        let pattern = if self.regex {
+            self.pattern.clone()
+        } else {
+            regex::escape(&self.pattern)
+        };
+        let pattern = if self.whole_word {
+            format!(r"\b(?:{})\b", pattern)
+        } else {
+            pattern
+        };
+        Ok(RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .multi_line(true)
+            .build()?)
+    }
+}
+
+/// 一处匹配（行列从 1 开始，偏移与长度以字符计，可直接用于 `ReplaceRange`）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchMatch {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+    pub len: usize,
+    /// 匹配起点所在行的文本
+    pub line_text: String,
+}
+
+/// 一个文件中的全部匹配
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileMatches {
+    pub path: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+/// 按文件逐个产出的搜索结果；丢弃流即停止搜索
+pub type SearchStream = Pin<Box<dyn Stream<Item = Result<FileMatches>> + Send>>;
+
+/// 一个文件中的替换次数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileReplacement {
+    pub path: String,
+    pub replacements: usize,
+}
+
+/// 一次批量替换的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplaceSummary {
+    /// 包含全部修改的 Change，没有任何匹配时为 `None`
+    pub change_id: Option<Uuid>,
+    pub files: Vec<FileReplacement>,
+}
+
+impl ReplaceSummary {
+    pub fn replacements(&self) -> usize {
+        self.files.iter().map(|f| f.replacements).sum()
+    }
+}
+
+/// 工作区范围的文本搜索
+///
+/// 文件经存储提供者列出与读取，跳过 `.gitignore` 忽略的文件、非 UTF-8 文件与过大的文件。
+pub struct WorkspaceSearch {
+    storage: Arc<dyn StorageProvider>,
+}
+
+impl WorkspaceSearch {
+    pub fn new(storage: Arc<dyn StorageProvider>) -> Self {
+        Self { storage }
+    }
+
+    /// 搜索工作区，每读完一个有匹配的文件即产出其结果
+    ///
+    /// 条件无效时立即报错；之后单个文件读取失败（如搜索期间被删除）时跳过该文件。
+    pub fn search(&self, query: &SearchQuery) -> Result<SearchStream> {
+        let regex = query.compile()?;
+        let storage = self.storage.clone();
+        let root = query.root.clone();
+        Ok(Box::pin(async_stream::stream! {
+            let paths = match ignore::walk(storage.as_ref(), &root).await {
+                Ok(paths) => paths,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+            for path in paths {
+                // 先查大小，过大的文件不读入内存
+                match storage.get_metadata(&path).await {
+                    Ok(metadata) if metadata.size <= MAX_FILE_BYTES as u64 => {}
+                    _ => continue,
+                }
+                let Ok(content) = storage.read_file(&path).await else {
+                    continue;
+                };
+                let Ok(text) = std::str::from_utf8(&content) else {
+                    continue;
+                };
+                let matches = find_matches(text, &regex);
+                if !matches.is_empty() {
+                    yield Ok(FileMatches { path, matches });
+                }
+            }
+        }))
+    }
+}
+
+/// 文本中的全部匹配（空匹配除外）
+pub fn find_matches(text: &str, regex: &Regex) -> Vec<SearchMatch> {
+    let mut matches = Vec::new();
+    // 已扫描到的字节位置与字符偏移，以及当前行的起点
+    let (mut byte, mut offset) = (0, 0);
+    let (mut line, mut line_start, mut line_offset) = (1, 0, 0);
+    for m in regex.find_iter(text) {
+        if m.is_empty() {
+            continue;
+        }
+        for (i, c) in text[byte..m.start()].char_indices() {
+            offset += 1;
+            if c == '\n' {
+                line += 1;
+                line_start = byte + i + 1;
+                line_offset = offset;
+            }
+        }
+        byte = m.start();
+        let line_end = text[line_start..]
+            .find('\n')
+            .map_or(text.len(), |end| line_start + end);
+        matches.push(SearchMatch {
+            line,
+            column: offset - line_offset + 1,
+            offset,
+            len: m.as_str().chars().count(),
+            line_text: text[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+        });
+    }
+    matches
+}
+
+/// 替换缓冲区中的全部匹配，返回产生的操作与替换次数
+///
+/// 从后向前替换，使每个操作的偏移都基于此前操作应用后的文本。
+/// 正则表达式条件的替换文本可引用捕获组，字面条件按原样插入。
+pub fn replace_in_buffer(
+    buffer: &mut TextBuffer,
+    query: &SearchQuery,
+    regex: &Regex,
+    replacement: &str,
+) -> Result<(Vec<TextOperation>, usize)> {
+    let text = buffer.text();
+    let mut edits = Vec::new();
+    let (mut byte, mut offset) = (0, 0);
+    for captures in regex.captures_iter(text) {
+        let m = captures.get(0).expect("group 0 is the whole match");
+        if m.is_empty() {
+            continue;
+        }
+        offset += text[byte..m.start()].chars().count();
+        byte = m.start();
+        let mut with = String::new();
+        if query.regex {
+            captures.expand(replacement, &mut with);
+        } else {
+            with.push_str(replacement);
+        }
+        edits.push((offset, m.as_str().chars().count(), with));
+    }
+    let count = edits.len();
+    let mut ops = Vec::new();
+    for (offset, len, with) in edits.into_iter().rev() {
+        ops.extend(buffer.replace(offset, len, &with)?);
+    }
+    Ok((ops, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use futures::StreamExt;
+
+    #[test]
+    fn test_find_matches_reports_positions() {
+        let text = "fn main() {\r\n    let wert = 1;\n    println!(\"{}\", Wert);\n}";
+        let regex = SearchQuery::literal("wert").whole_word().compile().unwrap();
+        let matches = find_matches(text, &regex);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(
+            (matches[0].line, matches[0].column, matches[0].len),
+            (2, 9, 4)
+        );
+        assert_eq!(matches[0].line_text, "    let wert = 1;");
+        assert_eq!(
+            text.chars()
+                .skip(matches[1].offset)
+                .take(4)
+                .collect::<String>(),
+            "Wert"
+        );
+        let exact = SearchQuery::literal("wert")
+            .case_sensitive()
+            .compile()
+            .unwrap();
+        assert_eq!(find_matches(text, &exact).len(), 1);
+        assert!(SearchQuery::regex("(").compile().is_err());
+        assert!(SearchQuery::literal("").compile().is_err());
+    }
+
+    #[test]
+    fn test_replace_in_buffer() {
+        let query = SearchQuery::regex(r"(\w+)\.unwrap\(\)").case_sensitive();
+        let regex = query.compile().unwrap();
+        let mut buffer = TextBuffer::new("let ä = a.unwrap();\nb.unwrap()".to_string());
+        let (ops, count) = replace_in_buffer(&mut buffer, &query, &regex, "$1?").unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(buffer.text(), "let ä = a?;\nb?");
+
+        // 操作按顺序应用到原文本得到同样的结果
+        let mut text = "let ä = a.unwrap();\nb.unwrap()".to_string();
+        for op in &ops {
+            op.apply(&mut text).unwrap();
+        }
+        assert_eq!(text, buffer.text());
+
+        let query = SearchQuery::literal("?");
+        let regex = query.compile().unwrap();
+        replace_in_buffer(&mut buffer, &query, &regex, "$0").unwrap();
+        assert_eq!(buffer.text(), "let ä = a$0;\nb$0");
+    }
+
+    #[tokio::test]
+    async fn test_search_streams_files_respecting_gitignore() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        for (path, content) in [
+            (".gitignore", "target/\n*.gen.rs\n"),
+            ("src/a.rs", "// TODO: a\n"),
+            ("src/b.rs", "fn b() {}\n"),
+            ("src/c.rs", "// todo c\n// TODO d\n"),
+            ("src/api.gen.rs", "// TODO generated\n"),
+            ("target/gen.rs", "// TODO generated\n"),
+        ] {
+            storage.write_file(path, content.as_bytes()).await.unwrap();
+        }
+        storage
+            .write_file("src/blob.bin", &[0xff, b'T', b'O', b'D', b'O'])
+            .await
+            .unwrap();
+        let mut large = b"// TODO large\n".to_vec();
+        large.resize(MAX_FILE_BYTES + 1, b' ');
+        storage.write_file("src/large.rs", &large).await.unwrap();
+
+        let search = WorkspaceSearch::new(storage);
+        let results: Vec<FileMatches> = search
+            .search(&SearchQuery::literal("todo").under("src"))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(
+            results
+                .iter()
+                .map(|f| (f.path.as_str(), f.matches.len()))
+                .collect::<Vec<_>>(),
+            vec![("src/a.rs", 1), ("src/c.rs", 2)]
+        );
+        assert_eq!(results[1].matches[1].line, 2);
+    }
+}
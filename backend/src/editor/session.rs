@@ -1,16 +1,33 @@
 use crate::common::change::Change;
+use crate::common::change::notebook::Notebook;
 use crate::common::change::operation::Operation;
+use crate::common::change::patch::diff_trees;
+use crate::common::change::presence::{
+    ParticipantKind, Presence, PresenceEvent, PresenceTracker, Selection,
+};
+use crate::common::change::signing::AuthorIdentity;
+use crate::common::change::sparse::{SparseCheckout, SparseConfig};
 use crate::common::change::thread::{ThreadId, ThreadManager};
-use crate::common::change::version::VectorClock;
+use crate::common::change::wal::{WalRecord, WriteAheadLog};
 use crate::common::intent::{EditorIntent, IntentHandler, SystemIntent};
 use crate::common::provider::traits::StorageProvider;
+use crate::common::provider::watch::FileChangeKind;
+use crate::editor::asset::{AssetInfo, AssetInspector};
+use crate::editor::autosave::{Autosave, AutosavePolicy, PromotionReason};
+use crate::editor::buffer::TextBuffer;
+use crate::editor::preview::MarkdownPreview;
 use crate::editor::reconciler::Reconciler;
-use crate::editor::tab::TabControl;
+use crate::editor::search::{
+    FileReplacement, ReplaceSummary, SearchQuery, SearchStream, WorkspaceSearch, replace_in_buffer,
+};
+use crate::editor::tab::{EditorLayout, TabControl, TabState};
 use anyhow::Result;
 use async_trait::async_trait;
-use std::collections::HashMap;
+use futures::StreamExt;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, broadcast};
 use uuid::Uuid;
 
 /// 编辑器会话的内部状态
@@ -22,11 +39,419 @@ pub struct EditorSessionState {
     pub thread_manager: Arc<ThreadManager>,
     pub reconciler: Reconciler,
     pub tabs: TabControl,
+    /// 活动组中聚焦的 Tab（与 `tabs.active_tab()` 一致）
     pub active_tab: Option<Uuid>,
     /// 暂存的变更（尚未提交到 Thread）
     pub pending_operations: Vec<Operation>,
     /// 当前 Thread 的最新 Change ID
     pub head_change_id: Option<Uuid>,
+    /// 稀疏检出状态（默认物化全部路径）
+    pub sparse: SparseCheckout,
+    /// Markdown 预览（提交后增量重新渲染）
+    pub previews: MarkdownPreview,
+    /// 资源检查器与已检查资源的元数据（路径 -> 元数据）
+    pub asset_inspector: AssetInspector,
+    pub assets: HashMap<String, AssetInfo>,
+    /// 预写日志（配置后提交先落日志再写入存储）
+    pub wal: Option<Arc<WriteAheadLog>>,
+    /// 本会话提交 Change 时使用的作者 ID
+    pub author_id: Uuid,
+    /// 本会话可撤销的 Change（仅含本会话作者的提交，栈顶为最近一次）
+    pub undo_stack: Vec<Uuid>,
+    /// 撤销产生的逆变更，重做时再次取反
+    pub redo_stack: Vec<Uuid>,
+    /// 自动保存（配置后暂存操作按策略落为草稿通道上的微变更）
+    pub autosave: Option<Autosave>,
+    /// 在线状态（配置后打开或切换文件时发布当前文件）
+    pub presence: Option<(Arc<PresenceTracker>, Presence)>,
+    /// 增量编辑的内存文本缓冲区（路径 -> 缓冲区），同一文件的多个 Tab 共用
+    pub buffers: HashMap<String, TextBuffer>,
+}
+
+impl EditorSessionState {
+    /// Thread 的最新 Change：他人（含 Agent）可能已在本会话之后提交
+    fn thread_head(&self) -> Option<Uuid> {
+        self.thread_manager
+            .get_thread(self.active_thread)
+            .and_then(|t| t.head_change_id)
+            .or(self.head_change_id)
+    }
+
+    /// 将暂存的操作提交为当前 Thread 上的一个 Change，返回其 ID（没有暂存操作时返回 `None`）
+    ///
+    /// 启用自动保存时先将剩余操作落到草稿通道，再以 `reason` 记录这次提升。
+    async fn commit_pending(&mut self, reason: PromotionReason) -> Result<Option<Uuid>> {
+        if self.pending_operations.is_empty() {
+            return Ok(None);
+        }
+        if let Some(autosave) = &mut self.autosave {
+            autosave
+                .flush(
+                    &self.thread_manager,
+                    self.active_thread,
+                    self.author_id,
+                    &self.pending_operations,
+                )
+                .await?;
+        }
+        let change = self.commit(self.pending_operations.clone()).await?;
+        self.pending_operations.clear();
+
+        // 新的保存使重做历史失效，全部 Tab 已无未保存的修改
+        self.undo_stack.push(change.id);
+        self.redo_stack.clear();
+        self.tabs.mark_all_clean();
+
+        // 草稿通道上的微变更已提升为正式 Change
+        if let Some(autosave) = &mut self.autosave {
+            autosave
+                .promoted(&self.thread_manager, change.id, reason)
+                .await?;
+        }
+        Ok(Some(change.id))
+    }
+
+    /// 以本会话作者在当前 Thread Head 上提交操作：写入预写日志、应用到存储，再提交到 Thread
+    ///
+    /// 签名或校验未通过时不写入日志与存储；写入存储失败时存储已回滚，撤回日志记录以免恢复时重放。
+    async fn commit(&mut self, operations: Vec<Operation>) -> Result<Change> {
+        let head = self.thread_head();
+        let mut version = head
+            .and_then(|id| self.thread_manager.get_change(id))
+            .map(|c| c.version)
+            .unwrap_or_default();
+        version.increment(self.author_id);
+
+        let mut change = Change::new(
+            self.author_id,
+            operations,
+            version,
+            head.into_iter().collect(),
+        );
+        self.thread_manager.sign(&mut change)?;
+        self.thread_manager.verify(&change)?;
+
+        // 0. 先写入预写日志，崩溃后由恢复流程补齐
+        let logged = match &self.wal {
+            Some(wal) => {
+                let mut thread = self
+                    .thread_manager
+                    .get_thread(self.active_thread)
+                    .ok_or_else(|| anyhow::anyhow!("Thread not found"))?;
+                thread.head_change_id = Some(change.id);
+                Some(
+                    wal.append(WalRecord::Commit {
+                        thread,
+                        change: Box::new(change.clone()),
+                    })
+                    .await?,
+                )
+            }
+            None => None,
+        };
+
+        // 1. 应用到物理文件系统 (Provider)
+        if let Err(e) = self.reconciler.apply_to_storage(&change).await {
+            if let (Some(wal), Some(seq)) = (&self.wal, logged) {
+                wal.retract(seq).await?;
+            }
+            return Err(e);
+        }
+
+        // 2. 提交到 ThreadManager
+        self.thread_manager
+            .commit_change(self.active_thread, change.clone())?;
+
+        // 3. 更新本地 Head，刷新受影响的 Markdown 预览
+        self.on_committed(&change).await;
+        Ok(change)
+    }
+
+    /// 暂存操作相对存储的统一差异，`path` 为空时包含全部文件；不写入存储
+    async fn diff(&self, path: Option<&str>) -> Result<Value> {
+        let operations = self
+            .pending_operations
+            .iter()
+            .filter(|op| path.is_none() || op.path() == path)
+            .cloned()
+            .collect();
+        let preview = Change::new(self.author_id, operations, Default::default(), Vec::new());
+        let mut before = BTreeMap::new();
+        let mut after = BTreeMap::new();
+        for (path, content) in self.reconciler.stage(&preview).await? {
+            if self.storage.exists(&path).await? {
+                before.insert(path.clone(), self.storage.read_file(&path).await?);
+            }
+            if let Some(content) = content {
+                after.insert(path, content);
+            }
+        }
+        let (files, diff) = diff_trees(&before, &after);
+        Ok(json!({ "files": files, "diff": diff }))
+    }
+
+    /// 暂存操作并标记打开了相关文件的 Tab 未保存；启用自动保存且按策略到期时，将暂存操作落到草稿通道
+    async fn stage(&mut self, operations: impl IntoIterator<Item = Operation>) -> Result<()> {
+        for op in operations {
+            if let Some(path) = op.path() {
+                self.tabs.set_dirty(path, true);
+            }
+            self.pending_operations.push(op);
+        }
+        if let Some(autosave) = &mut self.autosave
+            && autosave.is_due(self.pending_operations.len())
+        {
+            autosave
+                .flush(
+                    &self.thread_manager,
+                    self.active_thread,
+                    self.author_id,
+                    &self.pending_operations,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// 更新参与者的光标与选区并发布到在线状态
+    ///
+    /// 参与者为本会话的用户时同时更新会话自身的状态；其他参与者（如在本会话中编辑的
+    /// Agent Routine）按线程与参与者 ID 保存在在线状态中，首次更新时加入。
+    fn update_cursor(
+        &mut self,
+        peer_id: Uuid,
+        kind: ParticipantKind,
+        path: &str,
+        selections: Vec<Selection>,
+    ) -> Result<()> {
+        let thread_id = self.active_thread;
+        let Some((tracker, own)) = &mut self.presence else {
+            return Err(anyhow::anyhow!(
+                "Presence is not enabled for session {}",
+                self.id
+            ));
+        };
+        let presence = if own.peer_id == peer_id {
+            *own = own.clone().with_file(path).with_selections(selections);
+            own.clone()
+        } else {
+            tracker
+                .list(thread_id)
+                .into_iter()
+                .find(|p| p.peer_id == peer_id)
+                .unwrap_or_else(|| Presence::new(peer_id, thread_id, kind))
+                .with_file(path)
+                .with_selections(selections)
+        };
+        tracker.publish(presence);
+        Ok(())
+    }
+
+    /// 丢弃 `path` 上未保存的修改（暂存操作、文本缓冲区与草稿通道中的对应操作）
+    async fn discard(&mut self, path: &str) -> Result<()> {
+        self.pending_operations.retain(|op| op.path() != Some(path));
+        self.buffers.remove(path);
+        if let Some(autosave) = &mut self.autosave {
+            autosave
+                .discard(
+                    &self.thread_manager,
+                    self.active_thread,
+                    self.author_id,
+                    &self.pending_operations,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// 发布当前活动文件
+    fn announce(&mut self, path: &str) {
+        if let Some((tracker, presence)) = &mut self.presence {
+            *presence = presence.clone().with_file(path);
+            tracker.publish(presence.clone());
+        }
+    }
+
+    /// Change 已提交并写入存储后，更新 Head 并刷新受影响的预览、资源与文本缓冲区
+    async fn on_committed(&mut self, change: &Change) {
+        self.head_change_id = Some(change.id);
+        self.previews.on_change(change, self.storage.as_ref()).await;
+        for op in &change.operations {
+            match op {
+                Operation::FileWrite { path, .. }
+                | Operation::FileWriteRef { path, .. }
+                | Operation::FileDelete { path } => {
+                    self.assets.remove(path);
+                    // 整体写入后缓冲区以存储为准，下次编辑时重新载入
+                    self.buffers.remove(path);
+                }
+                Operation::NotebookCell { path, .. } => {
+                    self.buffers.remove(path);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// 取得文件的文本缓冲区，首次编辑时从存储载入
+    ///
+    /// 同一文件已有未保存的删除或单元格操作时存储内容已过时，需先保存。
+    async fn buffer(&mut self, path: &str) -> Result<&mut TextBuffer> {
+        if !self.buffers.contains_key(path) {
+            let stale = self.pending_operations.iter().any(|op| {
+                matches!(op, Operation::FileDelete { path: p } | Operation::NotebookCell { path: p, .. } if p == path)
+            });
+            if stale {
+                return Err(anyhow::anyhow!(
+                    "Save pending operations on {} before editing its text",
+                    path
+                ));
+            }
+            let content = self.storage.read_file(path).await?;
+            self.buffers
+                .insert(path.to_string(), TextBuffer::from_bytes(&content)?);
+        }
+        Ok(self.buffers.get_mut(path).expect("buffer was just loaded"))
+    }
+
+    /// 文件在编辑器之外被修改后重新载入，返回重新载入的 Tab
+    ///
+    /// 有未保存修改的文件保持不变，保存时以本会话的内容为准；文件被删除时 Tab 保持打开。
+    async fn reload(&mut self, path: &str, kind: FileChangeKind) -> Result<Vec<Uuid>> {
+        if self
+            .pending_operations
+            .iter()
+            .any(|op| op.path() == Some(path))
+        {
+            return Ok(Vec::new());
+        }
+        self.buffers.remove(path);
+        self.assets.remove(path);
+        let tabs: Vec<Uuid> = self.tabs.tabs_for(path).iter().map(|t| t.id).collect();
+        if tabs.is_empty() || kind == FileChangeKind::Removed {
+            return Ok(Vec::new());
+        }
+        let content = self.storage.read_file(path).await?;
+        if Notebook::is_notebook(path)
+            && let Ok(notebook) = Notebook::parse(&content)
+        {
+            for tab_id in &tabs {
+                self.tabs.set_cells(tab_id, notebook.cell_ids());
+            }
+        }
+        if self.previews.get(path).is_some() {
+            self.previews.preview(path, &content);
+        }
+        Ok(tabs)
+    }
+
+    /// 在当前 Thread 上提交 `change_id` 的逆变更并写入存储，返回逆变更的 ID
+    ///
+    /// 逆变更只撤销该 Change 自身的改动，之后他人对其他区域的修改保持不变；
+    /// 与之后的修改冲突时报错且不做任何改动。逆变更与保存一样先写入预写日志、
+    /// 再应用到存储，写入存储失败时 Thread 保持不变。
+    async fn commit_inverse(&mut self, change_id: Uuid) -> Result<Uuid> {
+        if !self.pending_operations.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Save pending operations before undo or redo"
+            ));
+        }
+        let operations = self
+            .thread_manager
+            .inverse_operations(change_id, self.active_thread)?;
+        Ok(self.commit(operations).await?.id)
+    }
+
+    /// 替换工作区中的全部匹配，并将所有文件的修改作为一个 Change 提交
+    ///
+    /// 每个文件的替换在其文本缓冲区上生成增量操作；任一文件失败时丢弃全部修改。
+    /// 已有暂存操作时拒绝，以免无关的修改混入这次替换。
+    async fn replace_all(
+        &mut self,
+        query: &SearchQuery,
+        replacement: &str,
+    ) -> Result<ReplaceSummary> {
+        if !self.pending_operations.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Save pending operations before replacing across the workspace"
+            ));
+        }
+        let files = match self.stage_replacements(query, replacement).await {
+            Ok(files) => files,
+            Err(e) => {
+                let touched: Vec<String> = self
+                    .pending_operations
+                    .iter()
+                    .filter_map(|op| op.path().map(str::to_string))
+                    .collect();
+                for path in touched {
+                    self.discard(&path).await?;
+                }
+                return Err(e);
+            }
+        };
+        let change_id = self.commit_pending(PromotionReason::Save).await?;
+        // 只为替换而载入的缓冲区不再保留
+        self.buffers
+            .retain(|path, _| self.tabs.is_open(path) || !files.iter().any(|f| &f.path == path));
+        Ok(ReplaceSummary { change_id, files })
+    }
+
+    async fn stage_replacements(
+        &mut self,
+        query: &SearchQuery,
+        replacement: &str,
+    ) -> Result<Vec<FileReplacement>> {
+        let regex = query.compile()?;
+        let mut results = WorkspaceSearch::new(self.storage.clone()).search(query)?;
+        let mut files = Vec::new();
+        while let Some(found) = results.next().await {
+            let path = found?.path;
+            let (ops, replacements) =
+                replace_in_buffer(self.buffer(&path).await?, query, &regex, replacement)?;
+            if replacements == 0 {
+                continue;
+            }
+            self.stage(
+                ops.into_iter()
+                    .map(|op| Operation::text_edit(path.clone(), op)),
+            )
+            .await?;
+            files.push(FileReplacement { path, replacements });
+        }
+        Ok(files)
+    }
+
+    /// 将 Notebook 的整体写入转换为相对于已存储版本的单元格操作
+    ///
+    /// 文件不存在、无法解析、没有稳定的单元格 ID（nbformat 4.5 之前）或已有未保存的同路径操作时返回 `None`。
+    async fn notebook_operations(&self, path: &str, content: &[u8]) -> Option<Vec<Operation>> {
+        if !Notebook::is_notebook(path)
+            || self.pending_operations.iter().any(|op| match op {
+                Operation::FileWrite { path: p, .. }
+                | Operation::FileWriteRef { path: p, .. }
+                | Operation::FileDelete { path: p }
+                | Operation::NotebookCell { path: p, .. }
+                | Operation::TextEdit { path: p, .. } => p == path,
+                _ => false,
+            })
+        {
+            return None;
+        }
+        let current = Notebook::parse(&self.storage.read_file(path).await.ok()?).ok()?;
+        let target = Notebook::parse(content).ok()?;
+        // 旧格式的单元格 ID 按位置生成，逐个应用的操作会引用错位的单元格
+        if !current.has_cell_ids() || !target.has_cell_ids() {
+            return None;
+        }
+        Some(
+            current
+                .operations_to(&target)
+                .into_iter()
+                .map(|op| Operation::notebook_cell(path.to_string(), op))
+                .collect(),
+        )
+    }
 }
 
 /// 单个编辑器会话（通过 Arc<RwLock> 实现线程安全）
@@ -43,6 +468,7 @@ impl EditorSession {
         thread_manager: Arc<ThreadManager>,
     ) -> Self {
         let reconciler = Reconciler::new(storage.clone());
+        let asset_inspector = AssetInspector::new(storage.clone());
         let head_change_id = thread_manager
             .get_thread(thread_id)
             .and_then(|t| t.head_change_id);
@@ -59,6 +485,17 @@ impl EditorSession {
             active_tab: None,
             pending_operations: Vec::new(),
             head_change_id,
+            sparse: SparseCheckout::default(),
+            previews: MarkdownPreview::new(),
+            asset_inspector,
+            assets: HashMap::new(),
+            wal: None,
+            author_id: Uuid::new_v4(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            autosave: None,
+            presence: None,
+            buffers: HashMap::new(),
         };
 
         Self {
@@ -66,63 +503,321 @@ impl EditorSession {
             state: Arc::new(RwLock::new(state)),
         }
     }
+
+    /// 启用稀疏检出配置
+    pub async fn set_sparse_config(&self, config: SparseConfig) {
+        self.state.write().await.sparse = SparseCheckout::new(config);
+    }
+
+    /// 启用预写日志（此后启用的自动保存同样写入该日志）
+    pub async fn set_wal(&self, wal: Arc<WriteAheadLog>) {
+        self.state.write().await.wal = Some(wal);
+    }
+
+    /// 启用自动保存；已启用预写日志时草稿通道的变化同样写入日志
+    pub async fn set_autosave(&self, policy: AutosavePolicy) {
+        let mut state = self.state.write().await;
+        let autosave = Autosave::new(policy);
+        state.autosave = Some(match &state.wal {
+            Some(wal) => autosave.with_wal(wal.clone()),
+            None => autosave,
+        });
+    }
+
+    /// 立即将尚未落盘的暂存操作提交到草稿通道（供定时器调用），返回微变更 ID
+    pub async fn flush_autosave(&self) -> Result<Option<Uuid>> {
+        let mut state = self.state.write().await;
+        let state = &mut *state;
+        match &mut state.autosave {
+            Some(autosave) => {
+                autosave
+                    .flush(
+                        &state.thread_manager,
+                        state.active_thread,
+                        state.author_id,
+                        &state.pending_operations,
+                    )
+                    .await
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// 恢复草稿通道上尚未提升的操作到暂存列表开头，返回恢复的操作数
+    ///
+    /// 需要与崩溃前相同的作者（见 `set_author`），且已启用自动保存。
+    pub async fn recover_autosave(&self) -> Result<usize> {
+        let mut state = self.state.write().await;
+        let state = &mut *state;
+        let Some(autosave) = &mut state.autosave else {
+            return Ok(0);
+        };
+        let mut recovered =
+            autosave.recover(&state.thread_manager, state.active_thread, state.author_id)?;
+        let count = recovered.len();
+        for path in recovered.iter().filter_map(Operation::path) {
+            state.tabs.set_dirty(path, true);
+        }
+        recovered.append(&mut state.pending_operations);
+        state.pending_operations = recovered;
+        // 恢复的操作排在缓冲区载入之前，缓冲区需按其重新载入
+        state.buffers.clear();
+        Ok(count)
+    }
+
+    /// Agent 完成一个步骤时将暂存操作提升为正式 Change
+    pub async fn complete_step(&self) -> Result<Option<Uuid>> {
+        self.state
+            .write()
+            .await
+            .commit_pending(PromotionReason::AgentStep)
+            .await
+    }
+
+    /// 发布本会话参与者的在线状态（活动文件随打开或切换 Tab 更新）
+    pub async fn set_presence(&self, tracker: Arc<PresenceTracker>, presence: Presence) {
+        tracker.publish(presence.clone());
+        self.state.write().await.presence = Some((tracker, presence));
+    }
+
+    /// 本会话线程中的在线参与者（含其他会话的用户与 Agent），未启用在线状态时为空
+    pub async fn participants(&self) -> Vec<Presence> {
+        let state = self.state.read().await;
+        match &state.presence {
+            Some((tracker, _)) => tracker.list(state.active_thread),
+            None => Vec::new(),
+        }
+    }
+
+    /// 订阅在线状态变化，供界面渲染其他参与者的光标（事件含其他线程，需按 `thread_id` 过滤）
+    pub async fn subscribe_presence(&self) -> Option<broadcast::Receiver<PresenceEvent>> {
+        let state = self.state.read().await;
+        state
+            .presence
+            .as_ref()
+            .map(|(tracker, _)| tracker.subscribe())
+    }
+
+    /// 文件在内存缓冲区中的当前文本（含未保存的增量编辑），未经增量编辑的文件返回 `None`
+    pub async fn buffer_text(&self, path: &str) -> Option<String> {
+        self.state
+            .read()
+            .await
+            .buffers
+            .get(path)
+            .map(|b| b.text().to_string())
+    }
+
+    /// 当前的编辑器布局（分屏、Tab 组与各组内 Tab 的顺序），前端以此为准渲染
+    pub async fn layout(&self) -> EditorLayout {
+        self.state.read().await.tabs.layout()
+    }
+
+    /// 在会话的工作区中搜索，结果按文件逐个产出
+    pub async fn search(&self, query: &SearchQuery) -> Result<SearchStream> {
+        let storage = self.state.read().await.storage.clone();
+        WorkspaceSearch::new(storage).search(query)
+    }
+
+    /// 以签名身份作为本会话的作者：保存、撤销与重做产生的 Change 均以其签名
+    pub async fn set_signer(&self, identity: Arc<AuthorIdentity>) {
+        let mut state = self.state.write().await;
+        state.author_id = identity.author_id;
+        state.thread_manager.add_signer(identity);
+    }
+
+    /// 设置本会话提交 Change 时使用的作者（默认随机生成）
+    pub async fn set_author(&self, author_id: Uuid) {
+        self.state.write().await.author_id = author_id;
+    }
 }
 
 #[async_trait]
 impl IntentHandler for EditorSession {
     async fn handle(&self, intent: SystemIntent) -> Result<()> {
+        self.respond(intent).await.map(|_| ())
+    }
+
+    /// 打开文件返回 Tab ID，调整分屏、移动与固定 Tab 返回新的布局（`EditorLayout`），
+    /// 检查资源返回元数据，外部修改返回重新载入的 Tab ID，批量替换返回 `ReplaceSummary`，
+    /// 查看差异返回 `{files, diff}`，
+    /// 保存、撤销与重做返回产生的 Change ID
+    async fn respond(&self, intent: SystemIntent) -> Result<Value> {
         match intent {
             SystemIntent::Editor(editor_intent) => {
                 let mut state = self.state.write().await;
                 match editor_intent {
                     EditorIntent::OpenFile { path } => {
-                        let _content = state.storage.read_file(&path).await?;
+                        let content = state.storage.read_file(&path).await?;
+                        // 打开锥外文件时按需展开
+                        state.sparse.expand(&path);
                         let thread_id = state.active_thread;
                         let tab_id = state.tabs.open_tab(thread_id, &path);
+                        if Notebook::is_notebook(&path) {
+                            let notebook = Notebook::parse(&content)?;
+                            state.tabs.set_cells(&tab_id, notebook.cell_ids());
+                        }
+                        if state
+                            .pending_operations
+                            .iter()
+                            .any(|op| op.path() == Some(path.as_str()))
+                        {
+                            state.tabs.set_dirty(&path, true);
+                        }
                         state.active_tab = Some(tab_id);
-                        Ok(())
+                        state.announce(&path);
+                        Ok(json!(tab_id))
                     }
                     EditorIntent::SwitchTab { tab_id } => {
-                        if state.tabs.get_tab(&tab_id).is_some() {
+                        if state.tabs.focus_tab(&tab_id) {
+                            let path = state
+                                .tabs
+                                .get_tab(&tab_id)
+                                .expect("tab exists")
+                                .file_path
+                                .clone();
                             state.active_tab = Some(tab_id);
+                            state.announce(&path);
                         }
-                        Ok(())
+                        Ok(Value::Null)
+                    }
+                    EditorIntent::SplitGroup {
+                        group_id,
+                        direction,
+                    } => {
+                        state.tabs.split_group(&group_id, direction)?;
+                        state.active_tab = state.tabs.active_tab();
+                        Ok(serde_json::to_value(state.tabs.layout())?)
+                    }
+                    EditorIntent::MoveTab {
+                        tab_id,
+                        group_id,
+                        index,
+                    } => {
+                        state.tabs.move_tab(&tab_id, &group_id, index)?;
+                        state.active_tab = Some(tab_id);
+                        Ok(serde_json::to_value(state.tabs.layout())?)
+                    }
+                    EditorIntent::PinTab { tab_id, pinned } => {
+                        state.tabs.set_pinned(&tab_id, pinned)?;
+                        Ok(serde_json::to_value(state.tabs.layout())?)
+                    }
+                    EditorIntent::UpdateCursor {
+                        peer_id,
+                        kind,
+                        path,
+                        selections,
+                    } => {
+                        state.update_cursor(peer_id, kind, &path, selections)?;
+                        Ok(Value::Null)
+                    }
+                    EditorIntent::CloseTab { tab_id, force } => {
+                        let tab = state.tabs.close_tab(&tab_id, force)?;
+                        // 强制关闭文件的最后一个 Tab 时丢弃其未保存的修改
+                        if tab.dirty && !state.tabs.is_open(&tab.file_path) {
+                            state.discard(&tab.file_path).await?;
+                        }
+                        // 活动组改为聚焦相邻的 Tab
+                        state.active_tab = state.tabs.active_tab();
+                        Ok(Value::Null)
                     }
                     EditorIntent::WriteFile { path, content } => {
-                        let op = Operation::file_write(path, content);
-                        state.pending_operations.push(op);
-                        Ok(())
+                        // Notebook 整体写入时尽量转换为单元格级操作
+                        if let Some(ops) = state.notebook_operations(&path, &content).await {
+                            state.buffers.remove(&path);
+                            state.stage(ops).await?;
+                        } else {
+                            // 之后的增量编辑基于写入的内容
+                            match TextBuffer::from_bytes(&content) {
+                                Ok(buffer) => state.buffers.insert(path.clone(), buffer),
+                                Err(_) => state.buffers.remove(&path),
+                            };
+                            state.stage([Operation::file_write(path, content)]).await?;
+                        }
+                        Ok(Value::Null)
+                    }
+                    EditorIntent::InsertText { path, offset, text } => {
+                        let op = state.buffer(&path).await?.insert(offset, &text)?;
+                        state.stage([Operation::text_edit(path, op)]).await?;
+                        Ok(Value::Null)
+                    }
+                    EditorIntent::DeleteRange { path, offset, len } => {
+                        let op = state.buffer(&path).await?.delete(offset, len)?;
+                        state.stage([Operation::text_edit(path, op)]).await?;
+                        Ok(Value::Null)
+                    }
+                    EditorIntent::ReplaceRange {
+                        path,
+                        offset,
+                        len,
+                        text,
+                    } => {
+                        let ops = state.buffer(&path).await?.replace(offset, len, &text)?;
+                        state
+                            .stage(
+                                ops.into_iter()
+                                    .map(|op| Operation::text_edit(path.clone(), op)),
+                            )
+                            .await?;
+                        Ok(Value::Null)
+                    }
+                    EditorIntent::EditCell { path, op } => {
+                        state.buffers.remove(&path);
+                        state.stage([Operation::notebook_cell(path, op)]).await?;
+                        Ok(Value::Null)
+                    }
+                    EditorIntent::InspectAsset { path } => {
+                        let info = state.asset_inspector.inspect(&path).await?;
+                        state.assets.insert(path, info.clone());
+                        Ok(serde_json::to_value(info)?)
+                    }
+                    EditorIntent::ReplaceAll { query, replacement } => {
+                        let summary = state.replace_all(&query, &replacement).await?;
+                        Ok(serde_json::to_value(summary)?)
                     }
                     EditorIntent::DeleteFile { path } => {
-                        let op = Operation::file_delete(path);
-                        state.pending_operations.push(op);
-                        Ok(())
+                        state.buffers.remove(&path);
+                        state.stage([Operation::file_delete(path)]).await?;
+                        Ok(Value::Null)
+                    }
+                    EditorIntent::FileChanged { path, kind } => {
+                        let reloaded = state.reload(&path, kind).await?;
+                        Ok(json!(reloaded))
                     }
+                    EditorIntent::Diff { path } => state.diff(path.as_deref()).await,
                     EditorIntent::Save => {
-                        if !state.pending_operations.is_empty() {
-                            let operations = std::mem::take(&mut state.pending_operations);
-                            let parents =
-                                state.head_change_id.map(|id| vec![id]).unwrap_or_default();
-
-                            let change = Change::new(
-                                Uuid::new_v4(), // 模拟用户 ID
-                                operations,
-                                VectorClock::new(),
-                                parents,
-                            );
-
-                            // 1. 应用到物理文件系统 (Provider)
-                            state.reconciler.apply_to_storage(&change).await?;
-
-                            // 2. 提交到 ThreadManager
-                            state
-                                .thread_manager
-                                .commit_change(state.active_thread, change.clone())?;
-
-                            // 3. 更新本地 Head
-                            state.head_change_id = Some(change.id);
+                        let change_id = state.commit_pending(PromotionReason::Save).await?;
+                        Ok(json!(change_id))
+                    }
+                    EditorIntent::Undo => {
+                        let Some(change_id) = state.undo_stack.pop() else {
+                            return Ok(Value::Null);
+                        };
+                        match state.commit_inverse(change_id).await {
+                            Ok(inverse) => {
+                                state.redo_stack.push(inverse);
+                                Ok(json!(inverse))
+                            }
+                            Err(e) => {
+                                state.undo_stack.push(change_id);
+                                Err(e)
+                            }
+                        }
+                    }
+                    EditorIntent::Redo => {
+                        let Some(change_id) = state.redo_stack.pop() else {
+                            return Ok(Value::Null);
+                        };
+                        match state.commit_inverse(change_id).await {
+                            Ok(inverse) => {
+                                state.undo_stack.push(inverse);
+                                Ok(json!(inverse))
+                            }
+                            Err(e) => {
+                                state.redo_stack.push(change_id);
+                                Err(e)
+                            }
                         }
-                        Ok(())
                     }
                 }
             }
@@ -171,6 +866,22 @@ impl SessionManager {
         self.sessions.get(id).cloned()
     }
 
+    /// 会话中有未保存修改的 Tab（按路径排序），供前端在关闭前提示保存
+    pub async fn dirty_tabs(&self, id: &Uuid) -> Vec<TabState> {
+        match self.sessions.get(id) {
+            Some(session) => session
+                .state
+                .read()
+                .await
+                .tabs
+                .dirty_tabs()
+                .into_iter()
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
     /// 关闭会话
     pub fn close_session(&mut self, id: &Uuid) {
         self.sessions.remove(id);
@@ -182,6 +893,7 @@ mod tests {
     use super::*;
     use crate::common::intent::{IntentCategory, IntentDispatcher};
     use crate::common::provider::traits::FileMetadata;
+    use crate::editor::tab::SplitDirection;
 
     struct MockStorage;
     #[async_trait]
@@ -245,9 +957,9 @@ mod tests {
             .register(IntentCategory::Editor, session.clone())
             .await;
 
-        // 1. 发送 Intent: 打开文件
-        dispatcher
-            .dispatch(SystemIntent::Editor(EditorIntent::OpenFile {
+        // 1. 发送 Intent: 打开文件，响应为新 Tab 的 ID
+        let tab_id: Uuid = dispatcher
+            .request(SystemIntent::Editor(EditorIntent::OpenFile {
                 path: "test.txt".to_string(),
             }))
             .await
@@ -255,7 +967,7 @@ mod tests {
 
         {
             let state = session.state.read().await;
-            assert!(state.active_tab.is_some());
+            assert_eq!(state.active_tab, Some(tab_id));
         }
 
         // 2. 发送 Intent: 写入文件
@@ -272,16 +984,766 @@ mod tests {
             assert_eq!(state.pending_operations.len(), 1);
         }
 
-        // 3. 发送 Intent: 保存
-        dispatcher
-            .dispatch(SystemIntent::Editor(EditorIntent::Save))
+        // 3. 发送 Intent: 保存，响应为提交的 Change ID
+        let change_id: Option<Uuid> = dispatcher
+            .request(SystemIntent::Editor(EditorIntent::Save))
             .await
             .unwrap();
 
         {
             let state = session.state.read().await;
             assert!(state.pending_operations.is_empty());
-            assert!(state.head_change_id.is_some());
+            assert!(change_id.is_some());
+            assert_eq!(state.head_change_id, change_id);
         }
     }
+
+    #[tokio::test]
+    async fn test_notebook_write_becomes_cell_operations() {
+        use crate::common::change::notebook::{CellOperation, CellType, NotebookCell};
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        let mut notebook = Notebook {
+            cells: vec![NotebookCell::new("a", CellType::Code, "x = 1")],
+            metadata: serde_json::json!({}),
+            nbformat: 4,
+            nbformat_minor: 5,
+            extra: serde_json::Map::new(),
+        };
+        storage
+            .write_file("nb.ipynb", &notebook.to_bytes().unwrap())
+            .await
+            .unwrap();
+
+        let thread_manager = Arc::new(ThreadManager::new());
+        let main_id = thread_manager.get_thread_id_by_name("main").unwrap();
+        let session = EditorSession::new("/".to_string(), main_id, storage, thread_manager);
+
+        session
+            .handle(SystemIntent::Editor(EditorIntent::OpenFile {
+                path: "nb.ipynb".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        notebook.cells[0].source = "x = 2".to_string();
+        session
+            .handle(SystemIntent::Editor(EditorIntent::WriteFile {
+                path: "nb.ipynb".to_string(),
+                content: notebook.to_bytes().unwrap(),
+            }))
+            .await
+            .unwrap();
+
+        let state = session.state.read().await;
+        let tab = state.tabs.get_tab(&state.active_tab.unwrap()).unwrap();
+        assert_eq!(tab.cell_ids, vec!["a".to_string()]);
+        assert!(matches!(
+            &state.pending_operations[..],
+            [Operation::NotebookCell {
+                op: CellOperation::UpdateCell { .. },
+                ..
+            }]
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_undo_redo_skips_interleaved_agent_edits() {
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let thread_manager = Arc::new(ThreadManager::new());
+        let main_id = thread_manager.get_thread_id_by_name("main").unwrap();
+        let user = EditorSession::new("/".into(), main_id, storage.clone(), thread_manager.clone());
+        let agent =
+            EditorSession::new("/".into(), main_id, storage.clone(), thread_manager.clone());
+        let (user_id, agent_id) = (Uuid::new_v4(), Uuid::new_v4());
+        user.set_author(user_id).await;
+        agent.set_author(agent_id).await;
+
+        async fn save(session: &EditorSession, path: &str, content: &str) {
+            for intent in [
+                EditorIntent::WriteFile {
+                    path: path.to_string(),
+                    content: content.as_bytes().to_vec(),
+                },
+                EditorIntent::Save,
+            ] {
+                session.handle(SystemIntent::Editor(intent)).await.unwrap();
+            }
+        }
+        let read =
+            || async { String::from_utf8(storage.read_file("a.txt").await.unwrap()).unwrap() };
+
+        save(&user, "a.txt", "one\ntwo\nthree\n").await;
+        save(&user, "a.txt", "ONE\ntwo\nthree\n").await;
+        save(&agent, "a.txt", "ONE\ntwo\nthree\nfour\n").await;
+
+        // 撤销只取反用户自己的改动，Agent 之后追加的行保留
+        user.handle(SystemIntent::Editor(EditorIntent::Undo))
+            .await
+            .unwrap();
+        assert_eq!(read().await, "one\ntwo\nthree\nfour\n");
+        let head = thread_manager.get_thread(main_id).unwrap().head_change_id;
+        let undo = thread_manager.get_change(head.unwrap()).unwrap();
+        assert_eq!(undo.author_id, user_id);
+        assert_eq!(undo.parents.len(), 1);
+
+        // Agent 继续编辑其他区域后重做
+        save(&agent, "a.txt", "one\ntwo\nthree\nfour\nfive\n").await;
+        user.handle(SystemIntent::Editor(EditorIntent::Redo))
+            .await
+            .unwrap();
+        assert_eq!(read().await, "ONE\ntwo\nthree\nfour\nfive\n");
+
+        // Agent 的撤销栈独立于用户
+        agent
+            .handle(SystemIntent::Editor(EditorIntent::Undo))
+            .await
+            .unwrap();
+        assert_eq!(read().await, "ONE\ntwo\nthree\nfour\n");
+        {
+            let state = user.state.read().await;
+            assert_eq!(state.undo_stack.len(), 2);
+            assert!(state.redo_stack.is_empty());
+        }
+
+        // 与之后的修改冲突时报错，栈保持不变
+        save(&agent, "a.txt", "uno\ntwo\nthree\nfour\n").await;
+        assert!(
+            user.handle(SystemIntent::Editor(EditorIntent::Undo))
+                .await
+                .is_err()
+        );
+        assert_eq!(read().await, "uno\ntwo\nthree\nfour\n");
+        assert_eq!(user.state.read().await.undo_stack.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_edits_stay_in_buffer_until_save() {
+        use crate::common::change::text::TextOperation;
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        storage
+            .write_file("a.txt", "héllo world".as_bytes())
+            .await
+            .unwrap();
+        let thread_manager = Arc::new(ThreadManager::new());
+        let main_id = thread_manager.get_thread_id_by_name("main").unwrap();
+        let session =
+            EditorSession::new("/".into(), main_id, storage.clone(), thread_manager.clone());
+        let edit = |intent| session.handle(SystemIntent::Editor(intent));
+        let path = || "a.txt".to_string();
+
+        edit(EditorIntent::InsertText {
+            path: path(),
+            offset: 11,
+            text: "!".to_string(),
+        })
+        .await
+        .unwrap();
+        edit(EditorIntent::ReplaceRange {
+            path: path(),
+            offset: 6,
+            len: 5,
+            text: "wörld".to_string(),
+        })
+        .await
+        .unwrap();
+        edit(EditorIntent::DeleteRange {
+            path: path(),
+            offset: 0,
+            len: 1,
+        })
+        .await
+        .unwrap();
+        // 越界的编辑被拒绝，缓冲区不变
+        assert!(
+            edit(EditorIntent::DeleteRange {
+                path: path(),
+                offset: 10,
+                len: 5,
+            })
+            .await
+            .is_err()
+        );
+        assert_eq!(
+            session.buffer_text("a.txt").await.as_deref(),
+            Some("éllo wörld!")
+        );
+        assert_eq!(
+            storage.read_file("a.txt").await.unwrap(),
+            "héllo world".as_bytes()
+        );
+
+        edit(EditorIntent::Save).await.unwrap();
+        assert_eq!(
+            storage.read_file("a.txt").await.unwrap(),
+            "éllo wörld!".as_bytes()
+        );
+        let head = thread_manager
+            .get_thread(main_id)
+            .unwrap()
+            .head_change_id
+            .unwrap();
+        let change = thread_manager.get_change(head).unwrap();
+        assert_eq!(change.operations.len(), 4);
+        assert!(matches!(
+            &change.operations[1],
+            Operation::TextEdit {
+                op: TextOperation::Delete {
+                    offset: 6,
+                    len: 5,
+                    base: Some(_),
+                },
+                ..
+            }
+        ));
+
+        // 整体写入后继续增量编辑，基于写入的内容
+        edit(EditorIntent::WriteFile {
+            path: path(),
+            content: b"abc".to_vec(),
+        })
+        .await
+        .unwrap();
+        edit(EditorIntent::InsertText {
+            path: path(),
+            offset: 3,
+            text: "d".to_string(),
+        })
+        .await
+        .unwrap();
+        edit(EditorIntent::Save).await.unwrap();
+        assert_eq!(storage.read_file("a.txt").await.unwrap(), b"abcd");
+    }
+
+    #[tokio::test]
+    async fn test_closing_dirty_tabs_requires_force() {
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        storage.write_file("a.txt", b"a").await.unwrap();
+        storage.write_file("b.txt", b"b").await.unwrap();
+        let thread_manager = Arc::new(ThreadManager::new());
+        let main_id = thread_manager.get_thread_id_by_name("main").unwrap();
+        let mut manager = SessionManager::new(thread_manager);
+        let id = manager
+            .create_session("/".into(), main_id, storage.clone())
+            .await;
+        let session = manager.get_session(&id).unwrap();
+        session
+            .set_autosave(AutosavePolicy::default().with_max_operations(1))
+            .await;
+        let open = |path: &str| {
+            session.respond(SystemIntent::Editor(EditorIntent::OpenFile {
+                path: path.to_string(),
+            }))
+        };
+        let a: Uuid = serde_json::from_value(open("a.txt").await.unwrap()).unwrap();
+        let b: Uuid = serde_json::from_value(open("b.txt").await.unwrap()).unwrap();
+        let close = |tab_id, force| {
+            session.handle(SystemIntent::Editor(EditorIntent::CloseTab {
+                tab_id,
+                force,
+            }))
+        };
+        for path in ["a.txt", "b.txt"] {
+            session
+                .handle(SystemIntent::Editor(EditorIntent::InsertText {
+                    path: path.to_string(),
+                    offset: 1,
+                    text: "!".to_string(),
+                }))
+                .await
+                .unwrap();
+        }
+        let dirty = manager.dirty_tabs(&id).await;
+        assert_eq!(dirty.iter().map(|t| t.id).collect::<Vec<_>>(), vec![a, b]);
+
+        assert!(close(a, false).await.is_err());
+        // 强制关闭丢弃 a.txt 的修改（包括草稿通道），b.txt 的修改保留
+        close(a, true).await.unwrap();
+        assert_eq!(manager.dirty_tabs(&id).await.len(), 1);
+        assert_eq!(session.buffer_text("a.txt").await, None);
+        let author = session.state.read().await.author_id;
+        let restarted = EditorSession::new(
+            "/".into(),
+            main_id,
+            storage.clone(),
+            session.state.read().await.thread_manager.clone(),
+        );
+        restarted.set_author(author).await;
+        restarted
+            .set_autosave(AutosavePolicy::default().with_max_operations(1))
+            .await;
+        assert_eq!(restarted.recover_autosave().await.unwrap(), 1);
+        session
+            .handle(SystemIntent::Editor(EditorIntent::Save))
+            .await
+            .unwrap();
+        assert_eq!(storage.read_file("a.txt").await.unwrap(), b"a");
+        assert_eq!(storage.read_file("b.txt").await.unwrap(), b"b!");
+
+        // 保存后可直接关闭
+        assert!(manager.dirty_tabs(&id).await.is_empty());
+        close(b, false).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cursor_updates_reach_other_sessions() {
+        use crate::common::change::presence::CursorPosition;
+
+        let storage = Arc::new(MockStorage);
+        let thread_manager = Arc::new(ThreadManager::new());
+        let main_id = thread_manager.get_thread_id_by_name("main").unwrap();
+        let tracker = Arc::new(PresenceTracker::default());
+        let (alice, routine) = (Uuid::new_v4(), Uuid::new_v4());
+        let editor =
+            EditorSession::new("/".into(), main_id, storage.clone(), thread_manager.clone());
+        let viewer = EditorSession::new("/".into(), main_id, storage, thread_manager);
+        editor
+            .set_presence(
+                tracker.clone(),
+                Presence::new(alice, main_id, ParticipantKind::Human),
+            )
+            .await;
+        viewer
+            .set_presence(
+                tracker.clone(),
+                Presence::new(Uuid::new_v4(), main_id, ParticipantKind::Human),
+            )
+            .await;
+        let mut events = viewer.subscribe_presence().await.unwrap();
+
+        let at = |line, column| CursorPosition { line, column };
+        let caret = |line| Selection {
+            anchor: at(line, 0),
+            head: at(line, 4),
+        };
+        // 用户的多光标，以及在本会话中编辑的 Agent Routine 的光标
+        for (peer_id, kind, selections) in [
+            (alice, ParticipantKind::Human, vec![caret(1), caret(5)]),
+            (routine, ParticipantKind::Agent, vec![caret(9)]),
+        ] {
+            editor
+                .handle(SystemIntent::Editor(EditorIntent::UpdateCursor {
+                    peer_id,
+                    kind,
+                    path: "src/lib.rs".to_string(),
+                    selections,
+                }))
+                .await
+                .unwrap();
+        }
+
+        let participants = viewer.participants().await;
+        assert_eq!(participants.len(), 3);
+        let agent = participants.iter().find(|p| p.peer_id == routine).unwrap();
+        assert_eq!(agent.kind, ParticipantKind::Agent);
+        assert_eq!(agent.active_file.as_deref(), Some("src/lib.rs"));
+        assert_eq!(agent.cursor, Some(at(9, 4)));
+        let human = participants.iter().find(|p| p.peer_id == alice).unwrap();
+        assert_eq!(human.selections, vec![caret(1), caret(5)]);
+        assert_eq!(human.selection, Some(caret(1)));
+        assert_eq!(
+            editor
+                .state
+                .read()
+                .await
+                .presence
+                .as_ref()
+                .unwrap()
+                .1
+                .selections
+                .len(),
+            2
+        );
+
+        let mut updated = Vec::new();
+        while let Ok(PresenceEvent::Updated { presence, .. }) = events.try_recv() {
+            updated.push(presence.peer_id);
+        }
+        assert_eq!(updated, vec![alice, routine]);
+
+        // 切换文件时清除旧文件中的光标
+        editor
+            .handle(SystemIntent::Editor(EditorIntent::OpenFile {
+                path: "README.md".to_string(),
+            }))
+            .await
+            .unwrap();
+        let human = viewer
+            .participants()
+            .await
+            .into_iter()
+            .find(|p| p.peer_id == alice)
+            .unwrap();
+        assert!(human.selections.is_empty() && human.cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_external_changes_reload_clean_tabs() {
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        storage.write_file("a.txt", b"a").await.unwrap();
+        storage.write_file("b.txt", b"b").await.unwrap();
+        let thread_manager = Arc::new(ThreadManager::new());
+        let main_id = thread_manager.get_thread_id_by_name("main").unwrap();
+        let session = EditorSession::new("/".into(), main_id, storage.clone(), thread_manager);
+        let edit = |intent| session.respond(SystemIntent::Editor(intent));
+        let a: Uuid = serde_json::from_value(
+            edit(EditorIntent::OpenFile {
+                path: "a.txt".to_string(),
+            })
+            .await
+            .unwrap(),
+        )
+        .unwrap();
+        edit(EditorIntent::OpenFile {
+            path: "b.txt".to_string(),
+        })
+        .await
+        .unwrap();
+        for path in ["a.txt", "b.txt"] {
+            edit(EditorIntent::InsertText {
+                path: path.to_string(),
+                offset: 1,
+                text: "!".to_string(),
+            })
+            .await
+            .unwrap();
+        }
+        edit(EditorIntent::Save).await.unwrap();
+        edit(EditorIntent::InsertText {
+            path: "b.txt".to_string(),
+            offset: 0,
+            text: "?".to_string(),
+        })
+        .await
+        .unwrap();
+
+        // 外部工具改写两个文件：a.txt 已保存，重新载入；b.txt 有未保存的修改，保持不变
+        storage.write_file("a.txt", b"external").await.unwrap();
+        storage.write_file("b.txt", b"external").await.unwrap();
+        let changed = |path: &str| EditorIntent::FileChanged {
+            path: path.to_string(),
+            kind: FileChangeKind::Modified,
+        };
+        assert_eq!(edit(changed("a.txt")).await.unwrap(), json!([a]));
+        assert_eq!(edit(changed("b.txt")).await.unwrap(), json!([]));
+        assert_eq!(session.buffer_text("a.txt").await, None);
+        assert_eq!(session.buffer_text("b.txt").await.as_deref(), Some("?b!"));
+
+        edit(EditorIntent::InsertText {
+            path: "a.txt".to_string(),
+            offset: 8,
+            text: "!".to_string(),
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            session.buffer_text("a.txt").await.as_deref(),
+            Some("external!")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layout_intents_return_layout() {
+        let thread_manager = Arc::new(ThreadManager::new());
+        let main_id = thread_manager.get_thread_id_by_name("main").unwrap();
+        let session = Arc::new(EditorSession::new(
+            "/".into(),
+            main_id,
+            Arc::new(MockStorage),
+            thread_manager,
+        ));
+        let dispatcher = IntentDispatcher::new();
+        dispatcher
+            .register(IntentCategory::Editor, session.clone())
+            .await;
+        let open = |path: &str| {
+            dispatcher.request::<Uuid>(SystemIntent::Editor(EditorIntent::OpenFile {
+                path: path.to_string(),
+            }))
+        };
+        let a = open("a.rs").await.unwrap();
+        let b = open("b.rs").await.unwrap();
+
+        let layout: EditorLayout = dispatcher
+            .request(SystemIntent::Editor(EditorIntent::PinTab {
+                tab_id: b,
+                pinned: true,
+            }))
+            .await
+            .unwrap();
+        let left = layout.active_group;
+        assert_eq!(layout.groups[0].tabs, vec![b, a]);
+
+        let layout: EditorLayout = dispatcher
+            .request(SystemIntent::Editor(EditorIntent::SplitGroup {
+                group_id: left,
+                direction: SplitDirection::Horizontal,
+            }))
+            .await
+            .unwrap();
+        let right = layout.active_group;
+        assert_eq!(layout.root.groups(), vec![left, right]);
+
+        let layout: EditorLayout = dispatcher
+            .request(SystemIntent::Editor(EditorIntent::MoveTab {
+                tab_id: a,
+                group_id: right,
+                index: None,
+            }))
+            .await
+            .unwrap();
+        assert_eq!(layout.groups[1].tabs, vec![a]);
+        assert_eq!(layout.groups[1].active_tab, Some(a));
+
+        // 关闭右侧唯一的 Tab 后其组关闭，焦点回到左侧
+        dispatcher
+            .dispatch(SystemIntent::Editor(EditorIntent::CloseTab {
+                tab_id: a,
+                force: false,
+            }))
+            .await
+            .unwrap();
+        let layout = session.layout().await;
+        assert_eq!(layout.root.groups(), vec![left]);
+        assert_eq!(layout.active_group, left);
+        assert_eq!(session.state.read().await.active_tab, Some(b));
+    }
+
+    #[tokio::test]
+    async fn test_replace_all_commits_one_change() {
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        for (path, content) in [
+            (".gitignore", "target/\n"),
+            ("src/a.rs", "fn old_name() {}\nold_name();\n"),
+            ("src/b.rs", "use crate::old_name;\n"),
+            ("src/c.rs", "fn old_names() {}\n"),
+            ("target/gen.rs", "old_name();\n"),
+        ] {
+            storage.write_file(path, content.as_bytes()).await.unwrap();
+        }
+        let thread_manager = Arc::new(ThreadManager::new());
+        let main_id = thread_manager.get_thread_id_by_name("main").unwrap();
+        let session =
+            EditorSession::new("/".into(), main_id, storage.clone(), thread_manager.clone());
+        let replace = || {
+            session.respond(SystemIntent::Editor(EditorIntent::ReplaceAll {
+                query: SearchQuery::literal("old_name").whole_word(),
+                replacement: "new_name".to_string(),
+            }))
+        };
+
+        let summary: ReplaceSummary = serde_json::from_value(replace().await.unwrap()).unwrap();
+        assert_eq!(summary.replacements(), 3);
+        assert_eq!(
+            summary
+                .files
+                .iter()
+                .map(|f| f.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["src/a.rs", "src/b.rs"]
+        );
+        let change = thread_manager
+            .get_change(summary.change_id.unwrap())
+            .unwrap();
+        // 每处替换为一次删除与一次插入
+        assert_eq!(change.operations.len(), 6);
+        assert_eq!(
+            storage.read_file("src/a.rs").await.unwrap(),
+            b"fn new_name() {}\nnew_name();\n"
+        );
+        assert_eq!(
+            storage.read_file("src/c.rs").await.unwrap(),
+            b"fn old_names() {}\n"
+        );
+        assert_eq!(
+            storage.read_file("target/gen.rs").await.unwrap(),
+            b"old_name();\n"
+        );
+        assert!(session.state.read().await.buffers.is_empty());
+
+        // 没有匹配时不提交 Change
+        let summary: ReplaceSummary = serde_json::from_value(replace().await.unwrap()).unwrap();
+        assert_eq!(summary, ReplaceSummary::default());
+
+        // 已有未保存的修改时拒绝
+        session
+            .handle(SystemIntent::Editor(EditorIntent::InsertText {
+                path: "src/c.rs".to_string(),
+                offset: 0,
+                text: "old_name ".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert!(replace().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_required_signatures_use_session_signer() {
+        use crate::common::change::signing::{SignaturePolicy, TrustedKeys};
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let identity = Arc::new(AuthorIdentity::generate(Uuid::new_v4()));
+        let keys = Arc::new(TrustedKeys::new(SignaturePolicy::Required));
+        keys.trust(&identity.public()).unwrap();
+        let thread_manager = Arc::new(ThreadManager::new().with_trusted_keys(keys));
+        let main_id = thread_manager.get_thread_id_by_name("main").unwrap();
+        let session = EditorSession::new("/".into(), main_id, storage.clone(), thread_manager);
+        let edit = |intent| session.respond(SystemIntent::Editor(intent));
+        edit(EditorIntent::WriteFile {
+            path: "a.txt".to_string(),
+            content: b"a".to_vec(),
+        })
+        .await
+        .unwrap();
+        edit(EditorIntent::InsertText {
+            path: "a.txt".to_string(),
+            offset: 1,
+            text: "b".to_string(),
+        })
+        .await
+        .unwrap();
+
+        // 没有签名身份时保存被拒绝，存储不变且暂存的操作保留
+        assert!(edit(EditorIntent::Save).await.is_err());
+        assert!(!storage.exists("a.txt").await.unwrap());
+        assert_eq!(session.state.read().await.pending_operations.len(), 2);
+
+        session.set_signer(identity.clone()).await;
+        let saved: Uuid = serde_json::from_value(edit(EditorIntent::Save).await.unwrap()).unwrap();
+        assert_eq!(storage.read_file("a.txt").await.unwrap(), b"ab");
+        let state = session.state.read().await;
+        let change = state.thread_manager.get_change(saved).unwrap();
+        assert_eq!(change.author_id, identity.author_id);
+        assert!(change.signature.is_some());
+        drop(state);
+
+        // 撤销产生的逆变更同样签名
+        let undone: Uuid = serde_json::from_value(edit(EditorIntent::Undo).await.unwrap()).unwrap();
+        assert!(!storage.exists("a.txt").await.unwrap());
+        let state = session.state.read().await;
+        assert!(
+            state
+                .thread_manager
+                .get_change(undone)
+                .unwrap()
+                .signature
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejected_save_is_not_replayed_after_a_crash() {
+        use crate::common::lifecycle::recovery::CrashRecovery;
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        storage.write_file("a.txt", b"a").await.unwrap();
+        let wal = Arc::new(WriteAheadLog::open(storage.clone(), "wal").await.unwrap());
+        let thread_manager = Arc::new(ThreadManager::new());
+        let main_id = thread_manager.get_thread_id_by_name("main").unwrap();
+        let session = EditorSession::new("/".into(), main_id, storage.clone(), thread_manager);
+        session.set_wal(wal.clone()).await;
+        let edit = |intent| session.respond(SystemIntent::Editor(intent));
+        edit(EditorIntent::WriteFile {
+            path: "a.txt".to_string(),
+            content: b"b".to_vec(),
+        })
+        .await
+        .unwrap();
+        edit(EditorIntent::DeleteFile {
+            path: "missing.txt".to_string(),
+        })
+        .await
+        .unwrap();
+
+        // 删除不存在的文件使整个 Change 无法应用
+        assert!(edit(EditorIntent::Save).await.is_err());
+        assert_eq!(storage.read_file("a.txt").await.unwrap(), b"a");
+        assert!(wal.read_all().await.unwrap().0.is_empty());
+
+        let threads = Arc::new(ThreadManager::new());
+        let report = CrashRecovery::new(threads.clone())
+            .with_wal(wal)
+            .with_workspace(storage.clone(), main_id)
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(report.replayed, 0);
+        assert!(report.is_clean());
+        assert!(threads.list_changes().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_undo_is_logged_and_applied_before_reaching_the_thread() {
+        use crate::common::lifecycle::recovery::CrashRecovery;
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let wal = Arc::new(WriteAheadLog::open(storage.clone(), "wal").await.unwrap());
+        let thread_manager = Arc::new(ThreadManager::new());
+        let main_id = thread_manager.get_thread_id_by_name("main").unwrap();
+        let session =
+            EditorSession::new("/".into(), main_id, storage.clone(), thread_manager.clone());
+        session.set_wal(wal.clone()).await;
+        let edit = |intent| session.respond(SystemIntent::Editor(intent));
+        for path in ["a.txt", "b.txt"] {
+            edit(EditorIntent::WriteFile {
+                path: path.to_string(),
+                content: b"x".to_vec(),
+            })
+            .await
+            .unwrap();
+            edit(EditorIntent::Save).await.unwrap();
+        }
+
+        // 文件在编辑器之外被删除，逆变更无法应用：Thread、日志与撤销栈都保持不变
+        storage.delete("b.txt", false).await.unwrap();
+        let head = thread_manager.get_thread(main_id).unwrap().head_change_id;
+        assert!(edit(EditorIntent::Undo).await.is_err());
+        assert_eq!(
+            thread_manager.get_thread(main_id).unwrap().head_change_id,
+            head
+        );
+        assert_eq!(wal.read_all().await.unwrap().0.len(), 2);
+        assert_eq!(session.state.read().await.undo_stack.len(), 2);
+
+        // 撤销成功后逆变更写入日志，崩溃恢复后 Head 指向逆变更
+        storage.write_file("b.txt", b"x").await.unwrap();
+        let undone: Uuid = serde_json::from_value(edit(EditorIntent::Undo).await.unwrap()).unwrap();
+        assert!(!storage.exists("b.txt").await.unwrap());
+        let threads = Arc::new(ThreadManager::new());
+        let report = CrashRecovery::new(threads.clone())
+            .with_wal(wal)
+            .with_workspace(storage.clone(), main_id)
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(report.replayed, 3);
+        assert!(report.is_clean());
+        assert_eq!(
+            threads.get_thread(main_id).unwrap().head_change_id,
+            Some(undone)
+        );
+    }
 }
@@ -1,15 +1,150 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+/// 分屏方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitDirection {
+    /// 左右排列
+    Horizontal,
+    /// 上下排列
+    Vertical,
+}
+
+/// 编辑器布局树：叶子为 Tab 组，内部节点按方向排列子节点
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LayoutNode {
+    Group {
+        id: Uuid,
+    },
+    Split {
+        direction: SplitDirection,
+        children: Vec<LayoutNode>,
+    },
+}
+
+impl LayoutNode {
+    fn is_group(&self, target: Uuid) -> bool {
+        matches!(self, LayoutNode::Group { id } if *id == target)
+    }
+
+    /// 布局中的 Tab 组（从左到右、从上到下）
+    pub fn groups(&self) -> Vec<Uuid> {
+        match self {
+            LayoutNode::Group { id } => vec![*id],
+            LayoutNode::Split { children, .. } => {
+                children.iter().flat_map(LayoutNode::groups).collect()
+            }
+        }
+    }
+
+    /// 在 `target` 之后按 `direction` 放入新组；父节点同向时并列，否则将 `target` 替换为新的分屏
+    fn split(&mut self, target: Uuid, group: Uuid, direction: SplitDirection) -> bool {
+        match self {
+            LayoutNode::Group { id } if *id == target => {
+                *self = LayoutNode::Split {
+                    direction,
+                    children: vec![
+                        LayoutNode::Group { id: target },
+                        LayoutNode::Group { id: group },
+                    ],
+                };
+                true
+            }
+            LayoutNode::Group { .. } => false,
+            LayoutNode::Split {
+                direction: current,
+                children,
+            } => {
+                if *current == direction
+                    && let Some(index) = children.iter().position(|c| c.is_group(target))
+                {
+                    children.insert(index + 1, LayoutNode::Group { id: group });
+                    return true;
+                }
+                children
+                    .iter_mut()
+                    .any(|child| child.split(target, group, direction))
+            }
+        }
+    }
+
+    /// 移除组；只剩一个子节点的分屏由该子节点取代
+    fn remove(&mut self, target: Uuid) -> bool {
+        let LayoutNode::Split { children, .. } = self else {
+            return false;
+        };
+        let removed = match children.iter().position(|c| c.is_group(target)) {
+            Some(index) => {
+                children.remove(index);
+                true
+            }
+            None => children.iter_mut().any(|child| child.remove(target)),
+        };
+        if removed && children.len() == 1 {
+            let only = children.pop().expect("split has one child");
+            *self = only;
+        }
+        removed
+    }
+}
+
+/// Tab 组：一个分屏中按顺序排列的 Tab，固定的 Tab 总在最前
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TabGroup {
+    pub id: Uuid,
+    pub tabs: Vec<Uuid>,
+    pub active_tab: Option<Uuid>,
+}
+
+impl TabGroup {
+    fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            tabs: Vec::new(),
+            active_tab: None,
+        }
+    }
+}
+
+/// 编辑器布局的完整快照，前端据此渲染而无需自行维护
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditorLayout {
+    pub root: LayoutNode,
+    /// 按布局顺序排列的组
+    pub groups: Vec<TabGroup>,
+    /// 按组与组内顺序排列的 Tab
+    pub tabs: Vec<TabState>,
+    pub active_group: Uuid,
+}
+
 /// 实现 Tab 的生命周期管理与元调用
+///
+/// Tab 属于某个 Tab 组，组按布局树分屏排列；始终至少有一个组。
 pub struct TabControl {
     tabs: HashMap<Uuid, TabState>,
+    groups: HashMap<Uuid, TabGroup>,
+    root: LayoutNode,
+    active_group: Uuid,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TabState {
     pub id: Uuid,
     pub thread_id: Uuid,
     pub file_path: String,
+    /// 所在的 Tab 组
+    pub group_id: Uuid,
+    /// 固定的 Tab 排在组内最前
+    pub pinned: bool,
+    /// Notebook 文件的单元格 ID（按顺序），普通文件为空
+    pub cell_ids: Vec<String>,
+    /// 当前聚焦的单元格
+    pub active_cell: Option<String>,
+    /// 文件有尚未保存的修改
+    pub dirty: bool,
 }
 
 impl Default for TabControl {
@@ -20,22 +155,35 @@ impl Default for TabControl {
 
 impl TabControl {
     pub fn new() -> Self {
+        let group = TabGroup::new();
+        let id = group.id;
         Self {
             tabs: HashMap::new(),
+            groups: HashMap::from([(id, group)]),
+            root: LayoutNode::Group { id },
+            active_group: id,
         }
     }
 
-    /// 打开新 Tab
+    /// 在活动组末尾打开新 Tab 并聚焦
     pub fn open_tab(&mut self, thread_id: Uuid, file_path: &str) -> Uuid {
         let id = Uuid::new_v4();
+        let group_id = self.active_group;
         self.tabs.insert(
             id,
             TabState {
                 id,
                 thread_id,
                 file_path: file_path.to_string(),
+                group_id,
+                pinned: false,
+                cell_ids: Vec::new(),
+                active_cell: None,
+                dirty: false,
             },
         );
+        self.place(id, group_id, None);
+        self.focus_tab(&id);
         id
     }
 
@@ -44,9 +192,246 @@ impl TabControl {
         self.tabs.get(id)
     }
 
-    /// 关闭 Tab
-    pub fn close_tab(&mut self, id: &Uuid) {
-        self.tabs.remove(id);
+    /// 聚焦 Tab 及其所在的组，返回 Tab 是否存在
+    pub fn focus_tab(&mut self, id: &Uuid) -> bool {
+        let Some(group_id) = self.tabs.get(id).map(|t| t.group_id) else {
+            return false;
+        };
+        if let Some(group) = self.groups.get_mut(&group_id) {
+            group.active_tab = Some(*id);
+        }
+        self.active_group = group_id;
+        true
+    }
+
+    /// 活动组中聚焦的 Tab
+    pub fn active_tab(&self) -> Option<Uuid> {
+        self.groups
+            .get(&self.active_group)
+            .and_then(|g| g.active_tab)
+    }
+
+    pub fn active_group(&self) -> Uuid {
+        self.active_group
+    }
+
+    pub fn group(&self, id: &Uuid) -> Option<&TabGroup> {
+        self.groups.get(id)
+    }
+
+    /// 布局树
+    pub fn root(&self) -> &LayoutNode {
+        &self.root
+    }
+
+    /// 当前布局的快照
+    pub fn layout(&self) -> EditorLayout {
+        let groups: Vec<TabGroup> = self
+            .root
+            .groups()
+            .iter()
+            .filter_map(|id| self.groups.get(id).cloned())
+            .collect();
+        let tabs = groups
+            .iter()
+            .flat_map(|g| &g.tabs)
+            .filter_map(|id| self.tabs.get(id).cloned())
+            .collect();
+        EditorLayout {
+            root: self.root.clone(),
+            groups,
+            tabs,
+            active_group: self.active_group,
+        }
+    }
+
+    /// 在 `group_id` 旁按 `direction` 拆分出一个新的空组并聚焦，返回新组 ID
+    pub fn split_group(
+        &mut self,
+        group_id: &Uuid,
+        direction: SplitDirection,
+    ) -> anyhow::Result<Uuid> {
+        if !self.groups.contains_key(group_id) {
+            return Err(anyhow::anyhow!("Tab group not found: {}", group_id));
+        }
+        let group = TabGroup::new();
+        let id = group.id;
+        self.root.split(*group_id, id, direction);
+        self.groups.insert(id, group);
+        self.active_group = id;
+        Ok(id)
+    }
+
+    /// 将 Tab 移到 `group_id` 的 `index` 处（默认末尾）并聚焦，同一组内即为重新排序
+    ///
+    /// 位置限制在 Tab 所属的区段内：固定的 Tab 只能排在固定区，其余排在固定区之后。
+    /// 移出后为空的组随之关闭（最后一个组除外）。
+    pub fn move_tab(
+        &mut self,
+        tab_id: &Uuid,
+        group_id: &Uuid,
+        index: Option<usize>,
+    ) -> anyhow::Result<()> {
+        if !self.groups.contains_key(group_id) {
+            return Err(anyhow::anyhow!("Tab group not found: {}", group_id));
+        }
+        let from = self
+            .detach(tab_id)
+            .ok_or_else(|| anyhow::anyhow!("Tab not found: {}", tab_id))?;
+        self.place(*tab_id, *group_id, index);
+        self.focus_tab(tab_id);
+        if from != *group_id {
+            self.close_if_empty(&from);
+        }
+        Ok(())
+    }
+
+    /// 固定或取消固定 Tab：固定的 Tab 移到固定区末尾，取消固定的移到固定区之后
+    pub fn set_pinned(&mut self, tab_id: &Uuid, pinned: bool) -> anyhow::Result<()> {
+        let tab = self
+            .tabs
+            .get_mut(tab_id)
+            .ok_or_else(|| anyhow::anyhow!("Tab not found: {}", tab_id))?;
+        if tab.pinned == pinned {
+            return Ok(());
+        }
+        tab.pinned = pinned;
+        let group_id = tab.group_id;
+        let was_active = self.groups[&group_id].active_tab == Some(*tab_id);
+        self.detach(tab_id);
+        let index = (!pinned).then(|| self.pinned_count(&group_id));
+        self.place(*tab_id, group_id, index);
+        if was_active {
+            self.groups
+                .get_mut(&group_id)
+                .expect("group exists")
+                .active_tab = Some(*tab_id);
+        }
+        Ok(())
+    }
+
+    /// 从所在组中取出 Tab（不删除），聚焦的 Tab 被取出时改为聚焦相邻的 Tab；返回原来的组
+    fn detach(&mut self, tab_id: &Uuid) -> Option<Uuid> {
+        let group_id = self.tabs.get(tab_id)?.group_id;
+        let group = self.groups.get_mut(&group_id)?;
+        let index = group.tabs.iter().position(|id| id == tab_id)?;
+        group.tabs.remove(index);
+        if group.active_tab == Some(*tab_id) {
+            group.active_tab = group.tabs.get(index).or_else(|| group.tabs.last()).copied();
+        }
+        Some(group_id)
+    }
+
+    /// 组内固定的 Tab 数
+    fn pinned_count(&self, group_id: &Uuid) -> usize {
+        self.groups[group_id]
+            .tabs
+            .iter()
+            .take_while(|id| self.tabs[*id].pinned)
+            .count()
+    }
+
+    /// 将已取出的 Tab 放入组中，位置限制在其所属区段内（默认为区段末尾）
+    fn place(&mut self, tab_id: Uuid, group_id: Uuid, index: Option<usize>) {
+        let pinned = self.pinned_count(&group_id);
+        let tab = self.tabs.get_mut(&tab_id).expect("tab exists");
+        tab.group_id = group_id;
+        let group = self.groups.get_mut(&group_id).expect("group exists");
+        let (start, end) = if tab.pinned {
+            (0, pinned)
+        } else {
+            (pinned, group.tabs.len())
+        };
+        let index = index.unwrap_or(end).clamp(start, end);
+        group.tabs.insert(index, tab_id);
+    }
+
+    /// 关闭空组（最后一个组保留），活动组被关闭时聚焦布局中的第一个组
+    fn close_if_empty(&mut self, group_id: &Uuid) {
+        if self.groups.len() == 1 || !self.groups.get(group_id).is_some_and(|g| g.tabs.is_empty()) {
+            return;
+        }
+        self.groups.remove(group_id);
+        self.root.remove(*group_id);
+        if self.active_group == *group_id {
+            self.active_group = self.root.groups()[0];
+        }
+    }
+
+    /// 更新 Notebook Tab 的单元格列表，失效的聚焦单元格会被清除
+    pub fn set_cells(&mut self, id: &Uuid, cell_ids: Vec<String>) {
+        if let Some(tab) = self.tabs.get_mut(id) {
+            if tab
+                .active_cell
+                .as_ref()
+                .is_some_and(|cell| !cell_ids.contains(cell))
+            {
+                tab.active_cell = None;
+            }
+            tab.cell_ids = cell_ids;
+        }
+    }
+
+    /// 聚焦指定单元格，返回是否成功
+    pub fn select_cell(&mut self, id: &Uuid, cell_id: &str) -> bool {
+        match self.tabs.get_mut(id) {
+            Some(tab) if tab.cell_ids.iter().any(|c| c == cell_id) => {
+                tab.active_cell = Some(cell_id.to_string());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 标记打开了 `path` 的全部 Tab 是否有未保存的修改
+    pub fn set_dirty(&mut self, path: &str, dirty: bool) {
+        for tab in self.tabs.values_mut().filter(|t| t.file_path == path) {
+            tab.dirty = dirty;
+        }
+    }
+
+    /// 标记全部 Tab 已保存
+    pub fn mark_all_clean(&mut self) {
+        for tab in self.tabs.values_mut() {
+            tab.dirty = false;
+        }
+    }
+
+    /// 有未保存修改的 Tab（按路径排序）
+    pub fn dirty_tabs(&self) -> Vec<&TabState> {
+        let mut dirty: Vec<_> = self.tabs.values().filter(|t| t.dirty).collect();
+        dirty.sort_by(|a, b| a.file_path.cmp(&b.file_path).then(a.id.cmp(&b.id)));
+        dirty
+    }
+
+    /// 打开了 `path` 的全部 Tab
+    pub fn tabs_for(&self, path: &str) -> Vec<&TabState> {
+        self.tabs.values().filter(|t| t.file_path == path).collect()
+    }
+
+    /// 是否还有 Tab 打开了 `path`
+    pub fn is_open(&self, path: &str) -> bool {
+        self.tabs.values().any(|t| t.file_path == path)
+    }
+
+    /// 关闭 Tab，返回其状态
+    ///
+    /// 有未保存修改的 Tab 只有在 `force` 时才会关闭；关闭后为空的组随之关闭（最后一个组除外）。
+    pub fn close_tab(&mut self, id: &Uuid, force: bool) -> anyhow::Result<TabState> {
+        let tab = self
+            .tabs
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("Tab not found: {}", id))?;
+        if tab.dirty && !force {
+            return Err(anyhow::anyhow!(
+                "Tab {} has unsaved changes to {}",
+                id,
+                tab.file_path
+            ));
+        }
+        let group_id = self.detach(id).expect("tab belongs to a group");
+        self.close_if_empty(&group_id);
+        Ok(self.tabs.remove(id).expect("tab exists"))
     }
 }
 
@@ -62,5 +447,83 @@ mod tests {
         let tab = control.get_tab(&id).unwrap();
         assert_eq!(tab.thread_id, thread_id);
         assert_eq!(tab.file_path, "src/lib.rs");
+
+        let nb = control.open_tab(thread_id, "analysis.ipynb");
+        control.set_cells(&nb, vec!["a".to_string(), "b".to_string()]);
+        assert!(control.select_cell(&nb, "b"));
+        assert!(!control.select_cell(&nb, "c"));
+        control.set_cells(&nb, vec!["a".to_string()]);
+        assert_eq!(control.get_tab(&nb).unwrap().active_cell, None);
+
+        // 有未保存修改的 Tab 需强制关闭
+        let split = control.open_tab(thread_id, "src/lib.rs");
+        control.set_dirty("src/lib.rs", true);
+        assert_eq!(control.dirty_tabs().len(), 2);
+        assert!(control.close_tab(&id, false).is_err());
+        assert!(control.close_tab(&id, true).unwrap().dirty);
+        assert!(control.is_open("src/lib.rs"));
+        control.mark_all_clean();
+        assert!(control.close_tab(&split, false).is_ok());
+        assert!(!control.is_open("src/lib.rs"));
+        assert!(control.close_tab(&split, true).is_err());
+    }
+
+    #[test]
+    fn test_groups_splits_and_pins() {
+        let mut control = TabControl::new();
+        let thread_id = Uuid::new_v4();
+        let left = control.active_group();
+        let a = control.open_tab(thread_id, "a.rs");
+        let b = control.open_tab(thread_id, "b.rs");
+        let c = control.open_tab(thread_id, "c.rs");
+        assert_eq!(control.active_tab(), Some(c));
+
+        // 固定的 Tab 排在最前，未固定的 Tab 不能移入固定区
+        control.set_pinned(&c, true).unwrap();
+        assert_eq!(control.group(&left).unwrap().tabs, vec![c, a, b]);
+        control.move_tab(&b, &left, Some(0)).unwrap();
+        assert_eq!(control.group(&left).unwrap().tabs, vec![c, b, a]);
+        control.set_pinned(&c, false).unwrap();
+        assert_eq!(control.group(&left).unwrap().tabs, vec![c, b, a]);
+
+        // 向右拆分后再在右侧向下拆分，形成嵌套的布局树
+        let right = control
+            .split_group(&left, SplitDirection::Horizontal)
+            .unwrap();
+        control.move_tab(&a, &right, None).unwrap();
+        assert_eq!(control.active_group(), right);
+        assert_eq!(control.get_tab(&a).unwrap().group_id, right);
+        assert_eq!(control.group(&left).unwrap().active_tab, Some(b));
+        let bottom = control
+            .split_group(&right, SplitDirection::Vertical)
+            .unwrap();
+        let third = control
+            .split_group(&left, SplitDirection::Horizontal)
+            .unwrap();
+        assert_eq!(control.root().groups(), vec![left, third, right, bottom]);
+
+        // 移出或关闭最后一个 Tab 的组随之关闭，分屏只剩一个子节点时折叠
+        let d = control.open_tab(thread_id, "d.rs");
+        assert_eq!(control.get_tab(&d).unwrap().group_id, third);
+        control.move_tab(&d, &bottom, None).unwrap();
+        control.close_tab(&a, false).unwrap();
+        assert_eq!(
+            control.root(),
+            &LayoutNode::Split {
+                direction: SplitDirection::Horizontal,
+                children: vec![
+                    LayoutNode::Group { id: left },
+                    LayoutNode::Group { id: bottom },
+                ],
+            }
+        );
+        let layout = control.layout();
+        assert_eq!(layout.active_group, bottom);
+        assert_eq!(
+            layout.tabs.iter().map(|t| t.id).collect::<Vec<_>>(),
+            vec![c, b, d]
+        );
+        assert!(control.move_tab(&d, &right, None).is_err());
+        assert_eq!(control.get_tab(&d).unwrap().group_id, bottom);
     }
 }
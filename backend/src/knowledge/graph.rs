@@ -1,9 +1,43 @@
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 知识图谱节点类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    /// HTTP 接口（`METHOD /path`）
+    Endpoint,
+    /// 数据结构（Protobuf message、OpenAPI schema）
+    Message,
+    Enum,
+    /// RPC 服务
+    Service,
+    /// RPC 方法
+    Rpc,
+    /// 实现上述接口的代码符号
+    Code,
+}
+
+/// 带类型的知识图谱节点
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnowledgeNode {
+    /// 全局唯一 ID，如 `openapi:GET /users/{id}`、`proto:shop.v1.Order`
+    pub id: String,
+    pub kind: NodeKind,
+    pub name: String,
+    /// 定义所在文件
+    pub source: Option<String>,
+    pub description: Option<String>,
+    /// 额外属性（字段列表、请求/响应类型等）
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+}
 
 /// 维护项目的高层架构关系
 pub struct KnowledgeGraph {
     // Mock 图结构：节点 -> 邻接列表
     edges: HashMap<String, Vec<String>>,
+    nodes: BTreeMap<String, KnowledgeNode>,
 }
 
 impl Default for KnowledgeGraph {
@@ -16,21 +50,97 @@ impl KnowledgeGraph {
     pub fn new() -> Self {
         Self {
             edges: HashMap::new(),
+            nodes: BTreeMap::new(),
         }
     }
 
     /// 添加关系
     pub fn add_relation(&mut self, from: &str, to: &str) {
-        self.edges
-            .entry(from.to_string())
-            .or_default()
-            .push(to.to_string());
+        let targets = self.edges.entry(from.to_string()).or_default();
+        if !targets.iter().any(|t| t == to) {
+            targets.push(to.to_string());
+        }
     }
 
     /// 获取受影响的节点
     pub fn get_affected(&self, node: &str) -> Vec<String> {
         self.edges.get(node).cloned().unwrap_or_default()
     }
+
+    /// 添加或替换节点
+    pub fn add_node(&mut self, node: KnowledgeNode) {
+        self.nodes.insert(node.id.clone(), node);
+    }
+
+    pub fn node(&self, id: &str) -> Option<&KnowledgeNode> {
+        self.nodes.get(id)
+    }
+
+    pub fn nodes(&self, kind: NodeKind) -> impl Iterator<Item = &KnowledgeNode> {
+        self.nodes.values().filter(move |n| n.kind == kind)
+    }
+
+    /// 节点的直接关联节点（出边）
+    pub fn neighbors(&self, id: &str) -> Vec<&KnowledgeNode> {
+        self.edges
+            .get(id)
+            .into_iter()
+            .flatten()
+            .filter_map(|to| self.nodes.get(to))
+            .collect()
+    }
+
+    /// 移除定义在 `source` 中的节点及其出入边（重新导入文件前调用）
+    pub fn remove_source(&mut self, source: &str) -> usize {
+        let removed: Vec<String> = self
+            .nodes
+            .values()
+            .filter(|n| n.source.as_deref() == Some(source))
+            .map(|n| n.id.clone())
+            .collect();
+        for id in &removed {
+            self.nodes.remove(id);
+            self.edges.remove(id);
+        }
+        for targets in self.edges.values_mut() {
+            targets.retain(|t| !removed.contains(t));
+        }
+        removed.len()
+    }
+
+    /// 按名称、描述与属性中的词检索节点，按命中词数降序
+    pub fn search(&self, query: &str) -> Vec<&KnowledgeNode> {
+        let terms: Vec<String> = query
+            .split(|c: char| !c.is_alphanumeric() && c != '/' && c != '{' && c != '}')
+            .filter(|t| t.len() > 1)
+            .map(str::to_lowercase)
+            .collect();
+        let mut scored: Vec<(usize, &KnowledgeNode)> = self
+            .nodes
+            .values()
+            .filter(|n| n.kind != NodeKind::Code)
+            .map(|node| {
+                let text = format!(
+                    "{} {} {}",
+                    node.name,
+                    node.description.as_deref().unwrap_or_default(),
+                    node.attributes
+                        .values()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                )
+                .to_lowercase();
+                (
+                    terms.iter().filter(|t| text.contains(t.as_str())).count(),
+                    node,
+                )
+            })
+            .filter(|(score, _)| *score > 0)
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.id.cmp(&b.1.id)));
+        scored.into_iter().map(|(_, node)| node).collect()
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,187 @@
+use crate::common::change::sparse::SparseCheckout;
+use crate::common::endpoint::traits::LLMClient;
+use crate::common::intent::IntentSubscription;
+use crate::common::provider::traits::StorageProvider;
+use crate::common::provider::watch::{FileChange, FileChangeKind};
+use crate::knowledge::store::VectorStore;
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// 单个文件默认最多嵌入的字节数
+pub const DEFAULT_MAX_BYTES: usize = 32 * 1024;
+
+/// 随文件变化重新嵌入工作区文件，使向量库与存储中的内容保持一致
+pub struct KnowledgeIndexer {
+    storage: Arc<dyn StorageProvider>,
+    client: Arc<dyn LLMClient>,
+    model: String,
+    store: Arc<RwLock<VectorStore>>,
+    /// 超出部分截断后再嵌入
+    max_bytes: usize,
+    /// 只索引稀疏检出锥内的文件
+    sparse: SparseCheckout,
+}
+
+impl KnowledgeIndexer {
+    pub fn new(
+        storage: Arc<dyn StorageProvider>,
+        client: Arc<dyn LLMClient>,
+        model: &str,
+        store: Arc<RwLock<VectorStore>>,
+    ) -> Self {
+        Self {
+            storage,
+            client,
+            model: model.to_string(),
+            store,
+            max_bytes: DEFAULT_MAX_BYTES,
+            sparse: SparseCheckout::default(),
+        }
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// 只为稀疏检出锥内的文件建立索引
+    pub fn with_sparse(mut self, sparse: SparseCheckout) -> Self {
+        self.sparse = sparse;
+        self
+    }
+
+    /// 按文件变化更新向量库（以路径为 ID），返回向量库是否改变
+    ///
+    /// 删除的文件移除其向量，其余文件重新嵌入；非 UTF-8 文件、空文件与稀疏锥外的文件
+    /// 不建立索引。
+    pub async fn on_file_changed(&self, change: &FileChange) -> Result<bool> {
+        if change.kind == FileChangeKind::Removed || !self.sparse.in_cone(&change.path) {
+            return Ok(self.store.write().await.remove(&change.path));
+        }
+        let content = self.storage.read_file(&change.path).await?;
+        let mut end = content.len().min(self.max_bytes);
+        let text = loop {
+            match std::str::from_utf8(&content[..end]) {
+                Ok(text) => break text,
+                // 截断点落在多字节字符中间时向前退到字符边界
+                Err(e) if e.error_len().is_none() => end = e.valid_up_to(),
+                Err(_) => return Ok(self.store.write().await.remove(&change.path)),
+            }
+        };
+        if text.trim().is_empty() {
+            return Ok(self.store.write().await.remove(&change.path));
+        }
+        let response = self.client.embed(&self.model, &[text.to_string()]).await?;
+        let vector = response
+            .data
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No embedding returned for {}", change.path))?;
+        self.store.write().await.add(&change.path, vector);
+        Ok(true)
+    }
+
+    /// 按订阅到的文件变化意图（见 `FileChange::topic`）持续更新索引，直到分发器被释放
+    ///
+    /// 单个文件嵌入失败不影响后续文件，下次变化时重试。
+    pub fn follow(self: Arc<Self>, mut changes: IntentSubscription) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(outcome) = changes.recv().await {
+                if let Some(change) = FileChange::from_outcome(&outcome) {
+                    let _ = self.on_file_changed(&change).await;
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::common::intent::{EditorIntent, IntentDispatcher, SystemIntent};
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+
+    /// 以文本长度作为一维向量
+    fn lengths() -> Arc<ScriptedClient> {
+        Arc::new(ScriptedClient::new("lengths").with_embedder(|text| vec![text.len() as f32]))
+    }
+
+    #[tokio::test]
+    async fn test_changed_files_are_reembedded() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        storage
+            .write_file("src/lib.rs", b"fn a() {}")
+            .await
+            .unwrap();
+        storage
+            .write_file("logo.png", &[0x89, 0xff, 0xfe])
+            .await
+            .unwrap();
+        let store = Arc::new(RwLock::new(VectorStore::new()));
+        let indexer = Arc::new(
+            KnowledgeIndexer::new(storage.clone(), lengths(), "embed", store.clone())
+                .with_max_bytes(4),
+        );
+        let dispatcher = IntentDispatcher::new();
+        let follower = indexer
+            .clone()
+            .follow(dispatcher.subscribe(FileChange::topic()));
+
+        for (path, kind) in [
+            ("src/lib.rs", FileChangeKind::Created),
+            ("logo.png", FileChangeKind::Created),
+        ] {
+            let _ = dispatcher
+                .dispatch(SystemIntent::Editor(EditorIntent::FileChanged {
+                    path: path.to_string(),
+                    kind,
+                }))
+                .await;
+        }
+        drop(dispatcher);
+        follower.await.unwrap();
+        {
+            let store = store.read().await;
+            assert_eq!(store.get("src/lib.rs"), Some(&[4.0][..]));
+            assert!(store.get("logo.png").is_none());
+        }
+
+        let removed = FileChange::new("src/lib.rs", FileChangeKind::Removed);
+        assert!(indexer.on_file_changed(&removed).await.unwrap());
+        assert!(store.read().await.get("src/lib.rs").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_files_outside_sparse_cone_are_not_indexed() {
+        use crate::common::change::sparse::SparseConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        storage
+            .write_file("src/lib.rs", b"fn a() {}")
+            .await
+            .unwrap();
+        storage.write_file("docs/guide.md", b"guide").await.unwrap();
+        let store = Arc::new(RwLock::new(VectorStore::new()));
+        store.write().await.add("docs/guide.md", vec![1.0]);
+        let indexer = KnowledgeIndexer::new(storage, lengths(), "embed", store.clone())
+            .with_sparse(SparseCheckout::new(SparseConfig {
+                enabled: true,
+                include_prefixes: vec!["src".into()],
+                max_expanded: None,
+            }));
+
+        for path in ["src/lib.rs", "docs/guide.md"] {
+            let change = FileChange::new(path, FileChangeKind::Modified);
+            assert!(indexer.on_file_changed(&change).await.unwrap());
+        }
+        let store = store.read().await;
+        assert!(store.get("src/lib.rs").is_some());
+        // 锥外文件的旧向量被移除
+        assert!(store.get("docs/guide.md").is_none());
+    }
+}
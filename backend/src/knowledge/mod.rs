@@ -1,7 +1,11 @@
 pub mod graph;
+pub mod indexer;
 pub mod retriever;
+pub mod schema;
 pub mod store;
 
-pub use graph::KnowledgeGraph;
+pub use graph::{KnowledgeGraph, KnowledgeNode, NodeKind};
+pub use indexer::KnowledgeIndexer;
 pub use retriever::Retriever;
+pub use schema::{ImportReport, SchemaImport, SchemaImporter, parse_openapi, parse_proto};
 pub use store::VectorStore;
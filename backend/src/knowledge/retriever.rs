@@ -1,15 +1,36 @@
 use crate::common::provider::traits::StorageProvider;
+use crate::knowledge::store::VectorStore;
 use anyhow::Result;
 use std::sync::Arc;
+use tokio::sync::RwLock;
 
 /// 执行多模态检索与重排
 pub struct Retriever {
     storage: Arc<dyn StorageProvider>,
+    /// 向量检索使用的向量库
+    store: Option<Arc<RwLock<VectorStore>>>,
 }
 
 impl Retriever {
     pub fn new(storage: Arc<dyn StorageProvider>) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            store: None,
+        }
+    }
+
+    /// 在指定的向量库中做向量检索
+    pub fn with_store(mut self, store: Arc<RwLock<VectorStore>>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// 按查询向量检索最相近的条目 ID，未配置向量库时为空
+    pub async fn search(&self, query: &[f32], limit: usize) -> Vec<String> {
+        match &self.store {
+            Some(store) => store.read().await.search(query, limit),
+            None => Vec::new(),
+        }
     }
 
     /// 检索上下文
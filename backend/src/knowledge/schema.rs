@@ -0,0 +1,564 @@
+use crate::common::provider::traits::StorageProvider;
+use crate::knowledge::graph::{KnowledgeGraph, KnowledgeNode, NodeKind};
+use crate::project::index::{SymbolKind, WorkspaceIndex};
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+
+/// 从接口定义中提取的节点与关系
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaImport {
+    pub nodes: Vec<KnowledgeNode>,
+    /// (起点 ID, 终点 ID)
+    pub relations: Vec<(String, String)>,
+}
+
+impl SchemaImport {
+    fn node(
+        &mut self,
+        id: String,
+        kind: NodeKind,
+        name: &str,
+        source: &str,
+        description: Option<String>,
+    ) -> &mut KnowledgeNode {
+        self.nodes.push(KnowledgeNode {
+            id,
+            kind,
+            name: name.to_string(),
+            source: Some(source.to_string()),
+            description,
+            attributes: BTreeMap::new(),
+        });
+        self.nodes.last_mut().unwrap()
+    }
+}
+
+/// 一次导入的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// 导入的接口定义文件
+    pub files: Vec<String>,
+    pub nodes: usize,
+    /// 链接到实现代码的节点数
+    pub linked: usize,
+    /// 无法解析的文件及原因
+    pub errors: Vec<(String, String)>,
+}
+
+const HTTP_METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// 解析 OpenAPI 3 / Swagger 2 文档（JSON 或 YAML）
+///
+/// 每个操作生成 `Endpoint` 节点，`components.schemas` / `definitions` 生成 `Message` 节点，
+/// 操作通过 `$ref` 引用的结构生成 `Endpoint -> Message` 关系。
+pub fn parse_openapi(path: &str, content: &str) -> Result<SchemaImport> {
+    let doc: Value = if path.ends_with(".json") {
+        serde_json::from_str(content)?
+    } else {
+        serde_yaml::from_str(content)?
+    };
+    if doc.get("openapi").is_none() && doc.get("swagger").is_none() {
+        return Err(anyhow!("Not an OpenAPI document"));
+    }
+    let mut import = SchemaImport::default();
+
+    let schemas = doc
+        .pointer("/components/schemas")
+        .or_else(|| doc.get("definitions"))
+        .and_then(Value::as_object);
+    for (name, schema) in schemas.into_iter().flatten() {
+        let node = import.node(
+            schema_id(name),
+            NodeKind::Message,
+            name,
+            path,
+            schema["description"].as_str().map(str::to_string),
+        );
+        if let Some(properties) = schema["properties"].as_object() {
+            let fields: Vec<&str> = properties.keys().map(String::as_str).collect();
+            node.attributes
+                .insert("fields".to_string(), fields.join(", "));
+        }
+    }
+
+    for (route, item) in doc["paths"].as_object().into_iter().flatten() {
+        for (method, operation) in item.as_object().into_iter().flatten() {
+            if !HTTP_METHODS.contains(&method.as_str()) {
+                continue;
+            }
+            let name = format!("{} {}", method.to_uppercase(), route);
+            let id = format!("openapi:{}", name);
+            let description = operation["summary"]
+                .as_str()
+                .or(operation["description"].as_str())
+                .map(str::to_string);
+            let node = import.node(id.clone(), NodeKind::Endpoint, &name, path, description);
+            if let Some(operation_id) = operation["operationId"].as_str() {
+                node.attributes
+                    .insert("operation_id".to_string(), operation_id.to_string());
+            }
+            if let Some(tags) = operation["tags"].as_array() {
+                let tags: Vec<&str> = tags.iter().filter_map(Value::as_str).collect();
+                node.attributes.insert("tags".to_string(), tags.join(", "));
+            }
+            let mut refs = Vec::new();
+            collect_refs(operation, &mut refs);
+            refs.sort();
+            refs.dedup();
+            for schema in refs {
+                import.relations.push((id.clone(), schema_id(&schema)));
+            }
+        }
+    }
+    Ok(import)
+}
+
+fn schema_id(name: &str) -> String {
+    format!("openapi:schema:{}", name)
+}
+
+/// 收集 `$ref: "#/components/schemas/X"` 引用的结构名
+fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(reference) = map.get("$ref").and_then(Value::as_str)
+                && let Some(name) = reference.rsplit('/').next()
+            {
+                refs.push(name.to_string());
+            }
+            map.values().for_each(|v| collect_refs(v, refs));
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_refs(v, refs)),
+        _ => {}
+    }
+}
+
+static PROTO_TOKEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"//[^\n]*|/\*(?s:.*?)\*/|"(?:[^"\\]|\\.)*"|[A-Za-z_.][\w.]*|\d+|[{}()<>;=,\[\]]"#)
+        .unwrap()
+});
+
+/// 解析 `.proto` 文件
+///
+/// 生成 `Message`、`Enum`、`Service` 与 `Rpc` 节点；服务包含其方法，
+/// 方法关联请求与响应消息。嵌套消息以 `Outer.Inner` 命名。
+pub fn parse_proto(path: &str, content: &str) -> Result<SchemaImport> {
+    let tokens: Vec<&str> = PROTO_TOKEN
+        .find_iter(content)
+        .map(|m| m.as_str())
+        .filter(|t| !t.starts_with("//") && !t.starts_with("/*"))
+        .collect();
+    let mut import = SchemaImport::default();
+    let mut package = String::new();
+    // (种类, 限定名, 节点下标)
+    let mut scopes: Vec<(&str, String, Option<usize>)> = Vec::new();
+    let qualify = |package: &str, name: &str| match package {
+        "" => name.to_string(),
+        package => format!("{}.{}", package, name),
+    };
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        let next = |offset: usize| tokens.get(i + offset).copied().unwrap_or_default();
+        match token {
+            "package" => {
+                package = next(1).to_string();
+                i += 2;
+            }
+            "syntax" | "import" | "option" | "reserved" | "extensions" => {
+                while i < tokens.len() && tokens[i] != ";" {
+                    i += 1;
+                }
+                i += 1;
+            }
+            "message" | "enum" | "service" | "oneof" if next(2) == "{" => {
+                let name = match scopes.iter().rev().find(|s| s.0 == "message") {
+                    Some((_, outer, _)) if token != "oneof" => format!("{}.{}", outer, next(1)),
+                    _ => next(1).to_string(),
+                };
+                let index = (token != "oneof").then(|| {
+                    let kind = match token {
+                        "message" => NodeKind::Message,
+                        "enum" => NodeKind::Enum,
+                        _ => NodeKind::Service,
+                    };
+                    import.node(
+                        format!("proto:{}", qualify(&package, &name)),
+                        kind,
+                        &name,
+                        path,
+                        None,
+                    );
+                    import.nodes.len() - 1
+                });
+                scopes.push((token, name, index));
+                i += 3;
+            }
+            "rpc" => {
+                // rpc Name ( [stream] Req ) returns ( [stream] Resp )
+                let mut j = i + 1;
+                let name = next(1);
+                let mut types = Vec::new();
+                while j < tokens.len() && !matches!(tokens[j], ";" | "{") {
+                    if tokens[j] == "(" {
+                        let mut k = j + 1;
+                        if tokens.get(k) == Some(&"stream") {
+                            k += 1;
+                        }
+                        types.push(tokens.get(k).copied().unwrap_or_default());
+                    }
+                    j += 1;
+                }
+                if let Some((_, service, Some(service_index))) =
+                    scopes.iter().rev().find(|s| s.0 == "service")
+                {
+                    let service_id = import.nodes[*service_index].id.clone();
+                    let id = format!("proto:{}/{}", qualify(&package, service), name);
+                    let node = import.node(id.clone(), NodeKind::Rpc, name, path, None);
+                    for (key, ty) in ["request", "response"].iter().zip(&types) {
+                        node.attributes.insert(key.to_string(), ty.to_string());
+                    }
+                    import.relations.push((service_id, id.clone()));
+                    for ty in types {
+                        let target = match ty.strip_prefix('.') {
+                            Some(full) => full.to_string(),
+                            None if ty.contains('.') => ty.to_string(),
+                            None => qualify(&package, ty),
+                        };
+                        import
+                            .relations
+                            .push((id.clone(), format!("proto:{}", target)));
+                    }
+                }
+                // 跳过可选的 `{ option ...; }` 方法体
+                if tokens.get(j) == Some(&"{") {
+                    while j < tokens.len() && tokens[j] != "}" {
+                        j += 1;
+                    }
+                }
+                i = j + 1;
+            }
+            "}" => {
+                scopes.pop();
+                i += 1;
+            }
+            _ => {
+                // 消息字段：[repeated|optional] 类型 名称 = 编号;（map<K, V> 视作一个类型）
+                let owner = scopes
+                    .iter()
+                    .rev()
+                    .find(|s| s.0 == "message" || s.0 == "enum")
+                    .and_then(|s| s.2);
+                let mut j = i;
+                if matches!(tokens[j], "repeated" | "optional" | "required") {
+                    j += 1;
+                }
+                if tokens.get(j) == Some(&"map") {
+                    while j < tokens.len() && tokens[j] != ">" {
+                        j += 1;
+                    }
+                }
+                let field = match (owner, tokens.get(j + 1), tokens.get(j + 2)) {
+                    // 枚举值：NAME = 0;
+                    (Some(owner), Some(&"="), _) if import.nodes[owner].kind == NodeKind::Enum => {
+                        Some((owner, tokens[j]))
+                    }
+                    (Some(owner), Some(name), Some(&"=")) => Some((owner, *name)),
+                    _ => None,
+                };
+                if let Some((owner, name)) = field {
+                    let fields = import.nodes[owner]
+                        .attributes
+                        .entry("fields".to_string())
+                        .or_default();
+                    if !fields.is_empty() {
+                        fields.push_str(", ");
+                    }
+                    fields.push_str(name);
+                }
+                while i < tokens.len() && !matches!(tokens[i], ";" | "}") {
+                    i += 1;
+                }
+                if tokens.get(i) == Some(&";") {
+                    i += 1;
+                }
+            }
+        }
+    }
+    if !scopes.is_empty() {
+        return Err(anyhow!("Unbalanced braces in {}", path));
+    }
+    Ok(import)
+}
+
+/// 将工作区中的 OpenAPI 文档与 `.proto` 文件导入知识图谱，并链接到实现代码
+///
+/// 以 `Code` 节点表示实现符号：OpenAPI 操作按 `operationId` 匹配，RPC 按方法名匹配，
+/// 消息、服务按类型名匹配（服务同时尝试 `Impl` / `Server` / `Handler` 后缀）。
+/// 名称比较忽略大小写与下划线，`getUser` 与 `get_user` 视为同名。
+pub struct SchemaImporter {
+    storage: Arc<dyn StorageProvider>,
+}
+
+impl SchemaImporter {
+    pub fn new(storage: Arc<dyn StorageProvider>) -> Self {
+        Self { storage }
+    }
+
+    /// 是否可能是接口定义文件（OpenAPI 需在解析时进一步确认）
+    pub fn is_schema_file(path: &str) -> bool {
+        path.ends_with(".proto")
+            || [".json", ".yaml", ".yml"].iter().any(|ext| {
+                let name = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+                name.ends_with(ext) && (name.contains("openapi") || name.contains("swagger"))
+            })
+    }
+
+    /// 导入 `index` 中的全部接口定义文件；重复导入会先移除同一文件此前的节点
+    pub async fn import(
+        &self,
+        graph: &mut KnowledgeGraph,
+        index: &WorkspaceIndex,
+    ) -> Result<ImportReport> {
+        let mut report = ImportReport::default();
+        let paths: Vec<String> = index
+            .files()
+            .filter(|p| Self::is_schema_file(p))
+            .map(str::to_string)
+            .collect();
+        for path in paths {
+            let content =
+                String::from_utf8_lossy(&self.storage.read_file(&path).await?).into_owned();
+            let parsed = if path.ends_with(".proto") {
+                parse_proto(&path, &content)
+            } else {
+                parse_openapi(&path, &content)
+            };
+            let import = match parsed {
+                Ok(import) => import,
+                Err(e) => {
+                    report.errors.push((path, e.to_string()));
+                    continue;
+                }
+            };
+            graph.remove_source(&path);
+            report.nodes += import.nodes.len();
+            for node in &import.nodes {
+                let links = implementations(node, index);
+                if !links.is_empty() {
+                    report.linked += 1;
+                }
+                for (symbol, file) in links {
+                    let code_id = format!("code:{}#{}", file, symbol);
+                    graph.add_node(KnowledgeNode {
+                        id: code_id.clone(),
+                        kind: NodeKind::Code,
+                        name: symbol,
+                        source: Some(file),
+                        description: None,
+                        attributes: BTreeMap::new(),
+                    });
+                    graph.add_relation(&node.id, &code_id);
+                }
+            }
+            for node in import.nodes {
+                graph.add_node(node);
+            }
+            for (from, to) in import.relations {
+                graph.add_relation(&from, &to);
+            }
+            report.files.push(path);
+        }
+        Ok(report)
+    }
+}
+
+/// 忽略大小写与下划线/连字符的名称
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 节点的实现符号 (符号名, 文件)
+fn implementations(node: &KnowledgeNode, index: &WorkspaceIndex) -> Vec<(String, String)> {
+    let (name, kinds, suffixes): (&str, &[SymbolKind], &[&str]) = match node.kind {
+        NodeKind::Endpoint => match node.attributes.get("operation_id") {
+            Some(operation_id) => (operation_id, &[SymbolKind::Function], &[""]),
+            None => return Vec::new(),
+        },
+        NodeKind::Rpc => (&node.name, &[SymbolKind::Function], &[""]),
+        NodeKind::Service => (
+            node.name.rsplit('.').next().unwrap_or(&node.name),
+            &[SymbolKind::Class, SymbolKind::Declaration],
+            &["", "impl", "server", "handler", "servicer"],
+        ),
+        NodeKind::Message | NodeKind::Enum => (
+            node.name.rsplit('.').next().unwrap_or(&node.name),
+            &[SymbolKind::Class, SymbolKind::Declaration],
+            &[""],
+        ),
+        NodeKind::Code => return Vec::new(),
+    };
+    let wanted: Vec<String> = suffixes
+        .iter()
+        .map(|suffix| format!("{}{}", normalize(name), suffix))
+        .collect();
+    index
+        .symbols()
+        .filter(|s| kinds.contains(&s.kind) && wanted.contains(&normalize(&s.name)))
+        .filter(|s| Some(&s.path) != node.source.as_ref())
+        .map(|s| (s.name.clone(), s.path.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::meta::MetaNode;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use serde_json::json;
+
+    const PROTO: &str = r#"
+syntax = "proto3";
+package shop.v1;
+
+import "google/protobuf/timestamp.proto";
+
+// 订单
+message Order {
+  string id = 1;
+  repeated LineItem items = 2;
+  map<string, string> labels = 3;
+  message LineItem { string sku = 1; int32 quantity = 2; }
+  oneof payment { string card = 4; string wallet = 5; }
+}
+
+enum Status { STATUS_UNSPECIFIED = 0; PAID = 1; }
+
+service OrderService {
+  rpc GetOrder (GetOrderRequest) returns (Order);
+  rpc WatchOrders (stream .shop.v1.Order) returns (stream Order) { option deadline = 5; }
+}
+"#;
+
+    const OPENAPI: &str = r##"
+openapi: 3.0.0
+info: { title: Users, version: "1" }
+paths:
+  /users/{id}:
+    get:
+      operationId: getUserById
+      summary: Fetch a user profile
+      tags: [users]
+      responses:
+        "200":
+          content:
+            application/json:
+              schema: { $ref: "#/components/schemas/User" }
+    parameters: []
+components:
+  schemas:
+    User:
+      description: A registered user
+      properties: { id: { type: string }, email: { type: string } }
+"##;
+
+    #[test]
+    fn test_parse_proto() {
+        let import = parse_proto("proto/shop.proto", PROTO).unwrap();
+        let ids: Vec<&str> = import.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "proto:shop.v1.Order",
+                "proto:shop.v1.Order.LineItem",
+                "proto:shop.v1.Status",
+                "proto:shop.v1.OrderService",
+                "proto:shop.v1.OrderService/GetOrder",
+                "proto:shop.v1.OrderService/WatchOrders",
+            ]
+        );
+        assert_eq!(
+            import.nodes[0].attributes["fields"],
+            "id, items, labels, card, wallet"
+        );
+        assert_eq!(import.nodes[1].attributes["fields"], "sku, quantity");
+        assert_eq!(
+            import.nodes[2].attributes["fields"],
+            "STATUS_UNSPECIFIED, PAID"
+        );
+        assert_eq!(import.nodes[4].attributes["request"], "GetOrderRequest");
+        assert!(import.relations.contains(&(
+            "proto:shop.v1.OrderService/WatchOrders".to_string(),
+            "proto:shop.v1.Order".to_string()
+        )));
+        assert!(parse_proto("bad.proto", "message A {").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_links_schemas_to_code() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("proto")).unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("proto/shop.proto"), PROTO).unwrap();
+        std::fs::write(dir.path().join("openapi.yaml"), OPENAPI).unwrap();
+        std::fs::write(dir.path().join("broken.swagger.json"), "{").unwrap();
+
+        let mut index = WorkspaceIndex::scan(&LocalFileSystem::new(dir.path()), "")
+            .await
+            .unwrap();
+        let module: MetaNode = serde_json::from_value(json!({
+            "type": "module", "name": "api", "path": "src/api.rs",
+            "children": [
+                {"type": "function", "name": "get_user_by_id", "params": [], "body": null},
+                {"type": "function", "name": "get_order", "params": [], "body": null},
+                {"type": "class", "name": "OrderServiceImpl", "members": [], "bases": []}
+            ]
+        }))
+        .unwrap();
+        index.index_symbols("src/api.rs", &module);
+
+        let importer = SchemaImporter::new(Arc::new(LocalFileSystem::new(dir.path())));
+        let mut graph = KnowledgeGraph::new();
+        let report = importer.import(&mut graph, &index).await.unwrap();
+        assert_eq!(report.files, vec!["openapi.yaml", "proto/shop.proto"]);
+        assert_eq!(report.nodes, 8);
+        assert_eq!(report.linked, 3);
+        assert_eq!(report.errors.len(), 1);
+
+        let endpoint = graph.node("openapi:GET /users/{id}").unwrap();
+        assert_eq!(endpoint.kind, NodeKind::Endpoint);
+        let neighbors: Vec<&str> = graph
+            .neighbors(&endpoint.id)
+            .iter()
+            .map(|n| n.id.as_str())
+            .collect();
+        assert_eq!(
+            neighbors,
+            vec!["code:src/api.rs#get_user_by_id", "openapi:schema:User"]
+        );
+        assert_eq!(
+            graph.get_affected("proto:shop.v1.OrderService/GetOrder"),
+            vec![
+                "code:src/api.rs#get_order",
+                "proto:shop.v1.GetOrderRequest",
+                "proto:shop.v1.Order"
+            ]
+        );
+        assert_eq!(graph.search("user profile")[0].id, endpoint.id);
+
+        // 重新导入不会产生重复节点或关系
+        importer.import(&mut graph, &index).await.unwrap();
+        assert_eq!(graph.nodes(NodeKind::Rpc).count(), 2);
+        assert_eq!(graph.get_affected("proto:shop.v1.OrderService").len(), 3);
+    }
+}
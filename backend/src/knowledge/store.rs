@@ -24,6 +24,16 @@ impl VectorStore {
         self.store.insert(id.to_string(), vector);
     }
 
+    /// 获取已存储的向量
+    pub fn get(&self, id: &str) -> Option<&[f32]> {
+        self.store.get(id).map(Vec::as_slice)
+    }
+
+    /// 移除向量，返回是否存在
+    pub fn remove(&mut self, id: &str) -> bool {
+        self.store.remove(id).is_some()
+    }
+
     /// 搜索相似向量
     pub fn search(&self, _query: &[f32], _limit: usize) -> Vec<String> {
         // Mock 逻辑：返回前 limit 个 ID
@@ -7,3 +7,4 @@ pub mod project;
 pub mod semantic;
 pub mod skill;
 pub mod syntax;
+pub mod tenant;
@@ -0,0 +1,287 @@
+use crate::common::provider::traits::StorageProvider;
+use crate::project::index::WorkspaceIndex;
+use serde::{Deserialize, Serialize};
+
+/// 测试框架
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestFramework {
+    Cargo,
+    Jest,
+    Vitest,
+    NodeTest,
+    Pytest,
+    GoTest,
+}
+
+impl TestFramework {
+    /// 提示词中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestFramework::Cargo => "Rust built-in tests (cargo test)",
+            TestFramework::Jest => "Jest",
+            TestFramework::Vitest => "Vitest",
+            TestFramework::NodeTest => "node:test",
+            TestFramework::Pytest => "pytest",
+            TestFramework::GoTest => "Go testing package (go test)",
+        }
+    }
+
+    /// 按文件名判断是否为该框架的测试文件
+    fn is_test_file(&self, path: &str) -> bool {
+        let name = file_name(path);
+        match self {
+            TestFramework::Cargo => {
+                name.ends_with(".rs") && (path.starts_with("tests/") || path.contains("/tests/"))
+            }
+            TestFramework::Jest | TestFramework::Vitest | TestFramework::NodeTest => {
+                name.contains(".test.") || name.contains(".spec.")
+            }
+            TestFramework::Pytest => {
+                name.ends_with(".py") && (name.starts_with("test_") || name.ends_with("_test.py"))
+            }
+            TestFramework::GoTest => name.ends_with("_test.go"),
+        }
+    }
+}
+
+/// 项目的测试约定：框架、生成测试的存放位置以及可供模仿的现有测试
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestConventions {
+    pub framework: TestFramework,
+    /// 项目根目录（清单文件所在目录，空字符串表示存储根目录）
+    pub root: String,
+    /// 生成测试写入的文件
+    pub test_path: String,
+    /// 测试是否以模块形式追加到源文件中（Rust `#[cfg(test)]`）
+    pub inline: bool,
+    /// 现有测试文件，按与源文件的距离排序
+    pub examples: Vec<String>,
+}
+
+/// 最多参考的现有测试文件数
+const MAX_EXAMPLES: usize = 3;
+
+impl TestConventions {
+    /// 从 `source` 所在目录向上查找项目清单，推断测试框架与约定
+    pub async fn detect(storage: &dyn StorageProvider, source: &str) -> anyhow::Result<Self> {
+        let mut dir = parent_dir(source);
+        let (root, framework) = loop {
+            if let Some(framework) = framework_at(storage, dir).await {
+                break (dir.to_string(), framework);
+            }
+            if dir.is_empty() {
+                anyhow::bail!("No supported test framework found for {}", source);
+            }
+            dir = parent_dir(dir);
+        };
+
+        let index = WorkspaceIndex::scan(storage, &root).await?;
+        let source_dir = parent_dir(source);
+        let mut examples: Vec<String> = index
+            .files()
+            .filter(|f| *f != source && framework.is_test_file(relative(&root, f)))
+            .map(str::to_string)
+            .collect();
+        examples.sort_by_key(|f| (!f.starts_with(source_dir), f.matches('/').count()));
+        examples.truncate(MAX_EXAMPLES);
+
+        let stem = file_name(source).split('.').next().unwrap_or_default();
+        let ext = file_name(source).split_once('.').map_or("", |(_, e)| e);
+        let (test_path, inline) = match framework {
+            TestFramework::Cargo => {
+                let content = storage.read_file(source).await.unwrap_or_default();
+                let has_module = String::from_utf8_lossy(&content).contains("#[cfg(test)]");
+                if has_module || examples.is_empty() {
+                    (source.to_string(), true)
+                } else {
+                    (join(&root, &format!("tests/{}.rs", stem)), false)
+                }
+            }
+            TestFramework::Jest | TestFramework::Vitest | TestFramework::NodeTest => {
+                let suffix = if examples.iter().any(|e| file_name(e).contains(".spec.")) {
+                    "spec"
+                } else {
+                    "test"
+                };
+                let dir = if examples.iter().any(|e| e.contains("__tests__/")) {
+                    join(source_dir, "__tests__")
+                } else {
+                    source_dir.to_string()
+                };
+                (join(&dir, &format!("{}.{}.{}", stem, suffix, ext)), false)
+            }
+            TestFramework::Pytest => {
+                let in_tests = index.files().any(|f| f.starts_with(&join(&root, "tests/")));
+                let dir = if in_tests {
+                    join(&root, "tests")
+                } else {
+                    source_dir.to_string()
+                };
+                (join(&dir, &format!("test_{}.py", stem)), false)
+            }
+            TestFramework::GoTest => (join(source_dir, &format!("{}_test.go", stem)), false),
+        };
+
+        Ok(Self {
+            framework,
+            root,
+            test_path,
+            inline,
+            examples,
+        })
+    }
+
+    /// 在项目根目录执行、只运行生成测试的命令
+    pub fn command(&self) -> String {
+        let path = relative(&self.root, &self.test_path);
+        match self.framework {
+            TestFramework::Cargo if self.inline => "cargo test".to_string(),
+            TestFramework::Cargo => {
+                let target = file_name(path).trim_end_matches(".rs");
+                format!("cargo test --test {}", target)
+            }
+            TestFramework::Jest => format!("npx jest {}", path),
+            TestFramework::Vitest => format!("npx vitest run {}", path),
+            TestFramework::NodeTest => format!("node --test {}", path),
+            TestFramework::Pytest => format!("python -m pytest {}", path),
+            TestFramework::GoTest => format!("go test ./{}", parent_dir(path)),
+        }
+    }
+}
+
+/// 目录中存在清单文件时返回对应的测试框架
+async fn framework_at(storage: &dyn StorageProvider, dir: &str) -> Option<TestFramework> {
+    let exists = |name: &str| {
+        let path = join(dir, name);
+        async move { storage.exists(&path).await.unwrap_or(false) }
+    };
+    if exists("Cargo.toml").await {
+        return Some(TestFramework::Cargo);
+    }
+    if exists("go.mod").await {
+        return Some(TestFramework::GoTest);
+    }
+    if let Ok(manifest) = storage.read_file(&join(dir, "package.json")).await {
+        let manifest = String::from_utf8_lossy(&manifest);
+        return Some(if manifest.contains("\"vitest\"") {
+            TestFramework::Vitest
+        } else if manifest.contains("\"jest\"") {
+            TestFramework::Jest
+        } else {
+            TestFramework::NodeTest
+        });
+    }
+    for name in ["pyproject.toml", "pytest.ini", "setup.cfg", "setup.py"] {
+        if exists(name).await {
+            return Some(TestFramework::Pytest);
+        }
+    }
+    None
+}
+
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn relative<'a>(root: &str, path: &'a str) -> &'a str {
+    if root.is_empty() {
+        path
+    } else {
+        path.strip_prefix(root)
+            .map_or(path, |p| p.trim_start_matches('/'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+
+    async fn workspace(files: &[(&str, &str)]) -> (tempfile::TempDir, LocalFileSystem) {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalFileSystem::new(dir.path());
+        for (path, content) in files {
+            storage.write_file(path, content.as_bytes()).await.unwrap();
+        }
+        (dir, storage)
+    }
+
+    #[tokio::test]
+    async fn test_detects_frameworks_and_placement() {
+        let (_dir, storage) = workspace(&[
+            (
+                "web/package.json",
+                r#"{"devDependencies": {"vitest": "^1.0.0"}}"#,
+            ),
+            ("web/src/__tests__/app.spec.ts", "test('x', () => {})"),
+            ("web/src/math.ts", "export const add = (a, b) => a + b;"),
+            ("crate/Cargo.toml", "[package]"),
+            ("crate/src/lib.rs", "pub fn add() {}"),
+            ("crate/tests/api.rs", "#[test] fn api() {}"),
+            ("py/pyproject.toml", ""),
+            ("py/pkg/util.py", "def f(): pass"),
+        ])
+        .await;
+
+        let web = TestConventions::detect(&storage, "web/src/math.ts")
+            .await
+            .unwrap();
+        assert_eq!(web.framework, TestFramework::Vitest);
+        assert_eq!(web.root, "web");
+        assert_eq!(web.test_path, "web/src/__tests__/math.spec.ts");
+        assert_eq!(web.examples, vec!["web/src/__tests__/app.spec.ts"]);
+        assert_eq!(web.command(), "npx vitest run src/__tests__/math.spec.ts");
+
+        let rust = TestConventions::detect(&storage, "crate/src/lib.rs")
+            .await
+            .unwrap();
+        assert_eq!(rust.framework, TestFramework::Cargo);
+        assert!(!rust.inline);
+        assert_eq!(rust.test_path, "crate/tests/lib.rs");
+        assert_eq!(rust.command(), "cargo test --test lib");
+
+        let py = TestConventions::detect(&storage, "py/pkg/util.py")
+            .await
+            .unwrap();
+        assert_eq!(py.framework, TestFramework::Pytest);
+        assert_eq!(py.test_path, "py/pkg/test_util.py");
+
+        assert!(
+            TestConventions::detect(&storage, "notes/readme.md")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rust_module_with_inline_tests_stays_inline() {
+        let (_dir, storage) = workspace(&[
+            ("Cargo.toml", "[package]"),
+            (
+                "src/lib.rs",
+                "pub fn add() {}\n#[cfg(test)]\nmod tests {}\n",
+            ),
+            ("tests/api.rs", "#[test] fn api() {}"),
+        ])
+        .await;
+        let conventions = TestConventions::detect(&storage, "src/lib.rs")
+            .await
+            .unwrap();
+        assert!(conventions.inline);
+        assert_eq!(conventions.test_path, "src/lib.rs");
+        assert_eq!(conventions.command(), "cargo test");
+    }
+}
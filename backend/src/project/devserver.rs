@@ -0,0 +1,837 @@
+use crate::common::change::Change;
+use crate::common::change::thread::ThreadManager;
+use crate::common::event::{BackendEvent, EventBus, EventEnvelope, EventSink};
+use crate::common::provider::traits::{ExecuteOptions, ExecutionProvider, StorageProvider};
+use crate::project::secrets::SecretsManager;
+use crate::skill::sandbox::ToolEffects;
+use crate::skill::tool::{Tool, ToolOutput};
+use crate::skill::traits::SkillError;
+use crate::syntax::query::matches;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// 工作区内的任务配置文件
+pub const TASKS_CONFIG_PATH: &str = ".zhiyun/tasks.json";
+
+/// 停止服务器后等待其释放端口的最长时间
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 任务配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TasksConfig {
+    #[serde(default)]
+    pub dev_servers: Vec<DevServerSpec>,
+}
+
+impl TasksConfig {
+    /// 读取 JSON 配置，文件不存在时返回空配置
+    pub async fn load(storage: &dyn StorageProvider, path: &str) -> Result<Self> {
+        if !storage.exists(path).await? {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_slice(&storage.read_file(path).await?)?)
+    }
+}
+
+/// 开发服务器声明
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevServerSpec {
+    pub name: String,
+    /// 启动命令，`{port}` 会替换为实际端口（同时通过 `PORT` 环境变量传入）
+    pub command: String,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// 固定端口；未设置时自动分配空闲端口
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// 触发自动重启的路径模式（支持 `*` 通配）；为空时任何文件变更都会触发
+    #[serde(default)]
+    pub watch: Vec<String>,
+    #[serde(default = "default_auto_restart")]
+    pub auto_restart: bool,
+    /// 等待端口可连接的最长时间
+    #[serde(default = "default_startup_timeout_ms")]
+    pub startup_timeout_ms: u64,
+}
+
+fn default_auto_restart() -> bool {
+    true
+}
+
+fn default_startup_timeout_ms() -> u64 {
+    30_000
+}
+
+impl DevServerSpec {
+    /// 变更中是否有文件与 `watch` 匹配
+    pub fn is_affected_by(&self, change: &Change) -> bool {
+        change
+            .operations
+            .iter()
+            .filter_map(|op| op.path())
+            .any(|path| {
+                self.watch.is_empty() || self.watch.iter().any(|pattern| matches(pattern, path))
+            })
+    }
+}
+
+/// 开发服务器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DevServerStatus {
+    Stopped,
+    /// 进程已启动，端口尚不可连接
+    Starting,
+    Running,
+    /// 启动超时仍无法连接端口（进程仍在运行）
+    Unhealthy,
+    /// 进程自行退出
+    Exited,
+}
+
+impl DevServerStatus {
+    pub fn is_active(self) -> bool {
+        matches!(
+            self,
+            DevServerStatus::Starting | DevServerStatus::Running | DevServerStatus::Unhealthy
+        )
+    }
+}
+
+/// 供 UI 与 Agent 查看的服务器状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevServerState {
+    pub name: String,
+    pub status: DevServerStatus,
+    pub port: Option<u16>,
+    pub restarts: u32,
+    pub started_at: Option<DateTime<Utc>>,
+    /// 最近的输出行
+    pub output: VecDeque<String>,
+}
+
+struct Instance {
+    state: DevServerState,
+    /// 每次启动递增，旧进程的后台任务据此忽略过期的状态更新
+    generation: u64,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+/// 后台任务与管理器共享的状态
+struct Shared {
+    instances: Mutex<BTreeMap<String, Instance>>,
+    events: Option<Arc<EventBus>>,
+    max_output_lines: usize,
+}
+
+impl Shared {
+    /// 在当前代次仍有效时更新状态并发布事件
+    fn update(&self, name: &str, generation: u64, f: impl FnOnce(&mut DevServerState)) {
+        let state = {
+            let mut instances = self.instances.lock().unwrap();
+            match instances.get_mut(name) {
+                Some(instance) if instance.generation == generation => {
+                    let before = instance.state.status;
+                    f(&mut instance.state);
+                    (instance.state.status != before).then(|| instance.state.clone())
+                }
+                _ => None,
+            }
+        };
+        if let Some(state) = state {
+            self.emit(&state);
+        }
+    }
+
+    fn emit(&self, state: &DevServerState) {
+        if let Some(events) = &self.events {
+            events.emit(BackendEvent::DevServer {
+                name: state.name.clone(),
+                detail: json!({
+                    "status": state.status,
+                    "port": state.port,
+                    "restarts": state.restarts,
+                }),
+            });
+        }
+    }
+}
+
+/// 开发服务器生命周期管理：按任务配置启动长期运行的进程、分配端口并探测就绪，
+/// 相关文件的 Change 提交后自动重启
+pub struct DevServerManager {
+    runner: Arc<dyn ExecutionProvider>,
+    specs: RwLock<BTreeMap<String, DevServerSpec>>,
+    shared: Arc<Shared>,
+    probe_interval: Duration,
+    secrets: Option<Arc<SecretsManager>>,
+}
+
+impl DevServerManager {
+    pub fn new(runner: Arc<dyn ExecutionProvider>) -> Self {
+        Self {
+            runner,
+            specs: RwLock::new(BTreeMap::new()),
+            shared: Arc::new(Shared {
+                instances: Mutex::new(BTreeMap::new()),
+                events: None,
+                max_output_lines: 200,
+            }),
+            probe_interval: Duration::from_millis(200),
+            secrets: None,
+        }
+    }
+
+    /// 状态变化时发布 `BackendEvent::DevServer`
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.shared = Arc::new(Shared {
+            instances: Mutex::new(BTreeMap::new()),
+            events: Some(events),
+            max_output_lines: self.shared.max_output_lines,
+        });
+        self
+    }
+
+    pub fn with_max_output_lines(mut self, lines: usize) -> Self {
+        self.shared = Arc::new(Shared {
+            instances: Mutex::new(BTreeMap::new()),
+            events: self.shared.events.clone(),
+            max_output_lines: lines.max(1),
+        });
+        self
+    }
+
+    pub fn with_probe_interval(mut self, interval: Duration) -> Self {
+        self.probe_interval = interval;
+        self
+    }
+
+    /// 启动时注入授予服务器（以服务器名为任务名）的密钥，并掩码保留的输出
+    pub fn with_secrets(mut self, secrets: Arc<SecretsManager>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// 注册（或替换）服务器声明
+    pub fn configure(&self, config: TasksConfig) {
+        let mut specs = self.specs.write().unwrap();
+        for spec in config.dev_servers {
+            specs.insert(spec.name.clone(), spec);
+        }
+    }
+
+    pub fn spec(&self, name: &str) -> Option<DevServerSpec> {
+        self.specs.read().unwrap().get(name).cloned()
+    }
+
+    /// 服务器状态；已声明但从未启动的服务器为 `Stopped`
+    pub fn state(&self, name: &str) -> Option<DevServerState> {
+        if let Some(instance) = self.shared.instances.lock().unwrap().get(name) {
+            return Some(instance.state.clone());
+        }
+        self.specs
+            .read()
+            .unwrap()
+            .contains_key(name)
+            .then(|| stopped(name))
+    }
+
+    /// 全部已声明服务器的状态（按名称排序）
+    pub fn list(&self) -> Vec<DevServerState> {
+        let names: Vec<String> = self.specs.read().unwrap().keys().cloned().collect();
+        names.iter().filter_map(|name| self.state(name)).collect()
+    }
+
+    /// 启动服务器；已在运行时直接返回当前状态
+    pub async fn start(&self, name: &str) -> Result<DevServerState> {
+        let spec = self
+            .spec(name)
+            .ok_or_else(|| anyhow!("Unknown dev server: {}", name))?;
+        if let Some(state) = self.state(name)
+            && state.status.is_active()
+        {
+            return Ok(state);
+        }
+
+        let port = match spec.port {
+            Some(port) => port,
+            None => free_port()?,
+        };
+        let mut env = spec.env.clone();
+        env.insert("PORT".to_string(), port.to_string());
+        let command = spec.command.replace("{port}", &port.to_string());
+        let mut options = ExecuteOptions {
+            cwd: spec.cwd.clone(),
+            env,
+            timeout_ms: None,
+        };
+        let masker = match &self.secrets {
+            Some(secrets) => {
+                secrets.inject(name, &mut options)?;
+                secrets.masker()?
+            }
+            None => Arc::default(),
+        };
+        let mut stream = self.runner.execute_stream(&command, options).await?;
+
+        let (state, generation) = {
+            let mut instances = self.shared.instances.lock().unwrap();
+            let instance = instances
+                .entry(name.to_string())
+                .or_insert_with(|| Instance {
+                    state: stopped(name),
+                    generation: 0,
+                    tasks: Vec::new(),
+                });
+            instance.generation += 1;
+            instance.state.status = DevServerStatus::Starting;
+            instance.state.port = Some(port);
+            instance.state.started_at = Some(Utc::now());
+            instance.state.output.clear();
+            (instance.state.clone(), instance.generation)
+        };
+        self.shared.emit(&state);
+
+        // 输出任务持有输出流；任务被取消时流随之丢弃，由提供者终止进程
+        let shared = self.shared.clone();
+        let server = name.to_string();
+        let output = tokio::spawn(async move {
+            while let Some(line) = stream.next().await {
+                let line = match line {
+                    Ok(line) => masker.mask(line.text()),
+                    Err(e) => format!("[error] {}", e),
+                };
+                shared.update(&server, generation, |state| {
+                    if state.output.len() >= shared.max_output_lines {
+                        state.output.pop_front();
+                    }
+                    state.output.push_back(line);
+                });
+            }
+            shared.update(&server, generation, |state| {
+                state.status = DevServerStatus::Exited;
+            });
+        });
+
+        let shared = self.shared.clone();
+        let server = name.to_string();
+        let interval = self.probe_interval;
+        let timeout = Duration::from_millis(spec.startup_timeout_ms);
+        let probe = tokio::spawn(async move {
+            let deadline = tokio::time::Instant::now() + timeout;
+            let status = loop {
+                if tokio::net::TcpStream::connect(("127.0.0.1", port))
+                    .await
+                    .is_ok()
+                {
+                    break DevServerStatus::Running;
+                }
+                if tokio::time::Instant::now() >= deadline {
+                    break DevServerStatus::Unhealthy;
+                }
+                tokio::time::sleep(interval).await;
+            };
+            shared.update(&server, generation, |state| {
+                if state.status == DevServerStatus::Starting {
+                    state.status = status;
+                }
+            });
+        });
+
+        if let Some(instance) = self.shared.instances.lock().unwrap().get_mut(name) {
+            instance.tasks = vec![output, probe];
+        }
+        Ok(state)
+    }
+
+    /// 停止服务器，等到旧进程退出（后台任务结束、端口不再接受连接）后返回
+    ///
+    /// 丢弃输出流时由执行提供者终止进程；本地进程在独立的进程组中运行，整组一起终止。
+    pub async fn stop(&self, name: &str) -> Result<DevServerState> {
+        let (state, tasks, port) = {
+            let mut instances = self.shared.instances.lock().unwrap();
+            let Some(instance) = instances.get_mut(name) else {
+                return self
+                    .state(name)
+                    .ok_or_else(|| anyhow!("Unknown dev server: {}", name));
+            };
+            let was_active = instance.state.status.is_active();
+            let tasks: Vec<JoinHandle<()>> = instance.tasks.drain(..).collect();
+            instance.generation += 1;
+            instance.state.status = DevServerStatus::Stopped;
+            (
+                instance.state.clone(),
+                tasks,
+                instance.state.port.filter(|_| was_active),
+            )
+        };
+        for task in &tasks {
+            task.abort();
+        }
+        for task in tasks {
+            // 任务结束时输出流已被丢弃
+            let _ = task.await;
+        }
+        if let Some(port) = port {
+            self.wait_released(port).await?;
+        }
+        self.shared.emit(&state);
+        Ok(state)
+    }
+
+    /// 等待端口不再接受连接，超过 `STOP_TIMEOUT` 仍在监听时报错
+    async fn wait_released(&self, port: u16) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + STOP_TIMEOUT;
+        while tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_ok()
+        {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow!("Port {} is still in use after stopping", port));
+            }
+            tokio::time::sleep(self.probe_interval).await;
+        }
+        Ok(())
+    }
+
+    pub async fn restart(&self, name: &str) -> Result<DevServerState> {
+        self.stop(name).await?;
+        if let Some(instance) = self.shared.instances.lock().unwrap().get_mut(name) {
+            instance.state.restarts += 1;
+        }
+        self.start(name).await
+    }
+
+    /// 正在运行、开启自动重启且受该变更影响的服务器
+    pub fn affected(&self, change: &Change) -> Vec<String> {
+        self.specs
+            .read()
+            .unwrap()
+            .values()
+            .filter(|spec| spec.auto_restart && spec.is_affected_by(change))
+            .filter(|spec| self.state(&spec.name).is_some_and(|s| s.status.is_active()))
+            .map(|spec| spec.name.clone())
+            .collect()
+    }
+
+    /// 重启受变更影响的服务器，返回重启的名称
+    pub async fn on_change(&self, change: &Change) -> Result<Vec<String>> {
+        let names = self.affected(change);
+        for name in &names {
+            self.restart(name).await?;
+        }
+        Ok(names)
+    }
+
+    /// 停止全部服务器；某个服务器停止失败时继续停止其余的，最后汇总报告
+    pub async fn shutdown(&self) -> Result<()> {
+        let names: Vec<String> = self
+            .shared
+            .instances
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        let mut errors = Vec::new();
+        for name in names {
+            if let Err(e) = self.stop(&name).await {
+                errors.push(format!("{}: {}", name, e));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Failed to stop dev servers: {}", errors.join("; ")))
+        }
+    }
+}
+
+fn stopped(name: &str) -> DevServerState {
+    DevServerState {
+        name: name.to_string(),
+        status: DevServerStatus::Stopped,
+        port: None,
+        restarts: 0,
+        started_at: None,
+        output: VecDeque::new(),
+    }
+}
+
+/// 由系统分配一个当前空闲的本地端口
+fn free_port() -> Result<u16> {
+    Ok(std::net::TcpListener::bind("127.0.0.1:0")?
+        .local_addr()?
+        .port())
+}
+
+/// 订阅 `ChangeCommitted` 事件，按提交内容自动重启开发服务器
+pub struct DevServerWatcher {
+    threads: Arc<ThreadManager>,
+    servers: Arc<DevServerManager>,
+}
+
+impl DevServerWatcher {
+    pub fn new(threads: Arc<ThreadManager>, servers: Arc<DevServerManager>) -> Self {
+        Self { threads, servers }
+    }
+}
+
+#[async_trait]
+impl EventSink for DevServerWatcher {
+    fn name(&self) -> &str {
+        "dev_server_watcher"
+    }
+
+    /// 逐个处理事件，某次重启失败不影响之后的事件，最后汇总报告失败
+    async fn publish(&self, events: &[EventEnvelope]) -> Result<()> {
+        let mut errors = Vec::new();
+        for envelope in events {
+            if let BackendEvent::ChangeCommitted { change_id, .. } = &envelope.event
+                && let Some(change) = self.threads.get_change(*change_id)
+                && let Err(e) = self.servers.on_change(&change).await
+            {
+                errors.push(e.to_string());
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Failed to restart dev servers: {}",
+                errors.join("; ")
+            ))
+        }
+    }
+}
+
+/// `dev_server` 工具：查看、启动、停止或重启开发服务器
+pub struct DevServerTool {
+    servers: Arc<DevServerManager>,
+}
+
+impl DevServerTool {
+    pub fn new(servers: Arc<DevServerManager>) -> Self {
+        Self { servers }
+    }
+}
+
+#[async_trait(?Send)]
+impl Tool for DevServerTool {
+    fn name(&self) -> &'static str {
+        "dev_server"
+    }
+
+    fn description(&self) -> &'static str {
+        "管理任务配置中声明的开发服务器：查看状态与最近输出，或启动、停止、重启指定服务器。"
+    }
+
+    fn parameter_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["status", "start", "stop", "restart"]
+                },
+                "name": {
+                    "type": "string",
+                    "description": "服务器名称；`status` 省略时列出全部服务器"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn effects(&self, args: &Value) -> ToolEffects {
+        match args["action"].as_str() {
+            Some("start" | "restart") => ToolEffects::runs(
+                args["name"]
+                    .as_str()
+                    .and_then(|name| self.servers.spec(name))
+                    .map(|spec| spec.command)
+                    .into_iter()
+                    .collect(),
+            ),
+            _ => ToolEffects::default(),
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
+        let action = args["action"]
+            .as_str()
+            .ok_or_else(|| SkillError::InvalidSkill("Missing 'action' parameter".into()))?;
+        let name = args["name"].as_str();
+        let io_error = |e: anyhow::Error| SkillError::IoError(std::io::Error::other(e.to_string()));
+        let states = match (action, name) {
+            ("status", None) => self.servers.list(),
+            ("status", Some(name)) => vec![
+                self.servers
+                    .state(name)
+                    .ok_or_else(|| SkillError::NotFound(name.to_string()))?,
+            ],
+            (_, None) => {
+                return Err(SkillError::InvalidSkill("Missing 'name' parameter".into()));
+            }
+            ("start", Some(name)) => vec![self.servers.start(name).await.map_err(io_error)?],
+            ("stop", Some(name)) => vec![self.servers.stop(name).await.map_err(io_error)?],
+            ("restart", Some(name)) => vec![self.servers.restart(name).await.map_err(io_error)?],
+            (other, _) => {
+                return Err(SkillError::InvalidSkill(format!(
+                    "Unknown action: {}",
+                    other
+                )));
+            }
+        };
+        let content = states
+            .iter()
+            .map(|s| match s.port {
+                Some(port) => format!("{}: {:?} on port {}", s.name, s.status, port),
+                None => format!("{}: {:?}", s.name, s.status),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(ToolOutput {
+            content,
+            data: Some(json!(states)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::{Operation, VectorClock};
+    use crate::common::event::{EventFilter, EventKind};
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::common::provider::traits::{ExecuteResult, OutputLine, OutputStream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use uuid::Uuid;
+
+    /// 在 `PORT` 上监听直到输出流被丢弃的假服务器
+    #[derive(Default)]
+    struct FakeServer {
+        starts: AtomicUsize,
+        /// 启动失败的命令
+        failing: Mutex<Option<String>>,
+    }
+
+    #[async_trait]
+    impl ExecutionProvider for FakeServer {
+        async fn execute(&self, _command: &str, _options: ExecuteOptions) -> Result<ExecuteResult> {
+            Err(anyhow!("not supported"))
+        }
+
+        async fn execute_stream(
+            &self,
+            command: &str,
+            options: ExecuteOptions,
+        ) -> Result<OutputStream> {
+            if self.failing.lock().unwrap().as_deref() == Some(command) {
+                return Err(anyhow!("{} failed to start", command));
+            }
+            self.starts.fetch_add(1, Ordering::SeqCst);
+            let port: u16 = options.env["PORT"].parse()?;
+            let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await?;
+            let line = format!("{} ready", command);
+            Ok(Box::pin(async_stream::stream! {
+                let _listener = listener;
+                yield Ok(OutputLine::Stdout(line));
+                futures::future::pending::<()>().await;
+            }))
+        }
+
+        async fn kill(&self, _task_id: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    struct Discard;
+
+    #[async_trait]
+    impl EventSink for Discard {
+        fn name(&self) -> &str {
+            "discard"
+        }
+
+        async fn publish(&self, _events: &[EventEnvelope]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    async fn wait_for(manager: &DevServerManager, name: &str, status: DevServerStatus) {
+        for _ in 0..100 {
+            if manager.state(name).unwrap().status == status {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("{} never reached {:?}", name, status);
+    }
+
+    fn write(path: &str) -> Change {
+        Change::new(
+            Uuid::new_v4(),
+            vec![Operation::file_write(path.to_string(), b"x".to_vec())],
+            VectorClock::new(),
+            vec![],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_dev_server_lifecycle_and_auto_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".zhiyun")).unwrap();
+        std::fs::write(
+            dir.path().join(TASKS_CONFIG_PATH),
+            r#"{"dev_servers": [
+                {"name": "web", "command": "npm run dev -- --port {port}", "watch": ["src/*"]},
+                {"name": "docs", "command": "mkdocs serve", "auto_restart": false}
+            ]}"#,
+        )
+        .unwrap();
+        let storage = LocalFileSystem::new(dir.path());
+        let config = TasksConfig::load(&storage, TASKS_CONFIG_PATH)
+            .await
+            .unwrap();
+
+        let runner = Arc::new(FakeServer::default());
+        let bus = Arc::new(EventBus::new());
+        bus.subscribe(Arc::new(Discard), EventFilter::only([EventKind::DevServer]));
+        let manager = DevServerManager::new(runner.clone())
+            .with_events(bus.clone())
+            .with_probe_interval(Duration::from_millis(10));
+        manager.configure(config);
+        assert_eq!(manager.list().len(), 2);
+        assert_eq!(
+            manager.state("web").unwrap().status,
+            DevServerStatus::Stopped
+        );
+
+        let state = manager.start("web").await.unwrap();
+        let port = state.port.unwrap();
+        wait_for(&manager, "web", DevServerStatus::Running).await;
+        let state = manager.state("web").unwrap();
+        assert_eq!(
+            state.output.back().map(String::as_str),
+            Some(format!("npm run dev -- --port {} ready", port).as_str())
+        );
+        // 已在运行时不会重复启动
+        manager.start("web").await.unwrap();
+        assert_eq!(runner.starts.load(Ordering::SeqCst), 1);
+
+        assert!(manager.affected(&write("README.md")).is_empty());
+        assert_eq!(
+            manager.on_change(&write("src/app.ts")).await.unwrap(),
+            vec!["web"]
+        );
+        wait_for(&manager, "web", DevServerStatus::Running).await;
+        assert_eq!(manager.state("web").unwrap().restarts, 1);
+        assert_eq!(runner.starts.load(Ordering::SeqCst), 2);
+
+        manager.stop("web").await.unwrap();
+        assert!(
+            tokio::net::TcpStream::connect(("127.0.0.1", port))
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            manager.state("web").unwrap().status,
+            DevServerStatus::Stopped
+        );
+        // starting、running、stopped、starting、running、stopped
+        assert_eq!(bus.pending(), 6);
+        assert!(manager.start("missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dev_server_tool_reports_status() {
+        let manager = Arc::new(
+            DevServerManager::new(Arc::new(FakeServer::default()))
+                .with_probe_interval(Duration::from_millis(10)),
+        );
+        manager.configure(TasksConfig {
+            dev_servers: vec![DevServerSpec {
+                name: "api".to_string(),
+                command: "cargo run".to_string(),
+                cwd: None,
+                port: None,
+                env: HashMap::new(),
+                watch: Vec::new(),
+                auto_restart: true,
+                startup_timeout_ms: 1_000,
+            }],
+        });
+        let tool = DevServerTool::new(manager.clone());
+
+        let output = tool
+            .execute(json!({"action": "start", "name": "api"}))
+            .await
+            .unwrap();
+        assert!(output.content.starts_with("api: Starting on port "));
+        wait_for(&manager, "api", DevServerStatus::Running).await;
+        let output = tool.execute(json!({"action": "status"})).await.unwrap();
+        assert!(output.content.starts_with("api: Running"));
+        assert!(tool.execute(json!({"action": "stop"})).await.is_err());
+        manager.shutdown().await.unwrap();
+        assert_eq!(
+            manager.state("api").unwrap().status,
+            DevServerStatus::Stopped
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watcher_keeps_restarting_after_a_failure() {
+        let runner = Arc::new(FakeServer::default());
+        let manager = Arc::new(
+            DevServerManager::new(runner.clone()).with_probe_interval(Duration::from_millis(10)),
+        );
+        let spec = |name: &str, watch: &str| DevServerSpec {
+            name: name.to_string(),
+            command: format!("{} serve", name),
+            cwd: None,
+            port: None,
+            env: HashMap::new(),
+            watch: vec![watch.to_string()],
+            auto_restart: true,
+            startup_timeout_ms: 1_000,
+        };
+        manager.configure(TasksConfig {
+            dev_servers: vec![spec("web", "src/*"), spec("docs", "docs/*")],
+        });
+        for name in ["web", "docs"] {
+            manager.start(name).await.unwrap();
+            wait_for(&manager, name, DevServerStatus::Running).await;
+        }
+
+        let threads = Arc::new(ThreadManager::new());
+        let main = threads.get_thread_id_by_name("main").unwrap();
+        let mut events = Vec::new();
+        for path in ["src/app.ts", "docs/index.md"] {
+            let change = write(path);
+            threads.commit_change(main, change.clone()).unwrap();
+            events.push(EventEnvelope::new(BackendEvent::change_committed(
+                main, &change,
+            )));
+        }
+        *runner.failing.lock().unwrap() = Some("web serve".to_string());
+        let watcher = DevServerWatcher::new(threads, manager.clone());
+
+        // web 重启失败后 docs 仍然重启，失败在最后汇总报告
+        let err = watcher.publish(&events).await.unwrap_err();
+        assert!(err.to_string().contains("web serve failed to start"));
+        assert_eq!(manager.state("docs").unwrap().restarts, 1);
+        wait_for(&manager, "docs", DevServerStatus::Running).await;
+        manager.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+use crate::common::meta::ast::MetaNode;
+use crate::common::provider::traits::StorageProvider;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 符号种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Module,
+    Function,
+    Class,
+    Declaration,
+}
+
+/// 索引中的一个符号定义
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolEntry {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// 定义所在文件
+    pub path: String,
+}
+
+/// 工作区索引：文件路径与从元 AST 提取的符号定义
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceIndex {
+    files: BTreeSet<String>,
+    /// 文件路径 -> 该文件中定义的符号
+    symbols: BTreeMap<String, Vec<SymbolEntry>>,
+}
+
+/// 扫描时跳过的目录
+const IGNORED_DIRS: &[&str] = &[".git", "target", "node_modules"];
+
+impl WorkspaceIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 递归扫描 `root` 下的全部文件（跳过版本库与构建产物目录）
+    pub async fn scan(storage: &dyn StorageProvider, root: &str) -> anyhow::Result<Self> {
+        let mut index = Self::new();
+        let mut pending = vec![root.to_string()];
+        while let Some(dir) = pending.pop() {
+            for entry in storage.list_dir(&dir).await? {
+                let name = entry.path.rsplit('/').next().unwrap_or(&entry.path);
+                if entry.is_dir {
+                    if !IGNORED_DIRS.contains(&name) {
+                        pending.push(entry.path);
+                    }
+                } else {
+                    index.add_file(&entry.path);
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    pub fn add_file(&mut self, path: &str) {
+        self.files.insert(path.to_string());
+    }
+
+    /// 移除文件及其符号
+    pub fn remove_file(&mut self, path: &str) {
+        self.files.remove(path);
+        self.symbols.remove(path);
+    }
+
+    /// 用解析得到的元 AST 替换文件的符号
+    pub fn index_symbols(&mut self, path: &str, root: &MetaNode) {
+        let mut entries = Vec::new();
+        collect_symbols(root, path, &mut entries);
+        self.files.insert(path.to_string());
+        self.symbols.insert(path.to_string(), entries);
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(String::as_str)
+    }
+
+    pub fn contains_file(&self, path: &str) -> bool {
+        self.files.contains(path)
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &SymbolEntry> {
+        self.symbols.values().flatten()
+    }
+
+    /// 按名称查找符号定义
+    pub fn find_symbol(&self, name: &str) -> Vec<&SymbolEntry> {
+        self.symbols().filter(|s| s.name == name).collect()
+    }
+}
+
+fn collect_symbols(node: &MetaNode, path: &str, entries: &mut Vec<SymbolEntry>) {
+    let mut push = |name: &str, kind| {
+        entries.push(SymbolEntry {
+            name: name.to_string(),
+            kind,
+            path: path.to_string(),
+        })
+    };
+    match node {
+        MetaNode::Module { name, children, .. } => {
+            push(name, SymbolKind::Module);
+            children
+                .iter()
+                .for_each(|c| collect_symbols(c, path, entries));
+        }
+        MetaNode::Function { name, body, .. } => {
+            push(name, SymbolKind::Function);
+            if let Some(body) = body {
+                collect_symbols(body, path, entries);
+            }
+        }
+        MetaNode::Class { name, members, .. } => {
+            push(name, SymbolKind::Class);
+            members
+                .iter()
+                .for_each(|m| collect_symbols(m, path, entries));
+        }
+        MetaNode::Declaration { name, .. } => push(name, SymbolKind::Declaration),
+        MetaNode::Block { statements, .. } => statements
+            .iter()
+            .for_each(|s| collect_symbols(s, path, entries)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_workspace_index_scan_and_symbols() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/engine")).unwrap();
+        std::fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("src/engine/parser.rs"), "").unwrap();
+        std::fs::write(dir.path().join("target/debug/app"), "").unwrap();
+
+        let storage = LocalFileSystem::new(dir.path());
+        let mut index = WorkspaceIndex::scan(&storage, "").await.unwrap();
+        let files: Vec<&str> = index.files().collect();
+        assert_eq!(files, vec!["src/engine/parser.rs", "src/main.rs"]);
+
+        let function = |name: &str| MetaNode::Function {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            params: vec![],
+            body: None,
+            metadata: HashMap::new(),
+        };
+        let tree = MetaNode::Module {
+            id: Uuid::new_v4(),
+            name: "parser".to_string(),
+            children: vec![MetaNode::Class {
+                id: Uuid::new_v4(),
+                name: "Parser".to_string(),
+                members: vec![function("parse_expr")],
+                bases: vec![],
+                metadata: HashMap::new(),
+            }],
+            metadata: HashMap::new(),
+        };
+        index.index_symbols("src/engine/parser.rs", &tree);
+        assert_eq!(index.symbols().count(), 3);
+        assert_eq!(
+            index.find_symbol("parse_expr")[0].path,
+            "src/engine/parser.rs"
+        );
+
+        index.remove_file("src/engine/parser.rs");
+        assert!(index.find_symbol("Parser").is_empty());
+        assert!(!index.contains_file("src/engine/parser.rs"));
+    }
+}
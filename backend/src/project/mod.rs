@@ -1,7 +1,23 @@
 pub mod adapter;
+pub mod conventions;
+pub mod devserver;
+pub mod index;
 pub mod resolver;
+pub mod secrets;
+pub mod stats;
 pub mod workspace;
 
 pub use adapter::{BuildSystemAdapter, CargoAdapter};
+pub use conventions::{TestConventions, TestFramework};
+pub use devserver::{
+    DevServerManager, DevServerSpec, DevServerState, DevServerStatus, DevServerTool,
+    DevServerWatcher, TasksConfig,
+};
+pub use index::{SymbolEntry, SymbolKind, WorkspaceIndex};
 pub use resolver::DependencyResolver;
+pub use secrets::{
+    RequestSecretTool, SecretGrant, SecretIntent, SecretMaskInterceptor, SecretMasker,
+    SecretRequest, SecretRequestStatus, SecretScopedRunner, SecretsManager,
+};
+pub use stats::{StatsAnalyzer, TaskSize, WorkspaceStats};
 pub use workspace::WorkspaceManager;
@@ -0,0 +1,1019 @@
+use crate::common::endpoint::config::SecretStore;
+use crate::common::endpoint::error::EndpointResult;
+use crate::common::endpoint::interceptor::{Intercept, InterceptRequest, Interceptor};
+use crate::common::endpoint::redaction::{Mask, mask_message};
+use crate::common::endpoint::stream::ChatResponse;
+use crate::common::endpoint::traits::ChatMessage;
+use crate::common::event::{BackendEvent, EventBus};
+use crate::common::intent::handler::IntentHandler;
+use crate::common::intent::traits::SystemIntent;
+use crate::common::provider::traits::{
+    ExecuteOptions, ExecuteResult, ExecutionProvider, OutputLine, OutputStream,
+};
+use crate::skill::tool::{Tool, ToolOutput};
+use crate::skill::traits::SkillError;
+use crate::syntax::query::matches;
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// 任务密钥名称需能直接作为环境变量名
+static ENV_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap());
+
+/// 短于该长度的值不做掩码，避免把常见短词替换掉
+const MIN_MASK_LEN: usize = 4;
+
+/// 授权列表在 `SecretStore` 中的名称（随密钥一同加密保存）
+const GRANTS_KEY: &str = "grants:secrets";
+
+/// 任务密钥在 `SecretStore` 中的名称
+pub fn secret_key(name: &str) -> String {
+    format!("secret:{}", name)
+}
+
+/// 将密钥授予匹配的任务
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretGrant {
+    pub secret: String,
+    /// 任务名模式，支持 `*` 通配
+    pub task: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretRequestStatus {
+    Pending,
+    Granted,
+    Denied,
+}
+
+/// Agent 发起的密钥授权请求
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretRequest {
+    pub id: Uuid,
+    pub secret: String,
+    pub task: String,
+    pub reason: String,
+    pub status: SecretRequestStatus,
+    pub requested_at: DateTime<Utc>,
+}
+
+/// 处理密钥授权请求的意图（由用户界面发出）
+#[derive(Clone)]
+pub enum SecretIntent {
+    /// 批准请求；`value` 为空时使用已保存的值
+    Grant {
+        request_id: Uuid,
+        value: Option<String>,
+    },
+    /// 拒绝请求
+    Deny { request_id: Uuid },
+}
+
+/// 调试输出中不包含密钥值
+impl fmt::Debug for SecretIntent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretIntent::Grant { request_id, value } => f
+                .debug_struct("Grant")
+                .field("request_id", request_id)
+                .field("value", &value.as_ref().map(|_| "***"))
+                .finish(),
+            SecretIntent::Deny { request_id } => f
+                .debug_struct("Deny")
+                .field("request_id", request_id)
+                .finish(),
+        }
+    }
+}
+
+/// 将文本中出现的密钥值替换为 `[secret:NAME]`
+#[derive(Clone, Default)]
+pub struct SecretMasker {
+    /// (标签, 值)，按值长度降序，较长的值优先替换
+    secrets: Vec<(String, String)>,
+}
+
+impl SecretMasker {
+    fn new(mut secrets: Vec<(String, String)>) -> Self {
+        secrets.retain(|(_, value)| value.len() >= MIN_MASK_LEN);
+        secrets.sort_by_key(|(_, value)| std::cmp::Reverse(value.len()));
+        Self { secrets }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.secrets.is_empty()
+    }
+
+    pub fn mask(&self, text: &str) -> String {
+        self.secrets
+            .iter()
+            .fold(text.to_string(), |text, (label, value)| {
+                if text.contains(value.as_str()) {
+                    text.replace(value.as_str(), &format!("[secret:{}]", label))
+                } else {
+                    text
+                }
+            })
+    }
+
+    fn mask_line(&self, line: OutputLine) -> OutputLine {
+        match line {
+            OutputLine::Stdout(text) => OutputLine::Stdout(self.mask(&text)),
+            OutputLine::Stderr(text) => OutputLine::Stderr(self.mask(&text)),
+        }
+    }
+
+    fn mask_message(&self, message: &mut ChatMessage) {
+        mask_message(message, |text| self.mask(text));
+    }
+}
+
+impl Mask for SecretMasker {
+    fn mask(&self, text: &str) -> String {
+        SecretMasker::mask(self, text)
+    }
+}
+
+/// 任务与 Agent 执行用的环境变量与密钥管理
+///
+/// 密钥值加密保存在 `SecretStore` 中，只注入到被授权的任务的环境变量；
+/// 授权列表同样保存在该存储中，重启后保留。
+/// Agent 缺少密钥时发起授权请求，由用户通过 `SecretIntent` 批准或拒绝。
+pub struct SecretsManager {
+    store: Arc<dyn SecretStore>,
+    grants: RwLock<Vec<SecretGrant>>,
+    /// 缓存的掩码器，经本管理器修改密钥时失效
+    masker: RwLock<Option<Arc<SecretMasker>>>,
+    requests: Mutex<BTreeMap<Uuid, SecretRequest>>,
+    /// 任一请求被处理时通知等待者
+    resolved: Notify,
+    events: Option<Arc<EventBus>>,
+}
+
+impl SecretsManager {
+    /// 打开管理器并载入已保存的授权
+    pub fn new(store: Arc<dyn SecretStore>) -> Result<Self> {
+        let grants = match store.get(GRANTS_KEY)? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            store,
+            grants: RwLock::new(grants),
+            masker: RwLock::new(None),
+            requests: Mutex::new(BTreeMap::new()),
+            resolved: Notify::new(),
+            events: None,
+        })
+    }
+
+    /// 授权请求创建与处理时发布 `BackendEvent::Secret`
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 替换全部授权并保存
+    pub fn with_grants(self, grants: Vec<SecretGrant>) -> Result<Self> {
+        self.save_grants(&grants)?;
+        *self.grants.write().unwrap() = grants;
+        Ok(self)
+    }
+
+    /// 保存密钥；名称需是合法的环境变量名
+    pub fn set(&self, name: &str, value: &str) -> Result<()> {
+        if !ENV_NAME.is_match(name) {
+            bail!("Invalid secret name: {}", name);
+        }
+        self.store.set(&secret_key(name), value)?;
+        self.refresh_masker();
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(self.store.get(&secret_key(name))?)
+    }
+
+    /// 删除密钥及其全部授权
+    pub fn delete(&self, name: &str) -> Result<()> {
+        self.store.delete(&secret_key(name))?;
+        self.refresh_masker();
+        let mut grants = self.grants.write().unwrap();
+        let kept: Vec<SecretGrant> = grants
+            .iter()
+            .filter(|g| g.secret != name)
+            .cloned()
+            .collect();
+        self.save_grants(&kept)?;
+        *grants = kept;
+        Ok(())
+    }
+
+    /// 已保存的任务密钥名称（不含提供者 API Key 等其他条目）
+    pub fn names(&self) -> Result<Vec<String>> {
+        Ok(self
+            .store
+            .names()?
+            .iter()
+            .filter_map(|key| key.strip_prefix("secret:"))
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// 把密钥授予匹配 `task` 模式的任务并保存
+    pub fn grant(&self, secret: &str, task: &str) -> Result<()> {
+        let grant = SecretGrant {
+            secret: secret.to_string(),
+            task: task.to_string(),
+        };
+        let mut grants = self.grants.write().unwrap();
+        if !grants.contains(&grant) {
+            let mut updated = grants.clone();
+            updated.push(grant);
+            self.save_grants(&updated)?;
+            *grants = updated;
+        }
+        Ok(())
+    }
+
+    /// 撤销授权并保存，返回授权是否存在
+    pub fn revoke(&self, secret: &str, task: &str) -> Result<bool> {
+        let mut grants = self.grants.write().unwrap();
+        let kept: Vec<SecretGrant> = grants
+            .iter()
+            .filter(|g| !(g.secret == secret && g.task == task))
+            .cloned()
+            .collect();
+        if kept.len() == grants.len() {
+            return Ok(false);
+        }
+        self.save_grants(&kept)?;
+        *grants = kept;
+        Ok(true)
+    }
+
+    pub fn grants(&self) -> Vec<SecretGrant> {
+        self.grants.read().unwrap().clone()
+    }
+
+    pub fn is_granted(&self, secret: &str, task: &str) -> bool {
+        self.grants
+            .read()
+            .unwrap()
+            .iter()
+            .any(|g| g.secret == secret && matches(&g.task, task))
+    }
+
+    /// 授予该任务且已保存的密钥，以密钥名为环境变量名
+    pub fn env_for(&self, task: &str) -> Result<HashMap<String, String>> {
+        let secrets: Vec<String> = self
+            .grants
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|g| matches(&g.task, task))
+            .map(|g| g.secret.clone())
+            .collect();
+        let mut env = HashMap::new();
+        for secret in secrets {
+            if let Some(value) = self.get(&secret)? {
+                env.insert(secret, value);
+            }
+        }
+        Ok(env)
+    }
+
+    /// 将授予任务的密钥写入执行选项的环境变量（覆盖同名变量）
+    pub fn inject(&self, task: &str, options: &mut ExecuteOptions) -> Result<()> {
+        options.env.extend(self.env_for(task)?);
+        Ok(())
+    }
+
+    /// 覆盖存储中全部条目（含提供者 API Key）的掩码器
+    ///
+    /// 首次调用时解密全部条目并缓存；绕过本管理器直接写入存储的条目
+    /// （如 `ModelRegistry::set_api_key`）需调用 `refresh_masker` 后才会被掩码。
+    pub fn masker(&self) -> Result<Arc<SecretMasker>> {
+        if let Some(masker) = self.masker.read().unwrap().as_ref() {
+            return Ok(masker.clone());
+        }
+        let mut secrets = Vec::new();
+        for key in self.store.names()? {
+            if key == GRANTS_KEY {
+                continue;
+            }
+            if let Some(value) = self.store.get(&key)? {
+                let label = key.strip_prefix("secret:").unwrap_or(&key).to_string();
+                secrets.push((label, value));
+            }
+        }
+        let masker = Arc::new(SecretMasker::new(secrets));
+        *self.masker.write().unwrap() = Some(masker.clone());
+        Ok(masker)
+    }
+
+    /// 丢弃缓存的掩码器，下次使用时重新读取存储
+    pub fn refresh_masker(&self) {
+        *self.masker.write().unwrap() = None;
+    }
+
+    /// 请求把密钥授予任务
+    ///
+    /// 已授权且已保存时直接返回 `Granted`；同一任务对同一密钥的待处理请求只保留一个。
+    /// 任务名按字面匹配，不接受 `*` 通配（批准后的授权只覆盖该任务）。
+    pub fn request(&self, task: &str, secret: &str, reason: &str) -> Result<SecretRequest> {
+        if !ENV_NAME.is_match(secret) {
+            bail!("Invalid secret name: {}", secret);
+        }
+        if task.is_empty() || task.contains('*') {
+            bail!("Invalid task name for a secret request: '{}'", task);
+        }
+        let status = if self.is_granted(secret, task) && self.get(secret)?.is_some() {
+            SecretRequestStatus::Granted
+        } else {
+            SecretRequestStatus::Pending
+        };
+        let mut requests = self.requests.lock().unwrap();
+        if status == SecretRequestStatus::Pending
+            && let Some(pending) = requests.values().find(|r| {
+                r.status == SecretRequestStatus::Pending && r.task == task && r.secret == secret
+            })
+        {
+            return Ok(pending.clone());
+        }
+        let request = SecretRequest {
+            id: Uuid::new_v4(),
+            secret: secret.to_string(),
+            task: task.to_string(),
+            reason: reason.to_string(),
+            status,
+            requested_at: Utc::now(),
+        };
+        if status == SecretRequestStatus::Pending {
+            requests.insert(request.id, request.clone());
+            drop(requests);
+            self.emit(&request);
+        }
+        Ok(request)
+    }
+
+    pub fn get_request(&self, id: Uuid) -> Option<SecretRequest> {
+        self.requests.lock().unwrap().get(&id).cloned()
+    }
+
+    /// 待处理的请求（按 ID 排序）
+    pub fn pending(&self) -> Vec<SecretRequest> {
+        self.requests
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|r| r.status == SecretRequestStatus::Pending)
+            .cloned()
+            .collect()
+    }
+
+    /// 批准请求：保存提供的值（如有）并把密钥授予请求的任务
+    pub fn approve(&self, id: Uuid, value: Option<&str>) -> Result<SecretRequest> {
+        let request = self.pending_request(id)?;
+        match value {
+            Some(value) => self.set(&request.secret, value)?,
+            None if self.get(&request.secret)?.is_none() => {
+                bail!("Secret '{}' has no stored value", request.secret)
+            }
+            None => {}
+        }
+        self.grant(&request.secret, &request.task)?;
+        self.resolve(id, SecretRequestStatus::Granted)
+    }
+
+    pub fn deny(&self, id: Uuid) -> Result<SecretRequest> {
+        self.pending_request(id)?;
+        self.resolve(id, SecretRequestStatus::Denied)
+    }
+
+    /// 等待请求被处理，超时仍未处理时返回 `Pending`
+    pub async fn wait(&self, id: Uuid, timeout: Duration) -> Result<SecretRequestStatus> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let notified = self.resolved.notified();
+            let status = self
+                .get_request(id)
+                .ok_or_else(|| anyhow!("Unknown secret request: {}", id))?
+                .status;
+            if status != SecretRequestStatus::Pending {
+                return Ok(status);
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return Ok(SecretRequestStatus::Pending);
+            }
+        }
+    }
+
+    fn pending_request(&self, id: Uuid) -> Result<SecretRequest> {
+        let request = self
+            .get_request(id)
+            .ok_or_else(|| anyhow!("Unknown secret request: {}", id))?;
+        if request.status != SecretRequestStatus::Pending {
+            bail!("Secret request {} was already resolved", id);
+        }
+        Ok(request)
+    }
+
+    fn resolve(&self, id: Uuid, status: SecretRequestStatus) -> Result<SecretRequest> {
+        let request = {
+            let mut requests = self.requests.lock().unwrap();
+            let request = requests
+                .get_mut(&id)
+                .ok_or_else(|| anyhow!("Unknown secret request: {}", id))?;
+            request.status = status;
+            request.clone()
+        };
+        self.resolved.notify_waiters();
+        self.emit(&request);
+        Ok(request)
+    }
+
+    fn save_grants(&self, grants: &[SecretGrant]) -> Result<()> {
+        Ok(self
+            .store
+            .set(GRANTS_KEY, &serde_json::to_string(grants)?)?)
+    }
+
+    fn emit(&self, request: &SecretRequest) {
+        if let Some(events) = &self.events {
+            events.emit(BackendEvent::Secret {
+                name: request.secret.clone(),
+                detail: json!({
+                    "request_id": request.id,
+                    "task": request.task,
+                    "reason": request.reason,
+                    "status": request.status,
+                }),
+            });
+        }
+    }
+}
+
+/// 以当前密钥掩码文本，供提示词日志、意图分发器与工具注册表使用；
+/// 存储不可读时原样返回，不阻断调用
+impl Mask for SecretsManager {
+    fn mask(&self, text: &str) -> String {
+        match self.masker() {
+            Ok(masker) => masker.mask(text),
+            Err(_) => text.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl IntentHandler for SecretsManager {
+    async fn handle(&self, intent: SystemIntent) -> Result<()> {
+        match intent {
+            SystemIntent::Secret(SecretIntent::Grant { request_id, value }) => {
+                self.approve(request_id, value.as_deref()).map(|_| ())
+            }
+            SystemIntent::Secret(SecretIntent::Deny { request_id }) => {
+                self.deny(request_id).map(|_| ())
+            }
+            other => Err(anyhow!("Unsupported intent: {}", other.summary())),
+        }
+    }
+}
+
+/// 为指定任务注入已授权密钥并掩码输出的执行提供者
+pub struct SecretScopedRunner {
+    inner: Arc<dyn ExecutionProvider>,
+    secrets: Arc<SecretsManager>,
+    task: String,
+}
+
+impl SecretScopedRunner {
+    pub fn new(
+        inner: Arc<dyn ExecutionProvider>,
+        secrets: Arc<SecretsManager>,
+        task: &str,
+    ) -> Self {
+        Self {
+            inner,
+            secrets,
+            task: task.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl ExecutionProvider for SecretScopedRunner {
+    async fn execute(&self, command: &str, mut options: ExecuteOptions) -> Result<ExecuteResult> {
+        self.secrets.inject(&self.task, &mut options)?;
+        let masker = self.secrets.masker()?;
+        let result = self.inner.execute(command, options).await?;
+        Ok(ExecuteResult {
+            exit_code: result.exit_code,
+            stdout: masker.mask(&result.stdout),
+            stderr: masker.mask(&result.stderr),
+        })
+    }
+
+    async fn execute_stream(
+        &self,
+        command: &str,
+        mut options: ExecuteOptions,
+    ) -> Result<OutputStream> {
+        self.secrets.inject(&self.task, &mut options)?;
+        let masker = self.secrets.masker()?;
+        let stream = self.inner.execute_stream(command, options).await?;
+        Ok(Box::pin(
+            stream.map(move |line| line.map(|line| masker.mask_line(line))),
+        ))
+    }
+
+    async fn kill(&self, task_id: &str) -> Result<()> {
+        self.inner.kill(task_id).await
+    }
+}
+
+/// 在请求模型前与响应返回前掩码消息中的密钥值，避免密钥进入提供者与对话记录
+pub struct SecretMaskInterceptor {
+    secrets: Arc<SecretsManager>,
+}
+
+impl SecretMaskInterceptor {
+    pub fn new(secrets: Arc<SecretsManager>) -> Self {
+        Self { secrets }
+    }
+
+    fn masker(&self) -> Arc<SecretMasker> {
+        // 存储不可读时不阻断模型调用
+        self.secrets.masker().unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl Interceptor for SecretMaskInterceptor {
+    fn name(&self) -> &str {
+        "secret_mask"
+    }
+
+    async fn on_request(&self, request: &mut InterceptRequest) -> EndpointResult<Intercept> {
+        let masker = self.masker();
+        if !masker.is_empty() {
+            for message in &mut request.messages {
+                masker.mask_message(message);
+            }
+        }
+        Ok(Intercept::Continue)
+    }
+
+    async fn on_response(
+        &self,
+        _request: &InterceptRequest,
+        response: &mut ChatResponse,
+    ) -> EndpointResult<()> {
+        let masker = self.masker();
+        if !masker.is_empty() {
+            for choice in &mut response.choices {
+                masker.mask_message(&mut choice.message);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `request_secret` 工具：Agent 缺少密钥时请求用户授权，等待用户处理
+pub struct RequestSecretTool {
+    secrets: Arc<SecretsManager>,
+    default_timeout: Duration,
+}
+
+impl RequestSecretTool {
+    pub fn new(secrets: Arc<SecretsManager>) -> Self {
+        Self {
+            secrets,
+            default_timeout: Duration::from_secs(300),
+        }
+    }
+
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = timeout;
+        self
+    }
+}
+
+#[async_trait(?Send)]
+impl Tool for RequestSecretTool {
+    fn name(&self) -> &'static str {
+        "request_secret"
+    }
+
+    fn description(&self) -> &'static str {
+        "请求用户将某个密钥（如 API Token）授予指定任务；批准后密钥以同名环境变量注入该任务，密钥值不会返回给 Agent。"
+    }
+
+    fn parameter_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "secret": {
+                    "type": "string",
+                    "description": "密钥名，同时作为环境变量名，如 STRIPE_API_KEY"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "需要该密钥的任务名（如开发服务器名）"
+                },
+                "reason": { "type": "string" },
+                "timeout_ms": { "type": "integer" }
+            },
+            "required": ["secret", "task", "reason"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
+        let field = |name: &str| {
+            args[name]
+                .as_str()
+                .ok_or_else(|| SkillError::InvalidSkill(format!("Missing '{}' parameter", name)))
+        };
+        let (secret, task, reason) = (field("secret")?, field("task")?, field("reason")?);
+        let timeout = args["timeout_ms"]
+            .as_u64()
+            .map(Duration::from_millis)
+            .unwrap_or(self.default_timeout);
+
+        let request = self
+            .secrets
+            .request(task, secret, reason)
+            .map_err(|e| SkillError::InvalidSkill(e.to_string()))?;
+        let status = match request.status {
+            SecretRequestStatus::Pending => self
+                .secrets
+                .wait(request.id, timeout)
+                .await
+                .map_err(|e| SkillError::IoError(std::io::Error::other(e.to_string())))?,
+            status => status,
+        };
+        let content = match status {
+            SecretRequestStatus::Granted => format!(
+                "Secret {} is granted to task '{}' and available as the ${} environment variable.",
+                secret, task, secret
+            ),
+            SecretRequestStatus::Denied => {
+                format!("The user denied access to secret {}.", secret)
+            }
+            SecretRequestStatus::Pending => format!(
+                "Request {} for secret {} is still waiting for the user.",
+                request.id, secret
+            ),
+        };
+        Ok(ToolOutput {
+            content,
+            data: Some(json!({ "request_id": request.id, "status": status })),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::config::EncryptedFileStore;
+    use crate::common::endpoint::traits::{MessageContent, MessageRole};
+    use crate::common::event::EventEnvelope;
+    use crate::common::event::{EventFilter, EventKind, EventSink};
+    use crate::common::intent::{IntentCategory, IntentDispatcher};
+
+    struct Echo;
+
+    #[async_trait]
+    impl ExecutionProvider for Echo {
+        async fn execute(&self, _command: &str, options: ExecuteOptions) -> Result<ExecuteResult> {
+            let mut env: Vec<String> = options
+                .env
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect();
+            env.sort();
+            Ok(ExecuteResult {
+                exit_code: 0,
+                stdout: env.join("\n"),
+                stderr: String::new(),
+            })
+        }
+
+        async fn kill(&self, _task_id: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    struct Discard;
+
+    #[async_trait]
+    impl EventSink for Discard {
+        fn name(&self) -> &str {
+            "discard"
+        }
+
+        async fn publish(&self, _events: &[EventEnvelope]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn manager(dir: &tempfile::TempDir) -> SecretsManager {
+        let store = EncryptedFileStore::open(
+            dir.path().join("secrets.json"),
+            &dir.path().join("master.key"),
+        )
+        .unwrap();
+        SecretsManager::new(Arc::new(store)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_scoped_injection_and_masking() {
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = Arc::new(manager(&dir));
+        secrets.set("STRIPE_KEY", "sk_test_123456").unwrap();
+        secrets.set("DB_PASSWORD", "hunter2hunter2").unwrap();
+        assert!(secrets.set("not-an-env", "x").is_err());
+        secrets.grant("STRIPE_KEY", "web*").unwrap();
+        assert_eq!(secrets.names().unwrap(), vec!["DB_PASSWORD", "STRIPE_KEY"]);
+
+        // 只有被授权的任务能拿到密钥，输出中的值被掩码
+        let web = SecretScopedRunner::new(Arc::new(Echo), secrets.clone(), "web-api");
+        let result = web.execute("env", ExecuteOptions::default()).await.unwrap();
+        assert_eq!(result.stdout, "STRIPE_KEY=[secret:STRIPE_KEY]");
+        let worker = SecretScopedRunner::new(Arc::new(Echo), secrets.clone(), "worker");
+        let result = worker
+            .execute("env", ExecuteOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(result.stdout, "");
+
+        let masker = secrets.masker().unwrap();
+        assert_eq!(
+            masker.mask("password is hunter2hunter2"),
+            "password is [secret:DB_PASSWORD]"
+        );
+        // 密钥值不会以明文落盘
+        let on_disk = std::fs::read_to_string(dir.path().join("secrets.json")).unwrap();
+        assert!(!on_disk.contains("hunter2"));
+
+        let interceptor = SecretMaskInterceptor::new(secrets.clone());
+        let mut request = InterceptRequest {
+            provider: "mock".to_string(),
+            model_id: "m".to_string(),
+            messages: vec![ChatMessage::text(
+                MessageRole::User,
+                "the key is sk_test_123456",
+            )],
+            options: Default::default(),
+        };
+        interceptor.on_request(&mut request).await.unwrap();
+        assert_eq!(
+            request.messages[0].content,
+            MessageContent::Text("the key is [secret:STRIPE_KEY]".to_string())
+        );
+
+        secrets.delete("STRIPE_KEY").unwrap();
+        assert!(secrets.grants().is_empty());
+        // 掩码器随密钥变更刷新
+        assert_eq!(
+            secrets.masker().unwrap().mask("sk_test_123456"),
+            "sk_test_123456"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_grant_flow_through_intents() {
+        let dir = tempfile::tempdir().unwrap();
+        let bus = Arc::new(EventBus::new());
+        bus.subscribe(Arc::new(Discard), EventFilter::only([EventKind::Secret]));
+        let secrets = Arc::new(manager(&dir).with_events(bus.clone()));
+        let dispatcher = IntentDispatcher::new();
+        dispatcher
+            .register(IntentCategory::Secret, secrets.clone())
+            .await;
+
+        let tool = RequestSecretTool::new(secrets.clone());
+        let agent = tokio::task::LocalSet::new();
+        let pending = agent.spawn_local(async move {
+            tool.execute(json!({
+                "secret": "GITHUB_TOKEN",
+                "task": "release",
+                "reason": "publish the package"
+            }))
+            .await
+            .unwrap()
+        });
+        let output = agent
+            .run_until(async {
+                let request = loop {
+                    if let Some(request) = secrets.pending().pop() {
+                        break request;
+                    }
+                    tokio::task::yield_now().await;
+                };
+                assert_eq!(request.task, "release");
+                // 重复请求复用同一个待处理请求
+                let again = secrets.request("release", "GITHUB_TOKEN", "retry").unwrap();
+                assert_eq!(again.id, request.id);
+
+                let intent = SecretIntent::Grant {
+                    request_id: request.id,
+                    value: Some("ghp_abcdef".to_string()),
+                };
+                assert!(!format!("{:?}", intent).contains("ghp_"));
+                dispatcher
+                    .dispatch(SystemIntent::Secret(intent))
+                    .await
+                    .unwrap();
+                pending.await.unwrap()
+            })
+            .await;
+        assert_eq!(output.data.unwrap()["status"], "granted");
+        assert!(!output.content.contains("ghp_"));
+        assert_eq!(
+            secrets.env_for("release").unwrap()["GITHUB_TOKEN"],
+            "ghp_abcdef"
+        );
+        // 已授权的请求直接返回
+        let granted = secrets.request("release", "GITHUB_TOKEN", "again").unwrap();
+        assert_eq!(granted.status, SecretRequestStatus::Granted);
+
+        let denied = secrets.request("deploy", "GITHUB_TOKEN", "deploy").unwrap();
+        dispatcher
+            .dispatch(SystemIntent::Secret(SecretIntent::Deny {
+                request_id: denied.id,
+            }))
+            .await
+            .unwrap();
+        assert_eq!(
+            secrets.wait(denied.id, Duration::ZERO).await.unwrap(),
+            SecretRequestStatus::Denied
+        );
+        assert!(secrets.deny(denied.id).is_err());
+        assert!(secrets.env_for("deploy").unwrap().is_empty());
+        assert_eq!(bus.pending(), 4);
+
+        // Agent 不能用通配任务名一次拿到所有任务的授权
+        assert!(secrets.request("*", "GITHUB_TOKEN", "all").is_err());
+        assert!(secrets.request("rel*", "GITHUB_TOKEN", "all").is_err());
+
+        // 授权随加密存储保存，重启后仍然有效
+        drop(dispatcher);
+        drop(secrets);
+        let reopened = manager(&dir);
+        assert_eq!(
+            reopened.grants(),
+            vec![SecretGrant {
+                secret: "GITHUB_TOKEN".to_string(),
+                task: "release".to_string(),
+            }]
+        );
+        assert_eq!(reopened.names().unwrap(), vec!["GITHUB_TOKEN"]);
+    }
+
+    #[tokio::test]
+    async fn test_secret_values_never_reach_logs_journal_audit_or_tool_output() {
+        use crate::common::endpoint::logging::{
+            LoggedClient, PromptLogConfig, PromptLogQuery, PromptLogger,
+        };
+        use crate::common::endpoint::scripted::ScriptedClient;
+        use crate::common::endpoint::traits::{ChatOptions, LLMClient};
+        use crate::common::intent::audit::{AuditFilter, IntentAuditLog};
+        use crate::common::intent::journal::IntentJournal;
+        use crate::common::intent::traits::EditorIntent;
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+        use crate::common::provider::traits::StorageProvider;
+        use crate::skill::tool::SkillToolRegistry;
+
+        const KEY: &str = "sk_test_123456";
+
+        /// 处理失败时在错误信息中带出密钥
+        struct Leaky;
+
+        #[async_trait]
+        impl IntentHandler for Leaky {
+            async fn handle(&self, _intent: SystemIntent) -> Result<()> {
+                bail!("upstream rejected token {}", KEY)
+            }
+        }
+
+        /// 在输出中回显密钥
+        struct Echoing;
+
+        #[async_trait(?Send)]
+        impl Tool for Echoing {
+            fn name(&self) -> &'static str {
+                "echo_env"
+            }
+
+            fn description(&self) -> &'static str {
+                "echo"
+            }
+
+            fn parameter_schema(&self) -> Value {
+                json!({ "type": "object" })
+            }
+
+            async fn execute(&self, _args: Value) -> Result<ToolOutput, SkillError> {
+                Ok(ToolOutput {
+                    content: format!("STRIPE_KEY={}", KEY),
+                    data: Some(json!({ "env": [{ "value": KEY }] })),
+                })
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = Arc::new(manager(&dir));
+        secrets.set("STRIPE_KEY", KEY).unwrap();
+        let storage: Arc<dyn StorageProvider> =
+            Arc::new(LocalFileSystem::new(dir.path().join("project")));
+
+        // 提示词日志
+        let config = PromptLogConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let logger = Arc::new(
+            PromptLogger::open(storage.clone(), "prompts.jsonl", config)
+                .await
+                .unwrap()
+                .with_mask(secrets.clone()),
+        );
+        let reply = format!("use {}", KEY);
+        let client = LoggedClient::new(
+            Arc::new(ScriptedClient::new("mock").with_replies([reply.as_str()])),
+            logger.clone(),
+            "mock",
+        );
+        let question = ChatMessage::text(MessageRole::User, &format!("is {} valid?", KEY));
+        client
+            .chat("m", &[question], &ChatOptions::default())
+            .await
+            .unwrap();
+        let logged = logger.query(&PromptLogQuery::default()).await;
+        assert_eq!(
+            logged[0].messages[0].content.as_text(),
+            "is [secret:STRIPE_KEY] valid?"
+        );
+
+        // 意图日志与审计日志
+        let journal = Arc::new(IntentJournal::new());
+        let audit = Arc::new(IntentAuditLog::new().with_storage(storage.clone()));
+        let dispatcher = IntentDispatcher::new()
+            .with_journal(journal.clone())
+            .with_mask(secrets.clone());
+        dispatcher
+            .register(IntentCategory::Editor, Arc::new(Leaky))
+            .await;
+        dispatcher.add_middleware(None, audit.clone()).await;
+        let error = dispatcher
+            .dispatch(SystemIntent::Editor(EditorIntent::OpenFile {
+                path: format!("notes/{}.txt", KEY),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "upstream rejected token [secret:STRIPE_KEY]"
+        );
+        let record = audit.records(&AuditFilter::all()).pop().unwrap();
+        assert!(record.intent.contains("[secret:STRIPE_KEY]"));
+
+        // 工具输出
+        let mut tools = SkillToolRegistry::new().with_mask(secrets.clone());
+        tools.register(Arc::new(Echoing));
+        let output = tools.execute("echo_env", json!({})).await.unwrap();
+        assert_eq!(output.content, "STRIPE_KEY=[secret:STRIPE_KEY]");
+
+        let persisted = [
+            storage.read_file("prompts.jsonl").await.unwrap(),
+            storage
+                .read_file(crate::common::intent::audit::AUDIT_LOG_PATH)
+                .await
+                .unwrap(),
+        ];
+        let recorded = format!(
+            "{:?}{:?}{:?}{:?}",
+            logged,
+            journal.entries(),
+            audit.records(&AuditFilter::all()),
+            output
+        );
+        assert!(!recorded.contains(KEY));
+        for bytes in persisted {
+            assert!(!String::from_utf8(bytes).unwrap().contains(KEY));
+        }
+    }
+}
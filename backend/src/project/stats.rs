@@ -0,0 +1,376 @@
+use crate::common::change::Change;
+use crate::common::change::merge::MergeEngine;
+use crate::common::change::notebook::apply_cell_operation;
+use crate::common::change::operation::Operation;
+use crate::common::change::snapshot::edit_error;
+use crate::common::change::text::apply_text_operation;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 单一语言的统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LanguageStats {
+    pub language: String,
+    pub files: usize,
+    /// 总行数
+    pub lines: usize,
+    /// 非空行数
+    pub code_lines: usize,
+    pub bytes: u64,
+}
+
+/// 某一天结束时的工作区规模与当天的变更量
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GrowthPoint {
+    pub date: NaiveDate,
+    pub changes: usize,
+    pub files: usize,
+    pub lines: usize,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+}
+
+/// 单个文件的变更频度
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileChurn {
+    pub path: String,
+    pub changes: usize,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+}
+
+/// 任务规模估计，供 Planner 拆分任务时参考
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSize {
+    Small,
+    Medium,
+    Large,
+}
+
+/// 工作区统计结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceStats {
+    pub total_files: usize,
+    pub total_lines: usize,
+    pub total_bytes: u64,
+    /// 按行数降序排列
+    pub languages: Vec<LanguageStats>,
+    /// 按日期升序排列
+    pub growth: Vec<GrowthPoint>,
+    /// 按变更次数降序排列
+    pub churn: Vec<FileChurn>,
+    /// 文件 -> 行数
+    #[serde(skip)]
+    file_lines: BTreeMap<String, usize>,
+}
+
+impl WorkspaceStats {
+    /// 指定路径（文件或目录前缀）下的总行数
+    pub fn lines_under(&self, paths: &[&str]) -> usize {
+        self.file_lines
+            .iter()
+            .filter(|(file, _)| paths.iter().any(|p| under(file, p)))
+            .map(|(_, lines)| lines)
+            .sum()
+    }
+
+    /// 根据涉及路径的代码量与历史变更频度估计任务规模
+    pub fn estimate_task_size(&self, paths: &[&str]) -> TaskSize {
+        let lines = self.lines_under(paths);
+        let churn: usize = self
+            .churn
+            .iter()
+            .filter(|c| paths.iter().any(|p| under(&c.path, p)))
+            .map(|c| c.lines_added + c.lines_removed)
+            .sum();
+        // 高频变动的代码通常耦合更紧，按一半权重计入
+        match lines + churn / 2 {
+            0..500 => TaskSize::Small,
+            500..5000 => TaskSize::Medium,
+            _ => TaskSize::Large,
+        }
+    }
+}
+
+/// 工作区统计分析器
+///
+/// 当前规模来自文件快照，增长与变更频度由变动图回放得出。
+/// 引用 Blob 的写入需先经 `BlobStore::hydrate` 还原，未还原的引用视为错误。
+#[derive(Debug, Clone, Default)]
+pub struct StatsAnalyzer {
+    /// 扩展名 -> 语言名称（覆盖内置映射）
+    languages: HashMap<String, String>,
+}
+
+impl StatsAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册扩展名对应的语言
+    pub fn register_language(&mut self, extension: &str, language: &str) {
+        self.languages.insert(
+            extension.trim_start_matches('.').to_lowercase(),
+            language.to_string(),
+        );
+    }
+
+    /// 根据文件路径识别语言
+    pub fn detect_language(&self, path: &str) -> String {
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        let Some((_, ext)) = file_name.rsplit_once('.') else {
+            return "Other".to_string();
+        };
+        let ext = ext.to_lowercase();
+        if let Some(language) = self.languages.get(&ext) {
+            return language.clone();
+        }
+        match ext.as_str() {
+            "rs" => "Rust",
+            "ts" | "tsx" => "TypeScript",
+            "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+            "py" => "Python",
+            "go" => "Go",
+            "java" => "Java",
+            "kt" | "kts" => "Kotlin",
+            "c" | "h" => "C",
+            "cc" | "cpp" | "cxx" | "hpp" => "C++",
+            "cs" => "C#",
+            "swift" => "Swift",
+            "rb" => "Ruby",
+            "vue" => "Vue",
+            "html" | "htm" => "HTML",
+            "css" | "scss" | "less" => "CSS",
+            "json" => "JSON",
+            "toml" => "TOML",
+            "yaml" | "yml" => "YAML",
+            "md" => "Markdown",
+            "sh" | "bash" => "Shell",
+            _ => "Other",
+        }
+        .to_string()
+    }
+
+    /// 统计文件快照（路径 -> 内容），不含历史信息
+    pub fn analyze_files(&self, files: &BTreeMap<String, Vec<u8>>) -> WorkspaceStats {
+        let mut languages: HashMap<String, LanguageStats> = HashMap::new();
+        let mut stats = WorkspaceStats::default();
+
+        for (path, content) in files {
+            let text = String::from_utf8_lossy(content);
+            let lines = text.lines().count();
+            let code_lines = text.lines().filter(|l| !l.trim().is_empty()).count();
+
+            let language = self.detect_language(path);
+            let entry = languages
+                .entry(language.clone())
+                .or_insert_with(|| LanguageStats {
+                    language,
+                    ..Default::default()
+                });
+            entry.files += 1;
+            entry.lines += lines;
+            entry.code_lines += code_lines;
+            entry.bytes += content.len() as u64;
+
+            stats.total_files += 1;
+            stats.total_lines += lines;
+            stats.total_bytes += content.len() as u64;
+            stats.file_lines.insert(path.clone(), lines);
+        }
+
+        stats.languages = languages.into_values().collect();
+        stats.languages.sort_by(|a, b| {
+            b.lines
+                .cmp(&a.lines)
+                .then_with(|| a.language.cmp(&b.language))
+        });
+        stats
+    }
+
+    /// 回放变动序列，统计当前规模、按天增长曲线与文件变更频度
+    ///
+    /// 增量编辑无法应用到回放出的内容时报错，避免统计悄悄偏离真实历史。
+    pub fn analyze_history(&self, changes: &[Change]) -> anyhow::Result<WorkspaceStats> {
+        let mut files: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+        let mut growth: Vec<GrowthPoint> = Vec::new();
+        let mut churn: HashMap<String, FileChurn> = HashMap::new();
+        // 当前总行数随每个操作的增删行数更新，无需在每个 Change 后重新统计所有文件
+        let mut lines = 0;
+
+        for change in MergeEngine::new().sort_changes(changes.to_vec()) {
+            let date = change.timestamp.date_naive();
+            if growth.last().is_none_or(|p| p.date != date) {
+                growth.push(GrowthPoint {
+                    date,
+                    changes: 0,
+                    files: 0,
+                    lines: 0,
+                    lines_added: 0,
+                    lines_removed: 0,
+                });
+            }
+            let point = growth.last_mut().unwrap();
+            point.changes += 1;
+
+            for op in &change.operations {
+                let (path, added, removed) = match op {
+                    Operation::FileWrite { path, content } => {
+                        let old = files.insert(path.clone(), content.clone());
+                        let (added, removed) = line_diff(old.as_deref().unwrap_or(&[]), content);
+                        (path, added, removed)
+                    }
+                    Operation::FileDelete { path } => match files.remove(path) {
+                        Some(old) => (path, 0, String::from_utf8_lossy(&old).lines().count()),
+                        None => continue,
+                    },
+                    Operation::NotebookCell { path, .. } | Operation::TextEdit { path, .. } => {
+                        let content = files.get_mut(path).ok_or_else(|| {
+                            edit_error(path, &change, anyhow::anyhow!("file does not exist"))
+                        })?;
+                        let updated = match op {
+                            Operation::NotebookCell { op, .. } => apply_cell_operation(content, op),
+                            Operation::TextEdit { op, .. } => apply_text_operation(content, op),
+                            _ => unreachable!(),
+                        }
+                        .map_err(|e| edit_error(path, &change, e))?;
+                        let (added, removed) = line_diff(content, &updated);
+                        *content = updated;
+                        (path, added, removed)
+                    }
+                    Operation::FileWriteRef { path, .. } => {
+                        return Err(edit_error(
+                            path,
+                            &change,
+                            anyhow::anyhow!("blob reference must be hydrated first"),
+                        ));
+                    }
+                    _ => continue,
+                };
+                point.lines_added += added;
+                point.lines_removed += removed;
+                lines = lines + added - removed;
+
+                let entry = churn.entry(path.clone()).or_insert_with(|| FileChurn {
+                    path: path.clone(),
+                    ..Default::default()
+                });
+                entry.changes += 1;
+                entry.lines_added += added;
+                entry.lines_removed += removed;
+            }
+
+            point.files = files.len();
+            point.lines = lines;
+        }
+
+        let mut stats = self.analyze_files(&files);
+        stats.growth = growth;
+        stats.churn = churn.into_values().collect();
+        stats
+            .churn
+            .sort_by(|a, b| b.changes.cmp(&a.changes).then_with(|| a.path.cmp(&b.path)));
+        Ok(stats)
+    }
+}
+
+/// 基于行多重集的增删行数（不考虑行顺序）
+fn line_diff(old: &[u8], new: &[u8]) -> (usize, usize) {
+    let old = String::from_utf8_lossy(old);
+    let new = String::from_utf8_lossy(new);
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for line in old.lines() {
+        *counts.entry(line).or_default() -= 1;
+    }
+    for line in new.lines() {
+        *counts.entry(line).or_default() += 1;
+    }
+    counts.values().fold((0, 0), |(added, removed), &n| {
+        if n > 0 {
+            (added + n as usize, removed)
+        } else {
+            (added, removed + n.unsigned_abs())
+        }
+    })
+}
+
+fn under(file: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    prefix.is_empty()
+        || file == prefix
+        || file
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::change::version::VectorClock;
+    use uuid::Uuid;
+
+    fn change(clock: &mut VectorClock, author: Uuid, operations: Vec<Operation>) -> Change {
+        clock.increment(author);
+        Change::new(author, operations, clock.clone(), vec![])
+    }
+
+    #[test]
+    fn test_analyze_history() {
+        let author = Uuid::new_v4();
+        let mut clock = VectorClock::new();
+        let changes = vec![
+            change(
+                &mut clock,
+                author,
+                vec![
+                    Operation::file_write(
+                        "src/main.rs".to_string(),
+                        b"fn main() {\n\n}\n".to_vec(),
+                    ),
+                    Operation::file_write("README.md".to_string(), b"# Demo\n".to_vec()),
+                ],
+            ),
+            change(
+                &mut clock,
+                author,
+                vec![Operation::file_write(
+                    "src/main.rs".to_string(),
+                    b"fn main() {\n    run();\n}\n".to_vec(),
+                )],
+            ),
+            change(
+                &mut clock,
+                author,
+                vec![Operation::file_delete("README.md".to_string())],
+            ),
+        ];
+
+        let stats = StatsAnalyzer::new().analyze_history(&changes).unwrap();
+        assert_eq!(stats.total_files, 1);
+        assert_eq!(stats.total_lines, 3);
+        assert_eq!(stats.languages.len(), 1);
+        assert_eq!(stats.languages[0].language, "Rust");
+        assert_eq!(stats.languages[0].code_lines, 3);
+
+        let point = stats.growth.last().unwrap();
+        assert_eq!(point.changes, 3);
+        assert_eq!(point.files, 1);
+        // 4 行初始写入 + 1 行修改；删除空行与 README
+        assert_eq!(point.lines_added, 5);
+        assert_eq!(point.lines_removed, 2);
+
+        let main = stats
+            .churn
+            .iter()
+            .find(|c| c.path == "src/main.rs")
+            .unwrap();
+        assert_eq!(
+            (main.changes, main.lines_added, main.lines_removed),
+            (2, 4, 1)
+        );
+        assert_eq!(stats.lines_under(&["src"]), 3);
+        assert_eq!(stats.estimate_task_size(&["src/"]), TaskSize::Small);
+    }
+}
@@ -1,11 +1,45 @@
 use crate::common::meta::MetaNode;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use uuid::Uuid;
 
+/// 定义在源码中的位置（行号从 1 开始，闭区间）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolLocation {
+    pub path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+impl SymbolLocation {
+    /// 是否与 `[start, end]` 行范围重叠
+    pub fn overlaps(&self, path: &str, start: u32, end: u32) -> bool {
+        self.path == path && self.start_line <= end && start <= self.end_line
+    }
+}
+
+/// 函数或类型定义
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Definition {
+    pub id: Uuid,
+    pub name: String,
+    /// 元数据 `path` / `start_line` / `end_line` 齐全时才有位置
+    pub location: Option<SymbolLocation>,
+    /// 元数据 `doc` 中的文档注释
+    pub doc: Option<String>,
+}
+
 /// 从元 AST 提取语义关系并填充图谱
+///
+/// 目前记录函数与类的定义，以及函数体内按名称调用的关系；
+/// 位置信息来自节点元数据 `start_line` / `end_line` 与所在模块的 `path`。
 pub struct GraphBuilder {
-    // 模拟图谱数据结构
     nodes: HashMap<Uuid, MetaNode>,
+    /// 名称 -> 定义（同名定义可能有多个）
+    definitions: BTreeMap<String, Vec<Definition>>,
+    /// 调用者名称 -> 被调用者名称
+    calls: BTreeMap<String, BTreeSet<String>>,
 }
 
 impl Default for GraphBuilder {
@@ -18,12 +52,14 @@ impl GraphBuilder {
     pub fn new() -> Self {
         Self {
             nodes: HashMap::new(),
+            definitions: BTreeMap::new(),
+            calls: BTreeMap::new(),
         }
     }
 
     /// 构建图谱
     pub fn build(&mut self, node: MetaNode) {
-        // Mock 构建逻辑：简单存储节点
+        self.collect(&node, None, None);
         self.nodes.insert(node.id(), node);
     }
 
@@ -31,11 +67,147 @@ impl GraphBuilder {
     pub fn node_count(&self) -> usize {
         self.nodes.len()
     }
+
+    /// 按名称查找定义
+    pub fn definitions(&self, name: &str) -> &[Definition] {
+        self.definitions.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// 与文件中行范围重叠的定义
+    pub fn definitions_in(&self, path: &str, start: u32, end: u32) -> Vec<&Definition> {
+        self.definitions
+            .values()
+            .flatten()
+            .filter(|d| {
+                d.location
+                    .as_ref()
+                    .is_some_and(|l| l.overlaps(path, start, end))
+            })
+            .collect()
+    }
+
+    /// `name` 调用的、图谱中有定义的符号
+    pub fn callees(&self, name: &str) -> Vec<&str> {
+        self.calls
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(|callee| self.definitions.contains_key(*callee))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// 调用了 `name` 的符号
+    pub fn callers(&self, name: &str) -> Vec<&str> {
+        self.calls
+            .iter()
+            .filter(|(_, callees)| callees.contains(name))
+            .map(|(caller, _)| caller.as_str())
+            .collect()
+    }
+
+    fn collect(&mut self, node: &MetaNode, path: Option<&str>, function: Option<&str>) {
+        match node {
+            MetaNode::Module {
+                children, metadata, ..
+            } => {
+                let path = metadata.get("path").and_then(Value::as_str).or(path);
+                for child in children {
+                    self.collect(child, path, function);
+                }
+            }
+            MetaNode::Function {
+                id,
+                name,
+                params,
+                body,
+                metadata,
+            } => {
+                self.define(*id, name, path, metadata);
+                self.calls.entry(name.clone()).or_default();
+                for child in params.iter().chain(body.as_deref()) {
+                    self.collect(child, path, Some(name));
+                }
+            }
+            MetaNode::Class {
+                id,
+                name,
+                members,
+                metadata,
+                ..
+            } => {
+                self.define(*id, name, path, metadata);
+                for member in members {
+                    self.collect(member, path, function);
+                }
+            }
+            MetaNode::Declaration { value, .. } => {
+                if let Some(value) = value {
+                    self.collect(value, path, function);
+                }
+            }
+            MetaNode::Assignment { target, value, .. } => {
+                self.collect(target, path, function);
+                self.collect(value, path, function);
+            }
+            MetaNode::Call { callee, args, .. } => {
+                if let (Some(caller), MetaNode::Identifier { name, .. }) =
+                    (function, callee.as_ref())
+                {
+                    self.calls
+                        .entry(caller.to_string())
+                        .or_default()
+                        .insert(name.clone());
+                }
+                self.collect(callee, path, function);
+                for arg in args {
+                    self.collect(arg, path, function);
+                }
+            }
+            MetaNode::Block { statements, .. } => {
+                for statement in statements {
+                    self.collect(statement, path, function);
+                }
+            }
+            MetaNode::Identifier { .. } | MetaNode::Literal { .. } | MetaNode::Extension { .. } => {
+            }
+        }
+    }
+
+    fn define(
+        &mut self,
+        id: Uuid,
+        name: &str,
+        path: Option<&str>,
+        metadata: &HashMap<String, Value>,
+    ) {
+        let line = |key: &str| metadata.get(key).and_then(Value::as_u64).map(|l| l as u32);
+        let location = match (path, line("start_line"), line("end_line")) {
+            (Some(path), Some(start_line), Some(end_line)) => Some(SymbolLocation {
+                path: path.to_string(),
+                start_line,
+                end_line,
+            }),
+            _ => None,
+        };
+        let definitions = self.definitions.entry(name.to_string()).or_default();
+        definitions.retain(|d| d.id != id);
+        definitions.push(Definition {
+            id,
+            name: name.to_string(),
+            location,
+            doc: metadata
+                .get("doc")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        });
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
 
     #[test]
     fn test_graph_builder() {
@@ -44,4 +216,40 @@ mod tests {
         builder.build(node);
         assert_eq!(builder.node_count(), 1);
     }
+
+    #[test]
+    fn test_call_graph_and_locations() {
+        let module: MetaNode = serde_json::from_value(json!({
+            "type": "module",
+            "name": "lib",
+            "path": "src/lib.rs",
+            "children": [
+                {"type": "function", "name": "main", "params": [],
+                 "start_line": 1, "end_line": 4,
+                 "body": {"type": "block", "statements": [
+                     {"type": "call", "callee": {"type": "identifier", "name": "parse"}, "args": []},
+                     {"type": "call", "callee": {"type": "identifier", "name": "println"}, "args": []}
+                 ]}},
+                {"type": "function", "name": "parse", "params": [],
+                 "start_line": 6, "end_line": 9, "doc": "Parses input.", "body": null}
+            ]
+        }))
+        .unwrap();
+        let mut graph = GraphBuilder::new();
+        graph.build(module);
+
+        assert_eq!(graph.callees("main"), vec!["parse"]);
+        assert_eq!(graph.callers("parse"), vec!["main"]);
+        let selected: Vec<&str> = graph
+            .definitions_in("src/lib.rs", 7, 12)
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(selected, vec!["parse"]);
+        assert_eq!(
+            graph.definitions("parse")[0].doc.as_deref(),
+            Some("Parses input.")
+        );
+        assert!(graph.definitions_in("src/main.rs", 1, 100).is_empty());
+    }
 }
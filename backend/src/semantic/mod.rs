@@ -2,6 +2,6 @@ pub mod graph;
 pub mod refactor;
 pub mod resolver;
 
-pub use graph::GraphBuilder;
+pub use graph::{Definition, GraphBuilder, SymbolLocation};
 pub use refactor::RefactorEngine;
 pub use resolver::SymbolResolver;
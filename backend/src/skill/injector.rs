@@ -1,5 +1,7 @@
+use crate::common::prompt::{Template, TemplateError};
 use crate::skill::registry::SkillRegistry;
 use crate::skill::traits::{Skill, SkillCategory};
+use serde_json::Value;
 use std::sync::Arc;
 
 /// 技能注入配置
@@ -57,6 +59,30 @@ impl SkillInjector {
         )
     }
 
+    /// 渲染提示词模板，相关技能填入 `{{skills}}` 变量（没有相关技能时为空字符串，
+    /// 可用 `{{#if skills}}` 包裹标题）；模板未引用 `skills` 时渲染后按 `inject_to_prompt` 追加
+    ///
+    /// `context` 须为 JSON 对象，其中的 `skills` 字段会被覆盖。
+    pub fn inject_into_template(
+        &self,
+        task: &str,
+        template: &Template,
+        context: &Value,
+    ) -> Result<String, TemplateError> {
+        if !template.references("skills") {
+            return Ok(self.inject_to_prompt(task, &template.render(context)?));
+        }
+        let skills = self.find_relevant_skills(task);
+        let mut context = context.clone();
+        if let Value::Object(map) = &mut context {
+            map.insert(
+                "skills".to_string(),
+                Value::String(self.format_skills(&skills)),
+            );
+        }
+        template.render(&context)
+    }
+
     /// 为任务查找相关技能
     pub fn find_relevant_skills(&self, task: &str) -> Vec<Arc<Skill>> {
         let category = self.infer_category(task);
@@ -253,6 +279,47 @@ mod tests {
         assert!(result.contains("Test Skill"));
     }
 
+    #[test]
+    fn test_inject_into_template_slot() {
+        let mut registry = SkillRegistry::new();
+        registry
+            .register(create_test_skill(
+                "Test Skill",
+                "A test skill",
+                "Test content",
+                SkillCategory::new("Syntax"),
+            ))
+            .unwrap();
+        let injector = SkillInjector::new(registry);
+        let template = Template::parse(
+            "You are a {{role}}.\n{{#if skills}}\n## Skills\n{{skills}}\n{{/if}}\nTask: {{task}}",
+        )
+        .unwrap();
+        let context = serde_json::json!({"role": "parser expert", "task": "parse"});
+
+        let result = injector
+            .inject_into_template("Parse syntax tree", &template, &context)
+            .unwrap();
+        assert!(result.starts_with("You are a parser expert.\n## Skills\n### Test Skill"));
+        assert!(result.ends_with("\nTask: parse"));
+
+        // 没有相关技能时整个段落被省略
+        let empty = SkillInjector::new(SkillRegistry::new());
+        assert_eq!(
+            empty
+                .inject_into_template("Parse syntax tree", &template, &context)
+                .unwrap(),
+            "You are a parser expert.\nTask: parse"
+        );
+
+        // 未预留 {{skills}} 时追加到末尾
+        let plain = Template::parse("Base {{role}}").unwrap();
+        let result = injector
+            .inject_into_template("Parse syntax tree", &plain, &context)
+            .unwrap();
+        assert!(result.starts_with("Base parser expert\n\n## Relevant Skills"));
+    }
+
     #[test]
     fn test_format_skill() {
         let skill = create_test_skill(
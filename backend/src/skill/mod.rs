@@ -6,7 +6,14 @@
 
 pub mod injector;
 pub mod loader;
+pub mod patch;
+pub mod plugin;
+pub mod prompt;
 pub mod registry;
+pub mod rewrite;
+pub mod sandbox;
+pub mod semantic;
 pub mod state;
+pub mod store;
 pub mod tool;
 pub mod traits;
@@ -0,0 +1,540 @@
+use serde::{Deserialize, Serialize};
+
+/// 模糊匹配时最多丢弃的首尾上下文行数（与 GNU patch 的 fuzz 因子含义相同）
+const MAX_FUZZ: usize = 2;
+
+/// 补丁块中的一行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+/// 一个补丁块（`@@ -a,b +c,d @@` 之后的内容）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// 声明的原文件起始行（从 1 开始，0 表示未知或空文件）
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// 需要在原文件中匹配的行（上下文 + 删除）
+    pub fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    /// 去掉首尾各 `fuzz` 行上下文后的块（首尾不足 `fuzz` 行上下文时返回 `None`）
+    fn trimmed(&self, fuzz: usize) -> Option<Hunk> {
+        let is_context = |l: &HunkLine| matches!(l, HunkLine::Context(_));
+        let leading = self.lines.iter().take_while(|l| is_context(l)).count();
+        let trailing = self
+            .lines
+            .iter()
+            .rev()
+            .take_while(|l| is_context(l))
+            .count();
+        if leading < fuzz || trailing < fuzz || leading + trailing >= self.lines.len() {
+            return None;
+        }
+        Some(Hunk {
+            old_start: self.old_start + fuzz,
+            lines: self.lines[fuzz..self.lines.len() - fuzz].to_vec(),
+        })
+    }
+}
+
+/// 单个文件的补丁
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatch {
+    /// 原路径（新建文件时为 `None`）
+    pub old_path: Option<String>,
+    /// 新路径（删除文件时为 `None`）
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FilePatch {
+    /// 补丁作用的路径
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+
+    pub fn is_creation(&self) -> bool {
+        self.old_path.is_none()
+    }
+
+    pub fn is_deletion(&self) -> bool {
+        self.new_path.is_none()
+    }
+
+    /// 新旧路径不同（重命名或移动）
+    pub fn is_rename(&self) -> bool {
+        matches!((&self.old_path, &self.new_path), (Some(old), Some(new)) if old != new)
+    }
+}
+
+/// 补丁块最终采用的定位方式
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MatchStrategy {
+    /// 在声明的行号处精确匹配
+    Exact,
+    /// 按上下文重新定位（行号偏移 `offset`）
+    Relocated { offset: isize },
+    /// 忽略空白差异后匹配
+    WhitespaceInsensitive { offset: isize },
+    /// 丢弃首尾各 `fuzz` 行上下文后匹配
+    Fuzzy { offset: isize, fuzz: usize },
+}
+
+/// 成功应用的补丁块
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedHunk {
+    pub index: usize,
+    /// 实际应用位置（新文件中的起始行，从 1 开始）
+    pub line: usize,
+    pub strategy: MatchStrategy,
+}
+
+/// 与补丁块最接近的原文位置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClosestMatch {
+    /// 起始行（从 1 开始）
+    pub line: usize,
+    /// 忽略空白后相同的行所占比例
+    pub similarity: f32,
+    /// 该位置的实际内容，供修复补丁参考
+    pub actual: Vec<String>,
+}
+
+/// 无法应用的补丁块
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HunkFailure {
+    pub index: usize,
+    /// 补丁声明的起始行
+    pub expected_line: usize,
+    /// 补丁期望的原文内容
+    pub expected: Vec<String>,
+    pub closest: Option<ClosestMatch>,
+}
+
+/// 补丁错误
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PatchError {
+    #[error("malformed patch at line {line}: {message}")]
+    Parse { line: usize, message: String },
+
+    #[error("{} of the hunks failed to apply to {path}", failures.len())]
+    Conflict {
+        path: String,
+        failures: Vec<HunkFailure>,
+    },
+}
+
+/// 应用结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patched {
+    pub content: String,
+    pub hunks: Vec<AppliedHunk>,
+}
+
+/// 解析统一差异格式（unified diff）
+///
+/// 对模型生成的补丁尽量宽容：忽略块头中的行数、接受缺少前导空格的空上下文行，
+/// 也接受不带行号的 `@@ @@` 块头。
+pub fn parse_unified_diff(text: &str) -> Result<Vec<FilePatch>, PatchError> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if line.starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ ")) {
+            patches.push(FilePatch {
+                old_path: header_path(&line[4..]),
+                new_path: header_path(&lines[i + 1][4..]),
+                hunks: Vec::new(),
+            });
+            i += 2;
+        } else if line.starts_with("@@") {
+            let Some(patch) = patches.last_mut() else {
+                return Err(PatchError::Parse {
+                    line: i + 1,
+                    message: "hunk without file header".to_string(),
+                });
+            };
+            let old_start = parse_hunk_header(line).ok_or_else(|| PatchError::Parse {
+                line: i + 1,
+                message: format!("invalid hunk header '{}'", line),
+            })?;
+            i += 1;
+
+            let mut hunk = Hunk {
+                old_start,
+                lines: Vec::new(),
+            };
+            while i < lines.len() && !is_boundary(&lines, i) {
+                let body = lines[i];
+                match body.chars().next() {
+                    Some('+') => hunk.lines.push(HunkLine::Add(body[1..].to_string())),
+                    Some('-') => hunk.lines.push(HunkLine::Remove(body[1..].to_string())),
+                    Some(' ') => hunk.lines.push(HunkLine::Context(body[1..].to_string())),
+                    None => hunk.lines.push(HunkLine::Context(String::new())),
+                    Some('\\') => {} // "\ No newline at end of file"
+                    Some(_) => {
+                        return Err(PatchError::Parse {
+                            line: i + 1,
+                            message: format!("unexpected line in hunk '{}'", body),
+                        });
+                    }
+                }
+                i += 1;
+            }
+            // 补丁末尾的空行通常是复制时带入的，而非真实上下文
+            while hunk.lines.last() == Some(&HunkLine::Context(String::new())) {
+                hunk.lines.pop();
+            }
+            patch.hunks.push(hunk);
+        } else {
+            // `diff --git`、`index` 等元数据行
+            i += 1;
+        }
+    }
+
+    if patches.is_empty() {
+        return Err(PatchError::Parse {
+            line: 1,
+            message: "no file headers found".to_string(),
+        });
+    }
+    Ok(patches)
+}
+
+/// 将补丁块应用到文本
+///
+/// 每个补丁块依次尝试：声明行号处精确匹配 → 按上下文重新定位 → 忽略空白匹配 →
+/// 丢弃首尾上下文的模糊匹配。任一补丁块失败时不修改内容，并返回每个失败块的最接近位置。
+pub fn apply_hunks(original: &str, path: &str, hunks: &[Hunk]) -> Result<Patched, PatchError> {
+    let newline = if original.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let trailing_newline = original.is_empty() || original.ends_with('\n');
+    let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+
+    let mut applied = Vec::new();
+    let mut failures = Vec::new();
+    // 之前的补丁块造成的行数变化 + 最近一次重新定位的偏移
+    let mut delta: isize = 0;
+    let mut drift: isize = 0;
+    // 补丁块按顺序作用，不允许回到已修改区域之前
+    let mut floor = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let declared = hunk.old_start.saturating_sub(1) as isize;
+        let expected = (declared + delta + drift).clamp(0, lines.len() as isize) as usize;
+
+        match locate(&lines, hunk, expected, floor) {
+            Some((pos, hunk, strategy)) => {
+                let old_len = hunk.old_lines().len();
+                let mut replacement = Vec::with_capacity(hunk.lines.len());
+                let mut cursor = pos;
+                for line in &hunk.lines {
+                    match line {
+                        // 保留文件中的实际内容（空白不敏感匹配时可能与补丁不同）
+                        HunkLine::Context(_) => {
+                            replacement.push(lines[cursor].clone());
+                            cursor += 1;
+                        }
+                        HunkLine::Remove(_) => cursor += 1,
+                        HunkLine::Add(s) => replacement.push(s.clone()),
+                    }
+                }
+                let new_len = replacement.len();
+                lines.splice(pos..pos + old_len, replacement);
+
+                let declared = hunk.old_start.saturating_sub(1) as isize;
+                drift = pos as isize - (declared + delta);
+                delta += new_len as isize - old_len as isize;
+                floor = pos + new_len;
+                applied.push(AppliedHunk {
+                    index,
+                    line: pos + 1,
+                    strategy,
+                });
+            }
+            None => failures.push(HunkFailure {
+                index,
+                expected_line: hunk.old_start,
+                expected: hunk.old_lines().into_iter().map(str::to_string).collect(),
+                closest: closest_match(&lines, &hunk.old_lines(), expected),
+            }),
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(PatchError::Conflict {
+            path: path.to_string(),
+            failures,
+        });
+    }
+
+    let mut content = lines.join(newline);
+    if trailing_newline && !content.is_empty() {
+        content.push_str(newline);
+    }
+    Ok(Patched {
+        content,
+        hunks: applied,
+    })
+}
+
+/// 依次使用各策略定位补丁块，返回位置、实际使用的（可能已裁剪的）补丁块和策略
+fn locate(
+    lines: &[String],
+    hunk: &Hunk,
+    expected: usize,
+    floor: usize,
+) -> Option<(usize, Hunk, MatchStrategy)> {
+    let offset = |pos: usize| pos as isize - expected as isize;
+    let old = hunk.old_lines();
+
+    // 纯插入块：没有可匹配的内容，直接在预期位置插入
+    if old.is_empty() {
+        let pos = expected.max(floor).min(lines.len());
+        return Some((pos, hunk.clone(), MatchStrategy::Exact));
+    }
+
+    if expected >= floor && matches_at(lines, &old, expected, exact) {
+        return Some((expected, hunk.clone(), MatchStrategy::Exact));
+    }
+    if let Some(pos) = nearest(lines, &old, expected, floor, exact) {
+        let strategy = MatchStrategy::Relocated {
+            offset: offset(pos),
+        };
+        return Some((pos, hunk.clone(), strategy));
+    }
+    if let Some(pos) = nearest(lines, &old, expected, floor, loose) {
+        let strategy = MatchStrategy::WhitespaceInsensitive {
+            offset: offset(pos),
+        };
+        return Some((pos, hunk.clone(), strategy));
+    }
+    for fuzz in 1..=MAX_FUZZ {
+        let Some(trimmed) = hunk.trimmed(fuzz) else {
+            break;
+        };
+        let expected = expected + fuzz;
+        if let Some(pos) = nearest(lines, &trimmed.old_lines(), expected, floor, loose) {
+            let strategy = MatchStrategy::Fuzzy {
+                offset: pos as isize - expected as isize,
+                fuzz,
+            };
+            return Some((pos, trimmed, strategy));
+        }
+    }
+    None
+}
+
+/// 在 `floor` 之后寻找距 `expected` 最近的匹配位置
+fn nearest(
+    lines: &[String],
+    old: &[&str],
+    expected: usize,
+    floor: usize,
+    eq: fn(&str, &str) -> bool,
+) -> Option<usize> {
+    let last = lines.len().checked_sub(old.len())?;
+    (floor..=last)
+        .filter(|&pos| matches_at(lines, old, pos, eq))
+        .min_by_key(|&pos| pos.abs_diff(expected))
+}
+
+fn matches_at(lines: &[String], old: &[&str], pos: usize, eq: fn(&str, &str) -> bool) -> bool {
+    pos + old.len() <= lines.len()
+        && old
+            .iter()
+            .zip(&lines[pos..])
+            .all(|(expected, actual)| eq(expected, actual))
+}
+
+fn exact(a: &str, b: &str) -> bool {
+    a == b
+}
+
+/// 忽略空白数量与首尾空白的比较
+fn loose(a: &str, b: &str) -> bool {
+    a.split_whitespace().eq(b.split_whitespace())
+}
+
+/// 忽略空白后相同行最多的窗口（同分时取距离 `expected` 最近者）
+fn closest_match(lines: &[String], old: &[&str], expected: usize) -> Option<ClosestMatch> {
+    if old.is_empty() || lines.is_empty() {
+        return None;
+    }
+    let window = old.len().min(lines.len());
+    let (pos, score) = (0..=lines.len() - window)
+        .map(|pos| {
+            let same = old
+                .iter()
+                .zip(&lines[pos..pos + window])
+                .filter(|(a, b)| loose(a, b))
+                .count();
+            (pos, same)
+        })
+        .max_by(|(pa, sa), (pb, sb)| {
+            sa.cmp(sb)
+                .then_with(|| pb.abs_diff(expected).cmp(&pa.abs_diff(expected)))
+        })?;
+    if score == 0 {
+        return None;
+    }
+    Some(ClosestMatch {
+        line: pos + 1,
+        similarity: score as f32 / old.len() as f32,
+        actual: lines[pos..pos + window].to_vec(),
+    })
+}
+
+/// 解析 `--- a/path\t时间戳` 形式的文件头，`/dev/null` 返回 `None`
+fn header_path(raw: &str) -> Option<String> {
+    let path = raw.split('\t').next().unwrap_or_default().trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// 解析块头中的原文件起始行，`@@ @@` 返回 0
+fn parse_hunk_header(line: &str) -> Option<usize> {
+    let inner = line.strip_prefix("@@")?;
+    let inner = &inner[..inner.find("@@")?];
+    let Some(old) = inner.split_whitespace().find(|s| s.starts_with('-')) else {
+        return inner.trim().is_empty().then_some(0);
+    };
+    old[1..].split(',').next()?.parse().ok()
+}
+
+/// 是否为下一个块或下一个文件的开始
+fn is_boundary(lines: &[&str], i: usize) -> bool {
+    let line = lines[i];
+    line.starts_with("@@")
+        || line.starts_with("diff ")
+        || (line.starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str =
+        "fn main() {\n    let a = 1;\n    let b = 2;\n    println!(\"{}\", a + b);\n}\n";
+
+    fn single_hunk(diff: &str) -> Vec<Hunk> {
+        parse_unified_diff(diff).unwrap().remove(0).hunks
+    }
+
+    #[test]
+    fn test_parse_and_apply_exact() {
+        let diff = "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -2,2 +2,2 @@\n     let a = 1;\n-    let b = 2;\n+    let b = 3;\n";
+        let patches = parse_unified_diff(diff).unwrap();
+        assert_eq!(patches[0].path(), "src/main.rs");
+
+        let patched = apply_hunks(ORIGINAL, "src/main.rs", &patches[0].hunks).unwrap();
+        assert!(patched.content.contains("let b = 3;"));
+        assert!(patched.content.ends_with("}\n"));
+        assert_eq!(patched.hunks[0].strategy, MatchStrategy::Exact);
+    }
+
+    #[test]
+    fn test_relocates_wrong_line_numbers_and_whitespace() {
+        // 行号错误
+        let hunks = single_hunk(
+            "--- a/m.rs\n+++ b/m.rs\n@@ -40,2 +40,2 @@\n     let a = 1;\n-    let b = 2;\n+    let b = 3;\n",
+        );
+        let patched = apply_hunks(ORIGINAL, "m.rs", &hunks).unwrap();
+        assert!(matches!(
+            patched.hunks[0].strategy,
+            MatchStrategy::Relocated { .. }
+        ));
+
+        // 缩进错误（模型常把 4 空格写成 2 空格）
+        let hunks = single_hunk(
+            "--- a/m.rs\n+++ b/m.rs\n@@ -2,2 +2,2 @@\n   let a = 1;\n-  let b = 2;\n+    let b = 3;\n",
+        );
+        let patched = apply_hunks(ORIGINAL, "m.rs", &hunks).unwrap();
+        assert!(matches!(
+            patched.hunks[0].strategy,
+            MatchStrategy::WhitespaceInsensitive { .. }
+        ));
+        // 上下文行保留文件中的原始缩进
+        assert!(
+            patched
+                .content
+                .contains("\n    let a = 1;\n    let b = 3;\n")
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_drops_stale_context() {
+        let hunks = single_hunk(
+            "--- a/m.rs\n+++ b/m.rs\n@@ -1,4 +1,4 @@\n fn main() -> Result<()> {\n     let a = 1;\n-    let b = 2;\n+    let b = 3;\n     println!(\"{}\", a + b);\n",
+        );
+        let patched = apply_hunks(ORIGINAL, "m.rs", &hunks).unwrap();
+        assert!(matches!(
+            patched.hunks[0].strategy,
+            MatchStrategy::Fuzzy { fuzz: 1, .. }
+        ));
+        assert!(patched.content.starts_with("fn main() {\n"));
+        assert!(patched.content.contains("let b = 3;"));
+    }
+
+    #[test]
+    fn test_failure_reports_closest_match() {
+        let hunks = single_hunk(
+            "--- a/m.rs\n+++ b/m.rs\n@@ -2,3 +2,3 @@\n     let a = 1;\n-    let b = 20;\n+    let b = 3;\n     let c = 4;\n",
+        );
+        let err = apply_hunks(ORIGINAL, "m.rs", &hunks).unwrap_err();
+        let PatchError::Conflict { failures, .. } = err else {
+            panic!("expected conflict");
+        };
+        let closest = failures[0].closest.as_ref().unwrap();
+        assert_eq!(closest.line, 2);
+        assert!(closest.similarity > 0.3 && closest.similarity < 0.4);
+        assert_eq!(closest.actual[1], "    let b = 2;");
+    }
+
+    #[test]
+    fn test_creation_and_header_without_numbers() {
+        let patches =
+            parse_unified_diff("--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+hello\n+world\n")
+                .unwrap();
+        assert!(patches[0].is_creation());
+        let patched = apply_hunks("", "new.txt", &patches[0].hunks).unwrap();
+        assert_eq!(patched.content, "hello\nworld\n");
+
+        let hunks = single_hunk("--- m.rs\n+++ m.rs\n@@ @@\n-    let b = 2;\n+    let b = 3;\n");
+        assert!(
+            apply_hunks(ORIGINAL, "m.rs", &hunks)
+                .unwrap()
+                .content
+                .contains("let b = 3;")
+        );
+    }
+}
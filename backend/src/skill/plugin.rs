@@ -0,0 +1,285 @@
+use crate::common::meta::PluginManifest;
+use crate::common::provider::traits::StorageProvider;
+use crate::skill::loader::SkillLoader;
+use crate::skill::prompt::{PromptLibrary, PromptTemplate};
+use crate::skill::registry::SkillRegistry;
+use crate::skill::traits::{SkillError, SkillId};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// 一个插件向技能注册表与模板库贡献的内容
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginContribution {
+    pub plugin_id: String,
+    pub skills: Vec<SkillId>,
+    pub prompts: Vec<String>,
+}
+
+/// 跟踪各插件贡献的技能与提示词模板，支持随插件加载/卸载
+#[derive(Debug, Clone, Default)]
+pub struct PluginContributions {
+    loaded: HashMap<String, PluginContribution>,
+}
+
+impl PluginContributions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, plugin_id: &str) -> Option<&PluginContribution> {
+        self.loaded.get(plugin_id)
+    }
+
+    /// 读取清单中的技能文件与模板，以插件 ID 为命名空间注册
+    ///
+    /// 所有文件读取并校验成功后才会注册，失败时注册表与模板库保持不变。
+    /// 重复加载同一插件时以新版本替换旧版本的内容；清单中的路径不能是绝对路径或含 `..`。
+    pub async fn load(
+        &mut self,
+        manifest: &PluginManifest,
+        root: &str,
+        storage: Arc<dyn StorageProvider>,
+        registry: &mut SkillRegistry,
+        prompts: &mut PromptLibrary,
+    ) -> Result<PluginContribution, SkillError> {
+        let loader = SkillLoader::new(storage.clone());
+        let mut skills = Vec::new();
+        for file in &manifest.skills {
+            for mut skill in loader.load_from_file(&resolve(root, file)?).await? {
+                skill.id.name = manifest.namespaced(&skill.id.name);
+                skill.validate()?;
+                skills.push(skill);
+            }
+        }
+
+        let mut templates = Vec::new();
+        for (name, file) in &manifest.prompts {
+            let path = resolve(root, file)?;
+            let bytes = storage
+                .read_file(&path.to_string_lossy())
+                .await
+                .map_err(|e| SkillError::ParseError(format!("Failed to read prompt: {}", e)))?;
+            let content = String::from_utf8(bytes)
+                .map_err(|e| SkillError::ParseError(format!("Prompt is not valid UTF-8: {}", e)))?;
+            templates.push(PromptTemplate {
+                name: manifest.namespaced(name),
+                content,
+                plugin: Some(manifest.id.clone()),
+            });
+        }
+
+        let contribution = PluginContribution {
+            plugin_id: manifest.id.clone(),
+            skills: skills.iter().map(|s| s.id.clone()).collect(),
+            prompts: templates.iter().map(|t| t.name.clone()).collect(),
+        };
+        // 在副本上替换旧版本的技能，注册失败时旧版本保持可用
+        let mut staged = registry.clone();
+        if let Some(old) = self.loaded.get(&manifest.id) {
+            for id in &old.skills {
+                staged.unregister(id);
+            }
+        }
+        staged.register_all(skills)?;
+        *registry = staged;
+        if let Some(old) = self.loaded.remove(&manifest.id) {
+            for name in &old.prompts {
+                prompts.remove(name);
+            }
+        }
+        for template in templates {
+            prompts.register(template);
+        }
+        self.loaded
+            .insert(manifest.id.clone(), contribution.clone());
+        Ok(contribution)
+    }
+
+    /// 移除插件贡献的全部技能与模板
+    pub fn unload(
+        &mut self,
+        plugin_id: &str,
+        registry: &mut SkillRegistry,
+        prompts: &mut PromptLibrary,
+    ) -> Option<PluginContribution> {
+        let contribution = self.loaded.remove(plugin_id)?;
+        for id in &contribution.skills {
+            registry.unregister(id);
+        }
+        for name in &contribution.prompts {
+            prompts.remove(name);
+        }
+        Some(contribution)
+    }
+}
+
+/// 将清单中的相对路径拼接到插件目录下，拒绝绝对路径与 `..` 等越出插件目录的路径
+fn resolve(root: &str, file: &str) -> Result<PathBuf, SkillError> {
+    let relative = Path::new(file);
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(SkillError::InvalidSkill(format!(
+            "Plugin file escapes the plugin directory: {}",
+            file
+        )));
+    }
+    Ok(Path::new(root).join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::skill::traits::SkillCategory;
+
+    const SKILL_YAML: &str = r#"
+id:
+  category: Syntax
+  name: clippy-lints
+  language: Rust
+name: Clippy lints
+description: Common clippy fixes
+content: Prefer is_empty() over len() == 0
+metadata:
+  language: Rust
+  version: "1.0"
+  tags: [lint]
+"#;
+
+    #[tokio::test]
+    async fn test_plugin_contributions_are_namespaced_and_removed_on_unload() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        storage
+            .write_file(
+                "plugins/rust-extras/skills/clippy.yaml",
+                SKILL_YAML.as_bytes(),
+            )
+            .await
+            .unwrap();
+        storage
+            .write_file("plugins/rust-extras/prompts/review.md", b"Review {{file}}")
+            .await
+            .unwrap();
+        let manifest = PluginManifest::from_yaml(
+            "id: rust-extras\nversion: 0.1.0\nskills: [skills/clippy.yaml]\nprompts:\n  review: prompts/review.md\n",
+        )
+        .unwrap();
+
+        // 用户自定义的同名技能不受插件影响
+        let mut registry = SkillRegistry::new();
+        let mut user_skill = SkillLoader::load_from_yaml(SKILL_YAML).unwrap().remove(0);
+        user_skill.description = "user version".to_string();
+        let user_id = user_skill.id.clone();
+        registry.register(user_skill).unwrap();
+        let mut prompts = PromptLibrary::new();
+        let mut plugins = PluginContributions::new();
+
+        let contribution = plugins
+            .load(
+                &manifest,
+                "plugins/rust-extras",
+                storage.clone(),
+                &mut registry,
+                &mut prompts,
+            )
+            .await
+            .unwrap();
+        let plugin_id = SkillId::new(
+            SkillCategory::new("Syntax"),
+            "rust-extras:clippy-lints",
+            "Rust",
+        );
+        assert_eq!(contribution.skills, vec![plugin_id.clone()]);
+        assert_eq!(registry.count(), 2);
+        assert_eq!(
+            prompts.get("rust-extras:review").unwrap().content,
+            "Review {{file}}"
+        );
+
+        // 重新加载不会产生重复
+        plugins
+            .load(
+                &manifest,
+                "plugins/rust-extras",
+                storage,
+                &mut registry,
+                &mut prompts,
+            )
+            .await
+            .unwrap();
+        assert_eq!(registry.count(), 2);
+
+        plugins.unload("rust-extras", &mut registry, &mut prompts);
+        assert!(!registry.contains(&plugin_id));
+        assert_eq!(registry.get(&user_id).unwrap().description, "user version");
+        assert!(prompts.list().is_empty());
+        assert!(plugins.get("rust-extras").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_load_leaves_registry_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        storage
+            .write_file("p/skills/ok.yaml", SKILL_YAML.as_bytes())
+            .await
+            .unwrap();
+        let manifest = PluginManifest::from_yaml(
+            "id: broken\nversion: 0.1.0\nskills: [skills/ok.yaml]\nprompts:\n  missing: prompts/missing.md\n",
+        )
+        .unwrap();
+        let mut registry = SkillRegistry::new();
+        let mut prompts = PromptLibrary::new();
+        let result = PluginContributions::new()
+            .load(&manifest, "p", storage, &mut registry, &mut prompts)
+            .await;
+        assert!(matches!(result, Err(SkillError::ParseError(_))));
+        assert_eq!(registry.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_escaping_paths_are_rejected_and_keep_the_old_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        storage
+            .write_file("p/skills/ok.yaml", SKILL_YAML.as_bytes())
+            .await
+            .unwrap();
+        storage
+            .write_file("secret.yaml", SKILL_YAML.as_bytes())
+            .await
+            .unwrap();
+        let mut registry = SkillRegistry::new();
+        let mut prompts = PromptLibrary::new();
+        let mut plugins = PluginContributions::new();
+        let v1 =
+            PluginManifest::from_yaml("id: p\nversion: 0.1.0\nskills: [skills/ok.yaml]\n").unwrap();
+        let loaded = plugins
+            .load(&v1, "p", storage.clone(), &mut registry, &mut prompts)
+            .await
+            .unwrap();
+
+        for file in ["../secret.yaml", "skills/../../secret.yaml", "/secret.yaml"] {
+            let v2 = PluginManifest::from_yaml(&format!(
+                "id: p\nversion: 0.2.0\nskills: [\"{}\"]\n",
+                file
+            ))
+            .unwrap();
+            let result = plugins
+                .load(&v2, "p", storage.clone(), &mut registry, &mut prompts)
+                .await;
+            assert!(
+                matches!(result, Err(SkillError::InvalidSkill(_))),
+                "{}",
+                file
+            );
+        }
+        // 升级失败后旧版本仍然加载
+        assert_eq!(plugins.get("p"), Some(&loaded));
+        assert!(registry.get(&loaded.skills[0]).is_some());
+    }
+}
@@ -0,0 +1,55 @@
+use crate::common::prompt::{Template, TemplateError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 具名的提示词模板
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    /// 模板名称；插件贡献的模板为 `<插件 ID>:<名称>`
+    pub name: String,
+    pub content: String,
+    /// 贡献该模板的插件（用户模板为 `None`）
+    #[serde(default)]
+    pub plugin: Option<String>,
+}
+
+impl PromptTemplate {
+    /// 解析为可渲染的模板
+    pub fn compile(&self) -> Result<Template, TemplateError> {
+        Template::parse(&self.content)
+    }
+}
+
+/// 提示词模板库
+#[derive(Debug, Clone, Default)]
+pub struct PromptLibrary {
+    templates: BTreeMap<String, PromptTemplate>,
+}
+
+impl PromptLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册（或替换）模板
+    pub fn register(&mut self, template: PromptTemplate) {
+        self.templates.insert(template.name.clone(), template);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PromptTemplate> {
+        self.templates.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PromptTemplate> {
+        self.templates.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.templates.contains_key(name)
+    }
+
+    /// 按名称排序列出全部模板
+    pub fn list(&self) -> Vec<&PromptTemplate> {
+        self.templates.values().collect()
+    }
+}
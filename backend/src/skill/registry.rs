@@ -34,6 +34,9 @@ impl SkillRegistry {
         let id = skill.id.clone();
         let skill = Arc::new(skill);
 
+        // 替换同 ID 的旧技能，避免索引中残留旧条目
+        self.unregister(&id);
+
         // 插入主存储
         self.skills.insert(id.clone(), skill.clone());
 
@@ -71,6 +74,15 @@ impl SkillRegistry {
         Ok(())
     }
 
+    /// 移除技能并更新所有索引
+    pub fn unregister(&mut self, id: &SkillId) -> Option<Arc<Skill>> {
+        let skill = self.skills.remove(id)?;
+        remove_from_index(&mut self.by_category, &skill);
+        remove_from_index(&mut self.by_language, &skill);
+        remove_from_index(&mut self.by_tag, &skill);
+        Some(skill)
+    }
+
     /// Get a skill by its ID
     pub fn get(&self, id: &SkillId) -> Option<Arc<Skill>> {
         self.skills.get(id).cloned()
@@ -91,8 +103,9 @@ impl SkillRegistry {
         self.by_tag.get(tag).cloned().unwrap_or_default()
     }
 
-    /// 根据任务描述查找相关技能
-    /// 这是简化版本 - 在生产环境中，应使用向量嵌入进行语义搜索
+    /// 根据任务描述按关键字评分查找相关技能
+    ///
+    /// 语义检索见 `semantic::SkillIndex`，嵌入接口不可用时以此为后备。
     pub fn find_relevant(
         &self,
         task: &str,
@@ -121,7 +134,7 @@ impl SkillRegistry {
             .collect();
 
         // 按相关性分数降序排序
-        scored.sort_by(|a, b| b.0.cmp(&a.0));
+        scored.sort_by_key(|b| std::cmp::Reverse(b.0));
 
         // 取前 N 个
         scored
@@ -147,6 +160,14 @@ impl SkillRegistry {
     }
 }
 
+/// 从二级索引中移除技能，并清理空的索引项
+fn remove_from_index<K>(index: &mut HashMap<K, Vec<Arc<Skill>>>, skill: &Arc<Skill>) {
+    index.retain(|_, skills| {
+        skills.retain(|s| !Arc::ptr_eq(s, skill));
+        !skills.is_empty()
+    });
+}
+
 /// 计算技能与任务的相关性分数
 fn calculate_relevance(skill: &Skill, task: &str) -> usize {
     let mut score = 0;
@@ -210,6 +231,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_unregister_and_replace_update_indexes() {
+        let mut registry = SkillRegistry::new();
+        let skill = create_test_skill(SkillCategory::new("Syntax"), "a", "Rust", vec!["x"]);
+        let id = skill.id.clone();
+        registry.register(skill.clone()).unwrap();
+        registry.register(skill).unwrap();
+        assert_eq!(registry.by_tag("x").len(), 1);
+
+        assert!(registry.unregister(&id).is_some());
+        assert!(registry.unregister(&id).is_none());
+        assert_eq!(registry.count(), 0);
+        assert!(registry.by_language("Rust").is_empty());
+        assert!(registry.by_tag("x").is_empty());
+    }
+
     #[test]
     fn test_register_and_retrieve() {
         let mut registry = SkillRegistry::new();
@@ -0,0 +1,428 @@
+use crate::common::change::operation::Operation;
+use crate::common::change::threeway::line_edits;
+use crate::common::provider::traits::StorageProvider;
+use crate::skill::sandbox::ToolEffects;
+use crate::skill::tool::{Tool, ToolOutput};
+use crate::skill::traits::SkillError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// 整文件重写的最小化策略
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RewritePolicy {
+    /// 允许改动的原文件行数比例，超过时拒绝整份重写
+    pub max_churn_ratio: f32,
+    /// 原文件少于该行数时不做比例限制
+    pub min_lines: usize,
+    /// 是否保留仅有空白差异的改动块（默认丢弃，视为无关格式化）
+    pub keep_whitespace_changes: bool,
+}
+
+impl Default for RewritePolicy {
+    fn default() -> Self {
+        Self {
+            max_churn_ratio: 0.5,
+            min_lines: 20,
+            keep_whitespace_changes: false,
+        }
+    }
+}
+
+/// 相对原文件的一处改动
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RewriteHunk {
+    /// 原文件中的起始行（从 1 开始）
+    pub line: usize,
+    pub removed: Vec<String>,
+    pub inserted: Vec<String>,
+    /// 仅有空白差异
+    pub whitespace_only: bool,
+}
+
+impl RewriteHunk {
+    /// 改动涉及的行数
+    pub fn size(&self) -> usize {
+        self.removed.len().max(self.inserted.len())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RewriteError {
+    #[error(
+        "rewrite of {path} changes {changed} of {total} lines ({:.0}%), above the allowed {:.0}%; edit only the lines the task requires",
+        *changed as f32 / *total as f32 * 100.0,
+        max_ratio * 100.0
+    )]
+    TooMuchChurn {
+        path: String,
+        changed: usize,
+        total: usize,
+        max_ratio: f32,
+    },
+}
+
+/// 最小化后的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MinimizedRewrite {
+    pub path: String,
+    /// 被采纳的改动
+    pub kept: Vec<RewriteHunk>,
+    /// 被丢弃的无关改动
+    pub dropped: Vec<RewriteHunk>,
+    /// 只应用被采纳改动后的文件内容
+    pub content: String,
+}
+
+impl MinimizedRewrite {
+    pub fn is_unchanged(&self) -> bool {
+        self.kept.is_empty()
+    }
+
+    /// 表达被采纳改动的操作（没有改动时为空）
+    pub fn operations(&self) -> Vec<Operation> {
+        if self.is_unchanged() {
+            return Vec::new();
+        }
+        vec![Operation::file_write(
+            self.path.clone(),
+            self.content.clone().into_bytes(),
+        )]
+    }
+}
+
+impl RewritePolicy {
+    /// 将模型返回的整文件内容与原文件逐行比较（忽略行内空白），仅空白不同的行视为无关格式化；
+    /// 行首缩进与字符串字面量内的空白可能有语义，其差异视为真实改动。
+    /// 剩余改动超过阈值时拒绝，否则只把采纳的改动应用到原文件上
+    pub fn minimize(
+        &self,
+        path: &str,
+        original: &str,
+        rewritten: &str,
+    ) -> Result<MinimizedRewrite, RewriteError> {
+        let base: Vec<&str> = original.split_inclusive('\n').collect();
+        let other: Vec<&str> = rewritten.split_inclusive('\n').collect();
+        let base_keys: Vec<String> = base.iter().map(|l| squash(l)).collect();
+        let other_keys: Vec<String> = other.iter().map(|l| squash(l)).collect();
+        let base_keys: Vec<&str> = base_keys.iter().map(String::as_str).collect();
+        let other_keys: Vec<&str> = other_keys.iter().map(String::as_str).collect();
+
+        let mut minimized = Minimizer {
+            policy: self,
+            base: &base,
+            other: &other,
+            content: String::new(),
+            kept: Vec::new(),
+            dropped: Vec::new(),
+        };
+        // 原文件与新内容中已处理到的位置
+        let (mut pos, mut other_pos) = (0, 0);
+        for edit in line_edits(&base_keys, &other_keys) {
+            minimized.unchanged(pos, edit.start, other_pos);
+            other_pos += edit.start - pos;
+            let inserted = &other[other_pos..other_pos + edit.lines.len()];
+            minimized.content.push_str(&inserted.concat());
+            minimized.kept.push(RewriteHunk {
+                line: edit.start + 1,
+                removed: owned(&base[edit.start..edit.end]),
+                inserted: owned(inserted),
+                whitespace_only: false,
+            });
+            pos = edit.end;
+            other_pos += edit.lines.len();
+        }
+        minimized.unchanged(pos, base.len(), other_pos);
+
+        let Minimizer {
+            content,
+            kept,
+            dropped,
+            ..
+        } = minimized;
+        let total = base.len();
+        let changed: usize = kept
+            .iter()
+            .filter(|h| !h.whitespace_only)
+            .map(RewriteHunk::size)
+            .sum();
+        if total >= self.min_lines && changed as f32 > total as f32 * self.max_churn_ratio {
+            return Err(RewriteError::TooMuchChurn {
+                path: path.to_string(),
+                changed,
+                total,
+                max_ratio: self.max_churn_ratio,
+            });
+        }
+        Ok(MinimizedRewrite {
+            path: path.to_string(),
+            kept,
+            dropped,
+            content,
+        })
+    }
+}
+
+struct Minimizer<'a> {
+    policy: &'a RewritePolicy,
+    base: &'a [&'a str],
+    other: &'a [&'a str],
+    content: String,
+    kept: Vec<RewriteHunk>,
+    dropped: Vec<RewriteHunk>,
+}
+
+impl Minimizer<'_> {
+    /// 处理忽略空白后相同的行 `base[from..to]`（对应新内容中从 `other_from` 开始的行），
+    /// 把连续的仅空白差异合并为一个改动块
+    fn unchanged(&mut self, from: usize, to: usize, other_from: usize) {
+        let mut run: Option<usize> = None;
+        for i in from..=to {
+            let differs = i < to && self.base[i] != self.other[other_from + i - from];
+            match (differs, run) {
+                (true, None) => run = Some(i),
+                (false, Some(start)) => {
+                    let inserted = &self.other[other_from + start - from..other_from + i - from];
+                    let hunk = RewriteHunk {
+                        line: start + 1,
+                        removed: owned(&self.base[start..i]),
+                        inserted: owned(inserted),
+                        whitespace_only: true,
+                    };
+                    if self.policy.keep_whitespace_changes {
+                        self.content.push_str(&inserted.concat());
+                        self.kept.push(hunk);
+                    } else {
+                        self.content.push_str(&self.base[start..i].concat());
+                        self.dropped.push(hunk);
+                    }
+                    run = None;
+                }
+                _ => {}
+            }
+            if !differs && i < to {
+                self.content.push_str(self.base[i]);
+            }
+        }
+    }
+}
+
+fn owned(lines: &[&str]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|l| l.trim_end_matches('\n').to_string())
+        .collect()
+}
+
+/// 比较用的行键：保留行首缩进（Python、YAML 等语言中有语义）与字符串字面量内的空白，
+/// 去掉其余空白
+///
+/// 引号未闭合时（如注释中的撇号、Rust 生命周期）其后的空白都被保留，宁可多保留改动。
+fn squash(line: &str) -> String {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let body = line.trim_start();
+    let mut key = line[..line.len() - body.len()].to_string();
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for c in body.chars() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+                key.push(c);
+            }
+            None => {
+                if matches!(c, '"' | '\'' | '`') {
+                    quote = Some(c);
+                }
+                if !c.is_whitespace() {
+                    key.push(c);
+                }
+            }
+        }
+    }
+    key.truncate(key.trim_end().len());
+    key
+}
+
+/// `write_file` 工具：写入整文件内容
+///
+/// 文件已存在时先经 `RewritePolicy` 最小化，只写入与任务相关的改动；
+/// 改动过多时拒绝写入，要求模型改用局部编辑。
+pub struct WriteFileTool {
+    storage: Arc<dyn StorageProvider>,
+    policy: RewritePolicy,
+}
+
+impl WriteFileTool {
+    pub fn new(storage: Arc<dyn StorageProvider>) -> Self {
+        Self {
+            storage,
+            policy: RewritePolicy::default(),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: RewritePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+#[async_trait(?Send)]
+impl Tool for WriteFileTool {
+    fn name(&self) -> &'static str {
+        "write_file"
+    }
+
+    fn description(&self) -> &'static str {
+        "写入文件的完整内容。修改已有文件时只保留与任务相关的改动，大面积重写会被拒绝，请优先使用 apply_patch。"
+    }
+
+    fn parameter_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "文件路径" },
+                "content": { "type": "string", "description": "文件的完整新内容" }
+            },
+            "required": ["path", "content"]
+        })
+    }
+
+    async fn effects(&self, args: &Value) -> ToolEffects {
+        ToolEffects::writes(
+            args["path"]
+                .as_str()
+                .map(str::to_string)
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
+        let path = args["path"]
+            .as_str()
+            .ok_or_else(|| SkillError::InvalidSkill("Missing 'path' parameter".into()))?;
+        let rewritten = args["content"]
+            .as_str()
+            .ok_or_else(|| SkillError::InvalidSkill("Missing 'content' parameter".into()))?;
+        let io = |e: anyhow::Error| SkillError::IoError(std::io::Error::other(e.to_string()));
+
+        if !self.storage.exists(path).await.map_err(io)? {
+            self.storage
+                .write_file(path, rewritten.as_bytes())
+                .await
+                .map_err(io)?;
+            return Ok(ToolOutput {
+                content: format!("Created {}", path),
+                data: Some(json!({ "applied": true, "created": true })),
+            });
+        }
+
+        let original = String::from_utf8(self.storage.read_file(path).await.map_err(io)?)
+            .map_err(|_| SkillError::InvalidSkill(format!("{} is not valid UTF-8", path)))?;
+        let minimized = match self.policy.minimize(path, &original, rewritten) {
+            Ok(minimized) => minimized,
+            Err(e) => {
+                return Ok(ToolOutput {
+                    content: e.to_string(),
+                    data: Some(json!({ "applied": false, "error": e.to_string() })),
+                });
+            }
+        };
+        if !minimized.is_unchanged() {
+            self.storage
+                .write_file(path, minimized.content.as_bytes())
+                .await
+                .map_err(io)?;
+        }
+        Ok(ToolOutput {
+            content: format!(
+                "Wrote {} change(s) to {}; ignored {} whitespace-only change(s)",
+                minimized.kept.len(),
+                path,
+                minimized.dropped.len()
+            ),
+            data: Some(json!({
+                "applied": !minimized.is_unchanged(),
+                "kept": minimized.kept,
+                "dropped": minimized.dropped
+            })),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+
+    #[test]
+    fn test_minimize_rewrite() {
+        let original: String = (1..=20).map(|i| format!("let v{} = {};\n", i, i)).collect();
+        // 修改第 3 行，同时改动第 10 行的行内空白
+        let rewritten = original
+            .replace("let v3 = 3;", "let v3 = 30;")
+            .replace("let v10 = 10;", "let v10=10;  ");
+        let result = RewritePolicy::default()
+            .minimize("a.rs", &original, &rewritten)
+            .unwrap();
+        assert_eq!(result.kept.len(), 1);
+        assert_eq!(result.kept[0].line, 3);
+        assert_eq!(result.dropped[0].line, 10);
+        assert_eq!(
+            result.content,
+            original.replace("let v3 = 3;", "let v3 = 30;")
+        );
+        assert_eq!(result.operations().len(), 1);
+
+        // 大面积改写被拒绝
+        let rewritten = original.replace(" = ", " := ");
+        assert!(matches!(
+            RewritePolicy::default().minimize("a.rs", &original, &rewritten),
+            Err(RewriteError::TooMuchChurn { changed: 20, .. })
+        ));
+    }
+
+    #[test]
+    fn test_indentation_and_string_whitespace_are_real_changes() {
+        let original = "def f():\n    if x:\n        return ' a'\n    y = 1\n    return g( 1 )\n";
+        // 修改字符串中的空格并改变最后一行的缩进；第 4 行只改行内空白
+        let rewritten = "def f():\n    if x:\n        return 'a'\n    y=1\n return g(1)\n";
+        let result = RewritePolicy::default()
+            .minimize("a.py", original, rewritten)
+            .unwrap();
+        let kept: Vec<usize> = result.kept.iter().map(|h| h.line).collect();
+        assert_eq!(kept, vec![3, 5]);
+        assert_eq!(result.dropped[0].line, 4);
+        assert_eq!(
+            result.content,
+            "def f():\n    if x:\n        return 'a'\n    y = 1\n return g(1)\n"
+        );
+
+        assert_eq!(squash("  let s = \"a  b\" ;\r\n"), "  lets=\"a  b\";");
+        assert_eq!(squash("x = 'it\\'s  here'  "), "x='it\\'s  here'");
+    }
+
+    #[tokio::test]
+    async fn test_write_file_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        storage.write_file("a.txt", b"a\nb\n").await.unwrap();
+        let tool = WriteFileTool::new(storage.clone());
+
+        let output = tool
+            .execute(json!({ "path": "a.txt", "content": "a  \nc\n" }))
+            .await
+            .unwrap();
+        assert_eq!(output.data.unwrap()["applied"], true);
+        assert_eq!(storage.read_file("a.txt").await.unwrap(), b"a\nc\n");
+    }
+}
@@ -0,0 +1,330 @@
+use crate::common::provider::allowlist::{CommandAllowlist, permits_command};
+use crate::common::provider::traits::ExecutionProvider;
+use crate::skill::tool::Tool;
+use crate::skill::traits::SkillError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// 一次工具调用将产生的副作用，由工具根据参数声明（见 `Tool::effects`）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolEffects {
+    /// 将写入的路径；`Some` 但为空表示会写入、路径事先未知
+    pub writes: Option<Vec<String>>,
+    /// 将执行的命令；`Some` 但为空表示会执行、命令事先未知
+    pub commands: Option<Vec<String>>,
+}
+
+impl ToolEffects {
+    pub fn writes(paths: Vec<String>) -> Self {
+        Self {
+            writes: Some(paths),
+            commands: None,
+        }
+    }
+
+    pub fn runs(commands: Vec<String>) -> Self {
+        Self {
+            writes: None,
+            commands: Some(commands),
+        }
+    }
+
+    pub fn and_runs(mut self, commands: Vec<String>) -> Self {
+        self.commands = Some(commands);
+        self
+    }
+}
+
+/// Routine 的工具沙箱策略，由 `SkillToolRegistry` 在执行每次工具调用前统一检查
+///
+/// 默认不做限制。名称在拒绝列表中、或设置了允许列表而不在其中的工具不可用；
+/// 只读模式下任何写文件或执行命令的调用都被拒绝；设置可写路径前缀后，
+/// 写入路径必须位于其中之一；设置命令白名单后，命令必须以白名单中的某项开头。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolPolicy {
+    #[serde(default)]
+    pub allow: Option<BTreeSet<String>>,
+    #[serde(default)]
+    pub deny: BTreeSet<String>,
+    #[serde(default)]
+    pub read_only: bool,
+    /// 可写路径前缀，为空时不限制
+    #[serde(default)]
+    pub writable_paths: Vec<String>,
+    /// 命令白名单，`None` 时不限制
+    #[serde(default)]
+    pub commands: Option<Vec<String>>,
+}
+
+impl ToolPolicy {
+    /// 代码审查用的策略：只读，不能写文件或运行命令
+    pub fn review() -> Self {
+        Self::default().read_only()
+    }
+
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    pub fn allow(mut self, tool: &str) -> Self {
+        self.allow
+            .get_or_insert_with(BTreeSet::new)
+            .insert(tool.to_string());
+        self
+    }
+
+    pub fn deny(mut self, tool: &str) -> Self {
+        self.deny.insert(tool.to_string());
+        self
+    }
+
+    /// 添加可写路径前缀；包含 `..` 的前缀无法判断范围，返回 `SkillError::Forbidden`
+    pub fn with_writable_path(mut self, prefix: &str) -> Result<Self, SkillError> {
+        let normalized = normalize(prefix).ok_or_else(|| {
+            forbidden(format!("writable path '{}' escapes the workspace", prefix))
+        })?;
+        self.writable_paths.push(normalized);
+        Ok(self)
+    }
+
+    pub fn allow_command(mut self, command: &str) -> Self {
+        self.commands
+            .get_or_insert_with(Vec::new)
+            .push(command.trim().to_string());
+        self
+    }
+
+    /// 按名称判断工具是否可用（不可用的工具不会出现在发给模型的定义中）
+    pub fn permits_tool(&self, name: &str) -> bool {
+        !self.deny.contains(name) && self.allow.as_ref().is_none_or(|a| a.contains(name))
+    }
+
+    /// 检查一次工具调用，违反策略时返回 `SkillError::Forbidden`
+    pub async fn check(&self, tool: &dyn Tool, args: &Value) -> Result<(), SkillError> {
+        let name = tool.name();
+        if !self.permits_tool(name) {
+            return Err(forbidden(format!("tool '{}' is not allowed", name)));
+        }
+        let effects = tool.effects(args).await;
+        if let Some(paths) = effects.writes {
+            if self.read_only {
+                return Err(forbidden(format!(
+                    "'{}' writes files in read-only mode",
+                    name
+                )));
+            }
+            if !self.writable_paths.is_empty() {
+                if paths.is_empty() {
+                    return Err(forbidden(format!(
+                        "'{}' writes to paths that cannot be checked",
+                        name
+                    )));
+                }
+                if let Some(path) = paths.iter().find(|p| !self.is_writable(p)) {
+                    return Err(forbidden(format!("writing {} is not allowed", path)));
+                }
+            }
+        }
+        if let Some(commands) = effects.commands {
+            if self.read_only {
+                return Err(forbidden(format!(
+                    "'{}' runs commands in read-only mode",
+                    name
+                )));
+            }
+            if let Some(allowed) = &self.commands {
+                if commands.is_empty() {
+                    return Err(forbidden(format!(
+                        "'{}' runs commands that cannot be checked",
+                        name
+                    )));
+                }
+                if let Some(command) = commands.iter().find(|c| !permits_command(allowed, c)) {
+                    return Err(forbidden(format!("command '{}' is not allowed", command)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 按策略包装执行提供者：只读模式拒绝所有命令，设置了命令白名单时只放行白名单内的命令
+    pub fn process(&self, inner: Arc<dyn ExecutionProvider>) -> Arc<dyn ExecutionProvider> {
+        if self.read_only {
+            Arc::new(CommandAllowlist::new(inner, Vec::new()))
+        } else if let Some(allowed) = &self.commands {
+            Arc::new(CommandAllowlist::new(inner, allowed.clone()))
+        } else {
+            inner
+        }
+    }
+
+    fn is_writable(&self, path: &str) -> bool {
+        let Some(path) = normalize(path) else {
+            return false;
+        };
+        // 反序列化得到的前缀未经 `with_writable_path` 检查，无法规范化的前缀不匹配任何路径
+        let mut prefixes = self.writable_paths.iter().filter_map(|p| normalize(p));
+        prefixes.any(|prefix| {
+            prefix.is_empty()
+                || path == *prefix
+                || path
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+}
+
+fn forbidden(detail: String) -> SkillError {
+    SkillError::Forbidden(detail)
+}
+
+/// 去掉 `./` 与多余的分隔符；包含 `..` 的路径无法判断归属，返回 `None`
+fn normalize(path: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::ToolExecutor;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::common::provider::traits::StorageProvider;
+    use crate::skill::rewrite::WriteFileTool;
+    use crate::skill::tool::{ApplyPatchTool, SkillToolRegistry, ToolOutput};
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    struct Shell;
+
+    #[async_trait(?Send)]
+    impl Tool for Shell {
+        fn name(&self) -> &'static str {
+            "shell"
+        }
+
+        fn description(&self) -> &'static str {
+            "run a command"
+        }
+
+        fn parameter_schema(&self) -> Value {
+            json!({ "type": "object" })
+        }
+
+        async fn effects(&self, args: &Value) -> ToolEffects {
+            ToolEffects::runs(vec![args["command"].as_str().unwrap_or("").to_string()])
+        }
+
+        async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
+            Ok(ToolOutput {
+                content: format!("ran {}", args["command"]),
+                data: None,
+            })
+        }
+    }
+
+    fn registry(storage: Arc<dyn StorageProvider>, policy: ToolPolicy) -> SkillToolRegistry {
+        let mut registry = SkillToolRegistry::new().with_policy(policy);
+        registry.register(Arc::new(WriteFileTool::new(storage.clone())));
+        registry.register(Arc::new(ApplyPatchTool::new(storage)));
+        registry.register(Arc::new(Shell));
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_policy_is_enforced_for_every_tool_call() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+
+        // 审查 Agent：可以读取技能，但不能写文件或运行命令
+        let review = registry(storage.clone(), ToolPolicy::review());
+        let write = json!({ "path": "src/lib.rs", "content": "fn main() {}\n" });
+        let err = review
+            .execute("write_file", write.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SkillError::Forbidden(_)));
+        assert!(
+            review
+                .call("shell", json!({ "command": "rm -rf /" }))
+                .await
+                .is_err()
+        );
+        assert!(!storage.exists("src/lib.rs").await.unwrap());
+        assert!(review.execute("list_skills", json!({})).await.is_ok());
+        // 未声明副作用的工具按会写入处理
+        let skill = json!({ "name": "x", "description": "", "content": "" });
+        assert!(matches!(
+            review.execute("register_skill", skill).await,
+            Err(SkillError::Forbidden(_))
+        ));
+        let names: Vec<String> = review
+            .definitions()
+            .into_iter()
+            .map(|d| d.function.name)
+            .collect();
+        assert!(names.contains(&"write_file".to_string()));
+
+        // 只能写 docs/ 且只能运行 cargo test
+        let scoped = registry(
+            storage.clone(),
+            ToolPolicy::default()
+                .with_writable_path("./docs/")
+                .unwrap()
+                .allow_command("cargo test"),
+        );
+        let doc = json!({ "path": "docs/guide.md", "content": "# Guide\n" });
+        scoped.execute("write_file", doc).await.unwrap();
+        assert!(storage.exists("docs/guide.md").await.unwrap());
+        for path in ["src/lib.rs", "docs/../src/lib.rs", "docsx/a.md"] {
+            let args = json!({ "path": path, "content": "x\n" });
+            assert!(matches!(
+                scoped.execute("write_file", args).await,
+                Err(SkillError::Forbidden(_))
+            ));
+        }
+        let patch = "--- a/docs/guide.md\n+++ b/src/guide.md\n@@ -1 +1 @@\n-# Guide\n+# Moved\n";
+        assert!(matches!(
+            scoped
+                .execute("apply_patch", json!({ "patch": patch }))
+                .await,
+            Err(SkillError::Forbidden(_))
+        ));
+        for (command, allowed) in [
+            ("cargo test --workspace", true),
+            ("cargo test", true),
+            ("cargo testx", false),
+            ("cargo publish", false),
+        ] {
+            let result = scoped.execute("shell", json!({ "command": command })).await;
+            assert_eq!(result.is_ok(), allowed, "{}", command);
+        }
+
+        assert!(matches!(
+            ToolPolicy::default().with_writable_path("docs/../.."),
+            Err(SkillError::Forbidden(_))
+        ));
+        let escaped = ToolPolicy {
+            writable_paths: vec!["../".into()],
+            ..ToolPolicy::default()
+        };
+        assert!(!escaped.is_writable("src/lib.rs"));
+
+        let only_skills = ToolPolicy::default()
+            .allow("get_skill")
+            .allow("list_skills");
+        assert!(only_skills.permits_tool("list_skills"));
+        assert!(!only_skills.permits_tool("write_file"));
+    }
+}
@@ -0,0 +1,258 @@
+use crate::common::endpoint::error::{EndpointError, EndpointResult};
+use crate::common::endpoint::traits::LLMClient;
+use crate::skill::registry::SkillRegistry;
+use crate::skill::traits::{Skill, SkillId};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+/// 默认的最低相似度，低于该分数的技能视为不相关
+pub const DEFAULT_MIN_SCORE: f32 = 0.3;
+
+/// 技能的嵌入索引，按任务描述的语义相似度检索技能
+///
+/// 向量在检索时按需补齐：新注册或内容变化的技能批量嵌入，已移除的技能随之丢弃。
+/// 嵌入接口不可用时退回注册表的关键字评分。
+pub struct SkillIndex {
+    client: Arc<dyn LLMClient>,
+    model: String,
+    min_score: f32,
+    /// 技能 ID -> (嵌入文本的指纹, 向量)
+    vectors: Mutex<HashMap<SkillId, (u64, Vec<f32>)>>,
+}
+
+impl SkillIndex {
+    pub fn new(client: Arc<dyn LLMClient>, model: &str) -> Self {
+        Self {
+            client,
+            model: model.to_string(),
+            min_score: DEFAULT_MIN_SCORE,
+            vectors: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_min_score(mut self, min_score: f32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    /// 使索引与注册表一致，返回新嵌入的技能数
+    pub async fn sync(&self, registry: &SkillRegistry) -> EndpointResult<usize> {
+        let skills = registry.all();
+        let stale: Vec<(Arc<Skill>, u64)> = {
+            let mut vectors = self.vectors.lock().unwrap();
+            vectors.retain(|id, _| registry.contains(id));
+            skills
+                .into_iter()
+                .map(|skill| {
+                    let fingerprint = fingerprint(&skill_text(&skill));
+                    (skill, fingerprint)
+                })
+                .filter(|(skill, fingerprint)| {
+                    vectors.get(&skill.id).map(|(f, _)| f) != Some(fingerprint)
+                })
+                .collect()
+        };
+        if stale.is_empty() {
+            return Ok(0);
+        }
+
+        let texts: Vec<String> = stale.iter().map(|(skill, _)| skill_text(skill)).collect();
+        let response = self.client.embed(&self.model, &texts).await?;
+        if response.data.len() != stale.len() {
+            return Err(EndpointError::ProviderError(format!(
+                "Expected {} embeddings, got {}",
+                stale.len(),
+                response.data.len()
+            )));
+        }
+        let mut vectors = self.vectors.lock().unwrap();
+        for ((skill, fingerprint), vector) in stale.iter().zip(response.data) {
+            vectors.insert(skill.id.clone(), (*fingerprint, vector));
+        }
+        Ok(stale.len())
+    }
+
+    /// 按与任务的余弦相似度检索技能（降序），只返回不低于最低分数的技能
+    pub async fn search(
+        &self,
+        registry: &SkillRegistry,
+        task: &str,
+        language: Option<&str>,
+        limit: usize,
+    ) -> EndpointResult<Vec<(f32, Arc<Skill>)>> {
+        self.sync(registry).await?;
+        let query = self
+            .client
+            .embed(&self.model, &[task.to_string()])
+            .await?
+            .data
+            .into_iter()
+            .next()
+            .ok_or_else(|| EndpointError::ProviderError("No embedding returned".into()))?;
+
+        let candidates = match language {
+            Some(language) => registry.by_language(language),
+            None => registry.all(),
+        };
+        let vectors = self.vectors.lock().unwrap();
+        let mut scored: Vec<(f32, Arc<Skill>)> = candidates
+            .into_iter()
+            .filter_map(|skill| {
+                let (_, vector) = vectors.get(&skill.id)?;
+                Some((cosine_similarity(&query, vector), skill))
+            })
+            .filter(|(score, _)| *score >= self.min_score)
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    /// 语义检索相关技能，嵌入接口不可用时退回 `SkillRegistry::find_relevant` 的关键字评分
+    pub async fn find_relevant(
+        &self,
+        registry: &SkillRegistry,
+        task: &str,
+        language: Option<&str>,
+        limit: usize,
+    ) -> Vec<Arc<Skill>> {
+        match self.search(registry, task, language, limit).await {
+            Ok(scored) => scored.into_iter().map(|(_, skill)| skill).collect(),
+            Err(_) => registry.find_relevant(task, language, limit),
+        }
+    }
+}
+
+/// 余弦相似度，维度不同或含零向量时为 0
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        dot / denominator
+    }
+}
+
+/// 参与嵌入的技能文本：名称、描述与内容
+fn skill_text(skill: &Skill) -> String {
+    format!("{}\n{}\n{}", skill.name, skill.description, skill.content)
+}
+
+fn fingerprint(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::endpoint::scripted::ScriptedClient;
+    use crate::skill::traits::{SkillCategory, SkillMetadata};
+    use std::collections::HashSet;
+
+    /// 按关键词出现与否生成向量：[macro, async, test]；不可达时嵌入失败
+    fn keywords(reachable: bool) -> Arc<ScriptedClient> {
+        let client = ScriptedClient::new("keywords").with_embedder(|text| {
+            let text = text.to_lowercase();
+            ["macro", "async", "test"]
+                .iter()
+                .map(|k| if text.contains(k) { 1.0 } else { 0.0 })
+                .collect()
+        });
+        client.set_reachable(reachable);
+        Arc::new(client)
+    }
+
+    fn skill(name: &str, content: &str) -> Skill {
+        Skill {
+            id: SkillId::new(SkillCategory::new("Syntax"), name, "Rust"),
+            name: name.into(),
+            description: format!("{} skill", name),
+            content: content.into(),
+            examples: vec![],
+            related_tools: vec![],
+            metadata: SkillMetadata {
+                language: "Rust".into(),
+                version: "1.0".into(),
+                author: None,
+                tags: HashSet::new(),
+            },
+        }
+    }
+
+    fn registry() -> SkillRegistry {
+        let mut registry = SkillRegistry::new();
+        registry
+            .register_all([
+                skill("declarative", "Writing macro_rules! macros"),
+                skill("runtime", "Spawning async tasks with tokio"),
+                skill("async_macros", "Async test macro usage"),
+            ])
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_ranks_by_similarity() {
+        let client = keywords(true);
+        let index = SkillIndex::new(client.clone(), "embed").with_min_score(0.5);
+        let mut registry = registry();
+
+        // 查询文本不是任何技能的子串，关键字评分找不到
+        let task = "generate a Macro for me";
+        assert!(registry.find_relevant(task, None, 5).is_empty());
+        let scored = index.search(&registry, task, None, 5).await.unwrap();
+        let names: Vec<&str> = scored.iter().map(|(_, s)| s.name.as_str()).collect();
+        // "runtime" 与查询正交，低于最低分数
+        assert_eq!(names, vec!["declarative", "async_macros"]);
+        assert!(scored[0].0 > scored[1].0);
+
+        // 未变化的技能不重新嵌入；更新与移除的技能随之同步
+        assert_eq!(client.embedded(), 4);
+        assert_eq!(index.sync(&registry).await.unwrap(), 0);
+        registry
+            .register(skill("runtime", "Writing macro_rules! macros"))
+            .unwrap();
+        registry.unregister(&SkillId::new(
+            SkillCategory::new("Syntax"),
+            "declarative",
+            "Rust",
+        ));
+        assert_eq!(index.sync(&registry).await.unwrap(), 1);
+        let names: Vec<String> = index
+            .find_relevant(&registry, task, None, 1)
+            .await
+            .into_iter()
+            .map(|s| s.name.clone())
+            .collect();
+        assert_eq!(names, vec!["runtime"]);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_keywords_without_embeddings() {
+        let index = SkillIndex::new(keywords(false), "embed");
+        let registry = registry();
+        assert!(index.search(&registry, "tokio", None, 5).await.is_err());
+        let found = index
+            .find_relevant(&registry, "tokio", Some("Rust"), 5)
+            .await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "runtime");
+    }
+}
@@ -1,8 +1,14 @@
+use crate::common::lifecycle::LazySubsystem;
+use crate::common::meta::PluginManifest;
 use crate::skill::injector::SkillInjector;
 use crate::skill::loader::SkillConfig;
 use crate::skill::loader::SkillLoader;
+use crate::skill::plugin::{PluginContribution, PluginContributions};
+use crate::skill::prompt::PromptLibrary;
 use crate::skill::registry::SkillRegistry;
-use crate::skill::traits::SkillError;
+use crate::skill::semantic::SkillIndex;
+use crate::skill::store::SkillStore;
+use crate::skill::traits::{Skill, SkillError};
 use std::sync::Arc;
 use std::sync::OnceLock;
 use tokio::sync::RwLock;
@@ -11,6 +17,12 @@ use tokio::sync::RwLock;
 pub struct SkillState {
     pub registry: SkillRegistry,
     pub injector: SkillInjector,
+    pub prompts: PromptLibrary,
+    pub plugins: PluginContributions,
+    /// 持久化存储（配置后运行时注册的技能写入磁盘，重启后自动加载）
+    pub store: Option<SkillStore>,
+    /// 嵌入索引（配置后按语义相似度检索技能）
+    pub semantic: Option<SkillIndex>,
 }
 
 impl SkillState {
@@ -18,7 +30,90 @@ impl SkillState {
     pub fn new() -> Self {
         let registry = SkillRegistry::new();
         let injector = SkillInjector::new(registry.clone());
-        Self { registry, injector }
+        Self {
+            registry,
+            injector,
+            prompts: PromptLibrary::new(),
+            plugins: PluginContributions::new(),
+            store: None,
+            semantic: None,
+        }
+    }
+
+    /// 根据任务描述查找相关技能
+    ///
+    /// 配置了嵌入索引时按语义相似度检索（嵌入接口不可用时退回关键字评分），否则使用关键字评分。
+    pub async fn find_relevant(
+        &self,
+        task: &str,
+        language: Option<&str>,
+        limit: usize,
+    ) -> Vec<Arc<Skill>> {
+        match &self.semantic {
+            Some(index) => {
+                index
+                    .find_relevant(&self.registry, task, language, limit)
+                    .await
+            }
+            None => self.registry.find_relevant(task, language, limit),
+        }
+    }
+
+    /// 创建使用持久化存储的技能状态，并加载其中已保存的技能
+    pub async fn with_store(store: SkillStore) -> Result<Self, SkillError> {
+        let mut state = Self::new();
+        state.attach_store(store).await?;
+        Ok(state)
+    }
+
+    /// 配置持久化存储并加载其中已保存的技能，返回加载的技能数
+    pub async fn attach_store(&mut self, store: SkillStore) -> Result<usize, SkillError> {
+        let skills = store.load_all().await?;
+        let count = skills.len();
+        self.registry.register_all(skills)?;
+        self.store = Some(store);
+        Ok(count)
+    }
+
+    /// 注册运行时添加的技能，配置了持久化存储时先保存
+    ///
+    /// 返回实际注册的技能：更新已有技能时其版本号由存储分配。
+    pub async fn register(&mut self, skill: Skill) -> Result<Skill, SkillError> {
+        let skill = match &self.store {
+            Some(store) => store.save(skill).await?,
+            None => skill,
+        };
+        self.registry.register(skill.clone())?;
+        Ok(skill)
+    }
+
+    /// 注册插件随附的技能与提示词模板
+    pub async fn load_plugin(
+        &mut self,
+        manifest: &PluginManifest,
+        root: &str,
+        storage: Arc<dyn crate::common::provider::traits::StorageProvider>,
+    ) -> Result<PluginContribution, SkillError> {
+        self.plugins
+            .load(
+                manifest,
+                root,
+                storage,
+                &mut self.registry,
+                &mut self.prompts,
+            )
+            .await
+    }
+
+    /// 移除插件贡献的技能与提示词模板
+    pub fn unload_plugin(&mut self, plugin_id: &str) -> Option<PluginContribution> {
+        self.plugins
+            .unload(plugin_id, &mut self.registry, &mut self.prompts)
+    }
+
+    /// 为全局状态配置持久化存储并加载已保存的技能（在程序启动时调用）
+    pub async fn init_store(store: SkillStore) -> Result<usize, SkillError> {
+        Self::get().write().await.attach_store(store).await
     }
 
     /// 从配置预加载技能（在程序启动时调用）
@@ -31,6 +126,25 @@ impl SkillState {
         let mut state = Self::get().write().await;
         state.registry.register_all(skills)
     }
+
+    /// 将技能预加载推迟到首次使用，替代启动时调用 `preload_from_config`
+    ///
+    /// 子系统初始化时加载配置中的技能并注册到全局状态，值为注册的技能数。
+    pub fn deferred_preload(
+        config: SkillConfig,
+        storage: Arc<dyn crate::common::provider::traits::StorageProvider>,
+    ) -> LazySubsystem<usize> {
+        LazySubsystem::new("skills", move || {
+            let config = config.clone();
+            let storage = storage.clone();
+            async move {
+                let skills = SkillLoader::new(storage).from_config(&config).await?;
+                let count = skills.len();
+                Self::get().write().await.registry.register_all(skills)?;
+                Ok(count)
+            }
+        })
+    }
 }
 
 impl Default for SkillState {
@@ -88,6 +202,32 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_registered_skills_survive_restart() {
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        let mut state = SkillState::with_store(SkillStore::new(storage.clone(), "skills"))
+            .await
+            .unwrap();
+        let skill = create_test_skill("persisted");
+        let id = skill.id.clone();
+        state.register(skill.clone()).await.unwrap();
+        let mut updated = skill;
+        updated.content = "Updated content".into();
+        let updated = state.register(updated).await.unwrap();
+        assert_eq!(updated.metadata.version, "1.1");
+        drop(state);
+
+        let restarted = SkillState::with_store(SkillStore::new(storage, "skills"))
+            .await
+            .unwrap();
+        let loaded = restarted.registry.get(&id).unwrap();
+        assert_eq!(loaded.content, "Updated content");
+        assert_eq!(loaded.metadata.version, "1.1");
+    }
+
     #[tokio::test]
     async fn test_global_state_singleton() {
         // 获取全局状态并注册一个技能
@@ -187,4 +327,33 @@ mod tests {
             state.registry.count()
         );
     }
+
+    #[tokio::test]
+    async fn test_deferred_preload_registers_skills_on_first_use() {
+        use crate::common::lifecycle::StartupProfiler;
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+        use serde_json::json;
+
+        let dir = tempfile::tempdir().unwrap();
+        let skill = create_test_skill("test_deferred_preload");
+        let id = skill.id.clone();
+        let config = SkillConfig {
+            files: vec![],
+            inline_skills: vec![json!(skill)],
+        };
+        let profiler = Arc::new(StartupProfiler::new());
+        let skills =
+            SkillState::deferred_preload(config, Arc::new(LocalFileSystem::new(dir.path())))
+                .with_profiler(profiler.clone());
+
+        // 启动结束时尚未加载
+        assert_eq!(profiler.finish().deferred, vec!["skills"]);
+        assert!(!SkillState::get().read().await.registry.contains(&id));
+
+        assert_eq!(*skills.get().await.unwrap(), 1);
+        assert!(SkillState::get().read().await.registry.contains(&id));
+        let report = profiler.report();
+        assert!(report.deferred.is_empty());
+        assert!(report.phases.iter().any(|p| p.name == "skills" && p.lazy));
+    }
 }
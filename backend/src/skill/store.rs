@@ -0,0 +1,304 @@
+use crate::common::provider::traits::StorageProvider;
+use crate::skill::loader::SkillLoader;
+use crate::skill::traits::{Skill, SkillError, SkillId};
+use std::path::Path;
+use std::sync::Arc;
+
+/// 保存历史版本的子目录（不参与加载）
+const HISTORY_DIR: &str = ".history";
+
+/// 技能的持久化存储：目录中每个技能一个 YAML 文件
+///
+/// 文件位于 `<根目录>/<类别>/<语言>/<名称>.yaml`，格式与 `SkillLoader` 读取的技能文件相同，
+/// 可以直接手工编辑。更新已有技能时旧版本移到 `.history` 下，并为新内容分配更高的版本号。
+pub struct SkillStore {
+    storage: Arc<dyn StorageProvider>,
+    root: String,
+}
+
+impl SkillStore {
+    pub fn new(storage: Arc<dyn StorageProvider>, root: &str) -> Self {
+        Self {
+            storage,
+            root: root.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// 加载目录中的全部技能（按路径排序），历史版本除外
+    pub async fn load_all(&self) -> Result<Vec<Skill>, SkillError> {
+        if !self.storage.exists(&self.root).await.map_err(io_error)? {
+            return Ok(Vec::new());
+        }
+        let loader = SkillLoader::new(self.storage.clone());
+        let mut files = Vec::new();
+        let mut pending = vec![self.root.clone()];
+        while let Some(dir) = pending.pop() {
+            for entry in self.storage.list_dir(&dir).await.map_err(io_error)? {
+                if entry.is_dir {
+                    if entry.path.rsplit('/').next() != Some(HISTORY_DIR) {
+                        pending.push(entry.path);
+                    }
+                } else if entry.path.ends_with(".yaml") || entry.path.ends_with(".yml") {
+                    files.push(entry.path);
+                }
+            }
+        }
+        files.sort();
+
+        let mut skills = Vec::new();
+        for file in files {
+            skills.extend(loader.load_from_file(Path::new(&file)).await?);
+        }
+        Ok(skills)
+    }
+
+    /// 保存技能，返回实际写入的技能
+    ///
+    /// 已有同 ID 的技能时：内容相同则不做改动，返回已保存的版本；内容不同时旧版本移入历史，
+    /// 新版本号取请求的版本号（若更高）或在旧版本号末段加一。
+    pub async fn save(&self, mut skill: Skill) -> Result<Skill, SkillError> {
+        skill.validate()?;
+        let path = self.path(&skill.id);
+        if let Some(previous) = self.load(&path).await? {
+            if same_content(&previous, &skill) {
+                return Ok(previous);
+            }
+            let previous_version = &previous.metadata.version;
+            if !is_newer(&skill.metadata.version, previous_version) {
+                skill.metadata.version = next_version(previous_version);
+            }
+            self.write(&self.history_path(&skill.id, previous_version), &previous)
+                .await?;
+        }
+        self.write(&path, &skill).await?;
+        Ok(skill)
+    }
+
+    /// 删除技能的当前版本（保留历史），返回是否存在
+    pub async fn remove(&self, id: &SkillId) -> Result<bool, SkillError> {
+        let path = self.path(id);
+        if !self.storage.exists(&path).await.map_err(io_error)? {
+            return Ok(false);
+        }
+        self.storage.delete(&path, false).await.map_err(io_error)?;
+        Ok(true)
+    }
+
+    /// 技能已归档的历史版本号（从旧到新）
+    pub async fn versions(&self, id: &SkillId) -> Result<Vec<String>, SkillError> {
+        let dir = self.history_dir(id);
+        if !self.storage.exists(&dir).await.map_err(io_error)? {
+            return Ok(Vec::new());
+        }
+        let mut versions: Vec<String> = self
+            .storage
+            .list_dir(&dir)
+            .await
+            .map_err(io_error)?
+            .into_iter()
+            .filter(|entry| !entry.is_dir)
+            .filter_map(|entry| {
+                let name = entry.path.rsplit('/').next()?;
+                name.strip_suffix(".yaml").map(str::to_string)
+            })
+            .collect();
+        versions.sort_by(|a, b| compare_versions(a, b));
+        Ok(versions)
+    }
+
+    /// 读取技能的某个历史版本
+    pub async fn load_version(&self, id: &SkillId, version: &str) -> Result<Skill, SkillError> {
+        self.load(&self.history_path(id, version))
+            .await?
+            .ok_or_else(|| SkillError::NotFound(format!("{} version {}", id.name, version)))
+    }
+
+    async fn load(&self, path: &str) -> Result<Option<Skill>, SkillError> {
+        if !self.storage.exists(path).await.map_err(io_error)? {
+            return Ok(None);
+        }
+        let loader = SkillLoader::new(self.storage.clone());
+        Ok(loader
+            .load_from_file(Path::new(path))
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    async fn write(&self, path: &str, skill: &Skill) -> Result<(), SkillError> {
+        let yaml = serde_yaml::to_string(skill)
+            .map_err(|e| SkillError::ParseError(format!("Failed to serialize skill: {}", e)))?;
+        if let Some((dir, _)) = path.rsplit_once('/') {
+            self.storage.create_dir(dir, true).await.map_err(io_error)?;
+        }
+        self.storage
+            .write_file(path, yaml.as_bytes())
+            .await
+            .map_err(io_error)
+    }
+
+    fn relative(id: &SkillId) -> String {
+        format!(
+            "{}/{}/{}",
+            sanitize(id.category.as_str()),
+            sanitize(&id.language),
+            sanitize(&id.name)
+        )
+    }
+
+    fn path(&self, id: &SkillId) -> String {
+        format!("{}/{}.yaml", self.root, Self::relative(id))
+    }
+
+    fn history_dir(&self, id: &SkillId) -> String {
+        format!("{}/{}/{}", self.root, HISTORY_DIR, Self::relative(id))
+    }
+
+    fn history_path(&self, id: &SkillId, version: &str) -> String {
+        format!("{}/{}.yaml", self.history_dir(id), sanitize(version))
+    }
+}
+
+fn io_error(e: anyhow::Error) -> SkillError {
+    SkillError::IoError(std::io::Error::other(e.to_string()))
+}
+
+/// 将 ID 的组成部分转换为安全的文件名（如插件命名空间中的 `:`）
+fn sanitize(component: &str) -> String {
+    let name: String = component
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.trim_matches('.').is_empty() {
+        "_".to_string()
+    } else {
+        name
+    }
+}
+
+/// 除版本号外内容是否相同
+fn same_content(a: &Skill, b: &Skill) -> bool {
+    let examples = |s: &Skill| serde_json::to_value(&s.examples).ok();
+    a.name == b.name
+        && a.description == b.description
+        && a.content == b.content
+        && a.related_tools == b.related_tools
+        && a.metadata.language == b.metadata.language
+        && a.metadata.author == b.metadata.author
+        && a.metadata.tags == b.metadata.tags
+        && examples(a) == examples(b)
+}
+
+/// 以 `.` 分隔的数字版本号，含非数字部分时返回 `None`
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect()
+}
+
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    match (parse_version(a), parse_version(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+fn is_newer(version: &str, than: &str) -> bool {
+    parse_version(version).is_some() && compare_versions(version, than).is_gt()
+}
+
+/// 末段数字加一，无法解析时追加 `.1`
+fn next_version(version: &str) -> String {
+    let (prefix, last) = match version.rsplit_once('.') {
+        Some((prefix, last)) => (format!("{}.", prefix), last),
+        None => (String::new(), version),
+    };
+    match last.parse::<u64>() {
+        Ok(n) => format!("{}{}", prefix, n + 1),
+        Err(_) => format!("{}.1", version),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::skill::traits::{SkillCategory, SkillMetadata};
+    use std::collections::HashSet;
+
+    fn skill(name: &str, content: &str, version: &str) -> Skill {
+        Skill {
+            id: SkillId::new(SkillCategory::new("Syntax"), name, "Rust"),
+            name: name.into(),
+            description: format!("{} skill", name),
+            content: content.into(),
+            examples: vec![],
+            related_tools: vec![],
+            metadata: SkillMetadata {
+                language: "Rust".into(),
+                version: version.into(),
+                author: None,
+                tags: HashSet::from_iter(vec!["macro".into(), "syntax".into()]),
+            },
+        }
+    }
+
+    #[test]
+    fn test_versions() {
+        assert_eq!(next_version("1.0"), "1.1");
+        assert_eq!(next_version("1.9"), "1.10");
+        assert_eq!(next_version("3"), "4");
+        assert_eq!(next_version("beta"), "beta.1");
+        assert!(is_newer("1.10", "1.9"));
+        assert!(!is_newer("1.0", "1.0"));
+        assert!(!is_newer("beta", "1.0"));
+        assert_eq!(sanitize("plugin:fmt/../x"), "plugin_fmt_.._x");
+    }
+
+    #[tokio::test]
+    async fn test_save_load_and_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        let store = SkillStore::new(storage, "skills");
+        assert!(store.load_all().await.unwrap().is_empty());
+
+        let first = store.save(skill("macros", "v1", "1.0")).await.unwrap();
+        assert_eq!(first.metadata.version, "1.0");
+        store.save(skill("plugin:fmt", "fmt", "1.0")).await.unwrap();
+
+        // 内容不变时不产生新版本
+        let same = store.save(skill("macros", "v1", "1.0")).await.unwrap();
+        assert_eq!(same.metadata.version, "1.0");
+        assert!(store.versions(&first.id).await.unwrap().is_empty());
+
+        // 更新时旧版本进入历史，版本号递增；请求更高版本号时采用请求的版本号
+        let second = store.save(skill("macros", "v2", "1.0")).await.unwrap();
+        assert_eq!(second.metadata.version, "1.1");
+        let third = store.save(skill("macros", "v3", "2.0")).await.unwrap();
+        assert_eq!(third.metadata.version, "2.0");
+        assert_eq!(store.versions(&first.id).await.unwrap(), vec!["1.0", "1.1"]);
+        assert_eq!(
+            store.load_version(&first.id, "1.0").await.unwrap().content,
+            "v1"
+        );
+
+        let loaded = store.load_all().await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        let macros = loaded.iter().find(|s| s.id == first.id).unwrap();
+        assert_eq!(macros.content, "v3");
+        assert_eq!(macros.metadata.tags.len(), 2);
+        assert!(loaded.iter().any(|s| s.id.name == "plugin:fmt"));
+
+        assert!(store.remove(&first.id).await.unwrap());
+        assert!(!store.remove(&first.id).await.unwrap());
+        assert_eq!(store.load_all().await.unwrap().len(), 1);
+    }
+}
@@ -1,8 +1,15 @@
+use crate::common::endpoint::{FunctionDefinition, Mask, ToolDefinition, ToolExecutor};
+use crate::common::i18n::{self, Locale};
+use crate::common::provider::traits::StorageProvider;
+use crate::compiler::typecheck::TypeCheckGate;
 use crate::skill::loader::SkillLoader;
+use crate::skill::patch::{self, PatchError};
+use crate::skill::sandbox::{ToolEffects, ToolPolicy};
 use crate::skill::state::SkillState;
 use crate::skill::traits::SkillCategory;
 use crate::skill::traits::SkillError;
 use crate::skill::traits::SkillId;
+use crate::syntax::validate::SyntaxValidator;
 use async_trait::async_trait;
 use serde_json::Value;
 use serde_json::json;
@@ -26,9 +33,23 @@ pub trait Tool: Send + Sync {
     /// 工具描述（用于 LLM 理解使用方法）
     fn description(&self) -> &'static str;
 
+    /// 指定语言的描述（目录中缺少 `tool.<name>.description` 时使用内置描述）
+    fn localized_description(&self, locale: Locale) -> String {
+        i18n::lookup(locale, &format!("tool.{}.description", self.name()))
+            .unwrap_or_else(|| self.description().to_string())
+    }
+
     /// 参数模式（用于验证的 JSON Schema）
     fn parameter_schema(&self) -> Value;
 
+    /// 按参数声明本次调用的副作用，供 `ToolPolicy` 检查
+    ///
+    /// 默认视为会写入未知路径（只读模式与可写路径限制下都会被拒绝），
+    /// 没有副作用的工具需要显式返回 `ToolEffects::default()`。
+    async fn effects(&self, _args: &Value) -> ToolEffects {
+        ToolEffects::writes(Vec::new())
+    }
+
     /// 执行工具
     async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError>;
 }
@@ -66,10 +87,13 @@ impl Tool for RegisterSkillTool {
         let skill = SkillLoader::load_from_json_value(args["skill"].clone())?;
 
         let mut state = SkillState::get().write().await;
-        state.registry.register(skill.clone())?;
+        let skill = state.register(skill).await?;
 
         Ok(ToolOutput {
-            content: format!("Skill '{}' registered successfully", skill.name),
+            content: format!(
+                "Skill '{}' registered successfully (version {})",
+                skill.name, skill.metadata.version
+            ),
             data: Some(json!({
                 "id": {
                     "category": skill.id.category.as_str(),
@@ -77,7 +101,8 @@ impl Tool for RegisterSkillTool {
                     "language": skill.id.language
                 },
                 "name": skill.name,
-                "description": skill.description
+                "description": skill.description,
+                "version": skill.metadata.version
             })),
         })
     }
@@ -121,6 +146,11 @@ impl Tool for SearchSkillsTool {
         })
     }
 
+    /// 只读取技能库
+    async fn effects(&self, _args: &Value) -> ToolEffects {
+        ToolEffects::default()
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
         let task = args["task"]
             .as_str()
@@ -129,7 +159,7 @@ impl Tool for SearchSkillsTool {
         let limit = args["limit"].as_u64().unwrap_or(5) as usize;
 
         let state = SkillState::get().read().await;
-        let skills = state.registry.find_relevant(task, language, limit);
+        let skills = state.find_relevant(task, language, limit).await;
 
         let results: Vec<Value> = skills
             .iter()
@@ -190,6 +220,11 @@ impl Tool for InjectSkillsTool {
         })
     }
 
+    /// 只读取技能库
+    async fn effects(&self, _args: &Value) -> ToolEffects {
+        ToolEffects::default()
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
         let task = args["task"]
             .as_str()
@@ -245,6 +280,11 @@ impl Tool for GetSkillTool {
         })
     }
 
+    /// 只读取技能库
+    async fn effects(&self, _args: &Value) -> ToolEffects {
+        ToolEffects::default()
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
         let category_str = args["category"]
             .as_str()
@@ -321,6 +361,11 @@ impl Tool for ListSkillsTool {
         })
     }
 
+    /// 只读取技能库
+    async fn effects(&self, _args: &Value) -> ToolEffects {
+        ToolEffects::default()
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
         let state = SkillState::get().read().await;
 
@@ -352,6 +397,260 @@ impl Tool for ListSkillsTool {
     }
 }
 
+// ============================================================================
+// 工具 6: 应用补丁
+// ============================================================================
+
+/// 将统一差异格式的补丁应用到工作区
+///
+/// 补丁行号不准、缩进不一致或首尾上下文略有出入时会自动重新定位；
+/// 任一补丁块失败时不写入任何文件，并返回每个失败块最接近的原文位置。
+/// 新旧路径不同的补丁从原路径读取、写入新路径后删除原文件；新建或重命名的目标已存在时拒绝应用；
+/// 多文件写入中途失败时恢复已写入文件的原内容。
+/// 配置语法校验器后，补丁结果存在语法错误时同样不写入，并返回错误位置供模型修正；
+/// 配置类型检查后，写入完成即对被修改的文件做快速类型检查，失败信息随结果返回。
+pub struct ApplyPatchTool {
+    storage: Arc<dyn StorageProvider>,
+    validator: Option<Arc<SyntaxValidator>>,
+    type_check: Option<Arc<TypeCheckGate>>,
+}
+
+impl ApplyPatchTool {
+    pub fn new(storage: Arc<dyn StorageProvider>) -> Self {
+        Self {
+            storage,
+            validator: None,
+            type_check: None,
+        }
+    }
+
+    /// 写入前用语法引擎校验补丁后的文件内容
+    pub fn with_validator(mut self, validator: Arc<SyntaxValidator>) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// 写入后对被修改的文件运行快速类型检查
+    pub fn with_type_check(mut self, gate: Arc<TypeCheckGate>) -> Self {
+        self.type_check = Some(gate);
+        self
+    }
+
+    /// 依次写入或删除（`None`）文件；任一步失败时恢复已处理文件的原内容
+    async fn write_staged(&self, staged: &[(&str, Option<&[u8]>)]) -> anyhow::Result<()> {
+        let mut originals = Vec::with_capacity(staged.len());
+        for (path, _) in staged {
+            let original = if self.storage.exists(path).await? {
+                Some(self.storage.read_file(path).await?)
+            } else {
+                None
+            };
+            originals.push(original);
+        }
+
+        for (written, ((path, content), original)) in staged.iter().zip(&originals).enumerate() {
+            let result = match (content, original) {
+                (Some(content), _) => self.storage.write_file(path, content).await,
+                (None, Some(_)) => self.storage.delete(path, false).await,
+                (None, None) => Ok(()),
+            };
+            if let Err(e) = result {
+                for ((path, _), original) in staged.iter().zip(&originals).take(written + 1) {
+                    // 尽力回滚，保留最初的错误
+                    let _ = match original {
+                        Some(content) => self.storage.write_file(path, content).await,
+                        None => self.storage.delete(path, false).await,
+                    };
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Tool for ApplyPatchTool {
+    fn name(&self) -> &'static str {
+        "apply_patch"
+    }
+
+    fn description(&self) -> &'static str {
+        "应用统一差异格式的补丁。行号偏差和空白差异会被自动修正；失败时返回每个补丁块最接近的匹配位置。"
+    }
+
+    fn parameter_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "patch": {
+                    "type": "string",
+                    "description": "统一差异格式（unified diff）的补丁内容"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "仅检查能否应用，不写入文件（默认 false）"
+                }
+            },
+            "required": ["patch"]
+        })
+    }
+
+    async fn effects(&self, args: &Value) -> ToolEffects {
+        if args["dry_run"].as_bool().unwrap_or(false) {
+            return ToolEffects::default();
+        }
+        let files = args["patch"]
+            .as_str()
+            .and_then(|text| patch::parse_unified_diff(text).ok())
+            .unwrap_or_default();
+        ToolEffects::writes(
+            files
+                .iter()
+                .flat_map(|f| [f.old_path.clone(), f.new_path.clone()])
+                .flatten()
+                .collect(),
+        )
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolOutput, SkillError> {
+        let text = args["patch"]
+            .as_str()
+            .ok_or_else(|| SkillError::InvalidSkill("Missing 'patch' parameter".into()))?;
+        let dry_run = args["dry_run"].as_bool().unwrap_or(false);
+        let files =
+            patch::parse_unified_diff(text).map_err(|e| SkillError::ParseError(e.to_string()))?;
+
+        // 新建或重命名的目标已存在时拒绝覆盖
+        let mut existing = Vec::new();
+        for file in files.iter().filter(|f| f.is_creation() || f.is_rename()) {
+            if self
+                .storage
+                .exists(file.path())
+                .await
+                .map_err(storage_error)?
+            {
+                existing.push(file.path().to_string());
+            }
+        }
+        if !existing.is_empty() {
+            return Ok(ToolOutput {
+                content: format!(
+                    "Patch not applied: {} already exist(s); patch the existing file instead",
+                    existing.join(", ")
+                ),
+                data: Some(json!({ "applied": false, "existing": existing })),
+            });
+        }
+
+        let mut results = Vec::with_capacity(files.len());
+        let mut conflicts = Vec::new();
+        for file in &files {
+            let path = file.path();
+            let original = match file.old_path.as_deref() {
+                None => String::new(),
+                // 重命名时从原路径读取
+                Some(source) => {
+                    let bytes = self
+                        .storage
+                        .read_file(source)
+                        .await
+                        .map_err(storage_error)?;
+                    String::from_utf8(bytes).map_err(|_| {
+                        SkillError::InvalidSkill(format!("{} is not valid UTF-8", source))
+                    })?
+                }
+            };
+            match patch::apply_hunks(&original, path, &file.hunks) {
+                Ok(patched) => results.push((file, patched)),
+                Err(PatchError::Conflict { path, failures }) => {
+                    conflicts.push(json!({ "path": path, "failures": failures }))
+                }
+                Err(e) => return Err(SkillError::ParseError(e.to_string())),
+            }
+        }
+
+        if !conflicts.is_empty() {
+            return Ok(ToolOutput {
+                content: format!(
+                    "Patch not applied: {} file(s) have hunks that could not be located",
+                    conflicts.len()
+                ),
+                data: Some(json!({ "applied": false, "conflicts": conflicts })),
+            });
+        }
+
+        if let Some(validator) = &self.validator {
+            let mut reports = Vec::new();
+            let mut hints = Vec::new();
+            for (file, patched) in results.iter().filter(|(f, _)| !f.is_deletion()) {
+                let report = validator.validate(file.path(), &patched.content).await;
+                if !report.is_valid() {
+                    hints.push(report.hint(&patched.content));
+                    reports.push(report);
+                }
+            }
+            if !reports.is_empty() {
+                return Ok(ToolOutput {
+                    content: format!(
+                        "Patch not applied: the result has syntax errors. Fix them and retry.\n\n{}",
+                        hints.join("\n\n")
+                    ),
+                    data: Some(json!({ "applied": false, "syntax_errors": reports })),
+                });
+            }
+        }
+
+        if !dry_run {
+            let mut staged: Vec<(&str, Option<&[u8]>)> = Vec::new();
+            for (file, patched) in &results {
+                if let Some(path) = file.new_path.as_deref() {
+                    staged.push((path, Some(patched.content.as_bytes())));
+                }
+                if file.is_deletion() || file.is_rename() {
+                    staged.push((file.old_path.as_deref().unwrap_or_default(), None));
+                }
+            }
+            self.write_staged(&staged).await.map_err(storage_error)?;
+        }
+
+        let files: Vec<Value> = results
+            .iter()
+            .map(|(file, patched)| json!({ "path": file.path(), "hunks": patched.hunks }))
+            .collect();
+        let mut content = format!(
+            "{} {} file(s)",
+            if dry_run {
+                "Patch applies cleanly to"
+            } else {
+                "Patched"
+            },
+            files.len()
+        );
+        let mut data = json!({ "applied": !dry_run, "files": files });
+
+        if let Some(gate) = self.type_check.as_ref().filter(|_| !dry_run) {
+            let paths: Vec<String> = results.iter().map(|(f, _)| f.path().to_string()).collect();
+            let report = gate.check(&paths).await.map_err(storage_error)?;
+            if !report.passed() {
+                content.push_str(&format!(
+                    ", but the type check failed. Fix these errors before completing the step:\n{}",
+                    report.feedback()
+                ));
+            }
+            data["type_check"] = json!({ "passed": report.passed(), "runs": report.runs });
+        }
+        Ok(ToolOutput {
+            content,
+            data: Some(data),
+        })
+    }
+}
+
+fn storage_error(e: anyhow::Error) -> SkillError {
+    SkillError::IoError(std::io::Error::other(e.to_string()))
+}
+
 // ============================================================================
 // 工具注册表
 // ============================================================================
@@ -359,6 +658,9 @@ impl Tool for ListSkillsTool {
 /// 所有技能工具的注册表
 pub struct SkillToolRegistry {
     tools: HashMap<&'static str, Arc<dyn Tool>>,
+    policy: ToolPolicy,
+    /// 工具输出与错误交给模型前应用的掩码
+    mask: Option<Arc<dyn Mask>>,
 }
 
 impl SkillToolRegistry {
@@ -373,7 +675,32 @@ impl SkillToolRegistry {
         tools.insert("inject_skills", Arc::new(InjectSkillsTool) as Arc<dyn Tool>);
         tools.insert("get_skill", Arc::new(GetSkillTool) as Arc<dyn Tool>);
         tools.insert("list_skills", Arc::new(ListSkillsTool) as Arc<dyn Tool>);
-        Self { tools }
+        Self {
+            tools,
+            policy: ToolPolicy::default(),
+            mask: None,
+        }
+    }
+
+    /// 使用调用者 Routine 的工具沙箱策略，每次执行前检查
+    pub fn with_policy(mut self, policy: ToolPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> &ToolPolicy {
+        &self.policy
+    }
+
+    /// 对工具输出（含 `data` 中的字符串）与错误信息应用掩码（如项目密钥）
+    pub fn with_mask(mut self, mask: Arc<dyn Mask>) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// 注册额外的工具（例如需要注入存储提供者的 `ApplyPatchTool`）
+    pub fn register(&mut self, tool: Arc<dyn Tool>) {
+        self.tools.insert(tool.name(), tool);
     }
 
     /// 根据名称获取工具
@@ -386,14 +713,20 @@ impl SkillToolRegistry {
         &self.tools
     }
 
-    /// 获取所有工具模式（用于 LLM 函数调用）
+    /// 获取所有工具模式（用于 LLM 函数调用），描述使用当前语言
     pub fn get_all_schemas(&self) -> Vec<Value> {
+        self.get_localized_schemas(i18n::current_locale())
+    }
+
+    /// 获取指定语言的工具模式
+    pub fn get_localized_schemas(&self, locale: Locale) -> Vec<Value> {
         self.tools
             .values()
+            .filter(|tool| self.policy.permits_tool(tool.name()))
             .map(|tool| {
                 json!({
                     "name": tool.name(),
-                    "description": tool.description(),
+                    "description": tool.localized_description(locale),
                     "parameters": tool.parameter_schema()
                 })
             })
@@ -405,7 +738,32 @@ impl SkillToolRegistry {
         let tool = self
             .get(name)
             .ok_or_else(|| SkillError::NotFound(format!("Tool not found: {}", name)))?;
-        tool.execute(args).await
+        self.policy.check(tool.as_ref(), &args).await?;
+        let output = tool.execute(args).await?;
+        let Some(mask) = &self.mask else {
+            return Ok(output);
+        };
+        Ok(ToolOutput {
+            content: mask.mask(&output.content),
+            data: output.data.map(|data| mask_value(mask.as_ref(), data)),
+        })
+    }
+}
+
+/// 递归地对 JSON 中的字符串应用掩码
+fn mask_value(mask: &dyn Mask, value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(mask.mask(&text)),
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(|v| mask_value(mask, v)).collect())
+        }
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k, mask_value(mask, v)))
+                .collect(),
+        ),
+        other => other,
     }
 }
 
@@ -415,6 +773,38 @@ impl Default for SkillToolRegistry {
     }
 }
 
+/// 让 `AgentLoop` 直接调用注册表中的工具；结果以 `ToolOutput` 的 JSON 形式返回给模型
+#[async_trait(?Send)]
+impl ToolExecutor for SkillToolRegistry {
+    fn definitions(&self) -> Vec<ToolDefinition> {
+        let locale = i18n::current_locale();
+        let mut definitions: Vec<ToolDefinition> = self
+            .tools
+            .values()
+            .filter(|tool| self.policy.permits_tool(tool.name()))
+            .map(|tool| ToolDefinition {
+                r#type: "function".to_string(),
+                function: FunctionDefinition {
+                    name: tool.name().to_string(),
+                    description: Some(tool.localized_description(locale)),
+                    parameters: tool.parameter_schema(),
+                },
+            })
+            .collect();
+        definitions.sort_by(|a, b| a.function.name.cmp(&b.function.name));
+        definitions
+    }
+
+    async fn call(&self, name: &str, arguments: Value) -> anyhow::Result<String> {
+        let output = match (self.execute(name, arguments).await, &self.mask) {
+            (Ok(output), _) => output,
+            (Err(e), Some(mask)) => return Err(anyhow::anyhow!(mask.mask(&e.to_string()))),
+            (Err(e), None) => return Err(e.into()),
+        };
+        Ok(serde_json::to_string(&output)?)
+    }
+}
+
 // ============================================================================
 // 测试
 // ============================================================================
@@ -582,6 +972,13 @@ mod tests {
         let schemas = registry.get_all_schemas();
         assert_eq!(schemas.len(), 5);
 
+        let list = registry.get("list_skills").unwrap();
+        assert_eq!(
+            list.localized_description(Locale::EnUs),
+            "List all registered skills, optionally filtered by category or language."
+        );
+        assert_eq!(list.localized_description(Locale::ZhCn), list.description());
+
         // 执行工具
         let result = registry
             .execute(
@@ -593,5 +990,149 @@ mod tests {
             .await;
 
         assert!(result.is_ok());
+
+        // 作为 AgentLoop 的工具执行器
+        let definitions = registry.definitions();
+        assert_eq!(definitions.len(), 5);
+        assert_eq!(definitions[0].function.name, "get_skill");
+        let output = registry
+            .call("list_skills", json!({"category": "Syntax"}))
+            .await
+            .unwrap();
+        assert!(serde_json::from_str::<ToolOutput>(&output).is_ok());
+        assert!(registry.call("missing", json!({})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_tool() {
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        storage
+            .write_file("lib.rs", b"fn a() {}\n\nfn b() {\n    1\n}\n")
+            .await
+            .unwrap();
+
+        let mut registry = SkillToolRegistry::new();
+        registry.register(Arc::new(ApplyPatchTool::new(storage.clone())));
+
+        // 行号错误的补丁仍可应用
+        let result = registry
+            .execute(
+                "apply_patch",
+                json!({
+                    "patch": "--- a/lib.rs\n+++ b/lib.rs\n@@ -10,3 +10,3 @@\n fn b() {\n-    1\n+    2\n }\n"
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.data.as_ref().unwrap()["applied"], true);
+        assert_eq!(
+            storage.read_file("lib.rs").await.unwrap(),
+            b"fn a() {}\n\nfn b() {\n    2\n}\n"
+        );
+
+        // 无法定位时不写入，并报告最接近的位置
+        let result = registry
+            .execute(
+                "apply_patch",
+                json!({
+                    "patch": "--- a/lib.rs\n+++ b/lib.rs\n@@ -3,3 +3,3 @@\n fn b() {\n-    3\n+    4\n }\n"
+                }),
+            )
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["applied"], false);
+        assert_eq!(data["conflicts"][0]["failures"][0]["closest"]["line"], 3);
+        assert_eq!(
+            storage.read_file("lib.rs").await.unwrap(),
+            b"fn a() {}\n\nfn b() {\n    2\n}\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_rejects_syntax_errors() {
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+        use crate::syntax::executor::ParserExecutor;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        storage
+            .write_file("config.json", b"{\n  \"a\": 1\n}\n")
+            .await
+            .unwrap();
+        let validator = Arc::new(SyntaxValidator::new(Arc::new(ParserExecutor::new())));
+        let tool = ApplyPatchTool::new(storage.clone()).with_validator(validator);
+
+        let result = tool
+            .execute(json!({
+                "patch": "--- a/config.json\n+++ b/config.json\n@@ -1,3 +1,3 @@\n {\n-  \"a\": 1\n+  \"a\": 1,\n }\n"
+            }))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["applied"], false);
+        assert_eq!(data["syntax_errors"][0]["path"], "config.json");
+        assert_eq!(data["syntax_errors"][0]["issues"][0]["line"], 3);
+        assert!(result.content.contains("config.json:3:"));
+        assert_eq!(
+            storage.read_file("config.json").await.unwrap(),
+            b"{\n  \"a\": 1\n}\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_renames_refuses_overwrite_and_rolls_back() {
+        use crate::common::provider::local::filesystem::LocalFileSystem;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(LocalFileSystem::new(dir.path()));
+        storage
+            .write_file("old.rs", b"fn a() {\n    1\n}\n")
+            .await
+            .unwrap();
+        storage.write_file("taken.rs", b"keep\n").await.unwrap();
+        storage.write_file("blocked", b"file\n").await.unwrap();
+        let tool = ApplyPatchTool::new(storage.clone());
+
+        // 重命名从原路径读取，写入新路径并删除原文件
+        let result = tool
+            .execute(json!({
+                "patch": "--- a/old.rs\n+++ b/new.rs\n@@ -1,3 +1,3 @@\n fn a() {\n-    1\n+    2\n }\n"
+            }))
+            .await
+            .unwrap();
+        assert_eq!(result.data.unwrap()["applied"], true);
+        assert_eq!(
+            storage.read_file("new.rs").await.unwrap(),
+            b"fn a() {\n    2\n}\n"
+        );
+        assert!(!storage.exists("old.rs").await.unwrap());
+
+        // 新建已存在的文件被拒绝
+        let result = tool
+            .execute(json!({
+                "patch": "--- /dev/null\n+++ b/taken.rs\n@@ -0,0 +1,1 @@\n+replaced\n"
+            }))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["applied"], false);
+        assert_eq!(data["existing"][0], "taken.rs");
+        assert_eq!(storage.read_file("taken.rs").await.unwrap(), b"keep\n");
+
+        // 后续文件写入失败时，已写入的文件恢复原内容
+        let result = tool
+            .execute(json!({
+                "patch": "--- a/new.rs\n+++ b/new.rs\n@@ -1,3 +1,3 @@\n fn a() {\n-    2\n+    3\n }\n--- /dev/null\n+++ b/blocked/inner.rs\n@@ -0,0 +1,1 @@\n+x\n"
+            }))
+            .await;
+        assert!(matches!(result, Err(SkillError::IoError(_))));
+        assert_eq!(
+            storage.read_file("new.rs").await.unwrap(),
+            b"fn a() {\n    2\n}\n"
+        );
     }
 }
@@ -1,3 +1,4 @@
+use crate::common::i18n::{Locale, Localize, translate};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fmt;
@@ -150,6 +151,27 @@ pub enum SkillError {
 
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+
+    /// 工具调用违反 Routine 的沙箱策略
+    #[error("forbidden by tool policy: {0}")]
+    Forbidden(String),
+}
+
+impl Localize for SkillError {
+    fn localize(&self, locale: Locale) -> String {
+        let (key, detail) = match self {
+            SkillError::InvalidSkill(d) => ("invalid", d.clone()),
+            SkillError::NotFound(d) => ("not_found", d.clone()),
+            SkillError::ParseError(d) => ("parse", d.clone()),
+            SkillError::IoError(e) => ("io", e.to_string()),
+            SkillError::Forbidden(d) => ("forbidden", d.clone()),
+        };
+        translate(
+            locale,
+            &format!("error.skill.{}", key),
+            &[("detail", &detail)],
+        )
+    }
 }
 
 #[cfg(test)]
@@ -1,10 +1,17 @@
+use crate::common::intent::IntentSubscription;
 use crate::common::meta::MetaNode;
+use crate::common::provider::watch::{FileChange, FileChangeKind};
 use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
 /// 管理增量解析的缓存
 pub struct IncrementalCache {
     cache: HashMap<Uuid, MetaNode>,
+    /// 文件路径 -> 文件 ID，供文件监听按路径失效
+    paths: HashMap<String, Uuid>,
 }
 
 impl Default for IncrementalCache {
@@ -17,6 +24,7 @@ impl IncrementalCache {
     pub fn new() -> Self {
         Self {
             cache: HashMap::new(),
+            paths: HashMap::new(),
         }
     }
 
@@ -34,11 +42,44 @@ impl IncrementalCache {
     pub fn invalidate(&mut self, file_id: &Uuid) {
         self.cache.remove(file_id);
     }
+
+    /// 记录文件 ID 对应的路径
+    pub fn bind_path(&mut self, path: &str, file_id: Uuid) {
+        self.paths.insert(path.to_string(), file_id);
+    }
+
+    /// 按路径清除缓存，返回是否清除了缓存的节点
+    pub fn invalidate_path(&mut self, path: &str) -> bool {
+        self.paths
+            .get(path)
+            .is_some_and(|id| self.cache.remove(id).is_some())
+    }
+
+    /// 文件变化时清除缓存，删除的文件同时解除路径绑定
+    pub fn on_file_changed(&mut self, change: &FileChange) -> bool {
+        let invalidated = self.invalidate_path(&change.path);
+        if change.kind == FileChangeKind::Removed {
+            self.paths.remove(&change.path);
+        }
+        invalidated
+    }
+
+    /// 按订阅到的文件变化意图（见 `FileChange::topic`）持续清除缓存，直到分发器被释放
+    pub fn follow(cache: Arc<RwLock<Self>>, mut changes: IntentSubscription) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(outcome) = changes.recv().await {
+                if let Some(change) = FileChange::from_outcome(&outcome) {
+                    cache.write().await.on_file_changed(&change);
+                }
+            }
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::intent::{EditorIntent, IntentDispatcher, SystemIntent};
 
     #[test]
     fn test_incremental_cache() {
@@ -52,4 +93,30 @@ mod tests {
         cache.invalidate(&file_id);
         assert!(cache.get(&file_id).is_none());
     }
+
+    #[tokio::test]
+    async fn test_file_changes_invalidate_by_path() {
+        let cache = Arc::new(RwLock::new(IncrementalCache::new()));
+        let file_id = Uuid::new_v4();
+        {
+            let mut cache = cache.write().await;
+            cache.bind_path("src/lib.rs", file_id);
+            cache.update(file_id, MetaNode::module("lib"));
+            assert!(!cache.invalidate_path("src/main.rs"));
+        }
+        let dispatcher = IntentDispatcher::new();
+        let follower =
+            IncrementalCache::follow(cache.clone(), dispatcher.subscribe(FileChange::topic()));
+
+        // 没有编辑器处理器时分发失败，缓存仍被清除
+        let _ = dispatcher
+            .dispatch(SystemIntent::Editor(EditorIntent::FileChanged {
+                path: "src/lib.rs".to_string(),
+                kind: FileChangeKind::Modified,
+            }))
+            .await;
+        drop(dispatcher);
+        follower.await.unwrap();
+        assert!(cache.read().await.get(&file_id).is_none());
+    }
 }
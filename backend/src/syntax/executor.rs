@@ -27,6 +27,11 @@ impl ParserExecutor {
         self.parsers.insert(language.to_string(), parser);
     }
 
+    /// 是否已注册该语言的解析器
+    pub fn supports(&self, language: &str) -> bool {
+        self.parsers.contains_key(language)
+    }
+
     /// 执行解析
     pub async fn parse(&self, language: &str, source: &str) -> Result<MetaNode> {
         let parser = self
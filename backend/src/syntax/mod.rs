@@ -2,8 +2,12 @@ pub mod cache;
 pub mod engine;
 pub mod executor;
 pub mod loader;
+pub mod query;
+pub mod validate;
 
 pub use cache::IncrementalCache;
 pub use engine::interface::Parser;
 pub use executor::ParserExecutor;
 pub use loader::GrammarLoader;
+pub use query::{QueryMatch, SyntaxQuery};
+pub use validate::{SyntaxError, SyntaxErrors, SyntaxValidator, ValidationReport};
@@ -0,0 +1,249 @@
+use crate::common::meta::MetaNode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// 语言无关的元 AST 查询
+///
+/// 名称模式支持 `*` 通配符；限定名（如 `axum::Server::bind`）与路径写法的
+/// 标识符按完整文本匹配，模式以 `*` 开头时可匹配任意前缀（如 `*Server::bind`）。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyntaxQuery {
+    /// 被调用者为匹配名称的调用
+    Call(String),
+    /// 对匹配名称的任意引用（标识符或被调用者）
+    Identifier(String),
+    /// 名称匹配的函数定义
+    Function(String),
+    /// 名称匹配的类定义
+    Class(String),
+    /// 继承或实现了匹配名称的类
+    Base(String),
+}
+
+/// 查询命中的节点
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryMatch {
+    pub node_id: Uuid,
+    /// 命中的名称文本
+    pub name: String,
+    /// 所在的（或自身即为）函数/类定义名称
+    pub symbol: Option<String>,
+    /// 最近一个带 `start_line` / `end_line` 元数据的祖先节点的行范围
+    pub start_line: Option<u32>,
+    pub end_line: Option<u32>,
+}
+
+/// 遍历时的外层定义信息
+#[derive(Clone, Copy, Default)]
+struct Scope<'a> {
+    symbol: Option<&'a str>,
+    lines: Option<(u32, u32)>,
+}
+
+impl SyntaxQuery {
+    /// 在整棵树中查找匹配的节点（先序）
+    pub fn find(&self, root: &MetaNode) -> Vec<QueryMatch> {
+        let mut matches = Vec::new();
+        self.visit(root, Scope::default(), &mut matches);
+        matches
+    }
+
+    fn visit<'a>(&self, node: &'a MetaNode, scope: Scope<'a>, out: &mut Vec<QueryMatch>) {
+        let mut hit = |id: Uuid, name: &str, scope: Scope| {
+            out.push(QueryMatch {
+                node_id: id,
+                name: name.to_string(),
+                symbol: scope.symbol.map(str::to_string),
+                start_line: scope.lines.map(|l| l.0),
+                end_line: scope.lines.map(|l| l.1),
+            })
+        };
+        match node {
+            MetaNode::Module {
+                children, metadata, ..
+            } => {
+                let scope = Scope {
+                    lines: lines(metadata).or(scope.lines),
+                    ..scope
+                };
+                for child in children {
+                    self.visit(child, scope, out);
+                }
+            }
+            MetaNode::Function {
+                id,
+                name,
+                params,
+                body,
+                metadata,
+            } => {
+                let scope = Scope {
+                    symbol: Some(name),
+                    lines: lines(metadata).or(scope.lines),
+                };
+                if let SyntaxQuery::Function(pattern) = self
+                    && matches(pattern, name)
+                {
+                    hit(*id, name, scope);
+                }
+                for child in params.iter().chain(body.as_deref()) {
+                    self.visit(child, scope, out);
+                }
+            }
+            MetaNode::Class {
+                id,
+                name,
+                members,
+                bases,
+                metadata,
+            } => {
+                let scope = Scope {
+                    symbol: Some(name),
+                    lines: lines(metadata).or(scope.lines),
+                };
+                match self {
+                    SyntaxQuery::Class(pattern) if matches(pattern, name) => hit(*id, name, scope),
+                    SyntaxQuery::Base(pattern) => {
+                        for base in bases.iter().filter(|b| matches(pattern, b)) {
+                            hit(*id, base, scope);
+                        }
+                    }
+                    _ => {}
+                }
+                for member in members {
+                    self.visit(member, scope, out);
+                }
+            }
+            MetaNode::Declaration {
+                value, metadata, ..
+            } => {
+                let scope = Scope {
+                    lines: lines(metadata).or(scope.lines),
+                    ..scope
+                };
+                if let Some(value) = value {
+                    self.visit(value, scope, out);
+                }
+            }
+            MetaNode::Assignment { target, value, .. } => {
+                self.visit(target, scope, out);
+                self.visit(value, scope, out);
+            }
+            MetaNode::Call { id, callee, args } => {
+                if let (SyntaxQuery::Call(pattern), MetaNode::Identifier { name, .. }) =
+                    (self, callee.as_ref())
+                    && matches(pattern, name)
+                {
+                    hit(*id, name, scope);
+                }
+                self.visit(callee, scope, out);
+                for arg in args {
+                    self.visit(arg, scope, out);
+                }
+            }
+            MetaNode::Identifier { id, name, .. } => {
+                if let SyntaxQuery::Identifier(pattern) = self
+                    && matches(pattern, name)
+                {
+                    hit(*id, name, scope);
+                }
+            }
+            MetaNode::Block { statements, .. } => {
+                for statement in statements {
+                    self.visit(statement, scope, out);
+                }
+            }
+            MetaNode::Literal { .. } | MetaNode::Extension { .. } => {}
+        }
+    }
+}
+
+fn lines(metadata: &HashMap<String, Value>) -> Option<(u32, u32)> {
+    let line = |key: &str| metadata.get(key).and_then(Value::as_u64).map(|l| l as u32);
+    Some((line("start_line")?, line("end_line")?))
+}
+
+/// `*` 通配符匹配
+pub(crate) fn matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tree() -> MetaNode {
+        serde_json::from_value(json!({
+            "type": "module",
+            "name": "main",
+            "children": [
+                {"type": "function", "name": "serve", "params": [],
+                 "start_line": 3, "end_line": 8,
+                 "body": {"type": "block", "statements": [
+                     {"type": "call", "args": [],
+                      "callee": {"type": "identifier", "name": "axum::Server::bind"}},
+                     {"type": "declaration", "name": "body", "kind": "let",
+                      "value": {"type": "identifier", "name": "axum::body::BoxBody"}}
+                 ]}},
+                {"type": "class", "name": "Api", "members": [], "bases": ["FromRequest"],
+                 "start_line": 10, "end_line": 12}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_wildcard_matching() {
+        assert!(matches("*Server::bind", "axum::Server::bind"));
+        assert!(matches("axum::*", "axum::body::BoxBody"));
+        assert!(matches("*::BoxBody", "axum::body::BoxBody"));
+        assert!(matches("a*c*e", "abcde"));
+        assert!(!matches("Server::bind", "axum::Server::bind"));
+        assert!(!matches("a*a", "a"));
+    }
+
+    #[test]
+    fn test_queries_report_enclosing_symbol_and_lines() {
+        let tree = tree();
+        let calls = SyntaxQuery::Call("*Server::bind".to_string()).find(&tree);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].symbol.as_deref(), Some("serve"));
+        assert_eq!((calls[0].start_line, calls[0].end_line), (Some(3), Some(8)));
+
+        // 被调用者本身也是标识符引用
+        let refs = SyntaxQuery::Identifier("axum::*".to_string()).find(&tree);
+        let names: Vec<&str> = refs.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["axum::Server::bind", "axum::body::BoxBody"]);
+
+        let bases = SyntaxQuery::Base("FromRequest".to_string()).find(&tree);
+        assert_eq!(bases[0].symbol.as_deref(), Some("Api"));
+        assert_eq!(bases[0].start_line, Some(10));
+        assert!(
+            SyntaxQuery::Function("main".to_string())
+                .find(&tree)
+                .is_empty()
+        );
+
+        let query: SyntaxQuery = serde_json::from_value(json!({"call": "*::bind"})).unwrap();
+        assert_eq!(query.find(&tree).len(), 1);
+    }
+}
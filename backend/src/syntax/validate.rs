@@ -0,0 +1,256 @@
+use crate::syntax::executor::ParserExecutor;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 单个语法错误位置（行列从 1 开始，0 表示未知）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("{line}:{column}: {message}")]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl SyntaxError {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+/// 解析器报告的语法错误集合
+///
+/// 解析器插件以 `anyhow::Error` 返回该类型时，校验器可取得精确位置；
+/// 其他错误将作为位置未知的单条错误处理。
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{} syntax error(s)", .0.len())]
+pub struct SyntaxErrors(pub Vec<SyntaxError>);
+
+/// 一次写入前校验的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub path: String,
+    /// 未识别的语言为 `None`，此时不做校验
+    pub language: Option<String>,
+    pub issues: Vec<SyntaxError>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// 供模型修正的提示：每个错误附带出错行及其上下文，并用 `^` 标出列
+    pub fn hint(&self, content: &str) -> String {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut out = format!(
+            "{}: {} syntax error(s) in {} code",
+            self.path,
+            self.issues.len(),
+            self.language.as_deref().unwrap_or("unknown")
+        );
+        for issue in &self.issues {
+            out.push_str(&format!("\n\n{}:{}\n", self.path, issue));
+            if issue.line == 0 || issue.line > lines.len() {
+                continue;
+            }
+            let first = issue.line.saturating_sub(2).max(1);
+            let last = (issue.line + 1).min(lines.len());
+            let width = last.to_string().len();
+            for n in first..=last {
+                out.push_str(&format!("{:>width$} | {}\n", n, lines[n - 1]));
+                if n == issue.line && issue.column > 0 {
+                    out.push_str(&format!(
+                        "{:>width$} | {}^\n",
+                        "",
+                        " ".repeat(issue.column - 1)
+                    ));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// 写入前的语法校验：按扩展名确定语言并交由语法引擎解析
+///
+/// 已注册解析器的语言交给 `ParserExecutor`；JSON 与 YAML 内置校验；
+/// 其余语言不做检查，避免阻塞无法解析的文件。
+pub struct SyntaxValidator {
+    executor: Arc<ParserExecutor>,
+    languages: HashMap<String, String>,
+}
+
+impl SyntaxValidator {
+    pub fn new(executor: Arc<ParserExecutor>) -> Self {
+        let languages = [
+            ("rs", "rust"),
+            ("py", "python"),
+            ("js", "javascript"),
+            ("mjs", "javascript"),
+            ("jsx", "javascript"),
+            ("ts", "typescript"),
+            ("tsx", "typescript"),
+            ("go", "go"),
+            ("java", "java"),
+            ("kt", "kotlin"),
+            ("c", "c"),
+            ("h", "c"),
+            ("cc", "cpp"),
+            ("cpp", "cpp"),
+            ("hpp", "cpp"),
+            ("cs", "csharp"),
+            ("rb", "ruby"),
+            ("swift", "swift"),
+            ("json", "json"),
+            ("yaml", "yaml"),
+            ("yml", "yaml"),
+        ]
+        .into_iter()
+        .map(|(ext, lang)| (ext.to_string(), lang.to_string()))
+        .collect();
+        Self {
+            executor,
+            languages,
+        }
+    }
+
+    /// 将扩展名（不含 `.`）映射到解析器语言
+    pub fn with_language(mut self, extension: &str, language: &str) -> Self {
+        self.languages
+            .insert(extension.to_lowercase(), language.to_string());
+        self
+    }
+
+    pub fn language_of(&self, path: &str) -> Option<&str> {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let (_, ext) = name.rsplit_once('.')?;
+        self.languages.get(&ext.to_lowercase()).map(String::as_str)
+    }
+
+    pub async fn validate(&self, path: &str, content: &str) -> ValidationReport {
+        let language = self.language_of(path).map(str::to_string);
+        let issues = match language.as_deref() {
+            Some(lang) if self.executor.supports(lang) => {
+                match self.executor.parse(lang, content).await {
+                    Ok(_) => Vec::new(),
+                    Err(e) => match e.downcast::<SyntaxErrors>() {
+                        Ok(SyntaxErrors(errors)) => errors,
+                        Err(e) => vec![SyntaxError::new(0, 0, e.to_string())],
+                    },
+                }
+            }
+            Some("json") => serde_json::from_str::<serde_json::Value>(content)
+                .err()
+                .map(|e| SyntaxError::new(e.line(), e.column(), e.to_string()))
+                .into_iter()
+                .collect(),
+            Some("yaml") => validate_yaml(content),
+            _ => Vec::new(),
+        };
+        ValidationReport {
+            path: path.to_string(),
+            language,
+            issues,
+        }
+    }
+}
+
+fn validate_yaml(content: &str) -> Vec<SyntaxError> {
+    let mut issues = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(content) {
+        if let Err(e) = serde_yaml::Value::deserialize(document) {
+            let (line, column) = e
+                .location()
+                .map(|l| (l.line(), l.column()))
+                .unwrap_or((0, 0));
+            issues.push(SyntaxError::new(line, column, e.to_string()));
+            break;
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::meta::MetaNode;
+    use crate::syntax::engine::interface::Parser;
+    use async_trait::async_trait;
+
+    /// 仅检查括号配对的解析器
+    struct BraceParser;
+
+    #[async_trait]
+    impl Parser for BraceParser {
+        fn language(&self) -> &str {
+            "rust"
+        }
+        async fn parse(&self, source: &str) -> anyhow::Result<MetaNode> {
+            let mut open = Vec::new();
+            for (i, line) in source.lines().enumerate() {
+                for (j, c) in line.chars().enumerate() {
+                    match c {
+                        '{' => open.push((i + 1, j + 1)),
+                        '}' if open.pop().is_none() => {
+                            return Err(SyntaxErrors(vec![SyntaxError::new(
+                                i + 1,
+                                j + 1,
+                                "unexpected `}`",
+                            )])
+                            .into());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            match open.pop() {
+                Some((line, column)) => {
+                    Err(SyntaxErrors(vec![SyntaxError::new(line, column, "unclosed `{`")]).into())
+                }
+                None => Ok(MetaNode::module("main")),
+            }
+        }
+        async fn load_scm(&self, _name: &str, _content: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_syntax_validator() {
+        let mut executor = ParserExecutor::new();
+        executor.register_parser("rust", Arc::new(BraceParser));
+        let validator = SyntaxValidator::new(Arc::new(executor));
+
+        assert!(
+            validator
+                .validate("src/lib.rs", "fn a() {}\n")
+                .await
+                .is_valid()
+        );
+
+        let source = "fn a() {\n    1\n\nfn b() {}\n";
+        let report = validator.validate("src/lib.rs", source).await;
+        assert_eq!(report.language.as_deref(), Some("rust"));
+        assert_eq!(report.issues, vec![SyntaxError::new(1, 8, "unclosed `{`")]);
+        let hint = report.hint(source);
+        assert!(hint.contains("src/lib.rs:1:8: unclosed `{`"));
+        assert!(hint.contains("1 | fn a() {\n  |        ^\n"));
+
+        // 内置 JSON 校验给出行列
+        let report = validator
+            .validate("package.json", "{\n  \"a\": 1,\n}\n")
+            .await;
+        assert_eq!(report.issues[0].line, 3);
+
+        let report = validator.validate("ci.yml", "a: [1, 2\n").await;
+        assert!(!report.is_valid());
+
+        // 未知语言不校验
+        assert!(validator.validate("notes.txt", "{{{").await.is_valid());
+    }
+}
@@ -0,0 +1,362 @@
+use crate::agent::{AgentIntent, ReviewIntent, TeamIntent};
+use crate::common::intent::{IntentDispatcher, IntentOrigin, SystemIntent};
+use crate::editor::EditorIntent;
+use crate::tenant::identity::{Identity, TenantError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, RwLock};
+
+/// 用户角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// 只读：查看文件、差异与诊断
+    Viewer,
+    /// 编辑：修改文件并提交变更
+    Editor,
+    /// Agent 操作员：调用工具（含 Shell）并控制 Agent
+    AgentOperator,
+    /// 管理员：全部权限
+    Admin,
+}
+
+/// 可授予的权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// 打开文件、切换 Tab、查看资源
+    Read,
+    /// 写入或删除文件、编辑单元格
+    Write,
+    /// 保存（提交 Change）
+    Commit,
+    /// 调用只读工具
+    ReadTools,
+    /// 调用会修改状态的工具
+    WriteTools,
+    /// 调用执行命令的工具
+    ShellTools,
+    /// 中止 Agent
+    ControlAgents,
+    /// 管理密钥并处理密钥授权请求
+    ManageSecrets,
+}
+
+/// 工具的风险等级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolClass {
+    Read,
+    Write,
+    Shell,
+}
+
+impl ToolClass {
+    fn permission(self) -> Permission {
+        match self {
+            ToolClass::Read => Permission::ReadTools,
+            ToolClass::Write => Permission::WriteTools,
+            ToolClass::Shell => Permission::ShellTools,
+        }
+    }
+}
+
+/// 基于角色的访问策略
+///
+/// 未分类的工具按 `ToolClass::Write` 处理，新增工具默认不对只读用户开放。
+pub struct AccessPolicy {
+    grants: RwLock<HashMap<Role, BTreeSet<Permission>>>,
+    tools: RwLock<HashMap<String, ToolClass>>,
+}
+
+impl Default for AccessPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AccessPolicy {
+    /// 默认策略：viewer ⊂ editor，agent-operator 在只读基础上可调用全部工具，admin 拥有全部权限
+    pub fn new() -> Self {
+        use Permission::*;
+        let viewer = BTreeSet::from([Read, ReadTools]);
+        let editor = BTreeSet::from([Read, Write, Commit, ReadTools, WriteTools]);
+        let operator = BTreeSet::from([Read, ReadTools, WriteTools, ShellTools, ControlAgents]);
+        let admin = BTreeSet::from([
+            Read,
+            Write,
+            Commit,
+            ReadTools,
+            WriteTools,
+            ShellTools,
+            ControlAgents,
+            ManageSecrets,
+        ]);
+
+        let tools = [
+            ("search_skills", ToolClass::Read),
+            ("get_skill", ToolClass::Read),
+            ("list_skills", ToolClass::Read),
+            ("inject_skills", ToolClass::Read),
+            ("register_skill", ToolClass::Write),
+            ("apply_patch", ToolClass::Write),
+            ("write_file", ToolClass::Write),
+            // 会启动进程或访问网络的工具
+            ("dev_server", ToolClass::Shell),
+            ("generate_tests", ToolClass::Shell),
+            ("tail_logs", ToolClass::Shell),
+            ("http_request", ToolClass::Shell),
+        ]
+        .into_iter()
+        .map(|(name, class)| (name.to_string(), class))
+        .collect();
+
+        Self {
+            grants: RwLock::new(HashMap::from([
+                (Role::Viewer, viewer),
+                (Role::Editor, editor),
+                (Role::AgentOperator, operator),
+                (Role::Admin, admin),
+            ])),
+            tools: RwLock::new(tools),
+        }
+    }
+
+    /// 为角色授予权限
+    pub fn grant(&self, role: Role, permission: Permission) {
+        self.grants
+            .write()
+            .unwrap()
+            .entry(role)
+            .or_default()
+            .insert(permission);
+    }
+
+    /// 撤销角色的权限
+    pub fn revoke(&self, role: Role, permission: Permission) {
+        if let Some(permissions) = self.grants.write().unwrap().get_mut(&role) {
+            permissions.remove(&permission);
+        }
+    }
+
+    /// 声明工具的风险等级
+    pub fn classify_tool(&self, name: &str, class: ToolClass) {
+        self.tools.write().unwrap().insert(name.to_string(), class);
+    }
+
+    pub fn tool_class(&self, name: &str) -> ToolClass {
+        self.tools
+            .read()
+            .unwrap()
+            .get(name)
+            .copied()
+            .unwrap_or(ToolClass::Write)
+    }
+
+    /// 身份是否拥有某项权限（任一角色授予即可）
+    pub fn allows(&self, identity: &Identity, permission: Permission) -> bool {
+        let grants = self.grants.read().unwrap();
+        identity
+            .roles
+            .iter()
+            .any(|role| grants.get(role).is_some_and(|p| p.contains(&permission)))
+    }
+
+    /// 意图所需的权限
+    pub fn required_permission(&self, intent: &SystemIntent) -> Permission {
+        match intent {
+            SystemIntent::Editor(intent) => match intent {
+                EditorIntent::OpenFile { .. }
+                | EditorIntent::SwitchTab { .. }
+                | EditorIntent::SplitGroup { .. }
+                | EditorIntent::MoveTab { .. }
+                | EditorIntent::PinTab { .. }
+                | EditorIntent::CloseTab { .. }
+                | EditorIntent::UpdateCursor { .. }
+                | EditorIntent::InspectAsset { .. }
+                | EditorIntent::FileChanged { .. }
+                | EditorIntent::Diff { .. } => Permission::Read,
+                EditorIntent::WriteFile { .. }
+                | EditorIntent::InsertText { .. }
+                | EditorIntent::DeleteRange { .. }
+                | EditorIntent::ReplaceRange { .. }
+                | EditorIntent::EditCell { .. }
+                | EditorIntent::DeleteFile { .. } => Permission::Write,
+                EditorIntent::ReplaceAll { .. }
+                | EditorIntent::Save
+                | EditorIntent::Undo
+                | EditorIntent::Redo => Permission::Commit,
+            },
+            SystemIntent::Agent(intent) => match intent {
+                AgentIntent::CallTool { name, .. } => self.tool_class(name).permission(),
+                AgentIntent::Abort => Permission::ControlAgents,
+                AgentIntent::GuardrailExceeded { .. } | AgentIntent::RequestApproval { .. } => {
+                    Permission::Read
+                }
+                AgentIntent::RunTests { .. } => Permission::ShellTools,
+                AgentIntent::Explain { .. } => Permission::ReadTools,
+            },
+            SystemIntent::Secret(_) => Permission::ManageSecrets,
+            SystemIntent::Review(intent) => match intent {
+                ReviewIntent::Approve { .. } | ReviewIntent::Reject { .. } => Permission::Commit,
+                ReviewIntent::Comment { .. } => Permission::Read,
+            },
+            SystemIntent::Approval(_) => Permission::ControlAgents,
+            SystemIntent::Team(intent) => match intent {
+                TeamIntent::Message(_) | TeamIntent::Submit { .. } => Permission::Read,
+                TeamIntent::Accept { .. } | TeamIntent::Reject { .. } => Permission::ControlAgents,
+            },
+        }
+    }
+
+    /// 检查身份能否发起意图
+    pub fn check_intent(
+        &self,
+        identity: &Identity,
+        intent: &SystemIntent,
+    ) -> Result<(), TenantError> {
+        let permission = self.required_permission(intent);
+        self.check(identity, permission)
+    }
+
+    /// 检查身份能否直接调用工具
+    pub fn check_tool(&self, identity: &Identity, name: &str) -> Result<(), TenantError> {
+        self.check(identity, self.tool_class(name).permission())
+    }
+
+    fn check(&self, identity: &Identity, permission: Permission) -> Result<(), TenantError> {
+        if self.allows(identity, permission) {
+            Ok(())
+        } else {
+            Err(TenantError::Forbidden {
+                user: identity.user.clone(),
+                permission,
+            })
+        }
+    }
+}
+
+/// 在分发前按调用者角色校验意图的分发器
+pub struct GuardedDispatcher {
+    dispatcher: Arc<IntentDispatcher>,
+    policy: Arc<AccessPolicy>,
+}
+
+impl GuardedDispatcher {
+    pub fn new(dispatcher: Arc<IntentDispatcher>, policy: Arc<AccessPolicy>) -> Self {
+        Self { dispatcher, policy }
+    }
+
+    pub fn policy(&self) -> &Arc<AccessPolicy> {
+        &self.policy
+    }
+
+    /// 底层分发器，用于注册处理器与订阅结果
+    pub fn inner(&self) -> &Arc<IntentDispatcher> {
+        &self.dispatcher
+    }
+
+    /// 以 `identity` 的身份分发意图，无权限时不会到达处理器
+    pub async fn dispatch(&self, identity: &Identity, intent: SystemIntent) -> anyhow::Result<()> {
+        self.policy.check_intent(identity, &intent)?;
+        self.dispatcher
+            .dispatch_as(IntentOrigin::user(&identity.user), intent)
+            .await
+    }
+
+    /// 以 `identity` 的身份分发意图并返回处理器的输出（见 `IntentDispatcher::request`）
+    pub async fn request<T: serde::de::DeserializeOwned>(
+        &self,
+        identity: &Identity,
+        intent: SystemIntent,
+    ) -> anyhow::Result<T> {
+        self.policy.check_intent(identity, &intent)?;
+        self.dispatcher
+            .request_as(IntentOrigin::user(&identity.user), intent)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::intent::{IntentCategory, IntentHandler};
+    use crate::tenant::identity::TenantId;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn identity(roles: &[Role]) -> Identity {
+        Identity {
+            user: "user".to_string(),
+            tenant: TenantId::new("team"),
+            roles: roles.iter().copied().collect(),
+        }
+    }
+
+    struct Counter(AtomicUsize);
+
+    #[async_trait]
+    impl IntentHandler for Counter {
+        async fn handle(&self, _intent: SystemIntent) -> anyhow::Result<()> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_roles_gate_intents_and_tools() {
+        let policy = Arc::new(AccessPolicy::new());
+        policy.classify_tool("run_command", ToolClass::Shell);
+        let viewer = identity(&[Role::Viewer]);
+        let editor = identity(&[Role::Editor]);
+        let operator = identity(&[Role::AgentOperator]);
+
+        let dispatcher = Arc::new(IntentDispatcher::new());
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        dispatcher
+            .register(IntentCategory::Editor, counter.clone())
+            .await;
+        dispatcher
+            .register(IntentCategory::Agent, counter.clone())
+            .await;
+        let guarded = GuardedDispatcher::new(dispatcher, policy.clone());
+
+        let open = SystemIntent::Editor(EditorIntent::OpenFile {
+            path: "a.rs".to_string(),
+        });
+        let shell = SystemIntent::Agent(AgentIntent::CallTool {
+            name: "run_command".to_string(),
+            args: "{}".to_string(),
+        });
+        guarded.dispatch(&viewer, open).await.unwrap();
+        assert!(
+            guarded
+                .dispatch(&viewer, SystemIntent::Editor(EditorIntent::Save))
+                .await
+                .is_err()
+        );
+        assert!(guarded.dispatch(&viewer, shell.clone()).await.is_err());
+        assert!(guarded.dispatch(&editor, shell.clone()).await.is_err());
+        guarded.dispatch(&operator, shell).await.unwrap();
+        guarded
+            .dispatch(&editor, SystemIntent::Editor(EditorIntent::Save))
+            .await
+            .unwrap();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 3);
+
+        assert!(policy.check_tool(&viewer, "list_skills").is_ok());
+        assert!(policy.check_tool(&viewer, "apply_patch").is_err());
+        // 未分类的工具按写入处理
+        assert!(policy.check_tool(&viewer, "unknown_tool").is_err());
+        assert!(policy.check_tool(&editor, "unknown_tool").is_ok());
+        for tool in ["dev_server", "generate_tests", "tail_logs", "http_request"] {
+            assert!(policy.check_tool(&editor, tool).is_err(), "{}", tool);
+            assert!(policy.check_tool(&operator, tool).is_ok(), "{}", tool);
+        }
+        assert!(
+            policy
+                .check_tool(&identity(&[Role::Admin]), "run_command")
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use crate::tenant::access::{Permission, Role};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::RwLock;
+
+/// 租户标识符
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TenantId(pub String);
+
+impl TenantId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for TenantId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// 经过认证的调用者身份
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+    /// 用户名（在租户内唯一）
+    pub user: String,
+    /// 所属租户
+    pub tenant: TenantId,
+    /// 角色（见 `AccessPolicy`）
+    #[serde(default)]
+    pub roles: BTreeSet<Role>,
+}
+
+/// 认证与租户错误
+#[derive(Debug, thiserror::Error)]
+pub enum TenantError {
+    #[error("authentication failed")]
+    Unauthenticated,
+
+    #[error("unknown tenant: {0}")]
+    UnknownTenant(TenantId),
+
+    #[error("tenant already exists: {0}")]
+    DuplicateTenant(TenantId),
+
+    #[error("invalid tenant id: {0}")]
+    InvalidTenantId(String),
+
+    #[error("provider {provider} unavailable: {error}")]
+    ProviderUnavailable { provider: String, error: String },
+
+    #[error("{user} lacks permission {permission:?}")]
+    Forbidden {
+        user: String,
+        permission: Permission,
+    },
+}
+
+/// 将 API 服务器收到的凭据解析为身份
+pub trait Authenticator: Send + Sync {
+    fn authenticate(&self, token: &str) -> Result<Identity, TenantError>;
+}
+
+/// 基于静态 API 令牌的认证器
+///
+/// 仅保存令牌的 SHA-256 摘要，不保存令牌明文。
+#[derive(Default)]
+pub struct TokenAuthenticator {
+    tokens: RwLock<HashMap<String, Identity>>,
+}
+
+impl TokenAuthenticator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为身份签发令牌（重复签发会覆盖同一令牌之前的身份）
+    pub fn issue(&self, token: &str, identity: Identity) {
+        self.tokens.write().unwrap().insert(digest(token), identity);
+    }
+
+    /// 吊销令牌，返回其对应的身份
+    pub fn revoke(&self, token: &str) -> Option<Identity> {
+        self.tokens.write().unwrap().remove(&digest(token))
+    }
+
+    /// 吊销某个租户的全部令牌
+    pub fn revoke_tenant(&self, tenant: &TenantId) {
+        self.tokens
+            .write()
+            .unwrap()
+            .retain(|_, identity| &identity.tenant != tenant);
+    }
+}
+
+impl Authenticator for TokenAuthenticator {
+    fn authenticate(&self, token: &str) -> Result<Identity, TenantError> {
+        let token = token.strip_prefix("Bearer ").unwrap_or(token).trim();
+        self.tokens
+            .read()
+            .unwrap()
+            .get(&digest(token))
+            .cloned()
+            .ok_or(TenantError::Unauthenticated)
+    }
+}
+
+fn digest(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_authenticator() {
+        let auth = TokenAuthenticator::new();
+        let alice = Identity {
+            user: "alice".to_string(),
+            tenant: TenantId::new("team-a"),
+            roles: BTreeSet::from([Role::Viewer]),
+        };
+        auth.issue("secret-a", alice.clone());
+
+        assert_eq!(auth.authenticate("Bearer secret-a").unwrap(), alice);
+        assert!(matches!(
+            auth.authenticate("secret-b"),
+            Err(TenantError::Unauthenticated)
+        ));
+
+        auth.revoke_tenant(&TenantId::new("team-a"));
+        assert!(auth.authenticate("secret-a").is_err());
+    }
+}
@@ -0,0 +1,501 @@
+use crate::common::change::thread::ThreadManager;
+use crate::common::endpoint::stream::{Endpoint, ProviderConfig};
+use crate::common::endpoint::{ModelRegistry, ProviderInfo, UsageLedger};
+use crate::common::intent::{IntentDispatcher, SystemIntent};
+use crate::common::lifecycle::{LazySubsystem, StartupProfiler};
+use crate::common::provider::traits::StorageProvider;
+use crate::editor::SessionManager;
+use crate::knowledge::retriever::Retriever;
+use crate::knowledge::store::VectorStore;
+use crate::project::index::WorkspaceIndex;
+use crate::tenant::access::{AccessPolicy, GuardedDispatcher};
+use crate::tenant::identity::{Authenticator, Identity, TenantError, TenantId};
+use crate::tenant::storage::NamespacedStorage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// 租户数据在共享存储中的根目录
+const TENANTS_ROOT: &str = "tenants";
+
+/// 租户配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantConfig {
+    pub id: TenantId,
+    pub name: String,
+    /// 租户自己的模型提供者配置（API Key 不在租户之间共享）
+    #[serde(default)]
+    pub providers: Vec<ProviderConfig>,
+}
+
+/// 租户运行时：每个租户拥有独立的线程、会话、模型配置、知识库、费用统计与意图分发器
+pub struct Tenant {
+    config: TenantConfig,
+    storage: Arc<dyn StorageProvider>,
+    threads: Arc<ThreadManager>,
+    sessions: RwLock<SessionManager>,
+    models: RwLock<ModelRegistry>,
+    knowledge: Arc<RwLock<VectorStore>>,
+    usage: Arc<UsageLedger>,
+    dispatcher: GuardedDispatcher,
+    /// 工作区符号索引，首次使用时才扫描租户存储
+    workspace: LazySubsystem<Arc<RwLock<WorkspaceIndex>>>,
+    startup: Arc<StartupProfiler>,
+}
+
+impl Tenant {
+    /// 创建租户运行时，并通过各提供者的客户端发现其模型
+    ///
+    /// 模型发现计入启动报告；工作区索引推迟到首次使用时建立。
+    async fn new(
+        config: TenantConfig,
+        storage: Arc<dyn StorageProvider>,
+        policy: Arc<AccessPolicy>,
+    ) -> Result<Self, TenantError> {
+        let startup = Arc::new(StartupProfiler::new());
+        let threads = Arc::new(ThreadManager::new());
+        let usage = Arc::new(UsageLedger::new());
+        let mut models = ModelRegistry::new().with_ledger(usage.clone());
+        for provider in &config.providers {
+            let info = ProviderInfo {
+                id: provider.name.clone(),
+                name: provider.name.clone(),
+                base_url: provider.base_url.clone(),
+            };
+            let client = Arc::new(Endpoint::from_config(provider.clone()));
+            startup
+                .phase(&format!("provider_catalog:{}", provider.name), async {
+                    Ok(models.add_provider(info, client).await?)
+                })
+                .await
+                .map_err(|e| TenantError::ProviderUnavailable {
+                    provider: provider.name.clone(),
+                    error: e.to_string(),
+                })?;
+        }
+        let workspace = LazySubsystem::new("workspace_index", {
+            let storage = storage.clone();
+            move || {
+                let storage = storage.clone();
+                async move {
+                    let index = WorkspaceIndex::scan(storage.as_ref(), "").await?;
+                    Ok(Arc::new(RwLock::new(index)))
+                }
+            }
+        })
+        .with_profiler(startup.clone());
+        startup.finish();
+        Ok(Self {
+            workspace,
+            startup,
+            sessions: RwLock::new(SessionManager::new(threads.clone())),
+            threads,
+            models: RwLock::new(models),
+            knowledge: Arc::new(RwLock::new(VectorStore::new())),
+            usage,
+            dispatcher: GuardedDispatcher::new(Arc::new(IntentDispatcher::new()), policy),
+            storage,
+            config,
+        })
+    }
+
+    pub fn id(&self) -> &TenantId {
+        &self.config.id
+    }
+
+    pub fn config(&self) -> &TenantConfig {
+        &self.config
+    }
+
+    /// 限定在租户命名空间内的存储
+    pub fn storage(&self) -> Arc<dyn StorageProvider> {
+        self.storage.clone()
+    }
+
+    pub fn threads(&self) -> &Arc<ThreadManager> {
+        &self.threads
+    }
+
+    pub fn sessions(&self) -> &RwLock<SessionManager> {
+        &self.sessions
+    }
+
+    pub fn models(&self) -> &RwLock<ModelRegistry> {
+        &self.models
+    }
+
+    pub fn knowledge(&self) -> &Arc<RwLock<VectorStore>> {
+        &self.knowledge
+    }
+
+    /// 只检索本租户知识的检索器（租户命名空间内的存储与租户自己的向量库）
+    pub fn retriever(&self) -> Retriever {
+        Retriever::new(self.storage.clone()).with_store(self.knowledge.clone())
+    }
+
+    /// 本租户的意图分发器，意图在到达处理器前按调用者角色校验
+    pub fn dispatcher(&self) -> &GuardedDispatcher {
+        &self.dispatcher
+    }
+
+    /// 本租户的用量账本（租户 `ModelRegistry` 的调用自动记入）
+    pub fn usage(&self) -> &Arc<UsageLedger> {
+        &self.usage
+    }
+
+    /// 工作区符号索引；首次调用时扫描租户存储建立，耗时计入启动报告
+    pub async fn workspace(&self) -> anyhow::Result<&Arc<RwLock<WorkspaceIndex>>> {
+        self.workspace.get().await
+    }
+
+    /// 租户的启动耗时分析器（含首次使用时才初始化的子系统）
+    pub fn startup(&self) -> &Arc<StartupProfiler> {
+        &self.startup
+    }
+}
+
+/// 租户管理器：按认证身份将请求路由到对应租户
+pub struct TenantManager {
+    storage: Arc<dyn StorageProvider>,
+    authenticator: Arc<dyn Authenticator>,
+    /// 所有租户共用的角色权限策略
+    policy: Arc<AccessPolicy>,
+    tenants: std::sync::RwLock<HashMap<TenantId, Arc<Tenant>>>,
+}
+
+impl TenantManager {
+    /// `storage` 为所有租户共享的底层存储，每个租户只能访问 `tenants/<id>/` 下的内容
+    pub fn new(storage: Arc<dyn StorageProvider>, authenticator: Arc<dyn Authenticator>) -> Self {
+        Self {
+            storage,
+            authenticator,
+            policy: Arc::new(AccessPolicy::new()),
+            tenants: std::sync::RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 设置租户意图分发器使用的权限策略
+    pub fn with_policy(mut self, policy: Arc<AccessPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> &Arc<AccessPolicy> {
+        &self.policy
+    }
+
+    /// 创建租户，提供者的模型发现失败时不创建
+    pub async fn create(&self, config: TenantConfig) -> Result<Arc<Tenant>, TenantError> {
+        let id = config.id.clone();
+        let valid = !id.0.is_empty()
+            && id
+                .0
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(TenantError::InvalidTenantId(id.0));
+        }
+
+        if self.get(&id).is_some() {
+            return Err(TenantError::DuplicateTenant(id));
+        }
+        let storage = Arc::new(NamespacedStorage::new(
+            self.storage.clone(),
+            &format!("{}/{}", TENANTS_ROOT, id),
+        ));
+        let tenant = Arc::new(Tenant::new(config, storage, self.policy.clone()).await?);
+        let mut tenants = self.tenants.write().unwrap();
+        if tenants.contains_key(&id) {
+            return Err(TenantError::DuplicateTenant(id));
+        }
+        tenants.insert(id, tenant.clone());
+        Ok(tenant)
+    }
+
+    pub fn get(&self, id: &TenantId) -> Option<Arc<Tenant>> {
+        self.tenants.read().unwrap().get(id).cloned()
+    }
+
+    /// 移除租户（不删除其存储中的数据）
+    pub fn remove(&self, id: &TenantId) -> Option<Arc<Tenant>> {
+        self.tenants.write().unwrap().remove(id)
+    }
+
+    pub fn list(&self) -> Vec<TenantId> {
+        let mut ids: Vec<TenantId> = self.tenants.read().unwrap().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// 认证 API 请求的凭据并返回调用者身份及其租户
+    pub fn authenticate(&self, token: &str) -> Result<(Identity, Arc<Tenant>), TenantError> {
+        let identity = self.authenticator.authenticate(token)?;
+        let tenant = self
+            .get(&identity.tenant)
+            .ok_or_else(|| TenantError::UnknownTenant(identity.tenant.clone()))?;
+        Ok((identity, tenant))
+    }
+
+    /// API 请求入口：认证凭据后以调用者身份将意图分发到其租户
+    pub async fn dispatch(&self, token: &str, intent: SystemIntent) -> anyhow::Result<()> {
+        let (identity, tenant) = self.authenticate(token)?;
+        tenant.dispatcher().dispatch(&identity, intent).await
+    }
+
+    /// 认证凭据后分发意图并返回处理器的输出（见 `GuardedDispatcher::request`）
+    pub async fn request<T: serde::de::DeserializeOwned>(
+        &self,
+        token: &str,
+        intent: SystemIntent,
+    ) -> anyhow::Result<T> {
+        let (identity, tenant) = self.authenticate(token)?;
+        tenant.dispatcher().request(&identity, intent).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::intent::{EditorIntent, IntentCategory, IntentHandler};
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+    use crate::tenant::access::Role;
+    use crate::tenant::identity::TokenAuthenticator;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn config(id: &str) -> TenantConfig {
+        TenantConfig {
+            id: TenantId::new(id),
+            name: id.to_uppercase(),
+            providers: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tenants_are_isolated() {
+        let dir = tempfile::tempdir().unwrap();
+        let auth = Arc::new(TokenAuthenticator::new());
+        let manager = TenantManager::new(Arc::new(LocalFileSystem::new(dir.path())), auth.clone());
+
+        let a = manager.create(config("team-a")).await.unwrap();
+        let b = manager.create(config("team-b")).await.unwrap();
+        assert!(matches!(
+            manager.create(config("team-a")).await,
+            Err(TenantError::DuplicateTenant(_))
+        ));
+        assert!(matches!(
+            manager.create(config("../etc")).await,
+            Err(TenantError::InvalidTenantId(_))
+        ));
+
+        auth.issue(
+            "token-a",
+            Identity {
+                user: "alice".to_string(),
+                tenant: TenantId::new("team-a"),
+                roles: Default::default(),
+            },
+        );
+        let (identity, tenant) = manager.authenticate("Bearer token-a").unwrap();
+        assert_eq!(identity.user, "alice");
+        assert!(Arc::ptr_eq(&tenant, &a));
+        assert!(manager.authenticate("token-b").is_err());
+
+        // 存储、会话、知识库、费用互不可见
+        a.storage()
+            .write_file("project/main.rs", b"fn main() {}")
+            .await
+            .unwrap();
+        assert!(!b.storage().exists("project/main.rs").await.unwrap());
+
+        let session = a
+            .sessions()
+            .write()
+            .await
+            .create_session(
+                "project".to_string(),
+                a.threads().get_thread_id_by_name("main").unwrap(),
+                a.storage(),
+            )
+            .await;
+        assert!(a.sessions().read().await.get_session(&session).is_some());
+        assert!(b.sessions().read().await.get_session(&session).is_none());
+
+        a.knowledge().write().await.add("doc", vec![1.0]);
+        assert!(b.knowledge().read().await.search(&[1.0], 10).is_empty());
+        assert_eq!(a.retriever().search(&[1.0], 10).await, vec!["doc"]);
+        assert!(b.retriever().search(&[1.0], 10).await.is_empty());
+
+        a.usage().record(
+            "gpt-4o",
+            "openai",
+            None,
+            &crate::common::endpoint::traits::Usage {
+                prompt_tokens: 10,
+                completion_tokens: 5,
+                total_tokens: 15,
+            },
+        );
+        assert_eq!(a.usage().total().requests, 1);
+        assert_eq!(b.usage().total().requests, 0);
+        assert!(Arc::ptr_eq(
+            a.models().read().await.ledger().unwrap(),
+            a.usage()
+        ));
+
+        manager.remove(&TenantId::new("team-a"));
+        assert!(matches!(
+            manager.authenticate("token-a"),
+            Err(TenantError::UnknownTenant(_))
+        ));
+        assert_eq!(manager.list(), vec![TenantId::new("team-b")]);
+    }
+
+    struct Counter(AtomicUsize);
+
+    #[async_trait]
+    impl IntentHandler for Counter {
+        async fn handle(&self, _intent: SystemIntent) -> anyhow::Result<()> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_dispatched_to_the_callers_tenant() {
+        let dir = tempfile::tempdir().unwrap();
+        let auth = Arc::new(TokenAuthenticator::new());
+        let manager = TenantManager::new(Arc::new(LocalFileSystem::new(dir.path())), auth.clone());
+        let a = manager.create(config("team-a")).await.unwrap();
+        let b = manager.create(config("team-b")).await.unwrap();
+        let (counter_a, counter_b) = (
+            Arc::new(Counter(AtomicUsize::new(0))),
+            Arc::new(Counter(AtomicUsize::new(0))),
+        );
+        a.dispatcher()
+            .inner()
+            .register(IntentCategory::Editor, counter_a.clone())
+            .await;
+        b.dispatcher()
+            .inner()
+            .register(IntentCategory::Editor, counter_b.clone())
+            .await;
+        for (token, role) in [("viewer", Role::Viewer), ("editor", Role::Editor)] {
+            auth.issue(
+                token,
+                Identity {
+                    user: token.to_string(),
+                    tenant: TenantId::new("team-a"),
+                    roles: [role].into(),
+                },
+            );
+        }
+
+        manager
+            .dispatch("editor", SystemIntent::Editor(EditorIntent::Save))
+            .await
+            .unwrap();
+        let denied = manager
+            .dispatch("viewer", SystemIntent::Editor(EditorIntent::Save))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            denied.downcast_ref::<TenantError>(),
+            Some(TenantError::Forbidden { .. })
+        ));
+        assert!(
+            manager
+                .dispatch("nobody", SystemIntent::Editor(EditorIntent::Save))
+                .await
+                .is_err()
+        );
+        assert_eq!(counter_a.0.load(Ordering::SeqCst), 1);
+        assert_eq!(counter_b.0.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_providers_register_discovered_models() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let body = r#"{"models":[{"name":"llama3.2:3b","model":"llama3.2:3b","details":{"families":["llama"]}}]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let manager = TenantManager::new(
+            Arc::new(LocalFileSystem::new(dir.path())),
+            Arc::new(TokenAuthenticator::new()),
+        );
+        let provider = |base_url: String| ProviderConfig {
+            name: "ollama".to_string(),
+            api_key: String::new(),
+            base_url: Some(base_url),
+            organization: None,
+        };
+        let tenant = manager
+            .create(TenantConfig {
+                providers: vec![provider(format!("http://{}", addr))],
+                ..config("team-a")
+            })
+            .await
+            .unwrap();
+        let models = tenant.models().read().await;
+        assert_eq!(models.list_by_provider("ollama")[0].id, "llama3.2:3b");
+        assert!(models.has_client("ollama"));
+        drop(models);
+        let report = tenant.startup().report();
+        assert_eq!(report.phases[0].name, "provider_catalog:ollama");
+        assert!(!report.phases[0].lazy);
+
+        // 提供者不可达时不创建租户
+        let unreachable = manager
+            .create(TenantConfig {
+                providers: vec![provider("http://127.0.0.1:1".to_string())],
+                ..config("team-b")
+            })
+            .await;
+        assert!(matches!(
+            unreachable,
+            Err(TenantError::ProviderUnavailable { .. })
+        ));
+        assert!(manager.get(&TenantId::new("team-b")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_workspace_index_is_built_on_first_use() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = TenantManager::new(
+            Arc::new(LocalFileSystem::new(dir.path())),
+            Arc::new(TokenAuthenticator::new()),
+        );
+        let tenant = manager.create(config("team-a")).await.unwrap();
+        assert_eq!(tenant.startup().report().deferred, vec!["workspace_index"]);
+
+        tenant
+            .storage()
+            .write_file("src/main.rs", b"fn main() {}")
+            .await
+            .unwrap();
+        let index = tenant.workspace().await.unwrap();
+        assert_eq!(
+            index.read().await.files().collect::<Vec<_>>(),
+            vec!["src/main.rs"]
+        );
+        let report = tenant.startup().report();
+        assert!(report.deferred.is_empty());
+        assert_eq!(report.phases.len(), 1);
+        assert!(report.phases[0].lazy);
+    }
+}
@@ -0,0 +1,21 @@
+//! # 多租户
+//!
+//! 让同一个后端进程为多个用户/团队提供服务：每个租户拥有独立的会话、模型提供者配置、
+//! 知识库命名空间与用量账本，API 服务器按认证身份将请求路由到对应租户。
+//!
+//! ## 模块
+//!
+//! - [`access`] - 基于角色的意图与工具访问控制
+//! - [`identity`] - 租户标识、调用者身份与令牌认证
+//! - [`manager`] - 租户运行时与租户管理器
+//! - [`storage`] - 按租户前缀隔离的存储提供者
+
+pub mod access;
+pub mod identity;
+pub mod manager;
+pub mod storage;
+
+pub use access::{AccessPolicy, GuardedDispatcher, Permission, Role, ToolClass};
+pub use identity::{Authenticator, Identity, TenantError, TenantId, TokenAuthenticator};
+pub use manager::{Tenant, TenantConfig, TenantManager};
+pub use storage::NamespacedStorage;
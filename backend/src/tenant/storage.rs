@@ -0,0 +1,118 @@
+use crate::common::provider::traits::{FileMetadata, StorageProvider};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// 将所有路径限制在某个前缀目录下的存储提供者
+///
+/// 租户看到的根目录即 `prefix`；包含 `..` 的路径会被拒绝，防止越界访问其他租户的数据。
+pub struct NamespacedStorage {
+    inner: Arc<dyn StorageProvider>,
+    prefix: String,
+    id: String,
+}
+
+impl NamespacedStorage {
+    pub fn new(inner: Arc<dyn StorageProvider>, prefix: &str) -> Self {
+        let prefix = prefix.trim_matches('/').to_string();
+        let id = format!("{}:{}", inner.id(), prefix);
+        Self { inner, prefix, id }
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn scoped(&self, path: &str) -> anyhow::Result<String> {
+        let path = path.trim_start_matches("./").trim_matches('/');
+        if path.split(['/', '\\']).any(|c| c == "..") {
+            return Err(anyhow::anyhow!("Path escapes tenant namespace: {}", path));
+        }
+        Ok(if path.is_empty() {
+            self.prefix.clone()
+        } else {
+            format!("{}/{}", self.prefix, path)
+        })
+    }
+
+    fn unscoped(&self, path: &str) -> String {
+        let path = path.trim_start_matches('/');
+        path.strip_prefix(&self.prefix)
+            .map(|p| p.trim_start_matches('/'))
+            .unwrap_or(path)
+            .to_string()
+    }
+}
+
+#[async_trait]
+impl StorageProvider for NamespacedStorage {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn read_file(&self, path: &str) -> anyhow::Result<Vec<u8>> {
+        self.inner.read_file(&self.scoped(path)?).await
+    }
+
+    async fn write_file(&self, path: &str, content: &[u8]) -> anyhow::Result<()> {
+        self.inner.write_file(&self.scoped(path)?, content).await
+    }
+
+    async fn write_file_durable(&self, path: &str, content: &[u8]) -> anyhow::Result<()> {
+        self.inner
+            .write_file_durable(&self.scoped(path)?, content)
+            .await
+    }
+
+    async fn append_file(&self, path: &str, content: &[u8]) -> anyhow::Result<()> {
+        self.inner.append_file(&self.scoped(path)?, content).await
+    }
+
+    async fn delete(&self, path: &str, recursive: bool) -> anyhow::Result<()> {
+        self.inner.delete(&self.scoped(path)?, recursive).await
+    }
+
+    async fn list_dir(&self, path: &str) -> anyhow::Result<Vec<FileMetadata>> {
+        let mut entries = self.inner.list_dir(&self.scoped(path)?).await?;
+        for entry in &mut entries {
+            entry.path = self.unscoped(&entry.path);
+        }
+        Ok(entries)
+    }
+
+    async fn get_metadata(&self, path: &str) -> anyhow::Result<FileMetadata> {
+        let mut meta = self.inner.get_metadata(&self.scoped(path)?).await?;
+        meta.path = self.unscoped(&meta.path);
+        Ok(meta)
+    }
+
+    async fn exists(&self, path: &str) -> anyhow::Result<bool> {
+        self.inner.exists(&self.scoped(path)?).await
+    }
+
+    async fn create_dir(&self, path: &str, recursive: bool) -> anyhow::Result<()> {
+        self.inner.create_dir(&self.scoped(path)?, recursive).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::provider::local::filesystem::LocalFileSystem;
+
+    #[tokio::test]
+    async fn test_namespaced_storage_isolation() {
+        let dir = tempfile::tempdir().unwrap();
+        let root: Arc<dyn StorageProvider> = Arc::new(LocalFileSystem::new(dir.path()));
+        let a = NamespacedStorage::new(root.clone(), "tenants/a");
+        let b = NamespacedStorage::new(root.clone(), "tenants/b");
+
+        a.write_file("/notes.md", b"alpha").await.unwrap();
+        assert_eq!(a.read_file("notes.md").await.unwrap(), b"alpha");
+        assert!(!b.exists("notes.md").await.unwrap());
+        assert!(root.exists("tenants/a/notes.md").await.unwrap());
+        assert!(b.read_file("../a/notes.md").await.is_err());
+
+        let entries = a.list_dir("").await.unwrap();
+        assert_eq!(entries[0].path, "notes.md");
+    }
+}